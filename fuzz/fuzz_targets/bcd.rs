@@ -0,0 +1,10 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use katsuba_bcd::Bcd;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Bcd::parse(Cursor::new(data));
+});
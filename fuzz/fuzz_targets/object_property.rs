@@ -0,0 +1,64 @@
+#![no_main]
+
+use std::sync::{Arc, OnceLock};
+
+use katsuba_object_property::serde::{
+    PropertyClass, Serializer, SerializerFlags, SerializerOptions,
+};
+use katsuba_types::TypeList;
+use libfuzzer_sys::fuzz_target;
+
+// A small type list covering simple data, strings, lists, enums
+// and nested objects.
+const TYPES: &str = r#"{
+    "version": 2,
+    "classes": {
+        "500460052": {
+            "name": "class FuzzNode",
+            "bases": [],
+            "hash": 500460052,
+            "properties": {
+                "m_id": { "type": "int", "id": 0, "flags": 24, "dynamic": false, "hash": 1 },
+                "m_name": { "type": "std::string", "id": 1, "flags": 24, "dynamic": false, "hash": 2 },
+                "m_wide": { "type": "std::wstring", "id": 2, "flags": 24, "dynamic": false, "hash": 3 },
+                "m_small": { "type": "bui5", "id": 3, "flags": 24, "dynamic": true, "hash": 4 },
+                "m_pos": { "type": "class Vector3D", "id": 4, "flags": 24, "dynamic": false, "hash": 5 },
+                "m_kind": {
+                    "type": "enum Kind",
+                    "id": 5,
+                    "flags": 2097176,
+                    "dynamic": false,
+                    "hash": 6,
+                    "enum_options": { "A": 0, "B": 1, "C": 2 }
+                },
+                "m_children": { "type": "class FuzzNode*", "id": 6, "flags": 24, "dynamic": true, "hash": 7 },
+                "m_delta": { "type": "double", "id": 7, "flags": 280, "dynamic": false, "hash": 8 }
+            }
+        }
+    }
+}"#;
+
+fn types() -> Arc<TypeList> {
+    static TYPES_CELL: OnceLock<Arc<TypeList>> = OnceLock::new();
+    TYPES_CELL
+        .get_or_init(|| Arc::new(TypeList::from_str(TYPES).unwrap()))
+        .clone()
+}
+
+fuzz_target!(|data: &[u8]| {
+    // The first byte selects the serializer configuration.
+    let Some((&config, data)) = data.split_first() else {
+        return;
+    };
+
+    let options = SerializerOptions {
+        flags: SerializerFlags::from_bits_truncate(config as u32),
+        shallow: config & (1 << 5) != 0,
+        manual_compression: config & (1 << 6) != 0,
+        skip_unknown_types: config & (1 << 7) != 0 && config & (1 << 5) == 0,
+        ..Default::default()
+    };
+
+    let mut serializer = Serializer::new(options, types()).unwrap();
+    let _ = serializer.deserialize::<PropertyClass>(data);
+});
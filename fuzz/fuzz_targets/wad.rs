@@ -0,0 +1,8 @@
+#![no_main]
+
+use katsuba_wad::Archive;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Archive::from_vec(data.to_vec());
+});
@@ -0,0 +1,13 @@
+//! Encodes byte buffers as base64 strings with serde.
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    BASE64_STANDARD.decode(s).map_err(D::Error::custom)
+}
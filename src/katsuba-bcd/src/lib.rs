@@ -13,10 +13,12 @@ use katsuba_utils::{
         io::{Read, Seek, Write},
         BinReaderExt, BinResult, BinWriterExt,
     },
-    binrw_ext::{read_prefixed_string, write_prefixed_string},
+    binrw_ext::{read_prefixed_string, read_remaining, write_prefixed_string},
 };
 use serde::{Deserialize, Serialize};
 
+mod base64_bytes;
+
 bitflags! {
     /// Attribute flags encoded in [`Geometry`] objects.
     #[binrw]
@@ -190,16 +192,36 @@ pub struct Bcd {
     /// A list of all [`Collision`] objects in the file.
     #[br(count = collision_count)]
     pub collisions: Vec<Collision>,
+
+    /// Bytes following the collisions which are not understood.
+    ///
+    /// Newer clients append an extra chunk to some files. It is
+    /// kept as-is so that writing the file reproduces it, and is
+    /// encoded as a base64 string by serde.
+    #[br(parse_with = read_remaining)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "base64_bytes")]
+    pub unknown_trailing: Vec<u8>,
 }
 
 impl Bcd {
     /// Attempts to parse a BCD file from a given [`Read`]er.
+    ///
+    /// Unknown data after the collisions is preserved in
+    /// [`Bcd::unknown_trailing`] with a warning.
     pub fn parse<R: Read + Seek>(mut reader: R) -> BinResult<Self> {
-        reader.read_le().map_err(Into::into)
+        let bcd: Self = reader.read_le()?;
+        if !bcd.unknown_trailing.is_empty() {
+            log::warn!(
+                "Preserving {} unknown bytes after BCD collision data",
+                bcd.unknown_trailing.len()
+            );
+        }
+
+        Ok(bcd)
     }
 
     /// Writes the BCD data to the given [`Write`]r.
     pub fn write<W: Write + Seek>(&self, mut writer: W) -> BinResult<()> {
-        writer.write_le(self).map_err(Into::into)
+        writer.write_le(self)
     }
 }
@@ -0,0 +1,56 @@
+use std::io::Cursor;
+
+use katsuba_bcd::*;
+
+fn sample() -> Bcd {
+    Bcd {
+        collisions: vec![Collision {
+            category_flags: CollisionFlags::WALKABLE,
+            collision_flags: CollisionFlags::OBJECT | CollisionFlags::HITSCAN,
+            mesh: None,
+            geometry: ProxyGeometry {
+                name: "floor".into(),
+                rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+                location: [0.0, -5.0, 0.0],
+                scale: 1.0,
+                material: "stone".into(),
+                params: GeomParams::Box {
+                    length: 10.0,
+                    width: 10.0,
+                    depth: 1.0,
+                },
+            },
+        }],
+        unknown_trailing: Vec::new(),
+    }
+}
+
+fn write(bcd: &Bcd) -> Vec<u8> {
+    let mut out = Cursor::new(Vec::new());
+    bcd.write(&mut out).unwrap();
+    out.into_inner()
+}
+
+#[test]
+fn without_trailing_data() {
+    let data = write(&sample());
+    assert_eq!(Bcd::parse(Cursor::new(&data)).unwrap(), sample());
+
+    let json = serde_json::to_value(sample()).unwrap();
+    assert!(json.get("unknown_trailing").is_none());
+}
+
+#[test]
+fn preserve_trailing_data() {
+    let mut data = write(&sample());
+    data.extend(b"\x07\0\0\0chunk");
+
+    let bcd = Bcd::parse(Cursor::new(&data)).unwrap();
+    assert_eq!(bcd.collisions, sample().collisions);
+    assert_eq!(bcd.unknown_trailing, b"\x07\0\0\0chunk");
+    assert_eq!(write(&bcd), data);
+
+    let json = serde_json::to_value(&bcd).unwrap();
+    assert_eq!(json["unknown_trailing"], "BwAAAGNodW5r");
+    assert_eq!(serde_json::from_value::<Bcd>(json).unwrap(), bcd);
+}
@@ -0,0 +1,153 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use katsuba_bench::{
+    object::{self, Encoding, Node},
+    Rng, SEED,
+};
+use katsuba_object_property::{
+    serde::{PropertyClass, Serializer},
+    value::Arena,
+};
+use katsuba_types::TypeList;
+
+// Counts heap allocations to compare the owned and arena-backed
+// deserialization modes.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn bench_encoding(c: &mut Criterion, name: &str, types: &Arc<TypeList>, encoding: Encoding) {
+    let mut rng = Rng::new(SEED);
+    let root = Node::tree(&mut rng, 4, 5);
+    let data = encoding.encode(&root);
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function(format!("{}_nodes", root.count()), |b| {
+        let mut serializer = Serializer::new(encoding.options(), types.clone()).unwrap();
+        b.iter(|| serializer.deserialize::<PropertyClass>(&data).unwrap());
+    });
+    group.finish();
+}
+
+fn deserialize(c: &mut Criterion) {
+    let types = object::type_list(&mut Rng::new(SEED), 0);
+
+    bench_encoding(
+        c,
+        "deserialize_deep",
+        &types,
+        Encoding {
+            shallow: false,
+            compact: false,
+        },
+    );
+    bench_encoding(
+        c,
+        "deserialize_shallow",
+        &types,
+        Encoding {
+            shallow: true,
+            compact: false,
+        },
+    );
+    bench_encoding(
+        c,
+        "deserialize_compact_prefixes",
+        &types,
+        Encoding {
+            shallow: true,
+            compact: true,
+        },
+    );
+}
+
+fn deserialize_arena(c: &mut Criterion) {
+    let types = object::type_list(&mut Rng::new(SEED), 0);
+    let encoding = Encoding {
+        shallow: false,
+        compact: false,
+    };
+
+    // A large file, for which allocations dominate.
+    let root = Node::tree(&mut Rng::new(SEED), 6, 5);
+    let data = encoding.encode(&root);
+
+    let mut serializer = Serializer::new(encoding.options(), types).unwrap();
+    let mut arena = Arena::new();
+
+    // Warm up both modes so that only the allocations of the tree
+    // itself are counted.
+    serializer.deserialize::<PropertyClass>(&data).unwrap();
+    serializer
+        .deserialize_in::<PropertyClass>(&arena, &data)
+        .unwrap();
+    arena.reset();
+
+    let owned = count_allocations(|| {
+        black_box(serializer.deserialize::<PropertyClass>(&data).unwrap());
+    });
+    let in_arena = count_allocations(|| {
+        black_box(
+            serializer
+                .deserialize_in::<PropertyClass>(&arena, &data)
+                .unwrap(),
+        );
+    });
+    println!(
+        "deserialize_arena: {} nodes, {owned} allocations owned, {in_arena} in the arena",
+        root.count()
+    );
+
+    let mut group = c.benchmark_group("deserialize_arena");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("owned", |b| {
+        b.iter(|| serializer.deserialize::<PropertyClass>(&data).unwrap());
+    });
+    group.bench_function("arena", |b| {
+        b.iter(|| {
+            arena.reset();
+            let value = serializer
+                .deserialize_in::<PropertyClass>(&arena, &data)
+                .unwrap();
+            black_box(&value);
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, deserialize, deserialize_arena);
+criterion_main!(benches);
@@ -0,0 +1,17 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use katsuba_bench::{object, Rng, SEED};
+use katsuba_types::TypeList;
+
+fn load_json(c: &mut Criterion) {
+    let json = object::type_list_json(&mut Rng::new(SEED), 5000);
+
+    let mut group = c.benchmark_group("type_list");
+    group.throughput(Throughput::Bytes(json.len() as u64));
+    group.bench_function("from_json", |b| {
+        b.iter(|| TypeList::from_str(&json).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, load_json);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+use std::fs;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use katsuba_bench::{wad, Rng, SEED};
+use katsuba_executor::{Buffer, Executor, FileBatcher};
+use katsuba_wad::{Archive, Inflater};
+
+fn parse_journal(c: &mut Criterion) {
+    let data = wad::archive(&mut Rng::new(SEED), 2000);
+
+    let mut group = c.benchmark_group("wad_journal");
+    group.throughput(Throughput::Elements(2000));
+    group.bench_function("2000_files", |b| {
+        b.iter(|| Archive::from_vec(data.clone()).unwrap())
+    });
+    group.finish();
+}
+
+fn inflate(c: &mut Criterion) {
+    let mut rng = Rng::new(SEED);
+    let mut inflater = Inflater::new();
+
+    let mut group = c.benchmark_group("wad_inflate");
+    for size in [1 << 10, 64 << 10, 1 << 20] {
+        let entry = wad::entry(&mut rng, size);
+
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &entry, |b, entry| {
+            b.iter(|| {
+                inflater
+                    .decompress(&entry.compressed, entry.uncompressed_size)
+                    .unwrap()
+                    .len()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn write_small_files(c: &mut Criterion) {
+    let mut rng = Rng::new(SEED);
+    let ex = Executor::get().unwrap();
+
+    // Many tiny files spread over a few directories, like icons or
+    // locale fragments in real archives.
+    let files: Vec<_> = (0..2000)
+        .map(|i| {
+            let name = format!("{}/{}_{i}.xml", i % 16, rng.ident(12));
+            let len = 64 + rng.below(512) as usize;
+            (name, rng.text(len))
+        })
+        .collect();
+
+    // Compares writing every file on its own to batching small ones.
+    let mut group = c.benchmark_group("wad_write");
+    group.throughput(Throughput::Elements(files.len() as u64));
+    group.sample_size(10);
+    for threshold in [0, 16 * 1024] {
+        group.bench_with_input(
+            BenchmarkId::new("threshold", threshold),
+            &threshold,
+            |b, &threshold| {
+                b.iter_batched(
+                    || {
+                        let dir = tempfile::tempdir().unwrap();
+                        for (name, _) in &files {
+                            let path = dir.path().join(name);
+                            fs::create_dir_all(path.parent().unwrap()).unwrap();
+                        }
+                        dir
+                    },
+                    |dir| {
+                        let mut batcher = FileBatcher::new(threshold);
+                        let mut tasks = Vec::new();
+                        for (name, data) in &files {
+                            let buffer = Buffer::owned(data.clone());
+                            tasks.extend(batcher.push(dir.path().join(name), buffer, 0o644));
+                        }
+                        tasks.extend(batcher.finish());
+
+                        for task in tasks {
+                            ex.dispatch(task).for_each(|r| r.unwrap());
+                        }
+                        ex.join().for_each(|r| r.unwrap());
+                        dir
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_journal, inflate, write_small_files);
+criterion_main!(benches);
@@ -0,0 +1,16 @@
+//! Synthetic fixtures for benchmarking the Katsuba project.
+//!
+//! All data is generated by code from a fixed seed, so benchmark
+//! runs are reproducible and no game files need to be shipped.
+
+#![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
+
+pub mod object;
+
+mod rng;
+pub use rng::Rng;
+
+pub mod wad;
+
+/// The seed used for generating all fixtures.
+pub const SEED: u64 = 0x4B41_5453_5542_4121;
@@ -0,0 +1,263 @@
+//! Synthetic ObjectProperty type lists and object graphs.
+
+use std::{fmt::Write as _, sync::Arc};
+
+use katsuba_bit_buf::BitWriter;
+use katsuba_object_property::serde::{SerializerFlags, SerializerOptions};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::{property_hash, string_id};
+
+use crate::Rng;
+
+/// The name of the class used for all nodes in the object graph.
+pub const NODE_CLASS: &str = "class BenchNode";
+
+const FLAGS: u32 = 24;
+
+const PRIMITIVES: &[&str] = &[
+    "int",
+    "unsigned int",
+    "float",
+    "double",
+    "bool",
+    "std::string",
+    "std::wstring",
+    "class Vector3D",
+    "gid",
+];
+
+// The properties of `NODE_CLASS` in declaration order.
+const NODE_PROPERTIES: &[(&str, &str, bool)] = &[
+    ("m_id", "int", false),
+    ("m_name", "std::string", false),
+    ("m_position", "class Vector3D", false),
+    ("m_weights", "float", true),
+    ("m_children", "class BenchNode*", true),
+    ("m_visible", "bool", false),
+];
+
+/// A node in the synthetic object graph.
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub id: i32,
+    pub name: String,
+    pub position: [f32; 3],
+    pub weights: Vec<f32>,
+    pub children: Vec<Node>,
+    pub visible: bool,
+}
+
+impl Node {
+    /// Generates a tree of nodes with the given `depth` where every
+    /// inner node has `fanout` children.
+    pub fn tree(rng: &mut Rng, depth: u32, fanout: usize) -> Self {
+        let name_len = 8 + rng.below(24) as usize;
+        let weights = rng.below(16) as usize;
+
+        Self {
+            id: rng.next_u64() as i32,
+            name: rng.ident(name_len),
+            position: [0; 3].map(|_| rng.next_u64() as u16 as f32 / 16.0),
+            weights: (0..weights)
+                .map(|_| rng.below(1000) as f32 / 10.0)
+                .collect(),
+            children: match depth {
+                0 => Vec::new(),
+                d => (0..fanout)
+                    .map(|_| Self::tree(rng, d - 1, fanout))
+                    .collect(),
+            },
+            visible: rng.below(2) == 0,
+        }
+    }
+
+    /// Counts the nodes in this tree.
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(Node::count).sum::<usize>()
+    }
+}
+
+/// The encoding parameters for an object graph.
+#[derive(Clone, Copy, Debug)]
+pub struct Encoding {
+    /// Whether the shallow encoding strategy is used.
+    pub shallow: bool,
+    /// Whether compact length prefixes are used.
+    pub compact: bool,
+}
+
+impl Encoding {
+    /// Gets the serializer options to decode data in this encoding.
+    pub fn options(self) -> SerializerOptions {
+        let mut options = SerializerOptions {
+            shallow: self.shallow,
+            ..Default::default()
+        };
+        if self.compact {
+            options.flags |= SerializerFlags::COMPACT_LENGTH_PREFIXES;
+        }
+
+        options
+    }
+
+    /// Serializes the object graph rooted at `node`.
+    pub fn encode(self, node: &Node) -> Vec<u8> {
+        let mut writer = BitWriter::new();
+        self.write_node(&mut writer, node);
+        writer.realign_to_byte();
+
+        writer.into_inner()
+    }
+
+    fn write_node(self, w: &mut BitWriter, node: &Node) {
+        w.realign_to_byte();
+        bits(w, string_id(NODE_CLASS.as_bytes()) as u64, u32::BITS);
+
+        if self.shallow {
+            self.write_properties(w, node);
+        } else {
+            w.length_prefixed(|w| self.write_properties(w, node));
+        }
+    }
+
+    fn write_properties(self, w: &mut BitWriter, node: &Node) {
+        for (idx, &(name, ty, _)) in NODE_PROPERTIES.iter().enumerate() {
+            if self.shallow {
+                self.write_property(w, node, idx);
+            } else {
+                w.length_prefixed(|w| {
+                    bits(
+                        w,
+                        property_hash(name.as_bytes(), ty.as_bytes()) as u64,
+                        u32::BITS,
+                    );
+                    self.write_property(w, node, idx);
+                });
+            }
+        }
+    }
+
+    fn write_property(self, w: &mut BitWriter, node: &Node, idx: usize) {
+        match idx {
+            0 => {
+                w.realign_to_byte();
+                bits(w, node.id as u32 as u64, u32::BITS);
+            }
+            1 => {
+                self.write_length(w, node.name.len(), u16::BITS);
+                if !node.name.is_empty() {
+                    w.realign_to_byte();
+                    w.write_bytes(node.name.as_bytes());
+                }
+            }
+            2 => {
+                w.realign_to_byte();
+                for v in node.position {
+                    w.write_bytes(&v.to_le_bytes());
+                }
+            }
+            3 => {
+                self.write_length(w, node.weights.len(), u32::BITS);
+                for v in &node.weights {
+                    w.realign_to_byte();
+                    bits(w, v.to_bits() as u64, u32::BITS);
+                }
+            }
+            4 => {
+                self.write_length(w, node.children.len(), u32::BITS);
+                for child in &node.children {
+                    self.write_node(w, child);
+                }
+            }
+            5 => bits(w, node.visible as u64, 1),
+            _ => unreachable!(),
+        }
+    }
+
+    fn write_length(self, w: &mut BitWriter, len: usize, nbits: u32) {
+        if self.compact {
+            let is_large = len >= 1 << (u8::BITS - 1);
+            bits(w, is_large as u64, 1);
+            match is_large {
+                true => bits(w, len as u64, u32::BITS - 1),
+                false => bits(w, len as u64, u8::BITS - 1),
+            }
+        } else {
+            w.realign_to_byte();
+            bits(w, len as u64, nbits);
+        }
+    }
+}
+
+fn bits(w: &mut BitWriter, value: u64, nbits: u32) {
+    if w.remaining() < nbits {
+        w.commit();
+    }
+    w.offer(value, nbits).unwrap();
+}
+
+/// Generates a JSON type list containing [`NODE_CLASS`] and
+/// `fillers` unrelated classes to pad it to a realistic size.
+pub fn type_list_json(rng: &mut Rng, fillers: usize) -> String {
+    let mut classes = vec![(
+        NODE_CLASS.to_owned(),
+        NODE_PROPERTIES
+            .iter()
+            .map(|&(name, ty, dynamic)| (name.to_owned(), ty.to_owned(), dynamic))
+            .collect::<Vec<_>>(),
+    )];
+
+    for _ in 0..fillers {
+        let name_len = 6 + rng.below(20) as usize;
+        let name = format!("class {}", rng.ident(name_len));
+
+        let properties = (0..rng.below(24))
+            .map(|_| {
+                let name_len = 4 + rng.below(20) as usize;
+                let ty = PRIMITIVES[rng.below(PRIMITIVES.len() as u64) as usize];
+                (
+                    format!("m_{}", rng.ident(name_len)),
+                    ty.to_owned(),
+                    rng.below(4) == 0,
+                )
+            })
+            .collect();
+
+        classes.push((name, properties));
+    }
+
+    let mut out = String::from(r#"{"version":2,"classes":{"#);
+    for (i, (name, properties)) in classes.iter().enumerate() {
+        let hash = string_id(name.as_bytes());
+        if i != 0 {
+            out.push(',');
+        }
+        write!(
+            out,
+            r#""{hash}":{{"name":"{name}","bases":[],"hash":{hash},"properties":{{"#
+        )
+        .unwrap();
+
+        for (id, (prop, ty, dynamic)) in properties.iter().enumerate() {
+            if id != 0 {
+                out.push(',');
+            }
+            write!(
+                out,
+                r#""{prop}":{{"type":"{ty}","id":{id},"flags":{FLAGS},"dynamic":{dynamic},"hash":{}}}"#,
+                property_hash(prop.as_bytes(), ty.as_bytes())
+            )
+            .unwrap();
+        }
+
+        out.push_str("}}");
+    }
+    out.push_str("}}");
+
+    out
+}
+
+/// Generates and parses a type list as in [`type_list_json`].
+pub fn type_list(rng: &mut Rng, fillers: usize) -> Arc<TypeList> {
+    Arc::new(TypeList::from_str(&type_list_json(rng, fillers)).unwrap())
+}
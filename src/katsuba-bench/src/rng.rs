@@ -0,0 +1,55 @@
+/// A small deterministic random number generator.
+///
+/// This implements SplitMix64, which is more than good enough
+/// for generating benchmark data.
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a new generator from a seed.
+    pub const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Generates the next random 64-bit value.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Generates a value in the range `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+
+    /// Generates an ASCII identifier-like string of `len` bytes.
+    pub fn ident(&mut self, len: usize) -> String {
+        const CHARSET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_";
+        (0..len)
+            .map(|_| CHARSET[self.below(CHARSET.len() as u64) as usize] as char)
+            .collect()
+    }
+
+    /// Generates `len` bytes of text-like data which compresses
+    /// about as well as typical game assets.
+    pub fn text(&mut self, len: usize) -> Vec<u8> {
+        let words: Vec<String> = (0..64)
+            .map(|_| {
+                let len = 3 + self.below(8) as usize;
+                self.ident(len)
+            })
+            .collect();
+
+        let mut out = Vec::with_capacity(len + 16);
+        while out.len() < len {
+            out.extend_from_slice(words[self.below(words.len() as u64) as usize].as_bytes());
+            out.push(b' ');
+        }
+        out.truncate(len);
+
+        out
+    }
+}
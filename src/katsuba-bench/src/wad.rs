@@ -0,0 +1,45 @@
+//! Synthetic KIWAD archives and compressed entries.
+
+use std::fs;
+
+use katsuba_utils::compress::Deflater;
+use katsuba_wad::ArchiveBuilder;
+
+use crate::Rng;
+
+/// Builds a KIWAD archive with `files` small compressed text files
+/// spread over a few directories and returns its raw bytes.
+pub fn archive(rng: &mut Rng, files: usize) -> Vec<u8> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Bench.wad");
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    for i in 0..files {
+        let name = format!("{}/{}_{i}.xml", rng.ident(6), rng.ident(12));
+        let len = 64 + rng.below(512) as usize;
+        builder.add_file_compressed(&name, &rng.text(len)).unwrap();
+    }
+    builder.finish().unwrap();
+
+    fs::read(path).unwrap()
+}
+
+/// A compressed archive entry.
+pub struct Entry {
+    /// The size of the data after decompression.
+    pub uncompressed_size: usize,
+    /// The zlib stream of the data.
+    pub compressed: Vec<u8>,
+}
+
+/// Generates a text-like entry of `len` bytes and compresses it
+/// the way archive builders do.
+pub fn entry(rng: &mut Rng, len: usize) -> Entry {
+    let mut compressed = Vec::new();
+    Deflater::best().zlib_into(&rng.text(len), &mut compressed);
+
+    Entry {
+        uncompressed_size: len,
+        compressed,
+    }
+}
@@ -0,0 +1,66 @@
+use katsuba_bench::{
+    object::{self, Encoding, Node},
+    wad, Rng, SEED,
+};
+use katsuba_object_property::{serde::*, Value};
+use katsuba_wad::{Archive, Inflater};
+
+fn check_node(value: &Value, node: &Node) {
+    let Value::Object { obj, .. } = value else {
+        panic!("expected object");
+    };
+
+    assert!(matches!(obj.get("m_id"), Some(&Value::Signed(id)) if id == node.id as i64));
+    assert!(matches!(obj.get("m_visible"), Some(&Value::Bool(v)) if v == node.visible));
+    assert!(
+        matches!(obj.get("m_position"), Some(Value::Vec3(v)) if [v.x, v.y, v.z] == node.position)
+    );
+
+    let Some(Value::List(weights)) = obj.get("m_weights") else {
+        panic!("expected weights");
+    };
+    assert_eq!(weights.len(), node.weights.len());
+
+    let Some(Value::List(children)) = obj.get("m_children") else {
+        panic!("expected children");
+    };
+    assert_eq!(children.len(), node.children.len());
+    for (value, child) in children.iter().zip(&node.children) {
+        check_node(value, child);
+    }
+}
+
+#[test]
+fn object_graph_roundtrip() {
+    let mut rng = Rng::new(SEED);
+    let types = object::type_list(&mut rng, 16);
+    let root = Node::tree(&mut rng, 2, 3);
+
+    for (shallow, compact) in [(false, false), (true, false), (true, true)] {
+        let encoding = Encoding { shallow, compact };
+        let data = encoding.encode(&root);
+
+        let mut serializer = Serializer::new(encoding.options(), types.clone()).unwrap();
+        let value = serializer.deserialize::<PropertyClass>(&data).unwrap();
+        check_node(&value, &root);
+
+        // Re-serializing must reproduce the input exactly.
+        let reencoded = serializer.serialize::<PropertyClass>(&value).unwrap();
+        assert_eq!(reencoded, data);
+    }
+}
+
+#[test]
+fn archive_entries() {
+    let mut rng = Rng::new(SEED);
+
+    let archive = Archive::from_vec(wad::archive(&mut rng, 8)).unwrap();
+    assert_eq!(archive.len(), 8);
+
+    let entry = wad::entry(&mut rng, 4096);
+    let data = Inflater::new()
+        .decompress(&entry.compressed, entry.uncompressed_size)
+        .map(<[u8]>::to_vec)
+        .unwrap();
+    assert_eq!(data.len(), 4096);
+}
@@ -0,0 +1,33 @@
+use katsuba_utils::thiserror::{self, Error};
+
+/// Errors that may occur when reading from a [`BitReader`][crate::BitReader].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+pub enum ReadError {
+    /// More bits were requested than are available.
+    ///
+    /// For bit-level operations, the available count refers to the
+    /// currently buffered bits only. A refill may make more of them
+    /// available, unless the end of the data was reached.
+    #[error("attempted to read {requested} bits with only {available} available")]
+    UnexpectedEof { requested: usize, available: usize },
+
+    /// A bit count was larger than a single read can provide.
+    #[error("cannot read {0} bits at once")]
+    InvalidBitCount(u32),
+
+    /// A seek targeted a position past the end of the data.
+    #[error("attempted to seek to bit {pos} in data of {len} bits")]
+    SeekOutOfBounds { pos: usize, len: usize },
+}
+
+impl ReadError {
+    /// Whether this error was caused by running out of data.
+    ///
+    /// Such errors are often expected, e.g. when probing for
+    /// configurations, and can cheaply be retried from a
+    /// [`Checkpoint`][crate::Checkpoint].
+    #[inline]
+    pub fn is_eof(&self) -> bool {
+        matches!(self, Self::UnexpectedEof { .. })
+    }
+}
@@ -24,8 +24,11 @@
     unsafe_op_in_unsafe_fn
 )]
 
+mod error;
+pub use error::ReadError;
+
 mod reader;
-pub use reader::BitReader;
+pub use reader::{BitReader, Checkpoint};
 
 mod writer;
 pub use writer::BitWriter;
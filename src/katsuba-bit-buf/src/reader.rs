@@ -1,4 +1,6 @@
-use std::{io, marker::PhantomData, ptr, slice};
+use std::{marker::PhantomData, ptr, slice};
+
+use crate::ReadError;
 
 // The maximum number of bits that can be stored in lookahead.
 //
@@ -27,6 +29,16 @@ unsafe fn read_64_le(ptr: *const u8) -> u64 {
 #[inline(always)]
 fn cold() {}
 
+/// A saved position of a [`BitReader`].
+///
+/// Restoring one is cheap as it includes the lookahead state.
+#[derive(Clone, Copy, Debug)]
+pub struct Checkpoint {
+    ptr: *const u8,
+    lookahead: u64,
+    remaining: u32,
+}
+
 /// A buffer which enables bit-based deserialization of data.
 ///
 /// Individual bit reading starts at the LSB of the byte, working
@@ -39,6 +51,9 @@ fn cold() {}
 /// buffered bits, [`Self::invalidate_and_realign_ptr`] can help.
 #[derive(Debug)]
 pub struct BitReader<'a> {
+    // Pointer to the first byte in the spanned byte view.
+    start: *const u8,
+
     // Pointer to the next byte where the bit lookahead
     // buffer will be fetched from.
     ptr: *const u8,
@@ -69,6 +84,7 @@ impl<'a> BitReader<'a> {
         // SAFETY: All pointer arithmetic in bounds or one past the end.
         unsafe {
             Self {
+                start: ptr,
                 ptr,
                 safeguard: ptr.add(len.saturating_sub(7)),
                 end: ptr.add(len),
@@ -98,6 +114,89 @@ impl<'a> BitReader<'a> {
         (self.untouched_bytes() << 3) + self.remaining as usize
     }
 
+    /// Gets the number of bits consumed from the start of the data.
+    #[inline]
+    pub fn bit_position(&self) -> usize {
+        // SAFETY: Byte pointers are derived from the same object,
+        // with `start <= end` being an internally maintained invariant.
+        let total = unsafe { self.end.offset_from(self.start) as usize };
+        (total << 3) - self.remaining_bits()
+    }
+
+    /// Gets the whole data spanned by the reader, regardless of
+    /// its current position.
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        // SAFETY: The pointers span the slice the reader was created from.
+        unsafe { slice::from_raw_parts(self.start, self.end.offset_from(self.start) as usize) }
+    }
+
+    /// Gets the number of whole bytes consumed from the start of
+    /// the data.
+    ///
+    /// A partially consumed byte does not count towards the result.
+    #[inline]
+    pub fn byte_position(&self) -> usize {
+        self.bit_position() >> 3
+    }
+
+    /// Moves the reader to the given absolute bit position.
+    ///
+    /// This discards the current lookahead, so a refill is needed
+    /// before bits can be peeked again.
+    pub fn seek_to_bit(&mut self, pos: usize) -> Result<(), ReadError> {
+        // SAFETY: Byte pointers are derived from the same object.
+        let total = unsafe { self.end.offset_from(self.start) as usize };
+        if pos > total << 3 {
+            return Err(ReadError::SeekOutOfBounds {
+                pos,
+                len: total << 3,
+            });
+        }
+
+        // SAFETY: We checked that `pos` is within the data.
+        self.ptr = unsafe { self.start.add(pos >> 3) };
+        self.lookahead = 0;
+        self.remaining = 0;
+
+        // Skip the already consumed bits of a partial byte.
+        let bit = (pos & 7) as u32;
+        if bit != 0 {
+            self.refill_bits();
+            self.consume(bit)?;
+        }
+
+        Ok(())
+    }
+
+    /// Captures the current position of the reader so it can be
+    /// returned to with [`Self::restore`].
+    #[inline]
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            ptr: self.ptr,
+            lookahead: self.lookahead,
+            remaining: self.remaining,
+        }
+    }
+
+    /// Rewinds the reader to a previously captured [`Checkpoint`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when `checkpoint` was not taken from this reader.
+    #[inline]
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        assert!(
+            self.start <= checkpoint.ptr && checkpoint.ptr <= self.end,
+            "checkpoint does not belong to this reader"
+        );
+
+        self.ptr = checkpoint.ptr;
+        self.lookahead = checkpoint.lookahead;
+        self.remaining = checkpoint.remaining;
+    }
+
     /// Gets the bits currently buffered in the reader.
     #[inline]
     pub fn buffered_bits(&self) -> u32 {
@@ -184,30 +283,35 @@ impl<'a> BitReader<'a> {
     /// Returns the next `count` bits from the internal buffer without removing
     /// them, if available.
     #[inline]
-    pub fn peek(&mut self, count: u32) -> io::Result<u64> {
-        if count <= CONSUMABLE_BITS && count <= self.remaining {
-            Ok(self.lookahead & ((1 << count) - 1))
+    pub fn peek(&mut self, count: u32) -> Result<u64, ReadError> {
+        if count > CONSUMABLE_BITS {
+            cold();
+            Err(ReadError::InvalidBitCount(count))
+        } else if count > self.remaining {
+            cold();
+            Err(ReadError::UnexpectedEof {
+                requested: count as usize,
+                available: self.remaining as usize,
+            })
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "attempted to read out of bounds",
-            ))
+            Ok(self.lookahead & ((1 << count) - 1))
         }
     }
 
     /// Removes `count` bits from the internal buffer, if available.
     #[inline]
-    pub fn consume(&mut self, count: u32) -> io::Result<()> {
+    pub fn consume(&mut self, count: u32) -> Result<(), ReadError> {
         if count <= self.remaining {
             self.lookahead >>= count;
             self.remaining -= count;
 
             Ok(())
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "attempted to read out of bounds",
-            ))
+            cold();
+            Err(ReadError::UnexpectedEof {
+                requested: count as usize,
+                available: self.remaining as usize,
+            })
         }
     }
 
@@ -215,7 +319,7 @@ impl<'a> BitReader<'a> {
     ///
     /// These are borrowed from the underlying byte view without
     /// copying them.
-    pub fn read_bytes(&mut self, count: usize) -> io::Result<&'a [u8]> {
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], ReadError> {
         if count <= self.untouched_bytes() {
             // SAFETY: A bounds check was done and an appropriate lifetime is
             // inferred through the function signature.
@@ -225,10 +329,11 @@ impl<'a> BitReader<'a> {
                 Ok(value)
             }
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "attempted to read out of bounds",
-            ))
+            cold();
+            Err(ReadError::UnexpectedEof {
+                requested: count << 3,
+                available: self.untouched_bytes() << 3,
+            })
         }
     }
 }
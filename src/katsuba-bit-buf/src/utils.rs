@@ -1,8 +1,17 @@
 //! Miscellaneous utilities for working with bits.
 
 /// Sign-extends an `nbits` wide value to [`i64`].
+///
+/// A width of `0` always produces `0`, and widths of 64 bits
+/// or more leave the value unchanged.
 #[inline]
 pub fn sign_extend(value: u64, nbits: u32) -> i64 {
-    let shift = u64::BITS - nbits;
-    (value << shift) as i64 >> shift
+    match nbits {
+        0 => 0,
+        1..=63 => {
+            let shift = u64::BITS - nbits;
+            (value << shift) as i64 >> shift
+        }
+        _ => value as i64,
+    }
 }
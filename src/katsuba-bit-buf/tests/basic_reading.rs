@@ -1,9 +1,7 @@
-use std::io;
-
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, ReadError};
 
 #[test]
-fn read_primitives() -> io::Result<()> {
+fn read_primitives() -> Result<(), ReadError> {
     let mut buf = BitReader::new(&[0xDE, 0xC0, 0xAD, 0xDE]);
 
     assert_eq!(buf.remaining_bits(), 32);
@@ -22,7 +20,7 @@ fn read_primitives() -> io::Result<()> {
 }
 
 #[test]
-fn read_bits_and_alignment() -> io::Result<()> {
+fn read_bits_and_alignment() -> Result<(), ReadError> {
     let mut buf = BitReader::new(&[1, 2, 3, 4]);
 
     assert_eq!(buf.refill_bits(), 32);
@@ -54,3 +52,95 @@ fn read_bits_and_alignment() -> io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn positions_and_seeking() -> Result<(), ReadError> {
+    let mut buf = BitReader::new(&[0b1010_1100, 0xFF, 0x12]);
+
+    assert_eq!(buf.bit_position(), 0);
+    assert_eq!(buf.data(), &[0b1010_1100, 0xFF, 0x12]);
+    buf.refill_bits();
+    buf.consume(3)?;
+    assert_eq!(buf.bit_position(), 3);
+    assert_eq!(buf.byte_position(), 0);
+
+    buf.consume(9)?;
+    assert_eq!(buf.bit_position(), 12);
+    assert_eq!(buf.byte_position(), 1);
+
+    buf.seek_to_bit(2)?;
+    assert_eq!(buf.bit_position(), 2);
+    assert!(matches!(buf.peek(4)?, 0b1011));
+
+    buf.seek_to_bit(16)?;
+    assert_eq!(buf.refill_bits(), 8);
+    assert!(matches!(buf.peek(8)?, 0x12));
+
+    buf.seek_to_bit(24)?;
+    assert_eq!(buf.remaining_bits(), 0);
+    assert!(buf.seek_to_bit(25).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn checkpoint_and_restore() -> Result<(), ReadError> {
+    let mut buf = BitReader::new(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+    buf.refill_bits();
+    buf.consume(5)?;
+    let checkpoint = buf.checkpoint();
+    let position = buf.bit_position();
+
+    buf.consume(11)?;
+    buf.realign_to_byte();
+    buf.read_bytes(4)?;
+    assert_ne!(buf.bit_position(), position);
+
+    buf.restore(checkpoint);
+    assert_eq!(buf.bit_position(), position);
+    assert!(matches!(buf.peek(3)?, 0));
+    buf.consume(3)?;
+    assert!(matches!(buf.peek(8)?, 2));
+
+    Ok(())
+}
+
+#[test]
+fn structured_errors() {
+    let mut buf = BitReader::new(&[0xAB, 0xCD]);
+
+    assert_eq!(buf.refill_bits(), 16);
+    assert_eq!(buf.peek(u64::BITS), Err(ReadError::InvalidBitCount(64)));
+
+    let err = buf.consume(17).unwrap_err();
+    assert!(err.is_eof());
+    assert_eq!(
+        err,
+        ReadError::UnexpectedEof {
+            requested: 17,
+            available: 16
+        }
+    );
+
+    buf.realign_to_byte();
+    assert_eq!(
+        buf.read_bytes(3),
+        Err(ReadError::UnexpectedEof {
+            requested: 24,
+            available: 16
+        })
+    );
+
+    assert!(!buf.seek_to_bit(17).unwrap_err().is_eof());
+}
+
+#[test]
+fn sign_extend_edge_widths() {
+    use katsuba_bit_buf::utils::sign_extend;
+
+    assert_eq!(sign_extend(0b101, 3), -3);
+    assert_eq!(sign_extend(u64::MAX, 0), 0);
+    assert_eq!(sign_extend(u64::MAX, 64), -1);
+    assert_eq!(sign_extend(1, 65), 1);
+}
@@ -0,0 +1,61 @@
+use std::{collections::HashMap, mem, path::PathBuf};
+
+use crate::{Buffer, Task};
+
+// The most files a single batch task will write.
+const MAX_BATCH_FILES: usize = 16;
+
+/// Groups small files by their target directory into batch tasks.
+///
+/// Files of at least the threshold size are turned into regular
+/// [`Task::create_file`] tasks right away. Smaller ones are held
+/// back until enough of them for the same directory and mode are
+/// collected, and then written by a single [`Task::create_files`].
+///
+/// Batches which are not full yet must be retrieved with
+/// [`FileBatcher::finish`] before joining the executor.
+pub struct FileBatcher {
+    threshold: usize,
+    pending: HashMap<(PathBuf, u32), Vec<(PathBuf, Buffer<'static>)>>,
+}
+
+impl FileBatcher {
+    /// Creates a batcher for files smaller than `threshold` bytes.
+    ///
+    /// A threshold of `0` disables batching.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Adds a file to be created and returns a task to dispatch, if
+    /// one is ready.
+    pub fn push(&mut self, path: PathBuf, contents: Buffer<'static>, mode: u32) -> Option<Task> {
+        if contents.len() >= self.threshold {
+            return Some(Task::create_file(path, contents, mode));
+        }
+
+        let dir = path.parent().map(PathBuf::from).unwrap_or_default();
+        let batch = self.pending.entry((dir.clone(), mode)).or_default();
+        batch.push((path, contents));
+
+        // Full batches are flushed so the memory held back by this
+        // batcher stays bounded.
+        if batch.len() < MAX_BATCH_FILES {
+            return None;
+        }
+
+        Some(Task::create_files(dir, mem::take(batch), mode))
+    }
+
+    /// Consumes the batcher and yields tasks for all the files which
+    /// are still pending.
+    pub fn finish(self) -> impl Iterator<Item = Task> {
+        self.pending
+            .into_iter()
+            .filter(|(_, files)| !files.is_empty())
+            .map(|((dir, mode), files)| Task::create_files(dir, files, mode))
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use thiserror::Error;
+
+/// Error returned when work was aborted through a
+/// [`CancellationToken`].
+#[derive(Clone, Debug, Error)]
+#[error("operation was cancelled")]
+pub struct Cancelled;
+
+/// A shared flag for cancelling work on an [`Executor`].
+///
+/// Once cancelled, tasks which have not started yet are dropped
+/// by the executor while in-flight tasks run to completion.
+///
+/// [`Executor`]: crate::Executor
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a new token which is not cancelled.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all work observing this token.
+    ///
+    /// Returns whether the token was already cancelled before.
+    #[inline]
+    pub fn cancel(&self) -> bool {
+        self.0.swap(true, Ordering::AcqRel)
+    }
+
+    /// Whether the token has been cancelled.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Fails with [`Cancelled`] if the token has been cancelled.
+    #[inline]
+    pub fn check(&self) -> Result<(), Cancelled> {
+        match self.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+}
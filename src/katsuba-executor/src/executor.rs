@@ -1,8 +1,15 @@
-use std::{env, io, option::IntoIter as OptionIter, path::PathBuf, thread};
+use std::{
+    env, fmt, io,
+    option::IntoIter as OptionIter,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
 
 use thiserror::Error;
 
-use crate::memory::Buffer;
+use crate::{memory::Buffer, CancellationToken, TimingLog};
 
 mod current;
 use current::Current;
@@ -31,6 +38,58 @@ fn available_threads() -> Result<usize, BadConfiguration> {
     }
 }
 
+/// An error from a task which failed on the executor.
+///
+/// Executors yield these from [`Executor::dispatch`] and
+/// [`Executor::join`], so that failures can be attributed to the
+/// task which caused them.
+#[derive(Debug, Error)]
+pub struct TaskError {
+    /// The path the failing operation was performed on.
+    ///
+    /// For [`TaskKind::CreateFiles`], this is the file which could
+    /// not be written rather than its directory. Tasks created with
+    /// [`Task::run`] have an empty path.
+    pub path: PathBuf,
+    /// The [name](TaskKind::name) of the failing task's kind.
+    pub kind: &'static str,
+    /// The number of files the task did not write.
+    ///
+    /// Batches stop at their first error, so this also counts the
+    /// files after the failing one.
+    pub unwritten: usize,
+    /// The underlying I/O error.
+    pub source: io::Error,
+}
+
+impl TaskError {
+    fn new(path: &Path, kind: &'static str, unwritten: usize, source: io::Error) -> Self {
+        Self {
+            path: path.to_owned(),
+            kind,
+            unwritten,
+            source,
+        }
+    }
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Jobs report their own context, see `Task::run`.
+        if self.path.as_os_str().is_empty() {
+            return write!(f, "{}", self.source);
+        }
+
+        write!(
+            f,
+            "{} '{}' failed: {}",
+            self.kind,
+            self.path.display(),
+            self.source
+        )
+    }
+}
+
 /// A task to carry out inside the executor.
 ///
 /// Tasks are constructed by the user and dispatched to the
@@ -43,25 +102,87 @@ pub struct Task {
     /// The specific type of operation to perform.
     pub kind: TaskKind,
     /// The outcome of the operation, set after completion.
-    pub result: io::Result<()>,
+    pub result: Result<(), TaskError>,
 }
 
+/// A unit of user-defined work for [`TaskKind::Run`].
+pub type Job = Box<dyn FnOnce() -> io::Result<()> + Send>;
+
 /// Types of I/O to process on the worker threads.
-#[derive(Debug)]
 pub enum TaskKind {
     /// Creates a new file at the given path with specified contents.
     ///
-    /// Optionally, on UNIX platforms, a file mode choice may be
-    /// respected by the platform.
+    /// On UNIX platforms, the file is created with the given mode.
+    /// Other platforms only map a mode without write permissions
+    /// to a read-only file.
     CreateFile {
         contents: Buffer<'static>,
         mode: u32,
     },
 
+    /// Creates several new files with their specified contents.
+    ///
+    /// This amortizes dispatch overhead for many small files, see
+    /// [`FileBatcher`]. The files are written in order and the first
+    /// error stops processing.
+    ///
+    /// [`FileBatcher`]: crate::FileBatcher
+    CreateFiles {
+        files: Vec<(PathBuf, Buffer<'static>)>,
+        mode: u32,
+    },
+
     /// Creates a directory from the given path.
     ///
     /// This will also create all subdirectories.
     CreateDir,
+
+    /// Runs an arbitrary closure.
+    ///
+    /// Closures which need memory for I/O may request a [`Buffer`]
+    /// with [`Executor::request_buffer`] before dispatching and
+    /// move it into the closure.
+    Run(Job),
+}
+
+impl TaskKind {
+    /// Gets a short name for the kind of task, e.g. `create_file`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CreateFile { .. } => "create_file",
+            Self::CreateFiles { .. } => "create_files",
+            Self::CreateDir => "create_dir",
+            Self::Run(..) => "run",
+        }
+    }
+
+    // The number of bytes the task writes to files.
+    fn bytes(&self) -> u64 {
+        match self {
+            Self::CreateFile { contents, .. } => contents.len() as u64,
+            Self::CreateFiles { files, .. } => files.iter().map(|(_, c)| c.len() as u64).sum(),
+            Self::CreateDir | Self::Run(..) => 0,
+        }
+    }
+}
+
+impl fmt::Debug for TaskKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateFile { contents, mode } => f
+                .debug_struct("CreateFile")
+                .field("contents", contents)
+                .field("mode", mode)
+                .finish(),
+            Self::CreateFiles { files, mode } => f
+                .debug_struct("CreateFiles")
+                .field("files", files)
+                .field("mode", mode)
+                .finish(),
+            Self::CreateDir => f.write_str("CreateDir"),
+            Self::Run(..) => f.debug_tuple("Run").finish_non_exhaustive(),
+        }
+    }
 }
 
 impl Task {
@@ -74,6 +195,16 @@ impl Task {
         }
     }
 
+    /// Creates a [`Task`] for making several new files in the
+    /// directory at `path`.
+    pub fn create_files(path: PathBuf, files: Vec<(PathBuf, Buffer<'static>)>, mode: u32) -> Self {
+        Self {
+            path,
+            kind: TaskKind::CreateFiles { files, mode },
+            result: Ok(()),
+        }
+    }
+
     /// Creates a [`Task`] for making new directories.
     pub fn create_dir(path: PathBuf) -> Self {
         Self {
@@ -83,16 +214,101 @@ impl Task {
         }
     }
 
+    /// Creates a [`Task`] which runs `f` on the executor.
+    ///
+    /// The task has an empty path; errors returned by `f` should
+    /// carry enough context to be reported on their own.
+    pub fn run<F>(f: F) -> Self
+    where
+        F: FnOnce() -> io::Result<()> + Send + 'static,
+    {
+        Self {
+            path: PathBuf::new(),
+            kind: TaskKind::Run(Box::new(f)),
+            result: Ok(()),
+        }
+    }
+
+    /// Creates a [`Task`] which runs `f` on the executor, along with
+    /// a [`Handle`] to its return value.
+    ///
+    /// Unlike [`Task::run`], `f` cannot fail on its own; fallible work
+    /// returns its [`Result`] through the handle.
+    pub fn spawn<F, T>(f: F) -> (Self, Handle<T>)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let task = Self::run(move || {
+            // The handle may have been dropped, which is fine.
+            let _ = tx.send(f());
+            Ok(())
+        });
+
+        (task, Handle(rx))
+    }
+
+    // Processes the task and records its timing to `timings`, if any.
+    // `queued` is when the task was dispatched.
+    pub(super) fn process_timed(&mut self, timings: Option<&TimingLog>, queued: Instant) {
+        let Some(timings) = timings else {
+            return self.process();
+        };
+
+        let bytes = self.kind.bytes();
+        let started = Instant::now();
+        self.process();
+        timings.record(&self.kind, bytes, queued, started);
+    }
+
     pub(super) fn process(&mut self) {
-        match &mut self.kind {
-            TaskKind::CreateFile { contents, mode } => {
-                self.result = r#impl::write_file(&self.path, contents, *mode);
+        let path = &self.path;
+        let kind = self.kind.name();
+        self.result = match &mut self.kind {
+            TaskKind::CreateFile { contents, mode } => r#impl::write_file(path, contents, *mode)
+                .map_err(|e| TaskError::new(path, kind, 1, e)),
+
+            TaskKind::CreateFiles { files, mode } => {
+                files
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(i, (path, contents))| {
+                        r#impl::write_file(path, contents, *mode)
+                            .map_err(|e| TaskError::new(path, kind, files.len() - i, e))
+                    })
             }
 
             TaskKind::CreateDir => {
-                self.result = r#impl::create_dir(&self.path);
+                r#impl::create_dir(path).map_err(|e| TaskError::new(path, kind, 0, e))
             }
-        }
+
+            TaskKind::Run(job) => {
+                // A job can only run once; leave a no-op in its place.
+                let job = std::mem::replace(job, Box::new(|| Ok(())));
+                job().map_err(|e| TaskError::new(path, kind, 0, e))
+            }
+        };
+    }
+}
+
+/// A handle to the return value of a task created with
+/// [`Task::spawn`].
+pub struct Handle<T>(mpsc::Receiver<T>);
+
+impl<T> Handle<T> {
+    /// Blocks until the task has finished and gets its value.
+    ///
+    /// Returns [`None`] when the task was dropped without being
+    /// processed, e.g. due to cancellation, or when it panicked.
+    pub fn wait(self) -> Option<T> {
+        self.0.recv().ok()
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").finish_non_exhaustive()
     }
 }
 
@@ -104,6 +320,9 @@ impl Task {
 ///
 /// The API is the same for both flavors of execution and users
 /// should not need to worry about any execution flavor details.
+///
+/// Every executor observes a [`CancellationToken`] which can be
+/// replaced with [`Executor::with_cancellation`].
 pub enum Executor {
     /// A single-threaded executor on the current thread.
     Current(Current),
@@ -118,13 +337,66 @@ impl Executor {
         Self::Current(Current::new())
     }
 
+    /// Creates a multithreaded executor with `nthreads` workers.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `nthreads` is zero.
+    #[inline]
+    pub fn threaded(nthreads: usize) -> Self {
+        Self::Threaded(Threaded::new(nthreads))
+    }
+
     /// Gets the preferred executor for the configuration of available
     /// worker threads on the system.
     #[inline]
     pub fn get() -> Result<Self, BadConfiguration> {
         match available_threads()? {
             0 | 1 => Ok(Self::current()),
-            n => Ok(Self::Threaded(Threaded::new(n))),
+            n => Ok(Self::threaded(n)),
+        }
+    }
+
+    /// Gets the number of tasks the executor can process at once.
+    #[inline]
+    pub fn workers(&self) -> usize {
+        match self {
+            Self::Threaded(t) => t.workers(),
+            Self::Current(..) => 1,
+        }
+    }
+
+    /// Makes the executor observe the given cancellation token.
+    ///
+    /// When `token` is cancelled, tasks which have not started
+    /// processing yet will be dropped.
+    #[inline]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        match &mut self {
+            Self::Threaded(t) => t.cancel = token,
+            Self::Current(c) => c.cancel = token,
+        }
+
+        self
+    }
+
+    /// Makes the executor record the timing of every task to `log`.
+    #[inline]
+    pub fn with_timings(mut self, log: TimingLog) -> Self {
+        match &mut self {
+            Self::Threaded(t) => t.timings = Some(log),
+            Self::Current(c) => c.timings = Some(log),
+        }
+
+        self
+    }
+
+    /// Gets the cancellation token observed by the executor.
+    #[inline]
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        match self {
+            Self::Threaded(t) => &t.cancel,
+            Self::Current(c) => &c.cancel,
         }
     }
 
@@ -149,6 +421,12 @@ impl Executor {
     }
 
     /// Dispatches a task to be performed inside the executor.
+    ///
+    /// If the executor was cancelled, the task is dropped without
+    /// being processed.
+    ///
+    /// While waiting for the queue to make room, this yields the
+    /// results of earlier tasks in the order they were dispatched.
     pub fn dispatch(&self, task: Task) -> SubmitIterator<'_> {
         match self {
             Self::Threaded(t) => SubmitIterator::Threaded(t.dispatch(task)),
@@ -157,6 +435,10 @@ impl Executor {
     }
 
     /// Joins all pending tasks on the executor.
+    ///
+    /// Results are yielded in the order the tasks were dispatched
+    /// in, regardless of the order they finish in. Tasks dropped due
+    /// to cancellation yield no result.
     pub fn join(&self) -> JoinIterator<'_> {
         match self {
             Self::Threaded(t) => JoinIterator::Threaded(t.join()),
@@ -169,12 +451,12 @@ impl Executor {
 /// available to enqueue the pending task.
 #[must_use = "Consume this Iterator to ensure the pending task gets executed"]
 pub enum SubmitIterator<'a> {
-    Current(OptionIter<io::Result<()>>),
+    Current(OptionIter<Result<(), TaskError>>),
     Threaded(threaded::SubmitIterator<'a>),
 }
 
 impl Iterator for SubmitIterator<'_> {
-    type Item = io::Result<()>;
+    type Item = Result<(), TaskError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self {
@@ -193,7 +475,7 @@ pub enum JoinIterator<'a> {
 }
 
 impl Iterator for JoinIterator<'_> {
-    type Item = io::Result<()>;
+    type Item = Result<(), TaskError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self {
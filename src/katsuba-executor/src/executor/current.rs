@@ -1,12 +1,17 @@
-use std::{io, option::IntoIter as OptionIter, sync::Arc};
+use std::{option::IntoIter as OptionIter, sync::Arc, time::Instant};
 
-use super::Task;
-use crate::memory::{Pool, PoolRef};
+use super::{Task, TaskError};
+use crate::{
+    memory::{Pool, PoolRef},
+    CancellationToken, TimingLog,
+};
 
 /// An executor flavor which carries out every task on the
 /// current thread in sequential order.
 pub struct Current {
     pool: Arc<Pool>,
+    pub(super) cancel: CancellationToken,
+    pub(super) timings: Option<TimingLog>,
 }
 
 impl Current {
@@ -19,7 +24,11 @@ impl Current {
         let pool = Pool::new(1);
         pool.create_with(|vec| vec.reserve_exact(1024 * 1024));
 
-        Self { pool }
+        Self {
+            pool,
+            cancel: CancellationToken::new(),
+            timings: None,
+        }
     }
 
     pub(super) fn acquire_memory(&self, size: usize) -> PoolRef {
@@ -30,8 +39,12 @@ impl Current {
     }
 
     #[must_use]
-    pub(super) fn dispatch(&self, mut task: Task) -> OptionIter<io::Result<()>> {
-        task.process();
+    pub(super) fn dispatch(&self, mut task: Task) -> OptionIter<Result<(), TaskError>> {
+        if self.cancel.is_cancelled() {
+            return None.into_iter();
+        }
+
+        task.process_timed(self.timings.as_ref(), Instant::now());
         Some(task.result).into_iter()
     }
 }
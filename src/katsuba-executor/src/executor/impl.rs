@@ -6,19 +6,29 @@ use std::{
 
 /// Creates a new file in the filesystem.
 ///
-/// The file mode may be optionally respected on UNIX platforms,
-/// but is ignored everywhere else.
-pub fn write_file(path: &Path, contents: &[u8], _mode: u32) -> io::Result<()> {
+/// On UNIX platforms, the file is created with the given mode,
+/// subject to the process umask. Elsewhere, only the lack of any
+/// write bits is respected by marking the file as read-only.
+pub fn write_file(path: &Path, contents: &[u8], mode: u32) -> io::Result<()> {
     let mut opts = fs::OpenOptions::new();
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::OpenOptionsExt;
-        opts.mode(_mode);
+        opts.mode(mode);
     }
 
     let mut file = opts.write(true).create(true).truncate(true).open(path)?;
-    file.write_all(contents)
+    file.write_all(contents)?;
+
+    #[cfg(not(unix))]
+    if mode & 0o222 == 0 {
+        let mut perms = file.metadata()?.permissions();
+        perms.set_readonly(true);
+        file.set_permissions(perms)?;
+    }
+
+    Ok(())
 }
 
 /// Creates a new directory in the filesystem.
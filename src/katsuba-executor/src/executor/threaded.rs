@@ -1,13 +1,18 @@
 use std::{
-    io,
+    cell::RefCell,
+    collections::BTreeMap,
     sync::{mpsc, Arc},
+    time::Instant,
 };
 
 use enum_map::{enum_map, Enum, EnumMap};
 use threadpool::{Builder, ThreadPool};
 
-use super::Task;
-use crate::memory::{Pool, PoolRef};
+use super::{Task, TaskError};
+use crate::{
+    memory::{Pool, PoolRef},
+    CancellationToken, TimingLog,
+};
 
 const WORKER_NAME: &str = "katsuba-worker";
 const WORKER_STACK: usize = 1_048_576;
@@ -58,17 +63,66 @@ impl Bucket {
     }
 }
 
+// Notifications carry the sequence number the task was dispatched
+// with, so that results can be put back into dispatch order.
 enum Notification {
-    Done(io::Result<()>),
+    Done(u64, Result<(), TaskError>),
+    // A queued task was dropped due to cancellation. This still
+    // notifies producers waiting for queue capacity.
+    Dropped(u64),
     End,
 }
 
+// Results of tasks which finished before all the tasks dispatched
+// ahead of them.
+#[derive(Default)]
+struct Reorder {
+    // The sequence number of the next dispatched task.
+    dispatched: u64,
+    // The sequence number of the next result to yield.
+    next: u64,
+    // Finished tasks by sequence number; dropped ones have no result.
+    finished: BTreeMap<u64, Option<Result<(), TaskError>>>,
+}
+
+impl Reorder {
+    fn push(&mut self, notification: Notification) {
+        match notification {
+            Notification::Done(seq, res) => self.finished.insert(seq, Some(res)),
+            Notification::Dropped(seq) => self.finished.insert(seq, None),
+            Notification::End => None,
+        };
+    }
+
+    // Takes the next result in dispatch order, if its task finished.
+    //
+    // With `flush`, all finished tasks are taken in order even when
+    // there are gaps, e.g. from tasks that panicked.
+    fn pop(&mut self, flush: bool) -> Option<Result<(), TaskError>> {
+        loop {
+            let seq = match flush {
+                true => *self.finished.keys().next()?,
+                false => self.next,
+            };
+
+            let res = self.finished.remove(&seq)?;
+            self.next = seq + 1;
+            if res.is_some() {
+                return res;
+            }
+        }
+    }
+}
+
 /// An executor flavor which processes tasks on background threads.
 pub struct Threaded {
     pool: ThreadPool,
     tx: mpsc::Sender<Notification>,
     rx: mpsc::Receiver<Notification>,
     memory_buckets: EnumMap<BucketSize, Bucket>,
+    reorder: RefCell<Reorder>,
+    pub(super) cancel: CancellationToken,
+    pub(super) timings: Option<TimingLog>,
 }
 
 impl Threaded {
@@ -101,6 +155,9 @@ impl Threaded {
             tx,
             rx,
             memory_buckets,
+            reorder: RefCell::default(),
+            cancel: CancellationToken::new(),
+            timings: None,
         }
     }
 
@@ -136,13 +193,38 @@ impl Threaded {
     }
 
     pub(super) fn execute(&self, mut task: Task) {
+        // Don't even bother queueing tasks when we're cancelled.
+        if self.cancel.is_cancelled() {
+            return;
+        }
+
+        let seq = {
+            let mut reorder = self.reorder.borrow_mut();
+            reorder.dispatched += 1;
+            reorder.dispatched - 1
+        };
+
         let tx = self.tx.clone();
+        let cancel = self.cancel.clone();
+        let timings = self.timings.clone();
+        let queued = Instant::now();
         self.pool.execute(move || {
-            task.process();
-            let _ = tx.send(Notification::Done(task.result));
+            // Cancellation may have happened while the task was
+            // waiting in the queue. Drop it without processing.
+            if cancel.is_cancelled() {
+                let _ = tx.send(Notification::Dropped(seq));
+                return;
+            }
+
+            task.process_timed(timings.as_ref(), queued);
+            let _ = tx.send(Notification::Done(seq, task.result));
         });
     }
 
+    pub(super) fn workers(&self) -> usize {
+        self.pool.max_count()
+    }
+
     #[must_use]
     pub(super) fn dispatch(&self, task: Task) -> SubmitIterator<'_> {
         SubmitIterator {
@@ -156,7 +238,10 @@ impl Threaded {
         self.pool.join();
         let _ = self.tx.send(Notification::End);
 
-        JoinIterator { threaded: self }
+        JoinIterator {
+            threaded: self,
+            ended: false,
+        }
     }
 }
 
@@ -172,50 +257,57 @@ pub struct SubmitIterator<'a> {
 }
 
 impl Iterator for SubmitIterator<'_> {
-    type Item = io::Result<()>;
+    type Item = Result<(), TaskError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.threaded.pool.queued_count() < QUEUE_THRESHOLD {
-            if let Some(t) = self.task.take() {
-                self.threaded.execute(t);
+        let threaded = self.threaded;
+        loop {
+            if let Some(res) = threaded.reorder.borrow_mut().pop(false) {
+                return Some(res);
             }
 
-            None
-        } else {
-            for notification in self.threaded.rx.iter() {
-                if let Notification::Done(t) = notification {
-                    return Some(t);
+            if threaded.pool.queued_count() < QUEUE_THRESHOLD {
+                if let Some(t) = self.task.take() {
+                    threaded.execute(t);
                 }
 
-                if self.threaded.pool.queued_count() < QUEUE_THRESHOLD {
-                    if let Some(t) = self.task.take() {
-                        self.threaded.execute(t);
-                    }
-
-                    return None;
-                }
+                return None;
             }
 
-            unreachable!()
+            // We hold a sender ourselves, so this never disconnects.
+            let notification = threaded.rx.recv().unwrap();
+            threaded.reorder.borrow_mut().push(notification);
         }
     }
 }
 
 pub struct JoinIterator<'a> {
     threaded: &'a Threaded,
+    ended: bool,
 }
 
 impl Iterator for JoinIterator<'_> {
-    type Item = io::Result<()>;
+    type Item = Result<(), TaskError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.threaded.rx.recv() {
-            Ok(notification) => match notification {
-                Notification::Done(t) => Some(t),
-                Notification::End => None,
-            },
+        let threaded = self.threaded;
+        loop {
+            let mut reorder = threaded.reorder.borrow_mut();
+            if let Some(res) = reorder.pop(self.ended) {
+                return Some(res);
+            }
+            if self.ended {
+                // Tasks which never reported back can't hold up the
+                // results of later joins.
+                reorder.next = reorder.dispatched;
+                return None;
+            }
 
-            Err(_) => None,
+            // All tasks notified us before the end of the join.
+            match threaded.rx.recv().unwrap() {
+                Notification::End => self.ended = true,
+                notification => reorder.push(notification),
+            }
         }
     }
 }
@@ -17,6 +17,32 @@
 //! complete at a much faster rate than any file I/O, all the processing
 //! mostly happens on the main thread and can still generate reasonable
 //! loads onto the executor.
+//!
+//! # Batching
+//!
+//! A [`FileBatcher`] can group small files in the same directory into
+//! single tasks. This is opt-in; the `wad_write` benchmark has not shown
+//! it to be faster than writing every file on its own.
+//!
+//! # Custom work
+//!
+//! Besides file I/O, arbitrary closures can be dispatched with
+//! [`Task::run`]. They share the worker threads and queue limits of
+//! the executor, so no second pool is needed for CPU-bound work that
+//! happens alongside extraction. [`Task::spawn`] additionally hands
+//! back the return value of a closure through a [`Handle`].
+//!
+//! # Timing
+//!
+//! For tuning, an executor can record how long every task waited in
+//! the queue and how long it took with a [`TimingLog`].
+//!
+//! # Cancellation
+//!
+//! Every executor holds a [`CancellationToken`]. When it is
+//! cancelled, queued tasks are dropped without being processed
+//! and in-flight tasks finish normally. Producers are expected
+//! to check the token and stop generating new work.
 
 #![deny(
     rust_2018_idioms,
@@ -24,8 +50,17 @@
     unsafe_op_in_unsafe_fn
 )]
 
+mod batch;
+pub use batch::FileBatcher;
+
+mod cancel;
+pub use cancel::{CancellationToken, Cancelled};
+
 mod executor;
 pub use executor::*;
 
 mod memory;
 pub use memory::Buffer;
+
+mod timing;
+pub use timing::{TaskTiming, TimingLog};
@@ -0,0 +1,59 @@
+use std::{
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::TaskKind;
+
+/// How long a task spent inside an [`Executor`].
+///
+/// [`Executor`]: crate::Executor
+#[derive(Clone, Copy, Debug)]
+pub struct TaskTiming {
+    /// The name of the task's kind, see [`TaskKind::name`].
+    pub kind: &'static str,
+    /// The number of bytes written by the task.
+    pub bytes: u64,
+    /// The time between dispatching the task and starting it.
+    pub queued: Duration,
+    /// The time it took to process the task.
+    pub duration: Duration,
+}
+
+/// A shared log of [`TaskTiming`]s for every task processed by an
+/// [`Executor`].
+///
+/// Collection is opt-in through [`Executor::with_timings`].
+///
+/// [`Executor`]: crate::Executor
+/// [`Executor::with_timings`]: crate::Executor::with_timings
+#[derive(Clone, Debug, Default)]
+pub struct TimingLog(Arc<Mutex<Vec<TaskTiming>>>);
+
+impl TimingLog {
+    /// Creates a new, empty log.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes all timings recorded so far out of the log.
+    pub fn take(&self) -> Vec<TaskTiming> {
+        let mut timings = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::take(&mut *timings)
+    }
+
+    pub(crate) fn record(&self, kind: &TaskKind, bytes: u64, queued: Instant, started: Instant) {
+        let timing = TaskTiming {
+            kind: kind.name(),
+            bytes,
+            queued: started.saturating_duration_since(queued),
+            duration: started.elapsed(),
+        };
+
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(timing);
+    }
+}
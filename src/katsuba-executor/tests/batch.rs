@@ -0,0 +1,83 @@
+use std::fs;
+
+use katsuba_executor::{Buffer, Executor, FileBatcher, Task, TaskKind};
+
+#[test]
+fn batch_small_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let (a, b) = (dir.path().join("a"), dir.path().join("b"));
+    fs::create_dir(&a).unwrap();
+    fs::create_dir(&b).unwrap();
+
+    let mut batcher = FileBatcher::new(16);
+    let mut tasks = Vec::new();
+
+    // Large files are not held back.
+    let large = batcher.push(a.join("large"), Buffer::owned(vec![1; 16]), 0o644);
+    assert!(matches!(large.unwrap().kind, TaskKind::CreateFile { .. }));
+
+    // A full batch is flushed right away.
+    for i in 0..16 {
+        let name = format!("{i}.txt");
+        tasks.extend(batcher.push(a.join(name), Buffer::owned(vec![i]), 0o644));
+    }
+    assert_eq!(tasks.len(), 1);
+
+    tasks.extend(batcher.push(a.join("x"), Buffer::borrowed(b"x"), 0o644));
+    tasks.extend(batcher.push(b.join("y"), Buffer::borrowed(b"y"), 0o644));
+    assert_eq!(tasks.len(), 1);
+
+    // The rest is grouped per directory.
+    tasks.extend(batcher.finish());
+    assert_eq!(tasks.len(), 3);
+    for task in &tasks {
+        let TaskKind::CreateFiles { files, .. } = &task.kind else {
+            panic!("expected batch task, got {task:?}");
+        };
+        assert!(files
+            .iter()
+            .all(|(path, _)| path.parent() == Some(&task.path)));
+    }
+
+    let ex = Executor::current();
+    for task in tasks {
+        for pending in ex.dispatch(task) {
+            pending.unwrap();
+        }
+    }
+
+    assert_eq!(fs::read(a.join("15.txt")).unwrap(), [15]);
+    assert_eq!(fs::read(a.join("x")).unwrap(), b"x");
+    assert_eq!(fs::read(b.join("y")).unwrap(), b"y");
+}
+
+#[test]
+fn zero_threshold_disables_batching() {
+    let mut batcher = FileBatcher::new(0);
+    let task = batcher.push("empty".into(), Buffer::borrowed(b""), 0o644);
+    assert!(matches!(task.unwrap().kind, TaskKind::CreateFile { .. }));
+    assert_eq!(batcher.finish().count(), 0);
+}
+
+#[test]
+fn failed_batch_names_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let blocked = dir.path().join("1");
+    fs::create_dir(&blocked).unwrap();
+
+    let files = (0..3)
+        .map(|i| (dir.path().join(i.to_string()), Buffer::owned(vec![i])))
+        .collect();
+    let task = Task::create_files(dir.path().to_owned(), files, 0o644);
+
+    let ex = Executor::current();
+    let errors: Vec<_> = ex.dispatch(task).filter_map(Result::err).collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path, blocked);
+    assert_eq!(errors[0].kind, "create_files");
+    assert_eq!(errors[0].unwritten, 2);
+    assert!(errors[0].to_string().contains(&*blocked.to_string_lossy()));
+
+    assert_eq!(fs::read(dir.path().join("0")).unwrap(), [0]);
+    assert!(!dir.path().join("2").exists());
+}
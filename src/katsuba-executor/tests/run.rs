@@ -0,0 +1,117 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use katsuba_executor::{Executor, Task, TaskKind};
+
+fn run_jobs(ex: &Executor) {
+    let done = Arc::new(AtomicUsize::new(0));
+    let mut errors = Vec::new();
+
+    for i in 0..32 {
+        let done = done.clone();
+        let task = Task::run(move || {
+            done.fetch_add(1, Ordering::Relaxed);
+            match i {
+                7 => Err(io::Error::other("job 7 failed")),
+                _ => Ok(()),
+            }
+        });
+        assert!(matches!(task.kind, TaskKind::Run(..)));
+
+        errors.extend(ex.dispatch(task).filter_map(Result::err));
+    }
+    errors.extend(ex.join().filter_map(Result::err));
+
+    assert_eq!(done.load(Ordering::Relaxed), 32);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].kind, "run");
+    assert_eq!(errors[0].to_string(), "job 7 failed");
+}
+
+#[test]
+fn run_jobs_on_current() {
+    run_jobs(&Executor::current());
+}
+
+#[test]
+fn run_jobs_on_preferred() {
+    run_jobs(&Executor::get().unwrap());
+}
+
+#[test]
+fn run_jobs_on_threaded() {
+    run_jobs(&Executor::threaded(4));
+}
+
+#[test]
+fn cancelled_jobs_do_not_run() {
+    let ex = Executor::current();
+    ex.cancellation_token().cancel();
+
+    let task = Task::run(|| panic!("cancelled job ran"));
+    assert_eq!(ex.dispatch(task).count(), 0);
+}
+
+#[test]
+fn results_in_dispatch_order() {
+    let ex = Executor::threaded(4);
+    let mut results = Vec::new();
+
+    // Earlier jobs take longer, so they finish last.
+    for i in 0..16u64 {
+        let task = Task::run(move || {
+            thread::sleep(Duration::from_millis(2 * (16 - i)));
+            Err(io::Error::other(format!("job {i}")))
+        });
+        results.extend(ex.dispatch(task));
+    }
+    results.extend(ex.join());
+
+    let order: Vec<_> = results
+        .into_iter()
+        .map(|r| r.unwrap_err().to_string())
+        .collect();
+    let expected: Vec<_> = (0..16).map(|i| format!("job {i}")).collect();
+    assert_eq!(order, expected);
+}
+
+fn spawn_jobs(ex: &Executor) {
+    let mut handles = Vec::new();
+    for i in 0..16u64 {
+        let (task, handle) = Task::spawn(move || i * i);
+        assert!(ex.dispatch(task).all(|r| r.is_ok()));
+        handles.push(handle);
+    }
+
+    let values: Vec<_> = handles.into_iter().map(|h| h.wait().unwrap()).collect();
+    assert_eq!(values, (0..16).map(|i| i * i).collect::<Vec<_>>());
+    assert!(ex.join().all(|r| r.is_ok()));
+}
+
+#[test]
+fn spawn_jobs_on_current() {
+    spawn_jobs(&Executor::current());
+}
+
+#[test]
+fn spawn_jobs_on_threaded() {
+    spawn_jobs(&Executor::threaded(4));
+}
+
+#[test]
+fn cancelled_spawns_have_no_value() {
+    let ex = Executor::threaded(2);
+    ex.cancellation_token().cancel();
+
+    let (task, handle) = Task::spawn(|| panic!("cancelled job ran"));
+    assert_eq!(ex.dispatch(task).count(), 0);
+    assert_eq!(ex.join().count(), 0);
+    assert!(handle.wait().is_none());
+}
@@ -0,0 +1,37 @@
+use std::fs;
+
+use katsuba_executor::{Buffer, Executor, Task, TimingLog};
+
+#[test]
+fn record_task_timings() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = TimingLog::new();
+    let ex = Executor::get().unwrap().with_timings(log.clone());
+
+    let tasks = [
+        Task::create_dir(dir.path().join("sub")),
+        Task::create_file(dir.path().join("a"), Buffer::owned(vec![0; 10]), 0o644),
+        Task::run(|| Ok(())),
+    ];
+    for task in tasks {
+        // Joining in between keeps the directory ahead of the file.
+        for pending in ex.dispatch(task) {
+            pending.unwrap();
+        }
+        for pending in ex.join() {
+            pending.unwrap();
+        }
+    }
+    assert_eq!(fs::read(dir.path().join("a")).unwrap(), [0; 10]);
+
+    let mut timings = log.take();
+    timings.sort_by_key(|t| t.kind);
+    let summary: Vec<_> = timings.iter().map(|t| (t.kind, t.bytes)).collect();
+    assert_eq!(
+        summary,
+        [("create_dir", 0), ("create_file", 10), ("run", 0)]
+    );
+
+    // Taking the timings empties the log.
+    assert!(log.take().is_empty());
+}
@@ -67,12 +67,12 @@ pub struct NavigationGraph {
 impl NavigationGraph {
     /// Attempts to parse a NAV graph from a given [`Read`]er.
     pub fn parse<R: Read + Seek>(mut reader: R) -> BinResult<Self> {
-        reader.read_le().map_err(Into::into)
+        reader.read_le()
     }
 
     /// Writes the NAV graph to the given [`Write`]r.
     pub fn write<W: Write + Seek>(&self, mut writer: W) -> BinResult<()> {
-        writer.write_le(self).map_err(Into::into)
+        writer.write_le(self)
     }
 }
 
@@ -98,11 +98,11 @@ pub struct ZoneNavigationGraph {
 impl ZoneNavigationGraph {
     /// Attempts to parse a zonenav graph from a given [`Read`]er.
     pub fn parse<R: Read + Seek>(mut reader: R) -> BinResult<Self> {
-        reader.read_le().map_err(Into::into)
+        reader.read_le()
     }
 
     /// Writes the zonenav graph to the given [`Write`]r.
     pub fn write<W: Write + Seek>(&self, mut writer: W) -> BinResult<()> {
-        writer.write_le(self).map_err(Into::into)
+        writer.write_le(self)
     }
 }
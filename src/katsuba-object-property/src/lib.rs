@@ -3,6 +3,33 @@
 //! ObjectProperty is a reflection and serialization system for C++ classes.
 //! Serialized object state can be found in various places of the networking
 //! protocol or the game files.
+//!
+//! Most files can be decoded with the functions at the crate root:
+//!
+//! ```no_run
+//! # #[cfg(feature = "de")] {
+//! use std::sync::Arc;
+//!
+//! use katsuba_types::TypeList;
+//!
+//! let types = TypeList::from_str(&std::fs::read_to_string("types.json")?)?;
+//! let value = katsuba_object_property::from_file("Root.xml", Arc::new(types))?;
+//! # }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The [`serde`] module offers full control over the process.
+//!
+//! # Features
+//!
+//! - `value`: The [`Value`] model on its own.
+//! - `de`: The `serde` module and the functions at the crate root.
+//!   Implies `value`.
+//! - `full`: All of the above, enabled by default.
+//! - `arena`: Values allocated from an arena, see `value::Arena`.
+//!   With `de`, these can be deserialized directly.
+//! - `serde`: `Serialize` and `Deserialize` implementations for values.
+//! - `option-guessing`: Guessing of serializer options from data.
 
 #![deny(
     rust_2018_idioms,
@@ -10,7 +37,15 @@
     unsafe_op_in_unsafe_fn
 )]
 
+#[cfg(feature = "de")]
+mod read;
+#[cfg(feature = "de")]
+pub use read::*;
+
+#[cfg(feature = "de")]
 pub mod serde;
 
+#[cfg(feature = "value")]
 pub mod value;
+#[cfg(feature = "value")]
 pub use value::Value;
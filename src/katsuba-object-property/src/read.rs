@@ -0,0 +1,48 @@
+use std::{fs, path::Path, sync::Arc};
+
+use katsuba_types::TypeList;
+
+use crate::{
+    serde::{Error, PropertyClass, Serializer, SerializerOptions},
+    Value,
+};
+
+/// Deserializes an object from `data` with the default options.
+///
+/// See [`from_slice_with`] for details.
+pub fn from_slice(data: &[u8], types: Arc<TypeList>) -> Result<Value, Error> {
+    from_slice_with(data, types, SerializerOptions::default())
+}
+
+/// Deserializes a [`PropertyClass`] object from `data`.
+///
+/// Game files are detected by their `BINd` prefix and always use
+/// their fixed config instead of `options`.
+pub fn from_slice_with(
+    data: &[u8],
+    types: Arc<TypeList>,
+    mut options: SerializerOptions,
+) -> Result<Value, Error> {
+    let data = options.strip_bind_magic(data);
+    Serializer::new(options, types)?.deserialize::<PropertyClass>(data)
+}
+
+/// Reads the file at `path` and deserializes an object from it with
+/// the default options.
+///
+/// See [`from_slice_with`] for details.
+pub fn from_file<P: AsRef<Path>>(path: P, types: Arc<TypeList>) -> Result<Value, Error> {
+    from_file_with(path, types, SerializerOptions::default())
+}
+
+/// Reads the file at `path` and deserializes an object from it.
+///
+/// See [`from_slice_with`] for details.
+pub fn from_file_with<P: AsRef<Path>>(
+    path: P,
+    types: Arc<TypeList>,
+    options: SerializerOptions,
+) -> Result<Value, Error> {
+    let data = fs::read(path)?;
+    from_slice_with(&data, types, options)
+}
@@ -1,30 +1,68 @@
 //! Serialization support for ObjectProperty values.
 
-use std::{io, sync::Arc};
+use std::{io, mem, sync::Arc};
 
 use bitflags::bitflags;
-use katsuba_types::{PropertyFlags, TypeList};
+use katsuba_types::{Property, PropertyFlags, TypeList};
 use katsuba_utils::{
-    libdeflater::{DecompressionError, Decompressor},
+    compress,
     thiserror::{self, Error},
 };
 
+use crate::value::{Path, PathSegment};
+
+mod build;
+use build::*;
+
+mod capture;
+pub use capture::*;
+
+mod container;
+
 mod de;
 
+mod defaults;
+
+mod ser;
+
 mod enum_variant;
 
 #[cfg(feature = "option-guessing")]
 mod guess;
 
+mod header;
+pub use header::*;
+
+mod limits;
+pub use limits::*;
+
+mod mask;
+pub use mask::*;
+
+mod mismatch;
+pub use mismatch::*;
+
+mod nested;
+
 mod object;
 
+mod parallel;
+
+mod pointer;
+
 mod property;
 
 mod simple_data;
 
+mod skip;
+pub use skip::*;
+
 mod type_tag;
 pub use type_tag::*;
 
+mod utf8;
+pub use utf8::*;
+
 mod utils;
 
 /// Magic header for persistent object state shipped with the client.
@@ -37,26 +75,38 @@ pub enum Error {
     #[error("{0}")]
     Io(#[from] io::Error),
 
+    /// Failed to read bits from the object stream, e.g. when it ends early.
+    #[error("{0}")]
+    Read(#[from] katsuba_bit_buf::ReadError),
+
     /// Failed to decompress a zlib object stream.
     #[error("{0}")]
-    Decompress(#[from] DecompressionError),
+    Decompress(#[from] compress::Error),
 
     /// The deserialized object as a whole was a null value.
     #[error("root object must not be null")]
     NullRoot,
 
-    /// The actual size of an inflated object after decompression does
-    /// not match the size expectation for it.
-    #[error("mismatch for inflated object size: expected {expected}, got {actual}")]
-    DecompressedSizeMismatch { expected: usize, actual: usize },
-
     /// Attempted to construct a serializer from a bad configuration.
     #[error("bad serializer configuration: {0:?}")]
     BadConfig(&'static str),
 
-    /// Configured recursion limit was exceeded during the process.
-    #[error("recursion limit exceeded")]
-    Recursion,
+    /// A class in the type list has properties with the same hash,
+    /// which would be ambiguous in serialized data.
+    #[error("properties {properties:?} of '{class}' share the hash {hash}")]
+    PropertyHashCollision {
+        class: String,
+        hash: u32,
+        properties: Vec<String>,
+    },
+
+    /// The data ends before the header the configuration expects.
+    #[error("data is too short to contain a header ({size} bytes, expected at least {expected})")]
+    TooShort { size: usize, expected: usize },
+
+    /// A configured [`Limits`] budget was exceeded.
+    #[error("exceeded {limit} limit at {path}")]
+    LimitExceeded { limit: Limit, path: Path },
 
     /// Failed to decode an UTF-8 string where one was expected.
     #[error("{0}")]
@@ -70,23 +120,94 @@ pub enum Error {
     #[error("failed to identify type with tag '{0}'")]
     UnknownType(u32),
 
+    /// A value's type has no known representation and
+    /// [`SerializerOptions::object_fallback`] is disabled.
+    #[error("no representation known for type '{0}'")]
+    UnknownValueType(String),
+
     /// Object stream specifies a property that is not part of the object.
     #[error("unknown property for object with hash '{0}'")]
     UnknownProperty(u32),
 
     /// Encoded property size did not match the actually consumed data for it.
-    #[error("mismatch for property size: expected {expected}, got {actual}")]
-    PropertySizeMismatch { expected: usize, actual: usize },
+    #[error("{0}")]
+    PropertySizeMismatch(Box<SizeMismatch>),
 
     /// Encoded properties for an object consume more size than the object is
     /// specified to be.
-    #[error("overflowed object size while consuming data")]
-    ObjectSizeMismatch,
+    #[error(
+        "property '{property}' of '{class}' spans {size} bits, \
+         but only {remaining} bits of the object remain"
+    )]
+    ObjectSizeMismatch {
+        class: String,
+        property: String,
+        size: usize,
+        remaining: usize,
+    },
+
+    /// An encoded object size is too small to cover its own prefix.
+    #[error("object size {0} is smaller than its 32-bit size prefix")]
+    InvalidObjectSize(u32),
+
+    /// An object claims to span more bits than the data has left.
+    #[error("object claims {size} bits, but only {available} remain in the data")]
+    ObjectTooLarge { size: usize, available: usize },
+
+    /// Data remains after the root object was fully deserialized.
+    #[error("{0} bytes of trailing data after the root object")]
+    TrailingData(usize),
+
+    /// Deserializing one of several consecutive root objects failed.
+    ///
+    /// `offset` is the byte offset of the object in the data after
+    /// decompression.
+    #[error("root object {index} at byte {offset}: {error}")]
+    Root {
+        index: usize,
+        offset: usize,
+        error: Box<Error>,
+    },
 
     /// When a delta-encoded property is missing from a stream which enforces
     /// its presence.
     #[error("missing delta value which must be present")]
     MissingDelta,
+
+    /// An object to serialize lacks a property which must be written.
+    #[error("missing value for property '{0}'")]
+    MissingProperty(String),
+
+    /// An object to serialize has a property its class does not declare.
+    #[error("property '{0}' is not declared by the object's class")]
+    UndeclaredProperty(String),
+
+    /// A pointer holds an object whose class does not inherit from
+    /// the declared pointee type.
+    #[error("expected object of type '{expected}' behind pointer, got '{found}'")]
+    PointeeMismatch { expected: String, found: String },
+
+    /// An object's class is neither the class its property is declared
+    /// with nor inherits from it, and
+    /// [`SerializerOptions::strict_classes`] is enabled.
+    #[error("expected object of class '{expected}', got '{found}'")]
+    ClassMismatch { expected: String, found: String },
+
+    /// A value to serialize does not match the type of its property.
+    #[error("expected value for type '{expected}', got {found}")]
+    ValueMismatch {
+        expected: String,
+        found: &'static str,
+    },
+
+    /// A length does not fit into the prefix it is encoded with.
+    #[error("length {0} exceeds the range of its prefix")]
+    LengthOverflow(usize),
+
+    /// A string to write with a null terminator contains a null
+    /// character itself.
+    #[error("null-terminated string contains a null character")]
+    InteriorNull,
 }
 
 bitflags! {
@@ -110,6 +231,31 @@ bitflags! {
     }
 }
 
+/// The order of the configuration header and the compression
+/// framing in data with [`SerializerFlags::STATEFUL_FLAGS`].
+///
+/// Without stateful flags, compression is configured upfront and
+/// only the marker precedes the object data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeaderOrder {
+    /// The flags come first and decide whether the compression
+    /// marker follows.
+    ///
+    /// This is how Katsuba serializes stateful data, and how game
+    /// files after their [`BIND_MAGIC`] are laid out.
+    #[default]
+    FlagsFirst,
+    /// The compression marker comes first, and the flags are the
+    /// start of the possibly compressed data behind it.
+    CompressionFirst,
+    /// Reads the data as [`HeaderOrder::FlagsFirst`] when the type
+    /// of the root object is known that way, and as
+    /// [`HeaderOrder::CompressionFirst`] otherwise.
+    ///
+    /// Serialization uses [`HeaderOrder::FlagsFirst`].
+    Auto,
+}
+
 /// Serializer configuration which influences how data is interpreted.
 #[derive(Clone, Copy, Debug)]
 pub struct SerializerOptions {
@@ -117,43 +263,165 @@ pub struct SerializerOptions {
     pub flags: SerializerFlags,
     /// A set of [`PropertyFlags`] for conditionally ignoring
     /// unmasked properties in a type.
+    ///
+    /// See [`PropertyMask`] for the masks of common use cases.
     pub property_mask: PropertyFlags,
     /// Whether the shallow encoding strategy is used for
     /// the data.
     pub shallow: bool,
     /// Whether the data is manually compressed.
     pub manual_compression: bool,
-    /// A recursion limit for nested data to avoid stack
-    /// overflows during deserialization.
+    /// The order of the configuration header and the compression
+    /// framing with [`SerializerFlags::STATEFUL_FLAGS`].
+    ///
+    /// Manual compression always wraps both.
+    pub header_order: HeaderOrder,
+    /// Resource budgets for deserializing untrusted data.
     ///
     /// Ignored during serialization.
-    pub recursion_limit: i8,
+    pub limits: Limits,
     /// Skips unknown types during deserialization of properties.
     ///
     /// Ignored during serialization.
     pub skip_unknown_types: bool,
+    /// Deserializes values as objects when their type is neither a
+    /// simple type nor a class in the type list.
+    ///
+    /// A warning naming the type is logged whenever this happens.
+    /// When disabled, such values fail with
+    /// [`Error::UnknownValueType`] instead.
+    ///
+    /// Ignored during serialization.
+    pub object_fallback: bool,
+    /// Fails with [`Error::ClassMismatch`] when a property declared
+    /// with a class, like `class Foo` or `class Foo*`, holds an object
+    /// of an unrelated class.
+    ///
+    /// Otherwise, a warning is logged. Such mismatches usually mean
+    /// that the data was misread some properties before.
+    ///
+    /// Ignored during serialization.
+    pub strict_classes: bool,
+    /// Fills in default values for the properties of objects which
+    /// are not part of the data, like unmasked properties in shallow
+    /// mode.
+    ///
+    /// See [`SerializerParts::default_object`] for the defaults.
+    ///
+    /// Ignored during serialization.
+    pub fill_defaults: bool,
+    /// Includes properties flagged as deprecated in shallow mode.
+    ///
+    /// Some older data was written before these properties were
+    /// deprecated and still contains them.
+    pub include_deprecated: bool,
     /// Uses djb2 for all hashes.
     ///
     /// Used by Pirate101.
     pub djb2_only: bool,
+    /// Decodes serialized objects embedded in string properties
+    /// into [`Value::Blob`][crate::Value::Blob]s.
+    ///
+    /// Candidates are recognized by a leading [`BIND_MAGIC`],
+    /// a known type hash or a stateful flags header.
+    pub decode_nested: bool,
+    /// Records the bits every property was decoded from as
+    /// [`RawSpan`]s.
+    ///
+    /// Ignored during serialization.
+    pub capture_raw: bool,
+    /// Records narrow strings which are not valid UTF-8 as
+    /// [`InvalidString`]s.
+    ///
+    /// The strings are still deserialized as they are, so this
+    /// only reports them. Strings in objects decoded with
+    /// [`SerializerOptions::decode_nested`] are not checked.
+    ///
+    /// Ignored during serialization.
+    pub strict_strings: bool,
+    /// Includes a window of the surrounding bytes in errors which
+    /// point at a location in the data.
+    ///
+    /// Ignored during serialization.
+    pub verbose_errors: bool,
 }
 
 impl Default for SerializerOptions {
     fn default() -> Self {
         Self {
             flags: SerializerFlags::empty(),
-            property_mask: PropertyFlags::TRANSMIT | PropertyFlags::PRIVILEGED_TRANSMIT,
+            property_mask: PropertyMask::transmit().flags(),
             shallow: true,
             manual_compression: false,
-            recursion_limit: i8::MAX,
+            header_order: HeaderOrder::FlagsFirst,
+            limits: Limits::default(),
             skip_unknown_types: false,
+            object_fallback: true,
+            strict_classes: false,
+            fill_defaults: false,
+            include_deprecated: false,
             djb2_only: false,
+            decode_nested: false,
+            capture_raw: false,
+            strict_strings: false,
+            verbose_errors: false,
         }
     }
 }
 
+impl SerializerOptions {
+    /// Detects game files by their [`BIND_MAGIC`] prefix.
+    ///
+    /// For game files, this switches to the fixed config they are
+    /// always serialized with and returns the data after the magic.
+    /// Other data is returned unchanged.
+    pub fn strip_bind_magic<'a>(&mut self, data: &'a [u8]) -> &'a [u8] {
+        match data.strip_prefix(BIND_MAGIC) {
+            Some(rest) => {
+                self.shallow = false;
+                self.flags = SerializerFlags::STATEFUL_FLAGS;
+                rest
+            }
+            None => data,
+        }
+    }
+
+    /// Whether `property` is part of an object in shallow mode.
+    #[inline]
+    pub fn is_shallow_property(&self, property: &Property) -> bool {
+        property.flags.contains(self.property_mask)
+            && (self.include_deprecated || !property.flags.contains(PropertyFlags::DEPRECATED))
+    }
+
+    /// The minimum number of bytes preceding the root object in
+    /// data serialized with these options.
+    ///
+    /// With [`SerializerFlags::STATEFUL_FLAGS`], the compression
+    /// marker is only known after reading the flags and thus not
+    /// accounted for.
+    pub fn header_size(&self) -> usize {
+        let mut size = 0;
+        if self.manual_compression {
+            size += mem::size_of::<u32>();
+        }
+
+        if self.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+            size += match self.header_order {
+                HeaderOrder::FlagsFirst => mem::size_of::<u32>(),
+                HeaderOrder::CompressionFirst | HeaderOrder::Auto => mem::size_of::<u8>(),
+            };
+        } else if self.flags.contains(SerializerFlags::WITH_COMPRESSION) {
+            size += mem::size_of::<u8>();
+        }
+
+        size
+    }
+}
+
 pub(super) struct ZlibParts {
-    inflater: Decompressor,
+    inflater: compress::Inflater,
+    // Only needed for serialization, so it is created lazily.
+    deflater: Option<compress::Deflater>,
 
     // Most of the time, only one of these will be in use.
     scratch1: Vec<u8>,
@@ -163,7 +431,8 @@ pub(super) struct ZlibParts {
 impl ZlibParts {
     pub fn new() -> Self {
         Self {
-            inflater: Decompressor::new(),
+            inflater: compress::Inflater::new(),
+            deflater: None,
             scratch1: Vec::new(),
             scratch2: Vec::new(),
         }
@@ -175,9 +444,32 @@ pub struct SerializerParts {
     /// The serializer configuration in use.
     pub options: SerializerOptions,
     pub(crate) types: Arc<TypeList>,
+
+    // Budget usage for the value currently being deserialized.
+    depth: u32,
+    values: usize,
+
+    // Spans recorded with `capture_raw` and the path leading to
+    // the value currently being deserialized.
+    captures: Vec<RawSpan>,
+    path: Path,
+
+    // Strings recorded with `strict_strings`.
+    invalid_strings: Vec<InvalidString>,
+
+    // Names of the root object's properties to keep, if restricted.
+    property_filter: Option<Vec<String>>,
+
+    // Where the last deserialization failed, in bytes.
+    error_offset: Option<usize>,
 }
 
 /// A serializer and deserializer for values in the ObjectProperty system.
+///
+/// Buffers for decompressing data are owned by the serializer and
+/// reused between calls, so callers only need to keep their input
+/// alive for the duration of a call. Uncompressed data is read in
+/// place without copying.
 pub struct Serializer {
     /// The raw serializer state.
     pub parts: SerializerParts,
@@ -185,20 +477,113 @@ pub struct Serializer {
 }
 
 impl SerializerParts {
+    /// Gets the spans recorded by the last deserialization with
+    /// [`SerializerOptions::capture_raw`] enabled.
+    ///
+    /// When deserialization failed, this holds the properties
+    /// which were decoded before the error.
+    pub fn raw_spans(&self) -> &[RawSpan] {
+        &self.captures
+    }
+
+    pub(super) fn new(options: SerializerOptions, types: Arc<TypeList>) -> Self {
+        Self {
+            options,
+            types,
+            depth: 0,
+            values: 0,
+            captures: Vec::new(),
+            path: Path::new(),
+            invalid_strings: Vec::new(),
+            property_filter: None,
+            error_offset: None,
+        }
+    }
+
+    /// Gets the byte offset at which the last deserialization with
+    /// [`Serializer::deserialize`] failed.
+    ///
+    /// The offset points into the data after decompression. It is
+    /// [`None`] when the call succeeded or failed before any object
+    /// data was read.
+    pub fn error_offset(&self) -> Option<usize> {
+        self.error_offset
+    }
+
+    /// Restricts deserialized root objects to the properties with
+    /// the given names, or lifts the restriction with [`None`].
+    ///
+    /// Other properties are left out of the result. In deep mode,
+    /// they are skipped by their size without being decoded at all.
+    /// Nested objects are not affected.
+    pub fn set_property_filter(&mut self, names: Option<Vec<String>>) {
+        self.property_filter = names;
+    }
+
+    /// Whether `name` is excluded by the property filter at the
+    /// current depth.
+    #[inline]
+    pub(super) fn filters_property(&self, name: &str) -> bool {
+        self.depth == 1
+            && self
+                .property_filter
+                .as_ref()
+                .is_some_and(|names| !names.iter().any(|n| n == name))
+    }
+
+    #[inline]
+    pub(super) fn reset_budgets(&mut self) {
+        self.depth = 0;
+        self.values = 0;
+
+        self.captures.clear();
+        self.path = Path::new();
+        self.invalid_strings.clear();
+    }
+
     #[inline]
     pub(super) fn with_recursion_limit<F, T>(&mut self, f: F) -> Result<T, Error>
     where
         F: FnOnce(&mut Self) -> Result<T, Error>,
     {
-        self.options.recursion_limit -= 1;
-        if self.options.recursion_limit < 0 {
-            return Err(Error::Recursion);
+        if self.depth >= self.options.limits.max_depth {
+            return Err(Error::limit(Limit::Depth));
         }
 
+        self.depth += 1;
         let res = f(self);
-
-        self.options.recursion_limit += 1;
+        self.depth -= 1;
 
         res
     }
+
+    /// Accounts for one more deserialized value.
+    #[inline]
+    pub(super) fn count_value(&mut self) -> Result<(), Error> {
+        self.values += 1;
+        if self.values > self.options.limits.max_total_values {
+            return Err(Error::limit(Limit::TotalValues));
+        }
+
+        Ok(())
+    }
+}
+
+impl Error {
+    #[inline]
+    pub(super) fn limit(limit: Limit) -> Self {
+        Self::LimitExceeded {
+            limit,
+            path: Path::new(),
+        }
+    }
+
+    /// Records that the error occurred inside the given segment.
+    #[inline]
+    pub(super) fn within(mut self, segment: impl FnOnce() -> PathSegment) -> Self {
+        if let Self::LimitExceeded { path, .. } = &mut self {
+            path.prepend(segment());
+        }
+        self
+    }
 }
@@ -0,0 +1,171 @@
+use std::{collections::BTreeMap, fmt};
+
+use smartstring::alias::String;
+
+#[cfg(feature = "arena")]
+use crate::value::{Arena, ArenaObject, ArenaValue};
+use crate::value::{CxxStr, List, Object, Value};
+
+/// Constructs the values produced by deserialization.
+///
+/// Objects, lists and strings make up most of a value tree and are
+/// built through this. Everything else is decoded as an owned
+/// [`Value`] first and then converted.
+pub(super) trait Build {
+    type Value: fmt::Debug;
+    type Object;
+    type List;
+
+    /// Converts an owned value.
+    fn value(&self, value: Value) -> Self::Value;
+
+    /// Whether `value` is [`Value::Empty`].
+    fn is_empty(value: &Self::Value) -> bool;
+
+    fn string(&self, s: &[u8]) -> Self::Value;
+
+    fn object(&self) -> Self::Object;
+
+    fn insert(&self, obj: &mut Self::Object, name: &String, value: Self::Value);
+
+    /// Inserts `value` unless `obj` already has a property `name`.
+    fn insert_default(&self, obj: &mut Self::Object, name: String, value: Value);
+
+    fn finish_object(&self, hash: u32, obj: Self::Object) -> Self::Value;
+
+    fn list(&self, capacity: usize) -> Self::List;
+
+    fn push(&self, list: &mut Self::List, value: Self::Value);
+
+    fn finish_list(&self, list: Self::List) -> Self::Value;
+}
+
+/// Builds owned [`Value`]s.
+pub(super) struct Owned;
+
+impl Build for Owned {
+    type Value = Value;
+    type Object = BTreeMap<String, Value>;
+    type List = Vec<Value>;
+
+    #[inline]
+    fn value(&self, value: Value) -> Value {
+        value
+    }
+
+    #[inline]
+    fn is_empty(value: &Value) -> bool {
+        matches!(value, Value::Empty)
+    }
+
+    #[inline]
+    fn string(&self, s: &[u8]) -> Value {
+        Value::String(CxxStr(s.to_owned()))
+    }
+
+    #[inline]
+    fn object(&self) -> Self::Object {
+        BTreeMap::new()
+    }
+
+    #[inline]
+    fn insert(&self, obj: &mut Self::Object, name: &String, value: Value) {
+        obj.insert(name.clone(), value);
+    }
+
+    #[inline]
+    fn insert_default(&self, obj: &mut Self::Object, name: String, value: Value) {
+        obj.entry(name).or_insert(value);
+    }
+
+    #[inline]
+    fn finish_object(&self, hash: u32, inner: Self::Object) -> Value {
+        Value::Object {
+            hash,
+            obj: Object { inner },
+        }
+    }
+
+    #[inline]
+    fn list(&self, capacity: usize) -> Self::List {
+        Vec::with_capacity(capacity)
+    }
+
+    #[inline]
+    fn push(&self, list: &mut Self::List, value: Value) {
+        list.push(value);
+    }
+
+    #[inline]
+    fn finish_list(&self, inner: Self::List) -> Value {
+        Value::List(List { inner })
+    }
+}
+
+/// Builds [`ArenaValue`]s in the given arena.
+#[cfg(feature = "arena")]
+pub(super) struct InArena<'a>(pub &'a Arena);
+
+#[cfg(feature = "arena")]
+impl<'a> Build for InArena<'a> {
+    type Value = ArenaValue<'a>;
+    type Object = bumpalo::collections::Vec<'a, (&'a str, ArenaValue<'a>)>;
+    type List = bumpalo::collections::Vec<'a, ArenaValue<'a>>;
+
+    #[inline]
+    fn value(&self, value: Value) -> ArenaValue<'a> {
+        self.0.alloc_value(&value)
+    }
+
+    #[inline]
+    fn is_empty(value: &ArenaValue<'a>) -> bool {
+        matches!(value, ArenaValue::Empty)
+    }
+
+    #[inline]
+    fn string(&self, s: &[u8]) -> ArenaValue<'a> {
+        ArenaValue::String(self.0.alloc_bytes(s))
+    }
+
+    #[inline]
+    fn object(&self) -> Self::Object {
+        bumpalo::collections::Vec::new_in(self.0.bump())
+    }
+
+    #[inline]
+    fn insert(&self, obj: &mut Self::Object, name: &String, value: ArenaValue<'a>) {
+        obj.push((self.0.alloc_str(name), value));
+    }
+
+    #[inline]
+    fn insert_default(&self, obj: &mut Self::Object, name: String, value: Value) {
+        if !obj.iter().any(|(n, _)| *n == name.as_str()) {
+            obj.push((self.0.alloc_str(&name), self.0.alloc_value(&value)));
+        }
+    }
+
+    #[inline]
+    fn finish_object(&self, hash: u32, obj: Self::Object) -> ArenaValue<'a> {
+        ArenaValue::Object {
+            hash,
+            obj: ArenaObject {
+                properties: obj.into_bump_slice(),
+            },
+        }
+    }
+
+    #[inline]
+    fn list(&self, capacity: usize) -> Self::List {
+        bumpalo::collections::Vec::with_capacity_in(capacity, self.0.bump())
+    }
+
+    #[inline]
+    fn push(&self, list: &mut Self::List, value: ArenaValue<'a>) {
+        list.push(value);
+    }
+
+    #[inline]
+    fn finish_list(&self, list: Self::List) -> ArenaValue<'a> {
+        ArenaValue::List(list.into_bump_slice())
+    }
+}
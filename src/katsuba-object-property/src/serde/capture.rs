@@ -0,0 +1,78 @@
+use katsuba_bit_buf::BitReader;
+
+use super::*;
+
+/// The bits a deserialized property was decoded from.
+///
+/// Offsets are relative to the data after decompression, if any.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawSpan {
+    /// The location of the property in the deserialized value.
+    pub path: Path,
+    /// The offset of the first bit of the property.
+    pub start: usize,
+    /// The number of bits occupied by the property.
+    pub len: usize,
+    /// The bytes overlapping with the bit range.
+    pub bytes: Vec<u8>,
+}
+
+impl SerializerParts {
+    /// Runs `f` to deserialize the value at `segment`, recording
+    /// its bits when [`SerializerOptions::capture_raw`] is set.
+    ///
+    /// The path to the value is also tracked for
+    /// [`SerializerOptions::strict_strings`].
+    #[inline]
+    pub(super) fn capture<'a, V, F>(
+        &mut self,
+        segment: impl FnOnce() -> PathSegment,
+        reader: &mut BitReader<'a>,
+        f: F,
+    ) -> Result<V, Error>
+    where
+        F: FnOnce(&mut Self, &mut BitReader<'a>) -> Result<V, Error>,
+    {
+        if !self.options.capture_raw {
+            if !self.options.strict_strings {
+                return f(self, reader);
+            }
+
+            self.path.push(segment());
+            let res = f(self, reader);
+            self.path.pop();
+            return res;
+        }
+
+        // Reserve the slot before deserializing so that spans end
+        // up in the order they appear in the data.
+        let idx = self.captures.len();
+        let start = reader.bit_position();
+        self.path.push(segment());
+        self.captures.push(RawSpan {
+            path: self.path.clone(),
+            start,
+            len: 0,
+            bytes: Vec::new(),
+        });
+
+        let res = f(self, reader);
+        self.path.pop();
+
+        match &res {
+            Ok(_) => {
+                let end = reader.bit_position();
+                let span = &mut self.captures[idx];
+                span.len = end - start;
+                span.bytes = reader.data()[start / 8..end.div_ceil(8)].to_vec();
+            }
+
+            // Keep what was decoded before the failure for inspection.
+            Err(_) => {
+                self.captures.remove(idx);
+            }
+        }
+
+        res
+    }
+}
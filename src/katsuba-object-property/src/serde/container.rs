@@ -0,0 +1,111 @@
+use katsuba_bit_buf::{BitReader, BitWriter};
+use katsuba_types::TemplateType;
+
+use super::{property, *};
+use crate::value::{PathSegment, Value};
+
+/// A C++ standard container with its element types.
+#[derive(Clone, Copy, Debug)]
+pub enum Container<'a> {
+    /// `std::map<K, V>`, encoded as a length followed by the entries.
+    Map(&'a str, &'a str),
+    /// `std::pair<A, B>`, encoded as both values in order.
+    Pair(&'a str, &'a str),
+}
+
+impl<'a> Container<'a> {
+    /// Identifies the container described by the type name `ty`.
+    pub fn parse(ty: &'a str) -> Option<Self> {
+        // Avoid parsing the many other template types in type lists.
+        if !ty.contains("std::map<") && !ty.contains("std::pair<") {
+            return None;
+        }
+
+        let template = TemplateType::parse(ty)?;
+        match (template.name, template.args.as_slice()) {
+            ("std::map", &[key, value]) => Some(Self::Map(key, value)),
+            ("std::pair", &[first, second]) => Some(Self::Pair(first, second)),
+            _ => None,
+        }
+    }
+
+    pub fn deserialize<T: TypeTag>(
+        self,
+        de: &mut SerializerParts,
+        reader: &mut BitReader<'_>,
+    ) -> Result<Value, Error> {
+        match self {
+            Self::Map(key_ty, value_ty) => {
+                let len = property::read_length(de, reader)?;
+                let mut entries = Vec::with_capacity(len.min(utils::PREALLOC_LIMIT));
+
+                de.with_recursion_limit(|de| {
+                    for idx in 0..len {
+                        let key = de
+                            .count_value()
+                            .and_then(|()| {
+                                property::deserialize_type::<T, _>(&Owned, de, key_ty, reader)
+                            })
+                            .map_err(|e| e.within(|| PathSegment::Key(idx)))?;
+                        let value =
+                            property::deserialize_type::<T, _>(&Owned, de, value_ty, reader)
+                                .map_err(|e| e.within(|| PathSegment::Value(idx)))?;
+                        entries.push((key, value));
+                    }
+
+                    Ok(())
+                })?;
+
+                Ok(Value::Map(entries))
+            }
+
+            Self::Pair(first_ty, second_ty) => de.with_recursion_limit(|de| {
+                let first = property::deserialize_type::<T, _>(&Owned, de, first_ty, reader)
+                    .map_err(|e| e.within(|| PathSegment::First))?;
+                let second = property::deserialize_type::<T, _>(&Owned, de, second_ty, reader)
+                    .map_err(|e| e.within(|| PathSegment::Second))?;
+
+                Ok(Value::Pair(Box::new((first, second))))
+            }),
+        }
+    }
+
+    pub fn serialize<T: TypeTag>(
+        self,
+        ser: &SerializerParts,
+        value: &Value,
+        writer: &mut BitWriter,
+    ) -> Result<(), Error> {
+        match (self, value) {
+            (Self::Map(key_ty, value_ty), Value::Map(entries)) => {
+                utils::write_container_length(
+                    writer,
+                    entries.len(),
+                    ser.options
+                        .flags
+                        .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+                )?;
+                for (key, value) in entries {
+                    property::serialize_type::<T>(ser, key_ty, key.resolve(), writer)?;
+                    property::serialize_type::<T>(ser, value_ty, value.resolve(), writer)?;
+                }
+
+                Ok(())
+            }
+
+            (Self::Pair(first_ty, second_ty), Value::Pair(pair)) => {
+                property::serialize_type::<T>(ser, first_ty, pair.0.resolve(), writer)?;
+                property::serialize_type::<T>(ser, second_ty, pair.1.resolve(), writer)
+            }
+
+            (Self::Map(key_ty, value_ty), _) => Err(Error::ValueMismatch {
+                expected: format!("std::map<{key_ty}, {value_ty}>"),
+                found: value.variant_name(),
+            }),
+            (Self::Pair(first_ty, second_ty), _) => Err(Error::ValueMismatch {
+                expected: format!("std::pair<{first_ty}, {second_ty}>"),
+                found: value.variant_name(),
+            }),
+        }
+    }
+}
@@ -3,46 +3,149 @@ use std::sync::Arc;
 use byteorder::{ReadBytesExt, LE};
 use katsuba_bit_buf::BitReader;
 use katsuba_types::TypeList;
-use katsuba_utils::libdeflater::Decompressor;
+use katsuba_utils::compress;
 
 use super::*;
+#[cfg(feature = "arena")]
+use crate::value::{Arena, ArenaValue};
 use crate::Value;
 
 #[inline]
 pub(super) fn zlib_decompress(
-    inflater: &mut Decompressor,
+    inflater: &mut compress::Inflater,
     mut data: &[u8],
     out: &mut Vec<u8>,
 ) -> Result<(), Error> {
-    let size = data.read_u32::<LE>()? as usize;
-    out.resize(size, 0);
-
-    let decompressed = inflater.zlib_decompress(data, out)?;
-    if decompressed != size {
-        return Err(Error::DecompressedSizeMismatch {
-            expected: size,
-            actual: decompressed,
+    // The stream is prefixed with its decompressed size.
+    if data.len() < 4 {
+        return Err(Error::TooShort {
+            size: data.len(),
+            expected: 4,
         });
     }
 
+    let size = data.read_u32::<LE>()? as usize;
+    inflater.zlib_vec(data, size, out)?;
+
     Ok(())
 }
 
+// Where the object data starts after a stateful header.
+#[derive(Clone, Copy)]
+enum Payload {
+    // At the given offset into the input.
+    Input(usize),
+    // At the given offset into the decompressed scratch buffer.
+    Scratch(usize),
+}
+
+impl Payload {
+    fn resolve<'a>(self, input: &'a [u8], scratch: &'a [u8]) -> &'a [u8] {
+        match self {
+            Self::Input(offset) => &input[offset..],
+            Self::Scratch(offset) => &scratch[offset..],
+        }
+    }
+}
+
+// Reads the configuration header and the compression framing of
+// stateful data in the given order, updating the flags in `opts`.
+fn read_stateful_header(
+    inflater: &mut compress::Inflater,
+    scratch: &mut Vec<u8>,
+    opts: &mut SerializerOptions,
+    order: HeaderOrder,
+    input: &[u8],
+) -> Result<Payload, Error> {
+    let mut data = input;
+    if order == HeaderOrder::CompressionFirst {
+        if data.read_u8()? != 0 {
+            zlib_decompress(inflater, data, scratch)?;
+
+            let mut data = &scratch[..];
+            opts.flags = SerializerFlags::from_bits_truncate(data.read_u32::<LE>()?);
+            return Ok(Payload::Scratch(4));
+        }
+
+        opts.flags = SerializerFlags::from_bits_truncate(data.read_u32::<LE>()?);
+        return Ok(Payload::Input(5));
+    }
+
+    opts.flags = SerializerFlags::from_bits_truncate(data.read_u32::<LE>()?);
+    if opts.flags.contains(SerializerFlags::WITH_COMPRESSION) && data.read_u8()? != 0 {
+        zlib_decompress(inflater, data, scratch)?;
+        return Ok(Payload::Scratch(0));
+    }
+
+    Ok(Payload::Input(input.len() - data.len()))
+}
+
 impl ZlibParts {
-    fn configure<'a>(
+    pub(super) fn configure<'a, T: TypeTag>(
         &'a mut self,
         opts: &mut SerializerOptions,
+        types: &TypeList,
         mut data: &'a [u8],
     ) -> Result<BitReader<'a>, Error> {
+        let expected = opts.header_size();
+        if data.len() < expected {
+            return Err(Error::TooShort {
+                size: data.len(),
+                expected,
+            });
+        }
+
         // If the data is manually compressed, uncompress into scratch.
         if opts.manual_compression {
             zlib_decompress(&mut self.inflater, data, &mut self.scratch1)?;
             data = &self.scratch1;
         }
 
-        // If the serializer flags are stateful, update the options.
+        // If the serializer flags are stateful, update the options
+        // and uncompress the data as laid out.
         if opts.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
-            opts.flags = SerializerFlags::from_bits_truncate(data.read_u32::<LE>()?);
+            let payload = match opts.header_order {
+                HeaderOrder::Auto => {
+                    let mut probe = *opts;
+                    let flags_first = read_stateful_header(
+                        &mut self.inflater,
+                        &mut self.scratch2,
+                        &mut probe,
+                        HeaderOrder::FlagsFirst,
+                        data,
+                    );
+
+                    // The type of the root object is the first thing
+                    // to go wrong when the order doesn't match. When
+                    // it matches, the probed payload is kept.
+                    let matches = flags_first.as_ref().is_ok_and(|p| {
+                        let mut reader = BitReader::new(p.resolve(data, &self.scratch2));
+                        matches!(T::identity(&mut reader, types), Ok(Some(_)))
+                    });
+                    match flags_first {
+                        Ok(payload) if matches => {
+                            *opts = probe;
+                            payload
+                        }
+                        _ => {
+                            log::debug!("Falling back to compression-first header order");
+                            read_stateful_header(
+                                &mut self.inflater,
+                                &mut self.scratch2,
+                                opts,
+                                HeaderOrder::CompressionFirst,
+                                data,
+                            )?
+                        }
+                    }
+                }
+                order => {
+                    read_stateful_header(&mut self.inflater, &mut self.scratch2, opts, order, data)?
+                }
+            };
+
+            data = payload.resolve(data, &self.scratch2);
+            return Ok(BitReader::new(data));
         }
 
         // If the data is compressed, uncompress it into scratch.
@@ -55,11 +158,28 @@ impl ZlibParts {
     }
 }
 
+// Properties sharing a hash can't be told apart in serialized data,
+// so type lists with any are rejected upfront rather than picking
+// one of them during deserialization.
+fn check_property_hashes(types: &TypeList) -> Result<(), Error> {
+    match types.property_collision() {
+        Some(collision) => Err(Error::PropertyHashCollision {
+            class: collision.class.to_string(),
+            hash: collision.hash,
+            properties: collision.properties.iter().map(|p| p.to_string()).collect(),
+        }),
+        None => Ok(()),
+    }
+}
+
 impl Serializer {
-    /// Creates a new deserializer with its configuration.
+    /// Creates a new serializer with its configuration.
+    ///
+    /// The instance is not tied to any data and can be reused for
+    /// any number of [`Serializer::deserialize`] calls.
     ///
-    /// No data for deserialization has been loaded at this point.
-    /// [`Deserializer::load_data`] should be called next.
+    /// Fails when a class in `types` has properties with the same
+    /// hash, see [`TypeList::audit_collisions`].
     pub fn new(options: SerializerOptions, types: Arc<TypeList>) -> Result<Self, Error> {
         if options.shallow && options.skip_unknown_types {
             return Err(Error::BadConfig(
@@ -67,8 +187,10 @@ impl Serializer {
             ));
         }
 
+        check_property_hashes(&types)?;
+
         Ok(Self {
-            parts: SerializerParts { options, types },
+            parts: SerializerParts::new(options, types),
             zlib_parts: ZlibParts::new(),
         })
     }
@@ -106,14 +228,128 @@ impl Serializer {
 
     /// Deserializes an object [`Value`] from the given data.
     pub fn deserialize<T: TypeTag>(&mut self, data: &[u8]) -> Result<Value, Error> {
-        let mut reader = self.zlib_parts.configure(&mut self.parts.options, data)?;
+        self.deserialize_with::<T, _>(&Owned, data)
+    }
+
+    /// Deserializes an object from the given data into `arena`.
+    ///
+    /// This saves the many small allocations of an owned [`Value`]
+    /// tree when it is only inspected and then discarded. The whole
+    /// tree is freed along with the arena.
+    #[cfg(feature = "arena")]
+    pub fn deserialize_in<'a, T: TypeTag>(
+        &mut self,
+        arena: &'a Arena,
+        data: &[u8],
+    ) -> Result<ArenaValue<'a>, Error> {
+        self.deserialize_with::<T, _>(&InArena(arena), data)
+    }
+
+    fn deserialize_with<T: TypeTag, B: Build>(
+        &mut self,
+        b: &B,
+        data: &[u8],
+    ) -> Result<B::Value, Error> {
+        self.parts.error_offset = None;
+        let mut reader =
+            self.zlib_parts
+                .configure::<T>(&mut self.parts.options, &self.parts.types, data)?;
+        self.parts.reset_budgets();
         log::info!("Deserializing object with config {:?}", self.parts.options);
 
-        let value = object::deserialize::<T>(&mut self.parts, &mut reader)?;
-        if let Value::Empty = value {
+        let value = match object::deserialize::<T, B>(b, &mut self.parts, &mut reader) {
+            Ok(value) => value,
+            Err(e) => {
+                self.parts.error_offset = Some(reader.bit_position() / u8::BITS as usize);
+                return Err(e);
+            }
+        };
+        if B::is_empty(&value) {
             return Err(Error::NullRoot);
         }
 
+        // Only padding to the next byte may follow the root object.
+        reader.realign_to_byte();
+        let trailing = reader.untouched_bytes();
+        if trailing != 0 {
+            self.parts.error_offset = Some(reader.bit_position() / u8::BITS as usize);
+            return Err(Error::TrailingData(trailing));
+        }
+
         Ok(value)
     }
+
+    /// Deserializes all consecutive root objects from the given data.
+    ///
+    /// Objects are read until the data is exhausted, with only
+    /// padding to the next byte between them. Errors are reported
+    /// as [`Error::Root`] with the index of the failing object and
+    /// the byte offset it starts at.
+    ///
+    /// Strings found with [`SerializerOptions::strict_strings`] are
+    /// kept for all objects, with paths that start with the index
+    /// of their object.
+    pub fn deserialize_all<T: TypeTag>(&mut self, data: &[u8]) -> Result<Vec<Value>, Error> {
+        let mut reader =
+            self.zlib_parts
+                .configure::<T>(&mut self.parts.options, &self.parts.types, data)?;
+        log::info!("Deserializing objects with config {:?}", self.parts.options);
+
+        let mut values = Vec::new();
+        let mut invalid_strings = Vec::new();
+        loop {
+            reader.realign_to_byte();
+            if reader.untouched_bytes() == 0 {
+                break;
+            }
+
+            let index = values.len();
+            let offset = reader.bit_position() / u8::BITS as usize;
+            let root = |error| Error::Root {
+                index,
+                offset,
+                error: Box::new(error),
+            };
+
+            self.parts.reset_budgets();
+            let value = object::deserialize::<T, Owned>(&Owned, &mut self.parts, &mut reader)
+                .map_err(root)?;
+            if Owned::is_empty(&value) {
+                return Err(root(Error::NullRoot));
+            }
+
+            values.push(value);
+
+            invalid_strings.extend(self.parts.invalid_strings.drain(..).map(|mut s| {
+                s.path.prepend(PathSegment::Index(index));
+                s
+            }));
+        }
+
+        self.parts.invalid_strings = invalid_strings;
+        Ok(values)
+    }
+
+    /// Locates consecutive root objects in the given data without
+    /// building their values.
+    ///
+    /// See [`SerializerParts::skip_object`] for details.
+    pub fn index<T: TypeTag>(&mut self, data: &[u8]) -> Result<Vec<ObjectSpan>, Error> {
+        let mut reader =
+            self.zlib_parts
+                .configure::<T>(&mut self.parts.options, &self.parts.types, data)?;
+        self.parts.reset_budgets();
+
+        let mut spans = Vec::new();
+        loop {
+            reader.realign_to_byte();
+            if reader.untouched_bytes() == 0 {
+                break;
+            }
+
+            spans.push(self.parts.skip_object::<T>(&mut reader)?);
+        }
+
+        Ok(spans)
+    }
 }
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use katsuba_types::{Property, PropertyFlags, TypeDef};
+
+use super::{container::Container, object, pointer, simple_data, SerializerParts};
+use crate::value::{List, Object, Value};
+
+impl SerializerParts {
+    /// Builds an object of the given type with the default value
+    /// for every property, as declared in the type list.
+    ///
+    /// Defaults are zero for numbers, empty strings and containers,
+    /// null for pointers and objects with defaults for embedded
+    /// classes. Enums default to their variant with value 0, or
+    /// their smallest one if there is none.
+    ///
+    /// Embedded classes nested deeper than the recursion limit
+    /// default to [`Value::Empty`], which keeps self-referencing
+    /// types from producing infinite trees.
+    pub fn default_object(&self, type_def: &TypeDef) -> Value {
+        self.default_object_at(type_def, 0)
+    }
+
+    fn default_object_at(&self, type_def: &TypeDef, depth: u32) -> Value {
+        if depth >= self.options.limits.max_depth {
+            return Value::Empty;
+        }
+
+        let inner: BTreeMap<_, _> = type_def
+            .properties
+            .iter()
+            .map(|p| (p.name.clone(), self.default_property(p, depth + 1)))
+            .collect();
+
+        Value::Object {
+            hash: object::type_hash(self, type_def),
+            obj: Object { inner },
+        }
+    }
+
+    fn default_property(&self, property: &Property, depth: u32) -> Value {
+        if property.dynamic {
+            Value::List(List { inner: Vec::new() })
+        } else if property.is_enum() {
+            Value::Enum(default_enum_variant(property))
+        } else {
+            self.default_type(&property.r#type, depth)
+        }
+    }
+
+    fn default_type(&self, ty: &str, depth: u32) -> Value {
+        match Container::parse(ty) {
+            Some(Container::Map(..)) => return Value::Map(Vec::new()),
+            Some(Container::Pair(first, second)) => {
+                let first = self.default_type(first, depth);
+                let second = self.default_type(second, depth);
+                return Value::Pair(Box::new((first, second)));
+            }
+            None => (),
+        }
+
+        if pointer::pointee(ty).is_some() {
+            return Value::Empty;
+        }
+
+        if ty.starts_with("enum ") {
+            return Value::Enum(0);
+        }
+
+        if let Some(value) = simple_data::default_value(ty, &self.options) {
+            return value;
+        }
+
+        match self.types.find(ty) {
+            Some((_, type_def)) => self.default_object_at(type_def, depth),
+            None => Value::Empty,
+        }
+    }
+}
+
+// Bit flags have no bits set by default.
+fn default_enum_variant(property: &Property) -> i64 {
+    let values = || property.enum_options.values().filter_map(|v| v.to_int());
+    if property.flags.contains(PropertyFlags::BITS) || values().any(|v| v == 0) {
+        0
+    } else {
+        values().min().unwrap_or(0)
+    }
+}
@@ -1,8 +1,8 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use katsuba_types::Property;
 
 use super::{utils, Error, SerializerFlags, SerializerParts};
-use crate::Value;
+use crate::value::{CxxStr, Value};
 
 pub fn deserialize(
     de: &SerializerParts,
@@ -25,3 +25,78 @@ pub fn deserialize(
         Ok(Value::Enum(value as i64))
     }
 }
+
+/// Deserializes an enum value without a [`Property`] describing it.
+///
+/// Human-readable variants cannot be decoded in that case, so they
+/// are kept as strings.
+pub fn deserialize_untyped(
+    de: &SerializerParts,
+    reader: &mut BitReader<'_>,
+) -> Result<Value, Error> {
+    if de
+        .options
+        .flags
+        .contains(SerializerFlags::HUMAN_READABLE_ENUMS)
+    {
+        let raw = utils::read_string(reader, &de.options)?;
+        Ok(Value::String(CxxStr(raw.to_owned())))
+    } else {
+        let value = utils::read_bits(reader, u32::BITS)?;
+        Ok(Value::Enum(value as i64))
+    }
+}
+
+pub fn serialize(
+    ser: &SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    let Value::Enum(value) = *value else {
+        return Err(Error::ValueMismatch {
+            expected: property.r#type.to_string(),
+            found: value.variant_name(),
+        });
+    };
+
+    if ser
+        .options
+        .flags
+        .contains(SerializerFlags::HUMAN_READABLE_ENUMS)
+    {
+        let variant = property.encode_enum_variant(value)?;
+        utils::write_string(writer, variant.as_bytes(), &ser.options)
+    } else {
+        utils::write_bits(writer, value as u32 as u64, u32::BITS);
+        Ok(())
+    }
+}
+
+/// Serializes an enum value of type `ty` without a [`Property`]
+/// describing it.
+///
+/// This is the inverse of [`deserialize_untyped`].
+pub fn serialize_untyped(
+    ser: &SerializerParts,
+    ty: &str,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    let human_readable = ser
+        .options
+        .flags
+        .contains(SerializerFlags::HUMAN_READABLE_ENUMS);
+
+    match (human_readable, value) {
+        (true, Value::String(variant)) => utils::write_string(writer, &variant.0, &ser.options),
+        (false, Value::Enum(value)) => {
+            utils::write_bits(writer, *value as u32 as u64, u32::BITS);
+            Ok(())
+        }
+        _ => Err(Error::ValueMismatch {
+            expected: ty.to_string(),
+            found: value.variant_name(),
+        }),
+    }
+}
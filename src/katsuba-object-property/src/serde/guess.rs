@@ -71,7 +71,7 @@ fn check_serialization_mode(opts: &mut SerializerOptions, offset: usize, data: &
 }
 
 fn zlib_decompress(
-    inflater: &mut Decompressor,
+    inflater: &mut compress::Inflater,
     out: &mut Vec<u8>,
     data: &[u8],
 ) -> Result<bool, Error> {
@@ -79,7 +79,7 @@ fn zlib_decompress(
         Ok(()) => Ok(true),
 
         // Assume this was a false positive stream.
-        Err(Error::Decompress(_)) => Ok(false),
+        Err(Error::Decompress(e)) if e.is_bad_data() => Ok(false),
 
         Err(e) => Err(e),
     }
@@ -139,10 +139,7 @@ impl Guesser {
         // - What is the utilized property filter mask?
 
         Ok(Serializer {
-            parts: SerializerParts {
-                options: self.opts,
-                types: self.types,
-            },
+            parts: SerializerParts::new(self.opts, self.types),
             zlib_parts: self.zlib,
         })
     }
@@ -0,0 +1,93 @@
+//! Helpers for the configuration header and compression framing
+//! which surround serialized object data.
+//!
+//! These are the inverse of what [`Serializer::deserialize`]
+//! consumes before the object itself, in the order the data is
+//! laid out on the wire.
+
+use super::*;
+
+/// Writes the configuration header for data serialized with
+/// [`SerializerFlags::STATEFUL_FLAGS`].
+///
+/// This is the little-endian encoding of `flags`, which replace
+/// the configured flags upon deserialization.
+pub fn write_config_header(out: &mut Vec<u8>, flags: SerializerFlags) {
+    out.extend(flags.bits().to_le_bytes());
+}
+
+/// Writes the marker byte in front of data serialized with
+/// [`SerializerFlags::WITH_COMPRESSION`].
+///
+/// Compressed data must follow with [`write_zlib`], otherwise the
+/// data is stored as-is.
+pub fn write_compression_marker(out: &mut Vec<u8>, compressed: bool) {
+    out.push(compressed as u8);
+}
+
+/// Writes `data` as a zlib stream prefixed with its uncompressed
+/// size.
+///
+/// This is the layout of compressed object data and of manually
+/// compressed state as a whole.
+pub fn write_zlib(out: &mut Vec<u8>, deflater: &mut compress::Deflater, data: &[u8]) {
+    out.extend((data.len() as u32).to_le_bytes());
+    deflater.zlib_into(data, out);
+}
+
+/// Frames serialized object `data` as described by `options`.
+///
+/// Data is always compressed when the flags ask for compression.
+/// Stateful data is laid out in the configured [`HeaderOrder`],
+/// where [`HeaderOrder::Auto`] writes the flags first.
+pub fn write_framing(
+    deflater: &mut compress::Deflater,
+    options: &SerializerOptions,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 9);
+    let compressed = options.flags.contains(SerializerFlags::WITH_COMPRESSION);
+
+    if !options.flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+        write_compressed(&mut out, deflater, compressed, data);
+    } else if options.header_order == HeaderOrder::CompressionFirst {
+        // The flags are compressed along with the object data.
+        let mut inner = Vec::with_capacity(data.len() + 4);
+        write_config_header(&mut inner, options.flags);
+        inner.extend_from_slice(data);
+
+        write_compression_marker(&mut out, compressed);
+        match compressed {
+            true => write_zlib(&mut out, deflater, &inner),
+            false => out.extend(inner),
+        }
+    } else {
+        write_config_header(&mut out, options.flags);
+        write_compressed(&mut out, deflater, compressed, data);
+    }
+
+    // Manual compression wraps everything else.
+    if options.manual_compression {
+        let mut compressed = Vec::new();
+        write_zlib(&mut compressed, deflater, &out);
+        out = compressed;
+    }
+
+    out
+}
+
+// Writes `data` behind a compression marker when `compressed`, and
+// as-is otherwise.
+fn write_compressed(
+    out: &mut Vec<u8>,
+    deflater: &mut compress::Deflater,
+    compressed: bool,
+    data: &[u8],
+) {
+    if compressed {
+        write_compression_marker(out, true);
+        write_zlib(out, deflater, data);
+    } else {
+        out.extend_from_slice(data);
+    }
+}
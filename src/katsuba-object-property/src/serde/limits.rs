@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// Resource budgets which bound the work done when deserializing
+/// a single value.
+///
+/// The defaults accommodate all known game data while keeping
+/// hostile inputs from exhausting memory or the stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of objects and lists.
+    pub max_depth: u32,
+    /// The maximum number of elements in a single list.
+    pub max_elements: usize,
+    /// The maximum length of a single string, in code units.
+    pub max_string_len: usize,
+    /// The maximum number of property values and list elements
+    /// in a deserialized value as a whole.
+    pub max_total_values: usize,
+}
+
+impl Limits {
+    /// Creates limits which never reject any input.
+    ///
+    /// Only use this for trusted data.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_depth: u32::MAX,
+            max_elements: usize::MAX,
+            max_string_len: usize::MAX,
+            max_total_values: usize::MAX,
+        }
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 127,
+            max_elements: 1 << 20,
+            max_string_len: 1 << 20,
+            max_total_values: 1 << 24,
+        }
+    }
+}
+
+/// Identifies one of the budgets in [`Limits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// [`Limits::max_depth`].
+    Depth,
+    /// [`Limits::max_elements`].
+    Elements,
+    /// [`Limits::max_string_len`].
+    StringLength,
+    /// [`Limits::max_total_values`].
+    TotalValues,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Depth => "max_depth",
+            Self::Elements => "max_elements",
+            Self::StringLength => "max_string_len",
+            Self::TotalValues => "max_total_values",
+        })
+    }
+}
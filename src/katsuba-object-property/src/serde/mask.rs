@@ -0,0 +1,54 @@
+use katsuba_types::PropertyFlags;
+
+/// Named presets for [`SerializerOptions::property_mask`] after the
+/// game's use cases for them.
+///
+/// Shallow objects consist of the properties whose flags contain
+/// every bit of the mask, so fewer bits select more properties.
+///
+/// [`SerializerOptions::property_mask`]: super::SerializerOptions::property_mask
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PropertyMask(pub PropertyFlags);
+
+impl PropertyMask {
+    /// State sent over the network, `TRANSMIT | PRIVILEGED_TRANSMIT`
+    /// (`0x18`).
+    ///
+    /// This is the default.
+    pub const fn transmit() -> Self {
+        Self(PropertyFlags::TRANSMIT.union(PropertyFlags::PRIVILEGED_TRANSMIT))
+    }
+
+    /// State saved to persistent storage, `SAVE` (`0x1`).
+    pub const fn save() -> Self {
+        Self(PropertyFlags::SAVE)
+    }
+
+    /// State duplicated when objects are copied in the editor,
+    /// `COPY` (`0x2`).
+    pub const fn copy() -> Self {
+        Self(PropertyFlags::COPY)
+    }
+
+    /// All properties, regardless of their flags (`0x0`).
+    pub const fn all() -> Self {
+        Self(PropertyFlags::empty())
+    }
+
+    /// Gets the flags of the mask.
+    pub const fn flags(self) -> PropertyFlags {
+        self.0
+    }
+}
+
+impl Default for PropertyMask {
+    fn default() -> Self {
+        Self::transmit()
+    }
+}
+
+impl From<PropertyMask> for PropertyFlags {
+    fn from(mask: PropertyMask) -> Self {
+        mask.0
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use katsuba_bit_buf::BitReader;
+
+// Number of bytes to show on either side of a mismatch.
+const CONTEXT_BYTES: usize = 8;
+
+/// Details on a property whose encoded size did not match the
+/// data consumed for it.
+#[derive(Debug)]
+pub struct SizeMismatch {
+    /// The name of the class the property belongs to.
+    pub class: String,
+    /// The name of the property.
+    pub property: String,
+    /// The declared type of the property.
+    pub r#type: String,
+    /// The absolute bit offset where the property starts.
+    pub start: usize,
+    /// The absolute bit offset where decoding the property ended.
+    pub end: usize,
+    /// The encoded size of the property in bits.
+    pub expected: usize,
+    /// The number of bits actually consumed for the property.
+    pub actual: usize,
+    /// The offset of the first byte and the bytes surrounding
+    /// [`SizeMismatch::end`], when verbose errors are enabled.
+    pub context: Option<(usize, Vec<u8>)>,
+}
+
+impl SizeMismatch {
+    pub(super) fn capture_context(&mut self, reader: &BitReader<'_>) {
+        let data = reader.data();
+        let pos = (self.end / 8).min(data.len());
+        let start = pos.saturating_sub(CONTEXT_BYTES);
+        let end = (pos + CONTEXT_BYTES).min(data.len());
+
+        self.context = Some((start, data[start..end].to_vec()));
+    }
+}
+
+impl fmt::Display for SizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "size mismatch for property '{}' ({}) of '{}' at bits {}..{}: expected {}, got {}",
+            self.property,
+            self.r#type,
+            self.class,
+            self.start,
+            self.end,
+            self.expected,
+            self.actual
+        )?;
+
+        if let Some((offset, bytes)) = &self.context {
+            // Highlight the byte where decoding ended.
+            let end = self.end / 8;
+            write!(f, "\n  bytes at {offset:#x}:")?;
+            for (i, b) in bytes.iter().enumerate() {
+                match offset + i == end {
+                    true => write!(f, " [{b:02x}]")?,
+                    false => write!(f, " {b:02x}")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! Decoding of serialized objects embedded in string properties.
+
+use std::borrow::Cow;
+
+use katsuba_bit_buf::BitWriter;
+
+use super::*;
+use crate::value::{Blob, CxxStr, PathSegment, Value};
+
+struct Sniffed {
+    options: SerializerOptions,
+    // Bytes of magic to skip before the serialized data.
+    skip: usize,
+    // Whether the data is known to be serialized, so that a
+    // failure to decode it must be reported.
+    certain: bool,
+}
+
+// Guesses whether `raw` holds a serialized object and how to read it.
+fn sniff(parts: &SerializerParts, raw: &[u8]) -> Option<Sniffed> {
+    let mut options = SerializerOptions {
+        manual_compression: false,
+        ..parts.options
+    };
+
+    // Like game files, these use a fixed base config.
+    if raw.starts_with(BIND_MAGIC) {
+        options.shallow = false;
+        options.flags = SerializerFlags::STATEFUL_FLAGS;
+
+        return Some(Sniffed {
+            options,
+            skip: BIND_MAGIC.len(),
+            certain: true,
+        });
+    }
+
+    let head = u32::from_le_bytes(raw.get(..4)?.try_into().unwrap());
+
+    // Data with the same config as the enclosing object starts
+    // with the hash of a type we know.
+    if head != 0 && parts.types.0.contains_key(&head) {
+        options.flags.remove(SerializerFlags::STATEFUL_FLAGS);
+    } else {
+        // Otherwise, a stateful flags header may lead the data.
+        let flags = SerializerFlags::from_bits(head)?;
+        if !flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+            return None;
+        }
+        options.flags = SerializerFlags::STATEFUL_FLAGS;
+    }
+
+    Some(Sniffed {
+        options,
+        skip: 0,
+        certain: false,
+    })
+}
+
+/// Attempts to decode `raw` as a nested object and returns it as
+/// a [`Value::Blob`], or as a plain [`Value::String`] otherwise.
+pub fn deserialize<T: TypeTag>(de: &mut SerializerParts, raw: CxxStr) -> Result<Value, Error> {
+    let Some(mut sniffed) = sniff(de, &raw.0) else {
+        return Ok(Value::String(raw));
+    };
+
+    // The nested object consumes the budgets of the enclosing one.
+    let limits = &mut sniffed.options.limits;
+    limits.max_depth = limits.max_depth.saturating_sub(de.depth);
+    limits.max_total_values = limits.max_total_values.saturating_sub(de.values);
+
+    let mut nested = Serializer::new(sniffed.options, de.types.clone())?;
+    match nested.deserialize::<T>(&raw.0[sniffed.skip..]) {
+        Ok(value) => {
+            de.values += nested.parts.values;
+            Ok(Value::Blob(Box::new(Blob { raw, value })))
+        }
+
+        // Limits must not be evaded by pretending data was a string.
+        Err(e) if sniffed.certain || matches!(e, Error::LimitExceeded { .. }) => {
+            Err(e.within(|| PathSegment::Nested))
+        }
+
+        Err(e) => {
+            log::debug!("String is not a nested object: {e}");
+            Ok(Value::String(raw))
+        }
+    }
+}
+
+/// Writes a [`Blob`] back into a string property.
+///
+/// When the decoded object is unchanged, the original bytes are
+/// written as-is since compression may not reproduce them.
+pub fn serialize<T: TypeTag>(
+    ser: &SerializerParts,
+    blob: &Blob,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    let raw = &blob.raw.0;
+    let data = match sniff(ser, raw) {
+        Some(sniffed) => {
+            let stateful = sniffed
+                .options
+                .flags
+                .contains(SerializerFlags::STATEFUL_FLAGS);
+            let mut nested = Serializer::new(sniffed.options, ser.types.clone())?;
+
+            let unchanged = nested
+                .deserialize::<T>(&raw[sniffed.skip..])
+                .is_ok_and(|v| v == blob.value);
+            if unchanged {
+                Cow::Borrowed(raw)
+            } else {
+                // Deserialization replaced stateful flags with the
+                // ones from the data, so restore the bit.
+                if stateful {
+                    nested.parts.options.flags |= SerializerFlags::STATEFUL_FLAGS;
+                }
+
+                let mut data = raw[..sniffed.skip].to_vec();
+                data.extend(nested.serialize::<T>(&blob.value)?);
+                Cow::Owned(data)
+            }
+        }
+
+        // Blobs only originate from data that was sniffed before.
+        None => Cow::Borrowed(raw),
+    };
+
+    utils::write_string(writer, &data, &ser.options)
+}
@@ -1,17 +1,17 @@
-use std::collections::BTreeMap;
-
-use katsuba_bit_buf::BitReader;
-use katsuba_types::{PropertyFlags, TypeDef};
+use katsuba_bit_buf::{BitReader, BitWriter};
+use katsuba_types::{Property, PropertyFlags, TypeDef};
 use katsuba_utils::{align::align_down, hash::djb2, hash::string_id};
-use smartstring::alias::String;
 
-use super::{property, utils, Error, SerializerFlags, SerializerParts, TypeTag};
+use super::{
+    property, utils, Build, Error, SerializerFlags, SerializerParts, SizeMismatch, TypeTag,
+};
 use crate::{value::Object, Value};
 
-pub fn deserialize<T: TypeTag>(
+pub fn deserialize<T: TypeTag, B: Build>(
+    b: &B,
     de: &mut SerializerParts,
     reader: &mut BitReader<'_>,
-) -> Result<Value, Error> {
+) -> Result<B::Value, Error> {
     de.with_recursion_limit(|de| {
         reader.realign_to_byte();
 
@@ -20,31 +20,20 @@ pub fn deserialize<T: TypeTag>(
             // If a type definition exists, read the full object.
             Ok(Some(type_def)) => {
                 let object_size = read_bit_size(de, reader)? as usize;
-                deserialize_properties::<T>(de, object_size, type_def, reader)?
+                check_bit_size(object_size, reader)?;
+                deserialize_properties::<T, B>(b, de, object_size, type_def, reader)?
             }
 
             // If we encountered a null pointer, return an empty value.
-            Ok(None) => Value::Empty,
+            Ok(None) => b.value(Value::Empty),
 
             // If no type definition exists but we're allowed to skip it,
             // consume the bits the object is supposed to occupy.
             Err(_) if de.options.skip_unknown_types => {
                 log::warn!("Encountered unknown type; skipping it");
 
-                let object_size = read_bit_size(de, reader)? as usize;
-                let aligned_object_size = align_down(object_size, u8::BITS as _);
-
-                // When skipping an object, we must make sure to consume
-                // exactly as many bits as specified or we might end up
-                // with property size mismatches.
-                //
-                // We first read the whole bytes out of the given bit size,
-                // then refill the buffer and consume only the remainder.
-                reader.read_bytes(utils::bits_to_bytes(aligned_object_size))?;
-                reader.refill_bits();
-                reader.consume((object_size - aligned_object_size) as u32)?;
-
-                Value::Empty
+                skip_sized(de, reader)?;
+                b.value(Value::Empty)
             }
 
             // If no type definition was found but we're also not allowed
@@ -56,65 +45,107 @@ pub fn deserialize<T: TypeTag>(
     })
 }
 
-fn deserialize_properties<T: TypeTag>(
+/// Consumes the remainder of an object after its identity by
+/// means of its size prefix.
+///
+/// This only works in deep mode, as shallow objects are unsized.
+pub(super) fn skip_sized(de: &SerializerParts, reader: &mut BitReader<'_>) -> Result<(), Error> {
+    let object_size = read_bit_size(de, reader)? as usize;
+    skip_bits(reader, object_size)
+}
+
+// Consumes exactly `nbits` from a reader positioned on a byte
+// boundary, so no size mismatches arise after skipping.
+fn skip_bits(reader: &mut BitReader<'_>, nbits: usize) -> Result<(), Error> {
+    check_bit_size(nbits, reader)?;
+    let aligned = align_down(nbits as u64, utils::BYTE) as usize;
+
+    // We first read the whole bytes out of the given bit size,
+    // then refill the buffer and consume only the remainder.
+    // Realigning only returns the lookahead to the reader.
+    reader.realign_to_byte();
+    reader.read_bytes(utils::bits_to_bytes(aligned))?;
+    reader.refill_bits();
+    reader.consume((nbits - aligned) as u32)?;
+
+    Ok(())
+}
+
+pub(super) fn deserialize_properties<T: TypeTag, B: Build>(
+    b: &B,
     de: &mut SerializerParts,
     object_size: usize,
     type_def: &TypeDef,
     reader: &mut BitReader<'_>,
-) -> Result<Value, Error> {
-    let mut inner = BTreeMap::new();
+) -> Result<B::Value, Error> {
+    let mut inner = b.object();
 
     if de.options.shallow {
-        deserialize_properties_shallow::<T>(&mut inner, de, type_def, reader)?;
+        deserialize_properties_shallow::<T, B>(b, &mut inner, de, type_def, reader)?;
     } else {
-        deserialize_properties_deep::<T>(&mut inner, de, object_size, type_def, reader)?;
+        deserialize_properties_deep::<T, B>(b, &mut inner, de, object_size, type_def, reader)?;
     }
 
-    let hash = match de.options.djb2_only {
-        true => djb2(type_def.name.as_bytes()),
-        false => string_id(type_def.name.as_bytes()),
-    };
+    // Decoded values take precedence over the defaults.
+    if de.options.fill_defaults {
+        if let Value::Object { mut obj, .. } = de.default_object(type_def) {
+            for (name, value) in std::mem::take(&mut obj.inner) {
+                if !de.filters_property(&name) {
+                    b.insert_default(&mut inner, name, value);
+                }
+            }
+        }
+    }
 
-    Ok(Value::Object {
-        hash,
-        obj: Object { inner },
-    })
+    Ok(b.finish_object(type_hash(de, type_def), inner))
 }
 
 #[inline]
-fn deserialize_properties_shallow<T: TypeTag>(
-    obj: &mut BTreeMap<String, Value>,
+fn deserialize_properties_shallow<T: TypeTag, B: Build>(
+    b: &B,
+    obj: &mut B::Object,
     de: &mut SerializerParts,
     type_def: &TypeDef,
     reader: &mut BitReader<'_>,
 ) -> Result<(), Error> {
     // In shallow mode, we walk masked properties in order.
-    let mask = de.options.property_mask;
+    let options = de.options;
     for property in type_def
-        .properties
-        .iter()
-        .filter(|p| p.flags.contains(mask) && !p.flags.contains(PropertyFlags::DEPRECATED))
+        .property_order()
+        .filter(|p| options.is_shallow_property(p))
     {
-        if property.flags.contains(PropertyFlags::DELTA_ENCODE)
-            && !utils::read_bool(reader)?
-            && de
+        de.count_value()?;
+
+        // Delta-encoded properties are only present when their
+        // preceding bit is set.
+        if property.flags.contains(PropertyFlags::DELTA_ENCODE) && !utils::read_bool(reader)? {
+            if de
                 .options
                 .flags
                 .contains(SerializerFlags::FORBID_DELTA_ENCODE)
-        {
-            return Err(Error::MissingDelta);
+            {
+                return Err(Error::MissingDelta);
+            }
+
+            if !de.filters_property(&property.name) {
+                b.insert(obj, &property.name, b.value(Value::Unset));
+            }
+            continue;
         }
 
-        let value = property::deserialize::<T>(de, property, reader)?;
-        obj.insert(property.name.clone(), value);
+        let value = property::deserialize::<T, B>(b, de, property, reader)?;
+        if !de.filters_property(&property.name) {
+            b.insert(obj, &property.name, value);
+        }
     }
 
     Ok(())
 }
 
 #[inline]
-fn deserialize_properties_deep<T: TypeTag>(
-    obj: &mut BTreeMap<String, Value>,
+fn deserialize_properties_deep<T: TypeTag, B: Build>(
+    b: &B,
+    obj: &mut B::Object,
     de: &mut SerializerParts,
     mut object_size: usize,
     type_def: &TypeDef,
@@ -122,9 +153,9 @@ fn deserialize_properties_deep<T: TypeTag>(
 ) -> Result<(), Error> {
     // In deep mode, the properties name themselves.
     while object_size > 0 {
-        // Back up the current buffer length and read the property size.
+        // Back up the current position and read the property size.
         // This will also count padding bits to byte boundaries.
-        let previous_buf_len = reader.remaining_bits();
+        let start = reader.bit_position();
         reader.realign_to_byte();
 
         let property_size = utils::read_bits(reader, u32::BITS)? as usize;
@@ -137,36 +168,239 @@ fn deserialize_properties_deep<T: TypeTag>(
             .find(|p| p.hash == property_hash)
             .ok_or_else(|| Error::UnknownProperty(property_hash))?;
 
+        // Filtered properties are consumed without decoding them.
+        if de.filters_property(&property.name) {
+            // The size may not even cover the prefix we just read.
+            let rest = start
+                .checked_add(property_size)
+                .and_then(|end| end.checked_sub(reader.bit_position()))
+                .ok_or_else(|| {
+                    size_mismatch(de, type_def, property, start, property_size, reader)
+                })?;
+            skip_bits(reader, rest)?;
+
+            object_size = remaining_size(object_size, property_size, type_def, property)?;
+            continue;
+        }
+
         // Deserialize the property's value.
-        let value = property::deserialize::<T>(de, property, reader)?;
+        de.count_value()?;
+        let value = property::deserialize::<T, B>(b, de, property, reader)?;
 
-        // Validate the size expectations.
-        let actual_size = previous_buf_len - reader.remaining_bits();
+        // Validate the size expectations. The reader only moves
+        // forward, so this cannot underflow.
+        let actual_size = reader.bit_position() - start;
         if property_size != actual_size {
-            return Err(Error::PropertySizeMismatch {
-                expected: property_size,
-                actual: actual_size,
-            });
+            return Err(size_mismatch(
+                de,
+                type_def,
+                property,
+                start,
+                property_size,
+                reader,
+            ));
         }
 
         // Prepare for the next round of deserialization.
-        object_size = object_size
-            .checked_sub(property_size)
-            .ok_or_else(|| Error::ObjectSizeMismatch)?;
+        object_size = remaining_size(object_size, property_size, type_def, property)?;
 
         // Lastly, insert the property into the object.
-        obj.insert(property.name.clone(), value);
+        b.insert(obj, &property.name, value);
     }
 
     Ok(())
 }
 
+// Describes a property whose encoded size of `expected` bits does
+// not match the data consumed for it up to the current position.
+fn size_mismatch(
+    de: &SerializerParts,
+    type_def: &TypeDef,
+    property: &Property,
+    start: usize,
+    expected: usize,
+    reader: &BitReader<'_>,
+) -> Error {
+    let end = reader.bit_position();
+    let mut mismatch = SizeMismatch {
+        class: type_def.name.to_string(),
+        property: property.name.to_string(),
+        r#type: property.r#type.to_string(),
+        start,
+        end,
+        expected,
+        actual: end - start,
+        context: None,
+    };
+    if de.options.verbose_errors {
+        mismatch.capture_context(reader);
+    }
+
+    Error::PropertySizeMismatch(Box::new(mismatch))
+}
+
+// Subtracts the size of a property from what is left of its object.
+fn remaining_size(
+    object_size: usize,
+    property_size: usize,
+    type_def: &TypeDef,
+    property: &Property,
+) -> Result<usize, Error> {
+    object_size
+        .checked_sub(property_size)
+        .ok_or_else(|| Error::ObjectSizeMismatch {
+            class: type_def.name.to_string(),
+            property: property.name.to_string(),
+            size: property_size,
+            remaining: object_size,
+        })
+}
+
+/// Computes the hash which identifies `type_def` in values.
+#[inline]
+pub(super) fn type_hash(de: &SerializerParts, type_def: &TypeDef) -> u32 {
+    match de.options.djb2_only {
+        true => djb2(type_def.name.as_bytes()),
+        false => string_id(type_def.name.as_bytes()),
+    }
+}
+
 #[inline]
 pub(crate) fn read_bit_size(
     de: &SerializerParts,
     reader: &mut BitReader<'_>,
 ) -> Result<u32, Error> {
-    (!de.options.shallow)
-        .then(|| Ok(utils::read_bits(reader, u32::BITS)? as u32 - u32::BITS))
-        .unwrap_or(Ok(0))
+    if de.options.shallow {
+        Ok(0)
+    } else {
+        // The encoded size includes its own 32 bits.
+        let size = utils::read_bits(reader, u32::BITS)? as u32;
+        size.checked_sub(u32::BITS)
+            .ok_or(Error::InvalidObjectSize(size))
+    }
+}
+
+// Rejects object sizes which cannot be satisfied by the data, so
+// truncated streams are reported as such rather than failing in
+// the middle of a property.
+#[inline]
+fn check_bit_size(object_size: usize, reader: &BitReader<'_>) -> Result<(), Error> {
+    let available = reader.remaining_bits();
+    if object_size > available {
+        return Err(Error::ObjectTooLarge {
+            size: object_size,
+            available,
+        });
+    }
+
+    Ok(())
+}
+
+pub fn serialize<T: TypeTag>(
+    ser: &SerializerParts,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    writer.realign_to_byte();
+
+    let (hash, obj) = match value.resolve() {
+        Value::Object { hash, obj } => (*hash, obj),
+
+        // Empty values are encoded as null pointers.
+        Value::Empty => {
+            T::write_identity(writer, None);
+            return Ok(());
+        }
+
+        _ => {
+            return Err(Error::ValueMismatch {
+                expected: "object".into(),
+                found: value.variant_name(),
+            })
+        }
+    };
+
+    let type_def = ser.types.0.get(&hash).ok_or(Error::UnknownType(hash))?;
+    T::write_identity(writer, Some(hash));
+
+    // Every property of the object must be known to its class.
+    if let Some(name) = obj
+        .keys()
+        .find(|name| !type_def.properties.iter().any(|p| p.name == name.as_str()))
+    {
+        return Err(Error::UndeclaredProperty(name.to_string()));
+    }
+
+    if ser.options.shallow {
+        serialize_properties_shallow::<T>(ser, obj, type_def, writer)
+    } else {
+        writer.length_prefixed(|writer| serialize_properties_deep::<T>(ser, obj, type_def, writer))
+    }
+}
+
+#[inline]
+fn serialize_properties_shallow<T: TypeTag>(
+    ser: &SerializerParts,
+    obj: &Object,
+    type_def: &TypeDef,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    // In shallow mode, all masked properties must be written in order.
+    for property in type_def
+        .property_order()
+        .filter(|p| ser.options.is_shallow_property(p))
+    {
+        let value = obj
+            .get(property.name.as_str())
+            .ok_or_else(|| Error::MissingProperty(property.name.to_string()))?;
+
+        if property.flags.contains(PropertyFlags::DELTA_ENCODE) {
+            let present = !matches!(value, Value::Unset);
+            if !present
+                && ser
+                    .options
+                    .flags
+                    .contains(SerializerFlags::FORBID_DELTA_ENCODE)
+            {
+                return Err(Error::MissingDelta);
+            }
+
+            utils::write_bool(writer, present);
+            if !present {
+                continue;
+            }
+        }
+
+        property::serialize::<T>(ser, property, value, writer)?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn serialize_properties_deep<T: TypeTag>(
+    ser: &SerializerParts,
+    obj: &Object,
+    type_def: &TypeDef,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    // In deep mode, the properties name themselves so we only
+    // write those which are present. Objects do not remember the
+    // order properties were read in, so they are written in the
+    // declared order too.
+    for property in type_def.property_order() {
+        let Some(value) = obj
+            .get(property.name.as_str())
+            .filter(|v| !matches!(v, Value::Unset))
+        else {
+            continue;
+        };
+
+        writer.length_prefixed(|writer| {
+            utils::write_bits(writer, property.hash as u64, u32::BITS);
+            property::serialize::<T>(ser, property, value, writer)
+        })?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,101 @@
+use std::{io, ops::Range, sync::Arc};
+
+use katsuba_bit_buf::BitReader;
+use katsuba_executor::{Executor, Task};
+
+use super::*;
+use crate::{value::List, Value};
+
+impl Serializer {
+    /// Deserializes independent root objects from the given data as
+    /// tasks on `ex`.
+    ///
+    /// `spans` locate the objects and are usually obtained from
+    /// [`Serializer::index`] on the same data. The objects are split
+    /// into one chunk per worker of the executor, and every chunk
+    /// uses its own deserializer state which shares the type list.
+    ///
+    /// The values are returned as a [`Value::List`] in the order of
+    /// `spans`, with null objects as [`Value::Empty`]. Raw spans are
+    /// not captured in this mode.
+    ///
+    /// Tasks must be able to make progress while this waits for them,
+    /// so it must not be called from a task on the same executor.
+    pub fn deserialize_spans<T: TypeTag + 'static>(
+        &mut self,
+        ex: &Executor,
+        data: &[u8],
+        spans: &[ObjectSpan],
+    ) -> Result<Value, Error> {
+        let reader =
+            self.zlib_parts
+                .configure::<T>(&mut self.parts.options, &self.parts.types, data)?;
+        let data = reader.data();
+
+        // Locate the bytes of every object up front so tasks only
+        // deal with their own input.
+        let objects = spans
+            .iter()
+            .map(|span| {
+                let end = span.start.checked_add(span.len).map(|end| end.div_ceil(8));
+                end.filter(|&end| end <= data.len())
+                    .map(|end| span.start / 8..end)
+                    .ok_or(Error::ObjectTooLarge {
+                        size: span.len,
+                        available: (data.len() * 8).saturating_sub(span.start),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let options = SerializerOptions {
+            capture_raw: false,
+            ..self.parts.options
+        };
+        log::info!(
+            "Deserializing {} objects with config {options:?}",
+            objects.len()
+        );
+
+        // Tasks must own their input, so the data is shared between
+        // them rather than borrowed.
+        let data: Arc<[u8]> = data.into();
+        let chunk_size = objects.len().div_ceil(ex.workers()).max(1);
+
+        let mut handles = Vec::new();
+        for chunk in objects.chunks(chunk_size) {
+            let chunk: Vec<Range<usize>> = chunk.to_vec();
+            let data = data.clone();
+            let types = self.parts.types.clone();
+            let filter = self.parts.property_filter.clone();
+
+            let (task, handle) = Task::spawn(move || {
+                let mut parts = SerializerParts::new(options, types);
+                parts.set_property_filter(filter);
+                chunk
+                    .into_iter()
+                    .map(|object| {
+                        parts.reset_budgets();
+                        let mut reader = BitReader::new(&data[object]);
+                        object::deserialize::<T, _>(&Owned, &mut parts, &mut reader)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            });
+
+            // Other tasks on the executor may finish while we wait.
+            for pending in ex.dispatch(task) {
+                pending.map_err(io::Error::other)?;
+            }
+            handles.push(handle);
+        }
+
+        let mut inner = Vec::with_capacity(spans.len());
+        for handle in handles {
+            let chunk = handle
+                .wait()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::Interrupted))?;
+            inner.extend(chunk?);
+        }
+
+        Ok(Value::List(List { inner }))
+    }
+}
@@ -0,0 +1,80 @@
+use katsuba_bit_buf::{BitReader, BitWriter};
+use katsuba_types::TemplateType;
+
+use super::*;
+use crate::Value;
+
+/// Gets the type a smart pointer type `ty` points to.
+///
+/// Returns [`None`] when `ty` is not a `SharedPointer` or `Ptr`.
+pub fn pointee(ty: &str) -> Option<&str> {
+    if !ty.contains("SharedPointer<") && !ty.contains("Ptr<") {
+        return None;
+    }
+
+    let template = TemplateType::parse(ty)?;
+    match (template.name, template.args.as_slice()) {
+        ("SharedPointer" | "Ptr", &[pointee]) => Some(pointee),
+        _ => None,
+    }
+}
+
+/// Deserializes the object behind a pointer to `pointee`.
+///
+/// A null type tag marks a null pointer and produces an empty
+/// value. Otherwise, the object's class must inherit from the
+/// pointee when the type list has the hierarchy to tell.
+pub fn deserialize<T: TypeTag, B: Build>(
+    b: &B,
+    de: &mut SerializerParts,
+    pointee: &str,
+    reader: &mut BitReader<'_>,
+) -> Result<B::Value, Error> {
+    if let Some(found) = mismatched_class::<T>(de, pointee, reader) {
+        return Err(Error::PointeeMismatch {
+            expected: pointee.to_string(),
+            found,
+        });
+    }
+
+    object::deserialize::<T, B>(b, de, reader)
+}
+
+/// Peeks at the class of the next object in `reader` and gets its
+/// name if it neither is `class` nor inherits from it.
+///
+/// The reader is left at the start of the object.
+pub(super) fn mismatched_class<T: TypeTag>(
+    de: &SerializerParts,
+    class: &str,
+    reader: &mut BitReader<'_>,
+) -> Option<String> {
+    reader.realign_to_byte();
+    let checkpoint = reader.checkpoint();
+    let identity = T::identity(reader, &de.types);
+    reader.restore(checkpoint);
+
+    let type_def = identity.ok()??;
+
+    // Without base classes, we cannot tell the hierarchy apart
+    // from a type list which lacks it.
+    let checkable = !type_def.bases.is_empty() && de.types.find(class).is_some();
+    (checkable && !de.types.inherits_from(&type_def.name, class)).then(|| type_def.name.to_string())
+}
+
+/// Serializes the object behind a pointer to `pointee`, or a null
+/// pointer for empty values.
+pub fn serialize<T: TypeTag>(
+    ser: &SerializerParts,
+    pointee: &str,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    match value {
+        Value::Object { .. } | Value::Empty => object::serialize::<T>(ser, value, writer),
+        _ => Err(Error::ValueMismatch {
+            expected: format!("SharedPointer<{pointee}>"),
+            found: value.variant_name(),
+        }),
+    }
+}
@@ -1,64 +1,268 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use katsuba_types::Property;
 
-use super::*;
-use crate::value::{List, Value};
+use super::{container::Container, *};
+use crate::value::{PathSegment, Value};
 
-pub fn deserialize<T: TypeTag>(
+pub fn deserialize<T: TypeTag, B: Build>(
+    b: &B,
     de: &mut SerializerParts,
     property: &Property,
     reader: &mut BitReader<'_>,
-) -> Result<Value, Error> {
+) -> Result<B::Value, Error> {
     log::debug!("Deserializing value for property '{}'", property.name);
 
-    let value = if property.dynamic {
-        deserialize_list::<T>(de, property, reader)?
-    } else {
-        deserialize_value::<T>(de, property, reader)?
-    };
+    let segment = || PathSegment::Property(property.name.clone());
+    let value = de
+        .capture(segment, reader, |de, reader| {
+            if property.dynamic {
+                deserialize_list::<T, B>(b, de, property, reader)
+            } else {
+                deserialize_value::<T, B>(b, de, property, reader)
+            }
+        })
+        .map_err(|e| e.within(segment))?;
 
     log::trace!("Got '{value:?}'");
 
     Ok(value)
 }
 
-fn deserialize_value<T: TypeTag>(
+fn deserialize_value<T: TypeTag, B: Build>(
+    b: &B,
     de: &mut SerializerParts,
     property: &Property,
     reader: &mut BitReader<'_>,
-) -> Result<Value, Error> {
+) -> Result<B::Value, Error> {
     if property.is_enum() {
-        enum_variant::deserialize(de, property, reader)
+        enum_variant::deserialize(de, property, reader).map(|v| b.value(v))
     } else {
-        // Try to interpret the value as simple data and if that fails,
-        // deserialize a new object as a fallback strategy.
-        match simple_data::deserialize(de, &property.r#type, reader) {
-            Some(v) => v,
-            None => object::deserialize::<T>(de, reader),
-        }
+        deserialize_type::<T, B>(b, de, &property.r#type, reader)
     }
 }
 
-fn deserialize_list<T: TypeTag>(
+/// Deserializes a value of type `ty`, which is not the type of an
+/// enum property.
+pub(super) fn deserialize_type<T: TypeTag, B: Build>(
+    b: &B,
     de: &mut SerializerParts,
-    property: &Property,
+    ty: &str,
     reader: &mut BitReader<'_>,
-) -> Result<Value, Error> {
+) -> Result<B::Value, Error> {
+    if let Some(container) = Container::parse(ty) {
+        return container.deserialize::<T>(de, reader).map(|v| b.value(v));
+    }
+
+    // Pointers are never simple data, so we go straight to the object.
+    if let Some(pointee) = pointer::pointee(ty) {
+        return pointer::deserialize::<T, B>(b, de, pointee, reader);
+    }
+
+    // Enums nested in containers have no property to decode them.
+    if ty.starts_with("enum ") {
+        return enum_variant::deserialize_untyped(de, reader).map(|v| b.value(v));
+    }
+
+    // Strings are the most common values which need allocations, so
+    // they are built in place rather than converted.
+    if ty == "std::string" && !de.options.decode_nested {
+        let s = utils::read_string(reader, &de.options)?;
+        de.check_utf8(s, reader);
+        return Ok(b.string(s));
+    }
+
+    // Types with a builtin encoding are always read as simple data,
+    // even if the type list reflects them as classes. Errors from
+    // them are final; falling back to objects would decode garbage.
+    if let Some(res) = simple_data::deserialize(de, ty, reader) {
+        return match res {
+            Ok(Value::String(raw)) if de.options.decode_nested => {
+                let value = nested::deserialize::<T>(de, raw)?;
+                if let Value::String(raw) = &value {
+                    de.check_utf8(&raw.0, reader);
+                }
+                Ok(b.value(value))
+            }
+            Ok(Value::String(raw)) => {
+                de.check_utf8(&raw.0, reader);
+                Ok(b.value(Value::String(raw)))
+            }
+            res => res.map(|v| b.value(v)),
+        };
+    }
+
+    // Properties may be declared with a class or a raw pointer to
+    // one. Objects of an unrelated class hint at misaligned data.
+    let class = ty.strip_suffix('*').map_or(ty, str::trim_end);
+    if de.types.find(class).is_some() {
+        if let Some(found) = pointer::mismatched_class::<T>(de, class, reader) {
+            if de.options.strict_classes {
+                return Err(Error::ClassMismatch {
+                    expected: class.to_string(),
+                    found,
+                });
+            }
+            log::warn!("Expected object of class '{class}', got '{found}'");
+        }
+
+        return object::deserialize::<T, B>(b, de, reader);
+    }
+
+    // The type is known to neither, so it might still be an object.
+    if !de.options.object_fallback {
+        return Err(Error::UnknownValueType(ty.to_string()));
+    }
+    log::warn!("No representation known for type '{ty}', decoding it as an object");
+
+    // Leave the reader where it was if the guess was wrong.
+    let checkpoint = reader.checkpoint();
+    object::deserialize::<T, B>(b, de, reader).inspect_err(|_| reader.restore(checkpoint))
+}
+
+/// Reads the length prefix of a container and validates it against
+/// the remaining data and the configured limits.
+pub(super) fn read_length(
+    de: &SerializerParts,
+    reader: &mut BitReader<'_>,
+) -> Result<usize, Error> {
     let len = utils::read_container_length(
         reader,
         de.options
             .flags
             .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
     )?;
-    let mut inner = Vec::with_capacity(len);
+
+    // Every element occupies at least one bit.
+    utils::check_remaining(reader, len, 1)?;
+    if len > de.options.limits.max_elements {
+        return Err(Error::limit(Limit::Elements));
+    }
+
+    Ok(len)
+}
+
+fn deserialize_list<T: TypeTag, B: Build>(
+    b: &B,
+    de: &mut SerializerParts,
+    property: &Property,
+    reader: &mut BitReader<'_>,
+) -> Result<B::Value, Error> {
+    let len = read_length(de, reader)?;
+    let mut list = b.list(len.min(utils::PREALLOC_LIMIT));
 
     de.with_recursion_limit(|de| {
-        for _ in 0..len {
-            inner.push(deserialize_value::<T>(de, property, reader)?);
+        for idx in 0..len {
+            let value = de
+                .count_value()
+                .and_then(|()| {
+                    de.capture(
+                        || PathSegment::Index(idx),
+                        reader,
+                        |de, reader| deserialize_value::<T, B>(b, de, property, reader),
+                    )
+                })
+                .map_err(|e| e.within(|| PathSegment::Index(idx)))?;
+            b.push(&mut list, value);
         }
 
         Ok(())
     })?;
 
-    Ok(Value::List(List { inner }))
+    Ok(b.finish_list(list))
+}
+
+pub fn serialize<T: TypeTag>(
+    ser: &SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    log::debug!("Serializing value for property '{}'", property.name);
+
+    let value = value.resolve();
+    if property.dynamic {
+        serialize_list::<T>(ser, property, value, writer)
+    } else {
+        serialize_value::<T>(ser, property, value, writer)
+    }
+}
+
+fn serialize_value<T: TypeTag>(
+    ser: &SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    if property.is_enum() {
+        enum_variant::serialize(ser, property, value, writer)
+    } else {
+        serialize_type::<T>(ser, &property.r#type, value, writer)
+    }
+}
+
+/// Serializes a value of type `ty`, which is not the type of an
+/// enum property.
+pub(super) fn serialize_type<T: TypeTag>(
+    ser: &SerializerParts,
+    ty: &str,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    if let Value::Blob(blob) = value {
+        return nested::serialize::<T>(ser, blob, writer);
+    }
+
+    if let Some(container) = Container::parse(ty) {
+        return container.serialize::<T>(ser, value, writer);
+    }
+
+    if let Some(pointee) = pointer::pointee(ty) {
+        return pointer::serialize::<T>(ser, pointee, value, writer);
+    }
+
+    if ty.starts_with("enum ") {
+        return enum_variant::serialize_untyped(ser, ty, value, writer);
+    }
+
+    // Values which don't fit the simple data representation of the
+    // type may still be objects, as for types of unknown representation
+    // during deserialization.
+    if let Some(true) = simple_data::serialize(ser, ty, value, writer).transpose()? {
+        return Ok(());
+    }
+
+    match value {
+        Value::Object { .. } | Value::Empty => object::serialize::<T>(ser, value, writer),
+        _ => Err(Error::ValueMismatch {
+            expected: ty.to_string(),
+            found: value.variant_name(),
+        }),
+    }
+}
+
+fn serialize_list<T: TypeTag>(
+    ser: &SerializerParts,
+    property: &Property,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Result<(), Error> {
+    let Value::List(list) = value else {
+        return Err(Error::ValueMismatch {
+            expected: format!("std::vector<{}>", property.r#type),
+            found: value.variant_name(),
+        });
+    };
+
+    utils::write_container_length(
+        writer,
+        list.len(),
+        ser.options
+            .flags
+            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+    )?;
+    for element in list {
+        serialize_value::<T>(ser, property, element.resolve(), writer)?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,34 @@
+use katsuba_bit_buf::BitWriter;
+
+use super::*;
+use crate::Value;
+
+impl ZlibParts {
+    fn finish(&mut self, opts: &SerializerOptions, data: Vec<u8>) -> Vec<u8> {
+        let deflater = self.deflater.get_or_insert_with(compress::Deflater::best);
+        write_framing(deflater, opts, &data)
+    }
+}
+
+impl Serializer {
+    /// Serializes an object [`Value`] to bytes.
+    ///
+    /// This is the inverse of [`Serializer::deserialize`] with the
+    /// same configuration. Compressed output may differ from what
+    /// the game produces for identical data.
+    pub fn serialize<T: TypeTag>(&mut self, value: &Value) -> Result<Vec<u8>, Error> {
+        log::info!("Serializing object with config {:?}", self.parts.options);
+
+        if let Value::Empty = value.resolve() {
+            return Err(Error::NullRoot);
+        }
+
+        let mut writer = BitWriter::new();
+        object::serialize::<T>(&self.parts, value, &mut writer)?;
+        writer.realign_to_byte();
+
+        Ok(self
+            .zlib_parts
+            .finish(&self.parts.options, writer.into_inner()))
+    }
+}
@@ -1,4 +1,4 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use phf::phf_map;
 
 use crate::value::*;
@@ -7,6 +7,9 @@ use super::{utils, Error, SerializerOptions, SerializerParts};
 
 type ReadCallback = fn(&mut BitReader<'_>, &SerializerOptions) -> Result<Value, Error>;
 
+// Writes a value and returns whether its variant was accepted.
+type WriteCallback = fn(&mut BitWriter, &SerializerOptions, &Value) -> Result<bool, Error>;
+
 static DESERIALIZER_LUT: phf::Map<&'static str, (bool, ReadCallback)> = phf_map! {
     // Primitive C++ types
     "bool" => (true, |r, _| utils::read_bool(r).map(Value::Bool)),
@@ -19,11 +22,17 @@ static DESERIALIZER_LUT: phf::Map<&'static str, (bool, ReadCallback)> = phf_map!
     "unsigned int" => (false, |r, _| utils::read_bits(r, u32::BITS).map(Value::Unsigned)),
     "long" => (false, |r, _| utils::read_signed_bits(r, i32::BITS).map(Value::Signed)),
     "unsigned long" => (false, |r, _| utils::read_bits(r, u32::BITS).map(Value::Unsigned)),
-    "float" => (false, |r, _| utils::read_bits(r, u32::BITS).map(|v| Value::Float(f32::from_bits(v as _) as f64))),
+    "float" => (false, |r, _| utils::read_bits(r, u32::BITS).map(|v| Value::Float32(f32::from_bits(v as _)))),
     "double" => (false, |r, _| utils::read_u64(r).map(|v| Value::Float(f64::from_bits(v)))),
     "unsigned __int64" => (false, |r, _| utils::read_u64(r).map(Value::Unsigned)),
-    "gid" => (false, |r, _| utils::read_u64(r).map(Value::Unsigned)),
-    "union gid" => (false, |r, _| utils::read_u64(r).map(Value::Unsigned)),
+    "gid" => (false, |r, _| utils::read_u64(r).map(Value::Gid)),
+    "union gid" => (false, |r, _| utils::read_u64(r).map(Value::Gid)),
+    "unsigned long long" => (false, |r, _| utils::read_u64(r).map(Value::Unsigned)),
+
+    // Timestamps
+    "time_t" => (false, |r, _| utils::read_u64(r).map(|v| Value::Time(Time::unix_seconds(v as i64)))),
+    "__time64_t" => (false, |r, _| utils::read_u64(r).map(|v| Value::Time(Time::unix_seconds(v as i64)))),
+    "__time32_t" => (false, |r, _| utils::read_signed_bits(r, i32::BITS).map(|v| Value::Time(Time::unix_seconds(v)))),
 
     // Bit integers
     "bi2" => (true, |r, _| utils::read_signed_bits(r, 2).map(Value::Signed)),
@@ -44,6 +53,7 @@ static DESERIALIZER_LUT: phf::Map<&'static str, (bool, ReadCallback)> = phf_map!
     // Strings
     "std::string" => (true, |r, opts| utils::read_string(r, opts).map(|v| Value::String(CxxStr(v.to_owned())))),
     "std::wstring" => (true, |r, opts| utils::read_wstring(r, opts).map(|v| Value::WString(CxxWStr(v)))),
+    "wchar_t*" => (true, |r, opts| utils::read_null_terminated_wstring(r, opts).map(|v| Value::WString(CxxWStr(v)))),
 
     // Miscellaneous leaf types that are not PropertyClasses
     "class Color" => (false, |r, _| utils::read_color(r).map(Value::Color)),
@@ -87,6 +97,18 @@ static DESERIALIZER_LUT: phf::Map<&'static str, (bool, ReadCallback)> = phf_map!
     }),
 };
 
+/// Gets the default value of the simple type `ty`.
+///
+/// This is what an all-zero encoding decodes to: zero numbers,
+/// empty strings and the Unix epoch for timestamps.
+pub fn default_value(ty: &str, options: &SerializerOptions) -> Option<Value> {
+    // Enough for the largest simple type, a 3x3 float matrix.
+    const ZEROES: [u8; 64] = [0; 64];
+
+    let (_, f) = DESERIALIZER_LUT.get(ty)?;
+    f(&mut BitReader::new(&ZEROES), options).ok()
+}
+
 pub fn deserialize(
     de: &SerializerParts,
     ty: &str,
@@ -100,3 +122,239 @@ pub fn deserialize(
         f(reader, &de.options)
     })
 }
+
+fn signed(w: &mut BitWriter, v: &Value, nbits: u32) -> bool {
+    match v {
+        Value::Signed(v) => {
+            utils::write_bits(w, *v as u64, nbits);
+            true
+        }
+        _ => false,
+    }
+}
+
+fn unsigned(w: &mut BitWriter, v: &Value, nbits: u32) -> bool {
+    match v {
+        Value::Unsigned(v) => {
+            utils::write_bits(w, *v, nbits);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Writes a global ID, or a plain integer in its place.
+fn gid(w: &mut BitWriter, v: &Value) -> bool {
+    match v {
+        Value::Gid(v) | Value::Unsigned(v) => {
+            utils::write_u64(w, *v);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Writes a timestamp, or a plain integer in its place.
+fn time(w: &mut BitWriter, v: &Value, nbits: u32) -> bool {
+    let raw = match v {
+        Value::Time(t) => t.raw,
+        Value::Signed(v) => *v,
+        Value::Unsigned(v) => *v as i64,
+        _ => return false,
+    };
+    match nbits {
+        64 => utils::write_u64(w, raw as u64),
+        _ => utils::write_bits(w, raw as u64, nbits),
+    }
+    true
+}
+
+fn unsigned64(w: &mut BitWriter, v: &Value) -> bool {
+    match v {
+        Value::Unsigned(v) => {
+            utils::write_u64(w, *v);
+            true
+        }
+        _ => false,
+    }
+}
+
+static SERIALIZER_LUT: phf::Map<&'static str, (bool, WriteCallback)> = phf_map! {
+    // Primitive C++ types
+    "bool" => (true, |w, _, v| match v {
+        Value::Bool(v) => {
+            utils::write_bool(w, *v);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "char" => (false, |w, _, v| Ok(signed(w, v, i8::BITS))),
+    "unsigned char" => (false, |w, _, v| Ok(unsigned(w, v, u8::BITS))),
+    "short" => (false, |w, _, v| Ok(signed(w, v, i16::BITS))),
+    "unsigned short" => (false, |w, _, v| Ok(unsigned(w, v, u16::BITS))),
+    "wchar_t" => (false, |w, _, v| Ok(unsigned(w, v, u16::BITS))),
+    "int" => (false, |w, _, v| Ok(signed(w, v, i32::BITS))),
+    "unsigned int" => (false, |w, _, v| Ok(unsigned(w, v, u32::BITS))),
+    "long" => (false, |w, _, v| Ok(signed(w, v, i32::BITS))),
+    "unsigned long" => (false, |w, _, v| Ok(unsigned(w, v, u32::BITS))),
+    "float" => (false, |w, _, v| {
+        let v = match v {
+            Value::Float32(v) => *v,
+            Value::Float(v) => *v as f32,
+            _ => return Ok(false),
+        };
+        utils::write_bits(w, v.to_bits() as u64, u32::BITS);
+        Ok(true)
+    }),
+    "double" => (false, |w, _, v| {
+        let v = match v {
+            Value::Float(v) => *v,
+            Value::Float32(v) => *v as f64,
+            _ => return Ok(false),
+        };
+        utils::write_u64(w, v.to_bits());
+        Ok(true)
+    }),
+    "unsigned __int64" => (false, |w, _, v| Ok(unsigned64(w, v))),
+    "gid" => (false, |w, _, v| Ok(gid(w, v))),
+    "union gid" => (false, |w, _, v| Ok(gid(w, v))),
+    "unsigned long long" => (false, |w, _, v| Ok(unsigned64(w, v))),
+
+    // Timestamps
+    "time_t" => (false, |w, _, v| Ok(time(w, v, 64))),
+    "__time64_t" => (false, |w, _, v| Ok(time(w, v, 64))),
+    "__time32_t" => (false, |w, _, v| Ok(time(w, v, i32::BITS))),
+
+    // Bit integers
+    "bi2" => (true, |w, _, v| Ok(signed(w, v, 2))),
+    "bui2" => (true, |w, _, v| Ok(unsigned(w, v, 2))),
+    "bi3" => (true, |w, _, v| Ok(signed(w, v, 3))),
+    "bui3" => (true, |w, _, v| Ok(unsigned(w, v, 3))),
+    "bi4" => (true, |w, _, v| Ok(signed(w, v, 4))),
+    "bui4" => (true, |w, _, v| Ok(unsigned(w, v, 4))),
+    "bi5" => (true, |w, _, v| Ok(signed(w, v, 5))),
+    "bui5" => (true, |w, _, v| Ok(unsigned(w, v, 5))),
+    "bi6" => (true, |w, _, v| Ok(signed(w, v, 6))),
+    "bui6" => (true, |w, _, v| Ok(unsigned(w, v, 6))),
+    "bi7" => (true, |w, _, v| Ok(signed(w, v, 7))),
+    "bui7" => (true, |w, _, v| Ok(unsigned(w, v, 7))),
+    "s24" => (true, |w, _, v| Ok(signed(w, v, 24))),
+    "u24" => (true, |w, _, v| Ok(unsigned(w, v, 24))),
+
+    // Strings
+    "std::string" => (true, |w, opts, v| match v {
+        Value::String(v) => utils::write_string(w, &v.0, opts).map(|()| true),
+        _ => Ok(false),
+    }),
+    "std::wstring" => (true, |w, opts, v| match v {
+        Value::WString(v) => utils::write_wstring(w, &v.0, opts).map(|()| true),
+        _ => Ok(false),
+    }),
+    "wchar_t*" => (true, |w, _, v| match v {
+        Value::WString(v) => utils::write_null_terminated_wstring(w, &v.0).map(|()| true),
+        _ => Ok(false),
+    }),
+
+    // Miscellaneous leaf types that are not PropertyClasses
+    "class Color" => (false, |w, _, v| match v {
+        Value::Color(v) => {
+            utils::write_color(w, v);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Vector3D" => (false, |w, _, v| match v {
+        Value::Vec3(v) => {
+            utils::write_f32s(w, [v.x, v.y, v.z]);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Quaternion" => (false, |w, _, v| match v {
+        Value::Quat(v) => {
+            utils::write_f32s(w, [v.x, v.y, v.z, v.w]);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Euler" => (false, |w, _, v| match v {
+        Value::Euler(v) => {
+            utils::write_f32s(w, [v.pitch, v.roll, v.yaw]);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Matrix3x3" => (false, |w, _, v| match v {
+        Value::Mat3x3(v) => {
+            utils::write_f32s(w, v.i.into_iter().chain(v.j).chain(v.k));
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Size<int>" => (false, |w, _, v| match v {
+        Value::SizeInt(v) => {
+            utils::write_bits(w, v.width as u32 as u64, i32::BITS);
+            utils::write_bits(w, v.height as u32 as u64, i32::BITS);
+
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Point<int>" => (false, |w, _, v| match v {
+        Value::PointInt(v) => {
+            utils::write_bits(w, v.x as u32 as u64, i32::BITS);
+            utils::write_bits(w, v.y as u32 as u64, i32::BITS);
+
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Point<float>" => (false, |w, _, v| match v {
+        Value::PointFloat(v) => {
+            utils::write_bits(w, v.x.to_bits() as u64, u32::BITS);
+            utils::write_bits(w, v.y.to_bits() as u64, u32::BITS);
+
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Rect<int>" => (false, |w, _, v| match v {
+        Value::RectInt(v) => {
+            for edge in [v.left, v.top, v.right, v.bottom] {
+                utils::write_bits(w, edge as u32 as u64, i32::BITS);
+            }
+
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+    "class Rect<float>" => (false, |w, _, v| match v {
+        Value::RectFloat(v) => {
+            for edge in [v.left, v.top, v.right, v.bottom] {
+                utils::write_bits(w, edge.to_bits() as u64, u32::BITS);
+            }
+
+            Ok(true)
+        }
+        _ => Ok(false),
+    }),
+};
+
+/// Writes `value` as simple data of type `ty`.
+///
+/// Returns [`None`] when `ty` is not simple data and `Some(false)`
+/// when `value` does not hold the variant `ty` is represented by.
+pub fn serialize(
+    ser: &SerializerParts,
+    ty: &str,
+    value: &Value,
+    writer: &mut BitWriter,
+) -> Option<Result<bool, Error>> {
+    SERIALIZER_LUT.get(ty).map(|(bits, f)| {
+        if ser.options.shallow && !bits {
+            writer.realign_to_byte();
+        }
+
+        f(writer, &ser.options, value)
+    })
+}
@@ -0,0 +1,62 @@
+use katsuba_bit_buf::BitReader;
+
+use super::*;
+
+/// The location of a serialized object in the data.
+///
+/// Offsets are relative to the data after decompression, if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjectSpan {
+    /// The type hash of the object, or `0` for null objects.
+    pub hash: u32,
+    /// The offset of the first bit of the object.
+    pub start: usize,
+    /// The number of bits occupied by the object.
+    pub len: usize,
+}
+
+impl SerializerParts {
+    /// Consumes exactly one object from `reader` without keeping
+    /// its value and returns the bits it covers.
+    ///
+    /// Deep objects are skipped using their size prefix, which also
+    /// works for types missing from the type list. Shallow objects
+    /// have to be decoded in full.
+    pub fn skip_object<T: TypeTag>(
+        &mut self,
+        reader: &mut BitReader<'_>,
+    ) -> Result<ObjectSpan, Error> {
+        reader.realign_to_byte();
+        let start = reader.bit_position();
+
+        let types = self.types.clone();
+        let hash = match T::identity(reader, &types) {
+            Ok(None) => 0,
+
+            Ok(Some(type_def)) => {
+                if self.options.shallow {
+                    self.with_recursion_limit(|de| {
+                        object::deserialize_properties::<T, _>(&Owned, de, 0, type_def, reader)
+                    })?;
+                } else {
+                    object::skip_sized(self, reader)?;
+                }
+
+                object::type_hash(self, type_def)
+            }
+
+            Err(Error::UnknownType(hash)) if !self.options.shallow => {
+                object::skip_sized(self, reader)?;
+                hash
+            }
+
+            Err(e) => return Err(e),
+        };
+
+        Ok(ObjectSpan {
+            hash,
+            start,
+            len: reader.bit_position() - start,
+        })
+    }
+}
@@ -1,4 +1,4 @@
-use katsuba_bit_buf::BitReader;
+use katsuba_bit_buf::{BitReader, BitWriter};
 use katsuba_types::{TypeDef, TypeList};
 
 use super::{utils, Error};
@@ -12,6 +12,10 @@ pub trait TypeTag: Sized {
         reader: &mut BitReader<'_>,
         types: &'a TypeList,
     ) -> Result<Option<&'a TypeDef>, Error>;
+
+    /// Writes the identity of an object with the given type
+    /// hash, or of a null object for [`None`].
+    fn write_identity(writer: &mut BitWriter, hash: Option<u32>);
 }
 
 /// A [`TypeTag`] that identifies regular PropertyClasses.
@@ -25,6 +29,10 @@ impl TypeTag for PropertyClass {
         let hash = utils::read_bits(reader, u32::BITS)? as u32;
         find_class_def(types, hash)
     }
+
+    fn write_identity(writer: &mut BitWriter, hash: Option<u32>) {
+        utils::write_bits(writer, hash.unwrap_or(0) as u64, u32::BITS);
+    }
 }
 
 #[inline]
@@ -0,0 +1,46 @@
+use katsuba_bit_buf::BitReader;
+
+use super::*;
+
+/// A narrow string which is not valid UTF-8, recorded with
+/// [`SerializerOptions::strict_strings`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidString {
+    /// The location of the string in the deserialized value.
+    ///
+    /// Strings in containers other than lists are reported at
+    /// the path of their property.
+    pub path: Path,
+    /// The offset of the first byte of the first invalid sequence.
+    ///
+    /// Offsets are relative to the data after decompression, if any.
+    pub offset: usize,
+}
+
+impl SerializerParts {
+    /// Gets the strings with invalid UTF-8 found by the last
+    /// deserialization with [`SerializerOptions::strict_strings`]
+    /// enabled.
+    pub fn invalid_strings(&self) -> &[InvalidString] {
+        &self.invalid_strings
+    }
+
+    // Records `s` when it is not valid UTF-8. `s` must be the last
+    // bytes read from `reader`.
+    pub(super) fn check_utf8(&mut self, s: &[u8], reader: &BitReader<'_>) {
+        if !self.options.strict_strings {
+            return;
+        }
+
+        if let Err(e) = std::str::from_utf8(s) {
+            let start = reader.bit_position() / u8::BITS as usize - s.len();
+            let offset = start + e.valid_up_to();
+            log::warn!("Invalid UTF-8 in string at {} (byte {offset})", self.path);
+
+            self.invalid_strings.push(InvalidString {
+                path: self.path.clone(),
+                offset,
+            });
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
-use katsuba_bit_buf::{utils::sign_extend, BitReader};
-use katsuba_utils::align::align_up;
+use katsuba_bit_buf::{utils::sign_extend, BitReader, BitWriter, ReadError};
+use katsuba_utils::align::Alignment;
 
-use super::{Error, SerializerFlags, SerializerOptions};
+use super::{Error, Limit, SerializerFlags, SerializerOptions};
 use crate::value::*;
 
+/// The maximum number of elements to preallocate for length
+/// prefixes read from untrusted data.
+pub const PREALLOC_LIMIT: usize = 1024;
+
+/// Alignment of bit counts to whole bytes.
+pub const BYTE: Alignment = Alignment::new(u8::BITS as u64);
+
 #[inline]
 pub const fn bits_to_bytes(bits: usize) -> usize {
-    align_up(bits, u8::BITS as _) >> 3
+    // Partial bytes count as a whole one.
+    bits.div_ceil(u8::BITS as usize)
 }
 
 #[inline]
@@ -78,6 +86,21 @@ pub fn read_container_length(reader: &mut BitReader<'_>, compact: bool) -> Resul
     Ok(len)
 }
 
+/// Rejects `len` elements of at least `bits` each when they cannot
+/// fit into the remaining data, before memory is reserved for them.
+#[inline]
+pub fn check_remaining(reader: &BitReader<'_>, len: usize, bits: usize) -> Result<(), Error> {
+    let available = reader.remaining_bits();
+    match len.checked_mul(bits) {
+        Some(requested) if requested <= available => Ok(()),
+        requested => Err(ReadError::UnexpectedEof {
+            requested: requested.unwrap_or(usize::MAX),
+            available,
+        }
+        .into()),
+    }
+}
+
 #[inline]
 pub fn read_string<'a>(
     reader: &mut BitReader<'a>,
@@ -88,6 +111,10 @@ pub fn read_string<'a>(
         opts.flags
             .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
     )?;
+    if len > opts.limits.max_string_len {
+        return Err(Error::limit(Limit::StringLength));
+    }
+    check_remaining(reader, len, u8::BITS as usize)?;
 
     if len != 0 {
         reader.realign_to_byte();
@@ -107,8 +134,12 @@ pub fn read_wstring(
         opts.flags
             .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
     )?;
+    if len > opts.limits.max_string_len {
+        return Err(Error::limit(Limit::StringLength));
+    }
+    check_remaining(reader, len, u16::BITS as usize)?;
 
-    let mut out = Vec::with_capacity(len);
+    let mut out = Vec::with_capacity(len.min(PREALLOC_LIMIT));
     if len != 0 {
         reader.realign_to_byte();
         for _ in 0..len {
@@ -119,6 +150,29 @@ pub fn read_wstring(
     Ok(out)
 }
 
+/// Reads a wide string which ends at a null character instead of
+/// being prefixed with its length, as used by `wchar_t*` properties.
+#[inline]
+pub fn read_null_terminated_wstring(
+    reader: &mut BitReader<'_>,
+    opts: &SerializerOptions,
+) -> Result<Vec<u16>, Error> {
+    reader.realign_to_byte();
+
+    let mut out = Vec::new();
+    loop {
+        let c = read_bits(reader, u16::BITS)? as u16;
+        if c == 0 {
+            break Ok(out);
+        }
+
+        if out.len() == opts.limits.max_string_len {
+            break Err(Error::limit(Limit::StringLength));
+        }
+        out.push(c);
+    }
+}
+
 #[inline]
 pub fn read_color(reader: &mut BitReader<'_>) -> Result<Color, Error> {
     if reader.buffered_bits() < u32::BITS {
@@ -139,6 +193,7 @@ pub fn read_color(reader: &mut BitReader<'_>) -> Result<Color, Error> {
 
 #[inline]
 pub fn read_vec3(reader: &mut BitReader<'_>) -> Result<Vec3, Error> {
+    reader.realign_to_byte();
     let mut data = reader.read_bytes(12)?;
 
     let x = data.read_f32::<LittleEndian>()?;
@@ -150,6 +205,7 @@ pub fn read_vec3(reader: &mut BitReader<'_>) -> Result<Vec3, Error> {
 
 #[inline]
 pub fn read_quat(reader: &mut BitReader<'_>) -> Result<Quaternion, Error> {
+    reader.realign_to_byte();
     let mut data = reader.read_bytes(16)?;
 
     let x = data.read_f32::<LittleEndian>()?;
@@ -162,6 +218,7 @@ pub fn read_quat(reader: &mut BitReader<'_>) -> Result<Quaternion, Error> {
 
 #[inline]
 pub fn read_euler(reader: &mut BitReader<'_>) -> Result<Euler, Error> {
+    reader.realign_to_byte();
     let mut data = reader.read_bytes(12)?;
 
     // TODO: Is this order correct?
@@ -174,6 +231,7 @@ pub fn read_euler(reader: &mut BitReader<'_>) -> Result<Euler, Error> {
 
 #[inline]
 pub fn read_matrix(reader: &mut BitReader<'_>) -> Result<Matrix, Error> {
+    reader.realign_to_byte();
     let mut data = reader.read_bytes(36)?;
 
     let i = [
@@ -194,3 +252,147 @@ pub fn read_matrix(reader: &mut BitReader<'_>) -> Result<Matrix, Error> {
 
     Ok(Matrix { i, j, k })
 }
+
+#[inline]
+pub fn write_bits(writer: &mut BitWriter, value: u64, nbits: u32) {
+    if writer.remaining() < nbits {
+        writer.commit();
+    }
+
+    // After a commit, there is always room for at least 56 bits.
+    writer.offer(value, nbits).unwrap();
+}
+
+#[inline]
+pub fn write_bytes(writer: &mut BitWriter, data: &[u8]) {
+    writer.realign_to_byte();
+    writer.write_bytes(data);
+}
+
+#[inline]
+pub fn write_u64(writer: &mut BitWriter, value: u64) {
+    write_bytes(writer, &value.to_le_bytes());
+}
+
+#[inline]
+pub fn write_bool(writer: &mut BitWriter, value: bool) {
+    write_bits(writer, value as u64, 1);
+}
+
+#[inline]
+pub fn write_compact_length(writer: &mut BitWriter, len: usize) -> Result<(), Error> {
+    if len >> (u32::BITS - 1) != 0 {
+        return Err(Error::LengthOverflow(len));
+    }
+
+    let is_large = len >> (u8::BITS - 1) != 0;
+    write_bool(writer, is_large);
+    match is_large {
+        true => write_bits(writer, len as u64, u32::BITS - 1),
+        false => write_bits(writer, len as u64, u8::BITS - 1),
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn write_string_length(writer: &mut BitWriter, len: usize, compact: bool) -> Result<(), Error> {
+    match compact {
+        true => write_compact_length(writer, len),
+        false => {
+            let len16 = u16::try_from(len).map_err(|_| Error::LengthOverflow(len))?;
+            writer.realign_to_byte();
+            write_bits(writer, len16 as u64, u16::BITS);
+            Ok(())
+        }
+    }
+}
+
+#[inline]
+pub fn write_container_length(
+    writer: &mut BitWriter,
+    len: usize,
+    compact: bool,
+) -> Result<(), Error> {
+    match compact {
+        true => write_compact_length(writer, len),
+        false => {
+            let len32 = u32::try_from(len).map_err(|_| Error::LengthOverflow(len))?;
+            writer.realign_to_byte();
+            write_bits(writer, len32 as u64, u32::BITS);
+            Ok(())
+        }
+    }
+}
+
+#[inline]
+pub fn write_string(
+    writer: &mut BitWriter,
+    value: &[u8],
+    opts: &SerializerOptions,
+) -> Result<(), Error> {
+    write_string_length(
+        writer,
+        value.len(),
+        opts.flags
+            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+    )?;
+
+    if !value.is_empty() {
+        write_bytes(writer, value);
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn write_wstring(
+    writer: &mut BitWriter,
+    value: &[u16],
+    opts: &SerializerOptions,
+) -> Result<(), Error> {
+    write_string_length(
+        writer,
+        value.len(),
+        opts.flags
+            .contains(SerializerFlags::COMPACT_LENGTH_PREFIXES),
+    )?;
+
+    if !value.is_empty() {
+        writer.realign_to_byte();
+        for &c in value {
+            write_bits(writer, c as u64, u16::BITS);
+        }
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn write_null_terminated_wstring(writer: &mut BitWriter, value: &[u16]) -> Result<(), Error> {
+    if value.contains(&0) {
+        return Err(Error::InteriorNull);
+    }
+
+    writer.realign_to_byte();
+    for &c in value.iter().chain(&[0]) {
+        write_bits(writer, c as u64, u16::BITS);
+    }
+
+    Ok(())
+}
+
+#[inline]
+pub fn write_color(writer: &mut BitWriter, value: &Color) {
+    for c in [value.r, value.g, value.b, value.a] {
+        write_bits(writer, c as u64, u8::BITS);
+    }
+}
+
+#[inline]
+pub fn write_f32s(writer: &mut BitWriter, values: impl IntoIterator<Item = f32>) {
+    writer.realign_to_byte();
+    for v in values {
+        writer.write_bytes(&v.to_le_bytes());
+    }
+}
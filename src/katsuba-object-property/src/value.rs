@@ -3,13 +3,39 @@
 //! Values have dynamic types and can be composed, at the cost of
 //! incurring memory and performance overhead.
 
+use std::sync::Arc;
+
 pub use smartstring::alias::String;
 
+mod access;
+pub use access::*;
+
+#[cfg(feature = "arena")]
+mod arena;
+#[cfg(feature = "arena")]
+pub use arena::*;
+
+#[cfg(feature = "de")]
+mod behaviors;
+#[cfg(feature = "de")]
+pub use behaviors::*;
+
+mod blob;
+pub use blob::*;
+
 mod color;
 pub use color::*;
 
 mod drop;
 
+mod gid;
+pub use gid::*;
+
+mod intern;
+
+mod json;
+pub use json::*;
+
 mod math;
 pub use math::*;
 
@@ -19,28 +45,55 @@ pub use list::*;
 mod object;
 pub use object::*;
 
+mod objects;
+pub use objects::*;
+
+mod path;
+pub use path::*;
+
 mod strings;
 pub use strings::*;
 
+mod time;
+pub use time::*;
+
 // TODO: Evaluate optimizations.
 
 /// A runtime value from the ObjectProperty system.
 ///
 /// Its type is dynamically assigned at runtime, which mandates
 /// appropriate checks for interpreting its contents.
+///
+/// Values compare equal by content, regardless of whether they are
+/// [`Value::Shared`].
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[cfg_attr(feature = "serde", serde(untagged))]
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum Value {
     /// An empty unit value.
     Empty,
+    /// A delta-encoded property which was not transmitted.
+    ///
+    /// Unlike [`Value::Empty`], this means there is no value at all.
+    /// Objects leave such properties out when encoded with serde.
+    Unset,
 
     /// A unsigned integer value.
     Unsigned(u64),
     /// A signed integer value.
     Signed(i64),
-    /// A floating-point value.
+    /// A 64-bit global ID from a `gid` property.
+    ///
+    /// Encoded with serde as a hex string, see [`gid_to_hex`].
+    #[cfg_attr(feature = "serde", serde(serialize_with = "gid::serialize_gid"))]
+    Gid(u64),
+    /// A double-precision floating-point value.
     Float(f64),
+    /// A single-precision floating-point value.
+    ///
+    /// This keeps `float` properties apart from `double` ones, so
+    /// they are formatted and serialized at their original width.
+    Float32(f32),
     /// A boolean value.
     Bool(bool),
 
@@ -48,12 +101,21 @@ pub enum Value {
     String(CxxStr),
     /// A wide string of code points, not null-terminated.
     WString(CxxWStr),
+    /// A serialized object which was decoded from a string.
+    Blob(Box<Blob>),
 
     /// An enum variant or bitflags.
     Enum(i64),
+    /// A timestamp from a `time_t` property.
+    Time(Time),
 
     /// A homogenous list of elements.
     List(List),
+    /// An associative container of key-value pairs, in the order
+    /// they were serialized in.
+    Map(Vec<(Value, Value)>),
+    /// A pair of two values of possibly different types.
+    Pair(Box<(Value, Value)>),
     /// An object which maps field names to values.
     Object {
         #[cfg_attr(feature = "serde", serde(rename = "$__type"))]
@@ -82,4 +144,10 @@ pub enum Value {
     RectInt(Rect<i32>),
     /// A rectangle described by floating-point edges.
     RectFloat(Rect<f32>),
+
+    /// A value which may be shared with other parts of the tree.
+    ///
+    /// These are produced by [`Value::intern`] and behave like the
+    /// value they hold.
+    Shared(Arc<Value>),
 }
@@ -0,0 +1,366 @@
+use std::{borrow::Cow, mem};
+
+use katsuba_utils::thiserror::{self, Error};
+
+use super::*;
+
+/// Errors that may occur when accessing a [`Value`] by [`Path`].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PathError {
+    /// No value exists at the given path.
+    #[error("no value at {0}")]
+    Missing(Path),
+
+    /// A segment of the path cannot be applied to the value at
+    /// that location, e.g. indexing into an object.
+    #[error("cannot apply {segment} to {found} at {path}")]
+    NotAContainer {
+        path: Path,
+        segment: PathSegment,
+        found: &'static str,
+    },
+
+    /// A typed setter found a different variant than it writes.
+    #[error("expected {expected} at {path}, found {found}")]
+    VariantMismatch {
+        path: Path,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl Value {
+    /// Gets the name of the variant stored in this value.
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::Unset => "Unset",
+            Self::Unsigned(..) => "Unsigned",
+            Self::Signed(..) => "Signed",
+            Self::Gid(..) => "Gid",
+            Self::Float(..) => "Float",
+            Self::Float32(..) => "Float32",
+            Self::Bool(..) => "Bool",
+            Self::String(..) => "String",
+            Self::WString(..) => "WString",
+            Self::Blob(..) => "Blob",
+            Self::Enum(..) => "Enum",
+            Self::Time(..) => "Time",
+            Self::List(..) => "List",
+            Self::Map(..) => "Map",
+            Self::Pair(..) => "Pair",
+            Self::Object { .. } => "Object",
+            Self::Color(..) => "Color",
+            Self::Vec3(..) => "Vec3",
+            Self::Quat(..) => "Quat",
+            Self::Euler(..) => "Euler",
+            Self::Mat3x3(..) => "Mat3x3",
+            Self::PointInt(..) => "PointInt",
+            Self::PointFloat(..) => "PointFloat",
+            Self::SizeInt(..) => "SizeInt",
+            Self::RectInt(..) => "RectInt",
+            Self::RectFloat(..) => "RectFloat",
+            Self::Shared(v) => v.variant_name(),
+        }
+    }
+
+    /// Gets a reference to the value at `path`, if one exists.
+    pub fn get_path(&self, path: &Path) -> Option<&Value> {
+        self.get_segments(path.segments())
+    }
+
+    fn get_segments(&self, segments: &[PathSegment]) -> Option<&Value> {
+        segments
+            .iter()
+            .try_fold(self, |value, segment| match (value.resolve(), segment) {
+                (Self::Object { obj, .. }, PathSegment::Property(name)) => obj.get(name),
+                (Self::List(list), PathSegment::Index(idx)) => list.get(*idx),
+                (Self::Blob(blob), PathSegment::Nested) => Some(&blob.value),
+                (Self::Map(entries), PathSegment::Key(idx)) => entries.get(*idx).map(|(k, _)| k),
+                (Self::Map(entries), PathSegment::Value(idx)) => entries.get(*idx).map(|(_, v)| v),
+                (Self::Pair(pair), PathSegment::First) => Some(&pair.0),
+                (Self::Pair(pair), PathSegment::Second) => Some(&pair.1),
+                _ => None,
+            })
+    }
+
+    /// Gets a mutable reference to the value at `path`, if one exists.
+    ///
+    /// Shared values along the way are unshared, see
+    /// [`Value::resolve_mut`].
+    pub fn get_path_mut(&mut self, path: &Path) -> Option<&mut Value> {
+        path.segments().iter().try_fold(self, |value, segment| {
+            match (value.resolve_mut(), segment) {
+                (Self::Object { obj, .. }, PathSegment::Property(name)) => obj.get_mut(name),
+                (Self::List(list), PathSegment::Index(idx)) => list.get_mut(*idx),
+                (Self::Blob(blob), PathSegment::Nested) => Some(&mut blob.value),
+                (Self::Map(entries), PathSegment::Key(idx)) => {
+                    entries.get_mut(*idx).map(|(k, _)| k)
+                }
+                (Self::Map(entries), PathSegment::Value(idx)) => {
+                    entries.get_mut(*idx).map(|(_, v)| v)
+                }
+                (Self::Pair(pair), PathSegment::First) => Some(&mut pair.0),
+                (Self::Pair(pair), PathSegment::Second) => Some(&mut pair.1),
+                _ => None,
+            }
+        })
+    }
+
+    /// Stores `new` at `path` and returns the value it replaced.
+    ///
+    /// All but the last segment of `path` must refer to existing
+    /// values. A missing property in the last segment is inserted
+    /// into its object, whereas list and map indices must be in
+    /// bounds.
+    ///
+    /// Objects are not checked against their type. When a new
+    /// property is inserted, it must be declared by the object's
+    /// class for the result to be serializable.
+    pub fn set_path(&mut self, path: &Path, new: Value) -> Result<Option<Value>, PathError> {
+        let Some((last, parents)) = path.segments().split_last() else {
+            return Ok(Some(mem::replace(self, new)));
+        };
+
+        let parent_path = Path::from(parents.to_vec());
+        let parent = self
+            .get_path_mut(&parent_path)
+            .ok_or_else(|| PathError::Missing(parent_path.clone()))?;
+
+        match (parent.resolve_mut(), last) {
+            (Self::Object { obj, .. }, PathSegment::Property(name)) => {
+                Ok(obj.insert(name.clone(), new))
+            }
+            (Self::List(list), PathSegment::Index(idx)) => match list.get_mut(*idx) {
+                Some(slot) => Ok(Some(mem::replace(slot, new))),
+                None => Err(PathError::Missing(path.clone())),
+            },
+            (Self::Blob(blob), PathSegment::Nested) => Ok(Some(mem::replace(&mut blob.value, new))),
+            (Self::Map(entries), PathSegment::Key(idx)) => match entries.get_mut(*idx) {
+                Some((key, _)) => Ok(Some(mem::replace(key, new))),
+                None => Err(PathError::Missing(path.clone())),
+            },
+            (Self::Map(entries), PathSegment::Value(idx)) => match entries.get_mut(*idx) {
+                Some((_, value)) => Ok(Some(mem::replace(value, new))),
+                None => Err(PathError::Missing(path.clone())),
+            },
+            (Self::Pair(pair), PathSegment::First) => Ok(Some(mem::replace(&mut pair.0, new))),
+            (Self::Pair(pair), PathSegment::Second) => Ok(Some(mem::replace(&mut pair.1, new))),
+            (parent, segment) => Err(PathError::NotAContainer {
+                path: parent_path,
+                segment: segment.clone(),
+                found: parent.variant_name(),
+            }),
+        }
+    }
+
+    /// Visits this value and all its children with `f`.
+    ///
+    /// Values are visited depth-first in pre-order: a value is
+    /// passed to `f` before its children. Object properties are
+    /// visited in name order, list elements and map entries in
+    /// index order, with each key before its value.
+    ///
+    /// Children are discovered after `f` returns, so values it
+    /// stores are visited in turn. Shared values with children are
+    /// unshared to visit them, see [`Value::resolve_mut`].
+    pub fn visit_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&Path, &mut Value),
+    {
+        // Nesting depth in the path, segment leading to the value,
+        // and the value itself. The stack avoids overflows with
+        // deeply nested values.
+        let mut stack = vec![(0, None, self)];
+        let mut path = Path::new();
+
+        while let Some((depth, segment, value)) = stack.pop() {
+            path.truncate(depth);
+            if let Some(segment) = segment {
+                path.push(segment);
+            }
+
+            f(&path, value);
+
+            let depth = path.depth();
+            match value.resolve_mut() {
+                Self::Object { obj, .. } => {
+                    for (name, child) in obj.iter_mut().rev() {
+                        stack.push((depth, Some(PathSegment::Property(name.clone())), child));
+                    }
+                }
+                Self::List(list) => {
+                    for (idx, child) in list.iter_mut().enumerate().rev() {
+                        stack.push((depth, Some(PathSegment::Index(idx)), child));
+                    }
+                }
+                Self::Blob(blob) => {
+                    stack.push((depth, Some(PathSegment::Nested), &mut blob.value));
+                }
+                Self::Map(entries) => {
+                    for (idx, (key, value)) in entries.iter_mut().enumerate().rev() {
+                        stack.push((depth, Some(PathSegment::Value(idx)), value));
+                        stack.push((depth, Some(PathSegment::Key(idx)), key));
+                    }
+                }
+                Self::Pair(pair) => {
+                    let (first, second) = &mut **pair;
+                    stack.push((depth, Some(PathSegment::Second), second));
+                    stack.push((depth, Some(PathSegment::First), first));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Iterates over all strings in this value and its children,
+    /// along with their paths.
+    ///
+    /// Both [`Value::String`]s and [`Value::WString`]s are included,
+    /// with invalid code units replaced. Enum variants are strings
+    /// when deserialized with human-readable enums, so they are
+    /// included as well.
+    ///
+    /// Values are visited in the same order as with
+    /// [`Value::visit_mut`].
+    pub fn strings(&self) -> Strings<'_> {
+        Strings {
+            stack: vec![(0, None, self)],
+            path: Path::new(),
+        }
+    }
+
+    fn set_primitive(&mut self, path: &Path, new: Value) -> Result<(), PathError> {
+        let slot = self
+            .get_path_mut(path)
+            .ok_or_else(|| PathError::Missing(path.clone()))?;
+
+        if mem::discriminant(slot) != mem::discriminant(&new) {
+            return Err(PathError::VariantMismatch {
+                path: path.clone(),
+                expected: new.variant_name(),
+                found: slot.variant_name(),
+            });
+        }
+
+        *slot = new;
+        Ok(())
+    }
+
+    /// Replaces the [`Value::Unsigned`] at `path`.
+    pub fn set_unsigned(&mut self, path: &Path, v: u64) -> Result<(), PathError> {
+        self.set_primitive(path, Self::Unsigned(v))
+    }
+
+    /// Replaces the [`Value::Signed`] at `path`.
+    pub fn set_signed(&mut self, path: &Path, v: i64) -> Result<(), PathError> {
+        self.set_primitive(path, Self::Signed(v))
+    }
+
+    /// Replaces the [`Value::Float`] or [`Value::Float32`] at `path`.
+    ///
+    /// Single-precision values keep their width, so `v` is rounded.
+    pub fn set_float(&mut self, path: &Path, v: f64) -> Result<(), PathError> {
+        match self.get_path(path).map(Value::resolve) {
+            Some(Self::Float32(..)) => self.set_primitive(path, Self::Float32(v as f32)),
+            _ => self.set_primitive(path, Self::Float(v)),
+        }
+    }
+
+    /// Replaces the [`Value::Bool`] at `path`.
+    pub fn set_bool(&mut self, path: &Path, v: bool) -> Result<(), PathError> {
+        self.set_primitive(path, Self::Bool(v))
+    }
+
+    /// Replaces the [`Value::Enum`] at `path`.
+    pub fn set_enum(&mut self, path: &Path, v: i64) -> Result<(), PathError> {
+        self.set_primitive(path, Self::Enum(v))
+    }
+
+    /// Replaces the [`Value::String`] at `path`.
+    pub fn set_string(&mut self, path: &Path, v: impl Into<Vec<u8>>) -> Result<(), PathError> {
+        self.set_primitive(path, Self::String(CxxStr(v.into())))
+    }
+
+    /// Replaces the [`Value::WString`] at `path` with the UTF-16
+    /// encoding of `v`.
+    pub fn set_wstring(&mut self, path: &Path, v: &str) -> Result<(), PathError> {
+        self.set_primitive(path, Self::WString(CxxWStr(v.encode_utf16().collect())))
+    }
+}
+
+impl Object {
+    /// Gets a reference to the value at `path` relative to this
+    /// object, if one exists.
+    ///
+    /// The root path refers to the object itself, which is not a
+    /// [`Value`], so it never has one.
+    pub fn get_path(&self, path: &Path) -> Option<&Value> {
+        match path.segments().split_first()? {
+            (PathSegment::Property(name), rest) => self.get(name)?.get_segments(rest),
+            _ => None,
+        }
+    }
+}
+
+/// An iterator over the strings in a [`Value`], created by
+/// [`Value::strings`].
+pub struct Strings<'a> {
+    stack: Vec<(usize, Option<PathSegment>, &'a Value)>,
+    path: Path,
+}
+
+impl<'a> Iterator for Strings<'a> {
+    type Item = (Path, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((depth, segment, value)) = self.stack.pop() {
+            self.path.truncate(depth);
+            if let Some(segment) = segment {
+                self.path.push(segment);
+            }
+
+            let depth = self.path.depth();
+            match value.resolve() {
+                Value::String(s) => {
+                    return Some((
+                        self.path.clone(),
+                        std::string::String::from_utf8_lossy(&s.0),
+                    ));
+                }
+                Value::WString(s) => return Some((self.path.clone(), Cow::Owned(s.to_string()))),
+
+                Value::Object { obj, .. } => {
+                    for (name, child) in obj.iter().rev() {
+                        let segment = PathSegment::Property(name.clone());
+                        self.stack.push((depth, Some(segment), child));
+                    }
+                }
+                Value::List(list) => {
+                    for (idx, child) in list.iter().enumerate().rev() {
+                        self.stack
+                            .push((depth, Some(PathSegment::Index(idx)), child));
+                    }
+                }
+                Value::Blob(blob) => {
+                    self.stack
+                        .push((depth, Some(PathSegment::Nested), &blob.value));
+                }
+                Value::Map(entries) => {
+                    for (idx, (key, value)) in entries.iter().enumerate().rev() {
+                        self.stack
+                            .push((depth, Some(PathSegment::Value(idx)), value));
+                        self.stack.push((depth, Some(PathSegment::Key(idx)), key));
+                    }
+                }
+                Value::Pair(pair) => {
+                    self.stack.push((depth, Some(PathSegment::Second), &pair.1));
+                    self.stack.push((depth, Some(PathSegment::First), &pair.0));
+                }
+                _ => (),
+            }
+        }
+
+        None
+    }
+}
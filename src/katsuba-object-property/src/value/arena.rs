@@ -0,0 +1,274 @@
+use bumpalo::Bump;
+
+use super::*;
+
+/// A memory arena which backs [`ArenaValue`] trees.
+///
+/// All parts of a value tree are allocated from the arena and freed
+/// at once when it is dropped or [reset](Arena::reset). This avoids
+/// the cost of many small allocations for trees which are only
+/// inspected and then discarded.
+#[derive(Debug, Default)]
+pub struct Arena {
+    bump: Bump,
+}
+
+impl Arena {
+    /// Creates a new, empty arena.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an arena with room for `capacity` bytes before it
+    /// needs to allocate again.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bump: Bump::with_capacity(capacity),
+        }
+    }
+
+    /// Frees all values allocated from the arena, keeping its
+    /// largest chunk of memory for reuse.
+    pub fn reset(&mut self) {
+        self.bump.reset();
+    }
+
+    /// Gets the number of bytes currently allocated by the arena.
+    pub fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
+    }
+
+    /// Copies an owned `value` into the arena.
+    pub fn alloc_value<'a>(&'a self, value: &Value) -> ArenaValue<'a> {
+        match value.resolve() {
+            Value::Empty => ArenaValue::Empty,
+            Value::Unset => ArenaValue::Unset,
+            Value::Unsigned(v) => ArenaValue::Unsigned(*v),
+            Value::Signed(v) => ArenaValue::Signed(*v),
+            Value::Gid(v) => ArenaValue::Gid(*v),
+            Value::Float(v) => ArenaValue::Float(*v),
+            Value::Float32(v) => ArenaValue::Float32(*v),
+            Value::Bool(v) => ArenaValue::Bool(*v),
+            Value::String(v) => ArenaValue::String(self.bump.alloc_slice_copy(&v.0)),
+            Value::WString(v) => ArenaValue::WString(self.bump.alloc_slice_copy(&v.0)),
+            Value::Blob(blob) => ArenaValue::Blob {
+                raw: self.bump.alloc_slice_copy(&blob.raw.0),
+                value: self.bump.alloc(self.alloc_value(&blob.value)),
+            },
+            Value::Enum(v) => ArenaValue::Enum(*v),
+            Value::Time(v) => ArenaValue::Time(*v),
+            Value::List(list) => ArenaValue::List(
+                self.bump
+                    .alloc_slice_fill_iter(list.iter().map(|v| self.alloc_value(v))),
+            ),
+            Value::Map(map) => ArenaValue::Map(
+                self.bump.alloc_slice_fill_iter(
+                    map.iter()
+                        .map(|(k, v)| (self.alloc_value(k), self.alloc_value(v))),
+                ),
+            ),
+            Value::Pair(pair) => ArenaValue::Pair(
+                self.bump
+                    .alloc((self.alloc_value(&pair.0), self.alloc_value(&pair.1))),
+            ),
+            Value::Object { hash, obj } => ArenaValue::Object {
+                hash: *hash,
+                obj: ArenaObject {
+                    properties: self.bump.alloc_slice_fill_iter(
+                        obj.iter()
+                            .map(|(k, v)| (self.alloc_str(k), self.alloc_value(v))),
+                    ),
+                },
+            },
+            Value::Color(v) => ArenaValue::Color(*v),
+            Value::Vec3(v) => ArenaValue::Vec3(*v),
+            Value::Quat(v) => ArenaValue::Quat(*v),
+            Value::Euler(v) => ArenaValue::Euler(*v),
+            Value::Mat3x3(v) => ArenaValue::Mat3x3(self.bump.alloc(**v)),
+            Value::PointInt(v) => ArenaValue::PointInt(*v),
+            Value::PointFloat(v) => ArenaValue::PointFloat(*v),
+            Value::SizeInt(v) => ArenaValue::SizeInt(*v),
+            Value::RectInt(v) => ArenaValue::RectInt(*v),
+            Value::RectFloat(v) => ArenaValue::RectFloat(*v),
+            Value::Shared(_) => unreachable!("resolved above"),
+        }
+    }
+
+    pub(crate) fn alloc_str(&self, s: &str) -> &str {
+        self.bump.alloc_str(s)
+    }
+
+    #[cfg(feature = "de")]
+    pub(crate) fn alloc_bytes(&self, bytes: &[u8]) -> &[u8] {
+        self.bump.alloc_slice_copy(bytes)
+    }
+
+    #[cfg(feature = "de")]
+    pub(crate) fn bump(&self) -> &Bump {
+        &self.bump
+    }
+}
+
+/// A runtime value from the ObjectProperty system which lives in
+/// an [`Arena`].
+///
+/// This mirrors [`Value`], but borrows all of its contents from the
+/// arena. Values are cheap to copy and never need to be dropped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaValue<'a> {
+    /// An empty unit value.
+    Empty,
+    /// A delta-encoded property which was not transmitted.
+    Unset,
+
+    /// A unsigned integer value.
+    Unsigned(u64),
+    /// A signed integer value.
+    Signed(i64),
+    /// A 64-bit global ID from a `gid` property.
+    Gid(u64),
+    /// A double-precision floating-point value.
+    Float(f64),
+    /// A single-precision floating-point value.
+    Float32(f32),
+    /// A boolean value.
+    Bool(bool),
+
+    /// A string of bytes, not null-terminated.
+    String(&'a [u8]),
+    /// A wide string of code points, not null-terminated.
+    WString(&'a [u16]),
+    /// A serialized object which was decoded from a string.
+    Blob {
+        /// The serialized bytes, exactly as they were read.
+        raw: &'a [u8],
+        /// The object decoded from `raw`.
+        value: &'a ArenaValue<'a>,
+    },
+
+    /// An enum variant or bitflags.
+    Enum(i64),
+    /// A timestamp from a `time_t` property.
+    Time(Time),
+
+    /// A homogenous list of elements.
+    List(&'a [ArenaValue<'a>]),
+    /// An associative container of key-value pairs, in the order
+    /// they were serialized in.
+    Map(&'a [(ArenaValue<'a>, ArenaValue<'a>)]),
+    /// A pair of two values of possibly different types.
+    Pair(&'a (ArenaValue<'a>, ArenaValue<'a>)),
+    /// An object which maps field names to values.
+    Object {
+        hash: u32,
+        obj: ArenaObject<'a>,
+    },
+
+    /// Representation of an RGBA color.
+    Color(Color),
+    Vec3(Vec3),
+    Quat(Quaternion),
+    Euler(Euler),
+    Mat3x3(&'a Matrix),
+
+    /// A 2D point with integer coordinates.
+    PointInt(Point<i32>),
+    /// A 2D point with floating-point coordinates.
+    PointFloat(Point<f32>),
+
+    /// A size description with integer measures.
+    SizeInt(Size<i32>),
+
+    /// A rectangle described by integer edges.
+    RectInt(Rect<i32>),
+    /// A rectangle described by floating-point edges.
+    RectFloat(Rect<f32>),
+}
+
+impl ArenaValue<'_> {
+    /// Copies this value out of the arena into an owned [`Value`].
+    pub fn to_value(&self) -> Value {
+        match *self {
+            Self::Empty => Value::Empty,
+            Self::Unset => Value::Unset,
+            Self::Unsigned(v) => Value::Unsigned(v),
+            Self::Signed(v) => Value::Signed(v),
+            Self::Gid(v) => Value::Gid(v),
+            Self::Float(v) => Value::Float(v),
+            Self::Float32(v) => Value::Float32(v),
+            Self::Bool(v) => Value::Bool(v),
+            Self::String(v) => Value::String(CxxStr(v.to_vec())),
+            Self::WString(v) => Value::WString(CxxWStr(v.to_vec())),
+            Self::Blob { raw, value } => Value::Blob(Box::new(Blob {
+                raw: CxxStr(raw.to_vec()),
+                value: value.to_value(),
+            })),
+            Self::Enum(v) => Value::Enum(v),
+            Self::Time(v) => Value::Time(v),
+            Self::List(list) => Value::List(List {
+                inner: list.iter().map(ArenaValue::to_value).collect(),
+            }),
+            Self::Map(map) => Value::Map(
+                map.iter()
+                    .map(|(k, v)| (k.to_value(), v.to_value()))
+                    .collect(),
+            ),
+            Self::Pair((first, second)) => {
+                Value::Pair(Box::new((first.to_value(), second.to_value())))
+            }
+            Self::Object { hash, obj } => Value::Object {
+                hash,
+                obj: Object {
+                    inner: obj.iter().map(|(k, v)| (k.into(), v.to_value())).collect(),
+                },
+            },
+            Self::Color(v) => Value::Color(v),
+            Self::Vec3(v) => Value::Vec3(v),
+            Self::Quat(v) => Value::Quat(v),
+            Self::Euler(v) => Value::Euler(v),
+            Self::Mat3x3(v) => Value::Mat3x3(Box::new(*v)),
+            Self::PointInt(v) => Value::PointInt(v),
+            Self::PointFloat(v) => Value::PointFloat(v),
+            Self::SizeInt(v) => Value::SizeInt(v),
+            Self::RectInt(v) => Value::RectInt(v),
+            Self::RectFloat(v) => Value::RectFloat(v),
+        }
+    }
+}
+
+/// Representation of an object which lives in an [`Arena`].
+///
+/// Properties are kept in the order they were deserialized in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArenaObject<'a> {
+    /// The class member names and their values.
+    pub properties: &'a [(&'a str, ArenaValue<'a>)],
+}
+
+impl<'a> ArenaObject<'a> {
+    /// Gets the value of the property `name`.
+    pub fn get(&self, name: &str) -> Option<&'a ArenaValue<'a>> {
+        // Like inserting into an owned object, the last occurrence
+        // of a property wins.
+        self.properties
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v)
+    }
+
+    /// Iterates over the properties of the object.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a ArenaValue<'a>)> {
+        self.properties.iter().map(|(n, v)| (*n, v))
+    }
+
+    /// Gets the number of properties in the object.
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    /// Whether the object has no properties.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+}
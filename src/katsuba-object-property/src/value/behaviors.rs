@@ -0,0 +1,55 @@
+use std::{collections::HashSet, slice};
+
+use katsuba_types::TypeList;
+
+use super::{objects::class_hashes, *};
+
+/// The name of the property holding the behaviors of a template.
+pub const BEHAVIORS: &str = "m_behaviors";
+
+impl Object {
+    /// Gets the first object in the `m_behaviors` list of this object
+    /// whose class is the one called `class` or inherits from it.
+    ///
+    /// See [`Object::behaviors_of`] for details.
+    pub fn behavior(&self, class: &str, types: &TypeList) -> Option<&Object> {
+        self.behaviors_of(class, types).next().map(|(_, obj)| obj)
+    }
+
+    /// Iterates over the objects in the `m_behaviors` list of this
+    /// object whose class is the one called `class` or inherits from
+    /// it, along with their type hashes.
+    ///
+    /// Subclasses are found through the base class information in
+    /// `types`, see [`TypeList::subclasses_of`]. Null entries and
+    /// objects without such a list are skipped.
+    pub fn behaviors_of(&self, class: &str, types: &TypeList) -> Behaviors<'_> {
+        let behaviors = match self.get(BEHAVIORS).map(Value::resolve) {
+            Some(Value::List(list)) => list.iter(),
+            _ => [].iter(),
+        };
+
+        Behaviors {
+            behaviors,
+            classes: class_hashes(class, types),
+        }
+    }
+}
+
+/// An iterator over the behaviors of an [`Object`], created by
+/// [`Object::behaviors_of`].
+pub struct Behaviors<'a> {
+    behaviors: slice::Iter<'a, Value>,
+    classes: HashSet<u32>,
+}
+
+impl<'a> Iterator for Behaviors<'a> {
+    type Item = (u32, &'a Object);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.behaviors.find_map(|value| match value.resolve() {
+            Value::Object { hash, obj } if self.classes.contains(hash) => Some((*hash, obj)),
+            _ => None,
+        })
+    }
+}
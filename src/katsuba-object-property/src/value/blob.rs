@@ -0,0 +1,24 @@
+use super::{CxxStr, Value};
+
+/// An ObjectProperty object which was serialized into a string.
+///
+/// Some types embed whole serialized objects in string properties.
+/// The original bytes are kept alongside the decoded object so it
+/// can be written back faithfully.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Blob {
+    /// The serialized bytes, exactly as they were read.
+    pub raw: CxxStr,
+    /// The object decoded from [`Blob::raw`].
+    pub value: Value,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Blob {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
@@ -7,3 +7,10 @@ pub struct Color {
     pub b: u8,
     pub a: u8,
 }
+
+impl Color {
+    /// Formats the color in `#RRGGBBAA` notation.
+    pub fn to_hex(&self) -> std::string::String {
+        format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
+    }
+}
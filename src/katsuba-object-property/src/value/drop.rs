@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::Value;
 
 /// Safely drops `value` in heap memory.
@@ -5,7 +7,11 @@ use super::Value;
 /// This avoids stack overflows with deeply nested types.
 pub fn safely(value: Value) {
     match value {
-        Value::List(..) | Value::Object { .. } => {}
+        Value::List(..)
+        | Value::Map(..)
+        | Value::Pair(..)
+        | Value::Object { .. }
+        | Value::Shared(..) => {}
         _ => return,
     }
 
@@ -18,11 +24,28 @@ pub fn safely(value: Value) {
                     stack.push(child);
                 }
             }
+            Value::Map(entries) => {
+                for (key, value) in entries {
+                    stack.push(key);
+                    stack.push(value);
+                }
+            }
+            Value::Pair(pair) => {
+                let (first, second) = *pair;
+                stack.push(first);
+                stack.push(second);
+            }
             Value::Object { hash: _, obj } => {
                 for (_, child) in obj {
                     stack.push(child);
                 }
             }
+            // Only the last owner of a shared value drops its children.
+            Value::Shared(shared) => {
+                if let Ok(child) = Arc::try_unwrap(shared) {
+                    stack.push(child);
+                }
+            }
             _ => (),
         }
     }
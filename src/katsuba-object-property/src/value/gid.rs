@@ -0,0 +1,24 @@
+/// Formats a global ID as a zero-padded hex string, such as
+/// `0x0000000100000002`.
+pub fn gid_to_hex(gid: u64) -> String {
+    format!("0x{gid:016X}")
+}
+
+/// Splits a global ID into its block in the high 32 bits and the
+/// ID within the block in the low 32 bits.
+pub fn gid_split(gid: u64) -> (u32, u32) {
+    ((gid >> 32) as u32, gid as u32)
+}
+
+/// Joins a global ID from the parts produced by [`gid_split`].
+pub fn gid_join(block: u32, id: u32) -> u64 {
+    (block as u64) << 32 | id as u64
+}
+
+#[cfg(feature = "serde")]
+pub(super) fn serialize_gid<S>(gid: &u64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&gid_to_hex(*gid))
+}
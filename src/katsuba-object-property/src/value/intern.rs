@@ -0,0 +1,288 @@
+use std::{
+    collections::{btree_map, BTreeMap, HashMap},
+    hash::{BuildHasher, Hash, Hasher, RandomState},
+    mem,
+    sync::Arc,
+    vec,
+};
+
+use super::*;
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Shared(a), Self::Shared(b)) if Arc::ptr_eq(a, b) => true,
+            (Self::Shared(a), b) => **a == *b,
+            (a, Self::Shared(b)) => *a == **b,
+
+            (Self::Empty, Self::Empty) | (Self::Unset, Self::Unset) => true,
+            (Self::Unsigned(a), Self::Unsigned(b)) => a == b,
+            (Self::Signed(a), Self::Signed(b)) => a == b,
+            (Self::Gid(a), Self::Gid(b)) => a == b,
+            (Self::Float(a), Self::Float(b)) => a == b,
+            (Self::Float32(a), Self::Float32(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::WString(a), Self::WString(b)) => a == b,
+            (Self::Blob(a), Self::Blob(b)) => a == b,
+            (Self::Enum(a), Self::Enum(b)) => a == b,
+            (Self::Time(a), Self::Time(b)) => a == b,
+            (Self::List(a), Self::List(b)) => a == b,
+            (Self::Map(a), Self::Map(b)) => a == b,
+            (Self::Pair(a), Self::Pair(b)) => a == b,
+            (Self::Object { hash: ha, obj: a }, Self::Object { hash: hb, obj: b }) => {
+                ha == hb && a == b
+            }
+            (Self::Color(a), Self::Color(b)) => a == b,
+            (Self::Vec3(a), Self::Vec3(b)) => a == b,
+            (Self::Quat(a), Self::Quat(b)) => a == b,
+            (Self::Euler(a), Self::Euler(b)) => a == b,
+            (Self::Mat3x3(a), Self::Mat3x3(b)) => a == b,
+            (Self::PointInt(a), Self::PointInt(b)) => a == b,
+            (Self::PointFloat(a), Self::PointFloat(b)) => a == b,
+            (Self::SizeInt(a), Self::SizeInt(b)) => a == b,
+            (Self::RectInt(a), Self::RectInt(b)) => a == b,
+            (Self::RectFloat(a), Self::RectFloat(b)) => a == b,
+
+            _ => false,
+        }
+    }
+}
+
+impl Value {
+    /// Gets the value behind any [`Value::Shared`] indirection.
+    #[inline]
+    pub fn resolve(&self) -> &Value {
+        match self {
+            Self::Shared(v) => v,
+            v => v,
+        }
+    }
+
+    /// Gets mutable access to the value behind any [`Value::Shared`]
+    /// indirection.
+    ///
+    /// A shared value is cloned first if it has other owners, so
+    /// changes do not affect the rest of the tree. Its children
+    /// remain shared.
+    #[inline]
+    pub fn resolve_mut(&mut self) -> &mut Value {
+        match self {
+            Self::Shared(v) => Arc::make_mut(v),
+            v => v,
+        }
+    }
+
+    /// Deduplicates identical subtrees of this value.
+    ///
+    /// Every object and non-empty list below the root is replaced
+    /// by a [`Value::Shared`] which holds the same [`Arc`] for all
+    /// of its duplicates. This saves memory for trees with many
+    /// repeated children.
+    ///
+    /// Blobs, maps and pairs are not looked into.
+    pub fn intern(&mut self) {
+        let mut interner = Interner::default();
+
+        // Nodes which are still missing children, from the root to
+        // the one being filled currently.
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut pending = mem::replace(self, Value::Empty);
+
+        loop {
+            // Descend into `pending` until we reach a leaf.
+            let mut done = match pending {
+                Value::Object { hash, obj } => {
+                    stack.push(Frame::Object {
+                        hash,
+                        done: BTreeMap::new(),
+                        rest: obj.into_iter(),
+                        name: None,
+                    });
+                    None
+                }
+                Value::List(list) if !list.is_empty() => {
+                    stack.push(Frame::List {
+                        done: Vec::with_capacity(list.len()),
+                        rest: list.into_iter(),
+                    });
+                    None
+                }
+                leaf => Some(leaf),
+            };
+
+            // Then ascend until we find the next child to descend into.
+            pending = loop {
+                let Some(frame) = stack.last_mut() else {
+                    // The root itself stays unshared.
+                    *self = done.unwrap();
+                    return;
+                };
+
+                if let Some(child) = done.take() {
+                    frame.accept(child);
+                }
+
+                if let Some(child) = frame.next_child() {
+                    break child;
+                }
+
+                let value = stack.pop().unwrap().finish();
+                done = Some(match stack.is_empty() {
+                    true => value,
+                    false => interner.share(value),
+                });
+            };
+        }
+    }
+}
+
+enum Frame {
+    Object {
+        hash: u32,
+        done: BTreeMap<String, Value>,
+        rest: btree_map::IntoIter<String, Value>,
+        name: Option<String>,
+    },
+    List {
+        done: Vec<Value>,
+        rest: vec::IntoIter<Value>,
+    },
+}
+
+impl Frame {
+    fn next_child(&mut self) -> Option<Value> {
+        match self {
+            Self::Object { rest, name, .. } => rest.next().map(|(k, v)| {
+                *name = Some(k);
+                v
+            }),
+            Self::List { rest, .. } => rest.next(),
+        }
+    }
+
+    fn accept(&mut self, child: Value) {
+        match self {
+            Self::Object { done, name, .. } => {
+                done.insert(name.take().unwrap(), child);
+            }
+            Self::List { done, .. } => done.push(child),
+        }
+    }
+
+    fn finish(self) -> Value {
+        match self {
+            Self::Object { hash, done, .. } => Value::Object {
+                hash,
+                obj: Object { inner: done },
+            },
+            Self::List { done, .. } => Value::List(List { inner: done }),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Interner {
+    state: RandomState,
+    nodes: HashMap<u64, Vec<Arc<Value>>>,
+}
+
+impl Interner {
+    // Shares `value`, whose children were already interned.
+    fn share(&mut self, value: Value) -> Value {
+        // Empty containers don't own any memory worth sharing.
+        if let Value::Object { obj, .. } = &value {
+            if obj.is_empty() {
+                return value;
+            }
+        }
+
+        let mut hasher = self.state.build_hasher();
+        shallow_hash(&value, &mut hasher);
+
+        let bucket = self.nodes.entry(hasher.finish()).or_default();
+        match bucket.iter().find(|node| shallow_eq(node, &value)) {
+            Some(node) => Value::Shared(node.clone()),
+            None => {
+                let node = Arc::new(value);
+                bucket.push(node.clone());
+                Value::Shared(node)
+            }
+        }
+    }
+}
+
+// Since children of interned values are shared exactly when they
+// are equal, they are hashed and compared by identity.
+
+fn shallow_hash<H: Hasher>(value: &Value, state: &mut H) {
+    fn floats<H: Hasher>(state: &mut H, values: &[f32]) {
+        values.iter().for_each(|v| v.to_bits().hash(state));
+    }
+
+    mem::discriminant(value).hash(state);
+    match value {
+        Value::Empty | Value::Unset => (),
+        Value::Unsigned(v) | Value::Gid(v) => v.hash(state),
+        Value::Signed(v) | Value::Enum(v) => v.hash(state),
+        Value::Time(v) => v.hash(state),
+        Value::Float(v) => v.to_bits().hash(state),
+        Value::Float32(v) => v.to_bits().hash(state),
+        Value::Bool(v) => v.hash(state),
+        Value::String(v) => v.0.hash(state),
+        Value::WString(v) => v.0.hash(state),
+        Value::Blob(v) => v.raw.0.hash(state),
+        Value::List(list) => {
+            list.len().hash(state);
+            list.iter().for_each(|v| shallow_hash(v, state));
+        }
+        Value::Map(entries) => {
+            entries.len().hash(state);
+            entries.iter().for_each(|(k, v)| {
+                shallow_hash(k, state);
+                shallow_hash(v, state);
+            });
+        }
+        Value::Pair(pair) => {
+            shallow_hash(&pair.0, state);
+            shallow_hash(&pair.1, state);
+        }
+        Value::Object { hash, obj } => {
+            hash.hash(state);
+            obj.len().hash(state);
+            obj.iter().for_each(|(k, v)| {
+                k.hash(state);
+                shallow_hash(v, state);
+            });
+        }
+        Value::Color(v) => [v.r, v.g, v.b, v.a].hash(state),
+        Value::Vec3(v) => floats(state, &[v.x, v.y, v.z]),
+        Value::Quat(v) => floats(state, &[v.x, v.y, v.z, v.w]),
+        Value::Euler(v) => floats(state, &[v.pitch, v.yaw, v.roll]),
+        Value::Mat3x3(v) => [v.i, v.j, v.k].iter().for_each(|r| floats(state, r)),
+        Value::PointInt(v) => [v.x, v.y].hash(state),
+        Value::PointFloat(v) => floats(state, &[v.x, v.y]),
+        Value::SizeInt(v) => [v.width, v.height].hash(state),
+        Value::RectInt(v) => [v.left, v.top, v.right, v.bottom].hash(state),
+        Value::RectFloat(v) => floats(state, &[v.left, v.top, v.right, v.bottom]),
+        Value::Shared(v) => Arc::as_ptr(v).hash(state),
+    }
+}
+
+fn shallow_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Shared(a), Value::Shared(b)) => Arc::ptr_eq(a, b),
+        (Value::List(a), Value::List(b)) => {
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| shallow_eq(a, b))
+        }
+        (Value::Object { hash: ha, obj: a }, Value::Object { hash: hb, obj: b }) => {
+            ha == hb
+                && a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|((ka, va), (kb, vb))| ka == kb && shallow_eq(va, vb))
+        }
+        (Value::Shared(..), _) | (_, Value::Shared(..)) => false,
+        (a, b) => a == b,
+    }
+}
@@ -0,0 +1,139 @@
+use katsuba_utils::thiserror::{self, Error};
+
+use super::*;
+
+/// How floats without a JSON representation are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Fail with the path of the first NaN or infinite float.
+    Error,
+    /// Write them as `null`, which cannot be serialized back.
+    Null,
+    /// Write them as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    #[default]
+    String,
+}
+
+/// How colors are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorFormat {
+    /// Objects with `r`, `g`, `b` and `a` channels.
+    #[default]
+    Rgba,
+    /// Strings in `#RRGGBBAA` notation.
+    Hex,
+}
+
+/// How global IDs are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GidFormat {
+    /// Zero-padded hex strings like `"0x0000000100000002"`.
+    #[default]
+    Hex,
+    /// Plain integers.
+    Decimal,
+    /// `[block, id]` pairs of the high and low 32 bits.
+    Split,
+}
+
+/// A NaN or infinite float was found with [`NonFinite::Error`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("non-finite float at {path}: {token}")]
+pub struct NonFiniteError {
+    /// The location of the first such float.
+    pub path: Path,
+    /// The string form of the float, e.g. `NaN`.
+    pub token: &'static str,
+}
+
+/// Options for writing values as JSON, where they have no canonical
+/// form.
+///
+/// serde encodes a [`Value`] the same way regardless of these, so
+/// they are applied by rewriting the value before encoding it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JsonFormat {
+    pub nonfinite: NonFinite,
+    pub color: ColorFormat,
+    pub humanize_time: bool,
+    pub gid: GidFormat,
+}
+
+impl JsonFormat {
+    /// Whether [`JsonFormat::apply`] leaves every value as it is.
+    pub fn is_identity(&self) -> bool {
+        // serde_json already writes non-finite floats as `null`.
+        self.nonfinite == NonFinite::Null
+            && self.color == ColorFormat::Rgba
+            && !self.humanize_time
+            && self.gid == GidFormat::Hex
+    }
+
+    /// Rewrites the values in `value` which are affected by the
+    /// options into their output form.
+    pub fn apply(&self, value: &mut Value) -> Result<(), NonFiniteError> {
+        if self.is_identity() {
+            return Ok(());
+        }
+
+        let mut first = None;
+        value.visit_mut(&mut |path, v| {
+            let token = match v {
+                Value::Float(f) => nonfinite_token(*f),
+                Value::Float32(f) => nonfinite_token(*f as f64),
+                Value::Color(c) if self.color == ColorFormat::Hex => {
+                    *v = string(&c.to_hex());
+                    return;
+                }
+                Value::Gid(gid) if self.gid != GidFormat::Hex => {
+                    *v = match self.gid {
+                        GidFormat::Split => {
+                            let (block, id) = gid_split(*gid);
+                            let pair = (Value::Unsigned(block.into()), Value::Unsigned(id.into()));
+                            Value::Pair(Box::new(pair))
+                        }
+                        _ => Value::Unsigned(*gid),
+                    };
+                    return;
+                }
+                Value::Time(t) if self.humanize_time => {
+                    let pair = (Value::Signed(t.raw), string(&t.to_iso8601()));
+                    *v = Value::Pair(Box::new(pair));
+                    return;
+                }
+                _ => None,
+            };
+
+            match (token, self.nonfinite) {
+                (Some(token), NonFinite::String) => *v = string(token),
+                (Some(token), NonFinite::Error) if first.is_none() => {
+                    first = Some(NonFiniteError {
+                        path: path.clone(),
+                        token,
+                    });
+                }
+                _ => (),
+            }
+        });
+
+        match first {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Encodes a non-finite float as its JSON string token.
+fn nonfinite_token(v: f64) -> Option<&'static str> {
+    if v.is_nan() {
+        Some("NaN")
+    } else if v.is_infinite() {
+        Some(if v > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        None
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(CxxStr(s.as_bytes().to_vec()))
+}
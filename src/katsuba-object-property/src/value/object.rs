@@ -10,14 +10,23 @@ use smartstring::alias::String;
 use super::{drop, Value};
 
 /// Representation of an object in the ObjectProperty system.
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
-#[cfg_attr(feature = "serde", serde(transparent))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct Object {
     /// A mapping of class member names to their values.
     pub inner: BTreeMap<String, Value>,
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Object {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Properties which were not transmitted are left out.
+        serializer.collect_map(self.iter().filter(|(_, v)| !matches!(v, Value::Unset)))
+    }
+}
+
 impl Drop for Object {
     fn drop(&mut self) {
         for (_, child) in mem::take(&mut self.inner) {
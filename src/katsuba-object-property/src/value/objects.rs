@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+
+use super::*;
+
+impl Value {
+    /// Iterates over all objects in this value, including itself,
+    /// along with their paths and type hashes.
+    ///
+    /// Objects are visited in the same order as with
+    /// [`Value::visit_mut`], including those in the keys and values
+    /// of maps and in pairs.
+    ///
+    /// The tree is walked with an explicit stack, so arbitrarily
+    /// deep values are fine.
+    pub fn objects(&self) -> Objects<'_> {
+        Objects {
+            stack: vec![(0, None, self)],
+            path: Path::new(),
+            classes: None,
+        }
+    }
+
+    /// Iterates over all objects in this value whose class is the
+    /// one called `name` or inherits from it, like
+    /// [`Value::objects`].
+    ///
+    /// Subclasses are found through the base class information in
+    /// `types`, see [`TypeList::subclasses_of`].
+    ///
+    /// [`TypeList::subclasses_of`]: katsuba_types::TypeList::subclasses_of
+    #[cfg(feature = "de")]
+    pub fn objects_of(&self, name: &str, types: &katsuba_types::TypeList) -> Objects<'_> {
+        Objects {
+            classes: Some(class_hashes(name, types)),
+            ..self.objects()
+        }
+    }
+}
+
+// Gets the hashes of the class called `name` and all its subclasses.
+#[cfg(feature = "de")]
+pub(super) fn class_hashes(name: &str, types: &katsuba_types::TypeList) -> HashSet<u32> {
+    let mut classes: HashSet<_> = types.subclasses_of(name).into_iter().collect();
+    if let Some((hash, _)) = types.find(name) {
+        classes.insert(hash);
+    }
+
+    classes
+}
+
+/// An iterator over the objects in a [`Value`], created by
+/// [`Value::objects`] or [`Value::objects_of`].
+pub struct Objects<'a> {
+    stack: Vec<(usize, Option<PathSegment>, &'a Value)>,
+    path: Path,
+    classes: Option<HashSet<u32>>,
+}
+
+impl<'a> Iterator for Objects<'a> {
+    type Item = (Path, u32, &'a Object);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((depth, segment, value)) = self.stack.pop() {
+            self.path.truncate(depth);
+            if let Some(segment) = segment {
+                self.path.push(segment);
+            }
+
+            let depth = self.path.depth();
+            match value.resolve() {
+                Value::Object { hash, obj } => {
+                    for (name, child) in obj.iter().rev() {
+                        let segment = PathSegment::Property(name.clone());
+                        self.stack.push((depth, Some(segment), child));
+                    }
+
+                    if self.classes.as_ref().is_none_or(|c| c.contains(hash)) {
+                        return Some((self.path.clone(), *hash, obj));
+                    }
+                }
+                Value::List(list) => {
+                    for (idx, child) in list.iter().enumerate().rev() {
+                        self.stack
+                            .push((depth, Some(PathSegment::Index(idx)), child));
+                    }
+                }
+                Value::Blob(blob) => {
+                    self.stack
+                        .push((depth, Some(PathSegment::Nested), &blob.value));
+                }
+                Value::Map(entries) => {
+                    for (idx, (key, value)) in entries.iter().enumerate().rev() {
+                        self.stack
+                            .push((depth, Some(PathSegment::Value(idx)), value));
+                        self.stack.push((depth, Some(PathSegment::Key(idx)), key));
+                    }
+                }
+                Value::Pair(pair) => {
+                    self.stack.push((depth, Some(PathSegment::Second), &pair.1));
+                    self.stack.push((depth, Some(PathSegment::First), &pair.0));
+                }
+                _ => (),
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,313 @@
+use std::{fmt, str::FromStr};
+
+use katsuba_utils::thiserror::{self, Error};
+
+use super::String;
+
+const NESTED: &str = "!nested";
+const KEY: &str = "!key";
+const VALUE: &str = "!value";
+const FIRST: &str = "!first";
+const SECOND: &str = "!second";
+
+// Characters which must be escaped with a backslash in property names.
+const SPECIAL: [char; 5] = ['.', '[', ']', '!', '\\'];
+
+/// Errors produced when parsing a [`Path`] from a string.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParsePathError {
+    /// A property name was empty, e.g. in `m_a..m_b`.
+    #[error("empty property name at offset {0}")]
+    EmptyProperty(usize),
+
+    /// An index was not terminated by a closing `]`.
+    #[error("unterminated index at offset {0}")]
+    UnterminatedIndex(usize),
+
+    /// An index did not consist of decimal digits.
+    #[error("invalid index at offset {0}")]
+    InvalidIndex(usize),
+
+    /// A character appeared where it is not allowed.
+    #[error("unexpected '{1}' at offset {0}")]
+    Unexpected(usize, char),
+
+    /// A backslash ended the path without a character to escape.
+    #[error("dangling escape at offset {0}")]
+    DanglingEscape(usize),
+}
+
+/// A single step in a [`Path`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A named property of an object.
+    Property(String),
+    /// An index into a list.
+    Index(usize),
+    /// The object decoded from a [`Blob`][super::Blob].
+    Nested,
+    /// The key of a map entry, by the entry's index.
+    Key(usize),
+    /// The value of a map entry, by the entry's index.
+    Value(usize),
+    /// The first element of a pair.
+    First,
+    /// The second element of a pair.
+    Second,
+}
+
+/// The location of a nested value, starting from the root object.
+///
+/// Paths are displayed in a familiar notation like
+/// `m_children[3].m_name`, with `!nested` stepping into
+/// objects decoded from blobs. Map entries are addressed as
+/// `m_map[3]!key` and `m_map[3]!value`, the elements of pairs
+/// as `m_pair!first` and `m_pair!second`. Characters in property names
+/// which would be mistaken for this notation are escaped with
+/// a backslash, as in `m_a\.b`.
+///
+/// With the `serde` feature, paths are encoded as strings in
+/// this notation.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    /// Creates an empty path referring to the root value.
+    pub const fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    /// Gets the segments of the path in order.
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Gets the number of segments in the path.
+    pub fn depth(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Whether the path refers to the root value.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Appends a segment to the end of the path.
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment);
+    }
+
+    /// Removes the last segment of the path.
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    /// Shortens the path to its first `len` segments.
+    pub fn truncate(&mut self, len: usize) {
+        self.segments.truncate(len);
+    }
+
+    /// Inserts a segment at the start of the path.
+    ///
+    /// This is useful for building paths while unwinding from the
+    /// innermost value.
+    pub fn prepend(&mut self, segment: PathSegment) {
+        self.segments.insert(0, segment);
+    }
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Property(name) => {
+                for c in name.chars() {
+                    if SPECIAL.contains(&c) {
+                        f.write_str("\\")?;
+                    }
+                    write!(f, "{c}")?;
+                }
+                Ok(())
+            }
+            Self::Index(idx) => write!(f, "[{idx}]"),
+            Self::Nested => f.write_str(NESTED),
+            Self::Key(idx) => write!(f, "[{idx}]{KEY}"),
+            Self::Value(idx) => write!(f, "[{idx}]{VALUE}"),
+            Self::First => f.write_str(FIRST),
+            Self::Second => f.write_str(SECOND),
+        }
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+impl FromIterator<PathSegment> for Path {
+    fn from_iter<I: IntoIterator<Item = PathSegment>>(iter: I) -> Self {
+        Self {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<PathSegment> for Path {
+    fn extend<I: IntoIterator<Item = PathSegment>>(&mut self, iter: I) {
+        self.segments.extend(iter);
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_root() {
+            return f.write_str("<root>");
+        }
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i != 0 && matches!(segment, PathSegment::Property(..)) {
+                f.write_str(".")?;
+            }
+            segment.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = ParsePathError;
+
+    /// Parses a path in the notation produced by its [`Display`][fmt::Display]
+    /// impl.
+    ///
+    /// Both the empty string and `<root>` denote the root path.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut path = Self::new();
+        if s.is_empty() || s == "<root>" {
+            return Ok(path);
+        }
+
+        let bytes = s.as_bytes();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            match bytes[pos] {
+                b'[' => {
+                    let start = pos + 1;
+                    let end = s[start..]
+                        .find(']')
+                        .map(|i| start + i)
+                        .ok_or(ParsePathError::UnterminatedIndex(pos))?;
+
+                    let digits = &s[start..end];
+                    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(ParsePathError::InvalidIndex(start));
+                    }
+                    let idx = digits
+                        .parse()
+                        .map_err(|_| ParsePathError::InvalidIndex(start))?;
+
+                    pos = end + 1;
+
+                    // Map entries are indexed like lists, followed by
+                    // the side of the entry.
+                    let (segment, len) = if s[pos..].starts_with(KEY) {
+                        (PathSegment::Key(idx), KEY.len())
+                    } else if s[pos..].starts_with(VALUE) {
+                        (PathSegment::Value(idx), VALUE.len())
+                    } else {
+                        (PathSegment::Index(idx), 0)
+                    };
+
+                    path.push(segment);
+                    pos += len;
+                }
+
+                b'!' => {
+                    let (segment, len) = [
+                        (PathSegment::Nested, NESTED),
+                        (PathSegment::First, FIRST),
+                        (PathSegment::Second, SECOND),
+                    ]
+                    .into_iter()
+                    .find(|(_, name)| s[pos..].starts_with(name))
+                    .map(|(segment, name)| (segment, name.len()))
+                    .ok_or(ParsePathError::Unexpected(pos, '!'))?;
+
+                    path.push(segment);
+                    pos += len;
+                }
+
+                b']' => return Err(ParsePathError::Unexpected(pos, ']')),
+
+                b => {
+                    // A property must either start the path or follow a dot.
+                    let start = match b {
+                        b'.' if !path.is_root() => pos + 1,
+                        b'.' => return Err(ParsePathError::Unexpected(pos, '.')),
+                        _ if path.is_root() => pos,
+                        _ => {
+                            let c = s[pos..].chars().next().unwrap();
+                            return Err(ParsePathError::Unexpected(pos, c));
+                        }
+                    };
+                    let (name, end) = parse_property(s, start)?;
+                    if name.is_empty() {
+                        return Err(ParsePathError::EmptyProperty(start));
+                    }
+
+                    path.push(PathSegment::Property(name));
+                    pos = end;
+                }
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+// Parses a property name starting at `start`, up to the next
+// unescaped special character.
+//
+// Returns the unescaped name and the offset where it ends.
+fn parse_property(s: &str, start: usize) -> Result<(String, usize), ParsePathError> {
+    let mut name = String::new();
+    let mut chars = s[start..].char_indices().map(|(i, c)| (start + i, c));
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, c)) => name.push(c),
+                None => return Err(ParsePathError::DanglingEscape(i)),
+            },
+            c if SPECIAL.contains(&c) => return Ok((name, i)),
+            c => name.push(c),
+        }
+    }
+
+    Ok((name, s.len()))
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Path {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Path {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = std::string::String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
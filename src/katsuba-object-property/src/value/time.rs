@@ -0,0 +1,79 @@
+use std::fmt;
+
+/// How the raw integer of a [`Time`] is interpreted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeKind {
+    /// Seconds since the Unix epoch, as with the C `time_t` types.
+    UnixSeconds,
+}
+
+/// A point in time, stored exactly as it was serialized.
+///
+/// The raw value is written back unchanged; [`Time::kind`] only
+/// tells how to interpret it for display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Time {
+    /// The integer which was serialized.
+    pub raw: i64,
+    /// The interpretation of [`Time::raw`].
+    pub kind: TimeKind,
+}
+
+impl Time {
+    /// Creates a time from seconds since the Unix epoch.
+    pub const fn unix_seconds(raw: i64) -> Self {
+        Self {
+            raw,
+            kind: TimeKind::UnixSeconds,
+        }
+    }
+
+    /// Gets an ISO 8601 representation of the time in UTC.
+    pub fn to_iso8601(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let TimeKind::UnixSeconds = self.kind;
+
+        let days = self.raw.div_euclid(86400);
+        let secs = self.raw.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Time {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_i64(self.raw)
+    }
+}
+
+// Converts days since 1970-01-01 into a proleptic Gregorian date.
+// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
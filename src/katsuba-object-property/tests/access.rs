@@ -0,0 +1,296 @@
+#![cfg(feature = "value")]
+
+use std::collections::BTreeMap;
+
+use katsuba_object_property::value::*;
+
+fn object(hash: u32, props: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Object {
+        hash,
+        obj: Object {
+            inner: props
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect::<BTreeMap<_, _>>(),
+        },
+    }
+}
+
+fn list(values: impl IntoIterator<Item = Value>) -> Value {
+    Value::List(List {
+        inner: values.into_iter().collect(),
+    })
+}
+
+fn item(cost: i64, name: &str) -> Value {
+    object(
+        1,
+        [
+            ("m_goldCost", Value::Signed(cost)),
+            ("m_displayName", Value::String(CxxStr(name.into()))),
+        ],
+    )
+}
+
+fn shop() -> Value {
+    object(
+        2,
+        [
+            ("m_items", list([item(10, "Wand"), item(25, "Hat")])),
+            ("m_open", Value::Bool(true)),
+        ],
+    )
+}
+
+fn path(s: &str) -> Path {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parse_paths() {
+    for s in ["m_items[1].m_goldCost", "m_open", "[0][1].m_a"] {
+        assert_eq!(path(s).to_string(), s);
+    }
+    assert!(path("").is_root());
+    assert!(path("<root>").is_root());
+
+    assert_eq!(
+        "m_a..m_b".parse::<Path>(),
+        Err(ParsePathError::EmptyProperty(4))
+    );
+    assert_eq!(
+        "m_a[1".parse::<Path>(),
+        Err(ParsePathError::UnterminatedIndex(3))
+    );
+    assert_eq!(
+        "m_a[x]".parse::<Path>(),
+        Err(ParsePathError::InvalidIndex(4))
+    );
+    assert_eq!(
+        "m_a[0]m_b".parse::<Path>(),
+        Err(ParsePathError::Unexpected(6, 'm'))
+    );
+}
+
+#[test]
+fn parse_escaped_paths() {
+    let dotted = path(r"m_a\.b.m_c\[0\]");
+    assert_eq!(
+        dotted.segments(),
+        [
+            PathSegment::Property("m_a.b".into()),
+            PathSegment::Property("m_c[0]".into()),
+        ]
+    );
+    assert_eq!(dotted.to_string(), r"m_a\.b.m_c\[0\]");
+
+    let odd = Path::from(vec![
+        PathSegment::Property(r"back\slash".into()),
+        PathSegment::Property("!nested".into()),
+        PathSegment::Nested,
+    ]);
+    assert_eq!(odd.to_string(), r"back\\slash.\!nested!nested");
+    assert_eq!(path(&odd.to_string()), odd);
+
+    assert_eq!(
+        r"m_a\".parse::<Path>(),
+        Err(ParsePathError::DanglingEscape(3))
+    );
+    assert_eq!(
+        r"m_a.\".parse::<Path>(),
+        Err(ParsePathError::DanglingEscape(4))
+    );
+}
+
+#[test]
+fn parse_map_and_pair_paths() {
+    let entries = path("m_map[2]!key.m_a[0]!value!first!second");
+    assert_eq!(
+        entries.segments(),
+        [
+            PathSegment::Property("m_map".into()),
+            PathSegment::Key(2),
+            PathSegment::Property("m_a".into()),
+            PathSegment::Value(0),
+            PathSegment::First,
+            PathSegment::Second,
+        ]
+    );
+    assert_eq!(
+        entries.to_string(),
+        "m_map[2]!key.m_a[0]!value!first!second"
+    );
+
+    assert_eq!(
+        "m_map!key".parse::<Path>(),
+        Err(ParsePathError::Unexpected(5, '!'))
+    );
+}
+
+#[test]
+fn parse_large_indices() {
+    let max = format!("m_a[{}]", usize::MAX);
+    assert_eq!(path(&max).segments()[1], PathSegment::Index(usize::MAX));
+    assert_eq!(path(&max).to_string(), max);
+
+    assert_eq!(
+        "m_a[18446744073709551616]".parse::<Path>(),
+        Err(ParsePathError::InvalidIndex(4))
+    );
+    assert_eq!(
+        "m_a[-1]".parse::<Path>(),
+        Err(ParsePathError::InvalidIndex(4))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn paths_as_json() {
+    let p = path(r"m_items[2].m_a\.b");
+    let json = serde_json::to_string(&p).unwrap();
+    assert_eq!(json, r#""m_items[2].m_a\\.b""#);
+    assert_eq!(serde_json::from_str::<Path>(&json).unwrap(), p);
+
+    assert!(serde_json::from_str::<Path>(r#""m_a[x]""#).is_err());
+}
+
+#[test]
+fn replace_list_element() {
+    let mut value = shop();
+
+    let old = value.set_path(&path("m_items[1]"), item(5, "Cap")).unwrap();
+    assert_eq!(old, Some(item(25, "Hat")));
+    assert_eq!(value.get_path(&path("m_items[1]")), Some(&item(5, "Cap")));
+
+    assert_eq!(
+        value.set_path(&path("m_items[2]"), item(1, "Boots")),
+        Err(PathError::Missing(path("m_items[2]")))
+    );
+}
+
+#[test]
+fn insert_into_object() {
+    let mut value = shop();
+
+    let old = value
+        .set_path(&path("m_items[0].m_rarity"), Value::Enum(3))
+        .unwrap();
+    assert_eq!(old, None);
+    assert_eq!(
+        value.get_path(&path("m_items[0].m_rarity")),
+        Some(&Value::Enum(3))
+    );
+
+    assert_eq!(
+        value.set_path(&path("m_missing.m_a"), Value::Empty),
+        Err(PathError::Missing(path("m_missing")))
+    );
+    assert_eq!(
+        value.set_path(&path("m_open[0]"), Value::Empty),
+        Err(PathError::NotAContainer {
+            path: path("m_open"),
+            segment: PathSegment::Index(0),
+            found: "Bool",
+        })
+    );
+}
+
+#[test]
+fn typed_setters() {
+    let mut value = shop();
+
+    value
+        .set_signed(&path("m_items[0].m_goldCost"), 99)
+        .unwrap();
+    value
+        .set_string(&path("m_items[0].m_displayName"), "Staff")
+        .unwrap();
+    assert_eq!(
+        value.get_path(&path("m_items[0]")),
+        Some(&item(99, "Staff"))
+    );
+
+    assert_eq!(
+        value.set_unsigned(&path("m_items[0].m_goldCost"), 1),
+        Err(PathError::VariantMismatch {
+            path: path("m_items[0].m_goldCost"),
+            expected: "Unsigned",
+            found: "Signed",
+        })
+    );
+    assert_eq!(
+        value.set_bool(&path("m_closed"), false),
+        Err(PathError::Missing(path("m_closed")))
+    );
+}
+
+#[test]
+fn visit_order() {
+    let mut value = shop();
+
+    let mut visited = Vec::new();
+    value.visit_mut(&mut |path, value| {
+        visited.push(path.to_string());
+        if let Value::Signed(cost) = value {
+            *cost *= 2;
+        }
+    });
+
+    assert_eq!(
+        visited,
+        [
+            "<root>",
+            "m_items",
+            "m_items[0]",
+            "m_items[0].m_displayName",
+            "m_items[0].m_goldCost",
+            "m_items[1]",
+            "m_items[1].m_displayName",
+            "m_items[1].m_goldCost",
+            "m_open",
+        ]
+    );
+    assert_eq!(
+        value.get_path(&path("m_items[1].m_goldCost")),
+        Some(&Value::Signed(50))
+    );
+}
+
+#[test]
+fn strings() {
+    let mut value = shop();
+    let nested = Blob {
+        raw: CxxStr(Vec::new()),
+        value: item(5, "Nested"),
+    };
+    value
+        .set_path(&path("m_blob"), Value::Blob(Box::new(nested)))
+        .unwrap();
+    value
+        .set_path(
+            &path("m_title"),
+            Value::WString(CxxWStr("Shop".encode_utf16().collect())),
+        )
+        .unwrap();
+    value
+        .set_path(&path("m_sign"), Value::String(CxxStr(b"Op\xffen".to_vec())))
+        .unwrap();
+
+    let strings: Vec<_> = value
+        .strings()
+        .map(|(path, s)| (path.to_string(), s.into_owned()))
+        .collect();
+    assert_eq!(
+        strings,
+        [
+            (
+                "m_blob!nested.m_displayName".to_string(),
+                "Nested".to_string()
+            ),
+            ("m_items[0].m_displayName".into(), "Wand".into()),
+            ("m_items[1].m_displayName".into(), "Hat".into()),
+            ("m_sign".into(), "Op\u{fffd}en".into()),
+            ("m_title".into(), "Shop".into()),
+        ]
+    );
+}
@@ -0,0 +1,126 @@
+#![cfg(all(feature = "de", feature = "arena"))]
+
+mod common;
+
+use std::sync::Arc;
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+
+use common::{class, type_list, Property};
+
+const PROPERTIES: &[Property] = &[
+    ("m_id", "int", 24, false),
+    ("m_name", "std::string", 24, false),
+    ("m_items", "int", 24, true),
+    ("m_next", "class SharedPointer<class Node>", 24, false),
+    ("m_counts", "std::map<int, int>", 24, false),
+    ("m_tint", "class Color", 24, false),
+];
+
+fn types() -> Arc<TypeList> {
+    type_list([class("class Node", &[], PROPERTIES)])
+}
+
+fn node(id: i64, next: Value) -> Value {
+    let mut obj = Object {
+        inner: Default::default(),
+    };
+    obj.insert("m_id".into(), Value::Signed(id));
+    obj.insert("m_name".into(), Value::String(CxxStr(b"kobold".to_vec())));
+    obj.insert(
+        "m_items".into(),
+        Value::List(List {
+            inner: vec![Value::Signed(1), Value::Signed(2)],
+        }),
+    );
+    obj.insert("m_next".into(), next);
+    obj.insert(
+        "m_counts".into(),
+        Value::Map(vec![(Value::Signed(3), Value::Signed(4))]),
+    );
+    obj.insert(
+        "m_tint".into(),
+        Value::Color(Color {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 4,
+        }),
+    );
+
+    Value::Object {
+        hash: string_id(b"class Node"),
+        obj,
+    }
+}
+
+fn serializer(options: SerializerOptions) -> Serializer {
+    common::serializer(types(), options)
+}
+
+#[test]
+fn arena_matches_owned() {
+    for shallow in [false, true] {
+        let options = SerializerOptions {
+            shallow,
+            ..Default::default()
+        };
+        let mut serializer = serializer(options);
+        let data = serializer
+            .serialize::<PropertyClass>(&node(1, node(2, Value::Empty)))
+            .unwrap();
+
+        let owned = serializer.deserialize::<PropertyClass>(&data).unwrap();
+        let arena = Arena::new();
+        let value = serializer
+            .deserialize_in::<PropertyClass>(&arena, &data)
+            .unwrap();
+
+        assert_eq!(value.to_value(), owned);
+        assert!(arena.allocated_bytes() > 0);
+    }
+}
+
+#[test]
+fn arena_object_access() {
+    let mut serializer = serializer(SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    });
+    let data = serializer
+        .serialize::<PropertyClass>(&node(1, Value::Empty))
+        .unwrap();
+
+    let arena = Arena::new();
+    let value = serializer
+        .deserialize_in::<PropertyClass>(&arena, &data)
+        .unwrap();
+    let ArenaValue::Object { hash, obj } = value else {
+        panic!("expected object, got {value:?}");
+    };
+
+    assert_eq!(hash, string_id(b"class Node"));
+    assert_eq!(obj.len(), 6);
+    assert_eq!(obj.get("m_id"), Some(&ArenaValue::Signed(1)));
+    assert_eq!(obj.get("m_name"), Some(&ArenaValue::String(b"kobold")));
+    assert_eq!(
+        obj.get("m_items"),
+        Some(&ArenaValue::List(&[
+            ArenaValue::Signed(1),
+            ArenaValue::Signed(2)
+        ]))
+    );
+    assert_eq!(obj.get("m_next"), Some(&ArenaValue::Empty));
+    assert_eq!(obj.get("m_missing"), None);
+}
+
+#[test]
+fn arena_null_root() {
+    let arena = Arena::new();
+    let res = serializer(SerializerOptions::default())
+        .deserialize_in::<PropertyClass>(&arena, &0_u32.to_le_bytes());
+
+    assert!(matches!(res, Err(Error::NullRoot)));
+}
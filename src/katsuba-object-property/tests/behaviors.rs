@@ -0,0 +1,103 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::sync::Arc;
+
+use katsuba_object_property::value::{List, Object, Value};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+
+use common::{class, type_list};
+
+fn hash(name: &str) -> u32 {
+    string_id(format!("class {name}").as_bytes())
+}
+
+fn types() -> Arc<TypeList> {
+    type_list([
+        class("class BehaviorTemplate", &[], &[]),
+        class(
+            "class RenderBehaviorTemplate",
+            &["class BehaviorTemplate"],
+            &[],
+        ),
+        class(
+            "class AnimationBehaviorTemplate",
+            &["class RenderBehaviorTemplate"],
+            &[],
+        ),
+        class(
+            "class CollisionBehaviorTemplate",
+            &["class BehaviorTemplate"],
+            &[],
+        ),
+    ])
+}
+
+fn behavior(name: &str, id: i64) -> Value {
+    Value::Object {
+        hash: hash(name),
+        obj: Object {
+            inner: [("m_id".into(), Value::Signed(id))].into_iter().collect(),
+        },
+    }
+}
+
+fn template(behaviors: Vec<Value>) -> Object {
+    Object {
+        inner: [("m_behaviors".into(), Value::List(List { inner: behaviors }))]
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn id(obj: &Object) -> &Value {
+    &obj["m_id"]
+}
+
+#[test]
+fn behaviors_by_class() {
+    let types = types();
+    let template = template(vec![
+        behavior("CollisionBehaviorTemplate", 1),
+        Value::Empty,
+        behavior("AnimationBehaviorTemplate", 2),
+        behavior("RenderBehaviorTemplate", 3),
+    ]);
+
+    let render = template.behavior("RenderBehaviorTemplate", &types).unwrap();
+    assert_eq!(id(render), &Value::Signed(2));
+
+    let collision = template.behavior("class CollisionBehaviorTemplate", &types);
+    assert_eq!(collision.map(id), Some(&Value::Signed(1)));
+
+    let all: Vec<_> = template
+        .behaviors_of("BehaviorTemplate", &types)
+        .map(|(hash, obj)| (hash, id(obj).clone()))
+        .collect();
+    assert_eq!(
+        all,
+        [
+            (hash("CollisionBehaviorTemplate"), Value::Signed(1)),
+            (hash("AnimationBehaviorTemplate"), Value::Signed(2)),
+            (hash("RenderBehaviorTemplate"), Value::Signed(3)),
+        ]
+    );
+
+    assert!(template
+        .behavior("MissingBehaviorTemplate", &types)
+        .is_none());
+}
+
+#[test]
+fn objects_without_behaviors() {
+    let types = types();
+    let empty = Object {
+        inner: Default::default(),
+    };
+    assert_eq!(empty.behaviors_of("BehaviorTemplate", &types).count(), 0);
+
+    let template = template(Vec::new());
+    assert!(template.behavior("BehaviorTemplate", &types).is_none());
+}
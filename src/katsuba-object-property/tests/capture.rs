@@ -0,0 +1,80 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use katsuba_object_property::serde::*;
+
+use common::{holder_bytes, holder_types, VALUES_AND_NAME};
+
+fn serializer(capture_raw: bool) -> Serializer {
+    let options = SerializerOptions {
+        capture_raw,
+        ..Default::default()
+    };
+
+    common::serializer(holder_types(VALUES_AND_NAME), options)
+}
+
+fn spans(ser: &Serializer) -> Vec<(String, usize, usize)> {
+    ser.parts
+        .raw_spans()
+        .iter()
+        .map(|s| (s.path.to_string(), s.start, s.len))
+        .collect()
+}
+
+#[test]
+fn captures_in_stream_order() {
+    let data = holder_bytes(&[7, -1], "ab");
+    let mut ser = serializer(true);
+    ser.deserialize::<PropertyClass>(&data).unwrap();
+
+    assert_eq!(
+        spans(&ser),
+        [
+            ("m_values".to_owned(), 32, 96),
+            ("m_values[0]".to_owned(), 64, 32),
+            ("m_values[1]".to_owned(), 96, 32),
+            ("m_name".to_owned(), 128, 32),
+        ]
+    );
+
+    let name = &ser.parts.raw_spans()[3];
+    assert_eq!(name.bytes, [2, 0, b'a', b'b']);
+}
+
+#[test]
+fn disabled_by_default() {
+    let mut ser = serializer(false);
+    ser.deserialize::<PropertyClass>(&holder_bytes(&[1], "x"))
+        .unwrap();
+
+    assert!(ser.parts.raw_spans().is_empty());
+}
+
+#[test]
+fn partial_spans_on_failure() {
+    let mut data = holder_bytes(&[3], "kobold");
+    data.truncate(data.len() - 2);
+
+    let mut ser = serializer(true);
+    ser.deserialize::<PropertyClass>(&data).unwrap_err();
+    assert_eq!(
+        spans(&ser),
+        [
+            ("m_values".to_owned(), 32, 64),
+            ("m_values[0]".to_owned(), 64, 32),
+        ]
+    );
+
+    // Spans are reset with every deserialization.
+    ser.deserialize::<PropertyClass>(&holder_bytes(&[], ""))
+        .unwrap();
+    assert_eq!(
+        spans(&ser),
+        [
+            ("m_values".to_owned(), 32, 32),
+            ("m_name".to_owned(), 64, 16)
+        ]
+    );
+}
@@ -0,0 +1,80 @@
+//! Type lists and serializers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use katsuba_object_property::serde::{Serializer, SerializerOptions};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+use serde_json::{json, Map};
+
+/// A property of a test class: its name, type, flags and whether
+/// it is dynamic.
+pub type Property<'a> = (&'a str, &'a str, u32, bool);
+
+/// The properties of the `class Holder` most tests work with.
+pub const VALUES_AND_NAME: &[Property] = &[
+    ("m_values", "int", 24, true),
+    ("m_name", "std::string", 24, false),
+];
+
+/// Describes a class for [`type_list`].
+///
+/// Properties are numbered in order, starting with id 0 and hash 1.
+pub fn class(name: &str, bases: &[&str], properties: &[Property]) -> (String, serde_json::Value) {
+    let hash = string_id(name.as_bytes());
+    let properties: Map<_, _> = properties
+        .iter()
+        .enumerate()
+        .map(|(id, &(name, ty, flags, dynamic))| {
+            let property = json!({
+                "type": ty,
+                "id": id,
+                "flags": flags,
+                "dynamic": dynamic,
+                "hash": id + 1,
+            });
+            (name.to_owned(), property)
+        })
+        .collect();
+
+    let class = json!({
+        "name": name,
+        "bases": bases,
+        "hash": hash,
+        "properties": properties,
+    });
+    (hash.to_string(), class)
+}
+
+/// Builds a type list from the given classes.
+pub fn type_list(classes: impl IntoIterator<Item = (String, serde_json::Value)>) -> Arc<TypeList> {
+    let classes: Map<_, _> = classes.into_iter().collect();
+
+    // The version has to come first, so this can't go through `json!`.
+    let json = format!(r#"{{ "version": 2, "classes": {} }}"#, json!(classes));
+    Arc::new(TypeList::from_str(&json).unwrap())
+}
+
+/// Builds a type list with only a `class Holder`.
+pub fn holder_types(properties: &[Property]) -> Arc<TypeList> {
+    type_list([class("class Holder", &[], properties)])
+}
+
+/// Creates a serializer for the given types.
+pub fn serializer(types: Arc<TypeList>, options: SerializerOptions) -> Serializer {
+    Serializer::new(options, types).unwrap()
+}
+
+/// Encodes a `class Holder` with [`VALUES_AND_NAME`] in shallow mode.
+pub fn holder_bytes(values: &[i32], name: &str) -> Vec<u8> {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend((values.len() as u32).to_le_bytes());
+    for v in values {
+        data.extend(v.to_le_bytes());
+    }
+    data.extend((name.len() as u16).to_le_bytes());
+    data.extend(name.as_bytes());
+    data
+}
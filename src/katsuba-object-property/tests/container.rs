@@ -0,0 +1,119 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_utils::hash::string_id;
+
+use common::{holder_types, Property};
+
+const PROPERTIES: &[Property] = &[
+    (
+        "m_scores",
+        "class std::map<std::string, class std::pair<int, enum Kind>>",
+        24,
+        false,
+    ),
+    (
+        "m_child",
+        "std::pair<unsigned int, class SharedPointer<class Holder>>",
+        24,
+        false,
+    ),
+];
+
+const COUNTS: &[Property] = &[("m_counts", "std::map<int, int>", 24, false)];
+
+fn serializer(properties: &[Property], shallow: bool) -> Serializer {
+    let options = SerializerOptions {
+        shallow,
+        ..Default::default()
+    };
+
+    common::serializer(holder_types(properties), options)
+}
+
+fn object(props: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Object {
+        hash: string_id(b"class Holder"),
+        obj: Object {
+            inner: props
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect::<BTreeMap<_, _>>(),
+        },
+    }
+}
+
+fn pair(first: Value, second: Value) -> Value {
+    Value::Pair(Box::new((first, second)))
+}
+
+fn holder(child: Value) -> Value {
+    let score = |name: &str, points, kind| {
+        (
+            Value::String(CxxStr(name.into())),
+            pair(Value::Signed(points), Value::Enum(kind)),
+        )
+    };
+
+    object([
+        (
+            "m_scores",
+            Value::Map(vec![score("b", 20, 1), score("a", -5, 0)]),
+        ),
+        ("m_child", pair(Value::Unsigned(7), child)),
+    ])
+}
+
+#[test]
+fn roundtrip_maps_and_pairs() {
+    let value = holder(holder(Value::Empty));
+
+    for shallow in [true, false] {
+        let mut ser = serializer(PROPERTIES, shallow);
+        let data = ser.serialize::<PropertyClass>(&value).unwrap();
+        let back = ser.deserialize::<PropertyClass>(&data).unwrap();
+
+        // Map entries keep their serialized order.
+        assert_eq!(back, value);
+    }
+}
+
+#[test]
+fn deserialize_map_layout() {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(2_u32.to_le_bytes());
+    for v in [3_i32, 30, -1, 10] {
+        data.extend(v.to_le_bytes());
+    }
+
+    let value = serializer(COUNTS, true)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap();
+    assert_eq!(
+        value,
+        object([(
+            "m_counts",
+            Value::Map(vec![
+                (Value::Signed(3), Value::Signed(30)),
+                (Value::Signed(-1), Value::Signed(10)),
+            ]),
+        )])
+    );
+}
+
+#[test]
+fn map_value_mismatch() {
+    let value = object([("m_counts", Value::List(List { inner: vec![] }))]);
+    let err = serializer(COUNTS, true)
+        .serialize::<PropertyClass>(&value)
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::ValueMismatch { expected, found: "List" } if expected == "std::map<int, int>"
+    ));
+}
@@ -0,0 +1,106 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::sync::Arc;
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+use serde_json::json;
+
+use common::{class, type_list, Property};
+
+const PROPERTIES: &[Property] = &[
+    ("m_id", "int", 24, false),
+    ("m_name", "std::string", 0, false),
+    ("m_kind", "enum Kind", 2097152, false),
+    ("m_items", "int", 0, true),
+    ("m_child", "class Node", 0, false),
+    ("m_next", "class SharedPointer<class Node>", 0, false),
+    ("m_counts", "std::map<int, int>", 0, false),
+    ("m_tint", "class Color", 0, false),
+];
+
+fn types() -> Arc<TypeList> {
+    let (hash, mut node) = class("class Node", &[], PROPERTIES);
+    node["properties"]["m_kind"]["enum_options"] = json!({ "B": 5, "A": 3 });
+    type_list([(hash, node)])
+}
+
+fn serializer(options: SerializerOptions) -> Serializer {
+    common::serializer(types(), options)
+}
+
+fn property<'a>(value: &'a Value, name: &str) -> &'a Value {
+    let Value::Object { obj, .. } = value else {
+        panic!("expected object, got {value:?}");
+    };
+    &obj[name]
+}
+
+#[test]
+fn default_objects_from_type_list() {
+    let mut options = SerializerOptions::default();
+    options.limits.max_depth = 3;
+    let ser = serializer(options);
+
+    let types = types();
+    let (hash, node) = types.find("class Node").unwrap();
+    let value = ser.parts.default_object(node);
+
+    assert!(matches!(value, Value::Object { hash: h, .. } if h == hash));
+    assert_eq!(property(&value, "m_id"), &Value::Signed(0));
+    assert_eq!(
+        property(&value, "m_name"),
+        &Value::String(CxxStr(Vec::new()))
+    );
+    assert_eq!(property(&value, "m_kind"), &Value::Enum(3));
+    assert_eq!(
+        property(&value, "m_items"),
+        &Value::List(List { inner: Vec::new() })
+    );
+    assert_eq!(property(&value, "m_next"), &Value::Empty);
+    assert_eq!(property(&value, "m_counts"), &Value::Map(Vec::new()));
+    assert_eq!(
+        property(&value, "m_tint"),
+        &Value::Color(Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 0
+        })
+    );
+
+    // The self-referencing child stops at the recursion limit.
+    let child = property(&value, "m_child");
+    let grandchild = property(child, "m_child");
+    assert_eq!(property(grandchild, "m_child"), &Value::Empty);
+}
+
+#[test]
+fn fill_defaults_for_shallow_properties() {
+    // Only `m_id` is masked and part of the data.
+    let mut data = string_id(b"class Node").to_le_bytes().to_vec();
+    data.extend(7_i32.to_le_bytes());
+
+    let plain = serializer(SerializerOptions::default())
+        .deserialize::<PropertyClass>(&data)
+        .unwrap();
+    let Value::Object { obj, .. } = &plain else {
+        panic!("expected object, got {plain:?}");
+    };
+    assert_eq!(obj.inner.len(), 1);
+
+    let options = SerializerOptions {
+        fill_defaults: true,
+        ..Default::default()
+    };
+    let filled = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap();
+    assert_eq!(property(&filled, "m_id"), &Value::Signed(7));
+    assert_eq!(property(&filled, "m_kind"), &Value::Enum(3));
+    assert_eq!(property(&filled, "m_next"), &Value::Empty);
+    assert!(matches!(property(&filled, "m_child"), Value::Object { .. }));
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use katsuba_object_property::{serde::*, value::Object, Value};
+use katsuba_utils::hash::string_id;
+
+use common::{holder_types, Property};
+
+const PROPERTIES: &[Property] = &[
+    ("m_first", "int", 24, false),
+    ("m_old", "int", 88, false),
+    ("m_last", "unsigned short", 24, false),
+];
+
+fn serializer(include_deprecated: bool) -> Serializer {
+    let options = SerializerOptions {
+        include_deprecated,
+        ..Default::default()
+    };
+
+    common::serializer(holder_types(PROPERTIES), options)
+}
+
+fn holder(props: &[(&str, Value)]) -> Value {
+    Value::Object {
+        hash: string_id(b"class Holder"),
+        obj: Object {
+            inner: props
+                .iter()
+                .map(|(k, v)| ((*k).into(), v.clone()))
+                .collect::<BTreeMap<_, _>>(),
+        },
+    }
+}
+
+// A shallow stream written before `m_old` was deprecated.
+fn old_stream() -> Vec<u8> {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(1_i32.to_le_bytes());
+    data.extend(2_i32.to_le_bytes());
+    data.extend(3_u16.to_le_bytes());
+    data
+}
+
+#[test]
+fn include_deprecated() {
+    let data = old_stream();
+
+    let value = serializer(true)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap();
+    assert_eq!(
+        value,
+        holder(&[
+            ("m_first", Value::Signed(1)),
+            ("m_old", Value::Signed(2)),
+            ("m_last", Value::Unsigned(3)),
+        ])
+    );
+    assert_eq!(
+        serializer(true).serialize::<PropertyClass>(&value).unwrap(),
+        data
+    );
+}
+
+#[test]
+fn skip_deprecated_by_default() {
+    // Without the deprecated property, the stream desyncs.
+    let data = old_stream();
+    let err = serializer(false)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(err, Error::TrailingData(4)));
+
+    let value = holder(&[
+        ("m_first", Value::Signed(1)),
+        ("m_last", Value::Unsigned(2)),
+    ]);
+    let mut ser = serializer(false);
+    let data = ser.serialize::<PropertyClass>(&value).unwrap();
+    assert_eq!(data.len(), 10);
+    assert_eq!(ser.deserialize::<PropertyClass>(&data).unwrap(), value);
+}
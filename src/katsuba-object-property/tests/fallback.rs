@@ -0,0 +1,70 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use katsuba_object_property::{serde::*, Value};
+use katsuba_utils::hash::string_id;
+
+use common::{holder_types, Property};
+
+const PROPERTIES: &[Property] = &[
+    ("m_position", "class Vector3D", 24, false),
+    ("m_other", "class Unlisted", 24, false),
+    ("m_tail", "int", 24, false),
+];
+
+fn serializer(options: SerializerOptions) -> Serializer {
+    common::serializer(holder_types(PROPERTIES), options)
+}
+
+fn holder(other: u32) -> Vec<u8> {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    for component in [1.0_f32, 2.0, 3.0] {
+        data.extend(component.to_le_bytes());
+    }
+    data.extend(other.to_le_bytes());
+    data.extend(7_i32.to_le_bytes());
+    data
+}
+
+#[test]
+fn unknown_types_fall_back_to_objects() {
+    // A null object for the type which is not in the list.
+    let value = serializer(SerializerOptions::default())
+        .deserialize::<PropertyClass>(&holder(0))
+        .unwrap();
+    let Value::Object { obj, .. } = value else {
+        panic!("expected object");
+    };
+
+    assert!(matches!(obj.get("m_position"), Some(Value::Vec3(_))));
+    assert!(matches!(obj.get("m_other"), Some(Value::Empty)));
+    assert!(matches!(obj.get("m_tail"), Some(Value::Signed(7))));
+}
+
+#[test]
+fn disabled_object_fallback() {
+    let options = SerializerOptions {
+        object_fallback: false,
+        ..Default::default()
+    };
+
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&holder(0))
+        .unwrap_err();
+    assert!(matches!(err, Error::UnknownValueType(ty) if ty == "class Unlisted"));
+}
+
+#[test]
+fn simple_data_errors_are_final() {
+    // Only room for two components of the vector, which must not be
+    // retried as a null object.
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(0_u32.to_le_bytes());
+    data.extend(7_i32.to_le_bytes());
+
+    let err = serializer(SerializerOptions::default())
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(err, Error::Read(e) if e.is_eof()));
+}
@@ -0,0 +1,62 @@
+//! Checks the APIs available under every combination of features.
+//!
+//! Run with e.g. `cargo test --no-default-features --features value`
+//! to cover a configuration other than the default one.
+
+#[cfg(feature = "value")]
+#[test]
+fn value_model() {
+    use katsuba_object_property::value::{CxxStr, Path, Value};
+
+    let path: Path = "m_name".parse().unwrap();
+    let value = Value::String(CxxStr(b"kobold".to_vec()));
+    assert_eq!(path.to_string(), "m_name");
+    assert_eq!(value.variant_name(), "String");
+}
+
+#[cfg(feature = "de")]
+#[test]
+fn deserializer() {
+    use std::{collections::HashMap, sync::Arc};
+
+    use katsuba_object_property::{from_slice, serde::Error, Value};
+    use katsuba_types::TypeList;
+
+    let types = Arc::new(TypeList::from(HashMap::new()));
+    let res: Result<Value, Error> = from_slice(&0_u32.to_le_bytes(), types);
+    assert!(matches!(res, Err(Error::NullRoot)));
+}
+
+#[cfg(all(feature = "value", feature = "serde"))]
+#[test]
+fn value_serde_impls() {
+    use katsuba_object_property::value::Value;
+
+    let json = serde_json::to_string(&Value::Bool(true)).unwrap();
+    assert_eq!(json, "true");
+}
+
+#[cfg(feature = "option-guessing")]
+#[test]
+fn option_guessing() {
+    use std::{collections::HashMap, sync::Arc};
+
+    use katsuba_object_property::serde::Serializer;
+    use katsuba_types::TypeList;
+
+    let types = Arc::new(TypeList::from(HashMap::new()));
+    // Empty data leaves nothing to rule out, so guessing succeeds.
+    assert!(Serializer::with_guessed_options(types, b"").is_ok());
+}
+
+#[cfg(feature = "arena")]
+#[test]
+fn arena_values() {
+    use katsuba_object_property::value::{Arena, ArenaValue, CxxStr, Value};
+
+    let arena = Arena::new();
+    let value = Value::String(CxxStr(b"kobold".to_vec()));
+    let in_arena = arena.alloc_value(&value);
+    assert_eq!(in_arena, ArenaValue::String(b"kobold"));
+    assert_eq!(in_arena.to_value(), value);
+}
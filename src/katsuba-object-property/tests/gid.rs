@@ -0,0 +1,16 @@
+#![cfg(feature = "value")]
+
+use katsuba_object_property::value::{gid_join, gid_split, gid_to_hex};
+
+#[test]
+fn hex_and_parts() {
+    assert_eq!(gid_to_hex(0), "0x0000000000000000");
+    assert_eq!(gid_to_hex(0x1_0000_00AB), "0x00000001000000AB");
+    assert_eq!(gid_to_hex(u64::MAX), "0xFFFFFFFFFFFFFFFF");
+
+    assert_eq!(gid_split(0x1_0000_00AB), (1, 0xAB));
+    for gid in [0, 0x1_0000_00AB, u64::MAX] {
+        let (block, id) = gid_split(gid);
+        assert_eq!(gid_join(block, id), gid);
+    }
+}
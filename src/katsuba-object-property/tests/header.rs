@@ -0,0 +1,260 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_utils::{compress::Deflater, hash::string_id};
+
+use common::{holder_types, VALUES_AND_NAME};
+
+fn serializer(options: SerializerOptions) -> Serializer {
+    common::serializer(holder_types(VALUES_AND_NAME), options)
+}
+
+fn sample() -> Value {
+    let values = Value::List(List {
+        inner: vec![Value::Signed(-3), Value::Signed(40)],
+    });
+    let name = Value::String(CxxStr(b"kobold".to_vec()));
+
+    Value::Object {
+        hash: string_id(b"class Holder"),
+        obj: Object {
+            inner: BTreeMap::from([("m_values".into(), values), ("m_name".into(), name)]),
+        },
+    }
+}
+
+// The shallow encoding of `sample` without any framing.
+fn sample_bytes(compact: bool) -> Vec<u8> {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    match compact {
+        // Small lengths take a single bit plus 7 bits of value.
+        true => data.push(2 << 1),
+        false => data.extend(2_u32.to_le_bytes()),
+    }
+    data.extend((-3_i32).to_le_bytes());
+    data.extend(40_i32.to_le_bytes());
+    match compact {
+        true => data.push(6 << 1),
+        false => data.extend(6_u16.to_le_bytes()),
+    }
+    data.extend(b"kobold");
+    data
+}
+
+fn all_flags() -> impl Iterator<Item = SerializerFlags> {
+    (0..1 << 5).map(SerializerFlags::from_bits_truncate)
+}
+
+#[test]
+fn roundtrip_matrix() {
+    let value = sample();
+
+    for flags in all_flags() {
+        for manual_compression in [false, true] {
+            let options = SerializerOptions {
+                flags,
+                manual_compression,
+                ..Default::default()
+            };
+
+            let data = serializer(options)
+                .serialize::<PropertyClass>(&value)
+                .unwrap();
+            let decoded = serializer(options).deserialize::<PropertyClass>(&data);
+            assert_eq!(
+                decoded.unwrap(),
+                value,
+                "flags {flags:?}, manual {manual_compression}"
+            );
+        }
+    }
+}
+
+#[test]
+fn hand_framed_matrix() {
+    let value = sample();
+    let mut deflater = Deflater::best();
+
+    for flags in all_flags() {
+        for compressed in [false, true] {
+            for manual_compression in [false, true] {
+                let compact = flags.contains(SerializerFlags::COMPACT_LENGTH_PREFIXES);
+                let body = sample_bytes(compact);
+
+                let mut data = Vec::new();
+                if flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+                    write_config_header(&mut data, flags);
+                }
+                if flags.contains(SerializerFlags::WITH_COMPRESSION) {
+                    write_compression_marker(&mut data, compressed);
+                    if compressed {
+                        write_zlib(&mut data, &mut deflater, &body);
+                    } else {
+                        data.extend(&body);
+                    }
+                } else {
+                    data.extend(&body);
+                }
+                if manual_compression {
+                    let mut wrapped = Vec::new();
+                    write_zlib(&mut wrapped, &mut deflater, &data);
+                    data = wrapped;
+                }
+
+                // Stateful data must override whatever flags are set.
+                let configured = match flags.contains(SerializerFlags::STATEFUL_FLAGS) {
+                    true => SerializerFlags::STATEFUL_FLAGS,
+                    false => flags,
+                };
+                let options = SerializerOptions {
+                    flags: configured,
+                    manual_compression,
+                    ..Default::default()
+                };
+
+                let decoded = serializer(options).deserialize::<PropertyClass>(&data);
+                assert_eq!(
+                    decoded.unwrap(),
+                    value,
+                    "flags {flags:?}, compressed {compressed}, manual {manual_compression}"
+                );
+            }
+        }
+    }
+}
+
+// Frames `body` with stateful `flags` in the given order, using the
+// writer helpers rather than `write_framing`.
+fn frame_stateful(
+    deflater: &mut Deflater,
+    order: HeaderOrder,
+    flags: SerializerFlags,
+    compressed: bool,
+    body: &[u8],
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    match order {
+        HeaderOrder::CompressionFirst => {
+            let mut inner = Vec::new();
+            write_config_header(&mut inner, flags);
+            inner.extend(body);
+
+            write_compression_marker(&mut data, compressed);
+            match compressed {
+                true => write_zlib(&mut data, deflater, &inner),
+                false => data.extend(inner),
+            }
+        }
+        _ => {
+            write_config_header(&mut data, flags);
+            if flags.contains(SerializerFlags::WITH_COMPRESSION) {
+                write_compression_marker(&mut data, compressed);
+            }
+            match compressed {
+                true => write_zlib(&mut data, deflater, body),
+                false => data.extend(body),
+            }
+        }
+    }
+    data
+}
+
+#[test]
+fn stateful_header_orders() {
+    let value = sample();
+    let mut deflater = Deflater::best();
+
+    for order in [HeaderOrder::FlagsFirst, HeaderOrder::CompressionFirst] {
+        for compressed in [false, true] {
+            for manual_compression in [false, true] {
+                let mut flags = SerializerFlags::STATEFUL_FLAGS;
+                flags.set(SerializerFlags::WITH_COMPRESSION, compressed);
+
+                let mut data = frame_stateful(
+                    &mut deflater,
+                    order,
+                    flags,
+                    compressed,
+                    &sample_bytes(false),
+                );
+                if manual_compression {
+                    let mut wrapped = Vec::new();
+                    write_zlib(&mut wrapped, &mut deflater, &data);
+                    data = wrapped;
+                }
+
+                // Both the exact order and detection must read the data.
+                for header_order in [order, HeaderOrder::Auto] {
+                    let options = SerializerOptions {
+                        flags: SerializerFlags::STATEFUL_FLAGS,
+                        manual_compression,
+                        header_order,
+                        ..Default::default()
+                    };
+
+                    let decoded = serializer(options).deserialize::<PropertyClass>(&data);
+                    assert_eq!(
+                        decoded.unwrap(),
+                        value,
+                        "{order:?} as {header_order:?}, compressed {compressed}, \
+                         manual {manual_compression}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn compression_first_roundtrip() {
+    let value = sample();
+    let options = SerializerOptions {
+        flags: SerializerFlags::STATEFUL_FLAGS | SerializerFlags::WITH_COMPRESSION,
+        header_order: HeaderOrder::CompressionFirst,
+        ..Default::default()
+    };
+
+    let data = serializer(options)
+        .serialize::<PropertyClass>(&value)
+        .unwrap();
+    assert_eq!(data[0], 1);
+
+    let expected = frame_stateful(
+        &mut Deflater::best(),
+        HeaderOrder::CompressionFirst,
+        options.flags,
+        true,
+        &sample_bytes(false),
+    );
+    assert_eq!(data, expected);
+
+    // Data in the wrong order is rejected rather than misread.
+    let wrong = SerializerOptions {
+        header_order: HeaderOrder::FlagsFirst,
+        ..options
+    };
+    assert!(serializer(wrong)
+        .deserialize::<PropertyClass>(&data)
+        .is_err());
+}
+
+#[test]
+fn framing_layout() {
+    let mut deflater = Deflater::best();
+    let options = SerializerOptions {
+        flags: SerializerFlags::STATEFUL_FLAGS | SerializerFlags::WITH_COMPRESSION,
+        ..Default::default()
+    };
+
+    let data = write_framing(&mut deflater, &options, b"abc");
+    assert_eq!(data[..4], 9_u32.to_le_bytes());
+    assert_eq!(data[4], 1);
+    assert_eq!(data[5..9], 3_u32.to_le_bytes());
+
+    let plain = write_framing(&mut deflater, &SerializerOptions::default(), b"abc");
+    assert_eq!(plain, b"abc");
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "value")]
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use katsuba_object_property::value::*;
+
+fn object(hash: u32, props: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Object {
+        hash,
+        obj: Object {
+            inner: props
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect::<BTreeMap<_, _>>(),
+        },
+    }
+}
+
+fn list(values: impl IntoIterator<Item = Value>) -> Value {
+    Value::List(List {
+        inner: values.into_iter().collect(),
+    })
+}
+
+fn behavior(id: u64) -> Value {
+    object(
+        3,
+        [
+            ("m_id", Value::Unsigned(id)),
+            ("m_tags", list([Value::Signed(1), Value::Signed(2)])),
+            ("m_adjectives", list([])),
+        ],
+    )
+}
+
+fn template() -> Value {
+    object(
+        1,
+        [
+            ("m_behaviors", list([behavior(1), behavior(2), behavior(1)])),
+            ("m_default", behavior(1)),
+        ],
+    )
+}
+
+fn path(s: &str) -> Path {
+    s.parse().unwrap()
+}
+
+fn shared(value: &Value) -> &Arc<Value> {
+    match value {
+        Value::Shared(v) => v,
+        v => panic!("expected shared value, got {v:?}"),
+    }
+}
+
+#[test]
+fn intern_shares_duplicates() {
+    let original = template();
+    let mut value = original.clone();
+    value.intern();
+
+    // Sharing is invisible to comparisons.
+    assert_eq!(value, original);
+    assert!(matches!(value, Value::Object { .. }));
+
+    let a = shared(value.get_path(&path("m_behaviors[0]")).unwrap());
+    let b = shared(value.get_path(&path("m_behaviors[1]")).unwrap());
+    let c = shared(value.get_path(&path("m_behaviors[2]")).unwrap());
+    let d = shared(value.get_path(&path("m_default")).unwrap());
+    assert!(Arc::ptr_eq(a, c) && Arc::ptr_eq(a, d));
+    assert!(!Arc::ptr_eq(a, b));
+
+    // Equal lists are shared across different parents.
+    let tags_a = shared(value.get_path(&path("m_behaviors[0].m_tags")).unwrap());
+    let tags_b = shared(value.get_path(&path("m_behaviors[1].m_tags")).unwrap());
+    assert!(Arc::ptr_eq(tags_a, tags_b));
+
+    // Empty lists have nothing to share.
+    assert_eq!(
+        value.get_path(&path("m_behaviors[0].m_adjectives")),
+        Some(&list([]))
+    );
+}
+
+#[test]
+fn mutation_unshares() {
+    let mut value = template();
+    value.intern();
+
+    value.set_unsigned(&path("m_behaviors[2].m_id"), 7).unwrap();
+
+    assert_eq!(value.get_path(&path("m_behaviors[2]")), Some(&behavior(7)));
+    assert_eq!(value.get_path(&path("m_default")), Some(&behavior(1)));
+    assert_eq!(value.get_path(&path("m_behaviors[0]")), Some(&behavior(1)));
+}
+
+#[test]
+fn intern_deep_tree() {
+    let mut value = Value::Empty;
+    for _ in 0..100_000 {
+        value = list([value]);
+    }
+
+    value.intern();
+    assert!(matches!(value, Value::List(..)));
+}
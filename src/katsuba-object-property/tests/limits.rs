@@ -0,0 +1,90 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use katsuba_object_property::{serde::*, value::PathSegment, Value};
+
+use common::{holder_bytes, holder_types, VALUES_AND_NAME};
+
+fn deserialize(limits: Limits, data: &[u8]) -> Result<Value, Error> {
+    let options = SerializerOptions {
+        limits,
+        ..Default::default()
+    };
+
+    common::serializer(holder_types(VALUES_AND_NAME), options).deserialize::<PropertyClass>(data)
+}
+
+fn limit_error(limits: Limits, data: &[u8]) -> (Limit, String) {
+    match deserialize(limits, data) {
+        Err(Error::LimitExceeded { limit, path }) => (limit, path.to_string()),
+        res => panic!("expected limit error, got {res:?}"),
+    }
+}
+
+#[test]
+fn default_limits_accept() {
+    let data = holder_bytes(&[1, 2, 3], "kobold");
+    deserialize(Limits::default(), &data).unwrap();
+    deserialize(Limits::unlimited(), &data).unwrap();
+}
+
+#[test]
+fn element_limit() {
+    let limits = Limits {
+        max_elements: 2,
+        ..Default::default()
+    };
+    let data = holder_bytes(&[1, 2, 3], "");
+
+    assert_eq!(
+        limit_error(limits, &data),
+        (Limit::Elements, "m_values".to_owned())
+    );
+}
+
+#[test]
+fn total_value_limit() {
+    // The property itself and its first two elements fit.
+    let limits = Limits {
+        max_total_values: 3,
+        ..Default::default()
+    };
+    let data = holder_bytes(&[1, 2, 3], "");
+
+    assert_eq!(
+        limit_error(limits, &data),
+        (Limit::TotalValues, "m_values[2]".to_owned())
+    );
+}
+
+#[test]
+fn string_limit() {
+    let limits = Limits {
+        max_string_len: 4,
+        ..Default::default()
+    };
+    let data = holder_bytes(&[], "kobold");
+
+    assert_eq!(
+        limit_error(limits, &data),
+        (Limit::StringLength, "m_name".to_owned())
+    );
+}
+
+#[test]
+fn depth_limit() {
+    let limits = Limits {
+        max_depth: 1,
+        ..Default::default()
+    };
+    let data = holder_bytes(&[1], "");
+
+    let err = deserialize(limits, &data).unwrap_err();
+    assert_eq!(err.to_string(), "exceeded max_depth limit at m_values");
+    assert!(matches!(
+        err,
+        Error::LimitExceeded { path, .. }
+            if path.segments() == [PathSegment::Property("m_values".into())]
+    ));
+}
@@ -0,0 +1,257 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::sync::Arc;
+
+use katsuba_object_property::serde::*;
+use katsuba_types::TypeList;
+use katsuba_utils::{compress, hash::string_id};
+
+use common::{class, type_list};
+
+fn serializer(options: SerializerOptions) -> Serializer {
+    let types = type_list([
+        class("class Holder", &[], &[("m_values", "int", 24, true)]),
+        class("class Named", &[], &[("m_name", "std::wstring", 24, false)]),
+    ]);
+    common::serializer(types, options)
+}
+
+#[test]
+fn impossible_list_length() {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(u32::MAX.to_le_bytes());
+
+    let err = serializer(SerializerOptions::default())
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(err, Error::Read(e) if e.is_eof()));
+}
+
+#[test]
+fn deep_object_size_underflow() {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(7_u32.to_le_bytes());
+
+    let options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidObjectSize(7)));
+}
+
+#[test]
+fn property_exceeds_object() {
+    // The object claims 64 bits after its size, its property 96.
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(96_u32.to_le_bytes());
+    data.extend(96_u32.to_le_bytes());
+    data.extend(1_u32.to_le_bytes());
+    data.extend(0_u32.to_le_bytes());
+
+    let options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "property 'm_values' of 'class Holder' spans 96 bits, \
+         but only 64 bits of the object remain"
+    );
+}
+
+#[test]
+fn compact_string_length_beyond_data() {
+    // A large compact prefix with the maximum 31-bit length.
+    let mut data = string_id(b"class Named").to_le_bytes().to_vec();
+    data.extend(u32::MAX.to_le_bytes());
+
+    let options = SerializerOptions {
+        flags: SerializerFlags::COMPACT_LENGTH_PREFIXES,
+        limits: Limits::unlimited(),
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Read(katsuba_bit_buf::ReadError::UnexpectedEof {
+            requested,
+            available: 0,
+        }) if requested == (u32::MAX >> 1) as usize * 16
+    ));
+}
+
+#[test]
+fn truncated_compression_prefix() {
+    // The stream is marked as compressed, but ends before its size.
+    let options = SerializerOptions {
+        flags: SerializerFlags::WITH_COMPRESSION,
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&[0x01, 0x10])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::TooShort {
+            size: 1,
+            expected: 4
+        }
+    ));
+}
+
+#[test]
+fn oversized_compressed_stream() {
+    let mut data = u32::MAX.to_le_bytes().to_vec();
+    data.extend([0x78, 0x9C, 0x03, 0x00]);
+
+    let options = SerializerOptions {
+        manual_compression: true,
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Decompress(compress::Error::ImpossibleSize { expected, compressed: 4 })
+            if expected == u32::MAX as usize
+    ));
+}
+
+#[test]
+fn property_size_mismatch_context() {
+    // The property claims 100 bits, but only consumes 96.
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(132_u32.to_le_bytes());
+    data.extend(100_u32.to_le_bytes());
+    data.extend(1_u32.to_le_bytes());
+    data.extend(0_u32.to_le_bytes());
+    data.extend([0xAA, 0xBB]);
+
+    let mut options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "size mismatch for property 'm_values' (int) of 'class Holder' \
+         at bits 64..160: expected 100, got 96"
+    );
+
+    options.verbose_errors = true;
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    let Error::PropertySizeMismatch(mismatch) = &err else {
+        panic!("expected size mismatch, got {err:?}");
+    };
+    assert_eq!(mismatch.context.as_ref().unwrap().0, 12);
+    assert!(err
+        .to_string()
+        .ends_with("bytes at 0xc: 01 00 00 00 00 00 00 00 [aa] bb"));
+}
+
+#[test]
+fn truncated_deep_object() {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(132_u32.to_le_bytes());
+    data.extend(96_u32.to_le_bytes());
+
+    let options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let mut ser = serializer(options);
+    let err = ser.deserialize::<PropertyClass>(&data).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ObjectTooLarge {
+            size: 100,
+            available: 32
+        }
+    ));
+
+    // Decoding stopped right after the object size.
+    assert_eq!(ser.parts.error_offset(), Some(8));
+}
+
+#[test]
+fn trailing_data() {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(0_u32.to_le_bytes());
+
+    let mut ser = serializer(SerializerOptions::default());
+    ser.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(ser.parts.error_offset(), None);
+
+    data.extend([0xDE, 0xAD]);
+    let err = ser.deserialize::<PropertyClass>(&data).unwrap_err();
+    assert!(matches!(err, Error::TrailingData(2)));
+    assert_eq!(ser.parts.error_offset(), Some(8));
+
+    // A deep object which claims no properties leaves them behind.
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    data.extend(32_u32.to_le_bytes());
+    data.extend(96_u32.to_le_bytes());
+    data.extend(1_u32.to_le_bytes());
+    data.extend(0_u32.to_le_bytes());
+
+    let options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let err = serializer(options)
+        .deserialize::<PropertyClass>(&data)
+        .unwrap_err();
+    assert!(matches!(err, Error::TrailingData(12)));
+}
+
+#[test]
+fn colliding_property_hashes() {
+    let types = TypeList::from_str(
+        r#"{
+            "version": 2,
+            "classes": {
+                "755254193": {
+                    "name": "class Holder",
+                    "hash": 755254193,
+                    "properties": {
+                        "m_first": { "type": "int", "id": 0, "flags": 24, "dynamic": false, "hash": 7 },
+                        "m_second": { "type": "int", "id": 1, "flags": 24, "dynamic": false, "hash": 7 }
+                    }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let err = Serializer::new(SerializerOptions::default(), Arc::new(types))
+        .err()
+        .unwrap();
+    match err {
+        Error::PropertyHashCollision {
+            class,
+            hash,
+            mut properties,
+        } => {
+            properties.sort();
+            assert_eq!(class, "class Holder");
+            assert_eq!(hash, 7);
+            assert_eq!(properties, ["m_first", "m_second"]);
+        }
+        err => panic!("unexpected error: {err}"),
+    }
+}
@@ -0,0 +1,22 @@
+#![cfg(feature = "de")]
+
+use katsuba_object_property::serde::{PropertyMask, SerializerOptions};
+
+// The presets are part of the data formats, so any change to them
+// must be deliberate.
+#[test]
+fn preset_values() {
+    assert_eq!(PropertyMask::transmit().flags().bits(), 0x18);
+    assert_eq!(PropertyMask::save().flags().bits(), 0x1);
+    assert_eq!(PropertyMask::copy().flags().bits(), 0x2);
+    assert_eq!(PropertyMask::all().flags().bits(), 0x0);
+}
+
+#[test]
+fn transmit_is_default() {
+    assert_eq!(PropertyMask::default(), PropertyMask::transmit());
+    assert_eq!(
+        SerializerOptions::default().property_mask,
+        PropertyMask::transmit().into()
+    );
+}
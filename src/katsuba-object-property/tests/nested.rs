@@ -0,0 +1,192 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_utils::hash::string_id;
+
+use common::{holder_types, VALUES_AND_NAME};
+
+fn holder(values: &[i64], name: Value) -> Value {
+    let values = Value::List(List {
+        inner: values.iter().copied().map(Value::Signed).collect(),
+    });
+
+    Value::Object {
+        hash: string_id(b"class Holder"),
+        obj: Object {
+            inner: BTreeMap::from([("m_values".into(), values), ("m_name".into(), name)]),
+        },
+    }
+}
+
+fn string(s: &[u8]) -> Value {
+    Value::String(CxxStr(s.to_vec()))
+}
+
+fn serializer(options: SerializerOptions) -> Serializer {
+    common::serializer(holder_types(VALUES_AND_NAME), options)
+}
+
+fn options(decode_nested: bool) -> SerializerOptions {
+    SerializerOptions {
+        decode_nested,
+        ..Default::default()
+    }
+}
+
+// Encodes `inner` the way game files are stored.
+fn bind_blob(inner: &Value) -> Vec<u8> {
+    let mut ser = serializer(SerializerOptions {
+        shallow: false,
+        flags: SerializerFlags::STATEFUL_FLAGS,
+        ..Default::default()
+    });
+
+    let mut data = BIND_MAGIC.to_vec();
+    data.extend(ser.serialize::<PropertyClass>(inner).unwrap());
+    data
+}
+
+fn nested_path() -> Path {
+    "m_name!nested".parse().unwrap()
+}
+
+#[test]
+fn decode_bind_blob() {
+    let inner = holder(&[4, 5], string(b"inner"));
+    let raw = bind_blob(&inner);
+    let data = serializer(options(false))
+        .serialize::<PropertyClass>(&holder(&[], string(&raw)))
+        .unwrap();
+
+    let mut ser = serializer(options(true));
+    let value = ser.deserialize::<PropertyClass>(&data).unwrap();
+
+    let Some(Value::Blob(blob)) = value.get_path(&"m_name".parse().unwrap()) else {
+        panic!("expected a blob, got {value:?}");
+    };
+    assert_eq!(blob.raw.0, raw);
+    assert_eq!(blob.value, inner);
+    assert_eq!(value.get_path(&nested_path()), Some(&inner));
+
+    // Unchanged blobs are written back verbatim.
+    assert_eq!(ser.serialize::<PropertyClass>(&value).unwrap(), data);
+}
+
+#[test]
+fn decode_type_hash_blob() {
+    let inner = holder(&[1], string(b""));
+    let raw = serializer(options(false))
+        .serialize::<PropertyClass>(&inner)
+        .unwrap();
+    let data = serializer(options(false))
+        .serialize::<PropertyClass>(&holder(&[], string(&raw)))
+        .unwrap();
+
+    let value = serializer(options(true))
+        .deserialize::<PropertyClass>(&data)
+        .unwrap();
+    assert_eq!(value.get_path(&nested_path()), Some(&inner));
+}
+
+#[test]
+fn plain_strings_stay_strings() {
+    let outer = holder(&[], string(b"just some text"));
+    let mut ser = serializer(options(true));
+
+    let data = ser.serialize::<PropertyClass>(&outer).unwrap();
+    assert_eq!(ser.deserialize::<PropertyClass>(&data).unwrap(), outer);
+}
+
+#[test]
+fn off_by_default() {
+    let raw = bind_blob(&holder(&[], string(b"")));
+    let outer = holder(&[], string(&raw));
+
+    let mut ser = serializer(SerializerOptions::default());
+    let data = ser.serialize::<PropertyClass>(&outer).unwrap();
+    assert_eq!(ser.deserialize::<PropertyClass>(&data).unwrap(), outer);
+}
+
+#[test]
+fn edit_reencodes_blob() {
+    let raw = bind_blob(&holder(&[4, 5], string(b"inner")));
+    let mut ser = serializer(options(true));
+    let data = ser
+        .serialize::<PropertyClass>(&holder(&[], string(&raw)))
+        .unwrap();
+
+    let mut value = ser.deserialize::<PropertyClass>(&data).unwrap();
+    let path = "m_name!nested.m_values[1]".parse().unwrap();
+    value.set_signed(&path, 42).unwrap();
+
+    let data = ser.serialize::<PropertyClass>(&value).unwrap();
+
+    // The edited blob keeps its game file encoding.
+    let plain = serializer(options(false))
+        .deserialize::<PropertyClass>(&data)
+        .unwrap();
+    let Some(Value::String(edited)) = plain.get_path(&"m_name".parse().unwrap()) else {
+        panic!("expected a string, got {plain:?}");
+    };
+    assert_eq!(edited.0, bind_blob(&holder(&[4, 42], string(b"inner"))));
+
+    let reread = ser.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(reread.get_path(&path), Some(&Value::Signed(42)));
+}
+
+#[test]
+fn limit_paths_include_nesting() {
+    let raw = bind_blob(&holder(&[1, 2, 3], string(b"")));
+    let data = serializer(options(false))
+        .serialize::<PropertyClass>(&holder(&[], string(&raw)))
+        .unwrap();
+
+    let mut ser = serializer(SerializerOptions {
+        limits: Limits {
+            max_elements: 2,
+            ..Default::default()
+        },
+        ..options(true)
+    });
+    let err = ser.deserialize::<PropertyClass>(&data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "exceeded max_elements limit at m_name!nested.m_values"
+    );
+}
+
+#[test]
+fn nested_path_syntax() {
+    let path: Path = "m_name!nested.m_values[0]".parse().unwrap();
+    assert_eq!(
+        path.segments(),
+        [
+            PathSegment::Property("m_name".into()),
+            PathSegment::Nested,
+            PathSegment::Property("m_values".into()),
+            PathSegment::Index(0),
+        ]
+    );
+    assert_eq!(path.to_string(), "m_name!nested.m_values[0]");
+}
+
+#[test]
+fn from_slice_detects_bind() {
+    let types = holder_types(VALUES_AND_NAME);
+    let inner = holder(&[1], string(b"game file"));
+
+    let value = katsuba_object_property::from_slice(&bind_blob(&inner), types.clone()).unwrap();
+    assert_eq!(value, inner);
+
+    // Other data is read with the given options.
+    let options = options(false);
+    let data = serializer(options)
+        .serialize::<PropertyClass>(&inner)
+        .unwrap();
+    let value = katsuba_object_property::from_slice_with(&data, types, options).unwrap();
+    assert_eq!(value, inner);
+}
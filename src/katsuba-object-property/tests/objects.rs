@@ -0,0 +1,155 @@
+#![cfg(feature = "value")]
+
+use katsuba_object_property::value::*;
+
+fn object(hash: u32, props: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Object {
+        hash,
+        obj: Object {
+            inner: props.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+        },
+    }
+}
+
+fn list(values: impl IntoIterator<Item = Value>) -> Value {
+    Value::List(List {
+        inner: values.into_iter().collect(),
+    })
+}
+
+fn shop() -> Value {
+    let nested = Blob {
+        raw: CxxStr(Vec::new()),
+        value: object(3, [("m_goldCost", Value::Signed(5))]),
+    };
+
+    object(
+        1,
+        [
+            ("m_blob", Value::Blob(Box::new(nested))),
+            (
+                "m_items",
+                list([
+                    object(2, [("m_goldCost", Value::Signed(10))]),
+                    object(3, [("m_goldCost", Value::Signed(25))]),
+                ]),
+            ),
+            ("m_map", Value::Map(vec![(Value::Signed(1), object(2, []))])),
+            (
+                "m_pair",
+                Value::Pair(Box::new((object(4, []), Value::Signed(2)))),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn all_objects() {
+    let value = shop();
+    let objects: Vec<_> = value
+        .objects()
+        .map(|(path, hash, _)| (path.to_string(), hash))
+        .collect();
+
+    assert_eq!(
+        objects,
+        [
+            ("<root>".to_string(), 1),
+            ("m_blob!nested".into(), 3),
+            ("m_items[0]".into(), 2),
+            ("m_items[1]".into(), 3),
+            ("m_map[0]!value".into(), 2),
+            ("m_pair!first".into(), 4),
+        ]
+    );
+}
+
+#[test]
+fn objects_in_maps_and_pairs() {
+    let value = shop();
+    for (path, hash, _) in value.objects().skip(4) {
+        assert!(
+            matches!(value.get_path(&path), Some(Value::Object { hash: h, .. }) if *h == hash),
+            "{path}"
+        );
+        assert_eq!(path.to_string().parse::<Path>().unwrap(), path);
+    }
+}
+
+#[test]
+fn object_paths() {
+    let value = shop();
+    let (path, _, obj) = value.objects().nth(3).unwrap();
+
+    assert_eq!(
+        obj.get_path(&"m_goldCost".parse().unwrap()),
+        Some(&Value::Signed(25))
+    );
+    assert_eq!(
+        value
+            .get_path(&path)
+            .and_then(|v| v.get_path(&"m_goldCost".parse().unwrap())),
+        Some(&Value::Signed(25))
+    );
+    assert_eq!(obj.get_path(&Path::new()), None);
+    assert_eq!(obj.get_path(&"[0]".parse().unwrap()), None);
+}
+
+#[test]
+fn deep_nesting() {
+    let mut value = object(7, []);
+    for _ in 0..100_000 {
+        value = list([value]);
+    }
+
+    let objects: Vec<_> = value.objects().collect();
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].0.depth(), 100_000);
+    assert_eq!(objects[0].1, 7);
+}
+
+#[cfg(feature = "de")]
+#[test]
+fn objects_of_class() {
+    use katsuba_types::TypeList;
+    use katsuba_utils::hash::string_id;
+
+    let class = |name: &str, bases: &[&str]| {
+        format!(
+            r#""{}": {{ "name": "class {name}", "bases": {bases:?}, "hash": {}, "properties": {{}} }}"#,
+            string_id(format!("class {name}").as_bytes()),
+            string_id(format!("class {name}").as_bytes()),
+        )
+    };
+    let types = TypeList::from_str(&format!(
+        r#"{{ "version": 2, "classes": {{ {}, {}, {} }} }}"#,
+        class("Template", &[]),
+        class("ItemTemplate", &["class Template"]),
+        class("Shop", &[]),
+    ))
+    .unwrap();
+
+    let hash = |name: &str| string_id(format!("class {name}").as_bytes());
+    let value = object(
+        hash("Shop"),
+        [(
+            "m_items",
+            list([
+                object(hash("ItemTemplate"), []),
+                object(hash("Template"), []),
+                object(hash("Shop"), []),
+            ]),
+        )],
+    );
+
+    let paths = |name: &str| -> Vec<_> {
+        value
+            .objects_of(name, &types)
+            .map(|(path, _, _)| path.to_string())
+            .collect()
+    };
+    assert_eq!(paths("Template"), ["m_items[0]", "m_items[1]"]);
+    assert_eq!(paths("class ItemTemplate"), ["m_items[0]"]);
+    assert_eq!(paths("Shop"), ["<root>", "m_items[2]"]);
+    assert!(paths("Missing").is_empty());
+}
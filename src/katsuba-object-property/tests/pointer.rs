@@ -0,0 +1,142 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+
+use common::{class, type_list, Property};
+
+fn types() -> Arc<TypeList> {
+    let properties: &[Property] = &[
+        ("m_shared", "class SharedPointer<class Base>", 24, false),
+        ("m_ptrs", "class Ptr<class Base>", 24, true),
+    ];
+    let raw_properties: &[Property] = &[
+        ("m_raw", "class Base*", 24, false),
+        ("m_plain", "class Base", 24, false),
+    ];
+
+    type_list([
+        class("class PropertyClass", &[], &[]),
+        class("class Base", &["class PropertyClass"], &[]),
+        class("class Derived", &["class Base"], &[]),
+        class("class Other", &["class PropertyClass"], &[]),
+        class("class Holder", &["class PropertyClass"], properties),
+        class("class RawHolder", &["class PropertyClass"], raw_properties),
+    ])
+}
+
+fn object(name: &str, props: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Object {
+        hash: string_id(name.as_bytes()),
+        obj: Object {
+            inner: props
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect::<BTreeMap<_, _>>(),
+        },
+    }
+}
+
+fn holder(shared: Value, ptrs: Vec<Value>) -> Value {
+    object(
+        "class Holder",
+        [
+            ("m_shared", shared),
+            ("m_ptrs", Value::List(List { inner: ptrs })),
+        ],
+    )
+}
+
+fn serializer(shallow: bool) -> Serializer {
+    let options = SerializerOptions {
+        shallow,
+        ..Default::default()
+    };
+
+    Serializer::new(options, types()).unwrap()
+}
+
+#[test]
+fn roundtrip_pointers() {
+    let values = [
+        holder(Value::Empty, vec![]),
+        holder(
+            object("class Derived", []),
+            vec![Value::Empty, object("class Base", [])],
+        ),
+    ];
+
+    for shallow in [true, false] {
+        let mut ser = serializer(shallow);
+        for value in &values {
+            let data = ser.serialize::<PropertyClass>(value).unwrap();
+            assert_eq!(&ser.deserialize::<PropertyClass>(&data).unwrap(), value);
+        }
+    }
+}
+
+#[test]
+fn reject_unrelated_pointee() {
+    let value = holder(Value::Empty, vec![object("class Other", [])]);
+
+    for shallow in [true, false] {
+        let mut ser = serializer(shallow);
+        let data = ser.serialize::<PropertyClass>(&value).unwrap();
+
+        let err = ser.deserialize::<PropertyClass>(&data).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::PointeeMismatch { expected, found }
+                if expected == "class Base" && found == "class Other"
+        ));
+    }
+}
+
+#[test]
+fn check_declared_classes() {
+    let raw_holder = |raw, plain| object("class RawHolder", [("m_raw", raw), ("m_plain", plain)]);
+    let strict = |shallow| {
+        let options = SerializerOptions {
+            shallow,
+            strict_classes: true,
+            ..Default::default()
+        };
+        Serializer::new(options, types()).unwrap()
+    };
+
+    let fine = raw_holder(object("class Derived", []), object("class Base", []));
+    let unrelated = raw_holder(Value::Empty, object("class Other", []));
+
+    for shallow in [true, false] {
+        let data = strict(shallow).serialize::<PropertyClass>(&fine).unwrap();
+        assert_eq!(
+            strict(shallow).deserialize::<PropertyClass>(&data).unwrap(),
+            fine
+        );
+
+        // Unrelated classes are only a warning unless strict.
+        let data = strict(shallow)
+            .serialize::<PropertyClass>(&unrelated)
+            .unwrap();
+        assert_eq!(
+            serializer(shallow)
+                .deserialize::<PropertyClass>(&data)
+                .unwrap(),
+            unrelated
+        );
+
+        let err = strict(shallow)
+            .deserialize::<PropertyClass>(&data)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::ClassMismatch { expected, found }
+                if expected == "class Base" && found == "class Other"
+        ));
+    }
+}
@@ -0,0 +1,548 @@
+#![cfg(feature = "de")]
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+
+const TYPES: &str = r#"{
+    "version": 2,
+    "classes": {
+        "1158769257": {
+            "name": "class Item",
+            "bases": [],
+            "hash": 1158769257,
+            "properties": {
+                "m_goldCost": {
+                    "type": "int",
+                    "id": 0,
+                    "flags": 24,
+                    "dynamic": false,
+                    "hash": 100
+                },
+                "m_displayName": {
+                    "type": "std::wstring",
+                    "id": 1,
+                    "flags": 24,
+                    "dynamic": false,
+                    "hash": 101
+                },
+                "m_rarity": {
+                    "type": "enum Rarity",
+                    "id": 2,
+                    "flags": 2097176,
+                    "dynamic": false,
+                    "hash": 102,
+                    "enum_options": { "Common": 0, "Rare": 1, "Epic": 2 }
+                },
+                "m_tint": {
+                    "type": "class Color",
+                    "id": 3,
+                    "flags": 280,
+                    "dynamic": false,
+                    "hash": 103
+                },
+                "m_tags": {
+                    "type": "std::string",
+                    "id": 4,
+                    "flags": 24,
+                    "dynamic": true,
+                    "hash": 104
+                },
+                "m_flags": {
+                    "type": "bui4",
+                    "id": 5,
+                    "flags": 24,
+                    "dynamic": false,
+                    "hash": 105
+                },
+                "m_upgrade": {
+                    "type": "class Item*",
+                    "id": 6,
+                    "flags": 24,
+                    "dynamic": false,
+                    "hash": 106
+                }
+            }
+        }
+    }
+}"#;
+
+fn types() -> Arc<TypeList> {
+    Arc::new(TypeList::from_str(TYPES).unwrap())
+}
+
+fn item(cost: i64, name: &str, upgrade: Value) -> Value {
+    let props: [(&str, Value); 7] = [
+        ("m_goldCost", Value::Signed(cost)),
+        (
+            "m_displayName",
+            Value::WString(CxxWStr(name.encode_utf16().collect())),
+        ),
+        ("m_rarity", Value::Enum(2)),
+        (
+            "m_tint",
+            Value::Color(Color {
+                r: 1,
+                g: 2,
+                b: 3,
+                a: 255,
+            }),
+        ),
+        (
+            "m_tags",
+            Value::List(List {
+                inner: vec![
+                    Value::String(CxxStr(b"hat".to_vec())),
+                    Value::String(CxxStr(Vec::new())),
+                ],
+            }),
+        ),
+        ("m_flags", Value::Unsigned(9)),
+        ("m_upgrade", upgrade),
+    ];
+
+    Value::Object {
+        hash: string_id(b"class Item"),
+        obj: Object {
+            inner: props
+                .into_iter()
+                .map(|(k, v)| (k.into(), v))
+                .collect::<BTreeMap<_, _>>(),
+        },
+    }
+}
+
+fn sample() -> Value {
+    item(500, "Cool Hat", item(-1, "Cooler Hat", Value::Empty))
+}
+
+fn roundtrip(options: SerializerOptions, value: &Value) -> Vec<u8> {
+    let mut serializer = Serializer::new(options, types()).unwrap();
+    let data = serializer.serialize::<PropertyClass>(value).unwrap();
+
+    let mut serializer = Serializer::new(options, types()).unwrap();
+    let decoded = serializer.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(&decoded, value);
+
+    // Encoding the decoded value again must be stable.
+    assert_eq!(
+        serializer.serialize::<PropertyClass>(&decoded).unwrap(),
+        data
+    );
+
+    data
+}
+
+#[test]
+fn roundtrip_configurations() {
+    let value = sample();
+
+    for shallow in [true, false] {
+        for flags in [
+            SerializerFlags::empty(),
+            SerializerFlags::COMPACT_LENGTH_PREFIXES,
+            SerializerFlags::HUMAN_READABLE_ENUMS,
+            SerializerFlags::STATEFUL_FLAGS | SerializerFlags::WITH_COMPRESSION,
+        ] {
+            let options = SerializerOptions {
+                flags,
+                shallow,
+                ..Default::default()
+            };
+            roundtrip(options, &value);
+        }
+    }
+
+    let options = SerializerOptions {
+        manual_compression: true,
+        ..Default::default()
+    };
+    roundtrip(options, &value);
+}
+
+#[test]
+fn edit_and_reserialize() {
+    let options = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let mut value = sample();
+
+    let cost: Path = "m_upgrade.m_goldCost".parse().unwrap();
+    value.set_signed(&cost, 750).unwrap();
+    value
+        .set_string(&"m_tags[1]".parse().unwrap(), "rare")
+        .unwrap();
+    roundtrip(options, &value);
+
+    // Deep mode omits properties which are not present.
+    let mut obj = value.clone();
+    let Value::Object { obj: inner, .. } = &mut obj else {
+        unreachable!()
+    };
+    inner.remove("m_tint");
+    roundtrip(options, &obj);
+
+    // Properties must be declared by the class.
+    value
+        .set_path(&"m_upgrade.m_bogus".parse().unwrap(), Value::Bool(true))
+        .unwrap();
+    let err = Serializer::new(options, types())
+        .unwrap()
+        .serialize::<PropertyClass>(&value)
+        .unwrap_err();
+    assert!(matches!(err, Error::UndeclaredProperty(name) if name == "m_bogus"));
+}
+
+#[test]
+fn reject_invalid_values() {
+    let mut serializer = Serializer::new(SerializerOptions::default(), types()).unwrap();
+
+    let mut value = sample();
+    value
+        .set_path(&"m_goldCost".parse().unwrap(), Value::Float(1.0))
+        .unwrap();
+    let err = serializer.serialize::<PropertyClass>(&value).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ValueMismatch { expected, found: "Float" } if expected == "int"
+    ));
+
+    // Shallow mode requires all masked properties to be present.
+    let mut value = sample();
+    if let Value::Object { obj, .. } = &mut value {
+        obj.remove("m_flags");
+    }
+    let err = serializer.serialize::<PropertyClass>(&value).unwrap_err();
+    assert!(matches!(err, Error::MissingProperty(name) if name == "m_flags"));
+
+    let err = serializer
+        .serialize::<PropertyClass>(&Value::Empty)
+        .unwrap_err();
+    assert!(matches!(err, Error::NullRoot));
+}
+
+#[test]
+fn unset_delta_properties() {
+    let tint: Path = "m_tint".parse().unwrap();
+    let mut value = sample();
+    value.set_path(&tint, Value::Unset).unwrap();
+
+    // Shallow mode encodes absence in the presence bit.
+    let shallow = SerializerOptions::default();
+    let unset = roundtrip(shallow, &value);
+    assert!(unset.len() < roundtrip(shallow, &sample()).len());
+
+    // Deep mode leaves the property out entirely.
+    let deep = SerializerOptions {
+        shallow: false,
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(deep, types()).unwrap();
+    let data = serializer.serialize::<PropertyClass>(&value).unwrap();
+    let decoded = serializer.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(decoded.get_path(&tint), None);
+
+    // Streams which enforce delta values reject unset ones.
+    let options = SerializerOptions {
+        flags: SerializerFlags::FORBID_DELTA_ENCODE,
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(options, types()).unwrap();
+    let err = serializer.serialize::<PropertyClass>(&value).unwrap_err();
+    assert!(matches!(err, Error::MissingDelta));
+
+    let mut serializer = Serializer::new(shallow, types()).unwrap();
+    serializer.parts.options.flags = SerializerFlags::FORBID_DELTA_ENCODE;
+    let err = serializer.deserialize::<PropertyClass>(&unset).unwrap_err();
+    assert!(matches!(err, Error::MissingDelta));
+
+    // Only delta-encoded properties may be unset.
+    let mut value = sample();
+    value
+        .set_path(&"m_goldCost".parse().unwrap(), Value::Unset)
+        .unwrap();
+    let err = Serializer::new(shallow, types())
+        .unwrap()
+        .serialize::<PropertyClass>(&value)
+        .unwrap_err();
+    assert!(matches!(err, Error::ValueMismatch { found: "Unset", .. }));
+}
+
+#[test]
+fn interned_values_serialize_identically() {
+    let value = sample();
+    let mut interned = value.clone();
+    interned.intern();
+
+    for shallow in [true, false] {
+        let options = SerializerOptions {
+            shallow,
+            ..Default::default()
+        };
+        let mut serializer = Serializer::new(options, types()).unwrap();
+
+        assert_eq!(
+            serializer.serialize::<PropertyClass>(&interned).unwrap(),
+            serializer.serialize::<PropertyClass>(&value).unwrap()
+        );
+    }
+}
+
+#[test]
+fn filter_root_properties() {
+    let value = sample();
+
+    for shallow in [true, false] {
+        let options = SerializerOptions {
+            shallow,
+            ..Default::default()
+        };
+        let mut serializer = Serializer::new(options, types()).unwrap();
+        let data = serializer.serialize::<PropertyClass>(&value).unwrap();
+
+        let names = vec!["m_goldCost".into(), "m_upgrade".into()];
+        serializer.parts.set_property_filter(Some(names));
+        let filtered = serializer.deserialize::<PropertyClass>(&data).unwrap();
+
+        let Value::Object { obj, .. } = &filtered else {
+            panic!("expected an object");
+        };
+        let keys: Vec<_> = obj.keys().map(|k| k.as_str()).collect();
+        assert_eq!(keys, ["m_goldCost", "m_upgrade"]);
+
+        // Nested objects keep all their properties.
+        let upgrade = "m_upgrade".parse().unwrap();
+        assert_eq!(filtered.get_path(&upgrade), value.get_path(&upgrade));
+
+        serializer.parts.set_property_filter(None);
+        assert_eq!(
+            serializer.deserialize::<PropertyClass>(&data).unwrap(),
+            value
+        );
+    }
+}
+
+#[test]
+fn filter_unset_delta_properties() {
+    let mut value = sample();
+    value
+        .set_path(&"m_tint".parse().unwrap(), Value::Unset)
+        .unwrap();
+
+    let mut serializer = Serializer::new(SerializerOptions::default(), types()).unwrap();
+    let data = serializer.serialize::<PropertyClass>(&value).unwrap();
+
+    // Unset properties are filtered like any other.
+    serializer
+        .parts
+        .set_property_filter(Some(vec!["m_goldCost".into()]));
+    let filtered = serializer.deserialize::<PropertyClass>(&data).unwrap();
+    let Value::Object { obj, .. } = &filtered else {
+        panic!("expected an object");
+    };
+    let keys: Vec<_> = obj.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, ["m_goldCost"]);
+}
+
+#[test]
+fn shallow_properties_in_declared_order() {
+    // Declaration order differs from both alphabetical order and
+    // the order of the entries in the JSON.
+    let types = TypeList::from_str(
+        r#"{
+            "version": 2,
+            "classes": {
+                "1495766180": {
+                    "name": "class Ordered",
+                    "bases": [],
+                    "hash": 1495766180,
+                    "properties": {
+                        "m_alpha": { "type": "int", "id": 2, "flags": 24, "dynamic": false, "hash": 10 },
+                        "m_zeta": { "type": "int", "id": 0, "flags": 24, "dynamic": false, "hash": 11 },
+                        "m_mid": { "type": "int", "id": 1, "flags": 24, "dynamic": false, "hash": 12 }
+                    }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let mut data = string_id(b"class Ordered").to_le_bytes().to_vec();
+    for v in [1i32, 2, 3] {
+        data.extend(v.to_le_bytes());
+    }
+
+    let options = SerializerOptions {
+        shallow: true,
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(options, Arc::new(types)).unwrap();
+    let value = serializer.deserialize::<PropertyClass>(&data).unwrap();
+    assert_eq!(
+        value.get_path(&"m_alpha".parse().unwrap()),
+        Some(&Value::Signed(3))
+    );
+
+    assert_eq!(serializer.serialize::<PropertyClass>(&value).unwrap(), data);
+}
+
+#[test]
+fn null_terminated_wide_strings() {
+    let types = TypeList::from_str(
+        r#"{
+            "version": 2,
+            "classes": {
+                "2080349504": {
+                    "name": "class Named",
+                    "bases": [],
+                    "hash": 2080349504,
+                    "properties": {
+                        "m_name": { "type": "std::wstring", "id": 0, "flags": 24, "dynamic": false, "hash": 300 },
+                        "m_title": { "type": "wchar_t*", "id": 1, "flags": 24, "dynamic": false, "hash": 301 }
+                    }
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+    let types = Arc::new(types);
+
+    let wide = |s: &str| Value::WString(CxxWStr(s.encode_utf16().collect()));
+    let named = |title: &str| Value::Object {
+        hash: 2080349504,
+        obj: Object {
+            inner: [
+                ("m_name".into(), wide("Hat")),
+                ("m_title".into(), wide(title)),
+            ]
+            .into_iter()
+            .collect(),
+        },
+    };
+
+    for shallow in [true, false] {
+        let options = SerializerOptions {
+            shallow,
+            ..Default::default()
+        };
+        let mut serializer = Serializer::new(options, types.clone()).unwrap();
+        let data = serializer
+            .serialize::<PropertyClass>(&named("Sir"))
+            .unwrap();
+        assert_eq!(
+            serializer.deserialize::<PropertyClass>(&data).unwrap(),
+            named("Sir")
+        );
+
+        if shallow {
+            let mut expected = 2080349504u32.to_le_bytes().to_vec();
+            expected.extend([3, 0, b'H', 0, b'a', 0, b't', 0]);
+            expected.extend([b'S', 0, b'i', 0, b'r', 0, 0, 0]);
+            assert_eq!(data, expected);
+        }
+
+        assert!(matches!(
+            serializer.serialize::<PropertyClass>(&named("S\0r")),
+            Err(Error::InteriorNull)
+        ));
+    }
+
+    // Unterminated strings are bounded by the string length limit.
+    let options = SerializerOptions {
+        shallow: true,
+        limits: Limits {
+            max_string_len: 2,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut serializer = Serializer::new(options, types).unwrap();
+    let mut data = 2080349504u32.to_le_bytes().to_vec();
+    data.extend([0, 0]);
+    data.extend([b'S', 0, b'i', 0, b'r', 0, 0, 0]);
+    assert!(matches!(
+        serializer.deserialize::<PropertyClass>(&data),
+        Err(Error::LimitExceeded {
+            limit: Limit::StringLength,
+            ..
+        })
+    ));
+}
+
+// Math types are byte-aligned, even when deep mode leaves the
+// reader in the middle of a byte.
+#[test]
+fn deep_math_types_realign() {
+    let hash = string_id(b"class Transform");
+    let property = |name: &str, ty: &str, id: u32| {
+        format!(
+            r#""{name}": {{ "type": "{ty}", "id": {id}, "flags": 24, "dynamic": false, "hash": {id} }}"#
+        )
+    };
+    let types = format!(
+        r#"{{ "version": 2, "classes": {{ "{hash}": {{ "name": "class Transform", "bases": [], "hash": {hash}, "properties": {{ {}, {}, {}, {}, {} }} }} }} }}"#,
+        property("m_visible", "bool", 1),
+        property("m_position", "class Vector3D", 2),
+        property("m_rotation", "class Quaternion", 3),
+        property("m_angles", "class Euler", 4),
+        property("m_basis", "class Matrix3x3", 5),
+    );
+    let types = Arc::new(TypeList::from_str(&types).unwrap());
+
+    let props = [
+        ("m_visible", Value::Bool(true)),
+        (
+            "m_position",
+            Value::Vec3(Vec3 {
+                x: 1.0,
+                y: 2.0,
+                z: 3.0,
+            }),
+        ),
+        (
+            "m_rotation",
+            Value::Quat(Quaternion {
+                x: 0.5,
+                y: -0.5,
+                z: 0.25,
+                w: 1.0,
+            }),
+        ),
+        (
+            "m_angles",
+            Value::Euler(Euler {
+                pitch: 10.0,
+                yaw: 20.0,
+                roll: 30.0,
+            }),
+        ),
+        (
+            "m_basis",
+            Value::Mat3x3(Box::new(Matrix {
+                i: [1.0, 2.0, 3.0],
+                j: [4.0, 5.0, 6.0],
+                k: [7.0, 8.0, 9.0],
+            })),
+        ),
+    ];
+    let value = Value::Object {
+        hash,
+        obj: Object {
+            inner: props.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+        },
+    };
+
+    for shallow in [true, false] {
+        let options = SerializerOptions {
+            shallow,
+            ..Default::default()
+        };
+        let mut serializer = Serializer::new(options, types.clone()).unwrap();
+        let data = serializer.serialize::<PropertyClass>(&value).unwrap();
+        let decoded = serializer.deserialize::<PropertyClass>(&data).unwrap();
+        assert_eq!(decoded, value);
+    }
+}
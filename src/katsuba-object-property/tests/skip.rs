@@ -0,0 +1,173 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use katsuba_bit_buf::BitReader;
+use katsuba_executor::Executor;
+use katsuba_object_property::{serde::*, value::*};
+use katsuba_utils::hash::string_id;
+
+use common::holder_types;
+
+fn serializer(shallow: bool) -> Serializer {
+    let options = SerializerOptions {
+        shallow,
+        ..Default::default()
+    };
+
+    common::serializer(holder_types(&[("m_values", "int", 24, true)]), options)
+}
+
+fn holder(values: &[i64]) -> Value {
+    let values = Value::List(List {
+        inner: values.iter().copied().map(Value::Signed).collect(),
+    });
+
+    Value::Object {
+        hash: string_id(b"class Holder"),
+        obj: Object {
+            inner: BTreeMap::from([("m_values".into(), values)]),
+        },
+    }
+}
+
+fn concat(shallow: bool, values: &[Value]) -> Vec<u8> {
+    let mut ser = serializer(shallow);
+    values
+        .iter()
+        .flat_map(|v| ser.serialize::<PropertyClass>(v).unwrap())
+        .collect()
+}
+
+#[test]
+fn index_both_modes() {
+    let hash = string_id(b"class Holder");
+
+    for shallow in [true, false] {
+        let data = concat(shallow, &[holder(&[1, 2]), holder(&[])]);
+        let spans = serializer(shallow).index::<PropertyClass>(&data).unwrap();
+
+        let first = spans[0].len;
+        assert_eq!(
+            spans,
+            [
+                ObjectSpan {
+                    hash,
+                    start: 0,
+                    len: first,
+                },
+                ObjectSpan {
+                    hash,
+                    start: first,
+                    len: data.len() * 8 - first,
+                },
+            ]
+        );
+    }
+}
+
+#[test]
+fn skip_unknown_deep_types() {
+    // An object of unknown type with 16 bits of properties.
+    let mut data = 0xDEAD_u32.to_le_bytes().to_vec();
+    data.extend(48_u32.to_le_bytes());
+    data.extend([0xAB, 0xCD]);
+    data.extend(concat(false, &[holder(&[7])]));
+
+    let mut ser = serializer(false);
+    let mut reader = BitReader::new(&data);
+    let unknown = ser.parts.skip_object::<PropertyClass>(&mut reader).unwrap();
+    assert_eq!(
+        unknown,
+        ObjectSpan {
+            hash: 0xDEAD,
+            start: 0,
+            len: 80,
+        }
+    );
+
+    let known = ser.parts.skip_object::<PropertyClass>(&mut reader).unwrap();
+    assert_eq!(known.start, 80);
+    assert_eq!(reader.bit_position(), data.len() * 8);
+
+    // Shallow objects cannot be skipped without their type.
+    let mut reader = BitReader::new(&data);
+    let err = serializer(true)
+        .parts
+        .skip_object::<PropertyClass>(&mut reader)
+        .unwrap_err();
+    assert!(matches!(err, Error::UnknownType(0xDEAD)));
+}
+
+#[test]
+fn deserialize_spans_in_order() {
+    let values: Vec<_> = (0..10).map(|i| holder(&vec![i; i as usize])).collect();
+
+    for shallow in [true, false] {
+        let data = concat(shallow, &values);
+        let mut ser = serializer(shallow);
+        let spans = ser.index::<PropertyClass>(&data).unwrap();
+
+        for ex in [
+            Executor::current(),
+            Executor::threaded(3),
+            Executor::threaded(16),
+        ] {
+            let list = ser
+                .deserialize_spans::<PropertyClass>(&ex, &data, &spans)
+                .unwrap();
+            assert_eq!(
+                list,
+                Value::List(List {
+                    inner: values.clone()
+                })
+            );
+        }
+
+        // Spans beyond the data are rejected.
+        let bogus = ObjectSpan {
+            hash: 0,
+            start: data.len() * 8,
+            len: 8,
+        };
+        let err = ser
+            .deserialize_spans::<PropertyClass>(&Executor::threaded(2), &data, &[bogus])
+            .unwrap_err();
+        assert!(matches!(err, Error::ObjectTooLarge { .. }));
+    }
+}
+
+#[test]
+fn deserialize_all_roots() {
+    let values: Vec<_> = (0..4).map(|i| holder(&vec![i; i as usize])).collect();
+
+    for shallow in [true, false] {
+        let data = concat(shallow, &values);
+        let all = serializer(shallow)
+            .deserialize_all::<PropertyClass>(&data)
+            .unwrap();
+        assert_eq!(all, values);
+
+        // Failures name the object and where it starts.
+        let first = concat(shallow, &values[..1]);
+        let mut data = first.clone();
+        data.extend(0xDEAD_u32.to_le_bytes());
+        data.extend([0; 8]);
+        let err = serializer(shallow)
+            .deserialize_all::<PropertyClass>(&data)
+            .unwrap_err();
+        match err {
+            Error::Root {
+                index,
+                offset,
+                error,
+            } => {
+                assert_eq!((index, offset), (1, first.len()));
+                assert!(matches!(*error, Error::UnknownType(0xDEAD)));
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+}
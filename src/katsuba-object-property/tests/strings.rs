@@ -0,0 +1,88 @@
+#![cfg(feature = "de")]
+
+mod common;
+
+use katsuba_object_property::{
+    serde::*,
+    value::{CxxStr, Value},
+};
+use katsuba_utils::hash::string_id;
+
+use common::{holder_types, Property};
+
+fn string(data: &mut Vec<u8>, s: &[u8]) {
+    data.extend((s.len() as u16).to_le_bytes());
+    data.extend(s);
+}
+
+fn holder(name: &[u8], tags: &[&[u8]]) -> Vec<u8> {
+    let mut data = string_id(b"class Holder").to_le_bytes().to_vec();
+    string(&mut data, name);
+    data.extend((tags.len() as u32).to_le_bytes());
+    for tag in tags {
+        string(&mut data, tag);
+    }
+    data
+}
+
+const PROPERTIES: &[Property] = &[
+    ("m_name", "std::string", 24, false),
+    ("m_tags", "std::string", 24, true),
+];
+
+fn serializer(strict_strings: bool) -> Serializer {
+    let options = SerializerOptions {
+        strict_strings,
+        ..Default::default()
+    };
+
+    common::serializer(holder_types(PROPERTIES), options)
+}
+
+fn invalid(ser: &Serializer) -> Vec<(String, usize)> {
+    ser.parts
+        .invalid_strings()
+        .iter()
+        .map(|s| (s.path.to_string(), s.offset))
+        .collect()
+}
+
+#[test]
+fn report_invalid_strings() {
+    let data = holder(b"ok\xFF", &[b"fine", b"\xC3\x28bad"]);
+
+    let mut ser = serializer(true);
+    let value = ser.deserialize::<PropertyClass>(&data).unwrap();
+    // Offsets point at the first invalid byte of each string.
+    assert_eq!(
+        invalid(&ser),
+        [("m_name".to_string(), 8), ("m_tags[1]".into(), 21)]
+    );
+
+    // The strings are still deserialized as they are.
+    assert_eq!(
+        value.get_path(&"m_name".parse().unwrap()),
+        Some(&Value::String(CxxStr(b"ok\xFF".to_vec())))
+    );
+
+    // Valid data and disabled checks report nothing.
+    let mut ser = serializer(true);
+    ser.deserialize::<PropertyClass>(&holder(b"ok", &[b"fine"]))
+        .unwrap();
+    assert!(invalid(&ser).is_empty());
+
+    let mut ser = serializer(false);
+    ser.deserialize::<PropertyClass>(&data).unwrap();
+    assert!(invalid(&ser).is_empty());
+}
+
+#[test]
+fn report_invalid_strings_in_all_roots() {
+    let first = holder(b"ok", &[]);
+    let mut data = first.clone();
+    data.extend(holder(b"\xFF", &[]));
+
+    let mut ser = serializer(true);
+    ser.deserialize_all::<PropertyClass>(&data).unwrap();
+    assert_eq!(invalid(&ser), [("[1].m_name".to_string(), first.len() + 6)]);
+}
@@ -0,0 +1,18 @@
+#![cfg(feature = "value")]
+
+use katsuba_object_property::value::Time;
+
+#[test]
+fn iso8601_in_utc() {
+    let cases = [
+        (0, "1970-01-01T00:00:00Z"),
+        (-1, "1969-12-31T23:59:59Z"),
+        (951782400, "2000-02-29T00:00:00Z"),
+        (2147483647, "2038-01-19T03:14:07Z"),
+        (-11644473600, "1601-01-01T00:00:00Z"),
+    ];
+
+    for (raw, iso) in cases {
+        assert_eq!(Time::unix_seconds(raw).to_iso8601(), iso, "{raw}");
+    }
+}
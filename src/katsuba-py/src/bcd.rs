@@ -0,0 +1,235 @@
+use std::{io, sync::Arc};
+
+use katsuba_bcd::GeomParams;
+use pyo3::{prelude::*, types::PyDict};
+
+use crate::error;
+
+#[pyclass(module = "katsuba.bcd")]
+pub struct Bcd(Arc<katsuba_bcd::Bcd>);
+
+#[pymethods]
+impl Bcd {
+    pub fn __len__(&self) -> usize {
+        self.0.collisions.len()
+    }
+
+    #[getter]
+    pub fn collisions(&self) -> Vec<Collision> {
+        (0..self.0.collisions.len())
+            .map(|idx| Collision {
+                bcd: self.0.clone(),
+                idx,
+            })
+            .collect()
+    }
+}
+
+#[pyclass(module = "katsuba.bcd")]
+pub struct Collision {
+    bcd: Arc<katsuba_bcd::Bcd>,
+    idx: usize,
+}
+
+impl Collision {
+    #[inline(always)]
+    fn get_ref(&self) -> &katsuba_bcd::Collision {
+        &self.bcd.collisions[self.idx]
+    }
+}
+
+#[pymethods]
+impl Collision {
+    #[getter]
+    pub fn category_flags(&self) -> u32 {
+        self.get_ref().category_flags.bits()
+    }
+
+    #[getter]
+    pub fn collision_flags(&self) -> u32 {
+        self.get_ref().collision_flags.bits()
+    }
+
+    #[getter]
+    pub fn geometry(&self) -> ProxyGeometry {
+        ProxyGeometry {
+            bcd: self.bcd.clone(),
+            idx: self.idx,
+        }
+    }
+
+    #[getter]
+    pub fn mesh(&self) -> Option<ProxyMesh> {
+        self.get_ref().mesh.as_ref().map(|_| ProxyMesh {
+            bcd: self.bcd.clone(),
+            idx: self.idx,
+        })
+    }
+}
+
+#[pyclass(module = "katsuba.bcd")]
+pub struct ProxyGeometry {
+    bcd: Arc<katsuba_bcd::Bcd>,
+    idx: usize,
+}
+
+impl ProxyGeometry {
+    #[inline(always)]
+    fn get_ref(&self) -> &katsuba_bcd::ProxyGeometry {
+        &self.bcd.collisions[self.idx].geometry
+    }
+}
+
+#[pymethods]
+impl ProxyGeometry {
+    #[getter]
+    pub fn name(&self) -> &str {
+        &self.get_ref().name
+    }
+
+    #[getter]
+    pub fn rotation(&self) -> [[f32; 3]; 3] {
+        self.get_ref().rotation
+    }
+
+    #[getter]
+    pub fn location(&self) -> [f32; 3] {
+        self.get_ref().location
+    }
+
+    #[getter]
+    pub fn scale(&self) -> f32 {
+        self.get_ref().scale
+    }
+
+    #[getter]
+    pub fn material(&self) -> &str {
+        &self.get_ref().material
+    }
+
+    /// The kind of shape, e.g. `"box"` or `"mesh"`.
+    #[getter]
+    pub fn shape(&self) -> &'static str {
+        match self.get_ref().params {
+            GeomParams::Box { .. } => "box",
+            GeomParams::Ray { .. } => "ray",
+            GeomParams::Sphere { .. } => "sphere",
+            GeomParams::Cylinder { .. } => "cylinder",
+            GeomParams::Tube { .. } => "tube",
+            GeomParams::Plane { .. } => "plane",
+            GeomParams::Mesh => "mesh",
+        }
+    }
+
+    /// The shape-specific parameters as a dict.
+    #[getter]
+    pub fn params<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+        match self.get_ref().params {
+            GeomParams::Box {
+                length,
+                width,
+                depth,
+            } => {
+                dict.set_item("length", length)?;
+                dict.set_item("width", width)?;
+                dict.set_item("depth", depth)?;
+            }
+            GeomParams::Ray {
+                position,
+                direction,
+                length,
+            } => {
+                dict.set_item("position", position)?;
+                dict.set_item("direction", direction)?;
+                dict.set_item("length", length)?;
+            }
+            GeomParams::Sphere { radius } => {
+                dict.set_item("radius", radius)?;
+            }
+            GeomParams::Cylinder { radius, length } | GeomParams::Tube { radius, length } => {
+                dict.set_item("radius", radius)?;
+                dict.set_item("length", length)?;
+            }
+            GeomParams::Plane { normal, distance } => {
+                dict.set_item("normal", normal)?;
+                dict.set_item("distance", distance)?;
+            }
+            GeomParams::Mesh => {}
+        }
+
+        Ok(dict)
+    }
+}
+
+#[pyclass(module = "katsuba.bcd")]
+pub struct ProxyMesh {
+    bcd: Arc<katsuba_bcd::Bcd>,
+    idx: usize,
+}
+
+impl ProxyMesh {
+    #[inline(always)]
+    fn get_ref(&self) -> &katsuba_bcd::ProxyMesh {
+        // Only constructed for collisions which have a mesh.
+        self.bcd.collisions[self.idx].mesh.as_ref().unwrap()
+    }
+}
+
+#[pymethods]
+impl ProxyMesh {
+    #[getter]
+    pub fn vertices(&self) -> Vec<[f32; 3]> {
+        self.get_ref().vertices.clone()
+    }
+
+    /// The vertex indices of every face.
+    #[getter]
+    pub fn faces(&self) -> Vec<[u32; 3]> {
+        self.get_ref().faces.iter().map(|f| f.face).collect()
+    }
+
+    /// The normal vectors of every face.
+    #[getter]
+    pub fn normals(&self) -> Vec<[f32; 3]> {
+        self.get_ref().faces.iter().map(|f| f.normal).collect()
+    }
+
+    /// Gets the vertices as a numpy array of shape `(N, 3)`.
+    #[cfg(feature = "numpy")]
+    pub fn vertices_array<'py>(&self, py: Python<'py>) -> &'py numpy::PyArray2<f32> {
+        numpy::PyArray2::from_owned_array(py, to_array(&self.get_ref().vertices))
+    }
+
+    /// Gets the face indices as a numpy array of shape `(N, 3)`.
+    #[cfg(feature = "numpy")]
+    pub fn faces_array<'py>(&self, py: Python<'py>) -> &'py numpy::PyArray2<u32> {
+        let faces: Vec<_> = self.get_ref().faces.iter().map(|f| f.face).collect();
+        numpy::PyArray2::from_owned_array(py, to_array(&faces))
+    }
+}
+
+#[cfg(feature = "numpy")]
+fn to_array<T: Copy>(rows: &[[T; 3]]) -> numpy::ndarray::Array2<T> {
+    let flat = rows.iter().flatten().copied().collect();
+    numpy::ndarray::Array2::from_shape_vec((rows.len(), 3), flat).unwrap()
+}
+
+/// Parses a BCD file from its raw bytes.
+#[pyfunction]
+pub fn parse(py: Python<'_>, data: &[u8]) -> PyResult<Bcd> {
+    py.allow_threads(|| katsuba_bcd::Bcd::parse(io::Cursor::new(data)))
+        .map(|bcd| Bcd(Arc::new(bcd)))
+        .map_err(error::binrw_to_py_err)
+}
+
+pub fn katsuba_bcd(m: &PyModule) -> PyResult<()> {
+    m.add_class::<Bcd>()?;
+    m.add_class::<Collision>()?;
+    m.add_class::<ProxyGeometry>()?;
+    m.add_class::<ProxyMesh>()?;
+
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+
+    Ok(())
+}
@@ -1,12 +1,52 @@
+use std::io;
+
 use katsuba_object_property::serde::Error as OpError;
-use katsuba_wad::ArchiveError;
-use pyo3::prelude::*;
+use katsuba_utils::{binrw, compress};
+use katsuba_wad::{extract::ExtractError, ArchiveError};
+use pyo3::{create_exception, prelude::*};
 
 use crate::KatsubaError;
 
+create_exception!(katsuba, UnknownTypeError, KatsubaError);
+create_exception!(katsuba, DecompressionError, KatsubaError);
+create_exception!(katsuba, SizeMismatchError, KatsubaError);
+create_exception!(katsuba, ArchiveCorruptError, KatsubaError);
+create_exception!(katsuba, ParseError, KatsubaError);
+
+/// Registers the exception types in the given module.
+pub fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("KatsubaError", py.get_type::<KatsubaError>())?;
+    m.add("UnknownTypeError", py.get_type::<UnknownTypeError>())?;
+    m.add("DecompressionError", py.get_type::<DecompressionError>())?;
+    m.add("SizeMismatchError", py.get_type::<SizeMismatchError>())?;
+    m.add("ArchiveCorruptError", py.get_type::<ArchiveCorruptError>())?;
+    m.add("ParseError", py.get_type::<ParseError>())?;
+
+    Ok(())
+}
+
+fn unknown_type_err(msg: String, hash: u32) -> PyErr {
+    Python::with_gil(|py| {
+        let err = UnknownTypeError::new_err(msg);
+        match err.value(py).setattr("type_hash", hash) {
+            Ok(()) => err,
+            Err(e) => e,
+        }
+    })
+}
+
 pub fn op_to_py_err(err: OpError) -> PyErr {
     match err {
         OpError::Io(e) => e.into(),
+        OpError::UnknownType(hash) => unknown_type_err(format!("{err}"), hash),
+        OpError::Decompress(compress::Error::SizeMismatch { .. }) => {
+            SizeMismatchError::new_err(format!("{err}"))
+        }
+        OpError::Decompress(..) => DecompressionError::new_err(format!("{err}")),
+        OpError::PropertySizeMismatch(..)
+        | OpError::ObjectSizeMismatch { .. }
+        | OpError::InvalidObjectSize(..)
+        | OpError::ObjectTooLarge { .. } => SizeMismatchError::new_err(format!("{err}")),
         e => KatsubaError::new_err(format!("{e}")),
     }
 }
@@ -14,6 +54,66 @@ pub fn op_to_py_err(err: OpError) -> PyErr {
 pub fn wad_to_py_err(err: ArchiveError) -> PyErr {
     match err {
         ArchiveError::Io(e) => e.into(),
+        ArchiveError::Zlib(..) => DecompressionError::new_err(format!("{err}")),
+        ArchiveError::AtPath { .. } => match err.inner() {
+            // Keep the exception type for the error kind, but mention
+            // the path in the message.
+            ArchiveError::Io(e) => io::Error::new(e.kind(), err.to_string()).into(),
+            ArchiveError::Zlib(..) => DecompressionError::new_err(format!("{err}")),
+            _ => ArchiveCorruptError::new_err(format!("{err}")),
+        },
+        _ => ArchiveCorruptError::new_err(format!("{err}")),
+    }
+}
+
+/// Makes the byte offset at which deserialization failed available
+/// as the `offset` attribute of `err`, or [`None`] if unknown.
+pub fn with_offset(py: Python<'_>, err: PyErr, offset: Option<usize>) -> PyErr {
+    match err.value(py).setattr("offset", offset) {
+        Ok(()) => err,
+        Err(e) => e,
+    }
+}
+
+/// Prefixes the message of `err` with the archive entry it
+/// originated from.
+///
+/// The path is also made available as the `entry_path` attribute.
+pub fn with_entry_path(py: Python<'_>, err: PyErr, path: &str) -> PyErr {
+    let value = err.value(py);
+    let msg = format!("{path}: {value}");
+
+    match value
+        .setattr("args", (msg,))
+        .and_then(|()| value.setattr("entry_path", path))
+    {
+        Ok(()) => err,
+        Err(e) => e,
+    }
+}
+
+pub fn binrw_to_py_err(err: binrw::Error) -> PyErr {
+    // binrw's own formatting of nested errors is meant for terminals,
+    // so we boil it down to a plain one-line description.
+    let msg = if err.is_eof() {
+        "unexpected end of input".to_string()
+    } else {
+        match err.root_cause() {
+            binrw::Error::EnumErrors { pos, .. } | binrw::Error::NoVariantMatch { pos } => {
+                format!("unknown variant at {pos:#x}")
+            }
+            cause => format!("{cause}"),
+        }
+    };
+
+    ParseError::new_err(msg)
+}
+
+pub fn extract_to_py_err(err: ExtractError) -> PyErr {
+    match err {
+        ExtractError::Archive(e) => wad_to_py_err(e),
+        ExtractError::MissingContents(..) => ArchiveCorruptError::new_err(format!("{err}")),
+        ExtractError::Io(e) => e.into(),
         e => KatsubaError::new_err(format!("{e}")),
     }
 }
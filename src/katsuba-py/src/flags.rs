@@ -0,0 +1,94 @@
+//! `enum.IntFlag` classes generated from the Rust flag types.
+
+use bitflags::Flags;
+use katsuba_object_property::serde::SerializerFlags;
+use katsuba_types::PropertyFlags;
+use pyo3::{exceptions::PyValueError, prelude::*, sync::GILOnceCell, types::IntoPyDict};
+
+/// A Rust flags type which is exposed as an `IntFlag` class.
+pub trait PyFlags: Flags<Bits = u32> {
+    /// The name of the Python class.
+    const NAME: &'static str;
+
+    /// The lazily created Python class.
+    fn class_cell() -> &'static GILOnceCell<PyObject>;
+}
+
+impl PyFlags for SerializerFlags {
+    const NAME: &'static str = "SerializerFlags";
+
+    fn class_cell() -> &'static GILOnceCell<PyObject> {
+        static CLASS: GILOnceCell<PyObject> = GILOnceCell::new();
+        &CLASS
+    }
+}
+
+impl PyFlags for PropertyFlags {
+    const NAME: &'static str = "PropertyFlags";
+
+    fn class_cell() -> &'static GILOnceCell<PyObject> {
+        static CLASS: GILOnceCell<PyObject> = GILOnceCell::new();
+        &CLASS
+    }
+}
+
+/// Gets the `IntFlag` class for `F`, with one member per named bit.
+pub fn class<F: PyFlags>(py: Python<'_>) -> PyResult<&PyAny> {
+    let class = F::class_cell().get_or_try_init(py, || {
+        // Flags spanning several bits are internal shorthands.
+        let members: Vec<(&str, u32)> = F::FLAGS
+            .iter()
+            .map(|f| (f.name(), f.value().bits()))
+            .filter(|(_, bits)| bits.is_power_of_two())
+            .collect();
+
+        let kwargs = [("module", "katsuba")].into_py_dict(py);
+        let class = py
+            .import("enum")?
+            .getattr("IntFlag")?
+            .call((F::NAME, members), Some(kwargs))?;
+        PyResult::Ok(class.to_object(py))
+    })?;
+
+    Ok(class.as_ref(py))
+}
+
+/// Converts `flags` into an instance of its `IntFlag` class.
+pub fn to_py<F: PyFlags>(py: Python<'_>, flags: F) -> PyResult<PyObject> {
+    class::<F>(py)?.call1((flags.bits(),)).map(Into::into)
+}
+
+/// Flags as accepted from Python.
+///
+/// This is either an int, which includes `IntFlag` members, or a list
+/// of member names.
+#[derive(FromPyObject)]
+pub enum FlagsArg {
+    Bits(u32),
+    Names(Vec<String>),
+}
+
+impl FlagsArg {
+    /// Converts the argument into flags of type `F`.
+    ///
+    /// Unknown bits are ignored, but unknown names are an error.
+    pub fn into_flags<F: PyFlags>(self) -> PyResult<F> {
+        match self {
+            Self::Bits(bits) => Ok(F::from_bits_truncate(bits)),
+            Self::Names(names) => names.iter().try_fold(F::empty(), |flags, name| {
+                let flag = F::from_name(name).ok_or_else(|| {
+                    PyValueError::new_err(format!("'{name}' is not a member of {}", F::NAME))
+                })?;
+                Ok(flags.union(flag))
+            }),
+        }
+    }
+}
+
+/// Adds the flag classes to `m`.
+pub fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add(SerializerFlags::NAME, class::<SerializerFlags>(py)?)?;
+    m.add(PropertyFlags::NAME, class::<PropertyFlags>(py)?)?;
+
+    Ok(())
+}
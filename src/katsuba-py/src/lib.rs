@@ -4,8 +4,13 @@
 //! library here to reduce complexity.
 
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
+// Expansions of the pyo3 macros trip these on newer compilers.
+#![allow(non_local_definitions, unexpected_cfgs)]
 
+mod bcd;
 mod error;
+mod flags;
+mod nav;
 mod op;
 mod utils;
 mod wad;
@@ -18,15 +23,20 @@ create_exception!(katsuba, KatsubaError, PyException);
 #[pymodule]
 pub fn katsuba(py: Python<'_>, module: &PyModule) -> PyResult<()> {
     // Bind the exception types utilized on the Python side.
-    module.add("KatsubaError", py.get_type::<KatsubaError>())?;
+    error::register(py, module)?;
+    flags::register(py, module)?;
 
     // Declare all the submodules in the package.
+    let bcd = PyModule::new(py, "bcd")?;
+    let nav = PyModule::new(py, "nav")?;
     let op = PyModule::new(py, "op")?;
     let utils = PyModule::new(py, "utils")?;
     let wad = PyModule::new(py, "wad")?;
 
     // Enable `from katsuba_py.x import A` imports.
     let locals = [
+        ("bcd", bcd.to_object(py)),
+        ("nav", nav.to_object(py)),
         ("op", op.to_object(py)),
         ("utils", utils.to_object(py)),
         ("wad", wad.to_object(py)),
@@ -35,6 +45,8 @@ pub fn katsuba(py: Python<'_>, module: &PyModule) -> PyResult<()> {
     py.run(
         r#"
 import sys
+sys.modules['katsuba.bcd'] = bcd
+sys.modules['katsuba.nav'] = nav
 sys.modules['katsuba.op'] = op
 sys.modules['katsuba.utils'] = utils
 sys.modules['katsuba.wad'] = wad
@@ -43,6 +55,14 @@ sys.modules['katsuba.wad'] = wad
         Some(locals),
     )?;
 
+    // Register katsuba_py.bcd module.
+    bcd::katsuba_bcd(bcd)?;
+    module.add_submodule(bcd)?;
+
+    // Register katsuba_py.nav module.
+    nav::katsuba_nav(nav)?;
+    module.add_submodule(nav)?;
+
     // Register katsuba_py.op module.
     op::katsuba_op(op)?;
     module.add_submodule(op)?;
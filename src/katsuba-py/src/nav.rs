@@ -0,0 +1,95 @@
+use std::io;
+
+use pyo3::prelude::*;
+
+use crate::error;
+
+#[derive(Clone)]
+#[pyclass(module = "katsuba.nav")]
+pub struct NavigationNode {
+    #[pyo3(get)]
+    pub location: [f32; 3],
+    #[pyo3(get)]
+    pub id: u16,
+}
+
+#[derive(Clone)]
+#[pyclass(module = "katsuba.nav")]
+pub struct NavigationLink {
+    #[pyo3(get)]
+    pub first: u16,
+    #[pyo3(get)]
+    pub second: u16,
+}
+
+#[pyclass(module = "katsuba.nav")]
+pub struct NavigationGraph {
+    #[pyo3(get)]
+    pub nodes: Vec<NavigationNode>,
+    #[pyo3(get)]
+    pub links: Vec<NavigationLink>,
+}
+
+impl From<katsuba_nav::NavigationGraph> for NavigationGraph {
+    fn from(graph: katsuba_nav::NavigationGraph) -> Self {
+        Self {
+            nodes: graph
+                .nodes
+                .into_iter()
+                .map(|n| NavigationNode {
+                    location: n.location,
+                    id: n.id,
+                })
+                .collect(),
+            links: graph
+                .links
+                .into_iter()
+                .map(|l| NavigationLink {
+                    first: l.first,
+                    second: l.second,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[pyclass(module = "katsuba.nav")]
+pub struct ZoneNavigationGraph {
+    #[pyo3(get)]
+    pub graph: Py<NavigationGraph>,
+    #[pyo3(get)]
+    pub zone_names: Vec<String>,
+}
+
+/// Parses a NAV file from its raw bytes.
+#[pyfunction]
+pub fn parse(py: Python<'_>, data: &[u8]) -> PyResult<NavigationGraph> {
+    py.allow_threads(|| katsuba_nav::NavigationGraph::parse(io::Cursor::new(data)))
+        .map(Into::into)
+        .map_err(error::binrw_to_py_err)
+}
+
+/// Parses a zone NAV file from its raw bytes.
+#[pyfunction]
+pub fn parse_zone(py: Python<'_>, data: &[u8]) -> PyResult<ZoneNavigationGraph> {
+    let zone = py
+        .allow_threads(|| katsuba_nav::ZoneNavigationGraph::parse(io::Cursor::new(data)))
+        .map_err(error::binrw_to_py_err)?;
+
+    Ok(ZoneNavigationGraph {
+        graph: Py::new(py, NavigationGraph::from(zone.graph))?,
+        zone_names: zone.zone_names,
+    })
+}
+
+pub fn katsuba_nav(m: &PyModule) -> PyResult<()> {
+    m.add_class::<NavigationNode>()?;
+    m.add_class::<NavigationLink>()?;
+    m.add_class::<NavigationGraph>()?;
+    m.add_class::<ZoneNavigationGraph>()?;
+
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_zone, m)?)?;
+
+    Ok(())
+}
@@ -1,21 +1,50 @@
-use std::{fs, io, path::PathBuf, sync::Arc};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use katsuba_object_property::{
     serde::{self, SerializerFlags},
     Value,
 };
-use pyo3::{prelude::*, types::PyType};
+use katsuba_types::PropertyFlags;
+use katsuba_utils::fs as kfs;
+use pyo3::{
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+    types::PyType,
+};
 
-use crate::{error, KatsubaError};
+use crate::{
+    error,
+    flags::{self, FlagsArg},
+    KatsubaError,
+};
 
 mod conversion;
 
+mod json;
+
 mod lazy;
 pub use lazy::*;
 
+mod many;
+
 mod leaf_types;
 pub use leaf_types::*;
 
+#[cfg(feature = "numpy")]
+mod array;
+
+mod pickle;
+
+mod tree;
+use tree::TreeRef;
+
+mod walk;
+pub use walk::ObjectWalker;
+
 #[derive(Clone)]
 #[pyclass(module = "katsuba.op")]
 pub struct TypeList(Arc<katsuba_types::TypeList>);
@@ -31,10 +60,161 @@ impl TypeList {
 
     #[classmethod]
     pub fn open(_cls: &PyType, path: PathBuf) -> PyResult<Self> {
-        let file = fs::File::open(path)?;
-        katsuba_types::TypeList::from_reader(io::BufReader::new(file))
-            .map(|v| Self(Arc::new(v)))
-            .map_err(|e| KatsubaError::new_err(e.to_string()))
+        read_type_list(path).map(|v| Self(Arc::new(v)))
+    }
+
+    #[classmethod]
+    pub fn open_many(_cls: &PyType, paths: Vec<PathBuf>) -> PyResult<Self> {
+        let mut paths = paths.into_iter();
+        let mut list = match paths.next() {
+            Some(path) => read_type_list(path)?,
+            None => return Err(KatsubaError::new_err("at least one type list is required")),
+        };
+
+        // Merge remaining type lists into `list`.
+        for path in paths {
+            list.merge(read_type_list(path)?);
+        }
+
+        Ok(Self(Arc::new(list)))
+    }
+
+    /// Opens type lists through the binary cache at `cache`.
+    ///
+    /// The cache is used as long as it is newer than all of `paths`.
+    /// Otherwise, the JSON files are merged like with `open_many` and
+    /// the cache is rebuilt from the result.
+    #[classmethod]
+    #[pyo3(signature = (cache, paths=Vec::new()))]
+    pub fn open_cache(_cls: &PyType, cache: PathBuf, paths: Vec<PathBuf>) -> PyResult<Self> {
+        if cache_is_fresh(&cache, &paths)? {
+            let file = fs::File::open(&cache)?;
+            return katsuba_types::TypeList::read_cache(io::BufReader::new(file))
+                .map(|v| Self(Arc::new(v)))
+                .map_err(|e| KatsubaError::new_err(format!("invalid type list cache: {e}")));
+        }
+
+        let mut paths = paths.into_iter();
+        let mut list = match paths.next() {
+            Some(path) => read_type_list(path)?,
+            None => return Err(KatsubaError::new_err("type list cache does not exist yet")),
+        };
+        for path in paths {
+            list.merge(read_type_list(path)?);
+        }
+
+        let mut data = Vec::new();
+        list.write_cache(&mut data)?;
+
+        // An interrupted write must not leave a truncated cache behind.
+        kfs::atomic_write(&cache, &data, true)?;
+
+        Ok(Self(Arc::new(list)))
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.0 .0.len()
+    }
+
+    pub fn __contains__(&self, hash: u32) -> bool {
+        self.0 .0.contains_key(&hash)
+    }
+
+    /// Gets the name of the type with the given hash, if any.
+    pub fn name_for(&self, hash: u32) -> Option<&str> {
+        self.0 .0.get(&hash).map(|def| def.name.as_str())
+    }
+
+    /// Gets the hash of the type with the given name, if any.
+    ///
+    /// The `class ` prefix may be omitted from class names.
+    pub fn hash_for(&self, name: &str) -> Option<u32> {
+        self.0.find(name).map(|(hash, _)| hash)
+    }
+
+    /// Gets `(hash, name)` pairs for all types in the list.
+    pub fn classes(&self) -> Vec<(u32, &str)> {
+        let types = &self.0 .0;
+        types
+            .iter()
+            .map(|(&hash, def)| (hash, def.name.as_str()))
+            .collect()
+    }
+
+    /// Gets `(name, type, flags, hash)` tuples for the properties
+    /// of the given type, in serialization order.
+    pub fn properties(&self, name: &str) -> PyResult<Vec<(&str, &str, u32, u32)>> {
+        let (_, def) = self
+            .0
+            .find(name)
+            .ok_or_else(|| PyKeyError::new_err(name.to_string()))?;
+
+        Ok(def
+            .property_order()
+            .map(|p| (p.name.as_str(), p.r#type.as_str(), p.flags.bits(), p.hash))
+            .collect())
+    }
+
+    /// Gets the names of all types deriving from the given one.
+    ///
+    /// This requires a type list which records base classes.
+    pub fn subclasses_of(&self, name: &str) -> Vec<&str> {
+        self.0
+            .subclasses_of(name)
+            .into_iter()
+            .map(|hash| self.0 .0[&hash].name.as_str())
+            .collect()
+    }
+}
+
+fn read_type_list(path: PathBuf) -> PyResult<katsuba_types::TypeList> {
+    let file = fs::File::open(path)?;
+    katsuba_types::TypeList::from_reader(io::BufReader::new(file))
+        .map_err(|e| KatsubaError::new_err(e.to_string()))
+}
+
+// Whether the cache at `cache` exists and is at least as recent as
+// all of the type lists at `paths`.
+fn cache_is_fresh(cache: &Path, paths: &[PathBuf]) -> PyResult<bool> {
+    let modified = match fs::metadata(cache) {
+        Ok(meta) => meta.modified()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    for path in paths {
+        if fs::metadata(path)?.modified()? > modified {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Named presets for the property mask of [`SerializerOptions`].
+#[pyclass(module = "katsuba.op")]
+pub struct PropertyMask;
+
+#[pymethods]
+impl PropertyMask {
+    #[staticmethod]
+    pub fn transmit(py: Python<'_>) -> PyResult<PyObject> {
+        flags::to_py(py, serde::PropertyMask::transmit().flags())
+    }
+
+    #[staticmethod]
+    pub fn save(py: Python<'_>) -> PyResult<PyObject> {
+        flags::to_py(py, serde::PropertyMask::save().flags())
+    }
+
+    #[staticmethod]
+    pub fn copy(py: Python<'_>) -> PyResult<PyObject> {
+        flags::to_py(py, serde::PropertyMask::copy().flags())
+    }
+
+    #[staticmethod]
+    pub fn all(py: Python<'_>) -> PyResult<PyObject> {
+        flags::to_py(py, serde::PropertyMask::all().flags())
     }
 }
 
@@ -45,28 +225,118 @@ pub struct SerializerOptions(serde::SerializerOptions);
 #[pymethods]
 impl SerializerOptions {
     #[new]
-    pub fn new() -> Self {
-        Self::default()
+    #[pyo3(signature = (
+        *,
+        flags = None,
+        property_mask = None,
+        shallow = None,
+        manual_compression = None,
+        header_order = None,
+        recursion_limit = None,
+        max_elements = None,
+        max_string_len = None,
+        max_total_values = None,
+        skip_unknown_types = None,
+        object_fallback = None,
+        strict_classes = None,
+        include_deprecated = None,
+        djb2_only = None,
+        decode_nested = None,
+        fill_defaults = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        flags: Option<FlagsArg>,
+        property_mask: Option<FlagsArg>,
+        shallow: Option<bool>,
+        manual_compression: Option<bool>,
+        header_order: Option<&str>,
+        recursion_limit: Option<u32>,
+        max_elements: Option<usize>,
+        max_string_len: Option<usize>,
+        max_total_values: Option<usize>,
+        skip_unknown_types: Option<bool>,
+        object_fallback: Option<bool>,
+        strict_classes: Option<bool>,
+        include_deprecated: Option<bool>,
+        djb2_only: Option<bool>,
+        decode_nested: Option<bool>,
+        fill_defaults: Option<bool>,
+    ) -> PyResult<Self> {
+        let mut this = Self::default();
+
+        if let Some(flags) = flags {
+            this.set_flags(flags)?;
+        }
+        if let Some(property_mask) = property_mask {
+            this.set_property_mask(property_mask)?;
+        }
+        if let Some(shallow) = shallow {
+            this.set_shallow(shallow);
+        }
+        if let Some(manual_compression) = manual_compression {
+            this.set_manual_compression(manual_compression);
+        }
+        if let Some(header_order) = header_order {
+            this.set_header_order(header_order)?;
+        }
+        if let Some(recursion_limit) = recursion_limit {
+            this.set_recursion_limit(recursion_limit);
+        }
+        if let Some(max_elements) = max_elements {
+            this.set_max_elements(max_elements);
+        }
+        if let Some(max_string_len) = max_string_len {
+            this.set_max_string_len(max_string_len);
+        }
+        if let Some(max_total_values) = max_total_values {
+            this.set_max_total_values(max_total_values);
+        }
+        if let Some(skip_unknown_types) = skip_unknown_types {
+            this.set_skip_unknown_types(skip_unknown_types);
+        }
+        if let Some(object_fallback) = object_fallback {
+            this.set_object_fallback(object_fallback);
+        }
+        if let Some(strict_classes) = strict_classes {
+            this.set_strict_classes(strict_classes);
+        }
+        if let Some(include_deprecated) = include_deprecated {
+            this.set_include_deprecated(include_deprecated);
+        }
+        if let Some(djb2_only) = djb2_only {
+            this.set_djb2_only(djb2_only);
+        }
+        if let Some(decode_nested) = decode_nested {
+            this.set_decode_nested(decode_nested);
+        }
+        if let Some(fill_defaults) = fill_defaults {
+            this.set_fill_defaults(fill_defaults);
+        }
+
+        Ok(this)
     }
 
     #[getter]
-    pub fn get_flags(&self) -> u32 {
-        self.0.flags.bits()
+    pub fn get_flags(&self, py: Python<'_>) -> PyResult<PyObject> {
+        flags::to_py(py, self.0.flags)
     }
 
     #[setter]
-    pub fn set_flags(&mut self, new: u32) {
-        self.0.flags = serde::SerializerFlags::from_bits_truncate(new);
+    pub fn set_flags(&mut self, new: FlagsArg) -> PyResult<()> {
+        self.0.flags = new.into_flags()?;
+        Ok(())
     }
 
     #[getter]
-    pub fn get_property_mask(&self) -> u32 {
-        self.0.property_mask.bits()
+    pub fn get_property_mask(&self, py: Python<'_>) -> PyResult<PyObject> {
+        flags::to_py(py, self.0.property_mask)
     }
 
     #[setter]
-    pub fn set_property_mask(&mut self, new: u32) {
-        self.0.property_mask = katsuba_types::PropertyFlags::from_bits_truncate(new);
+    pub fn set_property_mask(&mut self, new: FlagsArg) -> PyResult<()> {
+        self.0.property_mask = new.into_flags()?;
+        Ok(())
     }
 
     #[getter]
@@ -90,13 +360,73 @@ impl SerializerOptions {
     }
 
     #[getter]
-    pub fn get_recursion_limit(&self) -> i8 {
-        self.0.recursion_limit
+    pub fn get_header_order(&self) -> &'static str {
+        match self.0.header_order {
+            serde::HeaderOrder::FlagsFirst => "flags_first",
+            serde::HeaderOrder::CompressionFirst => "compression_first",
+            serde::HeaderOrder::Auto => "auto",
+        }
+    }
+
+    #[setter]
+    pub fn set_header_order(&mut self, new: &str) -> PyResult<()> {
+        self.0.header_order = match new {
+            "flags_first" => serde::HeaderOrder::FlagsFirst,
+            "compression_first" => serde::HeaderOrder::CompressionFirst,
+            "auto" => serde::HeaderOrder::Auto,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown header order '{new}'; expected 'flags_first', \
+                     'compression_first' or 'auto'"
+                )))
+            }
+        };
+        Ok(())
+    }
+
+    #[getter]
+    pub fn get_recursion_limit(&self) -> u32 {
+        self.0.limits.max_depth
     }
 
     #[setter]
-    pub fn set_recursion_limit(&mut self, new: i8) {
-        self.0.recursion_limit = new;
+    pub fn set_recursion_limit(&mut self, new: u32) {
+        self.0.limits.max_depth = new;
+    }
+
+    #[getter]
+    pub fn get_max_elements(&self) -> usize {
+        self.0.limits.max_elements
+    }
+
+    #[setter]
+    pub fn set_max_elements(&mut self, new: usize) {
+        self.0.limits.max_elements = new;
+    }
+
+    #[getter]
+    pub fn get_max_string_len(&self) -> usize {
+        self.0.limits.max_string_len
+    }
+
+    #[setter]
+    pub fn set_max_string_len(&mut self, new: usize) {
+        self.0.limits.max_string_len = new;
+    }
+
+    #[getter]
+    pub fn get_max_total_values(&self) -> usize {
+        self.0.limits.max_total_values
+    }
+
+    #[setter]
+    pub fn set_max_total_values(&mut self, new: usize) {
+        self.0.limits.max_total_values = new;
+    }
+
+    /// Disables all resource limits. Only use this for trusted data.
+    pub fn remove_limits(&mut self) {
+        self.0.limits = serde::Limits::unlimited();
     }
 
     #[getter]
@@ -109,6 +439,36 @@ impl SerializerOptions {
         self.0.skip_unknown_types = new;
     }
 
+    #[getter]
+    pub fn get_object_fallback(&self) -> bool {
+        self.0.object_fallback
+    }
+
+    #[setter]
+    pub fn set_object_fallback(&mut self, new: bool) {
+        self.0.object_fallback = new;
+    }
+
+    #[getter]
+    pub fn get_strict_classes(&self) -> bool {
+        self.0.strict_classes
+    }
+
+    #[setter]
+    pub fn set_strict_classes(&mut self, new: bool) {
+        self.0.strict_classes = new;
+    }
+
+    #[getter]
+    pub fn get_include_deprecated(&self) -> bool {
+        self.0.include_deprecated
+    }
+
+    #[setter]
+    pub fn set_include_deprecated(&mut self, new: bool) {
+        self.0.include_deprecated = new;
+    }
+
     #[getter]
     pub fn get_djb2_only(&self) -> bool {
         self.0.djb2_only
@@ -118,6 +478,26 @@ impl SerializerOptions {
     pub fn set_djb2_only(&mut self, new: bool) {
         self.0.djb2_only = new;
     }
+
+    #[getter]
+    pub fn get_decode_nested(&self) -> bool {
+        self.0.decode_nested
+    }
+
+    #[setter]
+    pub fn set_decode_nested(&mut self, new: bool) {
+        self.0.decode_nested = new;
+    }
+
+    #[getter]
+    pub fn get_fill_defaults(&self) -> bool {
+        self.0.fill_defaults
+    }
+
+    #[setter]
+    pub fn set_fill_defaults(&mut self, new: bool) {
+        self.0.fill_defaults = new;
+    }
 }
 
 #[pyclass(module = "katsuba.op")]
@@ -132,27 +512,64 @@ impl Serializer {
             .map_err(error::op_to_py_err)
     }
 
-    pub fn deserialize(&mut self, data: &[u8]) -> PyResult<LazyObject> {
-        self.0
-            .deserialize::<serde::PropertyClass>(data)
-            .map(|v| {
-                let value = Arc::new(v);
-                let (hash, obj) = match &*value {
-                    Value::Object { hash, obj } => (*hash, obj),
-                    _ => unreachable!(),
-                };
-
-                unsafe { LazyObject::new(value.clone(), hash, obj) }
+    pub fn deserialize(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<LazyObject> {
+        py.allow_threads(|| self.0.deserialize::<serde::PropertyClass>(data))
+            .map(into_lazy_object)
+            .map_err(|e| {
+                let offset = self.0.parts.error_offset();
+                error::with_offset(py, error::op_to_py_err(e), offset)
             })
-            .map_err(error::op_to_py_err)
     }
 }
 
+fn into_lazy_object(value: Value) -> LazyObject {
+    let root = TreeRef::root(Arc::new(value));
+    let Value::Object { hash, .. } = *root.get() else {
+        unreachable!()
+    };
+
+    let obj = root.project(|v| match v {
+        Value::Object { obj, .. } => obj,
+        _ => unreachable!(),
+    });
+    LazyObject::new(hash, obj)
+}
+
+/// Deserializes an object from `data` in a single call.
+///
+/// When no `options` are given, the default configuration is
+/// used. The GIL is released while the Rust code is running.
+#[pyfunction]
+#[pyo3(signature = (data, types, options = None))]
+pub fn deserialize(
+    py: Python<'_>,
+    data: &[u8],
+    types: &TypeList,
+    options: Option<SerializerOptions>,
+) -> PyResult<LazyObject> {
+    let options = options.unwrap_or_default();
+    let mut serializer = Serializer::new(options, types)?;
+
+    serializer.deserialize(py, data)
+}
+
 pub fn katsuba_op(m: &PyModule) -> PyResult<()> {
     m.add_class::<TypeList>()?;
+    m.add_class::<PropertyMask>()?;
     m.add_class::<SerializerOptions>()?;
     m.add_class::<Serializer>()?;
-
+    m.add_function(wrap_pyfunction!(deserialize, m)?)?;
+    m.add_function(wrap_pyfunction!(many::deserialize_many, m)?)?;
+    m.add_function(wrap_pyfunction!(pickle::dumps, m)?)?;
+    m.add_function(wrap_pyfunction!(pickle::loads, m)?)?;
+
+    // pickle resolves reconstructors by their module path, which
+    // pyo3 would otherwise report as the bare submodule name.
+    m.getattr("loads")?.setattr("__module__", "katsuba.op")?;
+
+    // The flag classes are also available here, next to the bare
+    // constants of their members.
+    flags::register(m.py(), m)?;
     m.add("STATEFUL_FLAGS", SerializerFlags::STATEFUL_FLAGS.bits())?;
     m.add(
         "COMPACT_LENGTH_PREFIXES",
@@ -168,12 +585,18 @@ pub fn katsuba_op(m: &PyModule) -> PyResult<()> {
         SerializerFlags::FORBID_DELTA_ENCODE.bits(),
     )?;
 
+    for (name, flag) in PropertyFlags::all().iter_names() {
+        m.add(name, flag.bits())?;
+    }
+
     m.add_class::<LazyList>()?;
     m.add_class::<LazyObject>()?;
+    m.add_class::<ObjectWalker>()?;
 
     m.add_class::<Vec3>()?;
     m.add_class::<Quaternion>()?;
     m.add_class::<Matrix>()?;
+    m.add("Matrix", m.getattr("Matrix3x3")?)?;
     m.add_class::<Euler>()?;
     m.add_class::<PointInt>()?;
     m.add_class::<PointFloat>()?;
@@ -181,6 +604,7 @@ pub fn katsuba_op(m: &PyModule) -> PyResult<()> {
     m.add_class::<RectInt>()?;
     m.add_class::<RectFloat>()?;
     m.add_class::<Color>()?;
+    m.add_class::<Time>()?;
 
     Ok(())
 }
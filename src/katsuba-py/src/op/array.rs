@@ -0,0 +1,109 @@
+//! Bulk conversion of numeric lists into numpy arrays.
+
+use katsuba_object_property::value::{List, Value};
+use numpy::{ndarray::Array2, IntoPyArray};
+use pyo3::{exceptions::PyTypeError, prelude::*};
+
+/// The element kind which determines dtype and shape of the array.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Unsigned,
+    Signed,
+    Float,
+    Float32,
+    Bool,
+    Vec3,
+    Quat,
+}
+
+impl Kind {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Unsigned(_) | Value::Gid(_) => Some(Self::Unsigned),
+            Value::Signed(_) => Some(Self::Signed),
+            Value::Float(_) => Some(Self::Float),
+            Value::Float32(_) => Some(Self::Float32),
+            Value::Bool(_) => Some(Self::Bool),
+            Value::Vec3(_) => Some(Self::Vec3),
+            Value::Quat(_) => Some(Self::Quat),
+            _ => None,
+        }
+    }
+}
+
+fn unsupported(idx: usize) -> PyErr {
+    PyTypeError::new_err(format!(
+        "list element at index {idx} cannot be converted to a numpy array"
+    ))
+}
+
+/// Converts a homogeneous list of numeric values into a numpy array.
+///
+/// Scalars produce 1-D arrays while vectors and quaternions produce
+/// arrays of shape `(N, 3)` and `(N, 4)`, respectively.
+pub fn list_to_numpy(py: Python<'_>, list: &List) -> PyResult<PyObject> {
+    let kind = match list.first() {
+        Some(first) => Kind::of(first).ok_or_else(|| unsupported(0))?,
+        None => return Ok(Vec::<f64>::new().into_pyarray(py).into_py(py)),
+    };
+
+    // Every element must agree with the first one so we get a uniform dtype.
+    if let Some(idx) = list.iter().position(|v| Kind::of(v) != Some(kind)) {
+        return Err(unsupported(idx));
+    }
+
+    macro_rules! collect {
+        ($($variant:ident)|+ => $map:expr) => {
+            list.iter()
+                .map(|v| match v {
+                    $(Value::$variant(v))|+ => $map(v),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+    }
+
+    let array = match kind {
+        Kind::Unsigned => collect!(Unsigned | Gid => |v: &u64| *v)
+            .into_pyarray(py)
+            .into_py(py),
+        Kind::Signed => collect!(Signed => |v: &i64| *v)
+            .into_pyarray(py)
+            .into_py(py),
+        Kind::Float => collect!(Float => |v: &f64| *v).into_pyarray(py).into_py(py),
+        Kind::Float32 => collect!(Float32 => |v: &f32| *v)
+            .into_pyarray(py)
+            .into_py(py),
+        Kind::Bool => collect!(Bool => |v: &bool| *v).into_pyarray(py).into_py(py),
+        Kind::Vec3 => {
+            let flat: Vec<f32> = list
+                .iter()
+                .flat_map(|v| match v {
+                    Value::Vec3(v) => [v.x, v.y, v.z],
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            Array2::from_shape_vec((list.len(), 3), flat)
+                .unwrap()
+                .into_pyarray(py)
+                .into_py(py)
+        }
+        Kind::Quat => {
+            let flat: Vec<f32> = list
+                .iter()
+                .flat_map(|v| match v {
+                    Value::Quat(v) => [v.x, v.y, v.z, v.w],
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            Array2::from_shape_vec((list.len(), 4), flat)
+                .unwrap()
+                .into_pyarray(py)
+                .into_py(py)
+        }
+    };
+
+    Ok(array)
+}
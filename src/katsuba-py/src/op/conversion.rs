@@ -1,9 +1,12 @@
-use std::{ptr, sync::Arc};
+use std::ptr;
 
 use katsuba_object_property::value::*;
-use pyo3::{prelude::*, types::PyBytes};
+use pyo3::{
+    prelude::*,
+    types::{PyBytes, PyList, PyTuple},
+};
 
-use super::{lazy::*, leaf_types};
+use super::{lazy::*, leaf_types, tree::TreeRef};
 
 fn convert_to_utf16(py: Python<'_>, x: &[u16]) -> PyObject {
     let ptr = x.as_ptr().cast::<u8>();
@@ -28,21 +31,74 @@ fn convert_to_utf16(py: Python<'_>, x: &[u16]) -> PyObject {
     }
 }
 
-// SAFETY: `value` must be derived from `base` in some way.
-pub unsafe fn value_to_python(base: Arc<Value>, value: &Value, py: Python<'_>) -> PyObject {
-    match value {
-        Value::Empty => py.None(),
+// Converts the value at `node` into a Python object.
+//
+// Lists and objects become lazy wrappers which keep the tree of
+// `node` alive.
+pub fn value_to_python(node: TreeRef<Value>, py: Python<'_>) -> PyObject {
+    match node.get() {
+        Value::Empty | Value::Unset => py.None(),
 
-        Value::Unsigned(v) => v.into_py(py),
+        Value::Unsigned(v) | Value::Gid(v) => v.into_py(py),
         Value::Signed(v) | Value::Enum(v) => v.into_py(py),
+        Value::Time(v) => leaf_types::Time(*v).into_py(py),
         Value::Float(v) => v.into_py(py),
+        // Widening would expose the binary error of `float` values, so
+        // Python gets the double closest to their shortest decimal.
+        Value::Float32(v) => v.to_string().parse::<f64>().unwrap().into_py(py),
         Value::Bool(v) => v.into_py(py),
 
         Value::String(v) => v.0.as_slice().into_py(py),
         Value::WString(v) => convert_to_utf16(py, &v.0),
+        Value::Blob(..) => {
+            let value = node.project(|v| match v {
+                Value::Blob(blob) => &blob.value,
+                _ => unreachable!(),
+            });
+            value_to_python(value, py)
+        }
+        // Shared values are owned by the tree, so it keeps them alive.
+        Value::Shared(..) => value_to_python(node.project(Value::resolve), py),
 
-        Value::List(v) => unsafe { LazyList::new(base, v).into_py(py) },
-        Value::Object { hash, obj } => unsafe { LazyObject::new(base, *hash, obj).into_py(py) },
+        Value::List(..) => {
+            let list = node.project(|v| match v {
+                Value::List(list) => list,
+                _ => unreachable!(),
+            });
+            LazyList::new(list).into_py(py)
+        }
+        // Keys may be objects, so maps become lists of pairs instead of dicts.
+        Value::Map(v) => {
+            let entry = |idx: usize| {
+                node.project(move |v| match v {
+                    Value::Map(map) => &map[idx],
+                    _ => unreachable!(),
+                })
+            };
+            let entries = (0..v.len()).map(|idx| {
+                let entry = entry(idx);
+                let key = value_to_python(entry.project(|(k, _)| k), py);
+                let value = value_to_python(entry.project(|(_, v)| v), py);
+                PyTuple::new(py, [key, value])
+            });
+            PyList::new(py, entries).into_py(py)
+        }
+        Value::Pair(..) => {
+            let pair = node.project(|v| match v {
+                Value::Pair(pair) => &**pair,
+                _ => unreachable!(),
+            });
+            let first = value_to_python(pair.project(|(first, _)| first), py);
+            let second = value_to_python(pair.project(|(_, second)| second), py);
+            PyTuple::new(py, [first, second]).into_py(py)
+        }
+        Value::Object { hash, .. } => {
+            let obj = node.project(|v| match v {
+                Value::Object { obj, .. } => obj,
+                _ => unreachable!(),
+            });
+            LazyObject::new(*hash, obj).into_py(py)
+        }
 
         Value::Color(v) => {
             let Color { r, g, b, a } = *v;
@@ -0,0 +1,128 @@
+//! JSON encoding of lazy values, matching the output of the CLI.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use katsuba_object_property::{
+    value::{JsonFormat, NonFinite, Object},
+    Value,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+/// An [`Object`] with its type hash, serialized the same way as
+/// the corresponding [`Value::Object`][katsuba_object_property::Value].
+pub struct TaggedObject<'a> {
+    pub hash: u32,
+    pub obj: &'a Object,
+}
+
+impl TaggedObject<'_> {
+    /// Copies the object into a [`Value::Object`].
+    pub fn to_value(&self) -> Value {
+        Value::Object {
+            hash: self.hash,
+            obj: self.obj.clone(),
+        }
+    }
+}
+
+impl Serialize for TaggedObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("$__type", &self.hash)?;
+        for (key, value) in self.obj.iter().filter(|(_, v)| !matches!(v, Value::Unset)) {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// A value to encode, either as it is in the tree or rewritten
+/// according to the output options.
+pub enum Formatted<'a, T> {
+    Borrowed(&'a T),
+    Owned(Value),
+}
+
+impl<T: Serialize> Serialize for Formatted<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Borrowed(v) => v.serialize(serializer),
+            Self::Owned(v) => v.serialize(serializer),
+        }
+    }
+}
+
+/// Prepares `value` for encoding, with non-finite floats written as
+/// `nonfinite` says, like the CLI's `--nonfinite` option.
+///
+/// Rewriting works on a copy, which `to_value` creates if needed.
+pub fn format<'a, T>(
+    value: &'a T,
+    nonfinite: &str,
+    to_value: impl FnOnce(&T) -> Value,
+) -> PyResult<Formatted<'a, T>> {
+    let nonfinite = match nonfinite {
+        "error" => NonFinite::Error,
+        "null" => NonFinite::Null,
+        "string" => NonFinite::String,
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "nonfinite must be 'error', 'null' or 'string', not '{nonfinite}'"
+            )))
+        }
+    };
+
+    let format = JsonFormat {
+        nonfinite,
+        ..Default::default()
+    };
+    if format.is_identity() {
+        return Ok(Formatted::Borrowed(value));
+    }
+
+    let mut owned = to_value(value);
+    format
+        .apply(&mut owned)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(Formatted::Owned(owned))
+}
+
+fn json_err(e: serde_json::Error) -> PyErr {
+    if e.is_io() {
+        PyErr::from(std::io::Error::from(e))
+    } else {
+        PyValueError::new_err(e.to_string())
+    }
+}
+
+/// Encodes `value` into a JSON string.
+pub fn to_string<T: Serialize + Sync>(py: Python<'_>, value: &T, pretty: bool) -> PyResult<String> {
+    py.allow_threads(|| match pretty {
+        true => serde_json::to_string_pretty(value),
+        false => serde_json::to_string(value),
+    })
+    .map_err(json_err)
+}
+
+/// Encodes `value` as JSON directly into the file at `path`.
+pub fn to_file<T: Serialize + Sync>(
+    py: Python<'_>,
+    path: &Path,
+    value: &T,
+    pretty: bool,
+) -> PyResult<()> {
+    py.allow_threads(|| {
+        let mut writer = BufWriter::new(File::create(path).map_err(serde_json::Error::io)?);
+        match pretty {
+            true => serde_json::to_writer_pretty(&mut writer, value)?,
+            false => serde_json::to_writer(&mut writer, value)?,
+        }
+        writer.flush().map_err(serde_json::Error::io)
+    })
+    .map_err(json_err)
+}
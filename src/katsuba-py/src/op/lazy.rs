@@ -1,42 +1,79 @@
-use std::{ptr::NonNull, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
-use katsuba_object_property::value::{List, Object, Value};
+use katsuba_object_property::value::{List, Object, Path, PathSegment, Value, BEHAVIORS};
 use pyo3::{
-    exceptions::{PyIndexError, PyKeyError},
+    exceptions::{PyIndexError, PyKeyError, PyValueError},
     prelude::*,
 };
 
-use super::conversion::value_to_python;
+use super::{
+    conversion::value_to_python, json, pickle, tree::TreeRef, walk::ObjectWalker, TypeList,
+};
+
+// Python objects for the children of a wrapper which were accessed
+// before, so that repeated lookups return the very same object.
+//
+// Children are keyed by their address, which is stable since values
+// are never mutated.
+type ChildCache = RefCell<HashMap<usize, PyObject>>;
+
+// Converts `value`, a child of a wrapper, through the wrapper's
+// `cache`.
+//
+// Only nested wrappers are cached. Other values are either cheap
+// to convert or become mutable Python objects which must not be
+// shared between lookups.
+fn cached_child(py: Python<'_>, cache: &ChildCache, value: TreeRef<Value>) -> PyObject {
+    if !matches!(
+        value.get().resolve(),
+        Value::List(..) | Value::Object { .. }
+    ) {
+        return value_to_python(value, py);
+    }
+
+    let key = value.addr();
+    if let Some(obj) = cache.borrow().get(&key) {
+        return obj.clone_ref(py);
+    }
+
+    let obj = value_to_python(value, py);
+    cache.borrow_mut().insert(key, obj.clone_ref(py));
+    obj
+}
 
-#[derive(Clone)]
 #[pyclass(module = "katsuba.op")]
-pub struct LazyList(Arc<Value>, NonNull<List>);
+pub struct LazyList(TreeRef<List>, ChildCache);
 
 impl LazyList {
-    // SAFETY: `current` must be derived from `base` in some way.
-    pub unsafe fn new(base: Arc<Value>, current: &List) -> Self {
-        Self(base, NonNull::from(current))
+    pub fn new(list: TreeRef<List>) -> Self {
+        Self(list, ChildCache::default())
     }
 
     #[inline(always)]
     fn get_ref(&self) -> &List {
-        // SAFETY: Constructor ensures our list is fine and we never get a mut ref.
-        unsafe { self.1.as_ref() }
+        self.0.get()
     }
-}
 
-// SAFETY: Raw pointers are never exposed for mutation.
-unsafe impl Send for LazyList {}
+    /// Encodes the list into its pickled representation.
+    pub fn dump(&self) -> Vec<u8> {
+        pickle::encode_list(self.get_ref())
+    }
+}
 
 #[pymethods]
 impl LazyList {
     pub fn __iter__(slf: PyRef<'_, Self>) -> PyResult<Py<LazyListIter>> {
+        let py = slf.py();
         let iter = LazyListIter {
-            list: slf.clone(),
+            list: slf.into(),
             idx: 0,
         };
 
-        Py::new(slf.py(), iter)
+        Py::new(py, iter)
     }
 
     pub fn __len__(&self) -> usize {
@@ -44,18 +81,49 @@ impl LazyList {
         list.len()
     }
 
-    pub fn __getitem__(&self, py: Python<'_>, idx: usize) -> PyResult<PyObject> {
-        let list = self.get_ref();
+    pub fn __reduce__(&self, py: Python<'_>) -> PyResult<(PyObject, (PyObject,))> {
+        pickle::reduce(py, self.dump())
+    }
 
-        list.get(idx)
-            .map(|v| unsafe { value_to_python(self.0.clone(), v, py) })
+    pub fn __getitem__(&self, py: Python<'_>, idx: usize) -> PyResult<PyObject> {
+        self.0
+            .try_project(|list| list.get(idx))
+            .map(|v| cached_child(py, &self.1, v))
             .ok_or_else(|| PyIndexError::new_err("list index out of range"))
     }
+
+    /// Encodes the list as JSON, the same way the CLI does.
+    ///
+    /// `nonfinite` is one of `"error"`, `"null"` or `"string"`, like
+    /// the CLI's `--nonfinite` option.
+    #[pyo3(signature = (pretty = false, nonfinite = "string"))]
+    pub fn to_json(&self, py: Python<'_>, pretty: bool, nonfinite: &str) -> PyResult<String> {
+        let value = json::format(self.get_ref(), nonfinite, |l| Value::List(l.clone()))?;
+        json::to_string(py, &value, pretty)
+    }
+
+    /// Encodes the list as JSON straight into the file at `path`.
+    #[pyo3(signature = (path, pretty = false, nonfinite = "string"))]
+    pub fn to_json_file(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        pretty: bool,
+        nonfinite: &str,
+    ) -> PyResult<()> {
+        let value = json::format(self.get_ref(), nonfinite, |l| Value::List(l.clone()))?;
+        json::to_file(py, &path, &value, pretty)
+    }
+
+    #[cfg(feature = "numpy")]
+    pub fn as_numpy(&self, py: Python<'_>) -> PyResult<PyObject> {
+        super::array::list_to_numpy(py, self.get_ref())
+    }
 }
 
 #[pyclass(module = "katsuba.op")]
 pub struct LazyListIter {
-    list: LazyList,
+    list: Py<LazyList>,
     idx: usize,
 }
 
@@ -69,24 +137,38 @@ impl LazyListIter {
         let idx = slf.idx;
         slf.idx += 1;
 
-        slf.list.__getitem__(slf.py(), idx).ok()
+        let py = slf.py();
+        slf.list.borrow(py).__getitem__(py, idx).ok()
     }
 }
 
-#[derive(Clone)]
 #[pyclass(module = "katsuba.op")]
-pub struct LazyObject(Arc<Value>, u32, NonNull<Object>);
+pub struct LazyObject(TreeRef<Object>, u32, ChildCache);
 
 impl LazyObject {
-    // SAFETY: `current` must be derived from `base` in some way.
-    pub unsafe fn new(base: Arc<Value>, hash: u32, current: &Object) -> Self {
-        Self(base, hash, NonNull::from(current))
+    pub fn new(hash: u32, obj: TreeRef<Object>) -> Self {
+        Self(obj, hash, ChildCache::default())
     }
 
     #[inline(always)]
     fn get_ref(&self) -> &Object {
-        // SAFETY: Constructor ensures our list is fine and we never get a mut ref.
-        unsafe { self.2.as_ref() }
+        self.0.get()
+    }
+
+    fn tagged(&self) -> json::TaggedObject<'_> {
+        json::TaggedObject {
+            hash: self.1,
+            obj: self.get_ref(),
+        }
+    }
+
+    fn get_value(&self, key: &str) -> Option<&Value> {
+        get_present(self.get_ref(), key)
+    }
+
+    /// Encodes the object into its pickled representation.
+    pub fn dump(&self) -> Vec<u8> {
+        pickle::encode_object(self.1, self.get_ref())
     }
 }
 
@@ -97,14 +179,17 @@ impl LazyObject {
         self.1
     }
 
+    pub fn __reduce__(&self, py: Python<'_>) -> PyResult<(PyObject, (PyObject,))> {
+        pickle::reduce(py, self.dump())
+    }
+
     pub fn __len__(&self) -> usize {
         let obj = self.get_ref();
-        obj.len()
+        obj.values().filter(|v| !matches!(v, Value::Unset)).count()
     }
 
     pub fn __contains__(&self, key: &str) -> bool {
-        let obj = self.get_ref();
-        obj.contains_key(key)
+        self.get_value(key).is_some()
     }
 
     pub fn __getitem__(&self, py: Python<'_>, key: &str) -> PyResult<PyObject> {
@@ -113,12 +198,168 @@ impl LazyObject {
     }
 
     pub fn get(&self, py: Python<'_>, key: &str) -> Option<PyObject> {
-        let obj = self.get_ref();
+        self.0
+            .try_project(|obj| get_present(obj, key))
+            .map(|v| cached_child(py, &self.2, v))
+    }
+
+    /// Looks up a nested value by a path like `m_items[2].m_name`.
+    ///
+    /// Segments may also be separated by `/` and list elements
+    /// given as plain numbers, like in `m_items/2/m_name`. As with
+    /// `get`, properties which were not transmitted are missing.
+    pub fn query(slf: PyRef<'_, Self>, path: &str) -> PyResult<PyObject> {
+        let py = slf.py();
+        let path = parse_query(path)?;
+        let mut missing = None;
+
+        let found = slf.0.try_project(|root| {
+            let mut current: Option<&Value> = None;
+            for segment in path.segments() {
+                let next = match (current.map(Value::resolve), segment) {
+                    (None, PathSegment::Property(name)) => get_present(root, name),
+                    (Some(Value::Object { obj, .. }), PathSegment::Property(name)) => {
+                        get_present(obj, name)
+                    }
+                    (Some(Value::List(list)), PathSegment::Index(idx)) => list.get(*idx),
+                    (Some(Value::Blob(blob)), PathSegment::Nested) => Some(&blob.value),
+                    (Some(Value::Map(entries)), PathSegment::Key(idx)) => {
+                        entries.get(*idx).map(|(k, _)| k)
+                    }
+                    (Some(Value::Map(entries)), PathSegment::Value(idx)) => {
+                        entries.get(*idx).map(|(_, v)| v)
+                    }
+                    (Some(Value::Pair(pair)), PathSegment::First) => Some(&pair.0),
+                    (Some(Value::Pair(pair)), PathSegment::Second) => Some(&pair.1),
+                    _ => None,
+                };
+
+                if next.is_none() {
+                    missing = Some(segment);
+                }
+                current = Some(next?);
+            }
+
+            current
+        });
+
+        match (found, missing) {
+            (_, Some(segment)) => Err(PyKeyError::new_err(segment.to_string())),
+            (Some(v), None) => Ok(cached_child(py, &slf.2, v)),
+            (None, None) => Ok(slf.into_py(py)),
+        }
+    }
+
+    /// Encodes the object as JSON, the same way the CLI does.
+    ///
+    /// `nonfinite` is one of `"error"`, `"null"` or `"string"`, like
+    /// the CLI's `--nonfinite` option.
+    #[pyo3(signature = (pretty = false, nonfinite = "string"))]
+    pub fn to_json(&self, py: Python<'_>, pretty: bool, nonfinite: &str) -> PyResult<String> {
+        let tagged = self.tagged();
+        let value = json::format(&tagged, nonfinite, json::TaggedObject::to_value)?;
+        json::to_string(py, &value, pretty)
+    }
+
+    /// Encodes the object as JSON straight into the file at `path`.
+    #[pyo3(signature = (path, pretty = false, nonfinite = "string"))]
+    pub fn to_json_file(
+        &self,
+        py: Python<'_>,
+        path: PathBuf,
+        pretty: bool,
+        nonfinite: &str,
+    ) -> PyResult<()> {
+        let tagged = self.tagged();
+        let value = json::format(&tagged, nonfinite, json::TaggedObject::to_value)?;
+        json::to_file(py, &path, &value, pretty)
+    }
+
+    /// Iterates over `(path, object)` pairs for every object nested
+    /// in this one.
+    ///
+    /// The results may be filtered by a class name or a type hash.
+    /// Class names are hashed with the String ID algorithm.
+    #[pyo3(signature = (type_name = None, type_hash = None))]
+    pub fn walk(&self, type_name: Option<&str>, type_hash: Option<u32>) -> ObjectWalker {
+        let type_hash = type_name
+            .map(|n| katsuba_utils::hash::string_id(n.as_bytes()))
+            .or(type_hash);
 
-        obj.get(key)
-            .map(|v| unsafe { value_to_python(self.0.clone(), v, py) })
+        ObjectWalker::new(self.0.clone(), type_hash)
     }
+
+    /// Gets the first object in `m_behaviors` whose class is the
+    /// given one or inherits from it, if any.
+    ///
+    /// This requires a type list which records base classes.
+    pub fn behavior(&self, type_name: &str, type_list: &TypeList) -> Option<LazyObject> {
+        self.behaviors_of(type_name, type_list).into_iter().next()
+    }
+
+    /// Gets all objects in `m_behaviors` whose class is the given one
+    /// or inherits from it, in list order.
+    ///
+    /// This requires a type list which records base classes.
+    pub fn behaviors_of(&self, type_name: &str, type_list: &TypeList) -> Vec<LazyObject> {
+        let matching: HashSet<_> = self
+            .get_ref()
+            .behaviors_of(type_name, &type_list.0)
+            .map(|(_, obj)| obj as *const Object)
+            .collect();
+
+        let Some(list) = self
+            .0
+            .try_project(|obj| match obj.get(BEHAVIORS)?.resolve() {
+                Value::List(list) => Some(list),
+                _ => None,
+            })
+        else {
+            return Vec::new();
+        };
+
+        (0..list.get().len())
+            .filter_map(|idx| {
+                let mut hash = 0;
+                let obj = list.try_project(|list| match list[idx].resolve() {
+                    Value::Object { hash: h, obj } => {
+                        hash = *h;
+                        Some(obj)
+                    }
+                    _ => None,
+                })?;
+
+                matching
+                    .contains(&(obj.get() as *const Object))
+                    .then(|| LazyObject::new(hash, obj))
+            })
+            .collect()
+    }
+}
+
+// Properties which were not transmitted are treated as absent.
+fn get_present<'a>(obj: &'a Object, key: &str) -> Option<&'a Value> {
+    obj.get(key).filter(|v| !matches!(v, Value::Unset))
 }
 
-// SAFETY: Raw pointers are never exposed for mutation.
-unsafe impl Send for LazyObject {}
+// Parses a path for `LazyObject.query`, where `/` also separates
+// segments and numeric property names are list indices.
+fn parse_query(query: &str) -> PyResult<Path> {
+    let mut path = Path::new();
+    for part in query.split('/').filter(|p| !p.is_empty()) {
+        let part: Path = part
+            .parse()
+            .map_err(|e| PyValueError::new_err(format!("invalid path '{query}': {e}")))?;
+
+        path.extend(part.segments().iter().map(|segment| {
+            match segment {
+                PathSegment::Property(name) if name.bytes().all(|b| b.is_ascii_digit()) => name
+                    .parse()
+                    .map_or_else(|_| segment.clone(), PathSegment::Index),
+                segment => segment.clone(),
+            }
+        }));
+    }
+
+    Ok(path)
+}
@@ -1,4 +1,96 @@
-use pyo3::prelude::*;
+use katsuba_object_property::value::{self, TimeKind};
+use pyo3::{
+    basic::CompareOp,
+    exceptions::{PyIndexError, PyKeyError},
+    prelude::*,
+    types::PyTuple,
+};
+
+/// Relative tolerance for comparing float components.
+const TOLERANCE: f32 = 1e-5;
+
+fn approx_eq(a: &[f32], b: &[f32]) -> bool {
+    a.iter()
+        .zip(b)
+        .all(|(a, b)| a == b || (a - b).abs() <= TOLERANCE * a.abs().max(b.abs()).max(1.0))
+}
+
+fn compare(lhs: &[f32], rhs: &[f32], op: CompareOp, py: Python<'_>) -> PyObject {
+    match op {
+        CompareOp::Eq => approx_eq(lhs, rhs).into_py(py),
+        CompareOp::Ne => (!approx_eq(lhs, rhs)).into_py(py),
+        _ => py.NotImplemented(),
+    }
+}
+
+fn index(idx: isize, len: usize) -> PyResult<usize> {
+    let real = if idx < 0 { idx + len as isize } else { idx };
+    usize::try_from(real)
+        .ok()
+        .filter(|&i| i < len)
+        .ok_or_else(|| PyIndexError::new_err("index out of range"))
+}
+
+fn repr(name: &str, fields: &[(&str, f32)]) -> String {
+    let fields: Vec<_> = fields.iter().map(|(k, v)| format!("{k}={v:?}")).collect();
+    format!("{name}({})", fields.join(", "))
+}
+
+/// Implements the tuple protocol, `__repr__`, equality with
+/// tolerance and numpy conversion for classes of float components.
+macro_rules! float_tuple {
+    ($ty:ident { $($field:ident),+ } $($extra:tt)*) => {
+        impl $ty {
+            fn components(&self) -> Vec<f32> {
+                vec![$(self.$field),+]
+            }
+        }
+
+        #[pymethods]
+        impl $ty {
+            #[new]
+            pub fn new($($field: f32),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            pub fn __len__(&self) -> usize {
+                self.components().len()
+            }
+
+            pub fn __getitem__(&self, idx: isize) -> PyResult<f32> {
+                let components = self.components();
+                index(idx, components.len()).map(|i| components[i])
+            }
+
+            pub fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+                let tuple = PyTuple::new(py, self.components());
+                tuple.call_method0("__iter__").map(Into::into)
+            }
+
+            pub fn __repr__(&self) -> String {
+                repr(stringify!($ty), &[$((stringify!($field), self.$field)),+])
+            }
+
+            pub fn __richcmp__(&self, other: PyRef<'_, Self>, op: CompareOp) -> PyObject {
+                compare(&self.components(), &other.components(), op, other.py())
+            }
+
+            #[cfg(feature = "numpy")]
+            #[pyo3(signature = (dtype=None, copy=None))]
+            pub fn __array__(
+                &self,
+                py: Python<'_>,
+                dtype: Option<&PyAny>,
+                copy: Option<bool>,
+            ) -> PyObject {
+                let _ = (dtype, copy);
+                numpy::PyArray1::from_vec(py, self.components()).into_py(py)
+            }
+
+            $($extra)*
+        }
+    };
+}
 
 #[pyclass(module = "katsuba.op")]
 pub struct Vec3 {
@@ -22,7 +114,7 @@ pub struct Quaternion {
     pub w: f32,
 }
 
-#[pyclass(module = "katsuba.op")]
+#[pyclass(module = "katsuba.op", name = "Matrix3x3")]
 pub struct Matrix {
     #[pyo3(get, set)]
     pub i: [f32; 3],
@@ -101,3 +193,218 @@ pub struct Color {
     #[pyo3(get, set)]
     pub a: u8,
 }
+
+/// A channel of a [`Color`], by position or by name.
+#[derive(FromPyObject)]
+pub enum Channel {
+    Index(isize),
+    Name(String),
+}
+
+impl Color {
+    fn channels(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
+
+#[pymethods]
+impl Color {
+    /// Formats the color in `#RRGGBBAA` notation.
+    pub fn as_hex(&self) -> String {
+        format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
+    }
+
+    pub fn __len__(&self) -> usize {
+        4
+    }
+
+    pub fn __getitem__(&self, channel: Channel) -> PyResult<u8> {
+        let channels = self.channels();
+        match channel {
+            Channel::Index(idx) => {
+                let idx = if idx < 0 { idx + 4 } else { idx };
+                usize::try_from(idx)
+                    .ok()
+                    .and_then(|idx| channels.get(idx).copied())
+                    .ok_or_else(|| PyIndexError::new_err("color index out of range"))
+            }
+            Channel::Name(name) => match name.as_str() {
+                "r" => Ok(self.r),
+                "g" => Ok(self.g),
+                "b" => Ok(self.b),
+                "a" => Ok(self.a),
+                _ => Err(PyKeyError::new_err(name)),
+            },
+        }
+    }
+
+    pub fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let channels = self.channels().into_py(py);
+        channels.call_method0(py, "__iter__")
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "Color(r={}, g={}, b={}, a={})",
+            self.r, self.g, self.b, self.a
+        )
+    }
+}
+
+float_tuple!(Vec3 { x, y, z }
+    /// The Euclidean length of the vector.
+    pub fn length(&self) -> f32 {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+);
+
+float_tuple!(Quaternion { x, y, z, w }
+    /// Converts the rotation into Euler angles in radians.
+    ///
+    /// The angles are applied around the X, then Y, then Z axis.
+    pub fn to_euler(&self) -> Euler {
+        // Normalizing first keeps the angles accurate near the poles,
+        // where stored quaternions are rarely of exact unit length.
+        let [x, y, z, w] = [self.x, self.y, self.z, self.w].map(f64::from);
+        let norm = (x * x + y * y + z * z + w * w).sqrt();
+        let [x, y, z, w] = [x, y, z, w].map(|c| c / norm);
+
+        let pitch = (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y));
+        let yaw = (2.0 * (w * y - z * x)).clamp(-1.0, 1.0).asin();
+        let roll = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+
+        Euler {
+            pitch: pitch as f32,
+            yaw: yaw as f32,
+            roll: roll as f32,
+        }
+    }
+);
+
+float_tuple!(Euler { pitch, yaw, roll });
+
+impl Matrix {
+    fn rows(&self) -> [[f32; 3]; 3] {
+        [self.i, self.j, self.k]
+    }
+
+    fn components(&self) -> Vec<f32> {
+        self.rows().concat()
+    }
+}
+
+#[pymethods]
+impl Matrix {
+    #[new]
+    pub fn new(i: [f32; 3], j: [f32; 3], k: [f32; 3]) -> Self {
+        Self { i, j, k }
+    }
+
+    pub fn __len__(&self) -> usize {
+        3
+    }
+
+    /// Gets a row of the matrix.
+    pub fn __getitem__(&self, idx: isize) -> PyResult<(f32, f32, f32)> {
+        let [x, y, z] = self.rows()[index(idx, 3)?];
+        Ok((x, y, z))
+    }
+
+    pub fn __iter__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let rows = self.rows().map(|[x, y, z]| (x, y, z));
+        let tuple = PyTuple::new(py, rows);
+        tuple.call_method0("__iter__").map(Into::into)
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("Matrix3x3(i={:?}, j={:?}, k={:?})", self.i, self.j, self.k)
+    }
+
+    pub fn __richcmp__(&self, other: PyRef<'_, Self>, op: CompareOp) -> PyObject {
+        compare(&self.components(), &other.components(), op, other.py())
+    }
+
+    #[cfg(feature = "numpy")]
+    #[pyo3(signature = (dtype=None, copy=None))]
+    pub fn __array__(&self, py: Python<'_>, dtype: Option<&PyAny>, copy: Option<bool>) -> PyObject {
+        let _ = (dtype, copy);
+        let rows = self.rows().iter().map(|r| r.to_vec()).collect::<Vec<_>>();
+        numpy::PyArray2::from_vec2(py, &rows).unwrap().into_py(py)
+    }
+}
+
+/// A timestamp, kept as its raw integer.
+///
+/// Conversion into a `datetime` happens only on request, so no
+/// timezone is assumed for the raw value.
+#[pyclass(module = "katsuba.op")]
+pub struct Time(pub value::Time);
+
+#[pymethods]
+impl Time {
+    #[new]
+    pub fn new(raw: i64) -> Self {
+        Self(value::Time::unix_seconds(raw))
+    }
+
+    /// The integer which was serialized.
+    #[getter]
+    pub fn raw(&self) -> i64 {
+        self.0.raw
+    }
+
+    /// How the raw integer is interpreted.
+    #[getter]
+    pub fn kind(&self) -> &'static str {
+        match self.0.kind {
+            TimeKind::UnixSeconds => "unix_seconds",
+        }
+    }
+
+    /// Formats the time in ISO 8601 notation, in UTC.
+    pub fn isoformat(&self) -> String {
+        self.0.to_iso8601()
+    }
+
+    /// Converts the time into an aware `datetime` in the timezone
+    /// `tz`, which defaults to UTC.
+    #[pyo3(signature = (tz=None))]
+    pub fn to_datetime(&self, py: Python<'_>, tz: Option<PyObject>) -> PyResult<PyObject> {
+        let datetime = py.import("datetime")?;
+        let tz = match tz {
+            Some(tz) => tz,
+            None => datetime.getattr("timezone")?.getattr("utc")?.into(),
+        };
+
+        let TimeKind::UnixSeconds = self.0.kind;
+        let cls = datetime.getattr("datetime")?;
+        Ok(cls.call_method1("fromtimestamp", (self.0.raw, tz))?.into())
+    }
+
+    pub fn __int__(&self) -> i64 {
+        self.0.raw
+    }
+
+    pub fn __index__(&self) -> i64 {
+        self.0.raw
+    }
+
+    pub fn __hash__(&self) -> i64 {
+        self.0.raw
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!("Time({})", self.0.raw)
+    }
+
+    pub fn __richcmp__(&self, other: &PyAny, op: CompareOp, py: Python<'_>) -> PyObject {
+        let other = match other.extract::<PyRef<'_, Self>>() {
+            Ok(other) => other.0.raw,
+            Err(_) => match other.extract::<i64>() {
+                Ok(raw) => raw,
+                Err(_) => return py.NotImplemented(),
+            },
+        };
+        op.matches(self.0.raw.cmp(&other)).into_py(py)
+    }
+}
@@ -0,0 +1,137 @@
+use std::{
+    fs,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use katsuba_object_property::{
+    serde::{self, Error as OpError, PropertyClass},
+    Value,
+};
+use pyo3::{prelude::*, types::PyDict};
+
+use super::{into_lazy_object, SerializerOptions, TypeList};
+use crate::error;
+
+// An error along with the offset at which deserialization failed.
+type Failure = (OpError, Option<usize>);
+
+/// Deserializes the files at `paths` on a pool of `workers` threads.
+///
+/// Returns a dict which maps every path to its object, or to the
+/// exception it failed with. Errors do not stop the other files.
+///
+/// Reading, decompression and decoding happen without the GIL. It
+/// is only taken to build the results and to invoke `callback` with
+/// `(path, result)` as each file completes. When the callback raises,
+/// no more files are started and its exception is propagated.
+#[pyfunction]
+#[pyo3(signature = (paths, types, options = None, workers = None, callback = None))]
+pub fn deserialize_many(
+    py: Python<'_>,
+    paths: Vec<PyObject>,
+    types: &TypeList,
+    options: Option<SerializerOptions>,
+    workers: Option<NonZeroUsize>,
+    callback: Option<PyObject>,
+) -> PyResult<Py<PyDict>> {
+    let files = paths
+        .iter()
+        .map(|p| p.extract(py))
+        .collect::<PyResult<Vec<PathBuf>>>()?;
+    let workers = workers
+        .or_else(|| thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get)
+        .min(files.len());
+
+    // Every worker gets a serializer of its own. An invalid config
+    // is reported right away rather than for every file.
+    let options = options.unwrap_or_default().0;
+    let serializers = (0..workers)
+        .map(|_| serde::Serializer::new(options, Arc::clone(&types.0)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(error::op_to_py_err)?;
+
+    let results: Py<PyDict> = PyDict::new(py).into();
+
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel::<(usize, Result<Value, Failure>)>();
+
+    py.allow_threads(|| {
+        thread::scope(|s| {
+            for mut de in serializers {
+                let tx = tx.clone();
+                let (files, next, stop) = (&files, &next, &stop);
+                s.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = files.get(idx) else { break };
+
+                        let res = deserialize_file(&mut de, options, path);
+                        if tx.send((idx, res)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+
+            // Results are collected here as they arrive. After a
+            // failed callback, the remaining ones are discarded.
+            let mut failure = None;
+            for (idx, res) in rx {
+                if failure.is_some() {
+                    continue;
+                }
+
+                let res = Python::with_gil(|py| {
+                    let value = match res {
+                        Ok(value) => into_lazy_object(value).into_py(py),
+                        Err((e, offset)) => error::with_offset(py, error::op_to_py_err(e), offset)
+                            .into_value(py)
+                            .into_py(py),
+                    };
+
+                    let key = paths[idx].as_ref(py);
+                    results.as_ref(py).set_item(key, &value)?;
+                    match &callback {
+                        Some(callback) => callback.call1(py, (key, value)).map(drop),
+                        None => Ok(()),
+                    }
+                });
+
+                if let Err(e) = res {
+                    stop.store(true, Ordering::Relaxed);
+                    failure = Some(e);
+                }
+            }
+
+            failure.map_or(Ok(()), Err)
+        })
+    })?;
+
+    Ok(results)
+}
+
+// Deserializes the file at `path`, starting from the base `options`.
+fn deserialize_file(
+    de: &mut serde::Serializer,
+    options: serde::SerializerOptions,
+    path: &Path,
+) -> Result<Value, Failure> {
+    let data = fs::read(path).map_err(|e| (e.into(), None))?;
+
+    // Game files switch to their fixed config, so every file starts
+    // from the base options again.
+    de.parts.options = options;
+    let data = de.parts.options.strip_bind_magic(&data);
+
+    de.deserialize::<PropertyClass>(data)
+        .map_err(|e| (e, de.parts.error_offset()))
+}
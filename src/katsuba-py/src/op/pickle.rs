@@ -0,0 +1,482 @@
+//! A compact binary encoding of [`Value`]s for pickling.
+//!
+//! The format is private to the bindings and only needs to round-trip
+//! between the same versions of the library.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use katsuba_object_property::value::*;
+use pyo3::{exceptions::PyTypeError, prelude::*, types::PyBytes};
+
+use super::{conversion::value_to_python, tree::TreeRef, LazyList, LazyObject};
+use crate::KatsubaError;
+
+const FORMAT_VERSION: u8 = 1;
+
+// Untrusted input should not be able to overflow the stack.
+const MAX_DEPTH: usize = 256;
+
+const EMPTY: u8 = 0;
+const UNSIGNED: u8 = 1;
+const SIGNED: u8 = 2;
+const FLOAT: u8 = 3;
+const BOOL: u8 = 4;
+const STRING: u8 = 5;
+const WSTRING: u8 = 6;
+const ENUM: u8 = 7;
+const LIST: u8 = 8;
+const OBJECT: u8 = 9;
+const COLOR: u8 = 10;
+const VEC3: u8 = 11;
+const QUAT: u8 = 12;
+const EULER: u8 = 13;
+const MAT3X3: u8 = 14;
+const POINT_INT: u8 = 15;
+const POINT_FLOAT: u8 = 16;
+const SIZE_INT: u8 = 17;
+const RECT_INT: u8 = 18;
+const RECT_FLOAT: u8 = 19;
+const BLOB: u8 = 20;
+const UNSET: u8 = 21;
+const MAP: u8 = 22;
+const PAIR: u8 = 23;
+const FLOAT32: u8 = 24;
+const TIME: u8 = 25;
+const GID: u8 = 26;
+
+/// Serializes a lazy value into bytes which can be restored with
+/// [`loads`].
+#[pyfunction]
+pub fn dumps<'py>(py: Python<'py>, value: &PyAny) -> PyResult<&'py PyBytes> {
+    let data = if let Ok(obj) = value.extract::<PyRef<'_, LazyObject>>() {
+        obj.dump()
+    } else if let Ok(list) = value.extract::<PyRef<'_, LazyList>>() {
+        list.dump()
+    } else {
+        return Err(PyTypeError::new_err("expected LazyObject or LazyList"));
+    };
+
+    Ok(PyBytes::new(py, &data))
+}
+
+/// Restores a standalone lazy value from bytes produced by [`dumps`].
+#[pyfunction]
+pub fn loads(py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+    let value = Arc::new(py.allow_threads(|| decode(data))?);
+    Ok(value_to_python(TreeRef::root(value), py))
+}
+
+/// Builds the `__reduce__` result for a lazy value encoded as `data`.
+pub fn reduce(py: Python<'_>, data: Vec<u8>) -> PyResult<(PyObject, (PyObject,))> {
+    let loads = py.import("katsuba.op")?.getattr("loads")?;
+    Ok((loads.into(), (PyBytes::new(py, &data).into(),)))
+}
+
+/// Encodes a list into a new byte buffer.
+///
+/// The result decodes into a [`Value::List`].
+pub fn encode_list(list: &List) -> Vec<u8> {
+    let mut out = vec![FORMAT_VERSION];
+    write_list(&mut out, list);
+    out
+}
+
+/// Encodes an object with its type hash into a new byte buffer.
+///
+/// The result decodes into a [`Value::Object`].
+pub fn encode_object(hash: u32, obj: &Object) -> Vec<u8> {
+    let mut out = vec![FORMAT_VERSION];
+    write_object(&mut out, hash, obj);
+    out
+}
+
+/// Decodes a [`Value`] previously encoded by this module.
+pub fn decode(mut data: &[u8]) -> PyResult<Value> {
+    match read_u8(&mut data)? {
+        FORMAT_VERSION => (),
+        v => return Err(KatsubaError::new_err(format!("unknown format version {v}"))),
+    }
+
+    let value = read_value(&mut data, 0)?;
+    if !data.is_empty() {
+        return Err(malformed());
+    }
+
+    Ok(value)
+}
+
+#[inline]
+fn write_len(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+#[inline]
+fn write_f32s(out: &mut Vec<u8>, values: &[f32]) {
+    for v in values {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+#[inline]
+fn write_i32s(out: &mut Vec<u8>, values: &[i32]) {
+    for v in values {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+fn write_list(out: &mut Vec<u8>, list: &List) {
+    out.push(LIST);
+
+    write_len(out, list.len());
+    for value in list.iter() {
+        write_value(out, value);
+    }
+}
+
+fn write_object(out: &mut Vec<u8>, hash: u32, obj: &Object) {
+    out.push(OBJECT);
+    out.extend_from_slice(&hash.to_le_bytes());
+
+    write_len(out, obj.len());
+    for (name, value) in obj.iter() {
+        write_len(out, name.len());
+        out.extend_from_slice(name.as_bytes());
+        write_value(out, value);
+    }
+}
+
+fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Empty => out.push(EMPTY),
+        Value::Unset => out.push(UNSET),
+        // Sharing is not preserved; every occurrence is written out.
+        Value::Shared(v) => write_value(out, v),
+
+        Value::Unsigned(v) => {
+            out.push(UNSIGNED);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Gid(v) => {
+            out.push(GID);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Signed(v) => {
+            out.push(SIGNED);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Float(v) => {
+            out.push(FLOAT);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Float32(v) => {
+            out.push(FLOAT32);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Bool(v) => {
+            out.push(BOOL);
+            out.push(*v as u8);
+        }
+
+        Value::String(v) => {
+            out.push(STRING);
+            write_len(out, v.0.len());
+            out.extend_from_slice(&v.0);
+        }
+        Value::WString(v) => {
+            out.push(WSTRING);
+            write_len(out, v.0.len());
+            for c in &v.0 {
+                out.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+        Value::Blob(v) => {
+            out.push(BLOB);
+            write_len(out, v.raw.0.len());
+            out.extend_from_slice(&v.raw.0);
+            write_value(out, &v.value);
+        }
+
+        Value::Enum(v) => {
+            out.push(ENUM);
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        Value::Time(v) => {
+            // Unix seconds are the only kind of time so far.
+            let TimeKind::UnixSeconds = v.kind;
+            out.push(TIME);
+            out.extend_from_slice(&v.raw.to_le_bytes());
+        }
+
+        Value::List(v) => write_list(out, v),
+        Value::Map(v) => {
+            out.push(MAP);
+            write_len(out, v.len());
+            for (key, value) in v {
+                write_value(out, key);
+                write_value(out, value);
+            }
+        }
+        Value::Pair(v) => {
+            out.push(PAIR);
+            write_value(out, &v.0);
+            write_value(out, &v.1);
+        }
+        Value::Object { hash, obj } => write_object(out, *hash, obj),
+
+        Value::Color(v) => {
+            out.push(COLOR);
+            out.extend_from_slice(&[v.r, v.g, v.b, v.a]);
+        }
+        Value::Vec3(v) => {
+            out.push(VEC3);
+            write_f32s(out, &[v.x, v.y, v.z]);
+        }
+        Value::Quat(v) => {
+            out.push(QUAT);
+            write_f32s(out, &[v.x, v.y, v.z, v.w]);
+        }
+        Value::Euler(v) => {
+            out.push(EULER);
+            write_f32s(out, &[v.pitch, v.yaw, v.roll]);
+        }
+        Value::Mat3x3(v) => {
+            out.push(MAT3X3);
+            write_f32s(out, &v.i);
+            write_f32s(out, &v.j);
+            write_f32s(out, &v.k);
+        }
+
+        Value::PointInt(v) => {
+            out.push(POINT_INT);
+            write_i32s(out, &[v.x, v.y]);
+        }
+        Value::PointFloat(v) => {
+            out.push(POINT_FLOAT);
+            write_f32s(out, &[v.x, v.y]);
+        }
+
+        Value::SizeInt(v) => {
+            out.push(SIZE_INT);
+            write_i32s(out, &[v.width, v.height]);
+        }
+
+        Value::RectInt(v) => {
+            out.push(RECT_INT);
+            write_i32s(out, &[v.left, v.top, v.right, v.bottom]);
+        }
+        Value::RectFloat(v) => {
+            out.push(RECT_FLOAT);
+            write_f32s(out, &[v.left, v.top, v.right, v.bottom]);
+        }
+    }
+}
+
+#[inline]
+fn malformed() -> PyErr {
+    KatsubaError::new_err("malformed pickle data")
+}
+
+#[inline]
+fn take<'a>(data: &mut &'a [u8], n: usize) -> PyResult<&'a [u8]> {
+    if data.len() < n {
+        return Err(malformed());
+    }
+
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+    Ok(head)
+}
+
+#[inline]
+fn take_array<const N: usize>(data: &mut &[u8]) -> PyResult<[u8; N]> {
+    take(data, N).map(|v| v.try_into().unwrap())
+}
+
+#[inline]
+fn read_u8(data: &mut &[u8]) -> PyResult<u8> {
+    take_array::<1>(data).map(|[v]| v)
+}
+
+#[inline]
+fn read_len(data: &mut &[u8]) -> PyResult<usize> {
+    take_array(data).map(|v| u32::from_le_bytes(v) as usize)
+}
+
+#[inline]
+fn read_f32(data: &mut &[u8]) -> PyResult<f32> {
+    take_array(data).map(f32::from_le_bytes)
+}
+
+#[inline]
+fn read_i32(data: &mut &[u8]) -> PyResult<i32> {
+    take_array(data).map(i32::from_le_bytes)
+}
+
+#[inline]
+fn read_f32s<const N: usize>(data: &mut &[u8]) -> PyResult<[f32; N]> {
+    let mut out = [0.; N];
+    for v in &mut out {
+        *v = read_f32(data)?;
+    }
+
+    Ok(out)
+}
+
+#[inline]
+fn read_i32s<const N: usize>(data: &mut &[u8]) -> PyResult<[i32; N]> {
+    let mut out = [0; N];
+    for v in &mut out {
+        *v = read_i32(data)?;
+    }
+
+    Ok(out)
+}
+
+fn read_value(data: &mut &[u8], depth: usize) -> PyResult<Value> {
+    if depth > MAX_DEPTH {
+        return Err(malformed());
+    }
+
+    let value = match read_u8(data)? {
+        EMPTY => Value::Empty,
+        UNSET => Value::Unset,
+
+        UNSIGNED => Value::Unsigned(u64::from_le_bytes(take_array(data)?)),
+        GID => Value::Gid(u64::from_le_bytes(take_array(data)?)),
+        SIGNED => Value::Signed(i64::from_le_bytes(take_array(data)?)),
+        FLOAT => Value::Float(f64::from_le_bytes(take_array(data)?)),
+        FLOAT32 => Value::Float32(f32::from_le_bytes(take_array(data)?)),
+        BOOL => Value::Bool(read_u8(data)? != 0),
+
+        STRING => {
+            let len = read_len(data)?;
+            Value::String(CxxStr(take(data, len)?.to_vec()))
+        }
+        WSTRING => {
+            let len = read_len(data)?;
+            let raw = take(data, len.checked_mul(2).ok_or_else(malformed)?)?;
+
+            Value::WString(CxxWStr(
+                raw.chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect(),
+            ))
+        }
+        BLOB => {
+            let len = read_len(data)?;
+            let raw = CxxStr(take(data, len)?.to_vec());
+            let value = read_value(data, depth + 1)?;
+
+            Value::Blob(Box::new(Blob { raw, value }))
+        }
+
+        ENUM => Value::Enum(i64::from_le_bytes(take_array(data)?)),
+        TIME => Value::Time(Time::unix_seconds(i64::from_le_bytes(take_array(data)?))),
+
+        LIST => {
+            let len = read_len(data)?;
+
+            // Every element occupies at least one byte; this keeps the
+            // allocation in check for bogus length values.
+            let mut inner = Vec::with_capacity(len.min(data.len()));
+            for _ in 0..len {
+                inner.push(read_value(data, depth + 1)?);
+            }
+
+            Value::List(List { inner })
+        }
+        MAP => {
+            let len = read_len(data)?;
+
+            // Entries occupy at least two bytes.
+            let mut inner = Vec::with_capacity(len.min(data.len() / 2));
+            for _ in 0..len {
+                let key = read_value(data, depth + 1)?;
+                inner.push((key, read_value(data, depth + 1)?));
+            }
+
+            Value::Map(inner)
+        }
+        PAIR => {
+            let first = read_value(data, depth + 1)?;
+            Value::Pair(Box::new((first, read_value(data, depth + 1)?)))
+        }
+        OBJECT => {
+            let hash = u32::from_le_bytes(take_array(data)?);
+            let len = read_len(data)?;
+
+            let mut inner = BTreeMap::new();
+            for _ in 0..len {
+                let name_len = read_len(data)?;
+                let name = std::str::from_utf8(take(data, name_len)?).map_err(|_| malformed())?;
+
+                inner.insert(name.into(), read_value(data, depth + 1)?);
+            }
+
+            Value::Object {
+                hash,
+                obj: Object { inner },
+            }
+        }
+
+        COLOR => {
+            let [r, g, b, a] = take_array(data)?;
+            Value::Color(Color { r, g, b, a })
+        }
+        VEC3 => {
+            let [x, y, z] = read_f32s(data)?;
+            Value::Vec3(Vec3 { x, y, z })
+        }
+        QUAT => {
+            let [x, y, z, w] = read_f32s(data)?;
+            Value::Quat(Quaternion { x, y, z, w })
+        }
+        EULER => {
+            let [pitch, yaw, roll] = read_f32s(data)?;
+            Value::Euler(Euler { pitch, yaw, roll })
+        }
+        MAT3X3 => {
+            let i = read_f32s(data)?;
+            let j = read_f32s(data)?;
+            let k = read_f32s(data)?;
+            Value::Mat3x3(Box::new(Matrix { i, j, k }))
+        }
+
+        POINT_INT => {
+            let [x, y] = read_i32s(data)?;
+            Value::PointInt(Point { x, y })
+        }
+        POINT_FLOAT => {
+            let [x, y] = read_f32s(data)?;
+            Value::PointFloat(Point { x, y })
+        }
+
+        SIZE_INT => {
+            let [width, height] = read_i32s(data)?;
+            Value::SizeInt(Size { width, height })
+        }
+
+        RECT_INT => {
+            let [left, top, right, bottom] = read_i32s(data)?;
+            Value::RectInt(Rect {
+                left,
+                top,
+                right,
+                bottom,
+            })
+        }
+        RECT_FLOAT => {
+            let [left, top, right, bottom] = read_f32s(data)?;
+            Value::RectFloat(Rect {
+                left,
+                top,
+                right,
+                bottom,
+            })
+        }
+
+        _ => return Err(malformed()),
+    };
+
+    Ok(value)
+}
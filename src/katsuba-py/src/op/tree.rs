@@ -0,0 +1,105 @@
+use std::{ptr::NonNull, sync::Arc};
+
+use katsuba_object_property::value::Value;
+
+/// A reference to a node in a shared value tree.
+///
+/// Python wrappers hand out nodes from deep inside a tree, so they
+/// hold on to the whole tree to keep them alive. A [`TreeRef`] can
+/// only be created for the root of a tree or by projecting into an
+/// existing one, which proves that the node is owned by `base`.
+pub struct TreeRef<T> {
+    base: Arc<Value>,
+    node: NonNull<T>,
+}
+
+impl TreeRef<Value> {
+    /// Creates a reference to the root of the tree `base`.
+    pub fn root(base: Arc<Value>) -> Self {
+        let node = NonNull::from(&*base);
+        Self { base, node }
+    }
+}
+
+impl<T> TreeRef<T> {
+    /// Gets the referenced node.
+    #[inline(always)]
+    pub fn get(&self) -> &T {
+        // SAFETY: The node is owned by `base`, which we keep alive and
+        // which is never mutated.
+        unsafe { self.node.as_ref() }
+    }
+
+    /// Gets the address of the referenced node.
+    #[inline]
+    pub fn addr(&self) -> usize {
+        self.node.as_ptr() as usize
+    }
+
+    /// Creates a reference to a node below this one.
+    ///
+    /// `f` must work for any lifetime of the borrow it is given, so
+    /// the node it returns cannot come from outside of the tree.
+    pub fn project<U>(&self, f: impl for<'a> FnOnce(&'a T) -> &'a U) -> TreeRef<U> {
+        self.derive(NonNull::from(f(self.get())))
+    }
+
+    /// Like [`TreeRef::project`], but for nodes which may not exist.
+    pub fn try_project<U>(
+        &self,
+        f: impl for<'a> FnOnce(&'a T) -> Option<&'a U>,
+    ) -> Option<TreeRef<U>> {
+        f(self.get()).map(|node| self.derive(NonNull::from(node)))
+    }
+
+    fn derive<U>(&self, node: NonNull<U>) -> TreeRef<U> {
+        #[cfg(test)]
+        assert!(
+            is_reachable(&self.base, node.as_ptr().cast::<u8>()),
+            "projected node is not owned by the tree"
+        );
+
+        TreeRef {
+            base: self.base.clone(),
+            node,
+        }
+    }
+}
+
+impl<T> Clone for TreeRef<T> {
+    fn clone(&self) -> Self {
+        Self {
+            base: self.base.clone(),
+            node: self.node,
+        }
+    }
+}
+
+// SAFETY: The node is never exposed for mutation and `Value` is
+// `Send + Sync`.
+unsafe impl<T: Sync> Send for TreeRef<T> {}
+
+// Checks whether `ptr` points into a value of the tree `base`.
+//
+// This walks the whole tree for every projection, which is too slow
+// for anything but the tests.
+#[cfg(test)]
+fn is_reachable(base: &Value, ptr: *const u8) -> bool {
+    let start = base as *const Value as *const u8;
+    let end = start.wrapping_add(size_of::<Value>());
+    if (start..end).contains(&ptr) {
+        return true;
+    }
+
+    match base {
+        Value::Blob(blob) => is_reachable(&blob.value, ptr),
+        Value::Shared(v) => is_reachable(v, ptr),
+        Value::List(list) => list.iter().any(|v| is_reachable(v, ptr)),
+        Value::Map(map) => map
+            .iter()
+            .any(|(k, v)| is_reachable(k, ptr) || is_reachable(v, ptr)),
+        Value::Pair(pair) => is_reachable(&pair.0, ptr) || is_reachable(&pair.1, ptr),
+        Value::Object { obj, .. } => obj.values().any(|v| is_reachable(v, ptr)),
+        _ => false,
+    }
+}
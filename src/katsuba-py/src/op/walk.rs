@@ -0,0 +1,85 @@
+use katsuba_object_property::value::{Object, Value};
+use pyo3::prelude::*;
+
+use super::{lazy::LazyObject, tree::TreeRef};
+
+/// Joins a child segment onto the path of its parent value.
+#[inline]
+pub fn join_path(parent: &str, segment: &str) -> String {
+    if parent.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{parent}/{segment}")
+    }
+}
+
+/// An iterator over all the objects nested in a [`LazyObject`].
+///
+/// The value tree is walked in pre-order on the Rust side, so only
+/// the yielded objects pay for conversion to Python.
+#[pyclass(module = "katsuba.op")]
+pub struct ObjectWalker {
+    stack: Vec<(String, TreeRef<Value>)>,
+    type_hash: Option<u32>,
+}
+
+impl ObjectWalker {
+    pub fn new(current: TreeRef<Object>, type_hash: Option<u32>) -> Self {
+        let mut this = Self {
+            stack: Vec::new(),
+            type_hash,
+        };
+        this.push_children("", &current);
+
+        this
+    }
+
+    fn push_children(&mut self, path: &str, obj: &TreeRef<Object>) {
+        // Children are pushed in reverse so they get popped in order.
+        for name in obj.get().keys().rev() {
+            let child = obj.project(|obj| &obj[name]);
+            self.stack.push((join_path(path, name), child));
+        }
+    }
+}
+
+#[pymethods]
+impl ObjectWalker {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(String, LazyObject)> {
+        while let Some((path, value)) = slf.stack.pop() {
+            let value = value.project(Value::resolve);
+
+            match value.get() {
+                Value::List(list) => {
+                    for idx in (0..list.len()).rev() {
+                        let child = value.project(|v| match v {
+                            Value::List(list) => &list[idx],
+                            _ => unreachable!(),
+                        });
+                        slf.stack.push((join_path(&path, &idx.to_string()), child));
+                    }
+                }
+
+                Value::Object { hash, .. } => {
+                    let obj = value.project(|v| match v {
+                        Value::Object { obj, .. } => obj,
+                        _ => unreachable!(),
+                    });
+                    slf.push_children(&path, &obj);
+
+                    if slf.type_hash.map(|h| h == *hash).unwrap_or(true) {
+                        return Some((path, LazyObject::new(*hash, obj)));
+                    }
+                }
+
+                _ => (),
+            }
+        }
+
+        None
+    }
+}
@@ -1,7 +1,13 @@
 use std::{borrow::Cow, collections::btree_map, path::PathBuf};
 
-use katsuba_object_property::serde;
-use pyo3::{exceptions::PyKeyError, prelude::*, types::PyType};
+use pyo3::{
+    exceptions::{PyKeyError, PyTypeError},
+    prelude::*,
+    types::PyType,
+};
+
+use katsuba_executor::Executor;
+use katsuba_wad::extract::{self, ExtractOptions};
 
 use crate::{error, op, KatsubaError};
 
@@ -11,7 +17,7 @@ fn extract_file_contents<'a>(
 ) -> PyResult<Cow<'a, [u8]>> {
     let contents = archive
         .file_contents(file)
-        .ok_or_else(|| KatsubaError::new_err("file contents missing from archive"))?;
+        .ok_or_else(|| error::ArchiveCorruptError::new_err("file contents missing from archive"))?;
 
     let contents = match file.compressed {
         true => {
@@ -20,7 +26,7 @@ fn extract_file_contents<'a>(
             let mut inflater = katsuba_wad::Inflater::new();
             inflater
                 .decompress(contents, file.uncompressed_size as _)
-                .map_err(|e| KatsubaError::new_err(format!("{e:?}")))?;
+                .map_err(|e| error::DecompressionError::new_err(e.to_string()))?;
 
             Cow::Owned(inflater.into_inner())
         }
@@ -32,7 +38,7 @@ fn extract_file_contents<'a>(
 }
 
 #[pyclass(module = "katsuba.wad")]
-struct Archive(katsuba_wad::Archive);
+pub struct Archive(katsuba_wad::Archive);
 
 #[pymethods]
 impl Archive {
@@ -44,9 +50,9 @@ impl Archive {
         self.0.files().contains_key(file)
     }
 
-    pub fn __getitem__(&self, file: &str) -> PyResult<Cow<'_, [u8]>> {
+    pub fn __getitem__(&self, py: Python<'_>, file: &str) -> PyResult<Cow<'_, [u8]>> {
         if let Some(file) = self.0.file_raw(file) {
-            extract_file_contents(&self.0, file)
+            py.allow_threads(|| extract_file_contents(&self.0, file))
         } else {
             Err(PyKeyError::new_err(file.to_string()))
         }
@@ -72,39 +78,167 @@ impl Archive {
     }
 
     #[classmethod]
-    pub fn heap(_cls: &PyType, path: PathBuf) -> PyResult<Self> {
-        katsuba_wad::Archive::open_heap(path)
+    pub fn heap(_cls: &PyType, py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        py.allow_threads(|| katsuba_wad::Archive::open_heap(path))
             .map(Self)
             .map_err(error::wad_to_py_err)
     }
 
     #[classmethod]
-    pub fn mmap(_cls: &PyType, path: PathBuf) -> PyResult<Self> {
-        katsuba_wad::Archive::open_mmap(path)
+    pub fn mmap(_cls: &PyType, py: Python<'_>, path: PathBuf) -> PyResult<Self> {
+        py.allow_threads(|| katsuba_wad::Archive::open_mmap(path))
             .map(Self)
             .map_err(error::wad_to_py_err)
     }
 
+    /// Extracts all files into the directory at `dest`.
+    ///
+    /// `mode` overrides the archive's mode for the created files.
+    /// With `resume`, files finished by an interrupted call are
+    /// skipped. With `keep_empty_dirs`, directories which end up
+    /// without files are not removed.
+    #[pyo3(signature = (dest, mode = None, resume = false, keep_empty_dirs = false))]
+    pub fn extract_all(
+        &self,
+        py: Python<'_>,
+        dest: PathBuf,
+        mode: Option<u32>,
+        resume: bool,
+        keep_empty_dirs: bool,
+    ) -> PyResult<ExtractReport> {
+        let opts = ExtractOptions {
+            mode,
+            resume,
+            keep_empty_dirs,
+            ..Default::default()
+        };
+
+        let report = py.allow_threads(|| {
+            let ex = Executor::get().map_err(|e| KatsubaError::new_err(e.to_string()))?;
+            extract::extract(&ex, &self.0, &dest, &opts, ()).map_err(error::extract_to_py_err)
+        })?;
+
+        Ok(report.into())
+    }
+
+    /// Deserializes the object stored in `file`.
+    ///
+    /// `source` is either a `Serializer` or a `TypeList`; the latter
+    /// creates a serializer configured with `options`.
+    #[pyo3(signature = (file, source, options = None))]
     pub fn deserialize(
         &self,
+        py: Python<'_>,
         file: &str,
-        serializer: &mut op::Serializer,
+        source: SerializerSource<'_>,
+        options: Option<op::SerializerOptions>,
     ) -> PyResult<op::LazyObject> {
-        let raw = self.__getitem__(file)?;
-        let mut raw: &[u8] = &raw;
+        let serializer = source.into_serializer(py, options)?;
+        let mut serializer = serializer.borrow_mut(py);
 
-        // Set generic configuration for game files if this is one.
-        if raw.get(0..4) == Some(serde::BIND_MAGIC) {
-            serializer.0.parts.options.flags |= serde::SerializerFlags::STATEFUL_FLAGS;
-            serializer.0.parts.options.shallow = false;
+        deserialize_entry(py, &self.0, file, &mut serializer)
+    }
+
+    /// Lazily deserializes every file matching the glob `pattern`,
+    /// yielding `(path, object)` pairs.
+    #[pyo3(signature = (pattern, source, options = None))]
+    pub fn deserialize_all(
+        slf: PyRef<'_, Self>,
+        pattern: &str,
+        source: SerializerSource<'_>,
+        options: Option<op::SerializerOptions>,
+    ) -> PyResult<Py<DeserializeIter>> {
+        let py = slf.py();
+        let matcher = katsuba_wad::glob::Matcher::new(pattern)
+            .map_err(|e| KatsubaError::new_err(format!("{e:?}")))?;
+
+        let iter = DeserializeIter {
+            serializer: source.into_serializer(py, options)?,
+            matcher,
+            iter: slf.0.files().clone().into_keys(),
+            archive: slf.into(),
+        };
 
-            raw = raw.get(4..).unwrap();
+        Py::new(py, iter)
+    }
+}
+
+/// The outcome of `Archive.extract_all`.
+#[pyclass(module = "katsuba.wad")]
+pub struct ExtractReport {
+    #[pyo3(get)]
+    pub from_archive: usize,
+    #[pyo3(get)]
+    pub from_patch: usize,
+    #[pyo3(get)]
+    pub skipped: usize,
+    #[pyo3(get)]
+    pub resumed: usize,
+    #[pyo3(get)]
+    pub unavailable: usize,
+}
+
+impl From<extract::ExtractReport> for ExtractReport {
+    fn from(report: extract::ExtractReport) -> Self {
+        Self {
+            from_archive: report.from_archive,
+            from_patch: report.from_patch,
+            skipped: report.skipped,
+            resumed: report.resumed,
+            unavailable: report.unavailable,
         }
+    }
+}
+
+#[derive(FromPyObject)]
+pub enum SerializerSource<'py> {
+    Serializer(Py<op::Serializer>),
+    TypeList(PyRef<'py, op::TypeList>),
+}
 
-        serializer.deserialize(raw)
+impl SerializerSource<'_> {
+    fn into_serializer(
+        self,
+        py: Python<'_>,
+        options: Option<op::SerializerOptions>,
+    ) -> PyResult<Py<op::Serializer>> {
+        match self {
+            Self::Serializer(serializer) if options.is_none() => Ok(serializer),
+            Self::Serializer(..) => Err(PyTypeError::new_err(
+                "options cannot be combined with a Serializer",
+            )),
+            Self::TypeList(types) => {
+                let serializer = op::Serializer::new(options.unwrap_or_default(), &types)?;
+                Py::new(py, serializer)
+            }
+        }
     }
 }
 
+fn deserialize_entry(
+    py: Python<'_>,
+    archive: &katsuba_wad::Archive,
+    file: &str,
+    serializer: &mut op::Serializer,
+) -> PyResult<op::LazyObject> {
+    let raw = archive
+        .file_raw(file)
+        .ok_or_else(|| PyKeyError::new_err(file.to_string()))?;
+    let raw = py
+        .allow_threads(|| extract_file_contents(archive, raw))
+        .map_err(|e| error::with_entry_path(py, e, file))?;
+
+    // Set generic configuration for game files if this is one.
+    // The caller's options are restored after deserialization.
+    let options = serializer.0.parts.options;
+    let raw = serializer.0.parts.options.strip_bind_magic(&raw);
+
+    let res = serializer.deserialize(py, raw);
+    serializer.0.parts.options = options;
+
+    res.map_err(|e| error::with_entry_path(py, e, file))
+}
+
 #[pyclass(module = "katsuba.wad")]
 pub struct ArchiveIter {
     iter: btree_map::IntoKeys<String, katsuba_wad::types::File>,
@@ -144,9 +278,45 @@ impl GlobArchiveIter {
     }
 }
 
+#[pyclass(module = "katsuba.wad")]
+pub struct DeserializeIter {
+    archive: Py<Archive>,
+    serializer: Py<op::Serializer>,
+    matcher: katsuba_wad::glob::Matcher,
+    iter: btree_map::IntoKeys<String, katsuba_wad::types::File>,
+}
+
+#[pymethods]
+impl DeserializeIter {
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    pub fn __next__(
+        mut slf: PyRefMut<'_, Self>,
+        py: Python<'_>,
+    ) -> PyResult<Option<(String, op::LazyObject)>> {
+        let path = loop {
+            match slf.iter.next() {
+                Some(path) if slf.matcher.is_match(&path) => break path,
+                Some(..) => continue,
+                None => return Ok(None),
+            }
+        };
+
+        let archive = slf.archive.borrow(py);
+        let mut serializer = slf.serializer.borrow_mut(py);
+        let obj = deserialize_entry(py, &archive.0, &path, &mut serializer)?;
+
+        Ok(Some((path, obj)))
+    }
+}
+
 pub fn katsuba_wad(m: &PyModule) -> PyResult<()> {
     m.add_class::<Archive>()?;
     m.add_class::<ArchiveIter>()?;
+    m.add_class::<DeserializeIter>()?;
+    m.add_class::<ExtractReport>()?;
     m.add_class::<GlobArchiveIter>()?;
 
     Ok(())
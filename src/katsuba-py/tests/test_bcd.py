@@ -0,0 +1,62 @@
+"""Checks parsing of BCD collision files.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import pathlib
+import unittest
+
+import katsuba
+from katsuba import bcd
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba-bcd" / "tests" / "data"
+
+WALKABLE = 1 << 1
+OBJECT = 1 << 0
+HITSCAN = 1 << 3
+WATER = 1 << 6
+
+
+class BcdTest(unittest.TestCase):
+    def setUp(self):
+        self.data = (DATA / "Collision.bcd").read_bytes()
+
+    def test_box(self):
+        floor = bcd.parse(self.data).collisions[0]
+
+        self.assertEqual(floor.category_flags, WALKABLE)
+        self.assertEqual(floor.collision_flags, OBJECT | HITSCAN)
+        self.assertIsNone(floor.mesh)
+
+        geometry = floor.geometry
+        self.assertEqual(geometry.name, "floor")
+        self.assertEqual(geometry.material, "stone")
+        self.assertEqual(geometry.location, [0.0, -5.0, 0.0])
+        self.assertEqual(geometry.shape, "box")
+        self.assertEqual(geometry.params, {"length": 10.0, "width": 10.0, "depth": 1.0})
+
+    def test_mesh(self):
+        collisions = bcd.parse(self.data)
+        self.assertEqual(len(collisions), 2)
+
+        pond = collisions.collisions[1]
+        self.assertEqual(pond.category_flags, WATER)
+        self.assertEqual(pond.geometry.shape, "mesh")
+        self.assertEqual(pond.geometry.params, {})
+
+        mesh = pond.mesh
+        self.assertEqual(mesh.vertices, [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]])
+        self.assertEqual(mesh.faces, [[0, 1, 2]])
+        self.assertEqual(mesh.normals, [[0.0, 0.0, 1.0]])
+
+    def test_truncated_input(self):
+        with self.assertRaises(katsuba.ParseError) as cm:
+            bcd.parse(self.data[:-3])
+
+        self.assertIsInstance(cm.exception, katsuba.KatsubaError)
+        self.assertEqual(str(cm.exception), "unexpected end of input")
+
+
+if __name__ == "__main__":
+    unittest.main()
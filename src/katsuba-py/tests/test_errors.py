@@ -0,0 +1,53 @@
+"""Checks the exceptions raised by failed deserialization.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import pathlib
+import unittest
+
+import katsuba
+from katsuba import op
+from katsuba.utils import string_id
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba" / "tests" / "data" / "op"
+
+
+def load_item(types="types.json"):
+    types = op.TypeList.open(str(DATA / types))
+    data = (DATA / "item.bin").read_bytes()[4:]
+    opts = op.SerializerOptions(flags=op.STATEFUL_FLAGS, shallow=False)
+    return op.Serializer(opts, types), data
+
+
+class ErrorTest(unittest.TestCase):
+    def test_offset_of_truncated_data(self):
+        ser, data = load_item()
+
+        with self.assertRaises(katsuba.SizeMismatchError) as cm:
+            ser.deserialize(data[:20])
+        self.assertEqual(cm.exception.offset, 8)
+
+    def test_offset_of_trailing_data(self):
+        ser, data = load_item()
+
+        # Offsets exclude the 4 bytes of serializer flags up front.
+        with self.assertRaises(katsuba.KatsubaError) as cm:
+            ser.deserialize(data + b"\xde\xad")
+        self.assertEqual(cm.exception.offset, len(data) - 4)
+
+    def test_unknown_type_hash(self):
+        ser, data = load_item("behaviors.json")
+
+        try:
+            ser.deserialize(data)
+        except katsuba.UnknownTypeError as e:
+            self.assertEqual(e.type_hash, string_id("class Item"))
+            self.assertIsInstance(e, katsuba.KatsubaError)
+        else:
+            self.fail("UnknownTypeError not raised")
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,54 @@
+"""Checks the generated flag classes against the Rust definitions.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import enum
+import unittest
+
+import katsuba
+from katsuba import op
+
+
+class FlagsTest(unittest.TestCase):
+    def test_members_match_constants(self):
+        # The bare module constants are taken from the Rust flag bits
+        # independently of the generated classes.
+        for cls in (op.SerializerFlags, op.PropertyFlags):
+            self.assertTrue(issubclass(cls, enum.IntFlag))
+            self.assertIs(getattr(katsuba, cls.__name__), cls)
+
+            for name, member in cls.__members__.items():
+                self.assertEqual(member.value, getattr(op, name), name)
+
+        self.assertEqual(len(op.SerializerFlags.__members__), 5)
+        self.assertEqual(op.PropertyFlags.TRANSMIT, 1 << 3)
+
+    def test_options_accept_flags(self):
+        flags = op.SerializerFlags.STATEFUL_FLAGS | op.SerializerFlags.WITH_COMPRESSION
+        for value in (flags, int(flags), ["STATEFUL_FLAGS", "WITH_COMPRESSION"]):
+            opts = op.SerializerOptions(flags=value)
+            self.assertEqual(opts.flags, flags)
+            self.assertIsInstance(opts.flags, op.SerializerFlags)
+
+        opts.property_mask = ["PUBLIC", "TRANSMIT"]
+        self.assertEqual(opts.property_mask, op.PUBLIC | op.TRANSMIT)
+
+        with self.assertRaises(ValueError):
+            opts.property_mask = ["NOT_A_FLAG"]
+
+    def test_mask_presets(self):
+        self.assertEqual(op.PropertyMask.transmit(), 0x18)
+        self.assertEqual(op.PropertyMask.save(), 0x1)
+        self.assertEqual(op.PropertyMask.copy(), 0x2)
+        self.assertEqual(op.PropertyMask.all(), 0x0)
+        self.assertIsInstance(op.PropertyMask.save(), op.PropertyFlags)
+
+        opts = op.SerializerOptions(property_mask=op.PropertyMask.save())
+        self.assertEqual(opts.property_mask, op.PropertyFlags.SAVE)
+        self.assertEqual(op.SerializerOptions().property_mask, op.PropertyMask.transmit())
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,127 @@
+"""Checks the JSON encoding of lazy values.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import json
+import math
+import os
+import pathlib
+import shutil
+import struct
+import subprocess
+import tempfile
+import unittest
+
+from katsuba import op
+from katsuba.utils import string_id
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba" / "tests" / "data" / "op"
+
+HOLDER = string_id("class Holder")
+
+
+def find_cli():
+    # An explicit path wins over a build of the workspace and `PATH`.
+    if "KATSUBA_CLI" in os.environ:
+        return os.environ["KATSUBA_CLI"]
+
+    target = pathlib.Path(__file__).parents[3] / "target"
+    for profile in ("debug", "release"):
+        path = target / profile / "katsuba"
+        if path.exists():
+            return str(path)
+
+    return shutil.which("katsuba")
+
+
+CLI = find_cli()
+
+
+def load_floats(values):
+    types = {
+        "version": 2,
+        "classes": {
+            str(HOLDER): {
+                "name": "class Holder",
+                "bases": [],
+                "hash": HOLDER,
+                "properties": {
+                    "m_floats": {
+                        "type": "float",
+                        "id": 0,
+                        "flags": 24,
+                        "dynamic": True,
+                        "hash": 1,
+                    }
+                },
+            }
+        },
+    }
+    with tempfile.TemporaryDirectory() as tmp:
+        path = pathlib.Path(tmp) / "types.json"
+        path.write_text(json.dumps(types))
+        types = op.TypeList.open(str(path))
+
+    data = struct.pack(f"<II{len(values)}f", HOLDER, len(values), *values)
+    opts = op.SerializerOptions(shallow=True)
+    return op.Serializer(opts, types).deserialize(data)
+
+
+class NonFiniteTest(unittest.TestCase):
+    def setUp(self):
+        self.holder = load_floats([1.5, math.nan, -math.inf])
+
+    def test_strings_by_default(self):
+        expected = {"$__type": HOLDER, "m_floats": [1.5, "NaN", "-Infinity"]}
+        self.assertEqual(json.loads(self.holder.to_json()), expected)
+        self.assertEqual(json.loads(self.holder["m_floats"].to_json()), expected["m_floats"])
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = pathlib.Path(tmp) / "holder.json"
+            self.holder.to_json_file(str(path))
+            self.assertEqual(json.loads(path.read_text()), expected)
+
+    def test_null(self):
+        floats = json.loads(self.holder.to_json(nonfinite="null"))["m_floats"]
+        self.assertEqual(floats, [1.5, None, None])
+
+    def test_error(self):
+        with self.assertRaisesRegex(ValueError, r"m_floats\[1\]: NaN"):
+            self.holder.to_json(nonfinite="error")
+        with self.assertRaisesRegex(ValueError, r"\[1\]: NaN"):
+            self.holder["m_floats"].to_json(nonfinite="error")
+
+        finite = load_floats([1.5])
+        self.assertEqual(json.loads(finite.to_json(nonfinite="error"))["m_floats"], [1.5])
+
+    def test_invalid_policy(self):
+        with self.assertRaises(ValueError):
+            self.holder.to_json(nonfinite="zero")
+
+
+@unittest.skipIf(CLI is None, "needs the katsuba CLI, e.g. from `cargo build -p katsuba`")
+class CliTest(unittest.TestCase):
+    def test_matches_op_de(self):
+        types = DATA / "types.json"
+        item = DATA / "item.bin"
+        cli = subprocess.run(
+            [CLI, "op", "-t", str(types), "de", "-o", "-", str(item)],
+            capture_output=True,
+            check=True,
+        ).stdout
+
+        opts = op.SerializerOptions(flags=op.STATEFUL_FLAGS, shallow=False)
+        ser = op.Serializer(opts, op.TypeList.open(str(types)))
+        value = ser.deserialize(item.read_bytes()[4:])
+        self.assertEqual(value.to_json().encode(), cli.rstrip(b"\n"))
+
+        with tempfile.TemporaryDirectory() as tmp:
+            path = pathlib.Path(tmp) / "item.json"
+            value.to_json_file(str(path))
+            self.assertEqual(path.read_bytes(), cli.rstrip(b"\n"))
+
+
+if __name__ == "__main__":
+    unittest.main()
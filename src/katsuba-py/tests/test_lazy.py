@@ -0,0 +1,111 @@
+"""Checks the wrappers for lazily converted values.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import gc
+import pathlib
+import unittest
+
+from katsuba import op
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba" / "tests" / "data" / "op"
+
+
+def load_item():
+    types = op.TypeList.open(str(DATA / "types.json"))
+    data = (DATA / "item.bin").read_bytes()[4:]
+    opts = op.SerializerOptions(flags=op.STATEFUL_FLAGS, shallow=False)
+    return op.Serializer(opts, types).deserialize(data)
+
+
+def load_template():
+    types = op.TypeList.open(str(DATA / "behaviors.json"))
+    data = (DATA / "template_shallow.bin").read_bytes()
+    opts = op.SerializerOptions(shallow=True)
+    return types, op.Serializer(opts, types).deserialize(data)
+
+
+class LazyTest(unittest.TestCase):
+    def test_children_are_cached(self):
+        item = load_item()
+
+        self.assertIs(item["m_upgrade"], item["m_upgrade"])
+        self.assertIs(item.get("m_tags"), item["m_tags"])
+        self.assertIs(item.query(""), item)
+        self.assertIs(item.query("m_upgrade"), item["m_upgrade"])
+
+        tags = item["m_tags"]
+        self.assertEqual(list(tags), [tags[i] for i in range(len(tags))])
+
+    def test_query_paths(self):
+        item = load_item()
+
+        self.assertEqual(item.query("m_tags[0]"), b"hat")
+        self.assertEqual(item.query("m_tags/0"), b"hat")
+        self.assertEqual(item.query("m_upgrade.m_displayName"), "Cooler Hat")
+
+        with self.assertRaises(ValueError):
+            item.query("m_tags[x]")
+
+    def test_behaviors_by_class(self):
+        types, template = load_template()
+
+        render = template.behavior("RenderBehaviorTemplate", types)
+        self.assertEqual(render["m_behaviorName"], b"Animation")
+        self.assertEqual(render.type_hash, types.hash_for("AnimationBehaviorTemplate"))
+
+        names = [b["m_behaviorName"] for b in template.behaviors_of("BehaviorTemplate", types)]
+        self.assertEqual(names, [b"Collision", b"Animation", b"Render"])
+
+        self.assertIsNone(template.behavior("MissingBehaviorTemplate", types))
+        self.assertEqual(render.behaviors_of("BehaviorTemplate", types), [])
+
+
+class TreeLifetimeTest(unittest.TestCase):
+    """Children must keep the tree alive after their root is gone.
+
+    These are most useful under a sanitizer, see the `python-asan`
+    job of the CI workflow.
+    """
+
+    def test_children_outlive_root(self):
+        item = load_item()
+        upgrade = item["m_upgrade"]
+        tags = item["m_tags"]
+        name = item.query("m_upgrade.m_displayName")
+
+        del item
+        for _ in range(10):
+            gc.collect()
+            self.assertEqual(upgrade["m_displayName"], name)
+            self.assertEqual(tags[0], b"hat")
+
+    def test_iterate_while_collecting(self):
+        for _ in range(50):
+            iterator = iter(load_item()["m_tags"])
+            gc.collect()
+
+            for tag in iterator:
+                gc.collect()
+                self.assertIsInstance(tag, bytes)
+
+    def test_walk_after_root_is_gone(self):
+        item = load_item()
+        expected = [path for path, _ in item.walk()]
+        walker = item.walk()
+
+        del item
+        gc.collect()
+        walked = []
+        for path, obj in walker:
+            gc.collect()
+            walked.append(path)
+            self.assertGreaterEqual(len(obj), 0)
+
+        self.assertEqual(walked, expected)
+
+
+if __name__ == "__main__":
+    unittest.main()
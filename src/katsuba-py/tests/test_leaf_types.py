@@ -0,0 +1,89 @@
+"""Checks the Python classes for leaf values.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import datetime
+import math
+import unittest
+
+from katsuba import op
+from test_lazy import load_item
+
+
+class ColorTest(unittest.TestCase):
+    def test_channels(self):
+        tint = load_item()["m_tint"]
+
+        self.assertEqual((tint.r, tint.g, tint.b, tint.a), (1, 2, 3, 255))
+        self.assertEqual(tint.as_hex(), "#010203FF")
+        self.assertEqual(repr(tint), "Color(r=1, g=2, b=3, a=255)")
+
+        r, g, b, a = tint
+        self.assertEqual([r, g, b, a], [1, 2, 3, 255])
+        self.assertEqual(len(tint), 4)
+        self.assertEqual((tint[0], tint[-1]), (1, 255))
+        self.assertEqual((tint["g"], tint["b"]), (2, 3))
+
+        with self.assertRaises(IndexError):
+            tint[4]
+        with self.assertRaises(KeyError):
+            tint["x"]
+
+
+class MathTest(unittest.TestCase):
+    def test_vec3(self):
+        pos = load_item()["m_position"]
+
+        self.assertEqual(tuple(pos), (1.25, 2.5, -3.75))
+        self.assertEqual(repr(pos), "Vec3(x=1.25, y=2.5, z=-3.75)")
+        self.assertEqual(pos[-1], -3.75)
+        self.assertEqual(op.Vec3(3, 4, 0).length(), 5)
+
+        self.assertEqual(pos, op.Vec3(1.25, 2.5, -3.75 + 1e-6))
+        self.assertNotEqual(pos, op.Vec3(1.25, 2.5, -3.7))
+
+    def test_quaternion_to_euler(self):
+        half = math.sqrt(0.5)
+        cases = [
+            (op.Quaternion(0, 0, 0, 1), (0, 0, 0)),
+            (op.Quaternion(half, 0, 0, half), (math.pi / 2, 0, 0)),
+            (op.Quaternion(0, half, 0, half), (0, math.pi / 2, 0)),
+            (op.Quaternion(0, 0, half, half), (0, 0, math.pi / 2)),
+        ]
+        for quat, (pitch, yaw, roll) in cases:
+            self.assertEqual(quat.to_euler(), op.Euler(pitch, yaw, roll), quat)
+
+    def test_matrix(self):
+        m = op.Matrix3x3([1, 0, 0], [0, 1, 0], [0, 0, 1])
+
+        self.assertIs(op.Matrix, op.Matrix3x3)
+        self.assertEqual(len(m), 3)
+        self.assertEqual(list(m), [(1, 0, 0), (0, 1, 0), (0, 0, 1)])
+        self.assertEqual(m[1], (0, 1, 0))
+        self.assertEqual(repr(m), "Matrix3x3(i=[1.0, 0.0, 0.0], j=[0.0, 1.0, 0.0], k=[0.0, 0.0, 1.0])")
+        self.assertEqual(m, op.Matrix3x3([1, 0, 0], [0, 1, 0], [0, 0, 1 + 1e-7]))
+
+
+class TimeTest(unittest.TestCase):
+    def test_conversions(self):
+        t = op.Time(951782400)
+
+        self.assertEqual((t.raw, t.kind), (951782400, "unix_seconds"))
+        self.assertEqual(int(t), 951782400)
+        self.assertEqual(t, 951782400)
+        self.assertLess(t, op.Time(951782401))
+        self.assertEqual(t.isoformat(), "2000-02-29T00:00:00Z")
+
+        utc = datetime.datetime(2000, 2, 29, tzinfo=datetime.timezone.utc)
+        self.assertEqual(t.to_datetime(), utc)
+
+        tz = datetime.timezone(datetime.timedelta(hours=2))
+        local = t.to_datetime(tz)
+        self.assertEqual(local.hour, 2)
+        self.assertEqual(local, utc)
+
+
+if __name__ == "__main__":
+    unittest.main()
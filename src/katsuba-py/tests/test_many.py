@@ -0,0 +1,75 @@
+"""Checks deserializing many files in parallel.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import pathlib
+import tempfile
+import unittest
+
+import katsuba
+from katsuba import op
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba" / "tests" / "data" / "op"
+
+
+class DeserializeManyTest(unittest.TestCase):
+    def setUp(self):
+        self.types = op.TypeList.open(str(DATA / "types.json"))
+        self.opts = op.SerializerOptions(flags=op.STATEFUL_FLAGS, shallow=False)
+
+    def test_collects_results_and_errors(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            garbage = pathlib.Path(tmp) / "garbage.bin"
+            garbage.write_bytes(b"\xff" * 16)
+            missing = str(pathlib.Path(tmp) / "missing.bin")
+            item = str(DATA / "item.bin")
+
+            seen = []
+            results = op.deserialize_many(
+                [item, garbage, missing],
+                self.types,
+                self.opts,
+                workers=2,
+                callback=lambda path, res: seen.append(path),
+            )
+
+        self.assertEqual(len(seen), 3)
+        self.assertEqual(set(results), {item, garbage, missing})
+        self.assertIsInstance(results[item], op.LazyObject)
+        self.assertIsInstance(results[garbage], Exception)
+        self.assertIsInstance(results[missing], FileNotFoundError)
+
+    def test_errors_have_offsets(self):
+        with tempfile.TemporaryDirectory() as tmp:
+            truncated = pathlib.Path(tmp) / "truncated.bin"
+            truncated.write_bytes((DATA / "item.bin").read_bytes()[:24])
+            missing = str(pathlib.Path(tmp) / "missing.bin")
+
+            results = op.deserialize_many([truncated, missing], self.types, self.opts)
+
+        # The same offset as with `Serializer.deserialize`, after the
+        # `BINd` magic and the serializer flags.
+        self.assertIsInstance(results[truncated], katsuba.SizeMismatchError)
+        self.assertEqual(results[truncated].offset, 8)
+        self.assertIsNone(results[missing].offset)
+
+    def test_invalid_options(self):
+        opts = op.SerializerOptions(shallow=True, skip_unknown_types=True)
+
+        with self.assertRaises(katsuba.KatsubaError):
+            op.deserialize_many([str(DATA / "item.bin")], self.types, opts)
+
+    def test_callback_errors_propagate(self):
+        def callback(path, res):
+            raise KeyboardInterrupt
+
+        with self.assertRaises(KeyboardInterrupt):
+            op.deserialize_many([str(DATA / "item.bin")] * 4, self.types, callback=callback)
+
+        self.assertEqual(op.deserialize_many([], self.types), {})
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,45 @@
+"""Checks parsing of NAV graph files.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import pathlib
+import unittest
+
+import katsuba
+from katsuba import nav
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba-nav" / "tests" / "data"
+
+
+class NavTest(unittest.TestCase):
+    def assert_graph(self, graph):
+        nodes = [(node.location, node.id) for node in graph.nodes]
+        self.assertEqual(nodes, [([-1.0, 0.0, 4.0], 0), ([3.0, 2.0, -4.0], 1)])
+
+        links = [(link.first, link.second) for link in graph.links]
+        self.assertEqual(links, [(0, 1)])
+
+    def test_graph(self):
+        self.assert_graph(nav.parse((DATA / "Graph.nav").read_bytes()))
+
+    def test_zone_graph(self):
+        zone = nav.parse_zone((DATA / "Zone.nav").read_bytes())
+
+        self.assert_graph(zone.graph)
+        self.assertEqual(zone.zone_names, ["WizardCity", "Krokotopia"])
+
+    def test_truncated_input(self):
+        for parse, name in [(nav.parse, "Graph.nav"), (nav.parse_zone, "Zone.nav")]:
+            data = (DATA / name).read_bytes()
+            with self.subTest(name):
+                with self.assertRaises(katsuba.ParseError) as cm:
+                    parse(data[:-1])
+
+                self.assertIsInstance(cm.exception, katsuba.KatsubaError)
+                self.assertEqual(str(cm.exception), "unexpected end of input")
+
+
+if __name__ == "__main__":
+    unittest.main()
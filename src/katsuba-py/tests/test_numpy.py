@@ -0,0 +1,98 @@
+"""Checks the conversion of lists into numpy arrays.
+
+Only runs when the bindings are built with the `numpy` feature and
+numpy is installed. Run with `python -m unittest` after installing
+the bindings, e.g. with `maturin develop --features numpy`.
+"""
+
+import json
+import pathlib
+import struct
+import tempfile
+import unittest
+
+from katsuba import op
+from katsuba.utils import string_id
+
+try:
+    import numpy
+except ImportError:
+    numpy = None
+
+HOLDER = string_id("class Holder")
+
+PROPERTIES = [
+    ("m_ints", "int"),
+    ("m_floats", "float"),
+    ("m_vectors", "class Vector3D"),
+    ("m_rotations", "class Quaternion"),
+    ("m_names", "std::string"),
+]
+
+
+def load_holder():
+    properties = {
+        name: {"type": ty, "id": i, "flags": 24, "dynamic": True, "hash": i + 1}
+        for i, (name, ty) in enumerate(PROPERTIES)
+    }
+    types = {
+        "version": 2,
+        "classes": {
+            str(HOLDER): {
+                "name": "class Holder",
+                "bases": [],
+                "hash": HOLDER,
+                "properties": properties,
+            }
+        },
+    }
+    with tempfile.TemporaryDirectory() as tmp:
+        path = pathlib.Path(tmp) / "types.json"
+        path.write_text(json.dumps(types))
+        types = op.TypeList.open(str(path))
+
+    data = struct.pack("<I", HOLDER)
+    data += struct.pack("<I3i", 3, 1, -2, 3)
+    data += struct.pack("<I2f", 2, 0.5, -1.5)
+    data += struct.pack("<I6f", 2, 1, 2, 3, 4, 5, 6)
+    data += struct.pack("<I4f", 1, 0, 0, 0, 1)
+    data += struct.pack("<IH3s", 1, 3, b"hat")
+
+    opts = op.SerializerOptions(shallow=True)
+    return op.Serializer(opts, types).deserialize(data)
+
+
+@unittest.skipIf(numpy is None, "needs numpy")
+@unittest.skipUnless(hasattr(op.LazyList, "as_numpy"), "needs the numpy feature")
+class NumpyTest(unittest.TestCase):
+    def setUp(self):
+        self.holder = load_holder()
+
+    def test_scalars(self):
+        ints = self.holder["m_ints"].as_numpy()
+        self.assertEqual(ints.shape, (3,))
+        self.assertEqual(ints.dtype, numpy.int64)
+        self.assertEqual(ints.tolist(), [1, -2, 3])
+
+        floats = self.holder["m_floats"].as_numpy()
+        self.assertEqual(floats.shape, (2,))
+        self.assertEqual(floats.dtype, numpy.float32)
+        self.assertEqual(floats.tolist(), [0.5, -1.5])
+
+    def test_vectors(self):
+        vectors = self.holder["m_vectors"].as_numpy()
+        self.assertEqual(vectors.shape, (2, 3))
+        self.assertEqual(vectors.tolist(), [[1, 2, 3], [4, 5, 6]])
+
+    def test_quaternions(self):
+        rotations = self.holder["m_rotations"].as_numpy()
+        self.assertEqual(rotations.shape, (1, 4))
+        self.assertEqual(rotations.tolist(), [[0, 0, 0, 1]])
+
+    def test_unsupported_elements(self):
+        with self.assertRaisesRegex(TypeError, "index 0"):
+            self.holder["m_names"].as_numpy()
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,139 @@
+"""Checks pickling of lazy values and the `dumps`/`loads` functions.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import json
+import pickle
+import struct
+import unittest
+
+import katsuba
+from katsuba import op
+from test_lazy import load_item
+
+# The tags of the private encoding in `op/pickle.rs`.
+(
+    EMPTY, UNSIGNED, SIGNED, FLOAT, BOOL, STRING, WSTRING, ENUM, LIST, OBJECT,
+    COLOR, VEC3, QUAT, EULER, MAT3X3, POINT_INT, POINT_FLOAT, SIZE_INT,
+    RECT_INT, RECT_FLOAT, BLOB, UNSET, MAP, PAIR, FLOAT32, TIME, GID,
+) = range(27)
+
+
+def tagged(tag, fmt="", *values):
+    return bytes([tag]) + struct.pack("<" + fmt, *values)
+
+
+def sized(tag, data):
+    return struct.pack("<BI", tag, len(data)) + data
+
+
+def string(s):
+    return sized(STRING, s)
+
+
+def obj(type_hash, fields):
+    data = struct.pack("<BII", OBJECT, type_hash, len(fields))
+    for name, value in sorted(fields.items()):
+        data += struct.pack("<I", len(name)) + name.encode() + value
+    return data
+
+
+def every_variant():
+    """Encodes an object holding a value of every variant.
+
+    Fields are in the order the decoder keeps them, so encoding the
+    result again must reproduce the same bytes.
+    """
+    fields = {
+        "m_blob": sized(BLOB, b"\x01\x00") + tagged(SIGNED, "q", 1),
+        "m_bool": tagged(BOOL, "B", 1),
+        "m_color": tagged(COLOR, "4B", 1, 2, 3, 255),
+        "m_empty": tagged(EMPTY),
+        "m_enum": tagged(ENUM, "q", 3),
+        "m_euler": tagged(EULER, "3f", 0.5, 1, -1),
+        "m_float": tagged(FLOAT, "d", 0.1),
+        "m_float32": tagged(FLOAT32, "f", 1.5),
+        "m_gid": tagged(GID, "Q", 0x0000000100000002),
+        "m_list": struct.pack("<BI", LIST, 2) + string(b"hat") + tagged(UNSIGNED, "Q", 7),
+        "m_map": struct.pack("<BI", MAP, 1) + string(b"key") + tagged(SIGNED, "q", -5),
+        "m_matrix": tagged(MAT3X3, "9f", 1, 0, 0, 0, 1, 0, 0, 0, 1),
+        "m_object": obj(4, {"m_name": string(b"inner")}),
+        "m_pair": tagged(PAIR) + tagged(UNSIGNED, "Q", 1) + tagged(FLOAT32, "f", 2),
+        "m_pointFloat": tagged(POINT_FLOAT, "2f", 0.5, -0.5),
+        "m_pointInt": tagged(POINT_INT, "2i", 3, -4),
+        "m_quat": tagged(QUAT, "4f", 0, 0, 0, 1),
+        "m_rectFloat": tagged(RECT_FLOAT, "4f", 0, 0, 1.5, 2.5),
+        "m_rectInt": tagged(RECT_INT, "4i", -1, -2, 3, 4),
+        "m_signed": tagged(SIGNED, "q", -42),
+        "m_size": tagged(SIZE_INT, "2i", 640, 480),
+        "m_string": string(b"hello"),
+        "m_time": tagged(TIME, "q", 1700000000),
+        "m_unset": tagged(UNSET),
+        "m_unsigned": tagged(UNSIGNED, "Q", 2**64 - 1),
+        "m_vec3": tagged(VEC3, "3f", 1.25, 2.5, -3.75),
+        "m_wstring": struct.pack("<BI", WSTRING, 4) + "wide".encode("utf-16-le"),
+    }
+    return b"\x01" + obj(1, fields)
+
+
+class PickleTest(unittest.TestCase):
+    def assertRoundTrips(self, value):
+        data = op.dumps(value)
+        self.assertEqual(op.dumps(op.loads(data)), data)
+
+        restored = pickle.loads(pickle.dumps(value))
+        self.assertIs(type(restored), type(value))
+        self.assertEqual(op.dumps(restored), data)
+        self.assertEqual(restored.to_json(), value.to_json())
+
+        return restored
+
+    def test_every_variant(self):
+        data = every_variant()
+        value = op.loads(data)
+        self.assertEqual(op.dumps(value), data)
+
+        restored = self.assertRoundTrips(value)
+        self.assertEqual(restored.type_hash, 1)
+        self.assertEqual(restored["m_string"], b"hello")
+        self.assertEqual(restored["m_wstring"], "wide")
+        self.assertEqual(restored["m_unsigned"], 2**64 - 1)
+        self.assertEqual(restored["m_float32"], 1.5)
+        self.assertEqual(restored["m_time"].raw, 1700000000)
+        self.assertEqual(restored["m_color"].as_hex(), "#010203FF")
+        self.assertEqual(restored["m_vec3"], op.Vec3(1.25, 2.5, -3.75))
+        self.assertEqual(restored["m_object"]["m_name"], b"inner")
+        self.assertEqual(restored.query("m_map[0]!value"), -5)
+        self.assertEqual(restored.query("m_pair!second"), 2)
+        self.assertIsNone(restored["m_empty"])
+        self.assertNotIn("m_unset", json.loads(restored.to_json()))
+
+    def test_deserialized(self):
+        item = load_item()
+
+        self.assertRoundTrips(item)
+        tags = self.assertRoundTrips(item["m_tags"])
+        self.assertEqual(tags[0], b"hat")
+
+        # Unpickled children own their subtree.
+        upgrade = pickle.loads(pickle.dumps(item["m_upgrade"]))
+        del item
+        self.assertEqual(upgrade["m_displayName"], "Cooler Hat")
+
+    def test_rejects_other_values(self):
+        with self.assertRaises(TypeError):
+            op.dumps(b"hat")
+
+    def test_malformed(self):
+        data = every_variant()
+
+        for bad in [b"", data[:-1], data + b"\x00", b"\x02" + data[1:], b"\x01\xff"]:
+            with self.subTest(bad=bad[:8]):
+                with self.assertRaises(katsuba.KatsubaError):
+                    op.loads(bad)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,101 @@
+"""Checks that deserialization releases the GIL.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import json
+import os
+import pathlib
+import struct
+import tempfile
+import threading
+import time
+import unittest
+
+from katsuba import op
+from katsuba.utils import string_id
+
+HOLDER = string_id("class Holder")
+
+# Stays within the default element limit.
+COUNT = 1_000_000
+
+
+def load_holder(count=COUNT):
+    types = {
+        "version": 2,
+        "classes": {
+            str(HOLDER): {
+                "name": "class Holder",
+                "bases": [],
+                "hash": HOLDER,
+                "properties": {
+                    "m_values": {
+                        "type": "int",
+                        "id": 0,
+                        "flags": 24,
+                        "dynamic": True,
+                        "hash": 1,
+                    }
+                },
+            }
+        },
+    }
+    with tempfile.TemporaryDirectory() as tmp:
+        path = pathlib.Path(tmp) / "types.json"
+        path.write_text(json.dumps(types))
+        types = op.TypeList.open(str(path))
+
+    data = struct.pack("<II", HOLDER, count) + bytes(4 * count)
+    return types, data
+
+
+class ThreadTest(unittest.TestCase):
+    def setUp(self):
+        self.types, self.data = load_holder()
+        self.opts = op.SerializerOptions(shallow=True)
+        self.lengths = []
+
+    def deserialize(self):
+        value = op.deserialize(self.data, self.types, self.opts)
+        self.lengths.append(len(value["m_values"]))
+
+    def timed(self, threads):
+        workers = [threading.Thread(target=self.deserialize) for _ in range(threads)]
+        start = time.perf_counter()
+        for worker in workers:
+            worker.start()
+        for worker in workers:
+            worker.join()
+
+        elapsed = time.perf_counter() - start
+        self.assertEqual(self.lengths, [COUNT] * threads)
+        self.lengths.clear()
+        return elapsed
+
+    @unittest.skipIf((os.cpu_count() or 1) < 2, "needs at least two cores")
+    def test_concurrent_deserialization(self):
+        single = self.timed(1)
+        both = self.timed(2)
+        self.assertLess(both, single * 1.5)
+
+    def test_python_threads_progress(self):
+        worker = threading.Thread(target=self.deserialize)
+        start = last = time.perf_counter()
+        gap = 0.0
+
+        # With the GIL held, this loop stalls until the worker is done.
+        worker.start()
+        while worker.is_alive():
+            time.sleep(0.001)
+            now = time.perf_counter()
+            gap, last = max(gap, now - last), now
+        worker.join()
+
+        self.assertEqual(self.lengths, [COUNT])
+        self.assertLess(gap, (last - start) / 2)
+
+
+if __name__ == "__main__":
+    unittest.main()
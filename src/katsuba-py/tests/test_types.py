@@ -0,0 +1,68 @@
+"""Checks loading type lists through the binary cache.
+
+Run with `python -m unittest` after installing the bindings, e.g.
+with `maturin develop`.
+"""
+
+import os
+import pathlib
+import shutil
+import tempfile
+import unittest
+
+import katsuba
+from katsuba import op
+
+DATA = pathlib.Path(__file__).parents[2] / "katsuba" / "tests" / "data" / "op"
+
+
+class CacheTest(unittest.TestCase):
+    def setUp(self):
+        tmp = tempfile.TemporaryDirectory()
+        self.addCleanup(tmp.cleanup)
+
+        self.dir = pathlib.Path(tmp.name)
+        self.cache = self.dir / "types.cache"
+        self.types = self.dir / "types.json"
+        shutil.copy(DATA / "types.json", self.types)
+
+    def open_cache(self, *paths):
+        return op.TypeList.open_cache(str(self.cache), [str(p) for p in paths])
+
+    def test_builds_and_reuses_cache(self):
+        expected = op.TypeList.open(str(self.types))
+
+        built = self.open_cache(self.types)
+        self.assertTrue(self.cache.exists())
+        self.assertEqual(sorted(built.classes()), sorted(expected.classes()))
+
+        # The JSON file is not read again while the cache is fresh.
+        os.utime(self.types, (0, 0))
+        cached = op.TypeList.open_cache(str(self.cache))
+        for _, name in expected.classes():
+            self.assertEqual(cached.properties(name), expected.properties(name))
+
+        ser = op.Serializer(op.SerializerOptions(flags=op.STATEFUL_FLAGS, shallow=False), cached)
+        item = ser.deserialize((DATA / "item.bin").read_bytes()[4:])
+        self.assertEqual(item["m_tags"][0], b"hat")
+
+    def test_rebuilds_stale_cache(self):
+        self.cache.write_bytes(b"stale")
+        os.utime(self.cache, (0, 0))
+
+        types = self.open_cache(self.types, DATA / "behaviors.json")
+        self.assertIsNotNone(types.hash_for("Item"))
+        self.assertIsNotNone(types.hash_for("BehaviorTemplate"))
+        self.assertNotEqual(self.cache.read_bytes(), b"stale")
+
+    def test_invalid_cache(self):
+        with self.assertRaisesRegex(katsuba.KatsubaError, "does not exist"):
+            self.open_cache()
+
+        self.cache.write_bytes(b"stale")
+        with self.assertRaisesRegex(katsuba.KatsubaError, "invalid type list cache"):
+            self.open_cache(self.types)
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use katsuba_utils::hash;
+use smartstring::alias::String;
+
+use super::{TypeDef, TypeList};
+
+/// The algorithm which produces the type hash of a class.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TypeHashKind {
+    /// The KingsIsle string ID algorithm, used by default.
+    StringId,
+    /// The DJB2 algorithm, used by some serializer configurations.
+    Djb2,
+}
+
+impl TypeHashKind {
+    /// Computes the type hash of a class called `name`.
+    pub fn hash(self, name: &str) -> u32 {
+        match self {
+            Self::StringId => hash::string_id(name.as_bytes()),
+            Self::Djb2 => hash::djb2(name.as_bytes()),
+        }
+    }
+}
+
+/// Properties of a single class which share the same hash.
+///
+/// Such properties cannot be told apart in serialized data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PropertyCollision {
+    /// The name of the class declaring the properties.
+    pub class: String,
+    /// The shared property hash.
+    pub hash: u32,
+    /// The names of all properties with the hash.
+    pub properties: Vec<String>,
+}
+
+/// Classes whose names produce the same type hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassCollision {
+    /// The algorithm under which the hashes collide.
+    pub kind: TypeHashKind,
+    /// The shared type hash.
+    pub hash: u32,
+    /// The names of all classes with the hash.
+    pub classes: Vec<String>,
+}
+
+/// The result of [`TypeList::audit_collisions`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CollisionReport {
+    /// Hash collisions between properties of the same class.
+    pub properties: Vec<PropertyCollision>,
+    /// Type hash collisions between classes.
+    pub classes: Vec<ClassCollision>,
+}
+
+impl CollisionReport {
+    /// Whether no collisions were found.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty() && self.classes.is_empty()
+    }
+}
+
+impl TypeDef {
+    /// Finds properties of this class which share the same hash.
+    pub fn property_collisions(&self) -> Vec<PropertyCollision> {
+        let mut by_hash: HashMap<u32, Vec<String>> = HashMap::new();
+        for property in &self.properties {
+            by_hash
+                .entry(property.hash)
+                .or_default()
+                .push(property.name.clone());
+        }
+
+        let mut collisions: Vec<_> = by_hash
+            .into_iter()
+            .filter(|(_, properties)| properties.len() > 1)
+            .map(|(hash, properties)| PropertyCollision {
+                class: self.name.clone(),
+                hash,
+                properties,
+            })
+            .collect();
+        collisions.sort_by_key(|c| c.hash);
+
+        collisions
+    }
+}
+
+impl TypeList {
+    /// Finds a class with properties which share the same hash.
+    ///
+    /// Serializers check this for every list they are created with,
+    /// so the result is computed once and cached.
+    pub fn property_collision(&self) -> Option<&PropertyCollision> {
+        self.1
+             .0
+            .get_or_init(|| {
+                self.0
+                    .values()
+                    .find_map(|def| def.property_collisions().into_iter().next())
+            })
+            .as_ref()
+    }
+
+    /// Checks the list for hashes which are shared by more than one
+    /// property of a class or by more than one class.
+    ///
+    /// Classes are checked under all [`TypeHashKind`]s. Note that
+    /// classes are keyed by their string ID when the list is loaded,
+    /// so colliding entries in v1 lists replace each other before
+    /// they can be found.
+    pub fn audit_collisions(&self) -> CollisionReport {
+        let mut report = CollisionReport::default();
+
+        for def in self.0.values() {
+            report.properties.extend(def.property_collisions());
+        }
+        report
+            .properties
+            .sort_by(|a, b| a.class.cmp(&b.class).then(a.hash.cmp(&b.hash)));
+
+        for kind in [TypeHashKind::StringId, TypeHashKind::Djb2] {
+            let mut by_hash: HashMap<u32, Vec<String>> = HashMap::new();
+            for def in self.0.values() {
+                by_hash
+                    .entry(kind.hash(&def.name))
+                    .or_default()
+                    .push(def.name.clone());
+            }
+
+            let mut collisions: Vec<_> = by_hash
+                .into_iter()
+                .filter(|(_, classes)| classes.len() > 1)
+                .map(|(hash, mut classes)| {
+                    classes.sort();
+                    ClassCollision {
+                        kind,
+                        hash,
+                        classes,
+                    }
+                })
+                .collect();
+            collisions.sort_by_key(|c| c.hash);
+
+            report.classes.extend(collisions);
+        }
+
+        report
+    }
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use smartstring::alias::String;
+
+use super::{Property, PropertyFlags, StringOrInt, TypeDef, TypeList};
+
+const CACHE_MAGIC: &[u8; 4] = b"KTYC";
+const CACHE_VERSION: u8 = 1;
+
+const OPTION_STRING: u8 = 0;
+const OPTION_INT: u8 = 1;
+
+impl TypeList {
+    /// Reads a type list from a binary cache previously produced by
+    /// [`TypeList::write_cache`].
+    ///
+    /// This is considerably faster than parsing the JSON dump the
+    /// cache was built from.
+    pub fn read_cache<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC || read_u8(&mut reader)? != CACHE_VERSION {
+            return Err(invalid_data("not a type list cache"));
+        }
+
+        let mut types = HashMap::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let hash = read_u32(&mut reader)?;
+            let name = read_string(&mut reader)?;
+
+            let mut bases = Vec::new();
+            for _ in 0..read_u32(&mut reader)? {
+                bases.push(read_string(&mut reader)?);
+            }
+
+            let mut properties = Vec::new();
+            for _ in 0..read_u32(&mut reader)? {
+                properties.push(read_property(&mut reader)?);
+            }
+
+            types.insert(
+                hash,
+                TypeDef {
+                    name,
+                    bases,
+                    properties,
+                },
+            );
+        }
+
+        Ok(Self::from(types))
+    }
+
+    /// Writes the type list to a binary cache which can be loaded
+    /// with [`TypeList::read_cache`].
+    pub fn write_cache<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(CACHE_MAGIC)?;
+        writer.write_all(&[CACHE_VERSION])?;
+        write_u32(&mut writer, self.0.len())?;
+
+        for (hash, def) in &self.0 {
+            writer.write_all(&hash.to_le_bytes())?;
+            write_string(&mut writer, &def.name)?;
+
+            write_u32(&mut writer, def.bases.len())?;
+            for base in &def.bases {
+                write_string(&mut writer, base)?;
+            }
+
+            // Properties are already sorted, so their order is kept.
+            write_u32(&mut writer, def.properties.len())?;
+            for property in &def.properties {
+                write_property(&mut writer, property)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_property<R: Read>(reader: &mut R) -> io::Result<Property> {
+    let name = read_string(reader)?;
+    let r#type = read_string(reader)?;
+    let id = read_u32(reader)?;
+    let flags = PropertyFlags::from_bits_retain(read_u32(reader)?);
+    let dynamic = read_u8(reader)? != 0;
+    let hash = read_u32(reader)?;
+
+    let mut enum_options = HashMap::new();
+    for _ in 0..read_u32(reader)? {
+        let key = read_string(reader)?;
+        let value = match read_u8(reader)? {
+            OPTION_STRING => StringOrInt::String(read_string(reader)?),
+            OPTION_INT => {
+                let mut buf = [0; 8];
+                reader.read_exact(&mut buf)?;
+                StringOrInt::Int(i64::from_le_bytes(buf))
+            }
+            _ => return Err(invalid_data("unknown enum option kind")),
+        };
+
+        enum_options.insert(key, value);
+    }
+
+    Ok(Property {
+        name,
+        r#type,
+        id,
+        flags,
+        dynamic,
+        hash,
+        enum_options,
+    })
+}
+
+fn write_property<W: Write>(writer: &mut W, property: &Property) -> io::Result<()> {
+    write_string(writer, &property.name)?;
+    write_string(writer, &property.r#type)?;
+    writer.write_all(&property.id.to_le_bytes())?;
+    writer.write_all(&property.flags.bits().to_le_bytes())?;
+    writer.write_all(&[property.dynamic as u8])?;
+    writer.write_all(&property.hash.to_le_bytes())?;
+
+    write_u32(writer, property.enum_options.len())?;
+    for (key, value) in &property.enum_options {
+        write_string(writer, key)?;
+        match value {
+            StringOrInt::String(s) => {
+                writer.write_all(&[OPTION_STRING])?;
+                write_string(writer, s)?;
+            }
+            StringOrInt::Int(v) => {
+                writer.write_all(&[OPTION_INT])?;
+                writer.write_all(&v.to_le_bytes())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    // Lengths come from the file, so memory only grows with the
+    // data that is actually there.
+    let len = read_u32(reader)?;
+    let mut value = Vec::new();
+    reader.by_ref().take(len.into()).read_to_end(&mut value)?;
+    if value.len() != len as usize {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    std::str::from_utf8(&value)
+        .map(Into::into)
+        .map_err(|_| invalid_data("invalid UTF-8"))
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u32).to_le_bytes())
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    write_u32(writer, value.len())?;
+    writer.write_all(value.as_bytes())
+}
@@ -0,0 +1,60 @@
+use super::{Property, StringOrInt, TypeDef, TypeList};
+
+/// A variant of an enum property, as listed by [`TypeList::enums`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EnumVariant<'a> {
+    /// The class declaring the property.
+    pub class: &'a TypeDef,
+    /// The enum property itself.
+    ///
+    /// Its [flags](Property::flags) tell whether the variants are
+    /// bit flags or plain enum values.
+    pub property: &'a Property,
+    /// The name of the variant.
+    pub name: &'a str,
+    /// The value of the variant as listed in the type list.
+    pub raw: &'a StringOrInt,
+    /// The integer value of the variant, if it has one.
+    ///
+    /// Values given as strings are parsed, see
+    /// [`StringOrInt::to_int`].
+    pub value: Option<i64>,
+}
+
+impl TypeList {
+    /// Iterates over the variants of every enum property in the
+    /// type list.
+    ///
+    /// Classes are visited by name, their properties in declaration
+    /// order and the variants of every property by value, so the
+    /// order is stable between runs.
+    pub fn enums(&self) -> impl Iterator<Item = EnumVariant<'_>> {
+        let mut classes: Vec<_> = self.0.values().collect();
+        classes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        classes.into_iter().flat_map(|class| {
+            class
+                .property_order()
+                .filter(|p| p.is_enum())
+                .flat_map(move |property| variants(class, property))
+        })
+    }
+}
+
+fn variants<'a>(class: &'a TypeDef, property: &'a Property) -> Vec<EnumVariant<'a>> {
+    let mut variants: Vec<_> = property
+        .enum_options
+        .iter()
+        .map(|(name, raw)| EnumVariant {
+            class,
+            property,
+            name,
+            raw,
+            value: raw.to_int(),
+        })
+        .collect();
+
+    // Unresolved values sort before all others.
+    variants.sort_by(|a, b| (a.value, a.name).cmp(&(b.value, b.name)));
+    variants
+}
@@ -16,12 +16,23 @@
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
 #![forbid(unsafe_code)]
 
-use std::{collections::HashMap, io};
+use std::{collections::HashMap, io, sync::OnceLock};
 
-use katsuba_utils::thiserror::{self, Error};
+use katsuba_utils::{
+    hash,
+    thiserror::{self, Error},
+};
 use serde::{Deserialize, Deserializer};
 use smartstring::alias::String;
 
+mod audit;
+pub use audit::*;
+
+mod cache;
+
+mod enums;
+pub use enums::*;
+
 mod property;
 pub use property::*;
 
@@ -30,6 +41,9 @@ mod serde_impl;
 mod string_or_int;
 pub use string_or_int::*;
 
+mod template;
+pub use template::*;
+
 /// Errors that may occur when working with [`TypeList`]s.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -54,8 +68,29 @@ impl From<serde_json::Error> for Error {
 }
 
 /// Representation of the list of types dumped from the game client.
+///
+/// Some checks over the whole list are cached, see
+/// [`TypeList::property_collision`]. They are not updated when the
+/// types are changed directly rather than through [`TypeList::merge`].
 #[derive(Clone, Debug, PartialEq)]
-pub struct TypeList(pub HashMap<u32, TypeDef>);
+pub struct TypeList(pub HashMap<u32, TypeDef>, PropertyCheck);
+
+// The cached result of `TypeList::property_collision`.
+#[derive(Clone, Debug, Default)]
+struct PropertyCheck(OnceLock<Option<PropertyCollision>>);
+
+// Caches don't make a difference for comparisons.
+impl PartialEq for PropertyCheck {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl From<HashMap<u32, TypeDef>> for TypeList {
+    fn from(types: HashMap<u32, TypeDef>) -> Self {
+        Self(types, PropertyCheck::default())
+    }
+}
 
 impl TypeList {
     /// Deserializes a type list in JSON format from a given reader.
@@ -69,8 +104,71 @@ impl TypeList {
         serde_json::from_str(data).map_err(Into::into)
     }
 
+    /// Finds a type by its name, along with its hash.
+    ///
+    /// The `class ` prefix may be omitted from class names.
+    pub fn find(&self, name: &str) -> Option<(u32, &TypeDef)> {
+        let lookup = |name: &str| {
+            let hash = hash::string_id(name.as_bytes());
+            self.0.get(&hash).map(|def| (hash, def))
+        };
+
+        lookup(name).or_else(|| lookup(&format!("class {name}")))
+    }
+
+    /// Gets the hashes of all types which directly or indirectly
+    /// inherit from the type called `name`.
+    ///
+    /// This relies on base class information, which is only present
+    /// in type lists dumped by recent wiztype versions.
+    pub fn subclasses_of(&self, name: &str) -> Vec<u32> {
+        let mut found = Vec::new();
+        let mut pending = vec![strip_class(name)];
+
+        while let Some(base) = pending.pop() {
+            for (&hash, def) in &self.0 {
+                if def.bases.iter().any(|b| strip_class(b) == base) && !found.contains(&hash) {
+                    found.push(hash);
+                    pending.push(strip_class(&def.name));
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Whether the type called `name` is the type called `base` or
+    /// directly or indirectly inherits from it.
+    ///
+    /// Like [`TypeList::subclasses_of`], this needs base class info
+    /// and can only follow bases which are part of the list.
+    pub fn inherits_from(&self, name: &str, base: &str) -> bool {
+        let base = strip_class(base);
+        let mut pending = vec![strip_class(name)];
+        let mut seen = Vec::new();
+
+        while let Some(name) = pending.pop() {
+            if name == base {
+                return true;
+            }
+
+            // Guard against cycles in malformed type lists.
+            if seen.contains(&name) {
+                continue;
+            }
+            seen.push(name);
+
+            if let Some((_, def)) = self.find(name) {
+                pending.extend(def.bases.iter().map(|b| strip_class(b)));
+            }
+        }
+
+        false
+    }
+
     /// Merges all entries from `other` into `self`.
     pub fn merge(&mut self, mut other: TypeList) {
+        self.1 = PropertyCheck::default();
         self.0.reserve(other.0.len());
 
         for (k, v) in other.0.drain() {
@@ -86,7 +184,7 @@ impl<'de> Deserialize<'de> for TypeList {
     {
         deserializer
             .deserialize_map(serde_impl::TypeListVisitor { version: 1 })
-            .map(Self)
+            .map(Self::from)
     }
 }
 
@@ -96,11 +194,29 @@ pub struct TypeDef {
     /// The type name.
     #[serde(default)]
     pub name: String,
+    /// The names of the direct base classes, if known.
+    #[serde(default)]
+    pub bases: Vec<String>,
     /// The properties of the class.
     #[serde(deserialize_with = "deserialize_property_list")]
     pub properties: Vec<Property>,
 }
 
+impl TypeDef {
+    /// Gets the properties of the class in the order they were
+    /// declared in, which is the order the game serializes them in.
+    ///
+    /// Type lists keep [`TypeDef::properties`] sorted by property ID
+    /// when they are loaded, so this is the order of that list.
+    pub fn property_order(&self) -> std::slice::Iter<'_, Property> {
+        self.properties.iter()
+    }
+}
+
+fn strip_class(name: &str) -> &str {
+    name.strip_prefix("class ").unwrap_or(name)
+}
+
 fn deserialize_property_list<'de, D>(deserializer: D) -> Result<Vec<Property>, D::Error>
 where
     D: Deserializer<'de>,
@@ -113,8 +229,9 @@ where
         })
         .collect();
 
-    // Sort properties by ID for correct order.
-    properties.sort_by(|a, b| a.id.cmp(&b.id));
+    // Sort properties by ID for correct order. The serializers
+    // rely on this through `TypeDef::property_order`.
+    properties.sort_by_key(|p| p.id);
 
     Ok(properties)
 }
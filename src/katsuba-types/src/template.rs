@@ -0,0 +1,55 @@
+/// A C++ template type name split into its components.
+///
+/// Type names like `class std::map<std::string, class Foo>` are
+/// found in type lists as-is, so consumers which need to know the
+/// argument types have to parse them out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateType<'a> {
+    /// The name of the template, without a `class` or `struct` prefix.
+    pub name: &'a str,
+    /// The template arguments, as they are spelled in the type name.
+    pub args: Vec<&'a str>,
+}
+
+impl<'a> TemplateType<'a> {
+    /// Parses a template instantiation out of the type name `ty`.
+    ///
+    /// Commas only separate arguments on the outermost level, so
+    /// nested templates are kept intact. Returns [`None`] when `ty`
+    /// is not a template or its angle brackets are unbalanced.
+    pub fn parse(ty: &'a str) -> Option<Self> {
+        let ty = ty.trim();
+        let ty = ty
+            .strip_prefix("class ")
+            .or_else(|| ty.strip_prefix("struct "))
+            .unwrap_or(ty);
+
+        let (name, rest) = ty.split_once('<')?;
+        let inner = rest.strip_suffix('>')?;
+
+        let mut args = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (idx, c) in inner.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth = depth.checked_sub(1)?,
+                ',' if depth == 0 => {
+                    args.push(inner[start..idx].trim());
+                    start = idx + 1;
+                }
+                _ => {}
+            }
+        }
+
+        if depth != 0 {
+            return None;
+        }
+        args.push(inner[start..].trim());
+
+        Some(Self {
+            name: name.trim(),
+            args,
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use katsuba_types::*;
+
+const TYPES: &str = r#"{
+    "version": 2,
+    "classes": {
+        "2": {
+            "name": "class Zone",
+            "bases": [],
+            "hash": 2,
+            "properties": {
+                "m_flags": {
+                    "type": "unsigned int", "id": 1, "flags": 1048583, "dynamic": false, "hash": 20,
+                    "enum_options": { "Hidden": 2, "Locked": "1" }
+                },
+                "m_name": { "type": "std::string", "id": 0, "flags": 7, "dynamic": false, "hash": 21 }
+            }
+        },
+        "1": {
+            "name": "class Item",
+            "bases": [],
+            "hash": 1,
+            "properties": {
+                "m_rarity": {
+                    "type": "enum Rarity", "id": 0, "flags": 2097159, "dynamic": false, "hash": 10,
+                    "enum_options": { "Rare": 2, "Common": 0, "__DEFAULT": "Common" }
+                }
+            }
+        }
+    }
+}"#;
+
+#[test]
+fn list_enum_variants() -> Result<(), Error> {
+    let list = TypeList::from_str(TYPES)?;
+
+    let rows: Vec<_> = list
+        .enums()
+        .map(|v| (&*v.class.name, &*v.property.name, v.name, v.value))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("class Item", "m_rarity", "__DEFAULT", None),
+            ("class Item", "m_rarity", "Common", Some(0)),
+            ("class Item", "m_rarity", "Rare", Some(2)),
+            ("class Zone", "m_flags", "Locked", Some(1)),
+            ("class Zone", "m_flags", "Hidden", Some(2)),
+        ]
+    );
+
+    let variant = list.enums().last().unwrap();
+    assert!(variant.property.flags.contains(PropertyFlags::BITS));
+    assert_eq!(variant.raw, &StringOrInt::Int(2));
+
+    Ok(())
+}
@@ -17,6 +17,35 @@ fn equal_parse_between_versions() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn properties_in_declared_order() -> Result<(), Error> {
+    let list = TypeList::from_str(
+        r#"{
+            "version": 2,
+            "classes": {
+                "1": {
+                    "name": "class Ordered",
+                    "bases": [],
+                    "hash": 1,
+                    "properties": {
+                        "m_alpha": { "type": "int", "id": 2, "flags": 24, "dynamic": false, "hash": 10 },
+                        "m_zeta": { "type": "int", "id": 0, "flags": 24, "dynamic": false, "hash": 11 },
+                        "m_mid": { "type": "int", "id": 1, "flags": 24, "dynamic": false, "hash": 12 }
+                    }
+                }
+            }
+        }"#,
+    )?;
+
+    let order: Vec<_> = list.0[&1]
+        .property_order()
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(order, ["m_zeta", "m_mid", "m_alpha"]);
+
+    Ok(())
+}
+
 #[test]
 fn query_types() -> Result<(), Error> {
     let list = read_type_list("tests/data/types_v2.json")?;
@@ -45,3 +74,139 @@ fn query_properties() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn find_types_by_name() -> Result<(), Error> {
+    let list = read_type_list("tests/data/types_v2.json")?;
+
+    let (hash, cls) = list.find("EquipmentSetList").unwrap();
+    assert_eq!(hash, 135649998);
+    assert_eq!(cls.name, "class EquipmentSetList");
+    assert_eq!(list.find("class EquipmentSetList").unwrap().0, hash);
+    assert!(list.find("NoSuchClass").is_none());
+
+    assert_eq!(list.subclasses_of("PropertyClass"), vec![135649998]);
+    assert!(list.subclasses_of("EquipmentSetList").is_empty());
+
+    assert!(list.inherits_from("EquipmentSetList", "class PropertyClass"));
+    assert!(list.inherits_from("class EquipmentSetList", "EquipmentSetList"));
+    assert!(!list.inherits_from("PropertyClass", "EquipmentSetList"));
+
+    Ok(())
+}
+
+#[test]
+fn parse_template_types() {
+    let map =
+        TemplateType::parse("class std::map<std::string, class SharedPointer<class Foo>>").unwrap();
+    assert_eq!(map.name, "std::map");
+    assert_eq!(map.args, ["std::string", "class SharedPointer<class Foo>"]);
+
+    let nested = TemplateType::parse(
+        "std::pair<class std::map<int,class Bar<a, b>>, class std::pair<bool,float>>",
+    )
+    .unwrap();
+    assert_eq!(nested.name, "std::pair");
+    assert_eq!(
+        nested.args,
+        [
+            "class std::map<int,class Bar<a, b>>",
+            "class std::pair<bool,float>"
+        ]
+    );
+
+    assert_eq!(TemplateType::parse("class Foo"), None);
+    assert_eq!(TemplateType::parse("class Foo<int>>"), None);
+    assert_eq!(TemplateType::parse("std::map<int, Bar<float>"), None);
+}
+
+#[test]
+fn audit_collisions() -> Result<(), Error> {
+    let list = read_type_list("tests/data/types_v2.json")?;
+    assert!(list.audit_collisions().is_empty());
+
+    // "Ez" and "FY" share their DJB2 hash.
+    let list = TypeList::from_str(
+        r#"{
+            "Ez": {
+                "properties": {
+                    "m_a": { "type": "int", "id": 0, "flags": 0, "dynamic": false, "hash": 1 },
+                    "m_b": { "type": "int", "id": 1, "flags": 0, "dynamic": false, "hash": 1 },
+                    "m_c": { "type": "int", "id": 2, "flags": 0, "dynamic": false, "hash": 2 }
+                }
+            },
+            "FY": { "properties": {} }
+        }"#,
+    )?;
+    let report = list.audit_collisions();
+
+    assert_eq!(report.properties.len(), 1);
+    let collision = &report.properties[0];
+    assert_eq!(collision.class, "Ez");
+    assert_eq!(collision.hash, 1);
+    assert_eq!(collision.properties, ["m_a", "m_b"]);
+
+    assert_eq!(
+        report.classes,
+        [ClassCollision {
+            kind: TypeHashKind::Djb2,
+            hash: TypeHashKind::Djb2.hash("Ez"),
+            classes: vec!["Ez".into(), "FY".into()],
+        }]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cached_property_collision() -> Result<(), Error> {
+    let mut list = read_type_list("tests/data/types_v2.json")?;
+    assert_eq!(list.property_collision(), None);
+
+    // Merging discards the cached result.
+    list.merge(TypeList::from_str(
+        r#"{
+            "Ez": {
+                "properties": {
+                    "m_a": { "type": "int", "id": 0, "flags": 0, "dynamic": false, "hash": 1 },
+                    "m_b": { "type": "int", "id": 1, "flags": 0, "dynamic": false, "hash": 1 }
+                }
+            }
+        }"#,
+    )?);
+    let collision = list.property_collision().unwrap();
+    assert_eq!(collision.class, "Ez");
+    assert_eq!(collision.properties, ["m_a", "m_b"]);
+
+    Ok(())
+}
+
+#[test]
+fn binary_cache_round_trip() -> Result<(), Error> {
+    let mut list = read_type_list("tests/data/types_v2.json")?;
+    list.merge(TypeList::from_str(
+        r#"{
+            "Ez": {
+                "bases": ["class PropertyClass"],
+                "properties": {
+                    "m_kind": {
+                        "type": "enum Kind", "id": 0, "flags": 2097152, "dynamic": false, "hash": 1,
+                        "enum_options": { "A": 0, "B": "1" }
+                    }
+                }
+            }
+        }"#,
+    )?);
+
+    let mut data = Vec::new();
+    list.write_cache(&mut data)?;
+    assert_eq!(TypeList::read_cache(data.as_slice())?, list);
+
+    // Truncated and foreign data is rejected.
+    let truncated = TypeList::read_cache(&data[..data.len() - 1]).unwrap_err();
+    assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+    let foreign = TypeList::read_cache(&b"KHIX\x01\0\0\0\0"[..]).unwrap_err();
+    assert_eq!(foreign.kind(), io::ErrorKind::InvalidData);
+
+    Ok(())
+}
@@ -1,22 +1,183 @@
 //! Utilities for working with memory alignment.
 
-/// Aligns `value` down to the next multiple of `align`.
+use std::io::{self, Seek, SeekFrom, Write};
+
+use thiserror::Error;
+
+/// A power of two to align values to.
 ///
-/// # Panics
+/// Constructing an [`Alignment`] checks the value once, so the
+/// alignment functions in this module need no checks of their own.
+/// [`Alignment::new`] in a `const` item fails to compile when given
+/// a value which is not a power of two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Alignment(u64);
+
+/// An error for alignments which are not a power of two.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("alignment {0} is not a power of two")]
+pub struct BadAlignment(pub u64);
+
+impl Alignment {
+    /// Creates an alignment of `align` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `align` is not a power of two. See
+    /// [`Alignment::checked`] for alignments from untrusted input.
+    pub const fn new(align: u64) -> Self {
+        match Self::checked(align) {
+            Ok(align) => align,
+            Err(_) => panic!("alignment must be a power of two"),
+        }
+    }
+
+    /// Creates an alignment of `align` bytes, if it is a power of two.
+    pub const fn checked(align: u64) -> Result<Self, BadAlignment> {
+        match align.is_power_of_two() {
+            true => Ok(Self(align)),
+            false => Err(BadAlignment(align)),
+        }
+    }
+
+    /// Gets the alignment as a number.
+    #[inline(always)]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    #[inline(always)]
+    const fn mask(self) -> u64 {
+        self.0 - 1
+    }
+}
+
+/// Aligns `value` down to the previous multiple of `align`.
 ///
-/// Panics in debug mode when `align` is not a power of two.
+/// Values which are already aligned are returned unchanged.
 #[inline(always)]
-pub const fn align_down(value: usize, align: usize) -> usize {
-    debug_assert!(align.is_power_of_two());
-    value & !(align - 1)
+pub const fn align_down(value: u64, align: Alignment) -> u64 {
+    value & !align.mask()
 }
 
 /// Aligns `value` up to the next multiple of `align`.
 ///
-/// # Panics
+/// Values which are already aligned are returned unchanged. Returns
+/// [`None`] when the aligned value does not fit into a [`u64`].
+#[inline(always)]
+pub const fn align_up(value: u64, align: Alignment) -> Option<u64> {
+    match value.checked_add(align.mask()) {
+        Some(value) => Some(align_down(value, align)),
+        None => None,
+    }
+}
+
+/// Gets the number of bytes to add to `value` to make it a multiple
+/// of `align`.
 ///
-/// Panics in debug mode when `align` is not a power of two.
+/// This is always less than `align` and never overflows, even when
+/// the aligned value itself would not fit into a [`u64`].
 #[inline(always)]
-pub const fn align_up(value: usize, align: usize) -> usize {
-    align_down(value + align - 1, align)
+pub const fn padding_for(value: u64, align: Alignment) -> u64 {
+    value.wrapping_neg() & align.mask()
+}
+
+fn invalid_input(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+// Computes the next absolute position aligned relative to `base`.
+fn next_aligned_position<S: Seek>(stream: &mut S, align: u64, base: u64) -> io::Result<(u64, u64)> {
+    let pos = stream.stream_position()?;
+    let relative = pos
+        .checked_sub(base)
+        .ok_or_else(|| invalid_input("stream position is before the alignment base"))?;
+
+    let align = Alignment::checked(align).map_err(invalid_input)?;
+    let target = align_up(relative, align)
+        .and_then(|target| target.checked_add(base))
+        .ok_or_else(|| invalid_input("aligned offset overflows"))?;
+
+    Ok((pos, target))
+}
+
+/// Seeks `stream` forward to the next multiple of `align` bytes,
+/// counted from the absolute offset `base`.
+///
+/// Returns the new stream position. A stream which is already
+/// aligned is not moved.
+pub fn seek_to_alignment<S: Seek>(stream: &mut S, align: u64, base: u64) -> io::Result<u64> {
+    let (pos, target) = next_aligned_position(stream, align, base)?;
+    if pos != target {
+        stream.seek(SeekFrom::Start(target))?;
+    }
+
+    Ok(target)
+}
+
+/// Writes zero bytes to `stream` until its position is a multiple
+/// of `align` bytes, counted from the absolute offset `base`.
+///
+/// Returns the new stream position.
+pub fn write_padding<W: Write + Seek>(stream: &mut W, align: u64, base: u64) -> io::Result<u64> {
+    const ZEROES: [u8; 64] = [0; 64];
+
+    let (mut pos, target) = next_aligned_position(stream, align, base)?;
+    while pos < target {
+        let chunk = (target - pos).min(ZEROES.len() as u64) as usize;
+        stream.write_all(&ZEROES[..chunk])?;
+        pos += chunk as u64;
+    }
+
+    Ok(target)
+}
+
+#[cfg(feature = "binrw")]
+mod padded {
+    use binrw::{
+        io::{Read, Seek, Write},
+        BinRead, BinResult, BinWrite, Endian,
+    };
+
+    /// A `T` followed by padding up to the next multiple of `N`
+    /// bytes in the stream.
+    ///
+    /// This is the equivalent of binrw's `align_after = N` for
+    /// types which are used in more than one place. Reading skips
+    /// over the padding, writing emits zero bytes for it.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Padded<T, const N: usize>(pub T);
+
+    impl<T: BinRead, const N: usize> BinRead for Padded<T, N> {
+        type Args<'a> = T::Args<'a>;
+
+        fn read_options<R: Read + Seek>(
+            reader: &mut R,
+            endian: Endian,
+            args: Self::Args<'_>,
+        ) -> BinResult<Self> {
+            let value = T::read_options(reader, endian, args)?;
+            super::seek_to_alignment(reader, N as u64, 0)?;
+
+            Ok(Self(value))
+        }
+    }
+
+    impl<T: BinWrite, const N: usize> BinWrite for Padded<T, N> {
+        type Args<'a> = T::Args<'a>;
+
+        fn write_options<W: Write + Seek>(
+            &self,
+            writer: &mut W,
+            endian: Endian,
+            args: Self::Args<'_>,
+        ) -> BinResult<()> {
+            self.0.write_options(writer, endian, args)?;
+            super::write_padding(writer, N as u64, 0)?;
+
+            Ok(())
+        }
+    }
 }
+#[cfg(feature = "binrw")]
+pub use padded::Padded;
@@ -2,18 +2,24 @@
 
 use std::{collections::HashMap, hash::Hash};
 
-use binrw::{io::SeekFrom, BinRead, BinResult, BinWrite, VecArgs};
+use binrw::{
+    io::{self, Read, SeekFrom},
+    BinRead, BinResult, BinWrite,
+};
+
+// The maximum number of elements to preallocate for untrusted counts.
+const PREALLOC_LIMIT: usize = 1024;
 
 /// Reads a length-prefixed UTF-8 string from the input stream.
-#[binrw::parser(reader, endian)]
+#[binrw::parser(reader)]
 pub fn read_prefixed_string(len: usize, null: bool) -> BinResult<String> {
-    let out: Vec<u8> = <_>::read_options(
-        reader,
-        endian,
-        VecArgs::builder()
-            .count(len.saturating_sub(null as usize))
-            .finalize(),
-    )?;
+    // Read through a bounded reader rather than preallocating, so
+    // bogus length prefixes cannot trigger huge allocations.
+    let count = len.saturating_sub(null as usize);
+    let mut out = Vec::new();
+    if reader.take(count as u64).read_to_end(&mut out)? != count {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
 
     let new_pos = reader.seek(SeekFrom::Current(null as i64))?;
     String::from_utf8(out).map_err(|e| binrw::Error::Custom {
@@ -33,10 +39,19 @@ pub fn write_prefixed_string(name: &String, null: bool) -> BinResult<()> {
     Ok(())
 }
 
+/// Reads all remaining bytes from the input stream.
+#[binrw::parser(reader)]
+pub fn read_remaining() -> BinResult<Vec<u8>> {
+    let mut out = Vec::new();
+    reader.read_to_end(&mut out)?;
+
+    Ok(out)
+}
+
 /// Reads a list of strings, each length-prefixed with a `u32`.
 #[binrw::parser(reader, endian)]
 pub fn read_string_list(count: usize, null: bool) -> BinResult<Vec<String>> {
-    let mut out = Vec::with_capacity(count);
+    let mut out = Vec::with_capacity(count.min(PREALLOC_LIMIT));
     for _ in 0..count {
         let prefix = <u32>::read_options(reader, endian, ())?;
         out.push(read_prefixed_string(reader, endian, (prefix as _, null))?);
@@ -65,7 +80,7 @@ where
     for<'a> V: BinRead<Args<'a> = VA>,
     for<'a> VI: BinRead<Args<'a> = ()>,
 {
-    let mut map = HashMap::with_capacity(count);
+    let mut map = HashMap::with_capacity(count.min(PREALLOC_LIMIT));
     for _ in 0..count {
         let key = K::read_options(reader, endian, ())?;
 
@@ -0,0 +1,335 @@
+//! Helpers for compressing and decompressing data in the formats
+//! used by the game client.
+//!
+//! The free functions are convenient for one-off operations. When
+//! many buffers are processed in a row, [`Inflater`] and [`Deflater`]
+//! should be preferred as they reuse their internal state.
+
+use libdeflater::{CompressionLvl, Compressor, DecompressionError, Decompressor};
+use thiserror::Error;
+
+/// Errors that may occur when (de)compressing data.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    /// The input ended before a complete stream could be read.
+    #[error("compressed stream is truncated")]
+    Truncated,
+
+    /// The input is not a valid stream in the requested format.
+    #[error("compressed stream is corrupt")]
+    Corrupt,
+
+    /// The inflated data does not have the size it was expected to.
+    ///
+    /// `actual` is [`None`] when the data exceeds the expected size.
+    #[error("mismatch for inflated size: expected {expected}, got {}", DisplaySize(*.actual))]
+    SizeMismatch {
+        expected: usize,
+        actual: Option<usize>,
+    },
+
+    /// The expected inflated size is more than `compressed` bytes
+    /// of input could inflate to, so the size is bogus.
+    #[error("inflated size {expected} is impossible for {compressed} compressed bytes")]
+    ImpossibleSize { expected: usize, compressed: usize },
+
+    /// An unsupported compression level was requested.
+    #[error("invalid compression level {0}; must be in range 0..=12")]
+    InvalidLevel(u32),
+}
+
+impl Error {
+    /// Whether the error indicates that the input was not a valid
+    /// compressed stream at all.
+    pub fn is_bad_data(&self) -> bool {
+        matches!(self, Self::Truncated | Self::Corrupt)
+    }
+}
+
+struct DisplaySize(Option<usize>);
+
+impl std::fmt::Display for DisplaySize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(size) => write!(f, "{size}"),
+            None => f.write_str("more"),
+        }
+    }
+}
+
+// The best compression ratio deflate can theoretically achieve.
+const MAX_RATIO: usize = 1032;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Raw,
+    Zlib,
+    Gzip,
+}
+
+impl Format {
+    // The smallest possible encoding of an empty stream.
+    fn min_len(self) -> usize {
+        match self {
+            Self::Raw => 2,
+            Self::Zlib => 8,
+            Self::Gzip => 20,
+        }
+    }
+}
+
+/// A reusable decompressor for raw deflate, zlib and gzip streams.
+pub struct Inflater {
+    raw: Decompressor,
+}
+
+impl Inflater {
+    /// Creates a new inflater.
+    pub fn new() -> Self {
+        Self {
+            raw: Decompressor::new(),
+        }
+    }
+
+    fn inflate(&mut self, format: Format, src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        let res = match format {
+            Format::Raw => self.raw.deflate_decompress(src, out),
+            Format::Zlib => self.raw.zlib_decompress(src, out),
+            Format::Gzip => self.raw.gzip_decompress(src, out),
+        };
+
+        match res {
+            Ok(written) if written == out.len() => Ok(()),
+            Ok(written) => Err(Error::SizeMismatch {
+                expected: out.len(),
+                actual: Some(written),
+            }),
+
+            Err(DecompressionError::InsufficientSpace) => Err(Error::SizeMismatch {
+                expected: out.len(),
+                actual: None,
+            }),
+            Err(DecompressionError::BadData) if src.len() < format.min_len() => {
+                Err(Error::Truncated)
+            }
+            Err(DecompressionError::BadData) => Err(Error::Corrupt),
+        }
+    }
+
+    fn inflate_vec(
+        &mut self,
+        format: Format,
+        src: &[u8],
+        expected_len: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        // Reject sizes that `src` cannot possibly inflate to before
+        // committing memory to them.
+        if expected_len > src.len().saturating_mul(MAX_RATIO) {
+            return Err(Error::ImpossibleSize {
+                expected: expected_len,
+                compressed: src.len(),
+            });
+        }
+
+        out.clear();
+        out.resize(expected_len, 0);
+        self.inflate(format, src, out)
+    }
+
+    /// Inflates a zlib stream into `out`, which must exactly match
+    /// the size of the decompressed data.
+    pub fn zlib_into(&mut self, src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        self.inflate(Format::Zlib, src, out)
+    }
+
+    /// Inflates a zlib stream of `expected_len` decompressed bytes
+    /// into `out`, replacing its previous contents.
+    pub fn zlib_vec(
+        &mut self,
+        src: &[u8],
+        expected_len: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        self.inflate_vec(Format::Zlib, src, expected_len, out)
+    }
+
+    /// Inflates a raw deflate stream into `out`, which must exactly
+    /// match the size of the decompressed data.
+    pub fn raw_into(&mut self, src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        self.inflate(Format::Raw, src, out)
+    }
+
+    /// Inflates a raw deflate stream of `expected_len` decompressed
+    /// bytes into `out`, replacing its previous contents.
+    pub fn raw_vec(
+        &mut self,
+        src: &[u8],
+        expected_len: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        self.inflate_vec(Format::Raw, src, expected_len, out)
+    }
+
+    /// Inflates a gzip stream into `out`, which must exactly match
+    /// the size of the decompressed data.
+    pub fn gzip_into(&mut self, src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        self.inflate(Format::Gzip, src, out)
+    }
+
+    /// Inflates a gzip stream of `expected_len` decompressed bytes
+    /// into `out`, replacing its previous contents.
+    pub fn gzip_vec(
+        &mut self,
+        src: &[u8],
+        expected_len: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        self.inflate_vec(Format::Gzip, src, expected_len, out)
+    }
+}
+
+impl Default for Inflater {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A reusable compressor for raw deflate, zlib and gzip streams.
+pub struct Deflater {
+    raw: Compressor,
+}
+
+impl Deflater {
+    /// Creates a new deflater at the given compression level.
+    ///
+    /// Valid levels range from `0` (no compression) to `12` (best).
+    pub fn new(level: u32) -> Result<Self, Error> {
+        let lvl = i32::try_from(level)
+            .ok()
+            .and_then(|l| CompressionLvl::new(l).ok())
+            .ok_or(Error::InvalidLevel(level))?;
+
+        Ok(Self {
+            raw: Compressor::new(lvl),
+        })
+    }
+
+    /// Creates a new deflater at the best compression level.
+    pub fn best() -> Self {
+        Self {
+            raw: Compressor::new(CompressionLvl::best()),
+        }
+    }
+
+    fn deflate(&mut self, format: Format, src: &[u8], out: &mut Vec<u8>) -> usize {
+        let start = out.len();
+        let bound = match format {
+            Format::Raw => self.raw.deflate_compress_bound(src.len()),
+            Format::Zlib => self.raw.zlib_compress_bound(src.len()),
+            Format::Gzip => self.raw.gzip_compress_bound(src.len()),
+        };
+        out.resize(start + bound, 0);
+
+        let dest = &mut out[start..];
+        let written = match format {
+            Format::Raw => self.raw.deflate_compress(src, dest),
+            Format::Zlib => self.raw.zlib_compress(src, dest),
+            Format::Gzip => self.raw.gzip_compress(src, dest),
+        }
+        // The output buffer is sized to the upper bound for `src`.
+        .expect("compression bound was insufficient");
+
+        out.truncate(start + written);
+        written
+    }
+
+    /// Compresses `src` as a zlib stream and appends it to `out`.
+    ///
+    /// Returns the number of bytes appended.
+    pub fn zlib_into(&mut self, src: &[u8], out: &mut Vec<u8>) -> usize {
+        self.deflate(Format::Zlib, src, out)
+    }
+
+    /// Compresses `src` as a raw deflate stream and appends it to
+    /// `out`.
+    ///
+    /// Returns the number of bytes appended.
+    pub fn raw_into(&mut self, src: &[u8], out: &mut Vec<u8>) -> usize {
+        self.deflate(Format::Raw, src, out)
+    }
+
+    /// Compresses `src` as a gzip stream and appends it to `out`.
+    ///
+    /// Returns the number of bytes appended.
+    pub fn gzip_into(&mut self, src: &[u8], out: &mut Vec<u8>) -> usize {
+        self.deflate(Format::Gzip, src, out)
+    }
+}
+
+impl Default for Deflater {
+    fn default() -> Self {
+        Self::best()
+    }
+}
+
+/// Inflates a zlib stream which decompresses to `expected_len` bytes.
+pub fn inflate_zlib(src: &[u8], expected_len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    Inflater::new().zlib_vec(src, expected_len, &mut out)?;
+    Ok(out)
+}
+
+/// Inflates a zlib stream into `out`, which must exactly match the
+/// size of the decompressed data.
+pub fn inflate_into(src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+    Inflater::new().zlib_into(src, out)
+}
+
+/// Compresses `src` into a zlib stream at the given level.
+pub fn deflate_zlib(src: &[u8], level: u32) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    Deflater::new(level)?.zlib_into(src, &mut out);
+    Ok(out)
+}
+
+/// Inflates a raw deflate stream which decompresses to `expected_len`
+/// bytes.
+pub fn inflate_raw(src: &[u8], expected_len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    Inflater::new().raw_vec(src, expected_len, &mut out)?;
+    Ok(out)
+}
+
+/// Inflates a raw deflate stream into `out`, which must exactly match
+/// the size of the decompressed data.
+pub fn inflate_raw_into(src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+    Inflater::new().raw_into(src, out)
+}
+
+/// Compresses `src` into a raw deflate stream at the given level.
+pub fn deflate_raw(src: &[u8], level: u32) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    Deflater::new(level)?.raw_into(src, &mut out);
+    Ok(out)
+}
+
+/// Inflates a gzip stream which decompresses to `expected_len` bytes.
+pub fn inflate_gzip(src: &[u8], expected_len: usize) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    Inflater::new().gzip_vec(src, expected_len, &mut out)?;
+    Ok(out)
+}
+
+/// Inflates a gzip stream into `out`, which must exactly match the
+/// size of the decompressed data.
+pub fn inflate_gzip_into(src: &[u8], out: &mut [u8]) -> Result<(), Error> {
+    Inflater::new().gzip_into(src, out)
+}
+
+/// Compresses `src` into a gzip stream at the given level.
+pub fn deflate_gzip(src: &[u8], level: u32) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    Deflater::new(level)?.gzip_into(src, &mut out);
+    Ok(out)
+}
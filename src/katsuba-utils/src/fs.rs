@@ -0,0 +1,259 @@
+//! File system helpers for reading and writing whole files and
+//! creating directory trees.
+
+use std::{
+    collections::{btree_set, BTreeSet},
+    fs::{self, File},
+    io::{self, Write},
+    iter,
+    ops::Deref,
+    path::Path,
+    process,
+};
+
+/// Files smaller than this are read into memory even when mapping
+/// is available, since mapping them costs more than copying.
+#[cfg(feature = "memmap2")]
+const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+/// The contents of a file, either memory-mapped or read into a
+/// heap buffer.
+///
+/// Dereferences to the file's bytes in both cases.
+#[derive(Debug)]
+pub enum MappedOrOwned {
+    /// A read-only memory map of the file.
+    #[cfg(feature = "memmap2")]
+    Mapped(memmap2::Mmap),
+    /// The file contents copied into memory.
+    Owned(Vec<u8>),
+}
+
+impl Deref for MappedOrOwned {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "memmap2")]
+            Self::Mapped(m) => m,
+            Self::Owned(v) => v,
+        }
+    }
+}
+
+impl AsRef<[u8]> for MappedOrOwned {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "memmap2")]
+#[allow(unsafe_code)]
+fn map(file: &File) -> io::Result<memmap2::Mmap> {
+    // SAFETY: The map is read-only. Concurrent modification of the
+    // file by other processes is outside of our control; this is the
+    // same trade-off every mmap-based reader makes.
+    unsafe { memmap2::Mmap::map(file) }
+}
+
+/// Reads the contents of the file at `path`.
+///
+/// Large regular files are memory-mapped when the `memmap2` feature
+/// is enabled. Small files, pipes and other special files, or files
+/// which fail to map are read into memory instead.
+pub fn read_mapped<P: AsRef<Path>>(path: P) -> io::Result<MappedOrOwned> {
+    #[cfg(feature = "memmap2")]
+    {
+        let file = File::open(path.as_ref())?;
+        let meta = file.metadata()?;
+
+        if meta.is_file() && meta.len() >= MMAP_THRESHOLD {
+            if let Ok(mapped) = map(&file) {
+                return Ok(MappedOrOwned::Mapped(mapped));
+            }
+        }
+    }
+
+    fs::read(path).map(MappedOrOwned::Owned)
+}
+
+/// Atomically replaces the file at `path` with `data`.
+///
+/// The data is written to a temporary file in the same directory
+/// which is then renamed over the destination, so readers observe
+/// either the old or the new contents but never a partial write.
+///
+/// When `sync` is set, the data is flushed to disk before the rename
+/// so the new contents also survive a crash.
+pub fn atomic_write<P: AsRef<Path>>(path: P, data: &[u8], sync: bool) -> io::Result<()> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path does not name a file"))?;
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp = path.with_file_name(temp_name);
+
+    let res = (|| {
+        let mut file = File::create(&temp)?;
+        file.write_all(data)?;
+        if sync {
+            file.sync_all()?;
+        }
+        drop(file);
+
+        fs::rename(&temp, path)
+    })();
+
+    // Don't leave the temporary file behind on failure.
+    if res.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+
+    res
+}
+
+/// Gets the number of bytes available to the current user on the
+/// file system which holds `path`.
+///
+/// `path` does not need to exist yet, its closest existing ancestor
+/// is queried instead.
+#[cfg(feature = "disk-space")]
+pub fn available_space<P: AsRef<Path>>(path: P) -> io::Result<u64> {
+    let path = path.as_ref();
+    let existing = path
+        .ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .unwrap_or(Path::new("."));
+
+    query_available_space(existing)
+}
+
+#[cfg(all(feature = "disk-space", unix))]
+#[allow(unsafe_code)]
+fn query_available_space(path: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is valid for writes.
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `statvfs` initialized the struct on success.
+    let stat = unsafe { stat.assume_init() };
+
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(all(feature = "disk-space", windows))]
+#[allow(unsafe_code)]
+fn query_available_space(path: &Path) -> io::Result<u64> {
+    use std::{os::windows::ffi::OsStrExt, ptr};
+
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain([0]).collect();
+
+    let mut available = 0;
+    // SAFETY: `path` is NUL-terminated and the other totals are
+    // optional.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            path.as_ptr(),
+            &mut available,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    match ok {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(available),
+    }
+}
+
+#[cfg(all(feature = "disk-space", not(any(unix, windows))))]
+fn query_available_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "querying free space is not supported on this platform",
+    ))
+}
+
+/// A structure which interns directory trees from given file paths
+/// and returns the minimal amount of paths to be created.
+///
+/// Only the deepest directories are yielded, since creating them
+/// recursively also creates all their parents. This ensures that we
+/// create a directory tree with the least required system calls,
+/// which has shown to greatly impact performance on Windows systems.
+#[derive(Debug, Default)]
+pub struct DirectoryTree<'a> {
+    inner: BTreeSet<&'a Path>,
+}
+
+impl<'a> DirectoryTree<'a> {
+    /// Creates an empty directory tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Given a path to a file, interns the directory tree needed
+    /// to be created for it.
+    ///
+    /// Paths ending in a separator name a directory, which is then
+    /// interned itself.
+    pub fn add(&mut self, path: &'a Path) {
+        let is_dir = path
+            .as_os_str()
+            .to_str()
+            .is_some_and(|s| s.ends_with(std::path::is_separator));
+
+        let dir = match is_dir {
+            true => Some(path),
+            false => path.parent(),
+        };
+        if let Some(p) = dir.filter(|p| !p.as_os_str().is_empty()) {
+            self.inner.insert(p);
+        }
+    }
+}
+
+impl<'a> IntoIterator for DirectoryTree<'a> {
+    type Item = &'a Path;
+
+    type IntoIter = Leaves<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Leaves {
+            inner: self.inner.into_iter().peekable(),
+        }
+    }
+}
+
+/// An iterator over the leaf directories of a [`DirectoryTree`]
+/// in sorted order.
+pub struct Leaves<'a> {
+    inner: iter::Peekable<btree_set::IntoIter<&'a Path>>,
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = &'a Path;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Paths are ordered by their components, so descendants of
+        // a directory immediately follow it.
+        loop {
+            let path = self.inner.next()?;
+            match self.inner.peek() {
+                Some(next) if next.starts_with(path) => continue,
+                _ => return Some(path),
+            }
+        }
+    }
+}
@@ -1,8 +1,19 @@
 //! Commonly used dictionary hash functions.
 
+mod crc32;
+pub use crc32::*;
+
+mod index;
+pub use index::*;
+
 /// Implementation of the String ID algorithm.
 ///
-/// This algorithm is hand-rolled by KingsIsle.
+/// This algorithm is hand-rolled by KingsIsle. Every byte is offset
+/// by -32 and XORed into the state at a bit position that advances
+/// by 5 for each byte, wrapping around the 32-bit state. The absolute
+/// value of the signed result is the final hash.
+///
+/// It is used to identify type names in serialized data.
 #[inline(always)]
 pub fn string_id(input: &[u8]) -> u32 {
     let mut state = 0;
@@ -22,6 +33,9 @@ pub fn string_id(input: &[u8]) -> u32 {
 
 /// Implementation of the [DJB2] hash function.
 ///
+/// Unlike the reference implementation, the most significant bit
+/// of the result is always cleared.
+///
 /// [DJB2]: https://theartincode.stanis.me/008-djb2/
 #[inline(always)]
 pub fn djb2(input: &[u8]) -> u32 {
@@ -33,3 +47,12 @@ pub fn djb2(input: &[u8]) -> u32 {
     // NOTE: KI's implementation strips the MSB.
     state & (u32::MAX >> 1)
 }
+
+/// Computes the hash of a property from its name and type name.
+///
+/// This is the [`djb2`] hash of the name added to the [`string_id`]
+/// of the type, as found in the `hash` field of type list properties.
+#[inline]
+pub fn property_hash(name: &[u8], r#type: &[u8]) -> u32 {
+    djb2(name).wrapping_add(string_id(r#type))
+}
@@ -0,0 +1,69 @@
+/// The reflected CRC-32 polynomial used by KIWAD archives.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+const TABLE: [u32; 256] = build_table();
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0; 256];
+
+    let mut i = 0;
+    while i < 256 {
+        let mut value = i as u32;
+
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 != 0 {
+                (value >> 1) ^ POLYNOMIAL
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+
+        table[i] = value;
+        i += 1;
+    }
+
+    table
+}
+
+/// A streaming implementation of the [`crc32`] algorithm.
+///
+/// Feeding data in several [`update`][Self::update] calls produces
+/// the same result as hashing the concatenated input at once.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crc32Hasher {
+    state: u32,
+}
+
+impl Crc32Hasher {
+    /// Creates a new hasher with the initial state.
+    pub const fn new() -> Self {
+        Self { state: 0 }
+    }
+
+    /// Feeds more `data` into the hasher.
+    pub fn update(&mut self, data: &[u8]) {
+        self.state = data.iter().fold(self.state, |state, &b| {
+            TABLE[((state ^ b as u32) & 0xFF) as usize] ^ (state >> 8)
+        });
+    }
+
+    /// Gets the checksum of all the data fed so far.
+    pub const fn finalize(&self) -> u32 {
+        self.state
+    }
+}
+
+/// Computes the CRC-32 checksum of `data` as encoded in KIWAD
+/// archive journals.
+///
+/// KingsIsle uses the common reflected polynomial `0xEDB88320`, but
+/// neither inverts the initial state nor the final value. Results
+/// thus differ from the usual zlib CRC-32 for all non-empty inputs.
+#[inline]
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut hasher = Crc32Hasher::new();
+    hasher.update(data);
+    hasher.finalize()
+}
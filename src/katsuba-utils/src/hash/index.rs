@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use super::{djb2, property_hash, string_id};
+
+const CACHE_MAGIC: &[u8; 4] = b"KHIX";
+const CACHE_VERSION: u8 = 1;
+
+/// The hash algorithm which produced a [`Candidate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum HashKind {
+    /// The [`string_id`] algorithm.
+    StringId = 0,
+    /// The [`djb2`] algorithm.
+    Djb2 = 1,
+    /// The [`property_hash`] of a property name and type.
+    Property = 2,
+}
+
+impl HashKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::StringId),
+            1 => Some(Self::Djb2),
+            2 => Some(Self::Property),
+            _ => None,
+        }
+    }
+}
+
+/// A known string which hashes to a looked up value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Candidate {
+    /// The algorithm that produced the hash.
+    pub kind: HashKind,
+    /// The original string.
+    ///
+    /// For [`HashKind::Property`], this is formatted as `name: type`.
+    pub value: String,
+}
+
+/// A reverse lookup table from hashes to known strings.
+///
+/// Strings are indexed under every supported algorithm, so a bare
+/// hash can be resolved without knowing how it was produced. When
+/// several strings map to the same hash, all of them are kept.
+#[derive(Clone, Debug, Default)]
+pub struct HashIndex {
+    entries: HashMap<u32, Vec<Candidate>>,
+}
+
+impl HashIndex {
+    /// Creates a new, empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the number of distinct hashes in the index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn add(&mut self, hash: u32, kind: HashKind, value: &str) {
+        let candidates = self.entries.entry(hash).or_default();
+        if !candidates
+            .iter()
+            .any(|c| c.kind == kind && c.value == value)
+        {
+            candidates.push(Candidate {
+                kind,
+                value: value.to_owned(),
+            });
+        }
+    }
+
+    /// Indexes a string under all plain string hash algorithms.
+    pub fn insert(&mut self, value: &str) {
+        self.add(string_id(value.as_bytes()), HashKind::StringId, value);
+        self.add(djb2(value.as_bytes()), HashKind::Djb2, value);
+    }
+
+    /// Indexes the hash of a property with the given name and type.
+    pub fn insert_property(&mut self, name: &str, r#type: &str) {
+        let hash = property_hash(name.as_bytes(), r#type.as_bytes());
+        self.add(hash, HashKind::Property, &format!("{name}: {type}"));
+    }
+
+    /// Gets all known strings which produce the given hash.
+    pub fn lookup(&self, hash: u32) -> &[Candidate] {
+        self.entries.get(&hash).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Merges all entries from `other` into `self`.
+    pub fn merge(&mut self, other: HashIndex) {
+        for (hash, candidates) in other.entries {
+            for c in candidates {
+                self.add(hash, c.kind, &c.value);
+            }
+        }
+    }
+
+    /// Reads an index previously written by [`HashIndex::write`].
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != CACHE_MAGIC || read_u8(&mut reader)? != CACHE_VERSION {
+            return Err(invalid_data("not a hash index cache"));
+        }
+
+        let mut this = Self::new();
+        for _ in 0..read_u32(&mut reader)? {
+            let hash = read_u32(&mut reader)?;
+            let kind = HashKind::from_u8(read_u8(&mut reader)?)
+                .ok_or_else(|| invalid_data("unknown hash kind"))?;
+
+            // Lengths come from the file, so memory only grows with
+            // the data that is actually there.
+            let len = read_u32(&mut reader)?;
+            let mut value = Vec::new();
+            reader.by_ref().take(len.into()).read_to_end(&mut value)?;
+            if value.len() != len as usize {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let value = String::from_utf8(value).map_err(|_| invalid_data("invalid UTF-8"))?;
+
+            this.add(hash, kind, &value);
+        }
+
+        Ok(this)
+    }
+
+    /// Writes the index in a compact binary format.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let count: usize = self.entries.values().map(Vec::len).sum();
+
+        writer.write_all(CACHE_MAGIC)?;
+        writer.write_all(&[CACHE_VERSION])?;
+        writer.write_all(&(count as u32).to_le_bytes())?;
+
+        for (hash, candidates) in &self.entries {
+            for c in candidates {
+                writer.write_all(&hash.to_le_bytes())?;
+                writer.write_all(&[c.kind as u8])?;
+                writer.write_all(&(c.value.len() as u32).to_le_bytes())?;
+                writer.write_all(c.value.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: AsRef<str>> Extend<S> for HashIndex {
+    fn extend<I: IntoIterator<Item = S>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value.as_ref());
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
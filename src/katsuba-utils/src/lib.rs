@@ -1,7 +1,13 @@
 //! Shared utility code throughout the Katsuba project.
 
 #![deny(rust_2018_idioms, rustdoc::broken_intra_doc_links)]
-#![forbid(unsafe_code)]
+// Memory mapping and querying disk space require unsafe code, which
+// is confined to single functions in the `fs` module.
+#![cfg_attr(
+    not(any(feature = "memmap2", feature = "disk-space")),
+    forbid(unsafe_code)
+)]
+#![cfg_attr(any(feature = "memmap2", feature = "disk-space"), deny(unsafe_code))]
 
 #[cfg(feature = "binrw")]
 pub use binrw;
@@ -12,4 +18,7 @@ pub use thiserror;
 pub mod align;
 #[cfg(feature = "binrw")]
 pub mod binrw_ext;
+#[cfg(feature = "libdeflater")]
+pub mod compress;
+pub mod fs;
 pub mod hash;
@@ -0,0 +1,161 @@
+use std::io::{Cursor, Seek, SeekFrom};
+
+use katsuba_utils::align::*;
+
+#[test]
+fn alignment_powers_of_two() {
+    for shift in 0..u64::BITS {
+        assert_eq!(Alignment::checked(1 << shift).unwrap().get(), 1 << shift);
+    }
+
+    for bad in [0, 3, 6, 12, u64::MAX, (1 << 63) + 1] {
+        assert_eq!(Alignment::checked(bad), Err(BadAlignment(bad)));
+    }
+    assert_eq!(
+        BadAlignment(6).to_string(),
+        "alignment 6 is not a power of two"
+    );
+}
+
+#[test]
+#[should_panic(expected = "alignment must be a power of two")]
+fn alignment_rejects_zero() {
+    Alignment::new(0);
+}
+
+#[test]
+fn align_small_values() {
+    const EIGHT: Alignment = Alignment::new(8);
+
+    let cases = [
+        // value, down, up, padding
+        (0, 0, 0, 0),
+        (1, 0, 8, 7),
+        (7, 0, 8, 1),
+        (8, 8, 8, 0),
+        (9, 8, 16, 7),
+        (15, 8, 16, 1),
+        (16, 16, 16, 0),
+    ];
+    for (value, down, up, padding) in cases {
+        assert_eq!(align_down(value, EIGHT), down, "align_down({value})");
+        assert_eq!(align_up(value, EIGHT), Some(up), "align_up({value})");
+        assert_eq!(padding_for(value, EIGHT), padding, "padding_for({value})");
+    }
+
+    // An alignment of one leaves every value alone.
+    let one = Alignment::new(1);
+    for value in [0, 1, 7, u64::MAX] {
+        assert_eq!(align_down(value, one), value);
+        assert_eq!(align_up(value, one), Some(value));
+        assert_eq!(padding_for(value, one), 0);
+    }
+}
+
+#[test]
+fn align_near_overflow() {
+    let eight = Alignment::new(8);
+    let last = u64::MAX - 7;
+
+    assert_eq!(align_up(last, eight), Some(last));
+    assert_eq!(align_up(last - 1, eight), Some(last));
+    assert_eq!(align_up(last + 1, eight), None);
+    assert_eq!(align_up(u64::MAX, eight), None);
+
+    assert_eq!(align_down(u64::MAX, eight), last);
+    assert_eq!(padding_for(u64::MAX, eight), 1);
+    assert_eq!(padding_for(last + 1, eight), 7);
+}
+
+#[test]
+fn align_to_max_alignment() {
+    let max = Alignment::new(1 << 63);
+
+    assert_eq!(align_up(0, max), Some(0));
+    assert_eq!(align_up(1, max), Some(1 << 63));
+    assert_eq!(align_up((1 << 63) - 1, max), Some(1 << 63));
+    assert_eq!(align_up(1 << 63, max), Some(1 << 63));
+    assert_eq!(align_up((1 << 63) + 1, max), None);
+
+    assert_eq!(align_down((1 << 63) - 1, max), 0);
+    assert_eq!(align_down(u64::MAX, max), 1 << 63);
+
+    assert_eq!(padding_for(1, max), (1 << 63) - 1);
+    assert_eq!(padding_for(u64::MAX, max), 1);
+}
+
+#[test]
+fn seek_at_boundaries() {
+    let mut stream = Cursor::new(vec![0; 64]);
+
+    // Already aligned streams stay where they are.
+    stream.set_position(16);
+    assert_eq!(seek_to_alignment(&mut stream, 8, 0).unwrap(), 16);
+
+    // One byte before and after a boundary.
+    stream.set_position(15);
+    assert_eq!(seek_to_alignment(&mut stream, 8, 0).unwrap(), 16);
+    stream.set_position(17);
+    assert_eq!(seek_to_alignment(&mut stream, 8, 0).unwrap(), 24);
+    assert_eq!(stream.position(), 24);
+}
+
+#[test]
+fn seek_relative_to_base() {
+    let mut stream = Cursor::new(vec![0; 64]);
+
+    stream.set_position(5);
+    assert_eq!(seek_to_alignment(&mut stream, 4, 3).unwrap(), 7);
+    stream.set_position(7);
+    assert_eq!(seek_to_alignment(&mut stream, 4, 3).unwrap(), 7);
+
+    // Positions before the base are rejected.
+    stream.set_position(2);
+    assert!(seek_to_alignment(&mut stream, 4, 3).is_err());
+}
+
+#[test]
+fn seek_past_u32_range() {
+    let mut stream = Cursor::new(Vec::new());
+
+    stream.set_position(u32::MAX as u64);
+    let pos = seek_to_alignment(&mut stream, 8, 0).unwrap();
+    assert_eq!(pos, u32::MAX as u64 + 1);
+
+    stream.set_position(u64::MAX - 2);
+    assert!(seek_to_alignment(&mut stream, 8, 0).is_err());
+
+    let err = seek_to_alignment(&mut stream, 6, 0).unwrap_err();
+    assert_eq!(err.to_string(), "alignment 6 is not a power of two");
+}
+
+#[test]
+fn write_padding_bytes() {
+    let mut stream = Cursor::new(vec![0xFF; 3]);
+    stream.seek(SeekFrom::End(0)).unwrap();
+
+    assert_eq!(write_padding(&mut stream, 4, 0).unwrap(), 4);
+    assert_eq!(stream.get_ref(), &[0xFF, 0xFF, 0xFF, 0]);
+
+    assert_eq!(write_padding(&mut stream, 4, 0).unwrap(), 4);
+    assert_eq!(stream.get_ref().len(), 4);
+
+    assert_eq!(write_padding(&mut stream, 128, 0).unwrap(), 128);
+    assert_eq!(stream.get_ref().len(), 128);
+}
+
+#[cfg(feature = "binrw")]
+#[test]
+fn padded_roundtrip() {
+    use katsuba_utils::binrw::{BinReaderExt, BinWriterExt};
+
+    let mut stream = Cursor::new(Vec::new());
+    stream.write_le(&Padded::<u8, 4>(0xAB)).unwrap();
+    stream.write_le(&0xCDEF_u16).unwrap();
+    assert_eq!(stream.get_ref(), &[0xAB, 0, 0, 0, 0xEF, 0xCD]);
+
+    stream.set_position(0);
+    let padded: Padded<u8, 4> = stream.read_le().unwrap();
+    let next: u16 = stream.read_le().unwrap();
+    assert_eq!((padded.0, next), (0xAB, 0xCDEF));
+}
@@ -0,0 +1,82 @@
+#![cfg(feature = "libdeflater")]
+
+use katsuba_utils::compress::*;
+
+const DATA: &[u8] = b"the quick brown fox jumps over the lazy dog, the quick brown fox";
+
+#[test]
+fn roundtrip_formats() {
+    let zlib = deflate_zlib(DATA, 12).unwrap();
+    assert_eq!(inflate_zlib(&zlib, DATA.len()).unwrap(), DATA);
+
+    let raw = deflate_raw(DATA, 6).unwrap();
+    assert_eq!(inflate_raw(&raw, DATA.len()).unwrap(), DATA);
+
+    let gzip = deflate_gzip(DATA, 1).unwrap();
+    assert_eq!(inflate_gzip(&gzip, DATA.len()).unwrap(), DATA);
+
+    let mut out = vec![0; DATA.len()];
+    inflate_into(&zlib, &mut out).unwrap();
+    assert_eq!(out, DATA);
+}
+
+#[test]
+fn size_mismatch() {
+    let zlib = deflate_zlib(DATA, 6).unwrap();
+
+    assert_eq!(
+        inflate_zlib(&zlib, DATA.len() + 1),
+        Err(Error::SizeMismatch {
+            expected: DATA.len() + 1,
+            actual: Some(DATA.len()),
+        })
+    );
+    assert_eq!(
+        inflate_zlib(&zlib, DATA.len() - 1),
+        Err(Error::SizeMismatch {
+            expected: DATA.len() - 1,
+            actual: None,
+        })
+    );
+
+    // Sizes beyond what deflate can encode are rejected upfront.
+    let err = inflate_zlib(&zlib, zlib.len() * 2000).unwrap_err();
+    assert_eq!(
+        err,
+        Error::ImpossibleSize {
+            expected: zlib.len() * 2000,
+            compressed: zlib.len(),
+        }
+    );
+    assert!(!err.is_bad_data());
+}
+
+#[test]
+fn bad_input() {
+    let zlib = deflate_zlib(DATA, 6).unwrap();
+
+    assert_eq!(inflate_zlib(&zlib[..4], DATA.len()), Err(Error::Truncated));
+    assert_eq!(inflate_zlib(&[0xFF; 32], DATA.len()), Err(Error::Corrupt));
+    assert!(inflate_gzip(&zlib, DATA.len()).unwrap_err().is_bad_data());
+
+    assert_eq!(deflate_zlib(DATA, 13), Err(Error::InvalidLevel(13)));
+}
+
+#[test]
+fn reuse_state() {
+    let mut deflater = Deflater::best();
+    let mut inflater = Inflater::new();
+
+    // Compressed streams are appended after existing data.
+    let mut out = b"prefix".to_vec();
+    let written = deflater.zlib_into(DATA, &mut out);
+    assert_eq!(out.len(), 6 + written);
+
+    let mut scratch = Vec::new();
+    for _ in 0..2 {
+        inflater
+            .zlib_vec(&out[6..], DATA.len(), &mut scratch)
+            .unwrap();
+        assert_eq!(scratch, DATA);
+    }
+}
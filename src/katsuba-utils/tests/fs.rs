@@ -0,0 +1,94 @@
+use std::{fs, path::Path};
+
+use katsuba_utils::fs::*;
+
+#[test]
+fn read_small_and_large_files() {
+    let dir = tempfile::tempdir().unwrap();
+
+    let small = dir.path().join("small.bin");
+    fs::write(&small, b"hello").unwrap();
+    assert_eq!(&*read_mapped(&small).unwrap(), b"hello");
+
+    let large = dir.path().join("large.bin");
+    let data: Vec<u8> = (0..256 * 1024).map(|i| i as u8).collect();
+    fs::write(&large, &data).unwrap();
+    assert_eq!(&*read_mapped(&large).unwrap(), data.as_slice());
+
+    let empty = dir.path().join("empty.bin");
+    fs::write(&empty, b"").unwrap();
+    assert!(read_mapped(&empty).unwrap().is_empty());
+
+    assert!(read_mapped(dir.path().join("missing.bin")).is_err());
+}
+
+#[test]
+fn atomic_write_replaces_contents() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("out.bin");
+
+    atomic_write(&path, b"first", false).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"first");
+
+    atomic_write(&path, b"second", true).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"second");
+
+    // No temporary files are left behind.
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    assert!(atomic_write(dir.path().join("missing/out.bin"), b"x", false).is_err());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+fn leaves<'a>(files: &'a [&'a str]) -> Vec<&'a Path> {
+    let mut tree = DirectoryTree::new();
+    for file in files {
+        tree.add(Path::new(file));
+    }
+    tree.into_iter().collect()
+}
+
+#[test]
+fn directory_tree_yields_leaves() {
+    // A single deep chain only needs its deepest directory.
+    let chain = ["a/b/c/d/e/f.txt", "a/b/c/g.txt", "a/h.txt", "a/b/c/d/i.txt"];
+    assert_eq!(leaves(&chain), [Path::new("a/b/c/d/e")]);
+
+    // Files in a flat directory share it, and top-level files need none.
+    let flat: Vec<String> = (0..100).map(|i| format!("dir/{i}.txt")).collect();
+    let mut flat: Vec<&str> = flat.iter().map(String::as_str).collect();
+    flat.push("root.txt");
+    assert_eq!(leaves(&flat), [Path::new("dir")]);
+
+    // Siblings and similar prefixes are kept apart, in sorted order.
+    let mixed = [
+        "b/x/1.bin",
+        "a/2.bin",
+        "a.b/3.bin",
+        "a/y/4.bin",
+        "a/y/z/5.bin",
+        "b/6.bin",
+        "ab/7.bin",
+    ];
+    assert_eq!(leaves(&mixed), ["a/y/z", "a.b", "ab", "b/x"].map(Path::new));
+
+    assert!(leaves(&[]).is_empty());
+}
+
+#[test]
+fn directory_tree_directory_entries() {
+    // Trailing separators name the directory itself.
+    assert_eq!(leaves(&["a/b/", "c.txt"]), [Path::new("a/b")]);
+    assert_eq!(leaves(&["a/b/", "a/b/c.txt"]), [Path::new("a/b")]);
+    assert_eq!(leaves(&["a/", "a/b/c.txt"]), [Path::new("a/b")]);
+}
+
+#[cfg(feature = "disk-space")]
+#[test]
+fn available_space_of_missing_path() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // Paths which don't exist yet are looked up by their ancestors.
+    assert!(available_space(dir.path()).unwrap() > 0);
+    assert!(available_space(dir.path().join("a/b/c")).unwrap() > 0);
+}
@@ -0,0 +1,86 @@
+use katsuba_utils::hash::*;
+
+#[test]
+fn lookup_by_any_algorithm() {
+    let mut index = HashIndex::new();
+    index.extend(["std::string", "m_packedName"]);
+
+    let candidates = index.lookup(1497788074);
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].kind, HashKind::StringId);
+    assert_eq!(candidates[0].value, "std::string");
+
+    let candidates = index.lookup(307420154);
+    assert_eq!(candidates[0].kind, HashKind::Djb2);
+    assert_eq!(candidates[0].value, "m_packedName");
+
+    assert!(index.lookup(0).is_empty());
+}
+
+#[test]
+fn lookup_properties() {
+    let mut index = HashIndex::new();
+    index.insert_property(
+        "m_equipmentSetList",
+        "class SharedPointer<class EquipmentSet>",
+    );
+
+    let candidates = index.lookup(1788831224);
+    assert_eq!(candidates[0].kind, HashKind::Property);
+    assert_eq!(
+        candidates[0].value,
+        "m_equipmentSetList: class SharedPointer<class EquipmentSet>"
+    );
+}
+
+#[test]
+fn collisions_keep_all_candidates() {
+    let mut index = HashIndex::new();
+    index.extend(["heliotropes", "neurospora", "heliotropes"]);
+
+    let mut values: Vec<_> = index
+        .lookup(djb2(b"heliotropes"))
+        .iter()
+        .map(|c| c.value.as_str())
+        .collect();
+    values.sort_unstable();
+
+    assert_eq!(values, ["heliotropes", "neurospora"]);
+}
+
+#[test]
+fn cache_roundtrip() {
+    let mut index = HashIndex::new();
+    index.extend(["class Matrix3x3", "heliotropes", "neurospora"]);
+    index.insert_property("m_packedName", "std::string");
+
+    let mut buf = Vec::new();
+    index.write(&mut buf).unwrap();
+    let restored = HashIndex::read(buf.as_slice()).unwrap();
+
+    assert_eq!(restored.len(), index.len());
+    for hash in [1479974833, djb2(b"neurospora")] {
+        let mut a = index.lookup(hash).to_vec();
+        let mut b = restored.lookup(hash).to_vec();
+        a.sort_by(|x, y| x.value.cmp(&y.value));
+        b.sort_by(|x, y| x.value.cmp(&y.value));
+        assert_eq!(a, b);
+    }
+
+    assert!(HashIndex::read(&b"nope"[..]).is_err());
+}
+
+#[test]
+fn truncated_cache() {
+    let mut index = HashIndex::new();
+    index.extend(["heliotropes"]);
+
+    let mut buf = Vec::new();
+    index.write(&mut buf).unwrap();
+
+    // A string length far beyond the data must not be trusted.
+    let len = buf.len() - "heliotropes".len() - 4;
+    buf[len..len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let err = HashIndex::read(buf.as_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
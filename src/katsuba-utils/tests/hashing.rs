@@ -3,14 +3,53 @@ use katsuba_utils::hash::*;
 #[test]
 fn test_djb2() {
     assert_eq!(djb2(b"m_packedName"), 307420154);
+    assert_eq!(djb2(b"m_equipmentSetList"), 306640881);
 }
 
 #[test]
 fn test_string_id() {
     assert_eq!(string_id(b"std::string"), 1497788074);
     assert_eq!(string_id(b"class FishTournamentEntry"), 1725212200);
+    assert_eq!(string_id(b"class EquipmentSetList"), 135649998);
+    assert_eq!(string_id(b"class Matrix3x3"), 1479974833);
     assert_eq!(
         string_id(b"class NonCombatMayCastSpellTemplate*"),
         920052956
     );
 }
+
+#[test]
+fn test_property_hash() {
+    assert_eq!(
+        property_hash(
+            b"m_equipmentSetList",
+            b"class SharedPointer<class EquipmentSet>"
+        ),
+        1788831224
+    );
+}
+
+// Contents and checksums of files extracted from a real KIWAD journal.
+#[test]
+fn crc32_wad_files() {
+    assert_eq!(crc32(b"uncompressed data\n"), 0x65a073d0);
+    assert_eq!(
+        crc32(&[
+            120, 218, 43, 201, 200, 44, 86, 0, 162, 146, 212, 138, 18, 67, 46, 0, 39, 77, 4, 213
+        ]),
+        0xcf4d7b4c
+    );
+
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn crc32_streaming() {
+    let data = b"uncompressed data\n";
+
+    let mut hasher = Crc32Hasher::new();
+    for chunk in data.chunks(5) {
+        hasher.update(chunk);
+    }
+    assert_eq!(hasher.finalize(), crc32(data));
+}
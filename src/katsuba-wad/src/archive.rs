@@ -3,18 +3,24 @@ use std::{
     fs,
     io::{self, Read},
     mem,
-    path::Path,
+    ops::{Range, RangeInclusive},
+    path::{Path, PathBuf},
 };
 
 use katsuba_utils::{
-    binrw,
-    libdeflater::DecompressionError,
+    binrw, compress,
     thiserror::{self, Error},
 };
 use memmap2::{Mmap, MmapOptions};
 
 use crate::{glob, types as wad_types};
 
+// The magic bytes every KIWAD archive starts with.
+const MAGIC: &[u8; 5] = b"KIWAD";
+
+// The format versions we know how to read.
+const KNOWN_VERSIONS: RangeInclusive<u32> = 1..=2;
+
 /// Errors that may occur when working with KIWAD archives.
 #[derive(Debug, Error)]
 pub enum ArchiveError {
@@ -24,15 +30,55 @@ pub enum ArchiveError {
 
     /// Decompression of a file in the archive failed.
     #[error("failed to decompress archive file: {0}")]
-    Zlib(#[from] DecompressionError),
+    Zlib(#[from] compress::Error),
+
+    /// The archive file has no contents at all.
+    #[error("archive is empty (0 bytes)")]
+    Empty,
+
+    /// The data does not start with the KIWAD magic.
+    #[error("not a WAD archive (bad magic): found {found:02x?}")]
+    BadMagic { found: Vec<u8> },
+
+    /// The archive header declares a version we cannot read.
+    #[error("unsupported WAD version {0}")]
+    UnsupportedVersion(u32),
+
+    /// The data ends before the archive header is complete.
+    #[error("archive header is truncated ({0} bytes)")]
+    TruncatedHeader(usize),
 
     /// Failed to parse the archive file.
     #[error("failed to parse archive: {0}")]
     Parse(binrw::Error),
 
-    /// CRC validation of an archive file failed.
+    /// Validation of an archive file against its journal entry failed.
     #[error("{0}")]
-    Crc(#[from] wad_types::CrcMismatch),
+    Verify(#[from] wad_types::VerifyError),
+
+    /// Opening the archive file at `path` failed.
+    #[error("'{}': {source}", path.display())]
+    AtPath {
+        path: PathBuf,
+        source: Box<ArchiveError>,
+    },
+}
+
+impl ArchiveError {
+    /// Gets the underlying error without the path it occurred at.
+    pub fn inner(&self) -> &ArchiveError {
+        match self {
+            Self::AtPath { source, .. } => source.inner(),
+            e => e,
+        }
+    }
+
+    fn at_path(path: &Path) -> impl FnOnce(Self) -> Self + '_ {
+        move |e| Self::AtPath {
+            path: path.to_owned(),
+            source: Box::new(e),
+        }
+    }
 }
 
 impl From<binrw::Error> for ArchiveError {
@@ -51,19 +97,50 @@ impl From<binrw::Error> for ArchiveError {
 ///
 /// It supports two modes of interacting with an underlying
 /// archive file: read or mmap.
-pub struct Archive(ArchiveInner);
+pub struct Archive {
+    inner: ArchiveInner,
+    patch: Option<Box<PatchSource>>,
+}
 
 enum ArchiveInner {
     MemoryMapped(MemoryMappedArchive),
     Heap(HeapArchive),
 }
 
+/// A secondary source for the data of unpatched archive files.
+///
+/// Some archives ship with files whose data is left zeroed out,
+/// while the actual contents live in a separate patch location.
+/// These are looked up by their path in the archive.
+pub enum PatchSource {
+    /// Another archive which holds the files.
+    Archive(Archive),
+    /// A directory which holds the files in uncompressed form.
+    Dir(PathBuf),
+}
+
+/// The contents of an unpatched file, as resolved from its
+/// [`PatchSource`].
+pub enum PatchedFile<'a> {
+    /// A file in the patch archive.
+    ///
+    /// Its contents can be extracted from the archive like usual.
+    Archived(&'a Archive, &'a wad_types::File),
+    /// The uncompressed contents of a file in the patch directory.
+    Loose(Vec<u8>),
+}
+
 impl Archive {
+    #[inline]
+    fn new(inner: ArchiveInner) -> Self {
+        Self { inner, patch: None }
+    }
+
     /// Creates an archive from an open file in heap-allocated memory.
     ///
     /// See [`Archive::open_heap`] for further details.
     pub fn heap(file: fs::File) -> Result<Self, ArchiveError> {
-        HeapArchive::new(file).map(|a| Self(ArchiveInner::Heap(a)))
+        HeapArchive::new(file, false).map(|a| Self::new(ArchiveInner::Heap(a)))
     }
 
     /// Creates an archive on the heap from a pre-allocated buffer holding
@@ -71,7 +148,15 @@ impl Archive {
     ///
     /// See [`Archive::open_heap`] for further details.
     pub fn from_vec(buf: Vec<u8>) -> Result<Self, ArchiveError> {
-        HeapArchive::from_vec(buf, 0o666).map(|a| Self(ArchiveInner::Heap(a)))
+        HeapArchive::from_vec(buf, 0o666, false).map(|a| Self::new(ArchiveInner::Heap(a)))
+    }
+
+    /// Creates an archive on the heap from a buffer which may hold
+    /// only a part of the archive contents.
+    ///
+    /// See [`Archive::open_mmap_partial`] for further details.
+    pub fn from_vec_partial(buf: Vec<u8>) -> Result<Self, ArchiveError> {
+        HeapArchive::from_vec(buf, 0o666, true).map(|a| Self::new(ArchiveInner::Heap(a)))
     }
 
     /// Opens a file at the given `path` and operates on it from
@@ -85,14 +170,25 @@ impl Archive {
     /// This is the preferred option of working with relatively small
     /// files but it's always best to profile.
     pub fn open_heap<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
-        HeapArchive::open(path).map(|a| Self(ArchiveInner::Heap(a)))
+        let path = path.as_ref();
+        HeapArchive::open(path, false)
+            .map(|a| Self::new(ArchiveInner::Heap(a)))
+            .map_err(ArchiveError::at_path(path))
     }
 
     /// Creates an archive by mapping the open file into memory.
     ///
     /// See [`Archive::open_mmap`] for further details.
     pub fn mmap(file: fs::File) -> Result<Self, ArchiveError> {
-        MemoryMappedArchive::new(file).map(|a| Self(ArchiveInner::MemoryMapped(a)))
+        MemoryMappedArchive::new(file, false).map(|a| Self::new(ArchiveInner::MemoryMapped(a)))
+    }
+
+    /// Creates an archive by mapping the open file into memory,
+    /// tolerating missing file data at its end.
+    ///
+    /// See [`Archive::open_mmap_partial`] for further details.
+    pub fn mmap_partial(file: fs::File) -> Result<Self, ArchiveError> {
+        MemoryMappedArchive::new(file, true).map(|a| Self::new(ArchiveInner::MemoryMapped(a)))
     }
 
     /// Opens a file at the given `path` and operates on it from
@@ -107,12 +203,71 @@ impl Archive {
     /// This is the preferred option of working with relatively large
     /// files but it's always best to profile.
     pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
-        MemoryMappedArchive::open(path).map(|a| Self(ArchiveInner::MemoryMapped(a)))
+        let path = path.as_ref();
+        MemoryMappedArchive::open(path, false)
+            .map(|a| Self::new(ArchiveInner::MemoryMapped(a)))
+            .map_err(ArchiveError::at_path(path))
+    }
+
+    /// Opens an archive which may be incomplete from a memory mapping.
+    ///
+    /// Archives which are still being written may have a complete
+    /// journal while the data of later files is not there yet. These
+    /// files are marked with [`wad_types::File::is_unavailable`]
+    /// instead of failing to open the whole archive.
+    ///
+    /// The mapping only covers the data present at the time of
+    /// opening, even when the file grows later.
+    pub fn open_mmap_partial<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        let path = path.as_ref();
+        MemoryMappedArchive::open(path, true)
+            .map(|a| Self::new(ArchiveInner::MemoryMapped(a)))
+            .map_err(ArchiveError::at_path(path))
+    }
+
+    /// Sets a source to resolve unpatched files from.
+    ///
+    /// See [`Archive::patched_file`] for details.
+    pub fn with_patch_source(mut self, source: PatchSource) -> Self {
+        self.patch = Some(Box::new(source));
+        self
+    }
+
+    /// Gets the configured [`PatchSource`], if any.
+    #[inline]
+    pub fn patch_source(&self) -> Option<&PatchSource> {
+        self.patch.as_deref()
+    }
+
+    /// Looks up the file at `name` in the patch source.
+    ///
+    /// This is meant for files which are unpatched in this archive
+    /// and returns [`None`] when the patch source does not have the
+    /// file or is not configured at all. When the file is unpatched
+    /// in a patch archive too, its own patch source is consulted.
+    pub fn patched_file(&self, name: &str) -> Result<Option<PatchedFile<'_>>, ArchiveError> {
+        match self.patch_source() {
+            None => Ok(None),
+
+            Some(PatchSource::Archive(archive)) => match archive.file_raw(name) {
+                Some(file) if file.is_unpatched => archive.patched_file(name),
+                Some(file) if file.is_unavailable => Ok(None),
+                Some(file) => Ok(Some(PatchedFile::Archived(archive, file))),
+                None => Ok(None),
+            },
+
+            Some(PatchSource::Dir(dir)) => match fs::read(dir.join(name)) {
+                Ok(data) => Ok(Some(PatchedFile::Loose(data))),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            },
+        }
     }
 
     /// Returns the UNIX permissions of the archive file.
     ///
-    /// On other platforms, this value may be ignored.
+    /// On other platforms, this is `0o444` for read-only files and
+    /// `0o666` otherwise.
     #[inline]
     pub fn mode(&self) -> u32 {
         self.journal().mode
@@ -126,7 +281,7 @@ impl Archive {
 
     #[inline]
     pub(crate) fn journal(&self) -> &Journal {
-        match &self.0 {
+        match &self.inner {
             ArchiveInner::MemoryMapped(a) => &a.journal,
             ArchiveInner::Heap(a) => &a.journal,
         }
@@ -134,7 +289,7 @@ impl Archive {
 
     #[inline]
     pub(crate) fn raw_archive(&self) -> &[u8] {
-        match &self.0 {
+        match &self.inner {
             ArchiveInner::MemoryMapped(a) => &a.mapping,
             ArchiveInner::Heap(a) => &a.data,
         }
@@ -174,9 +329,39 @@ impl Archive {
         self.journal().find(name)
     }
 
+    /// Gets the absolute byte range of the file data in the archive.
+    ///
+    /// This allows reading the data from the archive file directly,
+    /// e.g. with a seek or an HTTP range request.
+    #[inline]
+    pub fn entry_span(&self, file: &wad_types::File) -> Range<u64> {
+        file.span()
+    }
+
+    /// Sums up the uncompressed sizes of all files accepted by
+    /// `filter`.
+    ///
+    /// This is the number of bytes extracting these files writes,
+    /// which callers can compare against the free disk space first.
+    pub fn total_uncompressed<F>(&self, mut filter: F) -> u64
+    where
+        F: FnMut(&str, &wad_types::File) -> bool,
+    {
+        self.files()
+            .iter()
+            .filter(|(name, file)| filter(name, file))
+            .map(|(_, file)| file.uncompressed_size as u64)
+            .sum()
+    }
+
+    /// Gets the number of files whose data is not in a partial archive.
+    pub fn unavailable(&self) -> usize {
+        self.files().values().filter(|f| f.is_unavailable).count()
+    }
+
     /// Extracts the raw file contents out of the archive.
     pub fn file_contents(&self, file: &wad_types::File) -> Option<&[u8]> {
-        if file.is_unpatched {
+        if file.is_unpatched || file.is_unavailable {
             return None;
         }
 
@@ -242,7 +427,12 @@ struct MemoryMappedArchive {
 }
 
 impl MemoryMappedArchive {
-    fn new(file: fs::File) -> Result<Self, ArchiveError> {
+    fn new(file: fs::File, partial: bool) -> Result<Self, ArchiveError> {
+        // Empty files cannot be mapped on all platforms.
+        if file.metadata()?.len() == 0 {
+            return Err(ArchiveError::Empty);
+        }
+
         let mut this = Self {
             // SAFETY: We own the file and keep it around until the mapping
             // is closed; see comments in `MemoryMappedArchive` above.
@@ -256,17 +446,21 @@ impl MemoryMappedArchive {
         };
 
         // Parse the archive and build the file journal.
+        check_header(&this.mapping)?;
         let mut archive = wad_types::Archive::parse(io::Cursor::new(&this.mapping))?;
+        if partial {
+            archive.mark_unavailable(&this.mapping);
+        }
         archive.verify_crcs(&this.mapping)?;
         this.journal.build_from(archive);
 
         Ok(this)
     }
 
-    fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+    fn open<P: AsRef<Path>>(path: P, partial: bool) -> Result<Self, ArchiveError> {
         // Attempt to open the file at the given path.
         let file = fs::File::open(path)?;
-        Self::new(file)
+        Self::new(file, partial)
     }
 }
 
@@ -279,33 +473,72 @@ struct HeapArchive {
 }
 
 impl HeapArchive {
-    fn new(mut file: fs::File) -> Result<Self, ArchiveError> {
+    fn new(mut file: fs::File, partial: bool) -> Result<Self, ArchiveError> {
         let mut buf = Vec::new();
         file.read_to_end(&mut buf)?;
 
-        Self::from_vec(buf, file_mode(&file))
+        Self::from_vec(buf, file_mode(&file), partial)
     }
 
-    fn from_vec(buf: Vec<u8>, mode: u32) -> Result<Self, ArchiveError> {
+    fn from_vec(buf: Vec<u8>, mode: u32, partial: bool) -> Result<Self, ArchiveError> {
+        if buf.is_empty() {
+            return Err(ArchiveError::Empty);
+        }
+
         let mut this = Self {
             journal: Journal::new(mode),
             data: buf.into_boxed_slice(),
         };
 
         // Parse the archive and build the file journal.
+        check_header(&this.data)?;
         let mut archive = wad_types::Archive::parse(io::Cursor::new(&this.data))?;
+        if partial {
+            archive.mark_unavailable(&this.data);
+        }
         archive.verify_crcs(&this.data)?;
         this.journal.build_from(archive);
 
         Ok(this)
     }
 
-    fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+    fn open<P: AsRef<Path>>(path: P, partial: bool) -> Result<Self, ArchiveError> {
         let file = fs::File::open(path)?;
-        Self::new(file)
+        Self::new(file, partial)
     }
 }
 
+// Validates the magic and version of an archive before parsing it,
+// so that other files are rejected with a meaningful error.
+fn check_header(data: &[u8]) -> Result<(), ArchiveError> {
+    let magic = &data[..data.len().min(MAGIC.len())];
+    if magic != MAGIC {
+        // A prefix of the magic is just a very short archive.
+        if !MAGIC.starts_with(magic) {
+            return Err(ArchiveError::BadMagic {
+                found: magic.to_vec(),
+            });
+        }
+        return Err(ArchiveError::TruncatedHeader(data.len()));
+    }
+
+    let version = match data.get(5..9) {
+        Some(v) => u32::from_le_bytes(v.try_into().unwrap()),
+        None => return Err(ArchiveError::TruncatedHeader(data.len())),
+    };
+    if !KNOWN_VERSIONS.contains(&version) {
+        return Err(ArchiveError::UnsupportedVersion(version));
+    }
+
+    // The magic, version and file count, plus the flags since v2.
+    let header_len = 13 + usize::from(version >= 2);
+    if data.len() < header_len {
+        return Err(ArchiveError::TruncatedHeader(data.len()));
+    }
+
+    Ok(())
+}
+
 fn file_mode(_f: &fs::File) -> u32 {
     match () {
         #[cfg(unix)]
@@ -315,6 +548,9 @@ fn file_mode(_f: &fs::File) -> u32 {
         }
 
         #[cfg(not(unix))]
-        () => 0,
+        () => match _f.metadata() {
+            Ok(m) if m.permissions().readonly() => 0o444,
+            _ => 0o666,
+        },
     }
 }
@@ -6,13 +6,12 @@ use std::{
 };
 
 use katsuba_utils::{
-    binrw,
-    libdeflater::CompressionError,
+    binrw, hash,
     thiserror::{self, Error},
 };
 use tempfile::tempfile_in;
 
-use crate::{crc, deflater::Deflater, types as wad_types};
+use crate::{deflater::Deflater, types as wad_types, Archive, Inflater};
 
 const ALWAYS_UNCOMPRESSED: &[&str] = &["mp3", "ogg"];
 
@@ -27,10 +26,6 @@ pub enum BuilderError {
     #[error("archive too large to represent")]
     TooLarge,
 
-    /// Compression of a file's contents failed.
-    #[error("failed to compress archive file: {0}")]
-    Zlib(#[from] CompressionError),
-
     /// The archive could not be serialized to the output file.
     #[error("failed to serialize archive: {0}")]
     Serialize(binrw::Error),
@@ -135,6 +130,9 @@ pub struct ArchiveBuilder {
     // The zlib deflater to handle file compression, one at a time.
     deflater: Deflater,
 
+    // The zlib inflater to compare files against a baseline archive.
+    inflater: Inflater,
+
     // The output archive file we are writing to.
     outfile: BufWriter<File>,
 
@@ -163,6 +161,7 @@ impl ArchiveBuilder {
         Ok(Self {
             state: BuilderState::new(version, flags),
             deflater: Deflater::new(),
+            inflater: Inflater::new(),
             outfile,
             blob_cache,
         })
@@ -182,8 +181,9 @@ impl ArchiveBuilder {
             uncompressed_size: checked_u32(contents.len())?,
             compressed_size: u32::MAX,
             compressed: false,
-            crc: crc::hash(contents),
+            crc: hash::crc32(contents),
             is_unpatched: false,
+            is_unavailable: false,
             name: name.as_ref().to_string_lossy().to_string(),
         };
 
@@ -217,14 +217,15 @@ impl ArchiveBuilder {
             return self.add_file(name, contents);
         }
 
-        let compressed = self.deflater.compress(contents)?;
+        let compressed = self.deflater.compress(contents);
         let record = wad_types::File {
             offset: self.state.next_file_offset,
             uncompressed_size: checked_u32(contents.len())?,
             compressed_size: checked_u32(compressed.len())?,
             compressed: true,
-            crc: crc::hash(compressed),
+            crc: hash::crc32(compressed),
             is_unpatched: false,
+            is_unavailable: false,
             name: path.to_string_lossy().to_string(),
         };
 
@@ -234,6 +235,60 @@ impl ArchiveBuilder {
         Ok(())
     }
 
+    /// Adds a file by copying its stored data from a `baseline`
+    /// archive, if the file there has the same `contents`.
+    ///
+    /// This skips recompressing files which did not change since
+    /// the baseline was built. Returns `false` without adding the
+    /// file when the baseline has no matching entry for `name`.
+    ///
+    /// The CRC of compressed entries covers the compressed data, so
+    /// these are inflated and compared with `contents` instead.
+    pub fn add_file_from_baseline(
+        &mut self,
+        name: impl AsRef<Path>,
+        contents: &[u8],
+        baseline: &Archive,
+    ) -> Result<bool, BuilderError> {
+        let name = name.as_ref().to_string_lossy();
+        let Some(file) = baseline.file_raw(&name) else {
+            return Ok(false);
+        };
+        if file.uncompressed_size as usize != contents.len() {
+            return Ok(false);
+        }
+        let Some(data) = baseline.file_contents(file) else {
+            return Ok(false);
+        };
+
+        let unchanged = if file.compressed {
+            self.inflater
+                .decompress(data, contents.len())
+                .is_ok_and(|inflated| inflated == contents)
+        } else {
+            hash::crc32(contents) == file.crc && data == contents
+        };
+        if !unchanged {
+            return Ok(false);
+        }
+
+        let record = wad_types::File {
+            offset: self.state.next_file_offset,
+            uncompressed_size: file.uncompressed_size,
+            compressed_size: file.compressed_size,
+            compressed: file.compressed,
+            crc: file.crc,
+            is_unpatched: false,
+            is_unavailable: false,
+            name: name.into_owned(),
+        };
+
+        self.state.intern_file(record, data)?;
+        self.blob_cache.write_all(data)?;
+
+        Ok(true)
+    }
+
     /// Finalizes the archive building and writes all data to the
     /// output file.
     ///
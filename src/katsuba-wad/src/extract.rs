@@ -0,0 +1,580 @@
+//! Extraction of whole archives into a directory.
+//!
+//! Reading and decompressing files happens on the calling thread
+//! while writing them is dispatched to an [`Executor`], which also
+//! decides over cancellation of the work.
+
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use katsuba_executor::{Buffer, Cancelled, Executor, FileBatcher, Task, TaskError, TaskKind};
+use katsuba_utils::{
+    fs::DirectoryTree,
+    thiserror::{self, Error},
+};
+
+use crate::{types::File, Archive, ArchiveError, Inflater, PatchedFile};
+
+mod journal;
+use journal::Journal;
+pub use journal::FILE_NAME as JOURNAL_FILE_NAME;
+
+mod strip;
+pub use strip::StripPrefix;
+
+/// The mode of extracted files when the archive's is not preserved.
+pub const DEFAULT_MODE: u32 = 0o666;
+
+// How many task errors are listed in the message of a
+// [`WriteFailures`] error.
+const REPORTED_FAILURES: usize = 5;
+
+// How many finished writes are collected before they are committed
+// to the resume journal.
+const JOURNAL_CHECKPOINT: usize = 256;
+
+/// Errors that may occur during extraction of an archive.
+#[derive(Debug, Error)]
+pub enum ExtractError {
+    /// Reading a file from the archive or its patch source failed.
+    #[error("{0}")]
+    Archive(#[from] ArchiveError),
+
+    /// The data of a file lies outside of the archive.
+    #[error("missing contents of '{0}' in archive")]
+    MissingContents(String),
+
+    /// Writing the extracted files failed.
+    #[error("failed to write extracted files: {0}")]
+    Io(#[from] io::Error),
+
+    /// Writing some of the extracted files failed.
+    #[error("{0}")]
+    Write(WriteFailures),
+
+    /// Reading or writing the resume journal failed.
+    #[error("failed to update resume journal '{}': {source}", path.display())]
+    Journal { path: PathBuf, source: io::Error },
+
+    /// A file does not start with any of the prefixes to strip.
+    #[error("'{0}' does not start with any of the prefixes to strip")]
+    PrefixMismatch(String),
+
+    /// Several files end up at the same path after stripping their
+    /// prefixes.
+    #[error("several files would be extracted to '{0}' after stripping prefixes")]
+    PathCollision(String),
+
+    /// The executor was cancelled before extraction finished.
+    #[error("{0}")]
+    Cancelled(#[from] Cancelled),
+}
+
+/// The files which [`extract`] failed to write.
+///
+/// Extraction carries on past failed writes, so this holds the
+/// errors of all failed tasks along with the number of files which
+/// were written anyway.
+#[derive(Debug)]
+pub struct WriteFailures {
+    /// The number of files which were written successfully.
+    pub written: usize,
+    /// The errors of all failed tasks, in the order they were dispatched.
+    pub errors: Vec<TaskError>,
+}
+
+impl WriteFailures {
+    /// Gets the number of files which were not written.
+    pub fn failed(&self) -> usize {
+        self.errors.iter().map(|e| e.unwritten).sum()
+    }
+}
+
+impl fmt::Display for WriteFailures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.failed();
+        let total = self.written + failed;
+        write!(f, "failed to write {failed} of {total} files")?;
+
+        for error in self.errors.iter().take(REPORTED_FAILURES) {
+            write!(f, "\n  {error}")?;
+        }
+        if self.errors.len() > REPORTED_FAILURES {
+            let more = self.errors.len() - REPORTED_FAILURES;
+            write!(f, "\n  ...and {more} more errors")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Configuration for [`extract`].
+#[derive(Clone, Debug, Default)]
+pub struct ExtractOptions {
+    /// The mode to create all files with.
+    ///
+    /// When [`None`], files inherit the mode of the archive.
+    pub mode: Option<u32>,
+
+    /// Files smaller than this many bytes are written in batches.
+    ///
+    /// See [`FileBatcher`] for details. A value of `0`, the default,
+    /// writes every file on its own.
+    pub batch_threshold: usize,
+
+    /// Whether to skip files completed by an interrupted run.
+    ///
+    /// Extracted files are recorded in a journal named
+    /// [`JOURNAL_FILE_NAME`] in the destination directory, which is
+    /// removed again once the extraction succeeds. Files on disk are
+    /// not checked again.
+    pub resume: bool,
+
+    /// Whether to keep directories which end up without files.
+    ///
+    /// These come from directory entries in the archive and from
+    /// files which were not extracted because they are unpatched
+    /// or unavailable. By default, such directories are removed
+    /// again once extraction succeeds.
+    pub keep_empty_dirs: bool,
+
+    /// Leading directories to remove from the path of every file.
+    ///
+    /// The resume journal still records files by their path in the
+    /// archive.
+    pub strip_prefix: StripPrefix,
+}
+
+/// The outcome of a successful [`extract`] call.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExtractReport {
+    /// The number of files extracted from the archive itself.
+    pub from_archive: usize,
+    /// The number of files extracted from the patch source.
+    pub from_patch: usize,
+    /// The number of unpatched files with no data anywhere.
+    pub skipped: usize,
+    /// The number of files skipped because a previous run already
+    /// extracted them.
+    pub resumed: usize,
+    /// The number of files whose data is missing from a partial
+    /// archive.
+    pub unavailable: usize,
+}
+
+/// Where the contents of an extracted file were read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The archive which holds the file.
+    Archive,
+    /// The patch source of the archive.
+    Patch,
+}
+
+impl Source {
+    /// Gets a short, human-readable name for the source.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Archive => "archive",
+            Self::Patch => "patch",
+        }
+    }
+}
+
+/// Observes the progress of [`extract`].
+///
+/// All methods do nothing by default, and `()` can be used when no
+/// progress needs to be tracked.
+pub trait Progress {
+    /// Called with the contents of every file before it is written
+    /// to `path`.
+    ///
+    /// `started` is when reading the file from `source` began.
+    fn extracted(
+        &mut self,
+        path: &Path,
+        file: &File,
+        source: Source,
+        started: Instant,
+        contents: &[u8],
+    ) {
+        let _ = (path, file, source, started, contents);
+    }
+
+    /// Called for every unpatched file which has no data in the
+    /// patch source and is thus not written to `path`.
+    fn skipped(&mut self, path: &Path) {
+        let _ = path;
+    }
+
+    /// Called for every file whose data is missing from a partial
+    /// archive and is thus not written to `path`.
+    fn unavailable(&mut self, path: &Path) {
+        let _ = path;
+    }
+}
+
+impl Progress for () {}
+
+impl<P: Progress + ?Sized> Progress for &mut P {
+    fn extracted(
+        &mut self,
+        path: &Path,
+        file: &File,
+        source: Source,
+        started: Instant,
+        contents: &[u8],
+    ) {
+        (**self).extracted(path, file, source, started, contents)
+    }
+
+    fn skipped(&mut self, path: &Path) {
+        (**self).skipped(path)
+    }
+
+    fn unavailable(&mut self, path: &Path) {
+        (**self).unavailable(path)
+    }
+}
+
+// Joins all pending tasks on the executor when dropped, to make sure
+// none of them hold onto references into the archive anymore once
+// extraction returns to the caller.
+struct JoinGuard<'a>(&'a Executor);
+
+impl Drop for JoinGuard<'_> {
+    fn drop(&mut self) {
+        self.0.join().for_each(drop);
+    }
+}
+
+fn read_file_contents<'a>(
+    ex: &Executor,
+    archive: &'a Archive,
+    inflater: &mut Inflater,
+    name: &str,
+    file: &File,
+) -> Result<Buffer<'a>, ExtractError> {
+    let contents = archive
+        .file_contents(file)
+        .ok_or_else(|| ExtractError::MissingContents(name.to_owned()))?;
+
+    match file.compressed {
+        true => {
+            let len = file.uncompressed_size as usize;
+            let buffer = ex.request_buffer(len, |buf| {
+                buf.resize(len, 0);
+                inflater
+                    .decompress_into(buf, contents)
+                    .map_err(ArchiveError::from)?;
+
+                Ok::<_, ArchiveError>(())
+            })?;
+
+            Ok(buffer)
+        }
+
+        false => Ok(Buffer::borrowed(contents)),
+    }
+}
+
+/// Reads the contents of the archive file `name` for extraction.
+///
+/// Unpatched files are resolved from the patch source of `archive`.
+/// Returns [`None`] when there is none or it doesn't have the file.
+pub fn fetch_file_contents<'a>(
+    ex: &Executor,
+    archive: &'a Archive,
+    inflater: &mut Inflater,
+    name: &str,
+    file: &File,
+) -> Result<Option<(Buffer<'a>, Source)>, ExtractError> {
+    if !file.is_unpatched {
+        let buffer = read_file_contents(ex, archive, inflater, name, file)?;
+        return Ok(Some((buffer, Source::Archive)));
+    }
+
+    // Unpatched files may be resolved from the patch source, if any.
+    let buffer = match archive.patched_file(name)? {
+        Some(PatchedFile::Archived(patch, file)) => {
+            read_file_contents(ex, patch, inflater, name, file)?
+        }
+        Some(PatchedFile::Loose(data)) => Buffer::owned(data),
+        None => return Ok(None),
+    };
+
+    Ok(Some((buffer, Source::Patch)))
+}
+
+/// Whether the archive entry `name` names a directory rather than a
+/// file.
+///
+/// Such entries end in a path separator and carry no data.
+pub fn is_directory_entry(name: &str) -> bool {
+    name.ends_with('/')
+}
+
+fn create_directory_tree(
+    ex: &Executor,
+    archive: &Archive,
+    dest: &Path,
+    opts: &ExtractOptions,
+    errors: &mut Vec<TaskError>,
+) -> Result<(), ExtractError> {
+    // Pre-compute the directory structure we need to create.
+    let mut tree = DirectoryTree::new();
+    for name in archive.files().keys() {
+        if opts.keep_empty_dirs || !is_directory_entry(name) {
+            tree.add(opts.strip_prefix.strip(name)?.as_ref());
+        }
+    }
+
+    // The destination itself is not part of the tree, and files may
+    // end up right in it, e.g. after stripping their prefixes.
+    fs::create_dir_all(dest)?;
+
+    // Create all the directories with minimal required syscalls.
+    for path in tree {
+        ex.cancellation_token().check()?;
+
+        let task = Task::create_dir(dest.join(path));
+        errors.extend(ex.dispatch(task).filter_map(Result::err));
+    }
+
+    // Join all pending operations here so we don't accidentally
+    // try to write into directories that don't exist yet.
+    errors.extend(ex.join().filter_map(Result::err));
+
+    // Directory creation may have been skipped after the last check.
+    ex.cancellation_token().check()?;
+
+    Ok(())
+}
+
+// Removes the directories created for files which were not written
+// to `dest`, as long as nothing else ended up in them.
+fn prune_empty_dirs(dest: &Path, skipped: &[PathBuf]) {
+    let dirs: BTreeSet<_> = skipped
+        .iter()
+        .flat_map(|path| path.ancestors().skip(1))
+        .filter(|dir| dir.starts_with(dest) && *dir != dest)
+        .collect();
+
+    // Descendants sort after their parents, so walking backwards
+    // empties directories before their parents are tried.
+    for dir in dirs.into_iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+// Follows the file writes on the executor, so that only finished
+// writes are ever committed to the resume journal.
+//
+// Tasks which are dropped due to cancellation report no result. As
+// tasks are started in the order they were dispatched in, results
+// always belong to the oldest tasks which did not report back yet.
+#[derive(Default)]
+struct Writes<'a> {
+    // Files held back by the batcher, by their destination.
+    held: HashMap<PathBuf, (&'a str, u32)>,
+    // The files of the tasks which did not report back yet.
+    outstanding: VecDeque<Vec<(&'a str, u32)>>,
+    errors: Vec<TaskError>,
+}
+
+impl<'a> Writes<'a> {
+    fn push(&mut self, path: &Path, name: &'a str, crc: u32) {
+        self.held.insert(path.to_owned(), (name, crc));
+    }
+
+    fn dispatch(
+        &mut self,
+        ex: &Executor,
+        tasks: impl Iterator<Item = Task>,
+        journal: &mut Option<Journal>,
+    ) {
+        for task in tasks {
+            let files = match &task.kind {
+                TaskKind::CreateFiles { files, .. } => files
+                    .iter()
+                    .filter_map(|(p, _)| self.held.remove(p))
+                    .collect(),
+                _ => self.held.remove(&task.path).into_iter().collect(),
+            };
+            self.outstanding.push_back(files);
+
+            for res in ex.dispatch(task) {
+                self.finish(res, journal);
+            }
+        }
+    }
+
+    fn join(&mut self, ex: &Executor, journal: &mut Option<Journal>) {
+        for res in ex.join() {
+            self.finish(res, journal);
+        }
+    }
+
+    fn finish(&mut self, res: Result<(), TaskError>, journal: &mut Option<Journal>) {
+        let files = self.outstanding.pop_front().unwrap_or_default();
+        match res {
+            Ok(()) => {
+                if let Some(journal) = journal {
+                    for (name, crc) in files {
+                        journal.record(name, crc);
+                    }
+                }
+            }
+
+            // Batches stop at their first error, so none of their
+            // files are recorded.
+            Err(e) => self.errors.push(e),
+        }
+    }
+}
+
+/// Extracts all files in `archive` into the directory at `dest`.
+///
+/// Missing directories are created as needed and existing files are
+/// overwritten. The file I/O is carried out on `ex`, which is joined
+/// before this function returns, even on error.
+///
+/// All paths are checked against [`ExtractOptions::strip_prefix`]
+/// before anything is written.
+///
+/// Failing to write a file does not stop the extraction. The errors
+/// of all failed writes are returned together as
+/// [`ExtractError::Write`] in the end.
+pub fn extract<P: Progress>(
+    ex: &Executor,
+    archive: &Archive,
+    dest: &Path,
+    opts: &ExtractOptions,
+    mut progress: P,
+) -> Result<ExtractReport, ExtractError> {
+    // First, create all the directories for the output files.
+    opts.strip_prefix.check(archive)?;
+    let mut errors = Vec::new();
+    create_directory_tree(ex, archive, dest, opts, &mut errors)?;
+
+    // When resuming, files which were committed to the journal by a
+    // previous run are trusted and not written again.
+    let journal_err = |source| ExtractError::Journal {
+        path: dest.join(JOURNAL_FILE_NAME),
+        source,
+    };
+    let mut journal = match opts.resume {
+        true => Some(Journal::open(dest).map_err(journal_err)?),
+        false => None,
+    };
+
+    // This guard ensures we can safely share references into `archive`
+    // with the pool without risking dangling once we return.
+    let _guard = JoinGuard(ex);
+    let mode = opts.mode.unwrap_or_else(|| archive.mode());
+
+    // Next, we do the extraction of data out of the archive on the
+    // current thread while simultaneously dispatching the file I/O
+    // operations to the executor.
+    //
+    // Small files are grouped into batches before being dispatched.
+    //
+    // On cancellation, we stop producing new tasks and join the ones
+    // that are still in flight. The files written until then are
+    // committed to the journal before bailing out.
+    let mut inflater = Inflater::new();
+    let mut batcher = FileBatcher::new(opts.batch_threshold);
+    let mut writes = Writes {
+        errors,
+        ..Default::default()
+    };
+    let mut report = ExtractReport::default();
+    let mut skipped = Vec::new();
+    for (name, file) in archive.files() {
+        if ex.cancellation_token().is_cancelled() {
+            break;
+        }
+
+        // Directory entries were taken care of with all the others.
+        if is_directory_entry(name) {
+            continue;
+        }
+
+        if journal.as_ref().is_some_and(|j| j.is_done(name, file.crc)) {
+            report.resumed += 1;
+            continue;
+        }
+
+        let path = dest.join(opts.strip_prefix.strip(name)?);
+        if file.is_unavailable {
+            progress.unavailable(&path);
+            report.unavailable += 1;
+            skipped.push(path);
+            continue;
+        }
+
+        let started = Instant::now();
+
+        let (buffer, source) = match fetch_file_contents(ex, archive, &mut inflater, name, file)? {
+            Some(res) => res,
+            None => {
+                progress.skipped(&path);
+                report.skipped += 1;
+                skipped.push(path);
+                continue;
+            }
+        };
+        // SAFETY: We can never end up with dangling references into
+        // `archive` because the guard joins all pending tasks on drop.
+        let buffer = unsafe { buffer.extend_lifetime() };
+        progress.extracted(&path, file, source, started, &buffer);
+
+        match source {
+            Source::Archive => report.from_archive += 1,
+            Source::Patch => report.from_patch += 1,
+        }
+
+        writes.push(&path, name, file.crc);
+        let task = batcher.push(path, buffer, mode);
+        writes.dispatch(ex, task.into_iter(), &mut journal);
+
+        // Finished writes can be committed at any time.
+        if let Some(journal) = &mut journal {
+            if journal.pending() >= JOURNAL_CHECKPOINT {
+                journal.commit().map_err(journal_err)?;
+            }
+        }
+    }
+
+    // Make sure we didn't drop any of the queued tasks.
+    writes.dispatch(ex, batcher.finish(), &mut journal);
+    writes.join(ex, &mut journal);
+    if let Some(journal) = &mut journal {
+        journal.commit().map_err(journal_err)?;
+    }
+    ex.cancellation_token().check()?;
+
+    let errors = writes.errors;
+    if !errors.is_empty() {
+        let failed: usize = errors.iter().map(|e| e.unwritten).sum();
+        let written = report.from_archive + report.from_patch;
+        return Err(ExtractError::Write(WriteFailures {
+            written: written.saturating_sub(failed),
+            errors,
+        }));
+    }
+
+    if let Some(journal) = journal {
+        journal.finish().map_err(journal_err)?;
+    }
+
+    if !opts.keep_empty_dirs {
+        prune_empty_dirs(dest, &skipped);
+    }
+
+    Ok(report)
+}
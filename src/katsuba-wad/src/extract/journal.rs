@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The name of the journal file in an archive's output directory.
+pub const FILE_NAME: &str = ".katsuba-resume.jsonl";
+
+#[derive(Deserialize, Serialize)]
+struct Record {
+    path: String,
+    crc: u32,
+}
+
+/// An append-only log of the files extracted from an archive.
+///
+/// Every line is a standalone JSON record, so a crash while writing
+/// one leaves at most the last line torn. Such lines are ignored on
+/// the next run, along with the file they describe.
+pub struct Journal {
+    path: PathBuf,
+    done: HashMap<String, u32>,
+    pending: Vec<Record>,
+    file: BufWriter<File>,
+}
+
+impl Journal {
+    /// Opens the journal in `dir`, reading the records left behind
+    /// by a previous run.
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(FILE_NAME);
+        let contents = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let done = contents
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Record>(line).ok())
+            .map(|r| (r.path, r.crc))
+            .collect();
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let mut file = BufWriter::new(file);
+
+        // Start on a fresh line if the last record was torn.
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+
+        Ok(Self {
+            path,
+            done,
+            pending: Vec::new(),
+            file,
+        })
+    }
+
+    /// Whether a file with the given `crc` was already extracted.
+    pub fn is_done(&self, name: &str, crc: u32) -> bool {
+        self.done.get(name) == Some(&crc)
+    }
+
+    /// Marks a file as extracted once its write is committed.
+    pub fn record(&mut self, name: &str, crc: u32) {
+        self.pending.push(Record {
+            path: name.to_owned(),
+            crc,
+        });
+    }
+
+    /// The number of files recorded since the last commit.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Appends all recorded files to the journal.
+    ///
+    /// This must only be called after the writes of the recorded
+    /// files have finished.
+    pub fn commit(&mut self) -> io::Result<()> {
+        for record in self.pending.drain(..) {
+            serde_json::to_writer(&mut self.file, &record)?;
+            self.file.write_all(b"\n")?;
+        }
+
+        self.file.flush()?;
+        self.file.get_ref().sync_data()
+    }
+
+    /// Removes the journal after a successful extraction.
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.file);
+        fs::remove_file(&self.path)
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+use super::{is_directory_entry, ExtractError};
+use crate::Archive;
+
+/// Leading directories to remove from the paths of archive files
+/// before they are extracted.
+///
+/// Prefixes match whole path components, so `GameData` strips
+/// `GameData/Root.xml` but not `GameDataOld/Root.xml`. When several
+/// prefixes match a file, the longest one is removed.
+#[derive(Clone, Debug, Default)]
+pub struct StripPrefix {
+    prefixes: Vec<String>,
+    lenient: bool,
+}
+
+impl StripPrefix {
+    /// Creates a set of prefixes to strip.
+    ///
+    /// Files which start with none of `prefixes` are an error,
+    /// unless `lenient` is set, in which case they keep their path.
+    pub fn new<I, S>(prefixes: I, lenient: bool) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut prefixes: Vec<_> = prefixes
+            .into_iter()
+            .map(|p| p.as_ref().trim_matches('/').to_owned())
+            .filter(|p| !p.is_empty())
+            .collect();
+        prefixes.sort_by_key(|p| std::cmp::Reverse(p.len()));
+
+        Self { prefixes, lenient }
+    }
+
+    /// Whether there are no prefixes to strip.
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    /// Gets the path of the archive file `name` with its prefix
+    /// removed.
+    ///
+    /// A directory entry which names a prefix itself becomes empty.
+    pub fn strip<'a>(&self, name: &'a str) -> Result<&'a str, ExtractError> {
+        if self.is_empty() {
+            return Ok(name);
+        }
+
+        let stripped = self.prefixes.iter().find_map(|prefix| {
+            name.strip_prefix(prefix.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+        });
+
+        match stripped {
+            Some(rest) => Ok(rest),
+            None if self.lenient => Ok(name),
+            None => Err(ExtractError::PrefixMismatch(name.to_owned())),
+        }
+    }
+
+    /// Checks that the paths of all files in `archive` can be
+    /// stripped and that no two files end up at the same path.
+    ///
+    /// Directory entries may be merged, so they are not checked for
+    /// collisions.
+    pub fn check(&self, archive: &Archive) -> Result<(), ExtractError> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let mut seen = HashSet::with_capacity(archive.len());
+        for name in archive.files().keys() {
+            let path = self.strip(name)?;
+            if !is_directory_entry(path) && !path.is_empty() && !seen.insert(path) {
+                return Err(ExtractError::PathCollision(path.to_owned()));
+            }
+        }
+
+        Ok(())
+    }
+}
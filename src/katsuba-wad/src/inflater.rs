@@ -1,4 +1,4 @@
-use katsuba_utils::libdeflater::{DecompressionError, Decompressor};
+use katsuba_utils::compress::{self, Error};
 
 /// A zlib inflater for decompressing archive files.
 ///
@@ -9,23 +9,20 @@ use katsuba_utils::libdeflater::{DecompressionError, Decompressor};
 /// This however comes at the caveat that only one decompressed
 /// file can be borrowed from the archive at a time.
 pub struct Inflater {
-    raw: Decompressor,
+    raw: compress::Inflater,
     scratch: Vec<u8>,
 }
 
 impl Inflater {
     /// Creates a new inflater for zlib decompression.
     pub fn new() -> Self {
-        Self {
-            raw: Decompressor::new(),
-            scratch: Vec::new(),
-        }
+        Self::new_with(Vec::new())
     }
 
     /// Creates a new inflater from a pre-allocated memory buffer.
     pub fn new_with(buf: Vec<u8>) -> Self {
         Self {
-            raw: Decompressor::new(),
+            raw: compress::Inflater::new(),
             scratch: buf,
         }
     }
@@ -42,12 +39,8 @@ impl Inflater {
         &mut self,
         out: &'a mut [u8],
         data: &[u8],
-    ) -> Result<&'a [u8], DecompressionError> {
-        let written = self.raw.zlib_decompress(data, out)?;
-        if written != out.len() {
-            return Err(DecompressionError::BadData);
-        }
-
+    ) -> Result<&'a [u8], Error> {
+        self.raw.zlib_into(data, out)?;
         Ok(out)
     }
 
@@ -56,18 +49,8 @@ impl Inflater {
     ///
     /// `size_hint` must be the size of inflated output, otherwise
     /// this method will error.
-    pub fn decompress(
-        &mut self,
-        data: &[u8],
-        size_hint: usize,
-    ) -> Result<&[u8], DecompressionError> {
-        self.scratch.resize(size_hint, 0);
-
-        let written = self.raw.zlib_decompress(data, &mut self.scratch)?;
-        if written != size_hint {
-            return Err(DecompressionError::BadData);
-        }
-
+    pub fn decompress(&mut self, data: &[u8], size_hint: usize) -> Result<&[u8], Error> {
+        self.raw.zlib_vec(data, size_hint, &mut self.scratch)?;
         Ok(&self.scratch)
     }
 }
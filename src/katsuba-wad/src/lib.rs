@@ -18,11 +18,12 @@ mod builder;
 #[cfg(feature = "builder")]
 pub use builder::*;
 
-pub mod crc;
-
 #[cfg(feature = "builder")]
 pub mod deflater;
 
+#[cfg(feature = "extract")]
+pub mod extract;
+
 pub mod glob;
 
 mod inflater;
@@ -1,5 +1,7 @@
 //! Common types and structures in the KIWAD format.
 
+use std::ops::Range;
+
 use katsuba_utils::{
     binrw::{
         self, binrw,
@@ -7,11 +9,10 @@ use katsuba_utils::{
         BinReaderExt, BinResult, BinWriterExt,
     },
     binrw_ext::{read_prefixed_string, write_prefixed_string},
+    hash,
     thiserror::{self, Error},
 };
 
-use crate::crc;
-
 pub(crate) fn is_unpatched_file(data: &[u8]) -> bool {
     // SAFETY: Transmuting bytes to larger integer types is legal.
     let (prefix, aligned, suffix) = unsafe { data.align_to::<u128>() };
@@ -24,7 +25,7 @@ pub(crate) fn is_unpatched_file(data: &[u8]) -> bool {
         && suffix.iter().all(|&x| x == 0)
 }
 
-/// Error type produced by [`Archive::verify_crcs`].
+/// A file whose data does not match its journal CRC.
 #[derive(Clone, Copy, Debug, PartialEq, Error)]
 #[error("CRC mismatch -- expected {expected}, got {actual}")]
 pub struct CrcMismatch {
@@ -34,6 +35,18 @@ pub struct CrcMismatch {
     pub actual: u32,
 }
 
+/// Error type produced by [`Archive::verify_crcs`].
+#[derive(Clone, Copy, Debug, PartialEq, Error)]
+pub enum VerifyError {
+    /// The CRC of a file did not match its data.
+    #[error("{0}")]
+    Crc(#[from] CrcMismatch),
+
+    /// A journal entry refers to data outside of the archive.
+    #[error("file data at {offset:#x} exceeds archive bounds")]
+    OutOfBounds { offset: u32 },
+}
+
 /// The header of a KIWAD archive.
 #[binrw]
 #[derive(Clone, Copy, Debug)]
@@ -80,6 +93,14 @@ pub struct File {
     #[brw(ignore)]
     pub is_unpatched: bool,
 
+    /// Whether the data of this file extends past the end of a
+    /// partial archive.
+    ///
+    /// This happens for archives which are still being written,
+    /// where the journal is complete before all the data is.
+    #[brw(ignore)]
+    pub is_unavailable: bool,
+
     #[br(temp)]
     #[bw(calc(name.len() as u32 + 1))]
     name_len: u32,
@@ -110,14 +131,19 @@ impl File {
         }
     }
 
+    /// Gets the byte range of the file data in the archive file.
+    #[inline]
+    pub const fn span(&self) -> Range<u64> {
+        let offset = self.offset as u64;
+        offset..offset + self.size() as u64
+    }
+
     /// Extracts this file from the given raw archive bytes.
     ///
     /// When the archive is malformed, this returns [`None`].
     pub fn extract<'wad>(&self, raw_archive: &'wad [u8]) -> Option<&'wad [u8]> {
-        let offset = self.offset as usize;
-        let size = self.size();
-
-        raw_archive.get(offset..offset + size)
+        let Range { start, end } = self.span();
+        raw_archive.get(start as usize..end as usize)
     }
 }
 
@@ -147,25 +173,42 @@ impl Archive {
 
     /// Parses the archive from the given [`Read`]er.
     pub fn parse<R: Read + Seek>(mut reader: R) -> BinResult<Self> {
-        reader.read_le().map_err(Into::into)
+        reader.read_le()
     }
 
     /// Writes the archive data to the given [`Write`]r.
     pub fn write<W: Write + Seek>(&self, mut writer: W) -> BinResult<()> {
-        writer.write_le(self).map_err(Into::into)
+        writer.write_le(self)
+    }
+
+    /// Marks every file whose data extends past the end of the raw
+    /// archive bytes as unavailable.
+    ///
+    /// Returns the number of such files.
+    pub fn mark_unavailable(&mut self, raw_archive: &[u8]) -> usize {
+        let len = raw_archive.len() as u64;
+        self.files
+            .iter_mut()
+            .filter(|f| f.span().end > len)
+            .map(|f| f.is_unavailable = true)
+            .count()
     }
 
     /// Verifies the CRCs of every file in the archive given the
     /// raw bytes of the archive file.
     ///
-    /// # Panics
-    ///
-    /// Panics when the KIWAD archive encodes file journal entries
-    /// with no matching data.
-    pub fn verify_crcs(&mut self, raw_archive: &[u8]) -> Result<(), CrcMismatch> {
+    /// Journal entries with no matching data are rejected, unless
+    /// they were marked unavailable before.
+    pub fn verify_crcs(&mut self, raw_archive: &[u8]) -> Result<(), VerifyError> {
         self.files.iter_mut().try_for_each(|f| {
-            let data = f.extract(raw_archive).unwrap();
-            let hash = crc::hash(data);
+            if f.is_unavailable {
+                return Ok(());
+            }
+
+            let data = f
+                .extract(raw_archive)
+                .ok_or(VerifyError::OutOfBounds { offset: f.offset })?;
+            let hash = hash::crc32(data);
 
             if hash == f.crc {
                 Ok(())
@@ -180,7 +223,8 @@ impl Archive {
                 Err(CrcMismatch {
                     expected: f.crc,
                     actual: hash,
-                })
+                }
+                .into())
             }
         })
     }
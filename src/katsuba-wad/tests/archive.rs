@@ -1,4 +1,5 @@
-use katsuba_wad::{Archive, ArchiveError, Inflater};
+use katsuba_utils::hash;
+use katsuba_wad::{types::VerifyError, Archive, ArchiveError, Inflater, PatchSource, PatchedFile};
 
 #[test]
 fn open_mmap() -> Result<(), ArchiveError> {
@@ -83,3 +84,225 @@ fn inflate_twice() -> Result<(), ArchiveError> {
 
     Ok(())
 }
+
+#[test]
+fn journal_crcs() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+
+    for file in archive.files().values() {
+        let contents = archive.file_contents(file).unwrap();
+        assert_eq!(hash::crc32(contents), file.crc);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn out_of_bounds_journal() {
+    let mut data = b"KIWAD".to_vec();
+    data.extend(1_u32.to_le_bytes()); // version
+    data.extend(1_u32.to_le_bytes()); // file count
+
+    data.extend(0x1000_u32.to_le_bytes()); // offset
+    data.extend(16_u32.to_le_bytes()); // uncompressed size
+    data.extend(16_u32.to_le_bytes()); // compressed size
+    data.push(0); // compressed
+    data.extend(0_u32.to_le_bytes()); // crc
+    data.extend(2_u32.to_le_bytes()); // name length
+    data.extend(b"a\0");
+
+    assert!(matches!(
+        Archive::from_vec(data),
+        Err(ArchiveError::Verify(VerifyError::OutOfBounds {
+            offset: 0x1000
+        }))
+    ));
+}
+
+#[test]
+fn bogus_name_length() {
+    let mut data = b"KIWAD".to_vec();
+    data.extend(1_u32.to_le_bytes());
+    data.extend(1_u32.to_le_bytes());
+    data.extend([0; 17]);
+    data.extend(u32::MAX.to_le_bytes());
+
+    // Must fail cleanly instead of reserving 4 GiB for the name.
+    assert!(matches!(
+        Archive::from_vec(data),
+        Err(ArchiveError::Parse(_))
+    ));
+}
+
+#[test]
+fn empty_archive() {
+    assert!(matches!(
+        Archive::from_vec(Vec::new()),
+        Err(ArchiveError::Empty)
+    ));
+}
+
+#[test]
+fn entry_span() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let raw = std::fs::read("tests/data/Test.wad")?;
+
+    for file in archive.files().values() {
+        let span = archive.entry_span(file);
+        assert_eq!(span.end - span.start, file.size() as u64);
+        assert_eq!(
+            archive.file_contents(file),
+            Some(&raw[span.start as usize..span.end as usize])
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn patch_source() -> Result<(), ArchiveError> {
+    // Zero out the data of one file to make it unpatched.
+    let mut raw = std::fs::read("tests/data/Test.wad")?;
+    let span = {
+        let archive = Archive::from_vec(raw.clone())?;
+        archive.entry_span(archive.file_raw("uncompressed.mp3").unwrap())
+    };
+    raw[span.start as usize..span.end as usize].fill(0);
+
+    let archive = Archive::from_vec(raw)?;
+    let file = archive.file_raw("uncompressed.mp3").unwrap();
+    assert!(file.is_unpatched);
+    assert!(archive.file_contents(file).is_none());
+    assert!(archive.patched_file("uncompressed.mp3")?.is_none());
+
+    let archive = archive.with_patch_source(PatchSource::Archive(Archive::open_heap(
+        "tests/data/Test.wad",
+    )?));
+    match archive.patched_file("uncompressed.mp3")? {
+        Some(PatchedFile::Archived(patch, file)) => {
+            assert_eq!(patch.file_contents(file).unwrap(), b"uncompressed data\n");
+        }
+        _ => panic!("expected file from patch archive"),
+    }
+    assert!(archive.patched_file("missing.txt")?.is_none());
+
+    // Missing files in patch directories are not an error.
+    let archive = archive.with_patch_source(PatchSource::Dir("tests/data".into()));
+    assert!(matches!(
+        archive.patched_file("Test.wad")?,
+        Some(PatchedFile::Loose(..))
+    ));
+    assert!(archive.patched_file("uncompressed.mp3")?.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn not_a_wad() {
+    // The start of a JFIF image.
+    let jpeg = b"\xff\xd8\xff\xe0\x00\x10JFIF\x00".to_vec();
+
+    match Archive::from_vec(jpeg) {
+        Err(ArchiveError::BadMagic { found }) => assert_eq!(found, b"\xff\xd8\xff\xe0\x00"),
+        _ => panic!("expected bad magic"),
+    }
+}
+
+#[test]
+fn unsupported_version() {
+    let mut data = b"KIWAD".to_vec();
+    data.extend(7_u32.to_le_bytes());
+    data.extend(0_u32.to_le_bytes());
+
+    let err = Archive::from_vec(data).err().unwrap();
+    assert!(matches!(err, ArchiveError::UnsupportedVersion(7)));
+    assert_eq!(err.to_string(), "unsupported WAD version 7");
+}
+
+#[test]
+fn truncated_header() {
+    for data in [
+        &b"KIW"[..],
+        b"KIWAD\x02\x00",
+        b"KIWAD\x02\x00\x00\x00\x01\x00\x00\x00",
+    ] {
+        assert!(matches!(
+            Archive::from_vec(data.to_vec()),
+            Err(ArchiveError::TruncatedHeader(n)) if n == data.len()
+        ));
+    }
+}
+
+#[test]
+fn errors_include_path() -> std::io::Result<()> {
+    let dir = tempfile::tempdir()?;
+
+    let jpeg = dir.path().join("image.jpg");
+    std::fs::write(&jpeg, b"\xff\xd8\xff\xe0\x00\x10JFIF\x00")?;
+    let empty = dir.path().join("empty.wad");
+    std::fs::write(&empty, b"")?;
+
+    for path in [&jpeg, &empty] {
+        let heap = Archive::open_heap(path).err().unwrap();
+        let mmap = Archive::open_mmap(path).err().unwrap();
+
+        for err in [heap, mmap] {
+            assert!(err.to_string().contains(&*path.to_string_lossy()));
+            assert!(matches!(
+                err.inner(),
+                ArchiveError::BadMagic { .. } | ArchiveError::Empty
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn partial_archive() -> Result<(), ArchiveError> {
+    let mut raw = std::fs::read("tests/data/Test.wad")?;
+    let archive = Archive::from_vec(raw.clone())?;
+
+    // Cut off the archive in the middle of the last file's data.
+    let (last, file) = archive
+        .files()
+        .iter()
+        .max_by_key(|(_, f)| f.span().end)
+        .unwrap();
+    raw.truncate(file.span().start as usize + 1);
+
+    assert!(matches!(
+        Archive::from_vec(raw.clone()),
+        Err(ArchiveError::Verify(VerifyError::OutOfBounds { .. }))
+    ));
+
+    let partial = Archive::from_vec_partial(raw)?;
+    assert_eq!(partial.len(), archive.len());
+    assert_eq!(partial.unavailable(), 1);
+
+    let file = partial.file_raw(last).unwrap();
+    assert!(file.is_unavailable);
+    assert!(partial.file_contents(file).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn total_uncompressed() -> Result<(), ArchiveError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+
+    let all: u64 = archive
+        .files()
+        .values()
+        .map(|f| f.uncompressed_size as u64)
+        .sum();
+    assert_eq!(archive.total_uncompressed(|_, _| true), all);
+
+    assert_eq!(
+        archive.total_uncompressed(|name, _| name == "uncompressed.mp3"),
+        b"uncompressed data\n".len() as u64
+    );
+    assert_eq!(archive.total_uncompressed(|_, _| false), 0);
+
+    Ok(())
+}
@@ -27,3 +27,49 @@ fn build_and_extract() {
     assert!(!b.compressed);
     assert_eq!(archive.file_contents(b), Some(&b"it does!"[..]));
 }
+
+#[test]
+fn reuse_baseline_files() {
+    let baseline = NamedTempFile::new().unwrap();
+    let (file, path) = baseline.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder
+        .add_file_compressed("same.txt", b"unchanged contents")
+        .unwrap();
+    builder
+        .add_file_compressed("changed.txt", b"old contents")
+        .unwrap();
+    builder.add_file("raw.txt", b"stored as is").unwrap();
+    builder.finish().unwrap();
+    let baseline = Archive::heap(file).unwrap();
+
+    let temp = NamedTempFile::new().unwrap();
+    let (file, path) = temp.into_parts();
+
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    assert!(builder
+        .add_file_from_baseline("same.txt", b"unchanged contents", &baseline)
+        .unwrap());
+    assert!(builder
+        .add_file_from_baseline("raw.txt", b"stored as is", &baseline)
+        .unwrap());
+    assert!(!builder
+        .add_file_from_baseline("changed.txt", b"new contents", &baseline)
+        .unwrap());
+    assert!(!builder
+        .add_file_from_baseline("new.txt", b"not in baseline", &baseline)
+        .unwrap());
+    builder.finish().unwrap();
+
+    let archive = Archive::heap(file).unwrap();
+    assert_eq!(archive.len(), 2);
+    for name in ["same.txt", "raw.txt"] {
+        let old = baseline.file_raw(name).unwrap();
+        let new = archive.file_raw(name).unwrap();
+
+        assert_eq!(new.compressed, old.compressed);
+        assert_eq!(new.crc, old.crc);
+        assert_eq!(archive.file_contents(new), baseline.file_contents(old));
+    }
+}
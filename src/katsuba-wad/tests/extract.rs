@@ -0,0 +1,226 @@
+#![cfg(feature = "extract")]
+
+use std::{fs, path::Path, time::Instant};
+
+use katsuba_executor::{CancellationToken, Executor};
+use katsuba_wad::{
+    extract::{
+        self, ExtractError, ExtractOptions, ExtractReport, Progress, Source, StripPrefix,
+        JOURNAL_FILE_NAME,
+    },
+    types::File,
+    Archive, ArchiveBuilder,
+};
+
+#[test]
+fn extract_all() -> Result<(), ExtractError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let dest = tempfile::tempdir()?;
+
+    let ex = Executor::current();
+    let report = extract::extract(&ex, &archive, dest.path(), &ExtractOptions::default(), ())?;
+    assert_eq!(report.from_archive, archive.len());
+
+    assert_eq!(
+        fs::read(dest.path().join("uncompressed.mp3"))?,
+        b"uncompressed data\n"
+    );
+    assert!(dest.path().join("subdir/subdir_text1.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn extract_resume() -> Result<(), ExtractError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let dest = tempfile::tempdir()?;
+
+    let crc = archive.file_raw("uncompressed.mp3").unwrap().crc;
+    fs::write(
+        dest.path().join(JOURNAL_FILE_NAME),
+        format!("{{\"path\":\"uncompressed.mp3\",\"crc\":{crc}}}\n"),
+    )?;
+
+    let opts = ExtractOptions {
+        resume: true,
+        ..Default::default()
+    };
+    let report = extract::extract(&Executor::current(), &archive, dest.path(), &opts, ())?;
+
+    assert_eq!(
+        report,
+        ExtractReport {
+            from_archive: archive.len() - 1,
+            resumed: 1,
+            ..Default::default()
+        }
+    );
+    assert!(!dest.path().join("uncompressed.mp3").exists());
+    assert!(!dest.path().join(JOURNAL_FILE_NAME).exists());
+
+    Ok(())
+}
+
+// Cancels extraction when the given number of files was read.
+struct CancelAfter(usize, CancellationToken);
+
+impl Progress for CancelAfter {
+    fn extracted(&mut self, _: &Path, _: &File, _: Source, _: Instant, _: &[u8]) {
+        self.0 -= 1;
+        if self.0 == 0 {
+            self.1.cancel();
+        }
+    }
+}
+
+#[test]
+fn extract_resume_after_cancel() -> Result<(), ExtractError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let dest = tempfile::tempdir()?;
+    let opts = ExtractOptions {
+        resume: true,
+        ..Default::default()
+    };
+
+    // The third file is read, but never written.
+    let token = CancellationToken::new();
+    let ex = Executor::current().with_cancellation(token.clone());
+    let err = extract::extract(&ex, &archive, dest.path(), &opts, CancelAfter(3, token));
+    assert!(matches!(err, Err(ExtractError::Cancelled(..))));
+
+    // The files written before are not extracted again.
+    let report = extract::extract(&Executor::current(), &archive, dest.path(), &opts, ())?;
+    assert_eq!(report.resumed, 2);
+    assert_eq!(report.from_archive, archive.len() - 2);
+    assert!(!dest.path().join(JOURNAL_FILE_NAME).exists());
+
+    Ok(())
+}
+
+// An archive with a directory entry and an unpatched file, neither
+// of which shares its parent directories with other files.
+fn sparse_archive(dir: &std::path::Path) -> Archive {
+    let path = dir.join("Sparse.wad");
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_file("kept.txt", b"kept").unwrap();
+    builder.add_file("empty/nested/", b"").unwrap();
+    builder
+        .add_file("gone/deep/file.bin", b"unpatched")
+        .unwrap();
+    builder.finish().unwrap();
+
+    let mut raw = fs::read(&path).unwrap();
+    let archive = Archive::from_vec(raw.clone()).unwrap();
+    let span = archive.entry_span(archive.file_raw("gone/deep/file.bin").unwrap());
+    raw[span.start as usize..span.end as usize].fill(0);
+
+    Archive::from_vec(raw).unwrap()
+}
+
+#[test]
+fn extract_empty_dirs() -> Result<(), ExtractError> {
+    let fixture = tempfile::tempdir()?;
+    let archive = sparse_archive(fixture.path());
+    let ex = Executor::current();
+
+    let dest = tempfile::tempdir()?;
+    let report = extract::extract(&ex, &archive, dest.path(), &ExtractOptions::default(), ())?;
+    assert_eq!(
+        report,
+        ExtractReport {
+            from_archive: 1,
+            skipped: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(fs::read(dest.path().join("kept.txt"))?, b"kept");
+    assert!(!dest.path().join("empty").exists());
+    assert!(!dest.path().join("gone").exists());
+
+    let opts = ExtractOptions {
+        keep_empty_dirs: true,
+        ..Default::default()
+    };
+    let dest = tempfile::tempdir()?;
+    extract::extract(&ex, &archive, dest.path(), &opts, ())?;
+    assert!(dest.path().join("empty/nested").is_dir());
+    assert!(dest.path().join("gone/deep").is_dir());
+    assert!(!dest.path().join("gone/deep/file.bin").exists());
+
+    Ok(())
+}
+
+#[test]
+fn extract_reports_failed_writes() -> Result<(), ExtractError> {
+    let archive = Archive::open_heap("tests/data/Test.wad")?;
+    let dest = tempfile::tempdir()?;
+
+    // A directory in place of a file makes writing it fail.
+    let blocked = dest.path().join("uncompressed.mp3");
+    fs::create_dir(&blocked)?;
+
+    let opts = ExtractOptions {
+        batch_threshold: 0,
+        ..Default::default()
+    };
+    let err = extract::extract(&Executor::current(), &archive, dest.path(), &opts, ());
+    let Err(ExtractError::Write(failures)) = err else {
+        panic!("expected write failures, got {err:?}");
+    };
+
+    assert_eq!(failures.failed(), 1);
+    assert_eq!(failures.written, archive.len() - 1);
+    assert_eq!(failures.errors[0].path, blocked);
+    assert!(failures.to_string().contains("uncompressed.mp3"));
+
+    // The other files are still extracted.
+    assert!(dest.path().join("subdir/subdir_text1.txt").exists());
+
+    Ok(())
+}
+
+#[test]
+fn extract_strip_prefix() -> Result<(), ExtractError> {
+    let fixture = tempfile::tempdir()?;
+    let path = fixture.path().join("Prefixed.wad");
+    let mut builder = ArchiveBuilder::new(2, 0, &path).unwrap();
+    builder.add_file("GameData/", b"").unwrap();
+    builder.add_file("GameData/Root.xml", b"root").unwrap();
+    builder.add_file("GameData/sub/a.txt", b"a").unwrap();
+    builder.add_file("GameDataOld/a.txt", b"old").unwrap();
+    builder.finish().unwrap();
+    let archive = Archive::open_heap(&path)?;
+    let ex = Executor::current();
+
+    // Files outside of the prefix fail before anything is written.
+    let opts = ExtractOptions {
+        strip_prefix: StripPrefix::new(["GameData/"], false),
+        ..Default::default()
+    };
+    let dest = tempfile::tempdir()?;
+    let err = extract::extract(&ex, &archive, dest.path(), &opts, ());
+    assert!(matches!(err, Err(ExtractError::PrefixMismatch(name)) if name == "GameDataOld/a.txt"));
+    assert!(!dest.path().join("sub").exists());
+
+    let opts = ExtractOptions {
+        strip_prefix: StripPrefix::new(["GameData"], true),
+        keep_empty_dirs: true,
+        ..Default::default()
+    };
+    extract::extract(&ex, &archive, dest.path(), &opts, ())?;
+    assert_eq!(fs::read(dest.path().join("Root.xml"))?, b"root");
+    assert_eq!(fs::read(dest.path().join("sub/a.txt"))?, b"a");
+    assert_eq!(fs::read(dest.path().join("GameDataOld/a.txt"))?, b"old");
+    assert!(!dest.path().join("GameData").exists());
+
+    // Stripping both prefixes puts two files at the same path.
+    let opts = ExtractOptions {
+        strip_prefix: StripPrefix::new(["GameData/sub", "GameDataOld"], true),
+        ..Default::default()
+    };
+    let dest = tempfile::tempdir()?;
+    let err = extract::extract(&ex, &archive, dest.path(), &opts, ());
+    assert!(matches!(err, Err(ExtractError::PathCollision(path)) if path == "a.txt"));
+
+    Ok(())
+}
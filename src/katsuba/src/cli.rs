@@ -1,14 +1,20 @@
-use clap::{Parser, Subcommand};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 
 use crate::cmd::*;
 
 mod args;
 
+pub mod capabilities;
+
 pub mod helpers;
 
 mod io;
 pub use io::*;
 
+pub mod manifest;
+
+pub mod metrics;
+
 mod processor;
 pub use processor::*;
 
@@ -19,34 +25,61 @@ pub use processor::*;
 pub struct Cli {
     /// The selected command.
     #[clap(subcommand)]
-    pub command: KatsubaCommand,
+    command: Option<KatsubaCommand>,
+
+    /// Prints a JSON report of the commands, options and features
+    /// supported by this build, for use by other tools.
+    #[clap(long, exclusive = true)]
+    pub capabilities: bool,
 
     #[clap(flatten)]
     pub verbosity: args::Verbosity,
+
+    #[clap(flatten)]
+    pub output: args::OutputMode,
+}
+
+impl Cli {
+    /// Gets the selected command, exiting with a usage error when
+    /// there is none.
+    pub fn into_command(self) -> KatsubaCommand {
+        match self.command {
+            Some(command) => command,
+            None => <Self as CommandFactory>::command()
+                .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+                .exit(),
+        }
+    }
 }
 
 /// The top-level commands supported by Katsuba.
 #[derive(Debug, Subcommand)]
 pub enum KatsubaCommand {
     Bcd(bcd::Bcd),
+    Convert(convert::Convert),
     Cs(cs::ClientSig),
     Hash(hash::Hash),
     Nav(nav::Nav),
     Op(op::ObjectProperty),
     Poi(poi::Poi),
+    Types(types::Types),
     Wad(wad::Wad),
+    Zone(zone::Zone),
 }
 
 impl Command for KatsubaCommand {
     fn handle(self) -> eyre::Result<()> {
         match self {
             Self::Bcd(bcd) => bcd.handle(),
+            Self::Convert(convert) => convert.handle(),
             Self::Cs(cs) => cs.handle(),
             Self::Hash(hash) => hash.handle(),
             Self::Nav(nav) => nav.handle(),
             Self::Op(op) => op.handle(),
             Self::Poi(poi) => poi.handle(),
+            Self::Types(types) => types.handle(),
             Self::Wad(wad) => wad.handle(),
+            Self::Zone(zone) => zone.handle(),
         }
     }
 }
@@ -1,5 +1,7 @@
 use clap::{ArgAction, Args};
 
+use crate::utils;
+
 /// Configures the verbosity of the builtin logger.
 #[derive(Clone, Copy, Debug, Args)]
 pub struct Verbosity {
@@ -26,3 +28,36 @@ impl Verbosity {
         }
     }
 }
+
+/// Configures how outputs are written.
+#[derive(Clone, Copy, Debug, Args)]
+pub struct OutputMode {
+    /// Makes outputs byte-identical between runs and machines.
+    ///
+    /// Object keys are sorted by name, JSON is always pretty-printed
+    /// with LF newlines, floats use their shortest round-trip form,
+    /// and manifests leave out durations and list their entries
+    /// sorted by input and output path.
+    #[clap(long, global = true)]
+    pub stable_output: bool,
+
+    /// When to page output to stdout through `$PAGER`.
+    ///
+    /// Without a pager, output to a terminal stops after the number
+    /// of lines given by `--max-lines`.
+    #[clap(long, global = true, value_enum, default_value_t = utils::PagerChoice::Auto)]
+    pub pager: utils::PagerChoice,
+
+    /// The number of lines written to a terminal when no pager is
+    /// used. A value of 0 writes all output.
+    #[clap(long, global = true, default_value_t = 1000)]
+    pub max_lines: usize,
+}
+
+impl OutputMode {
+    /// Applies the settings to all output for the rest of the process.
+    pub fn setup(self) {
+        utils::set_stable_output(self.stable_output);
+        utils::set_pager(self.pager, self.max_lines);
+    }
+}
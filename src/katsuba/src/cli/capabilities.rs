@@ -0,0 +1,150 @@
+//! A machine-readable report of what this build of Katsuba supports.
+//!
+//! Commands and their options are collected from the clap command
+//! tree, so the report cannot fall out of sync with the actual CLI.
+
+use std::io::{self, Write};
+
+use clap::{builder::PossibleValue, Arg, Command};
+use katsuba_object_property::serde::SerializerFlags;
+use katsuba_types::PropertyFlags;
+use serde_json::{json, Value};
+
+use crate::utils;
+
+// The formats written by every leaf command, keyed by the names of
+// the commands leading to it.
+//
+// Commands with a `--format` option report its values instead.
+const OUTPUT_FORMATS: &[(&[&str], &[&str])] = &[
+    (&["bcd", "de"], &["json"]),
+    (&["convert"], &["json"]),
+    (&["cs", "arg"], &["text"]),
+    (&["cs", "decrypt"], &["binary"]),
+    (&["hash", "string-id"], &["text"]),
+    (&["hash", "djb2"], &["text"]),
+    (&["hash", "property"], &["text"]),
+    (&["hash", "lookup"], &["text"]),
+    (&["nav", "de"], &["json"]),
+    (&["op", "ser"], &["binary"]),
+    (&["op", "guess"], &["text", "json"]),
+    (&["op", "index"], &["json"]),
+    (&["op", "grep"], &["text"]),
+    (&["op", "edit"], &["binary"]),
+    (&["op", "to-sqlite"], &["sqlite"]),
+    (&["poi", "de"], &["json"]),
+    (&["types", "enums"], &["csv", "tsv"]),
+    (&["types", "schema"], &["json"]),
+    (&["types", "validate"], &["text"]),
+    (&["wad", "pack"], &["wad"]),
+    (&["wad", "ls"], &["text", "json"]),
+    (&["wad", "unpack"], &["files", "tar"]),
+    (&["zone", "info"], &["json"]),
+];
+
+/// Builds the capability report for the given root command.
+pub fn report(mut root: Command) -> Value {
+    root.build();
+
+    let commands: Vec<_> = subcommands(&root)
+        .map(|cmd| describe_command(cmd, &mut Vec::new()))
+        .collect();
+
+    json!({
+        "name": root.get_name(),
+        "version": root.get_version(),
+        "options": describe_options(&root, true),
+        "commands": commands,
+        "serializer_flags": SerializerFlags::all().iter_names().map(|(n, _)| n).collect::<Vec<_>>(),
+        "property_flags": PropertyFlags::all().iter_names().map(|(n, _)| n).collect::<Vec<_>>(),
+        // Neither is available in the CLI. numpy interop is part of
+        // the Python bindings only.
+        "features": {
+            "numpy": false,
+            "gltf": false,
+        },
+    })
+}
+
+/// Prints the capability report for the given root command to stdout.
+pub fn print(root: Command) -> eyre::Result<()> {
+    let mut stdout = io::BufWriter::new(utils::stdout());
+    serde_json::to_writer_pretty(&mut stdout, &report(root))?;
+    writeln!(stdout)?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+// The subcommands of `cmd`, without the one generated for `help`.
+fn subcommands(cmd: &Command) -> impl Iterator<Item = &Command> {
+    cmd.get_subcommands().filter(|sub| sub.get_name() != "help")
+}
+
+fn describe_command<'a>(cmd: &'a Command, path: &mut Vec<&'a str>) -> Value {
+    path.push(cmd.get_name());
+
+    let mut desc = json!({
+        "name": cmd.get_name(),
+        "about": cmd.get_about().map(|s| s.to_string()),
+        "options": describe_options(cmd, false),
+    });
+
+    if cmd.has_subcommands() {
+        desc["subcommands"] = subcommands(cmd)
+            .map(|sub| describe_command(sub, path))
+            .collect();
+    } else {
+        desc["output_formats"] = output_formats(cmd, path);
+    }
+
+    path.pop();
+    desc
+}
+
+// Global options are only described on the root command, where
+// they are declared.
+fn describe_options(cmd: &Command, root: bool) -> Vec<Value> {
+    cmd.get_arguments()
+        .filter(|arg| !matches!(arg.get_id().as_str(), "help" | "version"))
+        .filter(|arg| root || !arg.is_global_set())
+        .map(describe_option)
+        .collect()
+}
+
+fn describe_option(arg: &Arg) -> Value {
+    let values: Vec<_> = arg
+        .get_possible_values()
+        .iter()
+        .map(PossibleValue::get_name)
+        .map(str::to_owned)
+        .collect();
+
+    json!({
+        "name": arg.get_id().as_str(),
+        "long": arg.get_long(),
+        "short": arg.get_short(),
+        "positional": arg.is_positional(),
+        "required": arg.is_required_set(),
+        "takes_value": arg.get_action().takes_values(),
+        "values": (!values.is_empty()).then_some(values),
+    })
+}
+
+fn output_formats(cmd: &Command, path: &[&str]) -> Value {
+    let format = cmd
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some("format"));
+    if let Some(arg) = format {
+        return arg
+            .get_possible_values()
+            .iter()
+            .map(|v| Value::from(v.get_name()))
+            .collect();
+    }
+
+    OUTPUT_FORMATS
+        .iter()
+        .find(|(p, _)| *p == path)
+        .map_or(Value::Null, |(_, formats)| json!(formats))
+}
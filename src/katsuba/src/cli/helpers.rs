@@ -13,15 +13,34 @@ pub fn write_as_json<T: serde::Serialize>(
     value: T,
     out: OutputSource,
 ) -> eyre::Result<()> {
+    let out = output_path(inpath, out)?;
+    utils::serialize_to_output_source(ex, out, &value)
+}
+
+/// Helper function to be used with [`Executor::write_with`] for writing
+/// raw bytes to an output source.
+pub fn write_bytes(
+    ex: &Executor,
+    inpath: Option<PathBuf>,
+    value: Vec<u8>,
+    out: OutputSource,
+) -> eyre::Result<()> {
+    let out = output_path(inpath, out)?;
+    utils::write_to_output_source(ex, out, value)
+}
+
+// Determines the file to write the output for `inpath` to, or
+// `None` for stdout.
+fn output_path(inpath: Option<PathBuf>, out: OutputSource) -> eyre::Result<Option<PathBuf>> {
     match (out, inpath) {
-        (OutputSource::Stdout, _) => utils::serialize_to_output_source(ex, None, &value),
-        (OutputSource::File(path), _) => utils::serialize_to_output_source(ex, Some(path), &value),
+        (OutputSource::Stdout, _) => Ok(None),
+        (OutputSource::File(path), _) => Ok(Some(path)),
         (OutputSource::Dir(mut out, suffix), Some(path)) => {
             // Create a file named after the input in the output directory.
             let infile = path.with_extension(suffix);
             out.push(infile.file_name().unwrap());
 
-            utils::serialize_to_output_source(ex, Some(out), &value)
+            Ok(Some(out))
         }
 
         (OutputSource::Dir(..), None) => Err(eyre::eyre!(
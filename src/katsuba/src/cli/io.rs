@@ -1,7 +1,9 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use clap::Args;
+use eyre::Context;
 use glob::glob;
+use walkdir::WalkDir;
 
 const HYPHEN: &str = "-";
 
@@ -12,8 +14,12 @@ pub enum InputSource {
     Stdin,
     /// The input will be read from a single file.
     File(PathBuf),
-    /// Inputs will be read from multiple files (glob).
-    Files(Vec<PathBuf>),
+    /// Inputs will be read from multiple files.
+    ///
+    /// Every file is paired with the subdirectory its output goes
+    /// into, relative to the output directory. This mirrors the
+    /// structure of input directories.
+    Files(Vec<(PathBuf, PathBuf)>),
 }
 
 /// An output source to [`InputsOutputs`] machinery.
@@ -43,12 +49,15 @@ pub struct InputsOutputs {
     /// given instead.
     ///
     /// Everything else will be recognized as a file path. UNIX glob
-    /// patterns are supported to specify many files.
+    /// patterns are supported to specify many files. Directories
+    /// are searched recursively for files.
     ///
-    /// Note however that when a glob pattern matches more than one
-    /// file, an explicit output directory for all the result files
-    /// needs to be specified with the output option.
-    input: String,
+    /// Note however that when more than one file is given, an explicit
+    /// output directory for all the result files needs to be specified
+    /// with the output option. It will mirror the structure of input
+    /// directories.
+    #[clap(required = true)]
+    input: Vec<String>,
 
     /// An optional output source for the processed outputs.
     ///
@@ -59,42 +68,70 @@ pub struct InputsOutputs {
     /// where output files will be created for each input file.
     #[clap(short, default_value = HYPHEN)]
     output: PathBuf,
+
+    #[clap(flatten)]
+    pub batch: BatchOptions,
+}
+
+/// Command options for accepting many inputs which produce no
+/// output files.
+#[derive(Debug, Args)]
+pub struct Inputs {
+    /// Specifies the input sources to process.
+    ///
+    /// When the value is "-", then input will be read from stdin.
+    ///
+    /// Everything else will be recognized as a file path. UNIX glob
+    /// patterns are supported to specify many files. Directories
+    /// are searched recursively for files.
+    #[clap(required = true)]
+    input: Vec<String>,
+}
+
+impl Inputs {
+    /// Evaluates the supplied arguments into an input source.
+    pub fn evaluate(&self) -> eyre::Result<InputSource> {
+        input_source(&self.input)
+    }
+}
+
+/// Options for processing and reporting batches of inputs.
+#[derive(Clone, Debug, Default, Args)]
+pub struct BatchOptions {
+    /// Writes a JSON manifest of all produced outputs to the path.
+    ///
+    /// Every entry lists the input and output paths, their sizes,
+    /// the SHA-256 hash of the output, and the processing time.
+    /// Outputs written to stdout are not listed.
+    #[clap(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// Writes a JSON report of timing metrics to the path.
+    ///
+    /// The report has the total throughput and percentiles of the
+    /// time it took to read every entry, e.g. every file of an
+    /// archive, and of the time the executor spent writing files,
+    /// creating directories and waiting in its queue.
+    #[clap(long)]
+    pub metrics: Option<PathBuf>,
+
+    /// Continues with the remaining inputs when one of them fails.
+    ///
+    /// Failures are logged and listed in the manifest, and the
+    /// command reports an error after processing all inputs.
+    #[clap(long)]
+    pub keep_going: bool,
 }
 
 impl InputsOutputs {
     /// Evaluates the supplied arguments into input and output sources.
     pub fn evaluate(self, suffix: &'static str) -> eyre::Result<(InputSource, OutputSource)> {
-        let inputs = self.input_source()?;
+        let inputs = input_source(&self.input)?;
         let outputs = self.output_source(suffix, &inputs)?;
 
         Ok((inputs, outputs))
     }
 
-    fn input_source(&self) -> eyre::Result<InputSource> {
-        // First, check for a hyphen which indicates read from stdin.
-        if self.input == HYPHEN {
-            return Ok(InputSource::Stdin);
-        }
-
-        // Next, evaluate whatever we have as a glob pattern. Even if
-        // it's just a path to a single file, it will work fine here.
-        let mut paths: Vec<PathBuf> = glob(&self.input)?.collect::<Result<_, _>>()?;
-
-        // Determine how many matches the glob produced. If it's just a
-        // single file, we consider it separately because it requires
-        // less clunky output handling.
-        if paths.is_empty() {
-            Err(eyre::eyre!(
-                "failed to find files matching '{}'",
-                self.input
-            ))
-        } else if paths.len() == 1 {
-            Ok(InputSource::File(paths.remove(0)))
-        } else {
-            Ok(InputSource::Files(paths))
-        }
-    }
-
     fn output_source(
         self,
         suffix: &'static str,
@@ -102,6 +139,10 @@ impl InputsOutputs {
     ) -> eyre::Result<OutputSource> {
         // First, check for a hyphen which indicates write to stdout.
         if self.output.as_os_str() == HYPHEN {
+            if let InputSource::Files(..) = input {
+                eyre::bail!("multiple inputs require an output directory; specify one with '-o'");
+            }
+
             return Ok(OutputSource::Stdout);
         }
 
@@ -122,3 +163,68 @@ impl InputsOutputs {
         Ok(out)
     }
 }
+
+// Evaluates input arguments into an input source, expanding globs
+// and directories.
+fn input_source(inputs: &[String]) -> eyre::Result<InputSource> {
+    // First, check for a hyphen which indicates read from stdin.
+    if let [input] = inputs {
+        if input == HYPHEN {
+            return Ok(InputSource::Stdin);
+        }
+    } else if inputs.iter().any(|i| i == HYPHEN) {
+        eyre::bail!("stdin cannot be combined with other inputs");
+    }
+
+    let mut files = Vec::new();
+    let mut saw_dir = false;
+    for input in inputs {
+        // Evaluate whatever we have as a glob pattern. Even if
+        // it's just a path to a single file, it will work fine here.
+        let paths: Vec<PathBuf> = glob(input)?.collect::<Result<_, _>>()?;
+        if paths.is_empty() {
+            eyre::bail!("failed to find files matching '{input}'");
+        }
+
+        for path in paths {
+            if path.is_dir() {
+                saw_dir = true;
+                collect_dir(&path, &mut files)?;
+            } else {
+                files.push((path, PathBuf::new()));
+            }
+        }
+    }
+
+    // If it's just a single file, we consider it separately because
+    // it requires less clunky output handling. Directories always
+    // produce a directory output, even when they hold one file.
+    match files.len() {
+        0 => Err(eyre::eyre!("no files found in the given directories")),
+        1 if !saw_dir => Ok(InputSource::File(files.remove(0).0)),
+        _ => Ok(InputSource::Files(files)),
+    }
+}
+
+// Recursively collects all files in `dir` along with their output
+// subdirectories.
+fn collect_dir(dir: &Path, files: &mut Vec<(PathBuf, PathBuf)>) -> eyre::Result<()> {
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.context("failed to query input directory")?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let path = entry.into_path();
+        let subdir = path
+            .strip_prefix(dir)
+            .ok()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        files.push((path, subdir));
+    }
+
+    Ok(())
+}
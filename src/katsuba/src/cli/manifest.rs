@@ -0,0 +1,186 @@
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::utils;
+
+/// The outcome of producing a manifest [`Entry`].
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    Failed,
+}
+
+/// A record of an output produced from an input.
+///
+/// Failed inputs have no output and carry their error instead.
+#[derive(Debug, Serialize)]
+pub struct Entry {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub bytes_in: u64,
+    pub bytes_out: Option<u64>,
+    pub sha256: Option<String>,
+    /// Left out for stable output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    pub status: Status,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// The input currently being processed.
+struct Input {
+    path: PathBuf,
+    bytes: u64,
+    started: Instant,
+}
+
+#[derive(Default)]
+struct Recorder {
+    input: Option<Input>,
+    entries: Vec<Entry>,
+}
+
+// Writers run on the main thread and report their outputs here
+// while the processor is recording. When it isn't, nothing gets
+// hashed.
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+fn with_recorder(f: impl FnOnce(&mut Recorder)) {
+    RECORDER.with_borrow_mut(|r| {
+        if let Some(r) = r {
+            f(r)
+        }
+    });
+}
+
+fn hex_digest(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .fold(String::with_capacity(64), |mut s, b| {
+            let _ = write!(s, "{b:02x}");
+            s
+        })
+}
+
+// Measures the time since `started`, unless output must not depend
+// on it.
+fn duration_ms(started: Instant) -> Option<u64> {
+    (!utils::stable_output()).then(|| started.elapsed().as_millis() as u64)
+}
+
+/// Starts recording outputs on the current thread.
+pub fn start() {
+    RECORDER.set(Some(Recorder::default()));
+}
+
+/// Stops recording and returns the entries collected so far.
+///
+/// Entries are in the order outputs were written, or sorted by
+/// input and output path for stable output.
+pub fn finish() -> Vec<Entry> {
+    let mut entries = RECORDER.take().map(|r| r.entries).unwrap_or_default();
+    if utils::stable_output() {
+        entries.sort_by(|a, b| (&a.input, &a.output).cmp(&(&b.input, &b.output)));
+    }
+
+    entries
+}
+
+/// Marks the start of processing for the input at `path`.
+///
+/// Its size and timing are attributed to outputs recorded with
+/// [`record`] until the next input begins. `started` is when reading
+/// the input began.
+pub fn begin_input(path: &Path, bytes: u64, started: Instant) {
+    with_recorder(|r| {
+        r.input = Some(Input {
+            path: path.to_owned(),
+            bytes,
+            started,
+        });
+    });
+}
+
+/// Records `data` as being written to `output` from the current input.
+pub fn record(output: &Path, data: &[u8]) {
+    with_recorder(|r| {
+        let Some(input) = &r.input else { return };
+
+        let entry = Entry {
+            input: input.path.clone(),
+            output: Some(output.to_owned()),
+            bytes_in: input.bytes,
+            bytes_out: Some(data.len() as u64),
+            sha256: Some(hex_digest(data)),
+            duration_ms: duration_ms(input.started),
+            status: Status::Ok,
+            source: None,
+            error: None,
+        };
+        r.entries.push(entry);
+    });
+}
+
+/// Records `data` as being written to `output` from a part of the
+/// current input, e.g. a file in an archive.
+///
+/// `bytes_in` is the size of that part and `started` is when its
+/// processing began. `source` optionally names where the part was
+/// read from, when inputs can have several.
+pub fn record_part(
+    output: &Path,
+    bytes_in: u64,
+    started: Instant,
+    source: Option<&'static str>,
+    data: &[u8],
+) {
+    with_recorder(|r| {
+        let Some(input) = &r.input else { return };
+
+        let entry = Entry {
+            input: input.path.clone(),
+            output: Some(output.to_owned()),
+            bytes_in,
+            bytes_out: Some(data.len() as u64),
+            sha256: Some(hex_digest(data)),
+            duration_ms: duration_ms(started),
+            status: Status::Ok,
+            source,
+            error: None,
+        };
+        r.entries.push(entry);
+    });
+}
+
+/// Records that processing the current input failed with `error`.
+pub fn fail(error: &eyre::Report) {
+    with_recorder(|r| {
+        let Some(input) = &r.input else { return };
+
+        let entry = Entry {
+            input: input.path.clone(),
+            output: None,
+            bytes_in: input.bytes,
+            bytes_out: None,
+            sha256: None,
+            duration_ms: duration_ms(input.started),
+            status: Status::Failed,
+            source: None,
+            error: Some(format!("{error:#}")),
+        };
+        r.entries.push(entry);
+    });
+}
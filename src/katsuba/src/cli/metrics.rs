@@ -0,0 +1,139 @@
+use std::{
+    cell::RefCell,
+    time::{Duration, Instant},
+};
+
+use katsuba_executor::TimingLog;
+use serde_json::{json, Value};
+
+// The entries of the input currently being processed.
+struct Input {
+    bytes: u64,
+    started: Instant,
+    parts: usize,
+}
+
+struct Recorder {
+    started: Instant,
+    input: Option<Input>,
+    // The time it took to read every entry, with its size.
+    entries: Vec<(u64, Duration)>,
+    tasks: Option<TimingLog>,
+}
+
+// Like the manifest, entries are reported from the main thread.
+// Timings of the executor's tasks are collected in a shared log.
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+fn with_recorder(f: impl FnOnce(&mut Recorder)) {
+    RECORDER.with_borrow_mut(|r| {
+        if let Some(r) = r {
+            f(r)
+        }
+    });
+}
+
+/// Starts recording metrics on the current thread.
+///
+/// The timings of executor tasks are taken from `tasks`, if any.
+pub fn start(tasks: Option<TimingLog>) {
+    RECORDER.set(Some(Recorder {
+        started: Instant::now(),
+        input: None,
+        entries: Vec::new(),
+        tasks,
+    }));
+}
+
+/// Marks the start of processing for an input of `bytes` size.
+///
+/// Inputs without parts recorded by [`record_part`] count as a
+/// single entry, timed from `started` until [`end_input`].
+pub fn begin_input(bytes: u64, started: Instant) {
+    with_recorder(|r| {
+        r.input = Some(Input {
+            bytes,
+            started,
+            parts: 0,
+        });
+    });
+}
+
+/// Marks the end of processing for the current input.
+pub fn end_input() {
+    with_recorder(|r| {
+        if let Some(input) = r.input.take().filter(|i| i.parts == 0) {
+            r.entries.push((input.bytes, input.started.elapsed()));
+        }
+    });
+}
+
+/// Records a part of the current input, e.g. a file in an archive,
+/// with its size in `bytes`.
+///
+/// `started` is when reading and decompressing the part began.
+pub fn record_part(bytes: u64, started: Instant) {
+    with_recorder(|r| {
+        if let Some(input) = &mut r.input {
+            input.parts += 1;
+        }
+        r.entries.push((bytes, started.elapsed()));
+    });
+}
+
+/// Stops recording and returns the report of the collected metrics.
+pub fn finish() -> Option<Value> {
+    let r = RECORDER.take()?;
+    let elapsed = r.started.elapsed();
+    let tasks = r.tasks.map(|t| t.take()).unwrap_or_default();
+
+    let bytes: u64 = r.entries.iter().map(|(b, _)| b).sum();
+    let durations = |kinds: &[&str]| -> Vec<Duration> {
+        tasks
+            .iter()
+            .filter(|t| kinds.contains(&t.kind))
+            .map(|t| t.duration)
+            .collect()
+    };
+
+    Some(json!({
+        "elapsed_ms": elapsed.as_millis() as u64,
+        "entries": r.entries.len(),
+        "bytes": bytes,
+        "throughput_mb_s": bytes as f64 / 1e6 / elapsed.as_secs_f64().max(1e-9),
+        "read": percentiles(r.entries.iter().map(|(_, d)| *d).collect()),
+        "write": percentiles(durations(&["create_file", "create_files"])),
+        "create_dir": percentiles(durations(&["create_dir"])),
+        "queue_wait": percentiles(tasks.iter().map(|t| t.queued).collect()),
+    }))
+}
+
+/// Prints a summary line of `report` to stderr.
+pub fn print_summary(report: &Value) {
+    let megabytes = report["bytes"].as_u64().unwrap_or(0) as f64 / 1e6;
+    let seconds = report["elapsed_ms"].as_u64().unwrap_or(0) as f64 / 1e3;
+    eprintln!(
+        "Processed {} entries ({megabytes:.2} MB) in {seconds:.2}s, {:.2} MB/s",
+        report["entries"],
+        report["throughput_mb_s"].as_f64().unwrap_or(0.0),
+    );
+}
+
+// Summarizes `durations` in microseconds.
+fn percentiles(mut durations: Vec<Duration>) -> Value {
+    durations.sort_unstable();
+
+    let at = |p: usize| {
+        let idx = (durations.len() * p / 100).min(durations.len().saturating_sub(1));
+        durations.get(idx).map(|d| d.as_micros() as u64)
+    };
+    json!({
+        "count": durations.len(),
+        "p50_us": at(50),
+        "p90_us": at(90),
+        "p99_us": at(99),
+        "max_us": durations.last().map(|d| d.as_micros() as u64),
+    })
+}
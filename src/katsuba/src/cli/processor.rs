@@ -1,20 +1,34 @@
 use std::{
-    fs,
-    io::{self, Read, Seek},
+    collections::VecDeque,
+    fmt, fs,
+    io::{self, IsTerminal, Read, Seek},
     path::{Path, PathBuf},
+    time::Instant,
 };
 
 use eyre::Context;
-use katsuba_executor::{Buffer, Executor};
+use katsuba_executor::{Buffer, CancellationToken, Executor, Handle, Task, TimingLog};
+use katsuba_object_property::serde;
+use katsuba_utils::{fs as kfs, thiserror::Error};
+use katsuba_wad::ArchiveError;
 
 use self::sealed::Missing;
-use super::{InputSource, OutputSource};
+use super::{manifest, metrics, BatchOptions, InputSource, OutputSource};
 use crate::utils;
 
 mod sealed {
     pub struct Missing;
 }
 
+/// An input source which has no data to process.
+#[derive(Debug, Error)]
+pub enum EmptyInput {
+    #[error("standard input is empty (0 bytes)")]
+    Stdin,
+    #[error("input file '{}' is empty (0 bytes)", .0.display())]
+    File(PathBuf),
+}
+
 /// A [`Read`]er over a compatible input source.
 pub enum Reader<'a> {
     Stdin(io::Cursor<Vec<u8>>),
@@ -92,6 +106,7 @@ pub enum Bias {
 /// Processes input sources and maps them to output sources.
 pub struct Processor<R, W> {
     bias: Bias,
+    batch: BatchOptions,
     reader_fn: R,
     writer_fn: W,
 }
@@ -105,6 +120,7 @@ impl Processor<Missing, Missing> {
     pub fn new(bias: Bias) -> eyre::Result<Self> {
         Ok(Self {
             bias,
+            batch: BatchOptions::default(),
             reader_fn: Missing,
             writer_fn: Missing,
         })
@@ -112,22 +128,39 @@ impl Processor<Missing, Missing> {
 
     /// Configures a callback for reading an input source into an arbitrary
     /// type for further processing.
+    ///
+    /// When processing multiple input files, the callback is cloned
+    /// for every file and runs on the executor's worker threads. It
+    /// then gets a single-threaded executor of its own, so that any
+    /// work it dispatches runs in place.
     #[inline]
     pub fn read_with<F, T>(self, f: F) -> Processor<F, Missing>
     where
-        F: FnMut(Reader<'_>, &Executor) -> eyre::Result<T>,
+        F: FnMut(Reader<'_>, &Executor) -> eyre::Result<T> + Clone + Send + 'static,
+        T: Send + 'static,
     {
         Processor {
             bias: self.bias,
+            batch: self.batch,
             reader_fn: f,
             writer_fn: Missing,
         }
     }
 }
 
+impl<R, W> Processor<R, W> {
+    /// Configures how batches of inputs are processed and reported.
+    #[inline]
+    pub fn with_batch(mut self, batch: BatchOptions) -> Self {
+        self.batch = batch;
+        self
+    }
+}
+
 impl<R, T> Processor<R, Missing>
 where
-    R: FnMut(Reader<'_>, &Executor) -> eyre::Result<T>,
+    R: FnMut(Reader<'_>, &Executor) -> eyre::Result<T> + Clone + Send + 'static,
+    T: Send + 'static,
 {
     /// Configures a callback for writing an element to an output source.
     pub fn write_with<F>(self, f: F) -> Processor<R, F>
@@ -136,6 +169,7 @@ where
     {
         Processor {
             bias: self.bias,
+            batch: self.batch,
             reader_fn: self.reader_fn,
             writer_fn: f,
         }
@@ -144,7 +178,8 @@ where
 
 impl<R, W, T> Processor<R, W>
 where
-    R: FnMut(Reader<'_>, &Executor) -> eyre::Result<T>,
+    R: FnMut(Reader<'_>, &Executor) -> eyre::Result<T> + Clone + Send + 'static,
+    T: Send + 'static,
     W: FnMut(&Executor, Option<PathBuf>, T, OutputSource) -> eyre::Result<()>,
 {
     fn stdin(&self) -> eyre::Result<Reader<'static>> {
@@ -152,62 +187,204 @@ where
 
         let mut buf = io::Cursor::new(Vec::new());
         stdin.read_to_end(buf.get_mut())?;
+        if buf.get_ref().is_empty() {
+            return Err(EmptyInput::Stdin.into());
+        }
 
         Ok(Reader::Stdin(buf))
     }
 
-    fn file<'a>(&self, path: &'a Path) -> eyre::Result<Reader<'a>> {
-        let file = fs::File::open(path)
-            .with_context(|| format!("failed to open file '{}'", path.display()))?;
+    // Creates a task which reads the file at `path` on a worker.
+    fn read_task(&self, path: PathBuf, cancel: &CancellationToken) -> (Task, Handle<Decoded<T>>) {
+        let mut reader_fn = self.reader_fn.clone();
+        let cancel = cancel.clone();
+
+        Task::spawn(move || {
+            let started = Instant::now();
+            let ex = Executor::current().with_cancellation(cancel);
+            let value = open_file(&path).and_then(|reader| reader_fn(reader, &ex));
 
-        Ok(Reader::File(path, io::BufReader::new(file)))
+            (started, value)
+        })
     }
 
     /// Processes the given input source into the given output source.
     ///
     /// Depending on the configuration, this may use single-threaded or
     /// multi-threaded I/O for processing.
+    ///
+    /// When a manifest or metrics path is configured, it is written
+    /// even when processing fails.
+    ///
+    /// A summary of the throughput is printed to stderr when it is a
+    /// terminal and output does not go to stdout, or when metrics are
+    /// requested.
     pub fn process(mut self, input: InputSource, output: OutputSource) -> eyre::Result<()> {
-        let mut executor = match self.bias {
-            Bias::Current => Executor::current(),
-            Bias::Threaded => Executor::get()?,
+        let metrics_path = self.batch.metrics.take();
+        let summary = metrics_path.is_some()
+            || (io::stderr().is_terminal() && !matches!(output, OutputSource::Stdout));
+
+        // Task timings are only collected when they are reported.
+        let timings = metrics_path.as_ref().map(|_| TimingLog::new());
+        metrics::start(timings.clone());
+        let res = self.record(input, output, timings);
+        let Some(report) = metrics::finish() else {
+            return res;
+        };
+
+        if res.is_ok() && summary {
+            metrics::print_summary(&report);
+        }
+
+        if let Some(path) = metrics_path {
+            let json = serde_json::to_vec_pretty(&report)?;
+            kfs::atomic_write(&path, &json, false)
+                .with_context(|| format!("failed to write metrics '{}'", path.display()))?;
+        }
+
+        res
+    }
+
+    fn record(
+        &mut self,
+        input: InputSource,
+        output: OutputSource,
+        timings: Option<TimingLog>,
+    ) -> eyre::Result<()> {
+        let Some(path) = self.batch.manifest.take() else {
+            return self.run(input, output, timings);
         };
 
+        manifest::start();
+        let res = self.run(input, output, timings);
+        let entries = manifest::finish();
+
+        let json = serde_json::to_vec_pretty(&entries)?;
+        kfs::atomic_write(&path, &json, false)
+            .with_context(|| format!("failed to write manifest '{}'", path.display()))?;
+
+        res
+    }
+
+    fn run(
+        &mut self,
+        input: InputSource,
+        output: OutputSource,
+        timings: Option<TimingLog>,
+    ) -> eyre::Result<()> {
+        let cancel = utils::interrupt_token();
+        // When processing multiple input files, we ignore the bias.
+        // Only one executor is created, so all work of a run shares
+        // the same worker threads.
+        let mut executor = match (self.bias, &input) {
+            (Bias::Current, InputSource::Stdin | InputSource::File(..)) => Executor::current(),
+            _ => Executor::get()?,
+        }
+        .with_cancellation(cancel.clone());
+        if let Some(timings) = timings {
+            executor = executor.with_timings(timings);
+        }
+
         match (input, output) {
             (InputSource::Stdin, out) => {
+                let started = Instant::now();
                 let reader = self.stdin()?;
+                if let Reader::Stdin(buf) = &reader {
+                    manifest::begin_input(Path::new("-"), buf.get_ref().len() as u64, started);
+                    metrics::begin_input(buf.get_ref().len() as u64, started);
+                }
 
                 let value = (self.reader_fn)(reader, &executor)?;
-                (self.writer_fn)(&mut executor, None, value, out)
+                (self.writer_fn)(&executor, None, value, out)?;
+                metrics::end_input();
+
+                // Pending writes must complete before we record success.
+                for pending in executor.join() {
+                    pending?;
+                }
+
+                Ok(())
             }
 
             (InputSource::File(path), out) => {
-                let reader = self.file(&path)?;
+                let started = Instant::now();
+                manifest::begin_input(&path, file_size(&path), started);
+                metrics::begin_input(file_size(&path), started);
 
+                let reader = open_file(&path)?;
                 let value = (self.reader_fn)(reader, &executor)?;
-                (self.writer_fn)(&mut executor, Some(path), value, out)
-            }
+                (self.writer_fn)(&executor, Some(path), value, out)?;
+                metrics::end_input();
 
-            (InputSource::Files(paths), OutputSource::Dir(out, suffix)) => {
-                // When processing multiple input files, we ignore the bias.
-                if let Bias::Current = self.bias {
-                    executor = Executor::get()?;
+                for pending in executor.join() {
+                    pending?;
                 }
 
+                Ok(())
+            }
+
+            (InputSource::Files(paths), OutputSource::Dir(out, suffix)) => {
                 // Create the specified out directory if it doesn't exist.
                 fs::create_dir_all(&out)?;
 
-                // Dispatch work for all input paths onto the executor.
-                for path in paths {
-                    let reader = self.file(&path)?;
-                    let value = (self.reader_fn)(reader, &executor)?;
-
-                    (self.writer_fn)(
-                        &mut executor,
-                        Some(path),
-                        value,
-                        OutputSource::Dir(out.clone(), suffix),
-                    )?;
+                // Inputs are read on the executor while their values are
+                // written here in input order. Only a few inputs per worker
+                // are in flight at a time to bound memory usage.
+                let total = paths.len();
+                let window = executor.workers() * 2;
+                let mut paths = paths.into_iter();
+                let mut reading = VecDeque::with_capacity(window);
+                let mut failed = Failures::default();
+                loop {
+                    while reading.len() < window {
+                        let Some((path, subdir)) = paths.next() else {
+                            break;
+                        };
+
+                        cancel.check()?;
+                        let (task, handle) = self.read_task(path.clone(), &cancel);
+                        for pending in executor.dispatch(task) {
+                            pending?;
+                        }
+                        reading.push_back((path, subdir, handle));
+                    }
+
+                    let Some((path, subdir, handle)) = reading.pop_front() else {
+                        break;
+                    };
+
+                    // Tasks are only dropped without a value when they were
+                    // cancelled or panicked.
+                    let Some((started, value)) = handle.wait() else {
+                        cancel.check()?;
+                        eyre::bail!("failed to read '{}'", path.display());
+                    };
+                    manifest::begin_input(&path, file_size(&path), started);
+                    metrics::begin_input(file_size(&path), started);
+
+                    // Mirror the structure of input directories.
+                    let display = path.display().to_string();
+                    let res = value.and_then(|value| {
+                        let out = out.join(subdir);
+                        fs::create_dir_all(&out)?;
+                        (self.writer_fn)(
+                            &executor,
+                            Some(path),
+                            value,
+                            OutputSource::Dir(out, suffix),
+                        )
+                    });
+                    metrics::end_input();
+
+                    if let Err(e) = res {
+                        if !self.batch.keep_going || cancel.is_cancelled() {
+                            return Err(e);
+                        }
+
+                        log::error!("Failed to process '{display}': {e:#}");
+                        manifest::fail(&e);
+                        failed.count(&e);
+                    }
                 }
 
                 // Await the completion of all pending tasks on the executor.
@@ -215,6 +392,16 @@ where
                     pending?;
                 }
 
+                // Tasks may have been dropped after the last check.
+                cancel.check()?;
+
+                if failed.total > 0 {
+                    eyre::bail!(
+                        "failed to process {} of {total} inputs{failed}",
+                        failed.total
+                    );
+                }
+
                 Ok(())
             }
 
@@ -222,3 +409,68 @@ where
         }
     }
 }
+
+// Tally of inputs which failed to process in a batch.
+#[derive(Default)]
+struct Failures {
+    total: usize,
+    empty: usize,
+    too_short: usize,
+}
+
+impl Failures {
+    fn count(&mut self, e: &eyre::Report) {
+        self.total += 1;
+
+        for cause in e.chain() {
+            if cause.is::<EmptyInput>() || matches!(cause.downcast_ref(), Some(ArchiveError::Empty))
+            {
+                self.empty += 1;
+                break;
+            }
+
+            if let Some(serde::Error::TooShort { .. }) = cause.downcast_ref() {
+                self.too_short += 1;
+                break;
+            }
+        }
+    }
+}
+
+// Breaks the failures down by kind, if any are known.
+impl fmt::Display for Failures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<_> = [(self.empty, "empty"), (self.too_short, "too short")]
+            .into_iter()
+            .filter(|&(n, _)| n > 0)
+            .map(|(n, kind)| format!("{n} {kind}"))
+            .collect();
+
+        match kinds.is_empty() {
+            true => Ok(()),
+            false => write!(f, " ({})", kinds.join(", ")),
+        }
+    }
+}
+
+// The outcome of reading an input on a worker, along with when
+// reading began.
+type Decoded<T> = (Instant, eyre::Result<T>);
+
+fn open_file(path: &Path) -> eyre::Result<Reader<'_>> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed to open file '{}'", path.display()))?;
+
+    // Special files like pipes may report no size, so we only
+    // reject regular ones upfront.
+    let metadata = file.metadata()?;
+    if metadata.is_file() && metadata.len() == 0 {
+        return Err(EmptyInput::File(path.to_owned()).into());
+    }
+
+    Ok(Reader::File(path, io::BufReader::new(file)))
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
@@ -1,10 +1,13 @@
 pub mod bcd;
+pub mod convert;
 pub mod cs;
 pub mod hash;
 pub mod nav;
 pub mod op;
 pub mod poi;
+pub mod types;
 pub mod wad;
+pub mod zone;
 
 /// Represents a command in the Katsuba application.
 pub trait Command {
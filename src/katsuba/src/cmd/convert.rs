@@ -0,0 +1,110 @@
+use std::{io::Cursor, path::PathBuf, sync::Arc};
+
+use clap::Args;
+use katsuba_bcd::Bcd;
+use katsuba_nav::{NavigationGraph, ZoneNavigationGraph};
+use katsuba_object_property::{from_slice_with, serde::SerializerOptions, Value};
+use katsuba_poi::Poi;
+use katsuba_wad::Archive;
+use serde::Serialize;
+
+use super::{wad, Command};
+use crate::{
+    cli::{helpers, Bias, InputsOutputs, Processor, Reader},
+    utils::{
+        self,
+        detect::{self, Detection, Format},
+    },
+};
+
+/// Detects the format of files and deserializes them into JSON.
+///
+/// KIWAD archives and ObjectProperty game files are recognized by
+/// their magic bytes, BCD, NAV and POI files by their extension.
+/// Files whose format is unknown or ambiguous are reported instead
+/// of converted.
+#[derive(Debug, Args)]
+pub struct Convert {
+    #[clap(flatten)]
+    args: InputsOutputs,
+
+    /// A list of paths to JSON type list files to use.
+    ///
+    /// These are required for ObjectProperty game files.
+    #[clap(short, long)]
+    type_lists: Vec<PathBuf>,
+}
+
+// The deserialized contents of any supported file.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Converted {
+    Wad(Vec<serde_json::Value>),
+    ObjectProperty(Value),
+    Bcd(Bcd),
+    Nav(NavigationGraph),
+    ZoneNav(ZoneNavigationGraph),
+    Poi(Poi),
+}
+
+impl Command for Convert {
+    fn handle(self) -> eyre::Result<()> {
+        let type_list = match self.type_lists.is_empty() {
+            true => None,
+            false => Some(Arc::new(utils::merge_type_lists(self.type_lists)?)),
+        };
+
+        let batch = self.args.batch.clone();
+        let (inputs, outputs) = self.args.evaluate("de.json")?;
+        Processor::new(Bias::Current)?
+            .with_batch(batch)
+            .read_with(move |mut r, ex| {
+                let path = match &r {
+                    Reader::Stdin(..) => None,
+                    Reader::File(path, _) => Some(path.to_path_buf()),
+                };
+                let name = path
+                    .as_deref()
+                    .map_or_else(|| "-".into(), |p| p.display().to_string());
+
+                let buf = r.get_buffer(ex)?;
+                let format = match detect::detect(path.as_deref(), &buf) {
+                    Detection::Known(format) => format,
+                    Detection::Ambiguous(formats) => {
+                        let formats: Vec<_> = formats.iter().map(|f| f.to_string()).collect();
+                        eyre::bail!("format is ambiguous: could be {}", formats.join(" or "));
+                    }
+                    Detection::Unknown => eyre::bail!("unknown file format"),
+                };
+                eprintln!("{name}: {format}");
+
+                let converted = match format {
+                    Format::Wad => {
+                        let archive = Archive::from_vec(buf.to_vec())?;
+                        Converted::Wad(wad::file_listing(&archive))
+                    }
+                    Format::ObjectProperty => {
+                        let Some(type_list) = &type_list else {
+                            eyre::bail!("ObjectProperty files require a type list ('-t')");
+                        };
+                        let options = SerializerOptions::default();
+                        Converted::ObjectProperty(from_slice_with(
+                            &buf,
+                            type_list.clone(),
+                            options,
+                        )?)
+                    }
+                    Format::Bcd => Converted::Bcd(Bcd::parse(Cursor::new(&*buf))?),
+                    Format::Nav => Converted::Nav(NavigationGraph::parse(Cursor::new(&*buf))?),
+                    Format::ZoneNav => {
+                        Converted::ZoneNav(ZoneNavigationGraph::parse(Cursor::new(&*buf))?)
+                    }
+                    Format::Poi => Converted::Poi(Poi::parse(Cursor::new(&*buf))?),
+                };
+
+                Ok(converted)
+            })
+            .write_with(helpers::write_as_json)
+            .process(inputs, outputs)
+    }
+}
@@ -1,10 +1,11 @@
-use std::{fs, path::PathBuf};
+use std::{fs, io::Write, path::PathBuf};
 
 use clap::{Args, Subcommand};
 use eyre::Context;
 use katsuba_client_sig::PrivateKey;
 
 use super::Command;
+use crate::utils;
 
 /// Subcommand for working with Client Signatures.
 #[derive(Debug, Args)]
@@ -59,7 +60,7 @@ impl Command for ClientSig {
         match self.command {
             ClientSigCommand::Arg => {
                 let arg = private_key.make_access_key();
-                println!("{arg}");
+                writeln!(utils::stdout(), "{arg}")?;
             }
 
             ClientSigCommand::Decrypt { path, output } => {
@@ -1,38 +1,159 @@
-use clap::{Args, ValueEnum};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use clap::{Args, Subcommand};
 
 use katsuba_utils::hash::*;
 
 use super::Command;
+use crate::utils;
+
+mod index;
 
 /// Subcommand for hashing strings with common KingsIsle algorithms.
 #[derive(Debug, Args)]
 pub struct Hash {
-    /// The hash algorithm to apply.
-    #[clap(value_enum)]
-    algo: Algo,
+    #[clap(subcommand)]
+    command: HashCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum HashCommand {
+    /// Hashes strings with the KingsIsle string ID algorithm.
+    ///
+    /// This is used for type names in serialized data.
+    StringId {
+        /// The input strings to hash.
+        #[clap(required = true)]
+        inputs: Vec<String>,
+    },
+
+    /// Hashes strings with the DJB2 algorithm.
+    Djb2 {
+        /// The input strings to hash.
+        #[clap(required = true)]
+        inputs: Vec<String>,
+    },
+
+    /// Computes the hash of a property from its name and type.
+    ///
+    /// This is the value found in the `hash` field of type lists.
+    Property {
+        /// The name of the property, e.g. `m_packedName`.
+        name: String,
+
+        /// The name of the property's type, e.g. `std::string`.
+        r#type: String,
+    },
+
+    /// Finds known strings which produce a given hash.
+    ///
+    /// Strings are indexed under all supported algorithms, so the
+    /// hash can be looked up without knowing how it was produced.
+    /// All candidates are printed when multiple strings collide.
+    Lookup {
+        /// The hash to look up, in hexadecimal.
+        ///
+        /// A `0x` prefix is optional.
+        hash: String,
+
+        /// KIWAD archives whose file paths should be indexed.
+        #[clap(long = "from-wad")]
+        wads: Vec<PathBuf>,
+
+        /// Type list files whose class and property names should
+        /// be indexed.
+        #[clap(long = "from-types")]
+        types: Vec<PathBuf>,
+
+        /// Text files with one string per line to index, e.g. for
+        /// locale keys.
+        #[clap(long = "from-strings")]
+        strings: Vec<PathBuf>,
 
-    /// The input string to hash.
-    input: String,
+        /// A file to cache the index in.
+        ///
+        /// If the file exists, it is loaded and extended with any
+        /// given sources. Otherwise, it will be created.
+        #[clap(long)]
+        cache: Option<PathBuf>,
+    },
 }
 
-/// The hash algorithm to apply.
-#[derive(Clone, Debug, ValueEnum)]
-enum Algo {
-    /// The KingsIsle string ID algorithm.
-    StringId,
-    /// The DJB2 algorithm.
-    Djb2,
+fn parse_hash(input: &str) -> eyre::Result<u32> {
+    let hex = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+
+    u32::from_str_radix(hex, 16).map_err(|_| eyre::eyre!("'{input}' is not a valid 32-bit hash"))
+}
+
+fn kind_name(kind: HashKind) -> &'static str {
+    match kind {
+        HashKind::StringId => "string-id",
+        HashKind::Djb2 => "djb2",
+        HashKind::Property => "property",
+    }
+}
+
+fn print_hash(hash: u32, input: &str) -> io::Result<()> {
+    writeln!(utils::stdout(), "{hash:<10} {hash:#010x} {input}")
 }
 
 impl Command for Hash {
     fn handle(self) -> eyre::Result<()> {
-        let input = self.input.as_bytes();
-        let hash = match self.algo {
-            Algo::StringId => string_id(input),
-            Algo::Djb2 => djb2(input),
-        };
+        match self.command {
+            HashCommand::StringId { inputs } => {
+                for input in inputs {
+                    print_hash(string_id(input.as_bytes()), &input)?;
+                }
+            }
+
+            HashCommand::Djb2 { inputs } => {
+                for input in inputs {
+                    print_hash(djb2(input.as_bytes()), &input)?;
+                }
+            }
+
+            HashCommand::Property { name, r#type } => {
+                let hash = property_hash(name.as_bytes(), r#type.as_bytes());
+                print_hash(hash, &format!("{name}: {type}"))?;
+            }
+
+            HashCommand::Lookup {
+                hash,
+                wads,
+                types,
+                strings,
+                cache,
+            } => {
+                let hash = parse_hash(&hash)?;
+                let sources = index::Sources {
+                    wads,
+                    types,
+                    strings,
+                };
+                let index = index::build(sources, cache.as_deref())?;
+
+                let candidates = index.lookup(hash);
+                if candidates.is_empty() {
+                    eyre::bail!("no known string hashes to {hash:#010x}");
+                }
+
+                for candidate in candidates {
+                    writeln!(
+                        utils::stdout(),
+                        "{:<9} {}",
+                        kind_name(candidate.kind),
+                        candidate.value
+                    )?;
+                }
+            }
+        }
 
-        println!("{hash}");
         Ok(())
     }
 }
@@ -0,0 +1,108 @@
+use std::{
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+};
+
+use eyre::Context;
+use katsuba_types::TypeList;
+use katsuba_utils::{fs as kfs, hash::HashIndex};
+use katsuba_wad::Archive;
+
+/// The sources of known strings to build a [`HashIndex`] from.
+pub struct Sources {
+    pub wads: Vec<PathBuf>,
+    pub types: Vec<PathBuf>,
+    pub strings: Vec<PathBuf>,
+}
+
+impl Sources {
+    fn is_empty(&self) -> bool {
+        self.wads.is_empty() && self.types.is_empty() && self.strings.is_empty()
+    }
+}
+
+fn index_wad(index: &mut HashIndex, path: &Path) -> eyre::Result<()> {
+    let archive = Archive::open_mmap(path)?;
+
+    index.extend(archive.files().keys());
+    Ok(())
+}
+
+fn index_types(index: &mut HashIndex, path: &Path) -> eyre::Result<()> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed to open type list at '{}'", path.display()))?;
+    let list = TypeList::from_reader(io::BufReader::new(file))?;
+
+    for def in list.0.values() {
+        index.insert(&def.name);
+        for property in &def.properties {
+            index.insert(&property.name);
+            index.insert_property(&property.name, &property.r#type);
+        }
+    }
+
+    Ok(())
+}
+
+fn index_strings(index: &mut HashIndex, path: &Path) -> eyre::Result<()> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed to open string list at '{}'", path.display()))?;
+
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        let line = line.trim();
+        if !line.is_empty() {
+            index.insert(line);
+        }
+    }
+
+    Ok(())
+}
+
+/// Builds a [`HashIndex`] from the given sources.
+///
+/// When a `cache` path is given, a previously stored index is
+/// loaded from it and extended by the sources. The result is then
+/// written back so future lookups can skip the sources entirely.
+pub fn build(sources: Sources, cache: Option<&Path>) -> eyre::Result<HashIndex> {
+    let mut index = match cache {
+        Some(path) if path.exists() => {
+            let file = fs::File::open(path)?;
+            HashIndex::read(io::BufReader::new(file))
+                .with_context(|| format!("failed to read hash cache at '{}'", path.display()))?
+        }
+
+        Some(..) if sources.is_empty() => eyre::bail!("hash cache does not exist yet"),
+        None if sources.is_empty() => {
+            eyre::bail!("no sources for known strings given; see --help")
+        }
+
+        _ => HashIndex::new(),
+    };
+
+    if sources.is_empty() {
+        return Ok(index);
+    }
+
+    for path in &sources.wads {
+        index_wad(&mut index, path)?;
+    }
+    for path in &sources.types {
+        index_types(&mut index, path)?;
+    }
+    for path in &sources.strings {
+        index_strings(&mut index, path)?;
+    }
+
+    if let Some(path) = cache {
+        let mut data = Vec::new();
+        index.write(&mut data)?;
+
+        // An interrupted write must not leave a truncated cache behind.
+        kfs::atomic_write(path, &data, true)
+            .with_context(|| format!("failed to write hash cache at '{}'", path.display()))?;
+    }
+
+    Ok(index)
+}
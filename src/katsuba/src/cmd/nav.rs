@@ -34,8 +34,9 @@ impl Command for Nav {
     fn handle(self) -> eyre::Result<()> {
         match self.command {
             NavCommand::De(args) => {
+                let batch = args.batch.clone();
                 let (inputs, outputs) = args.evaluate("de.json")?;
-                let processor = Processor::new(Bias::Current)?;
+                let processor = Processor::new(Bias::Current)?.with_batch(batch);
 
                 match self.file_type {
                     FileType::Nav => processor
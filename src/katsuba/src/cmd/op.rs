@@ -1,13 +1,23 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, process, sync::Arc};
 
-use clap::{Args, Subcommand};
-use katsuba_object_property::serde;
+use clap::{Args, Subcommand, ValueEnum};
+use katsuba_object_property::{from_slice_with, serde, value};
 use katsuba_types::PropertyFlags;
 
 use super::Command;
-use crate::cli::{helpers, Bias, InputsOutputs, Processor};
+use crate::cli::{helpers, Bias, Inputs, InputsOutputs, OutputSource, Processor};
 
+mod edit;
+mod extract;
+mod format;
+mod grep;
 mod guess;
+mod index;
+mod parse;
+mod ser;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod strings;
 mod utils;
 
 /// Subcommand for working with ObjectProperty serialization.
@@ -36,14 +46,22 @@ pub struct ObjectProperty {
     #[clap(short, long, default_value_t = 0)]
     flags: u32,
 
-    /// Property filter mask to use.
+    /// Property filter mask to use, overriding `--mask-preset`.
     ///
     /// This mask can be used to conditionally exclude properties
     /// of an object from the serialization.
     ///
-    /// When in doubt what to pick, try the default value or 0.
-    #[clap(short, long, default_value_t = 0x18)]
-    mask: u32,
+    /// When in doubt what to pick, try a preset or 0.
+    #[clap(short, long)]
+    mask: Option<u32>,
+
+    /// The property filter mask for a use case of the game.
+    ///
+    /// `transmit` is for state sent over the network (0x18), `save`
+    /// for saved state (0x1), `copy` for state copied in the editor
+    /// (0x2) and `all` includes every property (0x0).
+    #[clap(long, value_enum, default_value_t = MaskPreset::Transmit)]
+    mask_preset: MaskPreset,
 
     /// Whether the object is serialized shallow.
     ///
@@ -64,9 +82,84 @@ pub struct ObjectProperty {
     #[clap(short, long, default_value_t = false)]
     zlib_manual: bool,
 
+    /// The order of the flags and the compression marker in data
+    /// with stateful flags.
+    ///
+    /// Some state has the flags inside the compressed data. `auto`
+    /// picks the order under which the root object's type is known.
+    #[clap(long, value_enum, default_value_t = HeaderOrder::FlagsFirst)]
+    header_order: HeaderOrder,
+
+    /// Whether deprecated properties are part of shallow objects.
+    ///
+    /// Data written before a property was deprecated still contains
+    /// it. Try this when shallow objects fail to deserialize.
+    #[clap(long, default_value_t = false)]
+    include_deprecated: bool,
+
     /// Whether we should use only the djb2 hash (Pirate101)
     #[clap(short, long, default_value_t = false)]
     djb2_only: bool,
+
+    /// Whether string properties holding serialized objects
+    /// should be decoded into their values.
+    ///
+    /// Decoded objects are written back into their strings when
+    /// editing state.
+    #[clap(long, default_value_t = false)]
+    decode_nested: bool,
+
+    /// Whether properties missing from the data, like those left
+    /// out by the mask of shallow objects, are filled in with their
+    /// default values.
+    #[clap(long, default_value_t = false)]
+    fill_defaults: bool,
+
+    /// Whether objects of a different class than the one their
+    /// property is declared with are an error.
+    ///
+    /// By default, only a warning is logged for them. Such objects
+    /// usually mean that the data was misread before.
+    #[clap(long, default_value_t = false)]
+    strict_classes: bool,
+}
+
+/// The order of stateful headers, see [`serde::HeaderOrder`].
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum HeaderOrder {
+    FlagsFirst,
+    CompressionFirst,
+    Auto,
+}
+
+impl From<HeaderOrder> for serde::HeaderOrder {
+    fn from(order: HeaderOrder) -> Self {
+        match order {
+            HeaderOrder::FlagsFirst => Self::FlagsFirst,
+            HeaderOrder::CompressionFirst => Self::CompressionFirst,
+            HeaderOrder::Auto => Self::Auto,
+        }
+    }
+}
+
+/// Presets for the property mask, see [`serde::PropertyMask`].
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum MaskPreset {
+    Transmit,
+    Save,
+    Copy,
+    All,
+}
+
+impl From<MaskPreset> for serde::PropertyMask {
+    fn from(preset: MaskPreset) -> Self {
+        match preset {
+            MaskPreset::Transmit => Self::transmit(),
+            MaskPreset::Save => Self::save(),
+            MaskPreset::Copy => Self::copy(),
+            MaskPreset::All => Self::all(),
+        }
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -79,6 +172,108 @@ enum ObjectPropertyCommand {
         /// Skips properties with unknown types during deserialization.
         #[clap(short, long, default_value_t = false)]
         ignore_unknown_types: bool,
+
+        /// Records the bit range and bytes of every property under
+        /// a `$__raw` key in the root object.
+        ///
+        /// Bit offsets are relative to the decompressed data.
+        #[clap(long, default_value_t = false)]
+        capture_raw: bool,
+
+        /// Deserializes consecutive root objects in a file on all
+        /// available cores.
+        ///
+        /// Files with more than one object produce a JSON list of
+        /// them, in their original order.
+        #[clap(long, default_value_t = false, conflicts_with = "capture_raw")]
+        parallel: bool,
+
+        /// Deserializes all consecutive root objects in a file into
+        /// a JSON list, even when there is only one.
+        ///
+        /// Errors name the failing object and its byte offset in
+        /// the decompressed data.
+        #[clap(long, default_value_t = false, conflicts_with_all = ["capture_raw", "parallel"])]
+        multi: bool,
+
+        /// Checks narrow strings for invalid UTF-8 and logs the
+        /// path and byte offset of every one that fails.
+        ///
+        /// The strings are still written as they are.
+        #[clap(long, default_value_t = false, conflicts_with = "parallel")]
+        strict_strings: bool,
+
+        /// Prints the number of strings with invalid UTF-8 in every
+        /// input to stderr.
+        #[clap(long, default_value_t = false, requires = "strict_strings")]
+        stats: bool,
+
+        /// How to write NaN and infinite floats, which JSON numbers
+        /// cannot represent.
+        ///
+        /// `ser` accepts the string forms for float properties.
+        #[clap(long, value_enum, default_value_t = format::NonFinite::String)]
+        nonfinite: format::NonFinite,
+
+        /// How to write colors.
+        ///
+        /// `ser` and `edit` accept both forms.
+        #[clap(long, value_enum, default_value_t = format::ColorFormat::Rgba)]
+        color_format: format::ColorFormat,
+
+        /// Writes timestamps as `[raw, "ISO 8601 in UTC"]` pairs
+        /// instead of their raw integers.
+        ///
+        /// `ser` and `edit` accept both forms.
+        #[clap(long, default_value_t = false)]
+        humanize_time: bool,
+
+        /// How to write global IDs from `gid` properties.
+        ///
+        /// `ser` and `edit` accept all forms.
+        #[clap(long, value_enum, default_value_t = format::GidFormat::Hex)]
+        gid_format: format::GidFormat,
+
+        /// The format to write values in.
+        ///
+        /// The float, color, time and global ID options only apply
+        /// to JSON.
+        #[clap(long, value_enum, default_value_t = format::OutputFormat::Json)]
+        format: format::OutputFormat,
+
+        /// Summarizes objects and containers nested deeper than this
+        /// in text output.
+        #[clap(long)]
+        depth: Option<usize>,
+
+        /// Shows at most this many elements of lists and maps in text
+        /// output, followed by the number of those left out.
+        #[clap(long)]
+        max_items: Option<usize>,
+
+        /// When to color text output.
+        ///
+        /// `auto` colors output to a terminal unless `NO_COLOR` is set.
+        #[clap(long, value_enum, default_value_t = format::ColorChoice::Auto)]
+        color: format::ColorChoice,
+    },
+
+    /// Serializes JSON in the format produced by `de` back to
+    /// ObjectProperty binary state.
+    ///
+    /// Values are validated against the declared types of their
+    /// properties. Unless the data is a game file, the base command
+    /// options must match the ones used for deserialization.
+    Ser {
+        #[clap(flatten)]
+        args: InputsOutputs,
+
+        /// Writes a game file, prefixed with the `BINd` magic.
+        ///
+        /// These always use deep mode with stateful flags, same as
+        /// when deserializing them.
+        #[clap(long, default_value_t = false)]
+        bind: bool,
     },
 
     /// Attempts to deserialize ObjectProperty binary state
@@ -103,17 +298,192 @@ enum ObjectPropertyCommand {
         #[clap(short, long)]
         quiet: bool,
     },
+
+    /// Lists the root objects in ObjectProperty binary state
+    /// without fully deserializing them.
+    ///
+    /// Prints a JSON list with the type hash and name of every
+    /// object along with its offset and size in bits. Offsets
+    /// are relative to the data after decompression.
+    Index {
+        /// Path to the file to index.
+        path: PathBuf,
+    },
+
+    /// Searches ObjectProperty binary state for objects with
+    /// matching property values.
+    ///
+    /// Prints every input which satisfies all predicates along with
+    /// the matched values. Exits with status 1 when nothing matched.
+    /// Inputs which fail to deserialize are skipped with a warning.
+    Grep {
+        #[clap(flatten)]
+        args: Inputs,
+
+        /// A predicate of the form `path<op>value` to match.
+        ///
+        /// Paths are written like `m_items[2].m_goldCost`. The
+        /// operators `=`, `!=`, `<`, `<=`, `>` and `>=` compare values
+        /// according to their type, `~` checks whether a string
+        /// contains the value. Predicates on lists match when any
+        /// element does.
+        #[clap(long = "where", value_name = "PREDICATE")]
+        predicates: Vec<String>,
+
+        /// A predicate of the form `path~regex` to match strings
+        /// against a regular expression.
+        #[clap(long = "where-regex", value_name = "PREDICATE")]
+        regex_predicates: Vec<String>,
+
+        /// Matches the predicates against every object of this class
+        /// or one of its subclasses instead of the root objects.
+        ///
+        /// Paths in predicates are relative to these objects, and
+        /// the paths of matching objects are printed as well.
+        #[clap(long)]
+        class: Option<String>,
+    },
+
+    /// Writes selected property values of ObjectProperty binary
+    /// state as a table.
+    ///
+    /// Every root object in the inputs produces one row, starting
+    /// with the input name and the index of the object in it.
+    /// Missing fields produce empty cells. Inputs which fail to
+    /// deserialize are skipped with a warning.
+    ExtractField {
+        #[clap(flatten)]
+        args: Inputs,
+
+        /// A path to a value to extract, like `m_items[2].m_goldCost`.
+        ///
+        /// Every field becomes a column, in the order given.
+        #[clap(long = "field", value_name = "PATH", required = true)]
+        fields: Vec<String>,
+
+        /// The format of the table.
+        #[clap(long, value_enum, default_value_t = extract::TableFormat::Csv)]
+        format: extract::TableFormat,
+
+        /// Writes a header row with the column names to CSV tables.
+        ///
+        /// This is the default.
+        #[clap(long, overrides_with = "no_header")]
+        header: bool,
+
+        /// Omits the header row from CSV tables.
+        #[clap(long, overrides_with = "header")]
+        no_header: bool,
+
+        /// Writes a row for every object of this class or one of its
+        /// subclasses instead of the root objects.
+        ///
+        /// Fields are relative to these objects, and a `path` column
+        /// after the object index tells where they are in it.
+        #[clap(long)]
+        class: Option<String>,
+    },
+
+    /// Inserts fields of the objects in ObjectProperty binary state
+    /// into a SQLite database.
+    ///
+    /// Every object becomes a row of the table, along with the input
+    /// it came from and its index in there. The table and an index
+    /// on the first field are created as needed. Missing fields are
+    /// NULL. Inputs which fail to deserialize are skipped with a
+    /// warning.
+    #[cfg(feature = "sqlite")]
+    ToSqlite {
+        #[clap(flatten)]
+        args: Inputs,
+
+        /// Path to the database to insert the rows into.
+        #[clap(short, long)]
+        output: PathBuf,
+
+        /// The name of the table to insert the rows into.
+        #[clap(long, default_value = "objects")]
+        table: String,
+
+        /// A path to a value and its column type, like
+        /// `m_goldCost:int`.
+        ///
+        /// Types are `int`, `real`, `text` and `json`. Every field
+        /// becomes a column named after its path, in the order given.
+        #[clap(long = "field", value_name = "PATH:TYPE", required = true)]
+        fields: Vec<String>,
+    },
+
+    /// Lists every string in ObjectProperty binary state along with
+    /// the path of the property holding it.
+    ///
+    /// Every distinct pair of path and string is written once, in
+    /// the order it is first found across all inputs. Inputs which
+    /// fail to deserialize are skipped with a warning.
+    Strings {
+        #[clap(flatten)]
+        args: Inputs,
+
+        /// The format of the table.
+        #[clap(long, value_enum, default_value_t = extract::TableFormat::Csv)]
+        format: extract::TableFormat,
+
+        /// Adds the string ID hash of every string as a column.
+        #[clap(long)]
+        hash: bool,
+    },
+
+    /// Modifies values in ObjectProperty binary state and
+    /// serializes it again.
+    ///
+    /// Values are addressed by paths like `m_items[2].m_goldCost`
+    /// and parsed according to the declared type of the property.
+    Edit {
+        /// Path to the file to edit.
+        path: PathBuf,
+
+        /// An assignment of the form `path=value` to apply.
+        ///
+        /// Values are given in JSON notation, in the same form as
+        /// `de` writes them. Strings and enum variants may also be
+        /// written without quotes.
+        #[clap(long = "set", value_name = "ASSIGNMENT")]
+        assignments: Vec<String>,
+
+        /// Path to a JSON file with a list of `{"path": ..., "value": ...}`
+        /// objects to apply before any assignments.
+        #[clap(long)]
+        patch: Option<PathBuf>,
+
+        /// Path to write the modified state to.
+        #[clap(short, long)]
+        output: PathBuf,
+
+        /// Writes the output even when the unmodified input does
+        /// not serialize back to identical bytes.
+        #[clap(long)]
+        force: bool,
+    },
 }
 
 impl Command for ObjectProperty {
     fn handle(self) -> eyre::Result<()> {
-        let type_list = Arc::new(utils::merge_type_lists(self.type_lists)?);
+        let type_list = Arc::new(crate::utils::merge_type_lists(self.type_lists)?);
         let mut options = serde::SerializerOptions {
             flags: serde::SerializerFlags::from_bits_truncate(self.flags),
-            property_mask: PropertyFlags::from_bits_truncate(self.mask),
+            property_mask: match self.mask {
+                Some(mask) => PropertyFlags::from_bits_truncate(mask),
+                None => serde::PropertyMask::from(self.mask_preset).into(),
+            },
             shallow: self.shallow,
             manual_compression: self.zlib_manual,
+            header_order: self.header_order.into(),
+            include_deprecated: self.include_deprecated,
             djb2_only: self.djb2_only,
+            decode_nested: self.decode_nested,
+            fill_defaults: self.fill_defaults,
+            strict_classes: self.strict_classes,
+            verbose_errors: log::log_enabled!(log::Level::Info),
             ..Default::default()
         };
 
@@ -121,36 +491,263 @@ impl Command for ObjectProperty {
             ObjectPropertyCommand::De {
                 args,
                 ignore_unknown_types,
+                capture_raw,
+                parallel,
+                multi,
+                strict_strings,
+                stats,
+                nonfinite,
+                color_format,
+                humanize_time,
+                gid_format,
+                format,
+                depth,
+                max_items,
+                color,
             } => {
-                let (inputs, outputs) = args.evaluate("de.xml")?;
+                let text = match format {
+                    format::OutputFormat::Json => None,
+                    format::OutputFormat::Text if capture_raw => {
+                        eyre::bail!("'--capture-raw' is only supported for JSON output")
+                    }
+                    format::OutputFormat::Text => Some(
+                        format::TextFormat::new()
+                            .depth(depth)
+                            .max_items(max_items)
+                            .types(type_list.clone()),
+                    ),
+                };
+
+                let batch = args.batch.clone();
+                let (inputs, outputs) = args.evaluate(match text {
+                    Some(_) => "de.txt",
+                    None => "de.json",
+                })?;
 
                 options.skip_unknown_types = ignore_unknown_types;
-                let mut de = serde::Serializer::new(options, type_list)?;
+                options.capture_raw = capture_raw;
+                options.strict_strings = strict_strings;
+                let json = text.is_none().then_some(value::JsonFormat {
+                    nonfinite: nonfinite.into(),
+                    color: color_format.into(),
+                    humanize_time,
+                    gid: gid_format.into(),
+                });
+                // Parallel deserialization runs on the executor's workers.
+                let bias = match parallel {
+                    true => Bias::Threaded,
+                    false => Bias::Current,
+                };
 
-                Processor::new(Bias::Current)?
+                Processor::new(bias)?
+                    .with_batch(batch)
                     .read_with(move |mut r, ex| {
                         let buf = r.get_buffer(ex)?;
-                        let mut buf: &[u8] = &buf;
 
-                        // If the data starts with the `BINd` magic, it is a game file.
-                        // These always use a fixed base config so we set it here.
-                        if buf.get(0..4) == Some(serde::BIND_MAGIC) {
-                            de.parts.options.shallow = false;
-                            de.parts.options.flags = serde::SerializerFlags::STATEFUL_FLAGS;
+                        // Plain deserialization needs nothing beyond the
+                        // top-level API.
+                        if !parallel && !capture_raw && !multi && !strict_strings {
+                            let mut value = from_slice_with(&buf, type_list.clone(), options)?;
+                            if let Some(json) = &json {
+                                json.apply(&mut value)?;
+                            }
+                            return Ok(utils::Captured {
+                                value,
+                                spans: None,
+                                invalid_strings: 0,
+                            });
+                        }
 
-                            buf = buf.get(4..).unwrap();
+                        // Readers may run on worker threads, so every input
+                        // gets a serializer of its own.
+                        let mut de = serde::Serializer::new(options, type_list.clone())?;
+                        let buf = de.parts.options.strip_bind_magic(&buf);
+
+                        let mut value = if parallel {
+                            // Indexing may pick up stateful flags, so restore
+                            // the config for the actual deserialization.
+                            let base = de.parts.options;
+                            let spans = de.index::<serde::PropertyClass>(buf)?;
+                            de.parts.options = base;
+
+                            match spans.len() {
+                                0 | 1 => de.deserialize::<serde::PropertyClass>(buf)?,
+                                _ => {
+                                    de.deserialize_spans::<serde::PropertyClass>(ex, buf, &spans)?
+                                }
+                            }
+                        } else if multi {
+                            let inner = de.deserialize_all::<serde::PropertyClass>(buf)?;
+                            value::Value::List(value::List { inner })
+                        } else {
+                            de.deserialize::<serde::PropertyClass>(buf)?
+                        };
+                        let spans = capture_raw.then(|| de.parts.raw_spans().to_vec());
+                        if let Some(json) = &json {
+                            json.apply(&mut value)?;
+                        }
+
+                        let invalid_strings = de.parts.invalid_strings().len();
+                        Ok(utils::Captured {
+                            value,
+                            spans,
+                            invalid_strings,
+                        })
+                    })
+                    .write_with(move |ex, inpath, captured, out| {
+                        if stats {
+                            let name = inpath
+                                .as_deref()
+                                .map_or_else(|| "-".into(), |p| p.display().to_string());
+                            eprintln!(
+                                "{name}: {} strings with invalid UTF-8",
+                                captured.invalid_strings
+                            );
+                        }
+
+                        match &text {
+                            Some(text) => {
+                                let to_stdout = matches!(out, OutputSource::Stdout);
+                                let text = text.clone().color(color.enabled(to_stdout));
+                                let formatted = text.format(&captured.value);
+                                helpers::write_bytes(ex, inpath, formatted.into_bytes(), out)
+                            }
+                            None => helpers::write_as_json(ex, inpath, captured, out),
+                        }
+                    })
+                    .process(inputs, outputs)
+            }
+
+            ObjectPropertyCommand::Ser { args, bind } => {
+                let batch = args.batch.clone();
+                let (inputs, outputs) = args.evaluate("bin")?;
+
+                // Game files use a fixed base config, same as in deserialization.
+                if bind {
+                    options.shallow = false;
+                    options.flags = serde::SerializerFlags::STATEFUL_FLAGS;
+                }
+
+                Processor::new(Bias::Current)?
+                    .with_batch(batch)
+                    .read_with(move |r, _| {
+                        let mut serializer = serde::Serializer::new(options, type_list.clone())?;
+                        let json = serde_json::from_reader(r)?;
+                        let value = ser::value_from_json(&options, &type_list, &json)?;
+
+                        let mut out = Vec::new();
+                        if bind {
+                            out.extend_from_slice(serde::BIND_MAGIC);
                         }
+                        out.extend(serializer.serialize::<serde::PropertyClass>(&value)?);
 
-                        de.deserialize::<serde::PropertyClass>(buf)
-                            .map_err(Into::into)
+                        Ok(out)
                     })
-                    .write_with(helpers::write_as_json)
+                    .write_with(helpers::write_bytes)
                     .process(inputs, outputs)
             }
 
             ObjectPropertyCommand::Guess { path, quiet } => {
                 guess::guess(options, type_list, path, quiet)
             }
+
+            ObjectPropertyCommand::Index { path } => index::index(options, type_list, path),
+
+            ObjectPropertyCommand::ExtractField {
+                args,
+                fields,
+                format,
+                header: _,
+                no_header,
+                class,
+            } => {
+                let fields = fields
+                    .iter()
+                    .map(|f| f.parse().map_err(Into::into))
+                    .collect::<eyre::Result<Vec<_>>>()?;
+
+                extract::extract(
+                    options,
+                    type_list,
+                    args.evaluate()?,
+                    &fields,
+                    format,
+                    !no_header,
+                    class.as_deref(),
+                )
+            }
+
+            #[cfg(feature = "sqlite")]
+            ObjectPropertyCommand::ToSqlite {
+                args,
+                output,
+                table,
+                fields,
+            } => {
+                let fields = fields
+                    .iter()
+                    .map(|f| f.parse())
+                    .collect::<eyre::Result<Vec<_>>>()?;
+
+                sqlite::to_sqlite(
+                    options,
+                    type_list,
+                    args.evaluate()?,
+                    &output,
+                    &table,
+                    &fields,
+                )
+            }
+
+            ObjectPropertyCommand::Strings { args, format, hash } => {
+                strings::strings(options, type_list, args.evaluate()?, format, hash)
+            }
+
+            ObjectPropertyCommand::Grep {
+                args,
+                predicates,
+                regex_predicates,
+                class,
+            } => {
+                let plain = predicates.iter().map(|p| grep::Predicate::parse(p, false));
+                let regex = regex_predicates
+                    .iter()
+                    .map(|p| grep::Predicate::parse(p, true));
+                let predicates = plain.chain(regex).collect::<eyre::Result<Vec<_>>>()?;
+                if predicates.is_empty() {
+                    eyre::bail!("at least one predicate is required");
+                }
+
+                let matched = grep::grep(
+                    options,
+                    type_list,
+                    args.evaluate()?,
+                    &predicates,
+                    class.as_deref(),
+                )?;
+                if !matched {
+                    process::exit(1);
+                }
+                Ok(())
+            }
+
+            ObjectPropertyCommand::Edit {
+                path,
+                assignments,
+                patch,
+                output,
+                force,
+            } => edit::edit(
+                options,
+                type_list,
+                edit::Edit {
+                    path,
+                    assignments,
+                    patch,
+                    output,
+                    force,
+                },
+            ),
         }
     }
 }
@@ -0,0 +1,191 @@
+use std::{borrow::Cow, fs, path::PathBuf, sync::Arc};
+
+use eyre::Context;
+use katsuba_object_property::{
+    serde::{self, BIND_MAGIC},
+    value::{Path, PathSegment},
+    Value,
+};
+use katsuba_types::{Property, TemplateType, TypeList};
+use katsuba_utils::fs as kfs;
+use serde_json::Value as Json;
+
+use super::ser;
+
+pub struct Edit {
+    pub path: PathBuf,
+    pub assignments: Vec<String>,
+    pub patch: Option<PathBuf>,
+    pub output: PathBuf,
+    pub force: bool,
+}
+
+pub fn edit(opts: serde::SerializerOptions, types: Arc<TypeList>, edit: Edit) -> eyre::Result<()> {
+    let edits = collect_edits(&edit)?;
+
+    let raw = kfs::read_mapped(&edit.path)?;
+
+    // Game files use a fixed base config, same as in deserialization.
+    let mut ser = serde::Serializer::new(opts, types.clone())?;
+    let data = ser.parts.options.strip_bind_magic(&raw);
+    let bind = data.len() != raw.len();
+    let stateful = ser
+        .parts
+        .options
+        .flags
+        .contains(serde::SerializerFlags::STATEFUL_FLAGS);
+
+    let mut value = ser.deserialize::<serde::PropertyClass>(data)?;
+
+    // Deserialization replaces stateful flags with the ones from the
+    // data, so we must restore the bit to write them back out.
+    if stateful {
+        ser.parts.options.flags |= serde::SerializerFlags::STATEFUL_FLAGS;
+    }
+
+    // Before changing anything, make sure that we can faithfully
+    // reproduce the input. Otherwise, the user might lose data.
+    let untouched = ser.serialize::<serde::PropertyClass>(&value)?;
+    if untouched != data {
+        if !edit.force {
+            eyre::bail!(
+                "re-serializing the unmodified object does not reproduce '{}'; \
+                 pass --force to write the output anyway",
+                edit.path.display()
+            );
+        }
+
+        log::warn!("Output fidelity cannot be guaranteed for this object");
+    }
+
+    for (path, rhs) in edits {
+        let property = resolve_property(&types, &value, &path)?;
+
+        // Paths ending in a property replace all of its elements.
+        let element = !matches!(path.segments().last(), Some(PathSegment::Property(_)));
+        let new = ser::property_from_json(
+            &ser.parts.options,
+            &types,
+            &property,
+            &rhs,
+            element,
+            &path.to_string(),
+        )?;
+
+        value.set_path(&path, new)?;
+    }
+
+    let mut out = Vec::new();
+    if bind {
+        out.extend_from_slice(BIND_MAGIC);
+    }
+    out.extend(ser.serialize::<serde::PropertyClass>(&value)?);
+
+    kfs::atomic_write(&edit.output, &out, false)
+        .with_context(|| format!("failed to write '{}'", edit.output.display()))
+}
+
+fn collect_edits(edit: &Edit) -> eyre::Result<Vec<(Path, Json)>> {
+    let mut edits = Vec::new();
+
+    if let Some(patch) = &edit.patch {
+        let file = fs::read_to_string(patch)
+            .with_context(|| format!("failed to read patch '{}'", patch.display()))?;
+        let Json::Array(entries) = serde_json::from_str(&file)? else {
+            eyre::bail!("patch must be a list of path/value pairs");
+        };
+
+        for entry in entries {
+            let (Some(Json::String(path)), Some(value)) = (entry.get("path"), entry.get("value"))
+            else {
+                eyre::bail!("patch entry is missing 'path' or 'value': {entry}");
+            };
+
+            edits.push((path.parse()?, value.clone()));
+        }
+    }
+
+    for assignment in &edit.assignments {
+        let (path, rhs) = assignment
+            .split_once('=')
+            .ok_or_else(|| eyre::eyre!("assignment '{assignment}' must be of form path=value"))?;
+
+        // Anything that isn't JSON is taken as a bare string.
+        let rhs = serde_json::from_str(rhs).unwrap_or_else(|_| Json::String(rhs.to_owned()));
+        edits.push((path.trim().parse()?, rhs));
+    }
+
+    Ok(edits)
+}
+
+/// Finds the declared property that a value at `path` belongs to.
+///
+/// Map and pair entries get a stand-in property of their own type.
+fn resolve_property<'a>(
+    types: &'a TypeList,
+    root: &Value,
+    path: &Path,
+) -> eyre::Result<Cow<'a, Property>> {
+    let mut current = Some(root);
+    let mut property: Option<Cow<'a, Property>> = None;
+
+    for segment in path.segments() {
+        current = match (segment, current) {
+            (PathSegment::Property(name), Some(Value::Object { hash, obj })) => {
+                let type_def = types
+                    .0
+                    .get(hash)
+                    .ok_or_else(|| eyre::eyre!("unknown type with hash {hash}"))?;
+                let p = type_def
+                    .properties
+                    .iter()
+                    .find(|p| p.name == name.as_str())
+                    .ok_or_else(|| eyre::eyre!("'{}' has no property '{name}'", type_def.name))?;
+
+                property = Some(Cow::Borrowed(p));
+                obj.get(name)
+            }
+
+            // Nested objects resolve properties against their own type.
+            (PathSegment::Nested, Some(Value::Blob(blob))) => Some(&blob.value),
+
+            (PathSegment::Index(idx), Some(Value::List(list)))
+                if property.as_ref().is_some_and(|p| p.dynamic) =>
+            {
+                list.get(*idx)
+            }
+
+            (PathSegment::Key(idx), Some(Value::Map(entries))) => {
+                property = Some(entry_property(property.as_deref(), 0)?);
+                entries.get(*idx).map(|(k, _)| k)
+            }
+            (PathSegment::Value(idx), Some(Value::Map(entries))) => {
+                property = Some(entry_property(property.as_deref(), 1)?);
+                entries.get(*idx).map(|(_, v)| v)
+            }
+            (PathSegment::First, Some(Value::Pair(pair))) => {
+                property = Some(entry_property(property.as_deref(), 0)?);
+                Some(&pair.0)
+            }
+            (PathSegment::Second, Some(Value::Pair(pair))) => {
+                property = Some(entry_property(property.as_deref(), 1)?);
+                Some(&pair.1)
+            }
+
+            _ => eyre::bail!("no value at '{path}'"),
+        };
+    }
+
+    property.ok_or_else(|| eyre::eyre!("cannot replace the root object"))
+}
+
+// Gets a stand-in property for the template argument at `idx` of
+// the map or pair type of `property`.
+fn entry_property<'a>(property: Option<&Property>, idx: usize) -> eyre::Result<Cow<'a, Property>> {
+    let property = property.ok_or_else(|| eyre::eyre!("map or pair without a property"))?;
+    let ty = TemplateType::parse(&property.r#type)
+        .and_then(|t| t.args.get(idx).copied())
+        .ok_or_else(|| eyre::eyre!("'{}' is not a map or pair type", property.r#type))?;
+
+    Ok(Cow::Owned(ser::element_property(property, ty)))
+}
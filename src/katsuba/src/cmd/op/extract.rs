@@ -0,0 +1,136 @@
+use std::{
+    io::{BufWriter, Write},
+    sync::Arc,
+};
+
+use clap::ValueEnum;
+use katsuba_executor::Executor;
+use katsuba_object_property::{
+    serde,
+    value::{Path, PathSegment, Value},
+};
+use katsuba_types::TypeList;
+
+use super::utils;
+use crate::{cli::InputSource, utils::write_csv_row};
+
+/// The format of extracted tables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TableFormat {
+    /// Comma-separated values with RFC 4180 quoting.
+    #[default]
+    Csv,
+    /// One JSON object per line, keyed by field path.
+    Jsonl,
+}
+
+/// Writes the values at `fields` for every object in the inputs as
+/// one row each.
+///
+/// Rows start with the input name and the index of the object in it,
+/// which is non-zero only for inputs with several root objects.
+///
+/// With a `class`, every object of that class or a subclass gets a
+/// row instead, with its path in the root object after the index.
+pub fn extract(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    inputs: InputSource,
+    fields: &[Path],
+    format: TableFormat,
+    header: bool,
+    class: Option<&str>,
+) -> eyre::Result<()> {
+    let mut de = serde::Serializer::new(opts, types.clone())?;
+    // Objects of a class may be anywhere, so nothing can be skipped.
+    if class.is_none() {
+        de.parts.set_property_filter(root_properties(fields));
+    }
+    let ex = Executor::get()?;
+
+    let mut out = BufWriter::new(crate::utils::stdout());
+    if header && format == TableFormat::Csv {
+        let mut names = vec!["file".to_string(), "object".to_string()];
+        if class.is_some() {
+            names.push("path".into());
+        }
+        names.extend(fields.iter().map(|f| f.to_string()));
+        write_csv_row(&mut out, names)?;
+    }
+
+    utils::for_each_input(inputs, |name, data| {
+        let objects = match utils::deserialize_roots(&mut de, &ex, opts, data) {
+            Ok(objects) => objects,
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                return Ok(());
+            }
+        };
+
+        for (idx, object) in objects.iter().enumerate() {
+            let matches: Box<dyn Iterator<Item = _>> = match class {
+                Some(class) => Box::new(object.objects_of(class, &types)),
+                // Without a class, only the root object is used.
+                None => Box::new(object.objects().take(1)),
+            };
+
+            for (path, _, obj) in matches {
+                let path = class.map(|_| path.to_string());
+                let values = fields.iter().map(|f| obj.get_path(f));
+                match format {
+                    TableFormat::Csv => {
+                        let cells = values.map(csv_cell).collect::<Result<Vec<_>, _>>()?;
+                        let row = [name.to_string(), idx.to_string()]
+                            .into_iter()
+                            .chain(path)
+                            .chain(cells);
+                        write_csv_row(&mut out, row)?;
+                    }
+                    TableFormat::Jsonl => {
+                        let mut line = format!("{{\"file\":{},\"object\":{idx}", json(name)?);
+                        if let Some(path) = path {
+                            line.push_str(&format!(",\"path\":{}", json(&path)?));
+                        }
+                        for (field, value) in fields.iter().zip(values) {
+                            let value = serde_json::to_string(&value)?;
+                            line.push_str(&format!(",{}:{value}", json(&field.to_string())?));
+                        }
+                        writeln!(out, "{line}}}")?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    })?;
+
+    out.flush()?;
+    Ok(())
+}
+
+// Collects the names of root properties that `fields` start with, so
+// everything else can be skipped during deserialization.
+pub(super) fn root_properties(fields: &[Path]) -> Option<Vec<String>> {
+    fields
+        .iter()
+        .map(|f| match f.segments().first() {
+            Some(PathSegment::Property(name)) => Some(name.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn json(s: &str) -> serde_json::Result<String> {
+    serde_json::to_string(s)
+}
+
+// Formats a value for a CSV cell. Strings are written verbatim and
+// everything else in JSON notation.
+fn csv_cell(value: Option<&Value>) -> serde_json::Result<String> {
+    match value.map(Value::resolve) {
+        None => Ok(String::new()),
+        Some(Value::String(s)) => Ok(s.to_string()),
+        Some(Value::WString(s)) => Ok(s.to_string()),
+        Some(v) => serde_json::to_string(v),
+    }
+}
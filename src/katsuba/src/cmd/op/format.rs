@@ -0,0 +1,330 @@
+//! Presentation of [`Value`]s as human-readable text, and the
+//! options for writing them as JSON, see [`value::JsonFormat`].
+
+use std::{
+    env,
+    fmt::{self, Write},
+    io::{self, IsTerminal},
+    sync::Arc,
+};
+
+use clap::ValueEnum;
+use katsuba_object_property::{
+    value::{self, gid_to_hex},
+    Value,
+};
+use katsuba_types::TypeList;
+
+/// The format values are written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JSON which can be serialized back.
+    #[default]
+    Json,
+    /// Indented text meant for reading.
+    Text,
+}
+
+/// How floats without a JSON representation are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum NonFinite {
+    /// Fail with the path of the first NaN or infinite float.
+    Error,
+    /// Write them as `null`, which cannot be serialized back.
+    Null,
+    /// Write them as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`.
+    #[default]
+    String,
+}
+
+/// How colors are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorFormat {
+    /// Objects with `r`, `g`, `b` and `a` channels.
+    #[default]
+    Rgba,
+    /// Strings in `#RRGGBBAA` notation.
+    Hex,
+}
+
+/// How global IDs are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GidFormat {
+    /// Zero-padded hex strings like `"0x0000000100000002"`.
+    #[default]
+    Hex,
+    /// Plain integers.
+    Decimal,
+    /// `[block, id]` pairs of the high and low 32 bits.
+    Split,
+}
+
+impl From<NonFinite> for value::NonFinite {
+    fn from(nonfinite: NonFinite) -> Self {
+        match nonfinite {
+            NonFinite::Error => Self::Error,
+            NonFinite::Null => Self::Null,
+            NonFinite::String => Self::String,
+        }
+    }
+}
+
+impl From<ColorFormat> for value::ColorFormat {
+    fn from(color: ColorFormat) -> Self {
+        match color {
+            ColorFormat::Rgba => Self::Rgba,
+            ColorFormat::Hex => Self::Hex,
+        }
+    }
+}
+
+impl From<GidFormat> for value::GidFormat {
+    fn from(gid: GidFormat) -> Self {
+        match gid {
+            GidFormat::Hex => Self::Hex,
+            GidFormat::Decimal => Self::Decimal,
+            GidFormat::Split => Self::Split,
+        }
+    }
+}
+
+/// When text output is colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Only when writing to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always, even when writing to a file.
+    Always,
+    /// Never.
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether output is colored, given whether it goes to stdout.
+    pub fn enabled(self, to_stdout: bool) -> bool {
+        match self {
+            Self::Auto => {
+                let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                to_stdout && !no_color && io::stdout().is_terminal()
+            }
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+// ANSI styles of the highlighted parts of text output.
+const KEY_STYLE: &str = "34";
+const TYPE_STYLE: &str = "35";
+const NUMBER_STYLE: &str = "33";
+
+// The key of an entry in a block of text output.
+enum Key<'a> {
+    None,
+    Name(&'a str),
+    Value(&'a Value),
+}
+
+/// Writes values as indented text, one entry per line.
+///
+/// Unlike JSON output, this can leave out parts of large values.
+#[derive(Clone, Debug, Default)]
+pub struct TextFormat {
+    depth: Option<usize>,
+    max_items: Option<usize>,
+    color: bool,
+    types: Option<Arc<TypeList>>,
+}
+
+impl TextFormat {
+    /// Creates a format which writes values in full and without
+    /// colors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Summarizes objects and containers nested deeper than `depth`
+    /// levels as `{...}` or `[...]`.
+    pub fn depth(mut self, depth: Option<usize>) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Shows at most `max_items` elements of lists and maps, followed
+    /// by the number of those left out.
+    pub fn max_items(mut self, max_items: Option<usize>) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// Highlights keys, type names and numbers with ANSI colors.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Names objects after their classes in `types`, instead of
+    /// their type hashes.
+    pub fn types(mut self, types: Arc<TypeList>) -> Self {
+        self.types = Some(types);
+        self
+    }
+
+    /// Formats `value` as text, ending in a newline.
+    pub fn format(&self, value: &Value) -> String {
+        let mut out = String::new();
+        self.value(&mut out, value, 0);
+        out.push('\n');
+        out
+    }
+
+    fn value(&self, out: &mut String, value: &Value, level: usize) {
+        match value {
+            Value::Shared(v) => self.value(out, v, level),
+            Value::Blob(blob) => self.value(out, &blob.value, level),
+
+            Value::Object { hash, obj } => {
+                let name = self.types.as_ref().and_then(|t| t.0.get(hash));
+                match name {
+                    Some(def) => self.paint(out, TYPE_STYLE, &def.name),
+                    None => self.paint(out, TYPE_STYLE, format_args!("{hash:#010x}")),
+                }
+                out.push(' ');
+
+                let entries: Vec<_> = obj
+                    .iter()
+                    .filter(|(_, v)| !matches!(v, Value::Unset))
+                    .map(|(k, v)| (Key::Name(k), v))
+                    .collect();
+                self.block(out, ('{', '}'), &entries, false, level);
+            }
+            Value::Map(map) => {
+                let entries: Vec<_> = map.iter().map(|(k, v)| (Key::Value(k), v)).collect();
+                self.block(out, ('{', '}'), &entries, true, level);
+            }
+            Value::List(list) => {
+                let entries: Vec<_> = list.iter().map(|v| (Key::None, v)).collect();
+                self.block(out, ('[', ']'), &entries, true, level);
+            }
+            Value::Pair(pair) => {
+                let entries = [(Key::None, &pair.0), (Key::None, &pair.1)];
+                self.block(out, ('(', ')'), &entries, false, level);
+            }
+
+            Value::Empty | Value::Unset => out.push_str("null"),
+            Value::Unsigned(v) => self.paint(out, NUMBER_STYLE, v),
+            Value::Signed(v) | Value::Enum(v) => self.paint(out, NUMBER_STYLE, v),
+            Value::Gid(gid) => self.paint(out, NUMBER_STYLE, gid_to_hex(*gid)),
+            Value::Float(v) => self.paint(out, NUMBER_STYLE, format_args!("{v:?}")),
+            Value::Float32(v) => self.paint(out, NUMBER_STYLE, format_args!("{v:?}")),
+            Value::Bool(v) => {
+                let _ = write!(out, "{v}");
+            }
+            Value::String(s) => {
+                let _ = write!(out, "{:?}", s.to_string());
+            }
+            Value::WString(s) => {
+                let _ = write!(out, "{:?}", s.to_string());
+            }
+            Value::Time(t) => {
+                let _ = write!(out, "{t}");
+            }
+            Value::Color(c) => out.push_str(&c.to_hex()),
+
+            Value::Vec3(v) => self.tuple(out, &[&v.x, &v.y, &v.z]),
+            Value::Quat(q) => self.tuple(out, &[&q.x, &q.y, &q.z, &q.w]),
+            Value::Euler(e) => self.tuple(out, &[&e.pitch, &e.yaw, &e.roll]),
+            Value::Mat3x3(m) => {
+                out.push('(');
+                for (i, row) in [m.i, m.j, m.k].iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.tuple(out, &[&row[0], &row[1], &row[2]]);
+                }
+                out.push(')');
+            }
+            Value::PointInt(p) => self.tuple(out, &[&p.x, &p.y]),
+            Value::PointFloat(p) => self.tuple(out, &[&p.x, &p.y]),
+            Value::SizeInt(s) => self.tuple(out, &[&s.width, &s.height]),
+            Value::RectInt(r) => self.tuple(out, &[&r.left, &r.top, &r.right, &r.bottom]),
+            Value::RectFloat(r) => self.tuple(out, &[&r.left, &r.top, &r.right, &r.bottom]),
+        }
+    }
+
+    // Writes entries on their own lines, one level deeper than the
+    // delimiters. Only `limited` blocks are cut off at `max_items`.
+    fn block(
+        &self,
+        out: &mut String,
+        (open, close): (char, char),
+        entries: &[(Key<'_>, &Value)],
+        limited: bool,
+        level: usize,
+    ) {
+        out.push(open);
+        if entries.is_empty() {
+            out.push(close);
+            return;
+        }
+        if self.depth.is_some_and(|depth| level >= depth) {
+            out.push_str("...");
+            out.push(close);
+            return;
+        }
+        out.push('\n');
+
+        let shown = match self.max_items {
+            Some(max) if limited => max.min(entries.len()),
+            _ => entries.len(),
+        };
+        for (key, value) in &entries[..shown] {
+            indent(out, level + 1);
+            match key {
+                Key::None => (),
+                Key::Name(name) => {
+                    self.paint(out, KEY_STYLE, name);
+                    out.push_str(": ");
+                }
+                Key::Value(key) => {
+                    self.value(out, key, level + 1);
+                    out.push_str(": ");
+                }
+            }
+            self.value(out, value, level + 1);
+            out.push('\n');
+        }
+        if shown < entries.len() {
+            indent(out, level + 1);
+            let _ = writeln!(out, "... ({} more)", entries.len() - shown);
+        }
+
+        indent(out, level);
+        out.push(close);
+    }
+
+    fn tuple(&self, out: &mut String, parts: &[&dyn fmt::Debug]) {
+        out.push('(');
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+            self.paint(out, NUMBER_STYLE, format_args!("{part:?}"));
+        }
+        out.push(')');
+    }
+
+    fn paint(&self, out: &mut String, style: &str, text: impl fmt::Display) {
+        let _ = match self.color {
+            true => write!(out, "\x1b[{style}m{text}\x1b[0m"),
+            false => write!(out, "{text}"),
+        };
+    }
+}
+
+fn indent(out: &mut String, level: usize) {
+    for _ in 0..level {
+        out.push_str("  ");
+    }
+}
@@ -0,0 +1,206 @@
+use std::{cmp::Ordering, io::Write, sync::Arc};
+
+use katsuba_object_property::{
+    from_slice_with, serde,
+    value::{Object, Path, Value},
+};
+use katsuba_types::TypeList;
+use regex::Regex;
+
+use super::utils;
+use crate::cli::InputSource;
+
+/// A comparison between the value at a path and a literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+/// A condition on the value at a property path.
+#[derive(Debug)]
+pub struct Predicate {
+    path: Path,
+    op: Op,
+    rhs: String,
+    regex: Option<Regex>,
+}
+
+impl Predicate {
+    /// Parses a predicate of the form `path<op>value`.
+    ///
+    /// With `regex`, only the `~` operator is accepted and the value
+    /// is compiled into a regular expression.
+    pub fn parse(s: &str, regex: bool) -> eyre::Result<Self> {
+        let (start, op, len) =
+            find_op(s).ok_or_else(|| eyre::eyre!("missing operator in predicate '{s}'"))?;
+        let path = s[..start].trim().parse()?;
+        let rhs = s[start + len..].to_string();
+
+        let regex = match regex {
+            true if op != Op::Contains => {
+                eyre::bail!("regex predicate '{s}' must use the '~' operator")
+            }
+            true => Some(Regex::new(&rhs)?),
+            false => None,
+        };
+
+        Ok(Self {
+            path,
+            op,
+            rhs,
+            regex,
+        })
+    }
+
+    /// Gets the value at the predicate's path in `obj` if it
+    /// satisfies the predicate.
+    ///
+    /// Lists satisfy it when any of their elements does.
+    pub fn find<'a>(&self, obj: &'a Object) -> Option<&'a Value> {
+        let value = obj.get_path(&self.path)?.resolve();
+        let hit = match value {
+            Value::List(list) => list.iter().any(|v| self.test(v.resolve())),
+            v => self.test(v),
+        };
+
+        hit.then_some(value)
+    }
+
+    fn test(&self, value: &Value) -> bool {
+        if self.op == Op::Contains {
+            let text = match value {
+                Value::String(s) => s.to_string(),
+                Value::WString(s) => s.to_string(),
+                _ => return false,
+            };
+
+            return match &self.regex {
+                Some(re) => re.is_match(&text),
+                None => text.contains(&self.rhs),
+            };
+        }
+
+        let Some(ord) = compare(value, &self.rhs) else {
+            return false;
+        };
+        match self.op {
+            Op::Eq => ord.is_eq(),
+            Op::Ne => ord.is_ne(),
+            Op::Lt => ord.is_lt(),
+            Op::Le => ord.is_le(),
+            Op::Gt => ord.is_gt(),
+            Op::Ge => ord.is_ge(),
+            Op::Contains => unreachable!(),
+        }
+    }
+}
+
+// Finds the first operator in `s`, returning its position, kind
+// and length.
+fn find_op(s: &str) -> Option<(usize, Op, usize)> {
+    s.char_indices().find_map(|(i, c)| {
+        let rest = &s[i..];
+        let (op, len) = match c {
+            '!' if rest.starts_with("!=") => (Op::Ne, 2),
+            '<' if rest.starts_with("<=") => (Op::Le, 2),
+            '>' if rest.starts_with(">=") => (Op::Ge, 2),
+            '<' => (Op::Lt, 1),
+            '>' => (Op::Gt, 1),
+            '=' => (Op::Eq, 1),
+            '~' => (Op::Contains, 1),
+            _ => return None,
+        };
+
+        Some((i, op, len))
+    })
+}
+
+// Compares `value` to the literal `rhs`, interpreted according to
+// the type of the value.
+fn compare(value: &Value, rhs: &str) -> Option<Ordering> {
+    let int = |v: i128| match parse_int(rhs) {
+        Some(rhs) => Some(v.cmp(&rhs)),
+        None => (v as f64).partial_cmp(&rhs.parse().ok()?),
+    };
+
+    match value {
+        Value::Unsigned(v) | Value::Gid(v) => int(*v as i128),
+        Value::Signed(v) | Value::Enum(v) => int(*v as i128),
+        Value::Time(t) => int(t.raw as i128),
+        Value::Float(v) => v.partial_cmp(&rhs.parse().ok()?),
+        Value::Float32(v) => (*v as f64).partial_cmp(&rhs.parse().ok()?),
+        Value::Bool(v) => Some(v.cmp(&rhs.parse().ok()?)),
+        Value::String(s) => Some(s.to_string().as_str().cmp(rhs)),
+        Value::WString(s) => Some(s.to_string().as_str().cmp(rhs)),
+        _ => None,
+    }
+}
+
+fn parse_int(s: &str) -> Option<i128> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => i128::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Prints the inputs which satisfy all `predicates`, along with the
+/// values that matched.
+///
+/// With a `class`, the predicates are matched against every object
+/// of that class or a subclass in the inputs instead, and the paths
+/// of matching objects are printed too.
+///
+/// Returns whether any input matched. Inputs which fail to
+/// deserialize are reported and skipped.
+pub fn grep(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    inputs: InputSource,
+    predicates: &[Predicate],
+    class: Option<&str>,
+) -> eyre::Result<bool> {
+    let mut matched = false;
+    utils::for_each_input(inputs, |name, data| {
+        let value = match from_slice_with(data, types.clone(), opts) {
+            Ok(value) => value,
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                return Ok(());
+            }
+        };
+
+        let objects: Box<dyn Iterator<Item = _>> = match class {
+            Some(class) => Box::new(value.objects_of(class, &types)),
+            // Without a class, only the root object is matched.
+            None => Box::new(value.objects().take(1)),
+        };
+
+        for (path, _, obj) in objects {
+            let hits: Option<Vec<_>> = predicates.iter().map(|p| p.find(obj)).collect();
+            let Some(hits) = hits else {
+                continue;
+            };
+            matched = true;
+
+            let hits = predicates
+                .iter()
+                .zip(hits)
+                .map(|(p, v)| serde_json::to_string(v).map(|v| format!("{}={v}", p.path)))
+                .collect::<Result<Vec<_>, _>>()?;
+            match class {
+                Some(_) => writeln!(crate::utils::stdout(), "{name}:{path}: {}", hits.join(" "))?,
+                None => writeln!(crate::utils::stdout(), "{name}: {}", hits.join(" "))?,
+            }
+        }
+
+        Ok(())
+    })?;
+
+    Ok(matched)
+}
@@ -1,5 +1,4 @@
 use std::{
-    fs,
     io::{self, Write},
     path::PathBuf,
     sync::Arc,
@@ -10,6 +9,7 @@ use katsuba_object_property::{
     Value,
 };
 use katsuba_types::TypeList;
+use katsuba_utils::fs;
 
 use crate::utils;
 
@@ -26,8 +26,7 @@ pub fn guess(
 ) -> eyre::Result<()> {
     let report = try_guess(opts, types, path)?;
 
-    let stdout = io::stdout();
-    let mut stdout = stdout.lock();
+    let mut stdout = utils::stdout();
 
     write_status(&mut stdout, &report)?;
     writeln!(stdout)?;
@@ -45,8 +44,8 @@ fn try_guess(
     types: Arc<TypeList>,
     path: PathBuf,
 ) -> eyre::Result<Report> {
-    let data = fs::read(path)?;
-    let mut data = data.as_slice();
+    let data = fs::read_mapped(path)?;
+    let mut data = &data[..];
 
     let mut de = serde::Serializer::with_guessed_options_from_base(opts, types, data)?;
     let mut res;
@@ -0,0 +1,44 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use katsuba_object_property::serde;
+use katsuba_types::TypeList;
+use katsuba_utils::fs;
+use serde_json::json;
+
+use crate::utils;
+
+pub fn index(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    path: PathBuf,
+) -> eyre::Result<()> {
+    let data = fs::read_mapped(&path)?;
+
+    // Game files use a fixed base config, same as in deserialization.
+    let mut de = serde::Serializer::new(opts, types.clone())?;
+    let data = de.parts.options.strip_bind_magic(&data);
+
+    let index: Vec<_> = de
+        .index::<serde::PropertyClass>(data)?
+        .into_iter()
+        .map(|span| {
+            let name = types.0.get(&span.hash).map(|t| t.name.as_str());
+            json!({
+                "$__type": span.hash,
+                "name": name,
+                "offset": span.start,
+                "size": span.len,
+            })
+        })
+        .collect();
+
+    let mut stdout = io::BufWriter::new(utils::stdout());
+    serde_json::to_writer_pretty(&mut stdout, &index)?;
+    stdout.flush()?;
+
+    Ok(())
+}
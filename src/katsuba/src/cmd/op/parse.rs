@@ -0,0 +1,191 @@
+//! Parsing of JSON values into [`Value`]s of declared property types.
+
+use katsuba_object_property::{
+    value::{gid_join, Color, CxxStr, CxxWStr, Point, Rect, Size, Time},
+    Value,
+};
+use katsuba_types::Property;
+use serde::de::DeserializeOwned;
+use serde_json::Value as Json;
+
+/// Parses a single element of `property`'s type.
+pub fn parse_element(property: &Property, rhs: &Json) -> eyre::Result<Value> {
+    parse_leaf(property, rhs)
+        .unwrap_or_else(|| eyre::bail!("cannot assign {rhs} to '{}'", property.r#type))
+}
+
+/// Parses a single element of `property`'s type, provided that it
+/// is a leaf type.
+///
+/// Returns [`None`] for types which are not leaves, i.e. objects.
+pub fn parse_leaf(property: &Property, rhs: &Json) -> Option<eyre::Result<Value>> {
+    let ty = property.r#type.as_str();
+
+    if property.is_enum() {
+        let value = match rhs {
+            Json::String(variant) => property
+                .decode_enum_variant(variant)
+                .map(Value::Enum)
+                .map_err(Into::into),
+            Json::Number(n) => n
+                .as_i64()
+                .map(Value::Enum)
+                .ok_or_else(|| eyre::eyre!("invalid enum value {n}")),
+            _ => Err(eyre::eyre!("expected variant name or number for '{ty}'")),
+        };
+        return Some(value);
+    }
+
+    let value = match (ty, rhs) {
+        ("bool", Json::Bool(v)) => Ok(Value::Bool(*v)),
+        ("float", Json::Number(n)) => Ok(Value::Float32(n.as_f64().unwrap() as f32)),
+        ("double", Json::Number(n)) => Ok(Value::Float(n.as_f64().unwrap())),
+        ("float", Json::String(s)) if nonfinite(s).is_some() => {
+            Ok(Value::Float32(nonfinite(s).unwrap() as f32))
+        }
+        ("double", Json::String(s)) if nonfinite(s).is_some() => {
+            Ok(Value::Float(nonfinite(s).unwrap()))
+        }
+        ("std::string", Json::String(s)) => Ok(Value::String(CxxStr(s.as_bytes().to_vec()))),
+        ("std::wstring" | "wchar_t*", Json::String(s)) => {
+            Ok(Value::WString(CxxWStr(s.encode_utf16().collect())))
+        }
+        ("bool" | "float" | "double" | "std::string" | "std::wstring" | "wchar_t*", _) => {
+            Err(eyre::eyre!("cannot assign {rhs} to '{ty}'"))
+        }
+
+        ("gid" | "union gid", _) => parse_gid(ty, rhs).map(Value::Gid),
+        ("time_t" | "__time64_t" | "__time32_t", _) => parse_time(ty, rhs).map(Value::Time),
+
+        ("class Color", Json::String(s)) => parse_hex_color(s)
+            .map(Value::Color)
+            .ok_or_else(|| eyre::eyre!("invalid color '{s}', expected #RRGGBBAA")),
+        ("class Color", _) => from_json(ty, rhs).map(Value::Color),
+        ("class Vector3D", _) => from_json(ty, rhs).map(Value::Vec3),
+        ("class Quaternion", _) => from_json(ty, rhs).map(Value::Quat),
+        ("class Euler", _) => from_json(ty, rhs).map(Value::Euler),
+        ("class Matrix3x3", _) => from_json(ty, rhs).map(|m| Value::Mat3x3(Box::new(m))),
+        ("class Size<int>", _) => from_json::<Size<_>>(ty, rhs).map(Value::SizeInt),
+        ("class Point<int>", _) => from_json::<Point<_>>(ty, rhs).map(Value::PointInt),
+        ("class Point<float>", _) => from_json::<Point<_>>(ty, rhs).map(Value::PointFloat),
+        ("class Rect<int>", _) => from_json::<Rect<_>>(ty, rhs).map(Value::RectInt),
+        ("class Rect<float>", _) => from_json::<Rect<_>>(ty, rhs).map(Value::RectFloat),
+
+        (ty, _) => parse_integer(ty, rhs)?,
+    };
+
+    Some(value)
+}
+
+/// Decodes the string tokens used for non-finite floats in JSON.
+pub fn nonfinite(token: &str) -> Option<f64> {
+    match token {
+        "NaN" => Some(f64::NAN),
+        "Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        _ => None,
+    }
+}
+
+// Parses a global ID from an integer, a hex string or the
+// `[block, id]` pair written by `--gid-format split`.
+fn parse_gid(ty: &str, rhs: &Json) -> eyre::Result<u64> {
+    let part = |v: &Json| v.as_u64().and_then(|v| u32::try_from(v).ok());
+    let gid = match rhs {
+        Json::Number(n) => n.as_u64(),
+        Json::String(s) => s
+            .strip_prefix("0x")
+            .or_else(|| s.strip_prefix("0X"))
+            .and_then(|hex| u64::from_str_radix(hex, 16).ok()),
+        Json::Array(pair) if pair.len() == 2 => part(&pair[0])
+            .zip(part(&pair[1]))
+            .map(|(block, id)| gid_join(block, id)),
+        _ => None,
+    };
+
+    gid.ok_or_else(|| eyre::eyre!("cannot assign {rhs} to '{ty}'"))
+}
+
+// Parses a timestamp from its raw integer, or the `[raw, iso]`
+// pair written by `--humanize-time`.
+fn parse_time(ty: &str, rhs: &Json) -> eyre::Result<Time> {
+    let raw = match rhs {
+        Json::Array(pair) if pair.len() == 2 && pair[1].is_string() => &pair[0],
+        _ => rhs,
+    };
+
+    let raw = raw
+        .as_i64()
+        .ok_or_else(|| eyre::eyre!("cannot assign {rhs} to '{ty}'"))?;
+    if ty == "__time32_t" && i32::try_from(raw).is_err() {
+        eyre::bail!("{raw} is out of range for '{ty}'");
+    }
+
+    Ok(Time::unix_seconds(raw))
+}
+
+/// Parses a color in `#RRGGBBAA` or `#RRGGBB` notation.
+pub fn parse_hex_color(hex: &str) -> Option<Color> {
+    let digits = hex.strip_prefix('#')?;
+    if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok();
+    Some(Color {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+        a: if digits.len() == 8 { channel(6)? } else { 0xFF },
+    })
+}
+
+fn from_json<T: DeserializeOwned>(ty: &str, rhs: &Json) -> eyre::Result<T> {
+    T::deserialize(rhs).map_err(|e| eyre::eyre!("invalid value for '{ty}': {e}"))
+}
+
+// Parses integral leaf types, or returns `None` for other types.
+fn parse_integer(ty: &str, rhs: &Json) -> Option<eyre::Result<Value>> {
+    let (signed, bits) = integer_width(ty)?;
+    let Json::Number(n) = rhs else {
+        return Some(Err(eyre::eyre!("cannot assign {rhs} to '{ty}'")));
+    };
+
+    let value = if signed {
+        let min = -1i64 << (bits - 1);
+        n.as_i64()
+            .filter(|v| (min..=!min).contains(v))
+            .map(Value::Signed)
+    } else {
+        n.as_u64()
+            .filter(|&v| bits == u64::BITS || v >> bits == 0)
+            .map(Value::Unsigned)
+    };
+
+    Some(value.ok_or_else(|| eyre::eyre!("{n} is out of range for '{ty}'")))
+}
+
+// Gets signedness and bit width of integral leaf types.
+fn integer_width(ty: &str) -> Option<(bool, u32)> {
+    let res = match ty {
+        "char" => (true, i8::BITS),
+        "unsigned char" => (false, u8::BITS),
+        "short" => (true, i16::BITS),
+        "unsigned short" | "wchar_t" => (false, u16::BITS),
+        "int" | "long" => (true, i32::BITS),
+        "unsigned int" | "unsigned long" => (false, u32::BITS),
+        "unsigned __int64" | "unsigned long long" => (false, u64::BITS),
+        "s24" => (true, 24),
+        "u24" => (false, 24),
+
+        _ => {
+            let (signed, bits) = match ty.strip_prefix("bui") {
+                Some(bits) => (false, bits),
+                None => (true, ty.strip_prefix("bi")?),
+            };
+            (signed, bits.parse().ok().filter(|b| (2..=7).contains(b))?)
+        }
+    };
+
+    Some(res)
+}
@@ -0,0 +1,234 @@
+//! Reconstruction of [`Value`]s from the JSON produced by `op de`.
+
+use std::collections::BTreeMap;
+
+use katsuba_object_property::{
+    serde::SerializerOptions,
+    value::{List, Object},
+    Value,
+};
+use katsuba_types::{Property, PropertyFlags, TemplateType, TypeDef, TypeList};
+use serde_json::Value as Json;
+
+use super::parse::{parse_element, parse_leaf};
+
+const TYPE_KEY: &str = "$__type";
+const RAW_KEY: &str = "$__raw";
+
+/// Converts a JSON document into the object [`Value`] it describes.
+///
+/// Properties are parsed according to their declared types. Errors
+/// name the JSON pointer of the offending field.
+pub fn value_from_json(
+    options: &SerializerOptions,
+    types: &TypeList,
+    json: &Json,
+) -> eyre::Result<Value> {
+    let ctx = Context { options, types };
+    ctx.object(json, "")
+}
+
+/// Converts the JSON form of a `property`'s value into a [`Value`].
+///
+/// Dynamic properties expect a list of elements unless `element` is
+/// set, in which case a single one of them is parsed. Errors name
+/// `location` followed by the JSON pointer of the offending field.
+pub fn property_from_json(
+    options: &SerializerOptions,
+    types: &TypeList,
+    property: &Property,
+    json: &Json,
+    element: bool,
+    location: &str,
+) -> eyre::Result<Value> {
+    let ctx = Context { options, types };
+    match property.dynamic && !element {
+        true => ctx.list(property, json, location),
+        false => ctx.element(property, json, location),
+    }
+}
+
+struct Context<'a> {
+    options: &'a SerializerOptions,
+    types: &'a TypeList,
+}
+
+impl<'a> Context<'a> {
+    fn type_def(&self, json: &Json, pointer: &str) -> eyre::Result<(u32, &'a TypeDef)> {
+        let found = match json {
+            Json::Number(n) => n
+                .as_u64()
+                .and_then(|h| u32::try_from(h).ok())
+                .and_then(|h| self.types.0.get(&h).map(|t| (h, t))),
+            Json::String(name) => self.types.find(name),
+            _ => eyre::bail!("{pointer}: expected type hash or name, found {json}"),
+        };
+
+        found.ok_or_else(|| eyre::eyre!("{pointer}: unknown type {json}"))
+    }
+
+    fn object(&self, json: &Json, pointer: &str) -> eyre::Result<Value> {
+        let map = match json {
+            // Null pointers to objects.
+            Json::Null => return Ok(Value::Empty),
+            Json::Object(map) => map,
+            _ => eyre::bail!("{pointer}: expected object, found {json}"),
+        };
+
+        let type_json = map
+            .get(TYPE_KEY)
+            .ok_or_else(|| eyre::eyre!("{pointer}: object is missing '{TYPE_KEY}'"))?;
+        let (hash, type_def) = self.type_def(type_json, &child(pointer, TYPE_KEY))?;
+
+        let mut obj = BTreeMap::new();
+        for (name, value) in map {
+            // Raw spans are only informative and not part of the object.
+            if name == TYPE_KEY || (pointer.is_empty() && name == RAW_KEY) {
+                continue;
+            }
+
+            let pointer = child(pointer, name);
+            let property = type_def
+                .properties
+                .iter()
+                .find(|p| p.name == *name)
+                .ok_or_else(|| {
+                    eyre::eyre!("{pointer}: '{}' has no property '{name}'", type_def.name)
+                })?;
+
+            let value = match property.dynamic {
+                true => self.list(property, value, &pointer)?,
+                false => self.element(property, value, &pointer)?,
+            };
+            obj.insert(name.as_str().into(), value);
+        }
+
+        // Shallow objects need all their properties. Delta-encoded
+        // ones are left out by `de` when they were not transmitted.
+        if self.options.shallow {
+            for property in type_def
+                .properties
+                .iter()
+                .filter(|p| self.options.is_shallow_property(p))
+            {
+                if obj.contains_key(property.name.as_str()) {
+                    continue;
+                }
+
+                if !property.flags.contains(PropertyFlags::DELTA_ENCODE) {
+                    eyre::bail!(
+                        "{}: missing property of '{}'",
+                        child(pointer, &property.name),
+                        type_def.name
+                    );
+                }
+                obj.insert(property.name.as_str().into(), Value::Unset);
+            }
+        }
+
+        Ok(Value::Object {
+            hash,
+            obj: Object { inner: obj },
+        })
+    }
+
+    fn list(&self, property: &Property, json: &Json, pointer: &str) -> eyre::Result<Value> {
+        let Json::Array(elements) = json else {
+            eyre::bail!(
+                "{pointer}: expected list for '{}', found {json}",
+                property.name
+            );
+        };
+
+        let inner = elements
+            .iter()
+            .enumerate()
+            .map(|(idx, e)| self.element(property, e, &child(pointer, &idx.to_string())))
+            .collect::<eyre::Result<_>>()?;
+        Ok(Value::List(List { inner }))
+    }
+
+    fn element(&self, property: &Property, json: &Json, pointer: &str) -> eyre::Result<Value> {
+        // Decoded nested objects lose the configuration they were
+        // serialized with, so we cannot reproduce them.
+        if property.r#type == "std::string" && json.is_object() {
+            eyre::bail!("{pointer}: nested objects in strings cannot be serialized from JSON");
+        }
+
+        if let Some(value) = self.container(property, json, pointer) {
+            return value;
+        }
+
+        match parse_leaf(property, json) {
+            Some(res) => res.map_err(|e| eyre::eyre!("{pointer}: {e}")),
+            None if json.is_object() || json.is_null() => self.object(json, pointer),
+            None => parse_element(property, json).map_err(|e| eyre::eyre!("{pointer}: {e}")),
+        }
+    }
+
+    // Parses maps and pairs, which `de` writes as lists of entries
+    // and two-element lists, respectively.
+    fn container(
+        &self,
+        property: &Property,
+        json: &Json,
+        pointer: &str,
+    ) -> Option<eyre::Result<Value>> {
+        let template = TemplateType::parse(&property.r#type)?;
+        let is_map = match template.name {
+            "std::map" => true,
+            "std::pair" => false,
+            _ => return None,
+        };
+        let &[first, second] = template.args.as_slice() else {
+            return None;
+        };
+
+        // Element types have no property of their own, so we give
+        // them a stand-in for parsing.
+        let first = element_property(property, first);
+        let second = element_property(property, second);
+        let entry = |json: &Json, pointer: &str| match json {
+            Json::Array(v) if v.len() == 2 => Ok((
+                self.element(&first, &v[0], &child(pointer, "0"))?,
+                self.element(&second, &v[1], &child(pointer, "1"))?,
+            )),
+            _ => eyre::bail!("{pointer}: expected two-element list, found {json}"),
+        };
+
+        if !is_map {
+            return Some(entry(json, pointer).map(|e| Value::Pair(Box::new(e))));
+        }
+
+        let Json::Array(entries) = json else {
+            return Some(Err(eyre::eyre!(
+                "{pointer}: expected list of entries for '{}', found {json}",
+                property.name
+            )));
+        };
+
+        let entries = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, e)| entry(e, &child(pointer, &idx.to_string())))
+            .collect::<eyre::Result<_>>();
+        Some(entries.map(Value::Map))
+    }
+}
+
+/// Makes a stand-in property for the elements of type `ty` in the
+/// map or pair `property`.
+pub fn element_property(property: &Property, ty: &str) -> Property {
+    Property {
+        r#type: ty.into(),
+        dynamic: false,
+        flags: PropertyFlags::empty(),
+        enum_options: Default::default(),
+        ..property.clone()
+    }
+}
+
+// Appends a reference token to a JSON pointer.
+fn child(pointer: &str, token: &str) -> String {
+    format!("{pointer}/{}", token.replace('~', "~0").replace('/', "~1"))
+}
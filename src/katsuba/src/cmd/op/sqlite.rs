@@ -0,0 +1,212 @@
+use std::{path::Path as FsPath, str::FromStr, sync::Arc};
+
+use clap::ValueEnum;
+use katsuba_executor::Executor;
+use katsuba_object_property::{
+    serde,
+    value::{Path, Value},
+};
+use katsuba_types::TypeList;
+use rusqlite::{types::Value as SqlValue, Connection};
+
+use super::{extract, utils};
+use crate::cli::InputSource;
+
+// The number of rows inserted in one transaction.
+const BATCH_SIZE: usize = 10_000;
+
+/// The type of a column in the database.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColumnType {
+    /// Integers, including enums, booleans, GIDs and raw times.
+    Int,
+    /// Floating-point numbers and integers.
+    Real,
+    /// Strings as they are, everything else in JSON notation.
+    Text,
+    /// Values in JSON notation.
+    Json,
+}
+
+impl ColumnType {
+    fn sql(self) -> &'static str {
+        match self {
+            Self::Int => "INTEGER",
+            Self::Real => "REAL",
+            Self::Text | Self::Json => "TEXT",
+        }
+    }
+
+    // Converts `value` for a column of this type. Values which do
+    // not fit the type are stored as NULL.
+    fn convert(self, value: &Value) -> eyre::Result<SqlValue> {
+        let value = value.resolve();
+        let converted = match self {
+            Self::Int => match *value {
+                Value::Signed(v) | Value::Enum(v) => SqlValue::Integer(v),
+                // SQLite only knows signed integers, so large values
+                // wrap around like they would in C.
+                Value::Unsigned(v) | Value::Gid(v) => SqlValue::Integer(v as i64),
+                Value::Bool(v) => SqlValue::Integer(v as i64),
+                Value::Time(v) => SqlValue::Integer(v.raw),
+                _ => SqlValue::Null,
+            },
+            Self::Real => match *value {
+                Value::Float(v) => SqlValue::Real(v),
+                Value::Float32(v) => SqlValue::Real(v as f64),
+                Value::Signed(v) | Value::Enum(v) => SqlValue::Real(v as f64),
+                Value::Unsigned(v) => SqlValue::Real(v as f64),
+                _ => SqlValue::Null,
+            },
+            Self::Text => match value {
+                Value::String(s) => SqlValue::Text(s.to_string()),
+                Value::WString(s) => SqlValue::Text(s.to_string()),
+                v => SqlValue::Text(serde_json::to_string(v)?),
+            },
+            Self::Json => SqlValue::Text(serde_json::to_string(value)?),
+        };
+
+        if converted == SqlValue::Null {
+            log::debug!(
+                "Storing NULL for {} value in {} column",
+                value.variant_name(),
+                self.sql()
+            );
+        }
+
+        Ok(converted)
+    }
+}
+
+/// A column of the table, given as `PATH:TYPE`.
+#[derive(Clone, Debug)]
+pub struct Field {
+    path: Path,
+    name: String,
+    ty: ColumnType,
+}
+
+impl FromStr for Field {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Paths may contain colons in map keys, the type never does.
+        let (path, ty) = s
+            .rsplit_once(':')
+            .ok_or_else(|| eyre::eyre!("'{s}' is missing a column type, like 'm_id:int'"))?;
+        let ty = ColumnType::from_str(ty, true).map_err(|e| eyre::eyre!("'{s}': {e}"))?;
+
+        Ok(Self {
+            path: path.parse()?,
+            name: path.to_string(),
+            ty,
+        })
+    }
+}
+
+/// Inserts the values at `fields` for every object in the inputs as
+/// one row each into `table` of the database at `output`.
+///
+/// The table is created if it does not exist, with columns for the
+/// input name and the index of the object in it followed by one per
+/// field. The first field is indexed.
+pub fn to_sqlite(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    inputs: InputSource,
+    output: &FsPath,
+    table: &str,
+    fields: &[Field],
+) -> eyre::Result<()> {
+    let mut de = serde::Serializer::new(opts, types)?;
+    let paths: Vec<_> = fields.iter().map(|f| f.path.clone()).collect();
+    de.parts
+        .set_property_filter(extract::root_properties(&paths));
+    let ex = Executor::get()?;
+
+    let conn = Connection::open(output)?;
+    create_schema(&conn, table, fields)?;
+
+    let columns: Vec<_> = ["file", "object"]
+        .into_iter()
+        .chain(fields.iter().map(|f| f.name.as_str()))
+        .map(quote)
+        .collect();
+    let insert = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote(table),
+        columns.join(", "),
+        vec!["?"; columns.len()].join(", ")
+    );
+
+    let mut rows = 0;
+    conn.execute_batch("BEGIN")?;
+    utils::for_each_input(inputs, |name, data| {
+        let objects = match utils::deserialize_roots(&mut de, &ex, opts, data) {
+            Ok(objects) => objects,
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                return Ok(());
+            }
+        };
+
+        let mut stmt = conn.prepare_cached(&insert)?;
+        for (idx, object) in objects.iter().enumerate() {
+            let mut row = vec![
+                SqlValue::Text(name.to_string()),
+                SqlValue::Integer(idx as i64),
+            ];
+            for field in fields {
+                row.push(match object.get_path(&field.path) {
+                    Some(value) => field.ty.convert(value)?,
+                    None => SqlValue::Null,
+                });
+            }
+            stmt.execute(rusqlite::params_from_iter(row))?;
+
+            rows += 1;
+            if rows % BATCH_SIZE == 0 {
+                conn.execute_batch("COMMIT; BEGIN")?;
+            }
+        }
+
+        Ok(())
+    })?;
+    conn.execute_batch("COMMIT")?;
+
+    log::info!("Inserted {rows} rows into '{table}'");
+    Ok(())
+}
+
+fn create_schema(conn: &Connection, table: &str, fields: &[Field]) -> rusqlite::Result<()> {
+    let mut columns = vec![
+        format!("{} TEXT NOT NULL", quote("file")),
+        format!("{} INTEGER NOT NULL", quote("object")),
+    ];
+    columns.extend(
+        fields
+            .iter()
+            .map(|f| format!("{} {}", quote(&f.name), f.ty.sql())),
+    );
+
+    let mut sql = format!(
+        "CREATE TABLE IF NOT EXISTS {} ({});",
+        quote(table),
+        columns.join(", ")
+    );
+    if let Some(first) = fields.first() {
+        sql.push_str(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {} ({});",
+            quote(&format!("{table}_{}", first.name)),
+            quote(table),
+            quote(&first.name)
+        ));
+    }
+
+    conn.execute_batch(&sql)
+}
+
+// Quotes an SQL identifier, so field paths can be used as names.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
@@ -0,0 +1,77 @@
+use std::{
+    collections::HashSet,
+    io::{BufWriter, Write},
+    sync::Arc,
+};
+
+use katsuba_executor::Executor;
+use katsuba_object_property::serde;
+use katsuba_types::TypeList;
+use katsuba_utils::hash::string_id;
+use serde_json::json;
+
+use super::{extract::TableFormat, utils};
+use crate::{cli::InputSource, utils::write_csv_row};
+
+/// Writes every distinct pair of property path and string in the
+/// inputs as one row each, in the order they were first seen.
+///
+/// With `hash`, rows end with the string ID of the string's UTF-8
+/// encoding.
+pub fn strings(
+    opts: serde::SerializerOptions,
+    types: Arc<TypeList>,
+    inputs: InputSource,
+    format: TableFormat,
+    hash: bool,
+) -> eyre::Result<()> {
+    let mut de = serde::Serializer::new(opts, types)?;
+    let ex = Executor::get()?;
+
+    let mut out = BufWriter::new(crate::utils::stdout());
+    if format == TableFormat::Csv {
+        let header = ["path", "string", "string_id"];
+        let columns = if hash { &header[..] } else { &header[..2] };
+        write_csv_row(&mut out, columns.iter().map(|c| c.to_string()))?;
+    }
+
+    let mut seen = HashSet::new();
+    utils::for_each_input(inputs, |name, data| {
+        let objects = match utils::deserialize_roots(&mut de, &ex, opts, data) {
+            Ok(objects) => objects,
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                return Ok(());
+            }
+        };
+
+        for (path, s) in objects.iter().flat_map(|o| o.strings()) {
+            let path = path.to_string();
+            if !seen.insert((path.clone(), s.to_string())) {
+                continue;
+            }
+
+            let id = hash.then(|| string_id(s.as_bytes()));
+            match format {
+                TableFormat::Csv => {
+                    let row = [path, s.into_owned()]
+                        .into_iter()
+                        .chain(id.map(|id| id.to_string()));
+                    write_csv_row(&mut out, row)?;
+                }
+                TableFormat::Jsonl => {
+                    let mut row = json!({ "path": path, "string": s });
+                    if let Some(id) = id {
+                        row["string_id"] = id.into();
+                    }
+                    writeln!(out, "{row}")?;
+                }
+            }
+        }
+
+        Ok(())
+    })?;
+
+    out.flush()?;
+    Ok(())
+}
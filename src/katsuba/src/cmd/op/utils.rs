@@ -1,27 +1,109 @@
-use std::{fs, io::BufReader, path::PathBuf};
+use std::{
+    fmt::Write,
+    io::{self, Read},
+    path::Path,
+};
 
-use eyre::Context;
-use katsuba_types::TypeList;
+use katsuba_executor::Executor;
+use katsuba_object_property::{
+    serde::{PropertyClass, RawSpan, SerializerOptions},
+    Value,
+};
+use katsuba_utils::fs as kfs;
+use serde::{ser::Error, Serialize, Serializer};
+use serde_json::{json, Map};
 
-/// Reads all the given type list paths and merges them into a single
-/// [`TypeList`] instance.
-pub fn merge_type_lists(paths: Vec<PathBuf>) -> eyre::Result<TypeList> {
-    let (first, rest) = paths
-        .split_first()
-        .ok_or_else(|| eyre::eyre!("at least one type list is required for deserialization"))?;
+use crate::cli::InputSource;
 
-    let first = fs::File::open(first)
-        .with_context(|| format!("failed to open type list at '{}'", first.display()))?;
-    let mut list = TypeList::from_reader(BufReader::new(first))?;
+/// A deserialized value with the raw spans of its properties,
+/// if they were captured.
+///
+/// Spans are added to the JSON representation of the root object
+/// under the `$__raw` key, mapping property paths to bit ranges.
+/// The number of strings with invalid UTF-8 is not serialized.
+pub struct Captured {
+    pub value: Value,
+    pub spans: Option<Vec<RawSpan>>,
+    pub invalid_strings: usize,
+}
+
+impl Serialize for Captured {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(spans) = &self.spans else {
+            return self.value.serialize(serializer);
+        };
+
+        let mut value = serde_json::to_value(&self.value).map_err(S::Error::custom)?;
+        let raw: Map<_, _> = spans
+            .iter()
+            .map(|span| {
+                let hex = span.bytes.iter().fold(String::new(), |mut out, b| {
+                    let _ = write!(out, "{b:02x}");
+                    out
+                });
+                let entry = json!({ "start": span.start, "len": span.len, "hex": hex });
 
-    // Merge remaining type lists into `list`.
-    for path in rest {
-        let file = fs::File::open(path)
-            .with_context(|| format!("failed to open type list at '{}'", path.display()))?;
-        let next = TypeList::from_reader(BufReader::new(file))?;
+                (span.path.to_string(), entry)
+            })
+            .collect();
 
-        list.merge(next);
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("$__raw".into(), raw.into());
+        }
+        value.serialize(serializer)
     }
+}
+
+/// Deserializes all root objects in `data`, starting from `opts`.
+pub fn deserialize_roots(
+    de: &mut katsuba_object_property::serde::Serializer,
+    ex: &Executor,
+    opts: SerializerOptions,
+    data: &[u8],
+) -> eyre::Result<Vec<Value>> {
+    // Start every file from the base config again.
+    de.parts.options = opts;
+    let data = de.parts.options.strip_bind_magic(data);
+    let base = de.parts.options;
 
-    Ok(list)
+    let spans = de.index::<PropertyClass>(data)?;
+    de.parts.options = base;
+    let objects = match spans.len() {
+        0 | 1 => vec![de.deserialize::<PropertyClass>(data)?],
+        _ => match de.deserialize_spans::<PropertyClass>(ex, data, &spans)? {
+            Value::List(list) => list.into_iter().collect(),
+            v => vec![v],
+        },
+    };
+
+    Ok(objects)
+}
+
+/// Calls `f` with the name and contents of every input in turn.
+///
+/// Inputs which cannot be read are reported and skipped.
+pub fn for_each_input<F>(inputs: InputSource, mut f: F) -> eyre::Result<()>
+where
+    F: FnMut(&str, &[u8]) -> eyre::Result<()>,
+{
+    let mut file = |path: &Path| {
+        let name = path.display().to_string();
+        match kfs::read_mapped(path) {
+            Ok(data) => f(&name, &data),
+            Err(e) => {
+                log::warn!("Skipping '{name}': {e}");
+                Ok(())
+            }
+        }
+    };
+
+    match inputs {
+        InputSource::Stdin => {
+            let mut data = Vec::new();
+            io::stdin().lock().read_to_end(&mut data)?;
+            f("-", &data)
+        }
+        InputSource::File(path) => file(&path),
+        InputSource::Files(files) => files.iter().try_for_each(|(path, _)| file(path)),
+    }
 }
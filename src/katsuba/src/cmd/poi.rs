@@ -21,8 +21,10 @@ impl Command for Poi {
     fn handle(self) -> eyre::Result<()> {
         match self.command {
             PoiCommand::De(args) => {
+                let batch = args.batch.clone();
                 let (inputs, outputs) = args.evaluate("de.json")?;
                 Processor::new(Bias::Current)?
+                    .with_batch(batch)
                     .read_with(|r, _| PoiFile::parse(r).map_err(Into::into))
                     .write_with(helpers::write_as_json)
                     .process(inputs, outputs)
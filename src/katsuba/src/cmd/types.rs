@@ -0,0 +1,142 @@
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    process,
+};
+
+use clap::{Args, Subcommand};
+use katsuba_types::TypeHashKind;
+
+use super::Command;
+use crate::utils;
+
+mod enums;
+
+mod schema;
+
+/// Subcommand for inspecting type lists.
+#[derive(Debug, Args)]
+pub struct Types {
+    #[clap(subcommand)]
+    command: TypesCommand,
+
+    /// A list of paths to JSON type list files to use.
+    ///
+    /// Multiple files can be provided, which will have their
+    /// entries merged into one type list.
+    #[clap(short, long)]
+    type_lists: Vec<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum TypesCommand {
+    /// Generates a JSON Schema (draft 2020-12) describing the JSON
+    /// that `op de` produces for objects of a class.
+    ///
+    /// The schema covers the default output formats. Every class
+    /// reachable from the given one is described in `$defs`.
+    Schema {
+        /// The name of the class, with or without the `class ` prefix.
+        class: String,
+
+        /// The file to write the schema to.
+        ///
+        /// Defaults to printing to stdout.
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Writes a table of every variant of every enum property in
+    /// the type list.
+    ///
+    /// Rows list the class, the property, its enum type, the name
+    /// and value of the variant, and the property's flags, which
+    /// tell bit flags (`BITS`) apart from plain enums (`ENUM`).
+    Enums {
+        /// The format of the table.
+        #[clap(short, long, value_enum, default_value_t = enums::EnumFormat::Csv)]
+        format: enums::EnumFormat,
+
+        /// Only writes the variants of the enum type with this name.
+        ///
+        /// The `enum ` prefix may be omitted. Can be given several
+        /// times.
+        #[clap(long)]
+        only: Vec<String>,
+    },
+
+    /// Checks the type list for problems which would break
+    /// (de)serialization.
+    ///
+    /// Every problem is printed, and the command exits with status
+    /// 1 when any were found. All checks run when none is selected.
+    Validate {
+        /// Checks for properties of a class which share the same
+        /// hash, and for classes which share the same type hash.
+        #[clap(long)]
+        collisions: bool,
+    },
+}
+
+impl Command for Types {
+    fn handle(self) -> eyre::Result<()> {
+        let types = utils::merge_type_lists(self.type_lists)?;
+
+        match self.command {
+            TypesCommand::Schema { class, output } => {
+                let schema = schema::generate(&types, &class)?;
+
+                let mut out: Box<dyn Write> = match output {
+                    Some(path) => Box::new(BufWriter::new(fs::File::create(path)?)),
+                    None => Box::new(BufWriter::new(utils::stdout())),
+                };
+                serde_json::to_writer_pretty(&mut out, &schema)?;
+                writeln!(out)?;
+                out.flush()?;
+
+                Ok(())
+            }
+
+            TypesCommand::Enums { format, only } => enums::write(&types, format, &only),
+
+            TypesCommand::Validate { collisions } => {
+                let all = !collisions;
+                let mut problems = 0;
+
+                if collisions || all {
+                    let report = types.audit_collisions();
+                    for c in &report.properties {
+                        writeln!(
+                            utils::stdout(),
+                            "{}: properties {} share the hash {:#010x}",
+                            c.class,
+                            c.properties.join(", "),
+                            c.hash
+                        )?;
+                    }
+                    for c in &report.classes {
+                        let kind = match c.kind {
+                            TypeHashKind::StringId => "string ID",
+                            TypeHashKind::Djb2 => "DJB2",
+                        };
+                        writeln!(
+                            utils::stdout(),
+                            "classes {} share the {kind} hash {:#010x}",
+                            c.classes.join(", "),
+                            c.hash
+                        )?;
+                    }
+
+                    problems += report.properties.len() + report.classes.len();
+                }
+
+                if problems > 0 {
+                    process::exit(1);
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
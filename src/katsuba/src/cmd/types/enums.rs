@@ -0,0 +1,71 @@
+use std::io::{BufWriter, Write};
+
+use clap::ValueEnum;
+use katsuba_types::{EnumVariant, StringOrInt, TypeList};
+
+use crate::utils::{self, write_csv_row, write_tsv_row};
+
+const HEADER: [&str; 6] = ["class", "property", "type", "variant", "value", "flags"];
+
+/// The format of enum tables.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum EnumFormat {
+    /// Comma-separated values with RFC 4180 quoting.
+    #[default]
+    Csv,
+    /// Tab-separated values with backslash escapes.
+    Tsv,
+}
+
+/// Writes a row for every variant of every enum property in `types`.
+///
+/// With `only`, just the enum types with one of the given names are
+/// written. The `enum ` prefix of their names may be omitted.
+pub fn write(types: &TypeList, format: EnumFormat, only: &[String]) -> eyre::Result<()> {
+    let mut out = BufWriter::new(utils::stdout());
+    let write_row = |out: &mut BufWriter<_>, cells: Vec<String>| match format {
+        EnumFormat::Csv => write_csv_row(out, cells),
+        EnumFormat::Tsv => write_tsv_row(out, cells),
+    };
+
+    write_row(&mut out, HEADER.map(String::from).to_vec())?;
+    for variant in types.enums().filter(|v| is_selected(v, only)) {
+        write_row(&mut out, row(&variant))?;
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+fn is_selected(variant: &EnumVariant<'_>, only: &[String]) -> bool {
+    let ty = &variant.property.r#type;
+    only.is_empty()
+        || only
+            .iter()
+            .any(|name| ty == name || ty.strip_prefix("enum ") == Some(name))
+}
+
+fn row(variant: &EnumVariant<'_>) -> Vec<String> {
+    // Values which are not numbers are written as they are.
+    let value = match (variant.value, variant.raw) {
+        (Some(value), _) => value.to_string(),
+        (None, StringOrInt::String(raw)) => raw.to_string(),
+        (None, StringOrInt::Int(raw)) => raw.to_string(),
+    };
+
+    let flags: Vec<_> = variant
+        .property
+        .flags
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect();
+
+    vec![
+        variant.class.name.to_string(),
+        variant.property.name.to_string(),
+        variant.property.r#type.to_string(),
+        variant.name.to_string(),
+        value,
+        flags.join("|"),
+    ]
+}
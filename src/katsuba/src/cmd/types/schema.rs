@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+
+use katsuba_types::{Property, PropertyFlags, TemplateType, TypeDef, TypeList};
+use serde_json::{json, Map, Value};
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Generates a JSON Schema for the output of `op de` for objects of
+/// the class called `class`.
+///
+/// Every class reachable from the root gets an entry in `$defs` and
+/// is referenced from there, which keeps cyclic types finite.
+pub fn generate(types: &TypeList, class: &str) -> eyre::Result<Value> {
+    let (hash, def) = types
+        .find(class)
+        .ok_or_else(|| eyre::eyre!("class '{class}' is not in the type list"))?;
+
+    let mut builder = Builder {
+        types,
+        defs: Map::new(),
+        pending: VecDeque::new(),
+    };
+    let root = builder.class_ref(hash, def);
+
+    while let Some((hash, def)) = builder.pending.pop_front() {
+        let schema = builder.class(hash, def);
+        builder.defs.insert(def_name(def).into(), schema);
+    }
+
+    Ok(json!({
+        "$schema": DIALECT,
+        "$ref": root["$ref"],
+        "$defs": builder.defs,
+    }))
+}
+
+struct Builder<'a> {
+    types: &'a TypeList,
+    defs: Map<String, Value>,
+    pending: VecDeque<(u32, &'a TypeDef)>,
+}
+
+impl<'a> Builder<'a> {
+    // Gets a reference to the definition of a class, queueing the
+    // class for generation when it is seen for the first time.
+    fn class_ref(&mut self, hash: u32, def: &'a TypeDef) -> Value {
+        let name = def_name(def);
+        if !self.defs.contains_key(name) {
+            // Reserve the name so cycles end up back here.
+            self.defs.insert(name.into(), Value::Null);
+            self.pending.push_back((hash, def));
+        }
+
+        json!({ "$ref": format!("#/$defs/{}", escape_pointer(name)) })
+    }
+
+    fn class(&mut self, hash: u32, def: &'a TypeDef) -> Value {
+        let mut properties = Map::new();
+        properties.insert("$__type".into(), json!({ "const": hash }));
+        for property in &def.properties {
+            properties.insert(property.name.to_string(), self.property(property));
+        }
+
+        // Properties may be masked out or absent from delta-encoded
+        // state, so only the type tag is always present.
+        json!({
+            "title": def.name.as_str(),
+            "type": "object",
+            "properties": properties,
+            "required": ["$__type"],
+        })
+    }
+
+    fn property(&mut self, property: &Property) -> Value {
+        let value = if property.is_enum() {
+            enum_values(property)
+        } else {
+            self.value(&property.r#type)
+        };
+
+        match property.dynamic {
+            true => json!({ "type": "array", "items": value }),
+            false => value,
+        }
+    }
+
+    fn value(&mut self, ty: &str) -> Value {
+        if let Some(template) = TemplateType::parse(ty) {
+            match (template.name, template.args.as_slice()) {
+                ("std::map", &[key, value]) => {
+                    let entry = self.tuple(&[key, value]);
+                    return json!({ "type": "array", "items": entry });
+                }
+                ("std::pair", &[first, second]) => return self.tuple(&[first, second]),
+                ("SharedPointer" | "Ptr", &[pointee]) => return self.object(pointee),
+                _ => {}
+            }
+        }
+
+        // Without a property, enums may also come out in their
+        // human-readable form.
+        if ty.starts_with("enum ") {
+            return json!({ "type": ["integer", "string"] });
+        }
+
+        leaf(ty).unwrap_or_else(|| self.object(ty))
+    }
+
+    fn tuple(&mut self, types: &[&str]) -> Value {
+        let items: Vec<_> = types.iter().map(|ty| self.value(ty)).collect();
+        json!({
+            "type": "array",
+            "prefixItems": items,
+            "minItems": types.len(),
+            "items": false,
+        })
+    }
+
+    // Objects may be null pointers or instances of any subclass of
+    // the declared type.
+    fn object(&mut self, ty: &str) -> Value {
+        let ty = ty.trim_end_matches(['*', ' ']);
+        let Some((hash, def)) = self.types.find(ty) else {
+            return json!({ "description": format!("unknown type '{ty}'") });
+        };
+
+        let mut classes = vec![(def_name(def), hash, def)];
+        for hash in self.types.subclasses_of(&def.name) {
+            let def = &self.types.0[&hash];
+            classes.push((def_name(def), hash, def));
+        }
+        classes[1..].sort_unstable_by_key(|&(name, ..)| name);
+
+        let mut any_of: Vec<_> = classes
+            .into_iter()
+            .map(|(_, hash, def)| self.class_ref(hash, def))
+            .collect();
+        any_of.push(json!({ "type": "null" }));
+
+        json!({ "anyOf": any_of })
+    }
+}
+
+fn enum_values(property: &Property) -> Value {
+    if property.flags.contains(PropertyFlags::BITS) {
+        return json!({ "type": "integer" });
+    }
+
+    let mut values: Vec<_> = property
+        .enum_options
+        .values()
+        .filter_map(|v| v.to_int())
+        .collect();
+    values.sort_unstable();
+    values.dedup();
+
+    match values.is_empty() {
+        true => json!({ "type": "integer" }),
+        false => json!({ "type": "integer", "enum": values }),
+    }
+}
+
+// Describes the types which are read as simple data.
+fn leaf(ty: &str) -> Option<Value> {
+    let integer = json!({ "type": "integer" });
+    let number = json!({ "type": "number" });
+
+    let schema = match ty {
+        "bool" => json!({ "type": "boolean" }),
+
+        "char" | "unsigned char" | "short" | "unsigned short" | "wchar_t" | "int"
+        | "unsigned int" | "long" | "unsigned long" | "unsigned __int64" | "unsigned long long"
+        | "s24" | "u24" => integer,
+        ty if is_bit_int(ty) => integer,
+
+        // Timestamps are written as their raw values by default.
+        "time_t" | "__time64_t" | "__time32_t" => integer,
+
+        // JSON has no representation of non-finite numbers, so they
+        // are written as strings.
+        "float" | "double" => json!({
+            "anyOf": [number, { "enum": ["NaN", "Infinity", "-Infinity"] }],
+        }),
+
+        "gid" | "union gid" => json!({ "type": "string", "pattern": "^0x[0-9A-F]{16}$" }),
+
+        "std::string" | "std::wstring" | "wchar_t*" => json!({ "type": "string" }),
+
+        "class Color" => fields(&["r", "g", "b", "a"], &integer),
+        "class Vector3D" => fields(&["x", "y", "z"], &number),
+        "class Quaternion" => fields(&["x", "y", "z", "w"], &number),
+        "class Euler" => fields(&["pitch", "yaw", "roll"], &number),
+        "class Matrix3x3" => {
+            let row = json!({
+                "type": "array",
+                "items": number,
+                "minItems": 3,
+                "maxItems": 3,
+            });
+            fields(&["i", "j", "k"], &row)
+        }
+        "class Size<int>" => fields(&["width", "height"], &integer),
+        "class Point<int>" => fields(&["x", "y"], &integer),
+        "class Point<float>" => fields(&["x", "y"], &number),
+        "class Rect<int>" => fields(&["left", "top", "right", "bottom"], &integer),
+        "class Rect<float>" => fields(&["left", "top", "right", "bottom"], &number),
+
+        _ => return None,
+    };
+
+    Some(schema)
+}
+
+fn is_bit_int(ty: &str) -> bool {
+    let bits = ty
+        .strip_prefix("bui")
+        .or_else(|| ty.strip_prefix("bi"))
+        .and_then(|n| n.parse::<u8>().ok());
+
+    matches!(bits, Some(2..=7))
+}
+
+fn fields(names: &[&str], schema: &Value) -> Value {
+    let properties: Map<_, _> = names
+        .iter()
+        .map(|name| (name.to_string(), schema.clone()))
+        .collect();
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": names,
+        "additionalProperties": false,
+    })
+}
+
+fn def_name(def: &TypeDef) -> &str {
+    def.name.strip_prefix("class ").unwrap_or(&def.name)
+}
+
+// Escapes a name for use in a JSON pointer inside a URI fragment.
+fn escape_pointer(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for b in name.bytes() {
+        match b {
+            b'~' => out.push_str("~0"),
+            b'/' => out.push_str("~1"),
+            b if b.is_ascii_alphanumeric() || b"-._!$&'()*+,;=:@".contains(&b) => {
+                out.push(b as char)
+            }
+            b => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+
+    out
+}
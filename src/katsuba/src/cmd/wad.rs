@@ -1,16 +1,29 @@
 use std::{
     fs,
+    io::{self, IsTerminal, Write},
     path::{Path, PathBuf},
+    process,
 };
 
 use clap::{Args, Subcommand};
 use eyre::Context;
-use katsuba_wad::{Archive, ArchiveBuilder};
+use katsuba_wad::{
+    extract::{ExtractOptions, StripPrefix},
+    Archive, ArchiveBuilder, PatchSource,
+};
+use serde_json::json;
 
 use super::Command;
-use crate::cli::{Bias, InputsOutputs, Processor, Reader};
+use crate::{
+    cli::{Bias, InputsOutputs, OutputSource, Processor, Reader},
+    utils,
+};
 
 mod extract;
+mod tarball;
+
+// The exit code when files were missing from partial archives.
+const EXIT_PARTIAL: i32 = 3;
 
 /// Subcommand for working with KIWAD archives.
 #[derive(Debug, Args)]
@@ -47,12 +60,127 @@ enum WadCommand {
         /// be created in the same parent directory.
         #[clap(short)]
         output: Option<PathBuf>,
+
+        /// An existing archive to reuse compressed file data from.
+        ///
+        /// Files which are unchanged from their entry in this archive
+        /// are copied over instead of being compressed again. This
+        /// makes repacking an extracted and slightly modified archive
+        /// a lot faster.
+        #[clap(long)]
+        baseline: Option<PathBuf>,
+    },
+
+    /// Lists the files in a given KIWAD archive.
+    Ls {
+        /// The path to the archive to list.
+        input: PathBuf,
+
+        /// Prints the metadata of every file as JSON instead.
+        ///
+        /// This includes the absolute byte ranges of file data in
+        /// the archive, so other tools can read it directly.
+        #[clap(long)]
+        json: bool,
+
+        /// Lists archives which are still being written.
+        ///
+        /// Files whose data is not there yet are marked unavailable.
+        #[clap(long)]
+        partial: bool,
     },
 
     /// Unpacks all files in a given KIWAD archive into a directory.
     Unpack {
         #[clap(flatten)]
         args: InputsOutputs,
+
+        /// An archive or directory to read unpatched files from.
+        ///
+        /// Some archives only hold zeroed out data for files which
+        /// are shipped elsewhere. These are looked up by their path
+        /// in the patch source, and skipped when they are missing.
+        #[clap(long)]
+        patch_source: Option<PathBuf>,
+
+        /// Writes the files as a tar stream to stdout instead.
+        ///
+        /// This is meant for piping into other tools and requires a
+        /// single input with no output path.
+        #[clap(long)]
+        stdout_tar: bool,
+
+        /// Writes the tar stream even when stdout is a terminal.
+        #[clap(long, requires = "stdout_tar")]
+        force: bool,
+
+        /// Creates all files with the given octal mode, e.g. 755.
+        ///
+        /// By default, files inherit the mode of the archive. On
+        /// platforms other than UNIX, only the lack of write bits is
+        /// respected by making files read-only.
+        #[clap(long, value_parser = parse_mode)]
+        chmod: Option<u32>,
+
+        /// Creates all files with the default mode (0666 minus the
+        /// umask) instead of the archive's mode.
+        #[clap(long, conflicts_with = "chmod")]
+        no_preserve_mode: bool,
+
+        /// Files smaller than this many bytes are written in batches.
+        ///
+        /// Groups small files in the same directory into a single
+        /// I/O task. By default, every file is written on its own.
+        #[clap(long, default_value_t = 0)]
+        batch_threshold: usize,
+
+        /// Skips files which were completed by an interrupted run.
+        ///
+        /// Extracted files are recorded in a journal in the output
+        /// directory of each archive, which is removed again once
+        /// the extraction succeeds. The journal is trusted as-is;
+        /// files on disk are not checked again.
+        #[clap(long, conflicts_with = "stdout_tar")]
+        resume: bool,
+
+        /// Unpacks the files present in archives which are still
+        /// being written.
+        ///
+        /// Files whose data is not there yet are skipped, and the
+        /// command exits with status 3 when there were any.
+        #[clap(long)]
+        partial: bool,
+
+        /// Keeps directories which end up without any files.
+        ///
+        /// These are directory entries in the archive and the parent
+        /// directories of unpatched or unavailable files which were
+        /// skipped. By default, they are left out of the output.
+        #[clap(long)]
+        keep_empty_dirs: bool,
+
+        /// Removes a leading directory from the path of every file,
+        /// e.g. `GameData`. May be given several times.
+        ///
+        /// Files which start with none of the prefixes are an error.
+        /// When several prefixes match, the longest one is removed.
+        #[clap(long, value_name = "PATH")]
+        strip_prefix: Vec<String>,
+
+        /// Keeps the paths of files which start with none of the
+        /// prefixes from `--strip-prefix` instead of failing.
+        #[clap(long, requires = "strip_prefix")]
+        strip_prefix_lenient: bool,
+
+        /// Extracts archives even when their files would not fit
+        /// into the free space at the destination.
+        #[clap(long, conflicts_with = "stdout_tar")]
+        no_space_check: bool,
+
+        /// Prints the number and total size of the files in each
+        /// archive instead of extracting them.
+        #[clap(long, conflicts_with = "stdout_tar")]
+        dry_run: bool,
     },
 }
 
@@ -63,6 +191,7 @@ impl Command for Wad {
                 input,
                 flags,
                 output,
+                baseline,
             } => {
                 if !input.is_dir() {
                     eyre::bail!("input for packing must be a directory");
@@ -82,9 +211,18 @@ impl Command for Wad {
                     }
                 };
 
+                let baseline = baseline
+                    .map(|path| {
+                        Archive::open_mmap(&path).with_context(|| {
+                            format!("failed to open baseline archive '{}'", path.display())
+                        })
+                    })
+                    .transpose()?;
+
                 let mut builder = ArchiveBuilder::new(2, flags, &output).with_context(|| {
                     format!("failed to build output archive at '{}'", output.display())
                 })?;
+                let mut reused = 0;
 
                 for entry in walkdir::WalkDir::new(&input) {
                     let entry = entry.context("failed to query input directory")?;
@@ -100,28 +238,217 @@ impl Command for Wad {
                     let contents = fs::read(path)
                         .with_context(|| format!("failed to read file at '{}'", path.display()))?;
 
-                    builder.add_file_compressed(path.strip_prefix(&input).unwrap(), &contents)?;
+                    let name = path.strip_prefix(&input).unwrap();
+                    if let Some(baseline) = &baseline {
+                        if builder.add_file_from_baseline(name, &contents, baseline)? {
+                            reused += 1;
+                            continue;
+                        }
+                    }
+
+                    builder.add_file_compressed(name, &contents)?;
                 }
 
                 builder.finish()?;
+                if baseline.is_some() {
+                    log::info!("Reused {reused} unchanged files from the baseline archive");
+                }
+
+                Ok(())
+            }
+
+            WadCommand::Ls {
+                input,
+                json,
+                partial,
+            } => {
+                let archive = match partial {
+                    true => Archive::open_mmap_partial(&input)?,
+                    false => Archive::open_mmap(&input)?,
+                };
+
+                let mut stdout = io::BufWriter::new(utils::stdout());
+
+                if json {
+                    serde_json::to_writer_pretty(&mut stdout, &file_listing(&archive))?;
+                    writeln!(stdout)?;
+                } else {
+                    for (name, file) in archive.files() {
+                        write!(stdout, "{name} ({} bytes)", file.uncompressed_size)?;
+                        if file.is_unavailable {
+                            write!(stdout, " (unavailable)")?;
+                        }
+                        writeln!(stdout)?;
+                    }
+                }
 
+                stdout.flush()?;
                 Ok(())
             }
 
-            WadCommand::Unpack { args } => {
+            WadCommand::Unpack {
+                args,
+                patch_source,
+                stdout_tar,
+                force,
+                chmod,
+                no_preserve_mode,
+                batch_threshold,
+                resume,
+                partial,
+                keep_empty_dirs,
+                strip_prefix,
+                strip_prefix_lenient,
+                no_space_check,
+                dry_run,
+            } => {
+                let mode = match no_preserve_mode {
+                    true => Some(katsuba_wad::extract::DEFAULT_MODE),
+                    false => chmod,
+                };
+
+                let strip_prefix = StripPrefix::new(strip_prefix, strip_prefix_lenient);
+
+                let batch = args.batch.clone();
                 let (inputs, outputs) = args.evaluate("")?;
-                Processor::new(Bias::Threaded)?
-                    .read_with(move |r, _| {
-                        let res = match r {
-                            Reader::Stdin(buf) => Archive::from_vec(buf.into_inner()),
-                            Reader::File(_, f) => Archive::mmap(f.into_inner()),
-                        };
-
-                        res.map_err(Into::into)
+                let processor =
+                    Processor::new(Bias::Threaded)?
+                        .with_batch(batch)
+                        .read_with(move |r, _| {
+                            let archive = match r {
+                                Reader::Stdin(buf) => match partial {
+                                    true => Ok(Archive::from_vec_partial(buf.into_inner())?),
+                                    false => Ok(Archive::from_vec(buf.into_inner())?),
+                                },
+                                Reader::File(path, f) => {
+                                    let f = f.into_inner();
+                                    match partial {
+                                        true => Archive::mmap_partial(f),
+                                        false => Archive::mmap(f),
+                                    }
+                                    .with_context(|| {
+                                        format!("failed to open archive '{}'", path.display())
+                                    })
+                                }
+                            }?;
+
+                            match &patch_source {
+                                Some(path) => {
+                                    Ok(archive.with_patch_source(open_patch_source(path)?))
+                                }
+                                None => Ok(archive),
+                            }
+                        });
+
+                // Files missing from partial archives, across all inputs.
+                let mut unavailable = 0;
+
+                if !stdout_tar {
+                    processor
+                        .write_with(|ex, inpath, archive, out| {
+                            let opts = ExtractOptions {
+                                mode,
+                                batch_threshold,
+                                resume,
+                                keep_empty_dirs,
+                                strip_prefix: strip_prefix.clone(),
+                            };
+                            let preflight = extract::Preflight {
+                                check_space: !no_space_check,
+                                dry_run,
+                            };
+                            unavailable += extract::extract_archive(
+                                ex, inpath, archive, out, &opts, preflight,
+                            )?;
+                            Ok(())
+                        })
+                        .process(inputs, outputs)?;
+
+                    return exit_partial(unavailable);
+                }
+
+                if !matches!(outputs, OutputSource::Stdout) {
+                    eyre::bail!("'--stdout-tar' cannot be combined with an output path");
+                }
+
+                let stdout = io::stdout();
+                if stdout.is_terminal() && !force {
+                    eyre::bail!(
+                        "refusing to write a tar stream to a terminal; pass '--force' to do it anyway"
+                    );
+                }
+
+                let mut builder = tar::Builder::new(io::BufWriter::new(stdout.lock()));
+                processor
+                    .write_with(|ex, inpath, archive, _| {
+                        unavailable += tarball::append_archive(
+                            ex,
+                            inpath,
+                            archive,
+                            mode,
+                            keep_empty_dirs,
+                            &strip_prefix,
+                            &mut builder,
+                        )?;
+                        Ok(())
                     })
-                    .write_with(extract::extract_archive)
-                    .process(inputs, outputs)
+                    .process(inputs, outputs)?;
+
+                builder.into_inner()?.flush()?;
+                exit_partial(unavailable)
             }
         }
     }
 }
+
+/// Describes the metadata of every file in `archive` as JSON.
+///
+/// This includes the absolute byte ranges of file data in the
+/// archive, so other tools can read it directly.
+pub fn file_listing(archive: &Archive) -> Vec<serde_json::Value> {
+    archive
+        .files()
+        .iter()
+        .map(|(name, file)| {
+            let span = archive.entry_span(file);
+            json!({
+                "name": name,
+                "offset": span.start,
+                "end": span.end,
+                "compressed": file.compressed,
+                "compressed_size": file.compressed_size,
+                "uncompressed_size": file.uncompressed_size,
+                "crc": file.crc,
+                "available": !file.is_unavailable,
+            })
+        })
+        .collect()
+}
+
+// Exits with a distinct status when only part of the files could be
+// unpacked.
+fn exit_partial(unavailable: usize) -> eyre::Result<()> {
+    if unavailable > 0 {
+        log::warn!("Skipped {unavailable} files which are not yet in the archive");
+        process::exit(EXIT_PARTIAL);
+    }
+
+    Ok(())
+}
+
+fn parse_mode(s: &str) -> Result<u32, String> {
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(format!("'{s}' is not an octal file mode")),
+    }
+}
+
+fn open_patch_source(path: &Path) -> eyre::Result<PatchSource> {
+    if path.is_dir() {
+        return Ok(PatchSource::Dir(path.to_owned()));
+    }
+
+    let archive = Archive::open_mmap(path).context("failed to open patch source")?;
+    Ok(PatchSource::Archive(archive))
+}
@@ -1,88 +1,83 @@
 use std::{
     env,
+    io::Write,
     path::{Path, PathBuf},
+    time::Instant,
 };
 
-use katsuba_executor::{Buffer, Executor, Task};
-use katsuba_wad::{Archive, Inflater};
-
-use crate::{cli::OutputSource, utils::DirectoryTree};
+use katsuba_executor::Executor;
+use katsuba_utils::fs::available_space;
+use katsuba_wad::{
+    extract::{self, is_directory_entry, ExtractError, ExtractOptions, Progress, Source},
+    types::File,
+    Archive,
+};
 
-struct SafeArchiveDrop<'a> {
-    ex: &'a Executor,
-    archive: Archive,
-}
+use crate::{
+    cli::{manifest, metrics, OutputSource},
+    utils,
+};
 
-impl Drop for SafeArchiveDrop<'_> {
-    fn drop(&mut self) {
-        // Join all pending tasks on the executor to make sure none of
-        // them hold onto dangling `archive` references anymore after
-        // dropping it.
-        self.ex.join().for_each(drop);
+// Reports extracted files to the manifest, the metrics and the log.
+struct CliProgress;
+
+impl Progress for CliProgress {
+    fn extracted(
+        &mut self,
+        path: &Path,
+        file: &File,
+        source: Source,
+        started: Instant,
+        contents: &[u8],
+    ) {
+        // Measured before hashing for the manifest.
+        metrics::record_part(contents.len() as u64, started);
+        manifest::record_part(
+            path,
+            file.size() as u64,
+            started,
+            Some(source.name()),
+            contents,
+        );
     }
-}
 
-fn fetch_file_contents<'a>(
-    ex: &'a Executor,
-    archive: &'a Archive,
-    inflater: &mut Inflater,
-    file: &katsuba_wad::types::File,
-) -> eyre::Result<Option<Buffer<'a>>> {
-    if file.is_unpatched {
-        return Ok(None);
+    fn skipped(&mut self, path: &Path) {
+        log::warn!("Skipping unpatched file '{}'", path.display());
     }
 
-    let contents = archive
-        .file_contents(file)
-        .ok_or_else(|| eyre::eyre!("missing file contents in archive"))?;
-
-    match file.compressed {
-        true => {
-            let len = file.uncompressed_size as usize;
-
-            ex.request_buffer(len, |buf| {
-                buf.resize(len, 0);
-                inflater.decompress_into(buf, contents)?;
-
-                Ok(())
-            })
-            .map(Some)
-        }
-
-        false => Ok(Some(Buffer::borrowed(contents))),
+    fn unavailable(&mut self, path: &Path) {
+        log::debug!("Skipping unavailable file '{}'", path.display());
     }
 }
 
-fn create_directory_tree(ex: &Executor, archive: &Archive, out: &Path) -> eyre::Result<()> {
-    // Pre-compute the directory structure we need to create.
-    let mut tree = DirectoryTree::new();
-    for file in archive.files().keys() {
-        tree.add(file.as_ref());
-    }
-
-    // Create all the directories with minimal required syscalls.
-    for path in tree {
-        let task = Task::create_dir(out.join(path));
-        for pending in ex.dispatch(task) {
-            pending?;
-        }
-    }
-
-    // Join all pending operations here so we don't accidentally
-    // try to write into directories that don't exist yet.
-    for pending in ex.join() {
-        pending?;
-    }
+/// Checks to run before extracting an archive.
+#[derive(Clone, Copy, Debug)]
+pub struct Preflight {
+    /// Whether to abort when the destination lacks the space for
+    /// the extracted files.
+    pub check_space: bool,
+    /// Whether to only print the size of the extracted files
+    /// instead of extracting them.
+    pub dry_run: bool,
+}
 
-    Ok(())
+// Whether a file takes up space when extracted.
+fn is_written(name: &str, file: &File) -> bool {
+    !is_directory_entry(name) && !file.is_unavailable
 }
 
+/// Extracts `archive` into a directory named after the input.
+///
+/// Returns the number of files which were unavailable in a partial
+/// archive.
 pub fn extract_archive(
     ex: &Executor,
     inpath: Option<PathBuf>,
     archive: Archive,
     out: OutputSource,
-) -> eyre::Result<()> {
+    opts: &ExtractOptions,
+    preflight: Preflight,
+) -> eyre::Result<usize> {
     // Determine the output directory for the archive files.
     // Since we can't print here, we use the cwd instead.
     let input_stem = inpath.as_ref().and_then(|p| p.file_stem()).unwrap();
@@ -92,37 +87,72 @@ pub fn extract_archive(
     };
     out.push(input_stem);
 
-    // First, create all the directories for the output files.
-    create_directory_tree(ex, &archive, &out)?;
-
-    // This guard ensures we can safely share references into `archive`
-    // with the pool without risking dangling in the case of an error.
-    let sad = SafeArchiveDrop { ex, archive };
-    let mode = sad.archive.mode();
-
-    // Next, we do the extraction of data out of the archive on the
-    // current thread while simultaneously dispatching the file I/O
-    // operations to the executor.
-    let mut inflater = Inflater::new();
-    for (path, file) in sad.archive.files() {
-        let path = out.join(path);
-
-        // SAFETY: We can never end up with dangling references into
-        // `archive` because `sad` joins all pending tasks on drop.
-        let buffer = match fetch_file_contents(ex, &sad.archive, &mut inflater, file)? {
-            Some(buf) => buf,
-            None => {
-                log::warn!("Skipping unpatched file '{}'", path.display());
-                continue;
-            }
-        };
-        let buffer = unsafe { buffer.extend_lifetime() };
+    // Unpatched files may still be skipped, so this errs on the
+    // side of caution.
+    let total = archive.total_uncompressed(is_written);
+    if preflight.dry_run {
+        opts.strip_prefix.check(&archive)?;
+        let files = archive
+            .files()
+            .iter()
+            .filter(|(name, file)| is_written(name, file))
+            .count();
+        let mut stdout = utils::stdout();
+        write!(stdout, "{}: {files} files, {total} bytes", out.display())?;
+        match available_space(&out) {
+            Ok(available) => writeln!(stdout, " ({available} bytes available)")?,
+            Err(_) => writeln!(stdout)?,
+        }
+
+        return Ok(0);
+    }
+
+    if preflight.check_space {
+        match available_space(&out) {
+            Ok(available) if available < total => eyre::bail!(
+                "not enough space to extract '{}': {total} bytes are needed, but only \
+                 {available} are available; pass '--no-space-check' to extract anyway",
+                out.display()
+            ),
+            Ok(_) => {}
+            Err(e) => log::warn!(
+                "Failed to query available space for '{}', extracting anyway: {e}",
+                out.display()
+            ),
+        }
+    }
+
+    let report = match extract::extract(ex, &archive, &out, opts, CliProgress) {
+        Ok(report) => report,
 
-        let task = Task::create_file(path, buffer, mode);
-        for pending in ex.dispatch(task) {
-            pending?;
+        // The error only lists the first few failures, so make the
+        // rest available with verbose logging.
+        Err(ExtractError::Write(failures)) => {
+            for error in &failures.errors {
+                log::debug!("{error}");
+            }
+            eyre::bail!("failed to extract '{}': {failures}", out.display());
         }
+
+        Err(e) => return Err(e.into()),
+    };
+
+    if report.resumed > 0 {
+        log::info!(
+            "Skipped {} files extracted by a previous run",
+            report.resumed
+        );
+    }
+
+    if report.from_patch > 0 || report.skipped > 0 {
+        log::info!(
+            "Extracted {} files from the archive and {} from the patch \
+             source, skipped {} unpatched files",
+            report.from_archive,
+            report.from_patch,
+            report.skipped
+        );
     }
 
-    Ok(())
+    Ok(report.unavailable)
 }
@@ -0,0 +1,144 @@
+use std::{
+    collections::BTreeSet,
+    io::Write,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use katsuba_executor::Executor;
+use katsuba_wad::{
+    extract::{fetch_file_contents, is_directory_entry, Source, StripPrefix},
+    Archive, Inflater,
+};
+use tar::{Builder, EntryType, Header};
+
+use crate::cli::{manifest, metrics};
+
+/// Appends all files in `archive` to the tar stream in `builder`.
+///
+/// Entries are written one at a time as soon as their contents are
+/// available, so at most one file is held in memory. They are put
+/// in a directory named after the input file, if there is one.
+///
+/// Entries are named without the prefixes in `strip`.
+///
+/// With `keep_empty_dirs`, directory entries of the archive and the
+/// parents of skipped files get entries of their own.
+///
+/// Returns the number of files which were unavailable in a partial
+/// archive.
+pub fn append_archive<W: Write>(
+    ex: &Executor,
+    inpath: Option<PathBuf>,
+    archive: Archive,
+    mode: Option<u32>,
+    keep_empty_dirs: bool,
+    strip: &StripPrefix,
+    builder: &mut Builder<W>,
+) -> eyre::Result<usize> {
+    strip.check(&archive)?;
+
+    let prefix = inpath
+        .as_ref()
+        .and_then(|p| p.file_stem())
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let mode = mode.unwrap_or_else(|| archive.mode()) & 0o7777;
+
+    let mut inflater = Inflater::new();
+    let (mut from_archive, mut from_patch, mut skipped, mut unavailable) = (0, 0, 0, 0);
+    let mut empty_dirs = BTreeSet::new();
+    for (name, file) in archive.files() {
+        ex.cancellation_token().check()?;
+
+        let path = prefix.join(strip.strip(name)?);
+        if is_directory_entry(name) {
+            empty_dirs.insert(path);
+            continue;
+        }
+
+        if file.is_unavailable {
+            log::debug!("Skipping unavailable file '{}'", path.display());
+            unavailable += 1;
+            empty_dirs.extend(path.parent().map(Path::to_path_buf));
+            continue;
+        }
+
+        let started = Instant::now();
+
+        let (buffer, source) = match fetch_file_contents(ex, &archive, &mut inflater, name, file)? {
+            Some(res) => res,
+            None => {
+                log::warn!("Skipping unpatched file '{}'", path.display());
+                skipped += 1;
+                empty_dirs.extend(path.parent().map(Path::to_path_buf));
+                continue;
+            }
+        };
+        // Measured before hashing for the manifest.
+        metrics::record_part(buffer.len() as u64, started);
+        manifest::record_part(
+            &path,
+            file.size() as u64,
+            started,
+            Some(source.name()),
+            &buffer,
+        );
+
+        match source {
+            Source::Archive => from_archive += 1,
+            Source::Patch => from_patch += 1,
+        }
+
+        append_file(builder, &path, mode, &buffer)?;
+    }
+
+    if keep_empty_dirs {
+        for dir in empty_dirs {
+            if !dir.as_os_str().is_empty() {
+                append_dir(builder, &dir, mode)?;
+            }
+        }
+    }
+
+    if from_patch > 0 || skipped > 0 {
+        log::info!(
+            "Wrote {from_archive} files from the archive and {from_patch} \
+             from the patch source, skipped {skipped} unpatched files"
+        );
+    }
+
+    Ok(unavailable)
+}
+
+fn append_file<W: Write>(
+    builder: &mut Builder<W>,
+    path: &Path,
+    mode: u32,
+    data: &[u8],
+) -> eyre::Result<()> {
+    // Archives carry no timestamps, so entries are stamped with the
+    // epoch for reproducible output.
+    let mut header = Header::new_gnu();
+    header.set_entry_type(EntryType::Regular);
+    header.set_size(data.len() as u64);
+    header.set_mode(mode);
+    header.set_mtime(0);
+
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+fn append_dir<W: Write>(builder: &mut Builder<W>, path: &Path, mode: u32) -> eyre::Result<()> {
+    // Directories need to be searchable wherever they are readable.
+    let mode = mode | (mode & 0o444) >> 2;
+
+    let mut header = Header::new_gnu();
+    header.set_entry_type(EntryType::Directory);
+    header.set_size(0);
+    header.set_mode(mode);
+    header.set_mtime(0);
+
+    builder.append_data(&mut header, path, &[][..])?;
+    Ok(())
+}
@@ -0,0 +1,238 @@
+use std::{collections::BTreeMap, io::Cursor, path::Path};
+
+use clap::{Args, Subcommand};
+use katsuba_bcd::{Bcd, GeomParams};
+use katsuba_nav::{NavigationGraph, ZoneNavigationGraph};
+use katsuba_poi::Poi;
+use katsuba_wad::{extract::is_directory_entry, types::File, Archive, Inflater};
+use serde_json::{json, Value};
+
+use super::Command;
+use crate::{
+    cli::{helpers, Bias, InputsOutputs, Processor},
+    utils::detect::{self, Detection, Format},
+};
+
+// The zone data found in zone archives, by file extension and the
+// key under which it is summarized.
+const MEMBERS: [(&str, &str); 3] = [
+    ("bcd", "collision"),
+    ("nav", "navigation"),
+    ("poi", "points_of_interest"),
+];
+
+/// Subcommand for working with game zones.
+#[derive(Debug, Args)]
+pub struct Zone {
+    #[clap(subcommand)]
+    command: ZoneCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ZoneCommand {
+    /// Summarizes the data in given zone archives as JSON.
+    ///
+    /// Every archive produces one document with an inventory of its
+    /// files and summaries of its collision (BCD), navigation (NAV)
+    /// and point of interest (POI) files, which are found by their
+    /// extensions. Missing files and files which fail to parse are
+    /// listed in the document.
+    Info(InputsOutputs),
+}
+
+impl Command for Zone {
+    fn handle(self) -> eyre::Result<()> {
+        match self.command {
+            ZoneCommand::Info(args) => {
+                let batch = args.batch.clone();
+                let (inputs, outputs) = args.evaluate("zone.json")?;
+                Processor::new(Bias::Current)?
+                    .with_batch(batch)
+                    .read_with(|mut r, ex| {
+                        let archive = Archive::from_vec(r.get_buffer(ex)?.to_vec())?;
+                        Ok(summarize(&archive))
+                    })
+                    .write_with(helpers::write_as_json)
+                    .process(inputs, outputs)
+            }
+        }
+    }
+}
+
+fn summarize(archive: &Archive) -> Value {
+    let mut inflater = Inflater::new();
+    let mut names = Vec::new();
+    let mut extensions: BTreeMap<String, usize> = BTreeMap::new();
+    let mut uncompressed_size = 0;
+    let mut members: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    for (name, file) in archive.files() {
+        if is_directory_entry(name) {
+            continue;
+        }
+
+        names.push(name.as_str());
+        uncompressed_size += file.size() as u64;
+
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+        *extensions.entry(extension.clone()).or_default() += 1;
+
+        let Some((_, key)) = MEMBERS.iter().find(|(ext, _)| *ext == extension) else {
+            continue;
+        };
+
+        match read_member(archive, &mut inflater, name, file) {
+            Ok(mut summary) => {
+                summary["name"] = name.as_str().into();
+                members.entry(key).or_default().push(summary);
+            }
+            Err(e) => {
+                log::warn!("Failed to read '{name}': {e}");
+                errors.push(json!({ "name": name, "error": e.to_string() }));
+            }
+        }
+    }
+
+    let missing: Vec<_> = MEMBERS
+        .iter()
+        .filter(|(ext, _)| !extensions.contains_key(*ext))
+        .map(|(ext, _)| *ext)
+        .collect();
+
+    let mut summary = json!({
+        "files": {
+            "count": names.len(),
+            "uncompressed_size": uncompressed_size,
+            "extensions": extensions,
+            "names": names,
+        },
+        "missing": missing,
+        "errors": errors,
+    });
+    for (_, key) in MEMBERS {
+        summary[key] = members.remove(key).unwrap_or_default().into();
+    }
+
+    summary
+}
+
+fn read_member(
+    archive: &Archive,
+    inflater: &mut Inflater,
+    name: &str,
+    file: &File,
+) -> eyre::Result<Value> {
+    let contents = archive
+        .file_contents(file)
+        .ok_or_else(|| eyre::eyre!("contents are not in the archive"))?;
+    let data = match file.compressed {
+        true => inflater.decompress(contents, file.uncompressed_size as usize)?,
+        false => contents,
+    };
+
+    match detect::detect(Some(Path::new(name)), data) {
+        Detection::Known(Format::Bcd) => Ok(collision(&Bcd::parse(Cursor::new(data))?)),
+        Detection::Known(Format::Nav) => Ok(navigation(
+            &NavigationGraph::parse(Cursor::new(data))?,
+            None,
+        )),
+        Detection::Known(Format::ZoneNav) => {
+            let nav = ZoneNavigationGraph::parse(Cursor::new(data))?;
+            Ok(navigation(&nav.graph, Some(&nav.zone_names)))
+        }
+        Detection::Known(Format::Poi) => Ok(points_of_interest(&Poi::parse(Cursor::new(data))?)),
+        _ => eyre::bail!("not a zone data file"),
+    }
+}
+
+fn collision(bcd: &Bcd) -> Value {
+    let mut shapes: BTreeMap<&str, usize> = BTreeMap::new();
+    for collision in &bcd.collisions {
+        let shape = match collision.geometry.params {
+            GeomParams::Box { .. } => "box",
+            GeomParams::Ray { .. } => "ray",
+            GeomParams::Sphere { .. } => "sphere",
+            GeomParams::Cylinder { .. } => "cylinder",
+            GeomParams::Tube { .. } => "tube",
+            GeomParams::Plane { .. } => "plane",
+            GeomParams::Mesh => "mesh",
+        };
+        *shapes.entry(shape).or_default() += 1;
+    }
+
+    json!({
+        "collisions": bcd.collisions.len(),
+        "shapes": shapes,
+        "unknown_trailing_size": bcd.unknown_trailing.len(),
+    })
+}
+
+fn navigation(graph: &NavigationGraph, zone_names: Option<&[String]>) -> Value {
+    let bounds = graph.nodes.split_first().map(|(first, rest)| {
+        let (mut min, mut max) = (first.location, first.location);
+        for node in rest {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(node.location[axis]);
+                max[axis] = max[axis].max(node.location[axis]);
+            }
+        }
+
+        json!({ "min": min, "max": max })
+    });
+
+    let mut summary = json!({
+        "nodes": graph.nodes.len(),
+        "links": graph.links.len(),
+        "bounds": bounds,
+    });
+    if let Some(zone_names) = zone_names {
+        summary["zone_names"] = zone_names.into();
+    }
+
+    summary
+}
+
+fn points_of_interest(poi: &Poi) -> Value {
+    let mut goals: Vec<_> = poi.goals.iter().collect();
+    goals.sort_by_key(|(id, _)| **id);
+
+    let goals: Vec<_> = goals
+        .into_iter()
+        .map(|(id, point)| {
+            json!({
+                "id": id,
+                "template_id": point.template_id,
+                "zone_id": point.zone_id,
+                "location": point.location,
+                "interactable": point.interactable,
+                "collectable": point.collectable,
+            })
+        })
+        .collect();
+
+    let mut teleporters: Vec<_> = poi.teleporters.iter().collect();
+    teleporters.sort_by_key(|(zone, _)| **zone);
+    let teleporters: Vec<_> = teleporters
+        .into_iter()
+        .flat_map(|(zone, list)| {
+            list.iter().map(move |t| {
+                json!({
+                    "zone_id": zone,
+                    "destination": t.destination,
+                    "position": t.position,
+                })
+            })
+        })
+        .collect();
+
+    json!({
+        "zone_names": poi.zone_names,
+        "goals": goals,
+        "teleporters": teleporters,
+    })
+}
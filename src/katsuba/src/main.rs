@@ -4,7 +4,9 @@
     unsafe_op_in_unsafe_fn
 )]
 
-use clap::Parser;
+use std::process;
+
+use clap::{CommandFactory, Parser};
 
 mod cli;
 use cli::Cli;
@@ -21,7 +23,20 @@ fn main() -> eyre::Result<()> {
     color_eyre::install()?;
 
     let cli = Cli::parse();
+    if cli.capabilities {
+        return cli::capabilities::print(Cli::command());
+    }
+
     cli.verbosity.setup();
+    cli.output.setup();
+
+    let res = cli.into_command().handle();
+    utils::finish_pager();
 
-    cli.command.handle()
+    match res {
+        // Writers other than `utils::stdout` surface a closed pipe as
+        // an error, see `utils::EXIT_BROKEN_PIPE`.
+        Err(e) if utils::is_broken_pipe(&e) => process::exit(utils::EXIT_BROKEN_PIPE),
+        res => res,
+    }
 }
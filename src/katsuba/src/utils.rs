@@ -1,9 +1,20 @@
+pub mod detect;
+
 mod io;
 pub use io::*;
 
+mod pager;
+pub use pager::*;
+
 mod serde;
 pub use serde::*;
 
+mod table;
+pub use table::*;
+
+mod types;
+pub use types::*;
+
 /// Converts a [`bool`] value into a human-readable description.
 #[inline]
 pub fn human_bool(v: bool) -> &'static str {
@@ -0,0 +1,200 @@
+//! Detection of file formats from their contents and names.
+//!
+//! Only KIWAD archives and ObjectProperty game files carry magic
+//! bytes, the other formats are recognized by their extension. NAV
+//! graphs and zone NAV graphs share one, so their sizes tell them
+//! apart.
+
+use std::{fmt, path::Path};
+
+use katsuba_object_property::serde::BIND_MAGIC;
+
+const WAD_MAGIC: &[u8] = b"KIWAD";
+
+// The byte sizes of the parts of a NAV graph.
+const NAV_HEADER: usize = 6;
+const NAV_NODE: usize = 14;
+const NAV_LINK: usize = 4;
+
+/// A file format known to Katsuba.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Wad,
+    ObjectProperty,
+    Bcd,
+    Nav,
+    ZoneNav,
+    Poi,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Wad => "KIWAD archive",
+            Self::ObjectProperty => "ObjectProperty game file",
+            Self::Bcd => "BCD",
+            Self::Nav => "NAV graph",
+            Self::ZoneNav => "zone NAV graph",
+            Self::Poi => "POI",
+        })
+    }
+}
+
+/// The outcome of detecting the format of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Detection {
+    /// The file is in the given format.
+    Known(Format),
+    /// The contents and the name of the file suggest different
+    /// formats.
+    Ambiguous(Vec<Format>),
+    /// The format could not be determined.
+    Unknown,
+}
+
+/// Detects the format of `data`, optionally read from `path`.
+pub fn detect(path: Option<&Path>, data: &[u8]) -> Detection {
+    let by_magic = if data.starts_with(WAD_MAGIC) {
+        Some(Format::Wad)
+    } else if data.starts_with(BIND_MAGIC) {
+        Some(Format::ObjectProperty)
+    } else {
+        None
+    };
+
+    let extension = path
+        .and_then(|p| p.extension())
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+    let by_extension = match extension.as_deref() {
+        Some("wad") => Some(Format::Wad),
+        Some("bcd") => Some(Format::Bcd),
+        Some("poi") => Some(Format::Poi),
+        Some("nav") => Some(nav_kind(data)),
+        _ => None,
+    };
+
+    match (by_magic, by_extension) {
+        (Some(a), Some(b)) if a != b => Detection::Ambiguous(vec![a, b]),
+        // Game files are named after what they describe, so a
+        // missing or unusual extension is expected.
+        (Some(f), _) => Detection::Known(f),
+        // Archives always have their magic.
+        (None, Some(Format::Wad)) => Detection::Unknown,
+        (None, Some(f)) => Detection::Known(f),
+        (None, None) => Detection::Unknown,
+    }
+}
+
+// Zone NAV graphs are regular graphs followed by zone names, so
+// any data after the graph makes it one.
+fn nav_kind(data: &[u8]) -> Format {
+    match nav_graph_len(data) {
+        Some(len) if len < data.len() => Format::ZoneNav,
+        _ => Format::Nav,
+    }
+}
+
+fn nav_graph_len(data: &[u8]) -> Option<usize> {
+    let read_u32 = |at: usize| -> Option<usize> {
+        let bytes = data.get(at..at + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    };
+
+    let nodes = read_u32(2)?;
+    let links_at = NAV_NODE.checked_mul(nodes)?.checked_add(NAV_HEADER)?;
+    let links = read_u32(links_at)?;
+
+    NAV_LINK.checked_mul(links)?.checked_add(links_at + 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect_named(name: &str, data: &[u8]) -> Detection {
+        detect(Some(Path::new(name)), data)
+    }
+
+    fn nav_graph() -> Vec<u8> {
+        let mut data = vec![1, 0];
+        data.extend(1u32.to_le_bytes());
+        data.extend([0; NAV_NODE]);
+        data.extend(0u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn wad() {
+        let data = b"KIWAD\x02\0\0\0";
+
+        assert_eq!(
+            detect_named("Root.wad", data),
+            Detection::Known(Format::Wad)
+        );
+        assert_eq!(detect(None, data), Detection::Known(Format::Wad));
+        assert_eq!(detect_named("Root.wad", b"KIWA"), Detection::Unknown);
+    }
+
+    #[test]
+    fn object_property() {
+        let data = b"BINd\0\0\0\0";
+
+        assert_eq!(
+            detect_named("Template.xml", data),
+            Detection::Known(Format::ObjectProperty)
+        );
+        assert_eq!(detect(None, data), Detection::Known(Format::ObjectProperty));
+    }
+
+    #[test]
+    fn bcd() {
+        assert_eq!(
+            detect_named("Collision.BCD", &[0; 4]),
+            Detection::Known(Format::Bcd)
+        );
+        assert_eq!(detect(None, &[0; 4]), Detection::Unknown);
+    }
+
+    #[test]
+    fn nav() {
+        let mut data = nav_graph();
+        assert_eq!(
+            detect_named("Graph.nav", &data),
+            Detection::Known(Format::Nav)
+        );
+
+        data.extend(0u32.to_le_bytes());
+        assert_eq!(
+            detect_named("Graph.nav", &data),
+            Detection::Known(Format::ZoneNav)
+        );
+
+        // Truncated or nonsensical sizes are left to the parser.
+        assert_eq!(
+            detect_named("Graph.nav", &[0xff; 7]),
+            Detection::Known(Format::Nav)
+        );
+    }
+
+    #[test]
+    fn poi() {
+        assert_eq!(
+            detect_named("Points.poi", &[0; 24]),
+            Detection::Known(Format::Poi)
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        assert_eq!(
+            detect_named("Collision.bcd", b"KIWAD\x02\0\0\0"),
+            Detection::Ambiguous(vec![Format::Wad, Format::Bcd])
+        );
+        assert_eq!(
+            detect_named("Root.wad", b"BINd"),
+            Detection::Ambiguous(vec![Format::ObjectProperty, Format::Wad])
+        );
+        assert_eq!(detect_named("notes.txt", b"hello"), Detection::Unknown);
+    }
+}
@@ -1,11 +1,11 @@
 use std::{
-    collections::{hash_set::IntoIter, HashSet},
     io::{self, IsTerminal},
-    path::Path,
     process,
+    sync::OnceLock,
 };
 
 use clap::CommandFactory;
+use katsuba_executor::CancellationToken;
 
 use crate::cli::Cli;
 
@@ -23,45 +23,33 @@ pub fn stdin_reader() -> io::BufReader<io::StdinLock<'static>> {
     io::BufReader::new(stdin.lock())
 }
 
-/// A structure which interns directory trees from given file paths
-/// and returns the minimal amount of paths to be created.
+/// Gets the token which is cancelled when the user hits Ctrl-C.
 ///
-/// This ensures that we create a directory tree with the least
-/// required system calls. This has shown to greatly impact
-/// performance on Windows systems.
-pub struct DirectoryTree<'a> {
-    inner: HashSet<&'a Path>,
-}
-
-impl<'a> DirectoryTree<'a> {
-    /// Creates an empty directory tree.
-    pub fn new() -> Self {
-        Self {
-            inner: HashSet::new(),
-        }
-    }
-
-    /// Given a path to a file, interns the directory tree needed
-    /// to be created for it.
-    pub fn add(&mut self, path: &'a Path) {
-        if let Some(p) = path.parent() {
-            self.inner.insert(p);
-
-            // Check for parent of parent so that we can remove
-            // entries which would just need unnecessary syscalls.
-            if let Some(p) = p.parent() {
-                self.inner.remove(p);
+/// The SIGINT handler is installed on first call. Afterwards, the
+/// first interrupt only cancels the token so that pending work can
+/// shut down gracefully, and a second one terminates the process.
+pub fn interrupt_token() -> CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+
+    TOKEN
+        .get_or_init(|| {
+            let token = CancellationToken::new();
+
+            let handler_token = token.clone();
+            let res = ctrlc::set_handler(move || {
+                if handler_token.cancel() {
+                    process::exit(130);
+                }
+
+                log::warn!(
+                    "Interrupted; finishing pending work (press Ctrl-C again to force exit)"
+                );
+            });
+            if let Err(e) = res {
+                log::warn!("Failed to install Ctrl-C handler: {e}");
             }
-        }
-    }
-}
-
-impl<'a> IntoIterator for DirectoryTree<'a> {
-    type Item = &'a Path;
 
-    type IntoIter = IntoIter<Self::Item>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        self.inner.into_iter()
-    }
+            token
+        })
+        .clone()
 }
@@ -0,0 +1,226 @@
+use std::{
+    env, error,
+    io::{self, IsTerminal, Write},
+    process::{self, Child, ChildStdin, Command, Stdio},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use clap::ValueEnum;
+
+/// The exit code when the reader of stdout goes away.
+///
+/// Shells report 141 for processes killed by `SIGPIPE`, but that fails
+/// pipelines like `katsuba wad ls Root.wad | head` under `pipefail`.
+/// Stopping early is what the reader asked for, so it is a success.
+pub const EXIT_BROKEN_PIPE: i32 = 0;
+
+/// When output to stdout is paged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PagerChoice {
+    /// Only when stdout is a terminal.
+    #[default]
+    Auto,
+    /// Always, even when stdout is not a terminal.
+    Always,
+    /// Never.
+    Never,
+}
+
+// Where output to stdout goes.
+enum Sink {
+    Direct,
+    Pager(ChildStdin),
+    // Output to a terminal without a pager, cut off after a number
+    // of lines.
+    Limited { lines: usize, left: usize },
+    // The line limit was reached.
+    Closed,
+}
+
+struct State {
+    choice: PagerChoice,
+    max_lines: usize,
+    // Opened on the first write, so commands without output to stdout
+    // never start a pager.
+    sink: Option<Sink>,
+    pager: Option<Child>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    choice: PagerChoice::Never,
+    max_lines: 0,
+    sink: None,
+    pager: None,
+});
+
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Configures how output written through [`stdout`] is paged.
+///
+/// The pager is taken from `$PAGER`. When it is not set or cannot be
+/// started, output to a terminal is cut off after `max_lines` lines
+/// instead, unless that is 0.
+pub fn set_pager(choice: PagerChoice, max_lines: usize) {
+    let mut state = state();
+    state.choice = choice;
+    state.max_lines = max_lines;
+}
+
+/// Waits for the pager to exit, if one was started.
+///
+/// This must be called before the process exits, so that the pager
+/// gets to show all output.
+pub fn finish_pager() {
+    let mut state = state();
+
+    // Closing its stdin tells the pager there is no more output.
+    state.sink = None;
+    if let Some(mut pager) = state.pager.take() {
+        let _ = pager.wait();
+    }
+}
+
+/// Gets a writer to stdout which goes through the configured pager.
+///
+/// All commands write to stdout through this. Once the pager or the
+/// process reading stdout exits, the process exits quietly with
+/// [`EXIT_BROKEN_PIPE`].
+pub fn stdout() -> Stdout {
+    Stdout(())
+}
+
+/// The writer returned by [`stdout`].
+pub struct Stdout(());
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        state().write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = state();
+        let res = match &mut state.sink {
+            Some(Sink::Pager(stdin)) => stdin.flush(),
+            Some(Sink::Direct | Sink::Limited { .. }) => io::stdout().flush(),
+            Some(Sink::Closed) | None => Ok(()),
+        };
+
+        state.exit_on_broken_pipe(res)
+    }
+}
+
+impl State {
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        if self.sink.is_none() {
+            self.sink = Some(self.open());
+        }
+
+        let res = match self.sink.as_mut().unwrap() {
+            Sink::Direct => io::stdout().write_all(buf),
+            Sink::Pager(stdin) => stdin.write_all(buf),
+            Sink::Limited { lines, left } => {
+                let mut newlines = buf.iter().enumerate().filter(|(_, b)| **b == b'\n');
+                match newlines.nth(*left - 1) {
+                    Some((end, _)) => {
+                        let lines = *lines;
+                        self.sink = Some(Sink::Closed);
+
+                        let mut stdout = io::stdout().lock();
+                        let res = stdout.write_all(&buf[..=end]).and_then(|()| stdout.flush());
+                        log::warn!(
+                            "Stopped output after {lines} lines; write it to a file with \
+                             '-o' or set $PAGER to page through it"
+                        );
+
+                        res
+                    }
+                    None => {
+                        *left -= count_lines(buf);
+                        io::stdout().write_all(buf)
+                    }
+                }
+            }
+            Sink::Closed => Ok(()),
+        };
+
+        self.exit_on_broken_pipe(res)
+    }
+
+    fn open(&mut self) -> Sink {
+        let terminal = io::stdout().is_terminal();
+        match self.choice {
+            PagerChoice::Never => return Sink::Direct,
+            PagerChoice::Auto if !terminal => return Sink::Direct,
+            _ => (),
+        }
+
+        if let Some(command) = env::var("PAGER").ok().filter(|p| !p.trim().is_empty()) {
+            match spawn_pager(&command) {
+                Ok(mut pager) => {
+                    let stdin = pager.stdin.take().unwrap();
+                    self.pager = Some(pager);
+                    return Sink::Pager(stdin);
+                }
+                Err(e) => log::warn!("Failed to start pager '{command}': {e}"),
+            }
+        }
+
+        match self.max_lines {
+            lines @ 1.. if terminal => Sink::Limited { lines, left: lines },
+            _ => Sink::Direct,
+        }
+    }
+
+    // Ends the process when the reader of its output is gone, since
+    // there is no one left to do any further work for.
+    fn exit_on_broken_pipe(&mut self, res: io::Result<()>) -> io::Result<()> {
+        match res {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.sink = None;
+                if let Some(mut pager) = self.pager.take() {
+                    let _ = pager.wait();
+                }
+
+                process::exit(EXIT_BROKEN_PIPE);
+            }
+            res => res,
+        }
+    }
+}
+
+fn spawn_pager(command: &str) -> io::Result<Child> {
+    let mut parts = command.split_whitespace();
+    let mut pager = Command::new(parts.next().unwrap());
+    pager.args(parts).stdin(Stdio::piped());
+
+    // Like git, make less exit for output which fits on one screen
+    // and pass colors through.
+    if env::var_os("LESS").is_none() {
+        pager.env("LESS", "FRX");
+    }
+
+    pager.spawn()
+}
+
+fn count_lines(buf: &[u8]) -> usize {
+    buf.iter().filter(|b| **b == b'\n').count()
+}
+
+/// Whether `e` was caused by writing to a closed pipe.
+pub fn is_broken_pipe(e: &eyre::Report) -> bool {
+    e.chain().any(|cause| {
+        // JSON errors wrap the I/O error they were caused by.
+        let cause = match cause.downcast_ref::<serde_json::Error>() {
+            Some(e) if e.is_io() => match error::Error::source(e) {
+                Some(source) => source,
+                None => return false,
+            },
+            _ => cause,
+        };
+        let kind = cause.downcast_ref::<io::Error>().map(io::Error::kind);
+        kind == Some(io::ErrorKind::BrokenPipe)
+    })
+}
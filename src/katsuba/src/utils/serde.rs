@@ -1,17 +1,37 @@
 use std::{
     io::{self, IsTerminal, Write},
     path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
-use katsuba_executor::{Executor, Task};
+use katsuba_executor::{Buffer, Executor, Task};
 use serde::Serialize;
 
+use crate::cli::manifest;
+
+static STABLE_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Configures whether outputs should be byte-stable, see
+/// [`stable_output`].
+pub fn set_stable_output(enabled: bool) {
+    STABLE_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether outputs should be identical between runs and machines.
+///
+/// JSON is then always pretty-printed with a trailing newline, and
+/// nothing derived from timing or the environment is written.
+pub fn stable_output() -> bool {
+    STABLE_OUTPUT.load(Ordering::Relaxed)
+}
+
 /// Serializes the given value to the respective output source.
 ///
 /// This will produce valid JSON. If the output is a file or piped to
 /// another application, a minified representation will be emitted.
 ///
-/// Output to stdout always gets pretty-printed.
+/// Output to a terminal always gets pretty-printed, and so does any
+/// output in [`stable_output`] mode.
 ///
 /// This will use the given executor to dispatch the work, so a call
 /// to [`Executor::join`] is necessary to ensure all tasks complete.
@@ -23,21 +43,54 @@ pub fn serialize_to_output_source<T: Serialize>(
     if let Some(out) = out {
         // We use a blanket size for buffers since they will grow as needed anyway.
         // But also most files shouldn't be this large so the memory can be reused.
-        let buffer = ex.request_buffer(1024 * 1024, |buf| serde_json::to_writer(buf, value))?;
+        let buffer = ex.request_buffer(1024 * 1024, |buf| {
+            if !stable_output() {
+                return serde_json::to_writer(buf, value);
+            }
+
+            serde_json::to_writer_pretty(&mut *buf, value)?;
+            buf.push(b'\n');
+            Ok(())
+        })?;
+        manifest::record(&out, &buffer);
 
         let task = Task::create_file(out, buffer, 0o666);
         for pending in ex.dispatch(task) {
             pending?;
         }
     } else {
-        let mut stdout = io::stdout().lock();
+        let mut stdout = io::BufWriter::new(super::stdout());
 
-        if stdout.is_terminal() {
+        if io::stdout().is_terminal() || stable_output() {
             serde_json::to_writer_pretty(&mut stdout, value)?;
             writeln!(stdout)?;
         } else {
             serde_json::to_writer(&mut stdout, value)?;
         }
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Writes the given bytes to the respective output source.
+///
+/// Like [`serialize_to_output_source`], writes to files are dispatched
+/// to the executor.
+pub fn write_to_output_source(
+    ex: &Executor,
+    out: Option<PathBuf>,
+    data: Vec<u8>,
+) -> eyre::Result<()> {
+    if let Some(out) = out {
+        manifest::record(&out, &data);
+
+        let task = Task::create_file(out, Buffer::owned(data), 0o666);
+        for pending in ex.dispatch(task) {
+            pending?;
+        }
+    } else {
+        super::stdout().write_all(&data)?;
     }
 
     Ok(())
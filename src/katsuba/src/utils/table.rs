@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+
+use super::stable_output;
+
+/// Writes one row of comma-separated values with RFC 4180 quoting.
+pub fn write_csv_row<W, I>(out: &mut W, cells: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = String>,
+{
+    for (i, cell) in cells.into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+
+        if cell.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", cell.replace('"', "\"\""))?;
+        } else {
+            out.write_all(cell.as_bytes())?;
+        }
+    }
+
+    // Stable output uses LF line endings everywhere.
+    match stable_output() {
+        true => out.write_all(b"\n"),
+        false => out.write_all(b"\r\n"),
+    }
+}
+
+/// Writes one row of tab-separated values.
+///
+/// Tabs, line breaks and backslashes in cells are escaped with a
+/// backslash, since TSV has no quoting.
+pub fn write_tsv_row<W, I>(out: &mut W, cells: I) -> io::Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = String>,
+{
+    for (i, cell) in cells.into_iter().enumerate() {
+        if i > 0 {
+            out.write_all(b"\t")?;
+        }
+
+        for c in cell.chars() {
+            match c {
+                '\t' => out.write_all(b"\\t")?,
+                '\n' => out.write_all(b"\\n")?,
+                '\r' => out.write_all(b"\\r")?,
+                '\\' => out.write_all(b"\\\\")?,
+                c => write!(out, "{c}")?,
+            }
+        }
+    }
+
+    out.write_all(b"\n")
+}
@@ -0,0 +1,79 @@
+use std::process::Command;
+
+use serde_json::Value;
+
+fn capabilities() -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_katsuba"))
+        .arg("--capabilities")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn find<'a>(commands: &'a Value, name: &str) -> &'a Value {
+    commands
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("missing command '{name}'"))
+}
+
+fn assert_output_formats(command: &Value) {
+    match command.get("subcommands") {
+        Some(subcommands) => subcommands
+            .as_array()
+            .unwrap()
+            .iter()
+            .for_each(assert_output_formats),
+        None => assert!(
+            command["output_formats"].is_array(),
+            "no output formats for '{}'",
+            command["name"]
+        ),
+    }
+}
+
+#[test]
+fn report() {
+    let report = capabilities();
+
+    assert_eq!(report["name"], "katsuba");
+    assert_eq!(report["version"], env!("CARGO_PKG_VERSION"));
+    assert!(report["features"]["numpy"].is_boolean());
+    assert!(report["features"]["gltf"].is_boolean());
+
+    let serializer_flags = report["serializer_flags"].as_array().unwrap();
+    assert!(serializer_flags.contains(&"STATEFUL_FLAGS".into()));
+    let property_flags = report["property_flags"].as_array().unwrap();
+    assert!(property_flags.contains(&"DEPRECATED".into()));
+
+    let op = find(&report["commands"], "op");
+    let extract = find(&op["subcommands"], "extract-field");
+    assert_eq!(
+        extract["output_formats"],
+        serde_json::json!(["csv", "jsonl"])
+    );
+
+    let unpack = find(&find(&report["commands"], "wad")["subcommands"], "unpack");
+    let options = unpack["options"].as_array().unwrap();
+    assert!(options.iter().any(|o| o["long"] == "resume"));
+
+    // Every command which does something must declare its output.
+    report["commands"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .for_each(assert_output_formats);
+}
+
+#[test]
+fn missing_subcommand() {
+    let status = Command::new(env!("CARGO_BIN_EXE_katsuba"))
+        .status()
+        .unwrap();
+
+    assert_eq!(status.code(), Some(2));
+}
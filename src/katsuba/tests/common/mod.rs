@@ -0,0 +1,59 @@
+//! Helpers shared by the CLI tests.
+
+#![allow(dead_code)]
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use tempfile::TempDir;
+
+/// Gets the path of an object property fixture.
+pub fn data(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data/op")
+        .join(name)
+}
+
+/// Creates a command for the `katsuba` binary.
+pub fn command() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_katsuba"))
+}
+
+/// Runs `katsuba` with `args` and feeds it `stdin`.
+pub fn katsuba(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = command()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Runs `katsuba` like [`katsuba`], which must succeed, and returns
+/// its standard output.
+pub fn run(args: &[&str], stdin: &[u8]) -> Vec<u8> {
+    let output = katsuba(args, stdin);
+    assert!(
+        output.status.success(),
+        "katsuba {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    output.stdout
+}
+
+/// Creates an empty directory which is removed again on drop, even
+/// when a test fails.
+pub fn scratch_dir() -> TempDir {
+    tempfile::Builder::new()
+        .prefix("katsuba-")
+        .tempdir()
+        .unwrap()
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::{fs, path::Path, process::Output};
+
+use common::scratch_dir;
+
+fn convert(path: &Path) -> Output {
+    common::command().arg("convert").arg(path).output().unwrap()
+}
+
+// Converts a file named `name` with `data` and returns the detected
+// format along with the produced JSON.
+fn convert_ok(name: &str, data: &[u8]) -> (String, serde_json::Value) {
+    let dir = scratch_dir();
+    let path = dir.path().join(name);
+    fs::write(&path, data).unwrap();
+
+    let output = convert(&path);
+    assert!(output.status.success(), "{output:?}");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let (_, format) = stderr.trim_end().rsplit_once(": ").unwrap();
+    (
+        format.to_owned(),
+        serde_json::from_slice(&output.stdout).unwrap(),
+    )
+}
+
+fn convert_err(name: &str, data: &[u8]) -> String {
+    let dir = scratch_dir();
+    let path = dir.path().join(name);
+    fs::write(&path, data).unwrap();
+
+    let output = convert(&path);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+
+    String::from_utf8(output.stderr).unwrap()
+}
+
+// A NAV graph with a single node and no links.
+fn nav_graph() -> Vec<u8> {
+    let mut data = vec![1, 0];
+    data.extend(1u32.to_le_bytes());
+    data.extend([0; 14]);
+    data.extend(0u32.to_le_bytes());
+    data
+}
+
+#[test]
+fn wad() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../katsuba-wad/tests/data/Test.wad");
+    let (format, json) = convert_ok("Test.wad", &fs::read(path).unwrap());
+
+    assert_eq!(format, "KIWAD archive");
+    assert!(json
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|f| f["name"] == "uncompressed.mp3"));
+}
+
+#[test]
+fn wad_without_extension() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../katsuba-wad/tests/data/Test.wad");
+    let (format, _) = convert_ok("Test", &fs::read(path).unwrap());
+
+    assert_eq!(format, "KIWAD archive");
+}
+
+#[test]
+fn object_property_requires_type_list() {
+    let stderr = convert_err("Object.xml", b"BINd\0\0\0\0");
+
+    assert!(stderr.contains("ObjectProperty game file"));
+    assert!(stderr.contains("type list"));
+}
+
+#[test]
+fn bcd() {
+    let (format, json) = convert_ok("Collision.bcd", &0u32.to_le_bytes());
+
+    assert_eq!(format, "BCD");
+    assert_eq!(json["collisions"], serde_json::json!([]));
+    assert!(json.get("unknown_trailing").is_none());
+}
+
+#[test]
+fn bcd_with_trailing_chunk() {
+    let mut data = 0u32.to_le_bytes().to_vec();
+    data.extend([0xAB; 6]);
+    let (format, json) = convert_ok("Collision.bcd", &data);
+
+    assert_eq!(format, "BCD");
+    assert_eq!(json["unknown_trailing"], "q6urq6ur");
+}
+
+#[test]
+fn nav() {
+    let (format, json) = convert_ok("Graph.nav", &nav_graph());
+
+    assert_eq!(format, "NAV graph");
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn zone_nav() {
+    let mut data = nav_graph();
+    data.extend(1u32.to_le_bytes());
+    data.extend(4u32.to_le_bytes());
+    data.extend(b"Zone");
+
+    let (format, _) = convert_ok("Zone.nav", &data);
+
+    assert_eq!(format, "zone NAV graph");
+}
+
+#[test]
+fn poi() {
+    let (format, _) = convert_ok("Points.poi", &[0; 24]);
+
+    assert_eq!(format, "POI");
+}
+
+#[test]
+fn unknown() {
+    let stderr = convert_err("notes.txt", b"hello");
+    assert!(stderr.contains("unknown file format"));
+
+    // Archives are never detected by their extension alone.
+    let stderr = convert_err("Fake.wad", b"hello");
+    assert!(stderr.contains("unknown file format"));
+}
+
+#[test]
+fn ambiguous() {
+    let stderr = convert_err("Collision.bcd", b"KIWAD\x02\0\0\0");
+
+    assert!(stderr.contains("could be KIWAD archive or BCD"));
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use std::fs;
+
+use common::scratch_dir;
+
+fn lookup(hash: &str) -> String {
+    let scratch = scratch_dir();
+    let strings = scratch.path().join("strings.txt");
+    fs::write(&strings, "bar\nbaz\n").unwrap();
+
+    let output = common::command()
+        .args(["hash", "lookup", hash, "--from-strings"])
+        .arg(&strings)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn lookup_parses_hex() {
+    // The string IDs of these consist of decimal digits only.
+    assert_eq!(lookup("00014062"), "string-id bar\n");
+    assert_eq!(lookup("0x16062"), "string-id baz\n");
+}
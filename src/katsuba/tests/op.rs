@@ -0,0 +1,702 @@
+mod common;
+
+use std::{fs, process::Output};
+
+use common::{data, scratch_dir};
+
+const TYPES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/op/types.json");
+
+// Runs `katsuba op` with the fixture type list.
+fn katsuba(args: &[&str], stdin: &[u8]) -> Output {
+    common::katsuba(&op(args), stdin)
+}
+
+fn run(args: &[&str], stdin: &[u8]) -> Vec<u8> {
+    common::run(&op(args), stdin)
+}
+
+fn op<'a>(args: &[&'a str]) -> Vec<&'a str> {
+    [&["op", "-t", TYPES], args].concat()
+}
+
+fn roundtrip(fixture: &str, config: &[&str], bind: bool) {
+    let original = fs::read(data(fixture)).unwrap();
+
+    let mut de = config.to_vec();
+    de.extend(["de", "-"]);
+    let json = run(&de, &original);
+
+    let mut ser = config.to_vec();
+    ser.extend(["ser", "-"]);
+    if bind {
+        ser.push("--bind");
+    }
+    assert_eq!(run(&ser, &json), original, "{fixture} did not round-trip");
+}
+
+#[test]
+fn roundtrip_fixtures() {
+    roundtrip("item.bin", &[], true);
+    roundtrip("item_shallow.bin", &["-s"], false);
+    roundtrip("item_deep_zlib.bin", &["-f", "8"], false);
+}
+
+fn ser_error(json: &str) -> String {
+    let output = katsuba(&["-s", "ser", "-"], json.as_bytes());
+    assert!(!output.status.success());
+
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn ser_reports_pointers() {
+    let original = fs::read(data("item_shallow.bin")).unwrap();
+    let json = run(&["-s", "de", "-"], &original);
+    let json = String::from_utf8(json).unwrap();
+
+    let unknown = json.replace("\"m_flags\":3", "\"m_bogus\":3");
+    assert!(ser_error(&unknown).contains("/m_upgrade/m_bogus"));
+
+    let mismatch = json.replace("\"m_goldCost\":-1", "\"m_goldCost\":\"cheap\"");
+    assert!(ser_error(&mismatch).contains("/m_upgrade/m_goldCost"));
+
+    let tag = json.replace("[\"hat\",\"\"]", "[\"hat\",3]");
+    assert!(ser_error(&tag).contains("/m_tags/1"));
+
+    let missing = json.replace("\"m_flags\":9,", "");
+    assert!(ser_error(&missing).contains("/m_flags: missing property"));
+}
+
+#[test]
+fn parallel_de_lists_objects() {
+    let item = fs::read(data("item_shallow.bin")).unwrap();
+    let single = run(&["-s", "de", "-"], &item);
+    assert_eq!(run(&["-s", "de", "--parallel", "-"], &item), single);
+
+    let data = [item.as_slice(); 3].concat();
+    let json: serde_json::Value =
+        serde_json::from_slice(&run(&["-s", "de", "--parallel", "-"], &data)).unwrap();
+    let single: serde_json::Value = serde_json::from_slice(&single).unwrap();
+    assert_eq!(json, serde_json::Value::Array(vec![single; 3]));
+}
+
+#[test]
+fn multi_de_lists_objects() {
+    let item = fs::read(data("item_shallow.bin")).unwrap();
+    let single: serde_json::Value =
+        serde_json::from_slice(&run(&["-s", "de", "-"], &item)).unwrap();
+
+    for count in [1, 3] {
+        let data = item.repeat(count);
+        let json: serde_json::Value =
+            serde_json::from_slice(&run(&["-s", "de", "--multi", "-"], &data)).unwrap();
+        assert_eq!(json, serde_json::Value::Array(vec![single.clone(); count]));
+    }
+
+    // A broken object is reported with its index and offset.
+    let mut data = item.repeat(2);
+    data.extend(0xDEAD_u32.to_le_bytes());
+    let output = katsuba(&["-s", "de", "--multi", "-"], &data);
+    assert!(!output.status.success());
+    let expected = format!("root object 2 at byte {}", item.len() * 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&expected));
+}
+
+#[test]
+fn strict_strings_stats() {
+    let item = fs::read(data("item_shallow.bin")).unwrap();
+    let stats = |data: &[u8]| {
+        let output = katsuba(&["-s", "de", "--strict-strings", "--stats", "-"], data);
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+
+    assert_eq!(stats(&item), "-: 0 strings with invalid UTF-8\n");
+
+    // Invalid strings are counted, but still written.
+    let mut bad = item.clone();
+    let tag = bad.windows(3).position(|w| w == b"hat").unwrap();
+    bad[tag + 1] = 0xC3;
+    assert_eq!(stats(&bad), "-: 1 strings with invalid UTF-8\n");
+}
+
+#[test]
+fn rejects_empty_and_short_inputs() {
+    let empty = katsuba(&["de", "-"], &[]);
+    assert!(String::from_utf8_lossy(&empty.stderr).contains("standard input is empty (0 bytes)"));
+
+    let short = katsuba(&["-z", "de", "-"], &[1, 2]);
+    assert!(String::from_utf8_lossy(&short.stderr)
+        .contains("too short to contain a header (2 bytes, expected at least 4)"));
+}
+
+#[test]
+fn floats_keep_width_and_shortest_form() {
+    let json =
+        r#"{"$__type":1379868502,"m_single":[0.1,16777217.0,1e-45],"m_double":[0.1,5e-324]}"#;
+    let bin = run(&["-s", "ser", "-"], json.as_bytes());
+
+    let out = String::from_utf8(run(&["-s", "de", "-"], &bin)).unwrap();
+    assert!(out.contains(r#""m_single":[0.1,16777216.0,1e-45]"#));
+    assert!(out.contains(r#""m_double":[0.1,5e-324]"#));
+}
+
+#[test]
+fn text_output() {
+    let json = r#"{"$__type":1379868502,"m_single":[1.0,2.0,3.0],"m_double":[]}"#;
+    let bin = run(&["-s", "ser", "-"], json.as_bytes());
+    let text = |args: &[&str]| {
+        let mut de = vec!["-s", "de", "--format", "text"];
+        de.extend(args);
+        de.push("-");
+        String::from_utf8(run(&de, &bin)).unwrap()
+    };
+
+    assert_eq!(
+        text(&[]),
+        "class Floats {\n  m_double: []\n  m_single: [\n    1.0\n    2.0\n    3.0\n  ]\n}\n"
+    );
+    assert_eq!(
+        text(&["--max-items", "1"]),
+        "class Floats {\n  m_double: []\n  m_single: [\n    1.0\n    ... (2 more)\n  ]\n}\n"
+    );
+    assert_eq!(
+        text(&["--depth", "1"]),
+        "class Floats {\n  m_double: []\n  m_single: [...]\n}\n"
+    );
+
+    // Output to a pipe is only colored on request.
+    assert!(!text(&[]).contains('\x1b'));
+    assert!(text(&["--color", "always"]).contains("\x1b[34mm_single\x1b[0m"));
+}
+
+#[test]
+fn nonfinite_float_policies() {
+    let json =
+        r#"{"$__type":1379868502,"m_single":["NaN","Infinity"],"m_double":[1.5,"-Infinity"]}"#;
+    let bin = run(&["-s", "ser", "-"], json.as_bytes());
+
+    // Strings are the default and serialize back to identical bits.
+    let out = run(&["-s", "de", "-"], &bin);
+    assert_eq!(out, run(&["-s", "de", "--nonfinite", "string", "-"], &bin));
+    assert!(String::from_utf8_lossy(&out).contains(r#""m_single":["NaN","Infinity"]"#));
+    assert_eq!(run(&["-s", "ser", "-"], &out), bin);
+
+    let out = run(&["-s", "de", "--nonfinite", "null", "-"], &bin);
+    assert!(String::from_utf8_lossy(&out).contains(r#""m_double":[1.5,null]"#));
+
+    let error = katsuba(&["-s", "de", "--nonfinite", "error", "-"], &bin);
+    assert!(!error.status.success());
+    assert!(String::from_utf8_lossy(&error.stderr).contains("non-finite float at m_double[1]"));
+}
+
+#[test]
+fn hex_colors_roundtrip() {
+    let original = fs::read(data("item_shallow.bin")).unwrap();
+    let json = run(&["-s", "de", "--color-format", "hex", "-"], &original);
+    assert!(String::from_utf8_lossy(&json).contains(r##""m_tint":"#010203FF""##));
+    assert_eq!(run(&["-s", "ser", "-"], &json), original);
+
+    let json = String::from_utf8(json).unwrap();
+    let short = json.replacen("#010203FF", "#010203", 1);
+    assert_eq!(run(&["-s", "ser", "-"], short.as_bytes()), original);
+
+    let bad = json.replacen("#010203FF", "#0102", 1);
+    assert!(ser_error(&bad).contains("invalid color '#0102'"));
+}
+
+#[test]
+fn humanized_times_roundtrip() {
+    let json = r#"{"$__type":305041809,"m_created":951782400,"m_legacy":-1}"#;
+    let bin = run(&["-s", "ser", "-"], json.as_bytes());
+    assert_eq!(run(&["-s", "de", "-"], &bin), json.as_bytes());
+
+    let human = run(&["-s", "de", "--humanize-time", "-"], &bin);
+    let human = String::from_utf8(human).unwrap();
+    assert!(human.contains(r#""m_created":[951782400,"2000-02-29T00:00:00Z"]"#));
+    assert!(human.contains(r#""m_legacy":[-1,"1969-12-31T23:59:59Z"]"#));
+    assert_eq!(run(&["-s", "ser", "-"], human.as_bytes()), bin);
+
+    let overflow = json.replace("-1", "4294967296");
+    assert!(ser_error(&overflow).contains("out of range for '__time32_t'"));
+}
+
+#[test]
+fn gid_formats_roundtrip() {
+    let json = r#"{"$__type":1159257321,"m_items":["0x0000000000000003","0x0000000400000005"],"m_owner":"0x0000000100000002"}"#;
+    let bin = run(&["-s", "ser", "-"], json.as_bytes());
+    assert_eq!(run(&["-s", "de", "-"], &bin), json.as_bytes());
+
+    let split = run(&["-s", "de", "--gid-format", "split", "-"], &bin);
+    assert!(String::from_utf8_lossy(&split).contains(r#""m_items":[[0,3],[4,5]],"m_owner":[1,2]"#));
+    assert_eq!(run(&["-s", "ser", "-"], &split), bin);
+
+    let decimal = run(&["-s", "de", "--gid-format", "decimal", "-"], &bin);
+    assert!(String::from_utf8_lossy(&decimal).contains(r#""m_owner":4294967298"#));
+    assert_eq!(run(&["-s", "ser", "-"], &decimal), bin);
+
+    let bad = json.replace("[\"0x0000000000000003\"", "[[1,4294967296]");
+    assert!(ser_error(&bad).contains("cannot assign [1,4294967296] to 'union gid'"));
+}
+
+#[test]
+fn grep_matches_predicates() {
+    let item = data("item.bin");
+    let item = item.to_str().unwrap();
+
+    let out = run(
+        &[
+            "grep",
+            "--where",
+            "m_goldCost>=500",
+            "--where",
+            "m_tags~ha",
+            item,
+        ],
+        &[],
+    );
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(
+        out,
+        format!("{item}: m_goldCost=500 m_tags=[\"hat\",\"\"]\n")
+    );
+
+    let out = run(
+        &["grep", "--where-regex", "m_displayName~^Cool H", item],
+        &[],
+    );
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .contains("m_displayName=\"Cool Hat\""));
+
+    // Every predicate must hold, and no match is reported by status.
+    let none = katsuba(
+        &[
+            "grep",
+            "--where",
+            "m_goldCost=500",
+            "--where",
+            "m_rarity!=2",
+            item,
+        ],
+        &[],
+    );
+    assert_eq!(none.status.code(), Some(1));
+    assert!(none.stdout.is_empty());
+
+    let bad = katsuba(&["grep", "--where-regex", "m_displayName=Hat", item], &[]);
+    assert!(String::from_utf8_lossy(&bad.stderr).contains("must use the '~' operator"));
+}
+
+#[test]
+fn extract_field_tables() {
+    let item = data("item.bin");
+    let item = item.to_str().unwrap();
+    let fields = [
+        "--field",
+        "m_goldCost",
+        "--field",
+        "m_displayName",
+        "--field",
+        "m_bogus",
+    ];
+
+    let csv = run(&[&["extract-field"][..], &fields, &[item]].concat(), &[]);
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        format!("file,object,m_goldCost,m_displayName,m_bogus\r\n{item},0,500,Cool Hat,\r\n")
+    );
+
+    let args = [
+        &["extract-field", "--no-header"][..],
+        &fields,
+        &[item, item],
+    ]
+    .concat();
+    let csv = String::from_utf8(run(&args, &[])).unwrap();
+    assert_eq!(csv.lines().count(), 2);
+    assert!(!csv.contains("m_goldCost"));
+
+    let args = [
+        &["extract-field", "--format", "jsonl"][..],
+        &fields,
+        &[item],
+    ]
+    .concat();
+    let jsonl: serde_json::Value = serde_json::from_slice(&run(&args, &[])).unwrap();
+    assert_eq!(jsonl["m_goldCost"], 500);
+    assert_eq!(jsonl["m_displayName"], "Cool Hat");
+    assert!(jsonl["m_bogus"].is_null());
+}
+
+#[test]
+fn search_objects_of_class() {
+    let item = data("item.bin");
+    let item = item.to_str().unwrap();
+
+    let out = run(
+        &[
+            "grep",
+            "--class",
+            "Item",
+            "--where",
+            "m_displayName~Cool",
+            item,
+        ],
+        &[],
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "{item}:<root>: m_displayName=\"Cool Hat\"\n\
+             {item}:m_upgrade: m_displayName=\"Cooler Hat\"\n"
+        )
+    );
+
+    let csv = run(
+        &[
+            "extract-field",
+            "--class",
+            "class Item",
+            "--field",
+            "m_goldCost",
+            item,
+        ],
+        &[],
+    );
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        format!(
+            "file,object,path,m_goldCost\r\n\
+             {item},0,<root>,500\r\n\
+             {item},0,m_upgrade,-1\r\n"
+        )
+    );
+
+    let none = katsuba(
+        &["grep", "--class", "Floats", "--where", "m_single>0", item],
+        &[],
+    );
+    assert_eq!(none.status.code(), Some(1));
+}
+
+#[test]
+fn strings_are_listed_once() {
+    let item = data("item.bin");
+    let item = item.to_str().unwrap();
+
+    let csv = run(&["strings", "--hash", item, item], &[]);
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "path,string,string_id\r\n\
+         m_displayName,Cool Hat,271005555\r\n\
+         m_tags[0],hat,88168\r\n\
+         m_tags[1],,0\r\n\
+         m_upgrade.m_displayName,Cooler Hat,1602357885\r\n"
+    );
+
+    let jsonl = String::from_utf8(run(&["strings", "--format", "jsonl", item], &[])).unwrap();
+    let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+    assert_eq!(
+        first,
+        serde_json::json!({ "path": "m_displayName", "string": "Cool Hat" })
+    );
+}
+
+#[test]
+fn stable_output_is_byte_identical() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    let convert = |run_dir: &str| {
+        let out = dir.join(run_dir);
+        fs::create_dir_all(&out).unwrap();
+        let manifest = out.join("manifest.json");
+
+        run(
+            &[
+                "--stable-output",
+                "de",
+                "--manifest",
+                manifest.to_str().unwrap(),
+                "-o",
+                out.to_str().unwrap(),
+                data("item.bin").to_str().unwrap(),
+            ],
+            &[],
+        );
+
+        let json = fs::read(out.join("item.de.json")).unwrap();
+        (json, fs::read(manifest).unwrap())
+    };
+
+    let (first, first_manifest) = convert("a");
+    let (second, second_manifest) = convert("b");
+
+    assert_eq!(first, second);
+    assert!(first.ends_with(b"}\n") && !first.contains(&b'\r'));
+
+    // Manifests differ only in the output directory.
+    let manifest = String::from_utf8(first_manifest).unwrap();
+    assert!(!manifest.contains("duration_ms"));
+    assert_eq!(
+        manifest.replace("/a/", "/b/"),
+        String::from_utf8(second_manifest).unwrap()
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn pager_exiting_early() {
+    // Enough output to still be writing when the pager exits.
+    let item = fs::read(data("item_shallow.bin")).unwrap();
+    let scratch = scratch_dir();
+    let input = scratch.path().join("items.bin");
+    fs::write(&input, item.repeat(3000)).unwrap();
+
+    let output = common::command()
+        .args(["op", "-s", "-t"])
+        .arg(data("types.json"))
+        .args(["de", "--parallel", "--format", "text", "--pager", "always"])
+        .arg(&input)
+        .env("PAGER", "head -n 2")
+        .output()
+        .unwrap();
+
+    // The pager closing its end of the pipe is not an error.
+    assert!(output.status.success());
+    assert!(
+        output.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn mask_presets() {
+    let shallow = fs::read(data("item_shallow.bin")).unwrap();
+    let default = run(&["-s", "de", "-"], &shallow);
+    assert_eq!(
+        run(&["-s", "--mask-preset", "transmit", "de", "-"], &shallow),
+        default
+    );
+
+    // No property of the fixture is saved, so the data is left over.
+    let save = katsuba(&["-s", "--mask-preset", "save", "de", "-"], &shallow);
+    assert!(!save.status.success());
+
+    // A raw mask overrides the preset.
+    assert_eq!(
+        run(
+            &["-s", "--mask-preset", "save", "-m", "24", "de", "-"],
+            &shallow
+        ),
+        default
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn to_sqlite_rows() {
+    let scratch = scratch_dir();
+    let db = scratch.path().join("items.db");
+    let item = data("item.bin");
+    let item = item.to_str().unwrap();
+
+    let args = [
+        "to-sqlite",
+        "-o",
+        db.to_str().unwrap(),
+        "--table",
+        "items",
+        "--field",
+        "m_goldCost:int",
+        "--field",
+        "m_displayName:text",
+        "--field",
+        "m_bogus:real",
+        item,
+        item,
+    ];
+    run(&args, &[]);
+
+    let conn = rusqlite::Connection::open(&db).unwrap();
+    let mut stmt = conn
+        .prepare(r#"SELECT "file", "object", "m_goldCost", "m_displayName", "m_bogus" FROM items"#)
+        .unwrap();
+    let rows: Vec<(String, i64, i64, String, Option<f64>)> = stmt
+        .query_map([], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+        })
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0], (item.to_string(), 0, 500, "Cool Hat".into(), None));
+
+    // The first field is indexed.
+    let index: String = conn
+        .query_row(
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'items'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(index, "items_m_goldCost");
+}
+
+#[test]
+fn many_inputs_on_workers() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+    let (input, out) = (dir.join("in"), dir.join("out"));
+    fs::create_dir_all(&input).unwrap();
+
+    let item = fs::read(data("item.bin")).unwrap();
+    for i in 0..12 {
+        fs::write(input.join(format!("item{i:02}.bin")), &item).unwrap();
+    }
+    fs::write(input.join("item06a.bin"), b"").unwrap();
+
+    let manifest = dir.join("manifest.json");
+    let output = common::command()
+        .env("KATSUBA_WORKER_THREADS", "4")
+        .arg("op")
+        .arg("-t")
+        .arg(data("types.json"))
+        .args(["de", "--keep-going", "--manifest"])
+        .arg(&manifest)
+        .arg("-o")
+        .arg(&out)
+        .arg(&input)
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("failed to process 1 of 13 inputs (1 empty)"),
+        "{stderr}"
+    );
+
+    // Outputs match those of a single input, and the manifest lists
+    // the inputs in order.
+    let expected = run(&["de", "-o", "-", data("item.bin").to_str().unwrap()], &[]);
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_slice(&fs::read(&manifest).unwrap()).unwrap();
+    let inputs: Vec<_> = entries
+        .iter()
+        .map(|e| {
+            e["input"]
+                .as_str()
+                .unwrap()
+                .rsplit('/')
+                .next()
+                .unwrap()
+                .to_owned()
+        })
+        .collect();
+    let mut sorted = inputs.clone();
+    sorted.sort();
+    assert_eq!(inputs, sorted);
+    assert_eq!(inputs.len(), 13);
+
+    for i in 0..12 {
+        let json = fs::read(out.join(format!("item{i:02}.de.json"))).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            json,
+            serde_json::from_slice::<serde_json::Value>(&expected).unwrap()
+        );
+    }
+}
+
+#[test]
+fn edit_assigns_objects_and_containers() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    let types = dir.join("types.json");
+    fs::write(
+        &types,
+        serde_json::json!({
+            "class Holder": {
+                "properties": {
+                    "m_counts": { "type": "std::map<std::string, int>", "id": 0, "flags": 24, "dynamic": false, "hash": 1 },
+                    "m_pair": { "type": "std::pair<int, class Holder*>", "id": 1, "flags": 24, "dynamic": false, "hash": 2 },
+                    "m_child": { "type": "class Holder*", "id": 2, "flags": 24, "dynamic": false, "hash": 3 }
+                }
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let op = |args: &[&str], stdin: &[u8]| {
+        let types = types.to_str().unwrap();
+        common::run(&[&["op", "-t", types], args].concat(), stdin)
+    };
+
+    let empty =
+        r#"{"$__type": "class Holder", "m_counts": [], "m_pair": [0, null], "m_child": null}"#;
+    let input = dir.join("in.bin");
+    fs::write(&input, op(&["ser", "-"], empty.as_bytes())).unwrap();
+
+    let patch = dir.join("patch.json");
+    fs::write(
+        &patch,
+        serde_json::json!([
+            { "path": "m_counts", "value": [["a", 1], ["b", 2]] },
+            { "path": "m_child", "value": serde_json::from_str::<serde_json::Value>(empty).unwrap() },
+        ])
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = dir.join("out.bin");
+    op(
+        &[
+            "edit",
+            input.to_str().unwrap(),
+            "--patch",
+            patch.to_str().unwrap(),
+            "--set",
+            "m_counts[1]!value=5",
+            "--set",
+            "m_counts[0]!key=c",
+            "--set",
+            r#"m_pair=[3, {"$__type": "class Holder", "m_counts": [["d", 4]], "m_pair": [0, null], "m_child": null}]"#,
+            "--set",
+            "m_pair!first=7",
+            "-o",
+            output.to_str().unwrap(),
+        ],
+        &[],
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_slice(&op(&["de", "-o", "-", output.to_str().unwrap()], &[])).unwrap();
+    assert_eq!(json["m_counts"], serde_json::json!([["c", 1], ["b", 5]]));
+    assert_eq!(json["m_pair"][0], 7);
+    assert_eq!(json["m_pair"][1]["m_counts"], serde_json::json!([["d", 4]]));
+    assert_eq!(json["m_child"]["m_counts"], serde_json::json!([]));
+
+    // Errors name the assigned path.
+    let output = common::katsuba(
+        &[
+            "op",
+            "-t",
+            types.to_str().unwrap(),
+            "edit",
+            input.to_str().unwrap(),
+            "--set",
+            "m_child={\"$__type\": \"class Holder\", \"m_bogus\": 1}",
+            "-o",
+            output.to_str().unwrap(),
+        ],
+        &[],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("m_child/m_bogus"), "{stderr}");
+}
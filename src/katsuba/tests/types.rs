@@ -0,0 +1,134 @@
+mod common;
+
+use std::fs;
+
+use serde_json::Value;
+
+use common::{data, katsuba, scratch_dir};
+
+#[test]
+fn schema_describes_de_output() {
+    let types = data("types.json");
+    let scratch = scratch_dir();
+    let out = scratch.path().join("schema.json");
+
+    let status = katsuba(
+        &[
+            "types",
+            "-t",
+            types.to_str().unwrap(),
+            "schema",
+            "Item",
+            "-o",
+            out.to_str().unwrap(),
+        ],
+        &[],
+    );
+    assert!(status.status.success());
+    let schema: Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+
+    assert_eq!(
+        schema["$schema"],
+        "https://json-schema.org/draft/2020-12/schema"
+    );
+    assert_eq!(schema["$ref"], "#/$defs/Item");
+
+    let item = &schema["$defs"]["Item"];
+    let properties = &item["properties"];
+    assert_eq!(properties["$__type"]["const"], 1158769257);
+    assert_eq!(properties["m_goldCost"]["type"], "integer");
+    assert_eq!(properties["m_rarity"]["enum"], serde_json::json!([0, 1, 2]));
+    assert_eq!(properties["m_tags"]["items"]["type"], "string");
+
+    // The self-referencing pointer refers back to the definition.
+    let upgrade = &properties["m_upgrade"]["anyOf"];
+    assert_eq!(upgrade[0]["$ref"], "#/$defs/Item");
+    assert_eq!(upgrade[1]["type"], "null");
+
+    // Every property in the actual output is described.
+    let de = katsuba(
+        &[
+            "op",
+            "-t",
+            types.to_str().unwrap(),
+            "de",
+            data("item.bin").to_str().unwrap(),
+        ],
+        &[],
+    );
+    let value: Value = serde_json::from_slice(&de.stdout).unwrap();
+    for key in value.as_object().unwrap().keys() {
+        assert!(properties.get(key).is_some(), "missing '{key}'");
+    }
+
+    let missing = katsuba(
+        &["types", "-t", types.to_str().unwrap(), "schema", "Nope"],
+        &[],
+    );
+    assert!(!missing.status.success());
+    assert!(
+        String::from_utf8_lossy(&missing.stderr).contains("class 'Nope' is not in the type list")
+    );
+}
+
+#[test]
+fn enums_table() {
+    let types = data("types.json");
+    let enums = |extra: &[&str]| {
+        let mut args = vec![
+            "--stable-output",
+            "types",
+            "-t",
+            types.to_str().unwrap(),
+            "enums",
+        ];
+        args.extend(extra);
+
+        let output = katsuba(&args, &[]);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let csv = enums(&[]);
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("class,property,type,variant,value,flags")
+    );
+    assert_eq!(
+        lines.next(),
+        Some("class Item,m_rarity,enum Rarity,Common,0,TRANSMIT|PRIVILEGED_TRANSMIT|ENUM")
+    );
+
+    let tsv = enums(&["--format", "tsv", "--only", "Rarity"]);
+    assert_eq!(tsv.lines().count(), 4);
+    assert!(tsv.contains("class Item\tm_rarity\tenum Rarity\tEpic\t2\t"));
+
+    assert_eq!(enums(&["--only", "enum Missing"]).lines().count(), 1);
+}
+
+#[test]
+fn validate_collisions() {
+    let types = data("types.json");
+    let ok = katsuba(&["types", "-t", types.to_str().unwrap(), "validate"], &[]);
+    assert!(ok.status.success());
+    assert!(ok.stdout.is_empty());
+
+    let bad = data("collisions.json");
+    let found = katsuba(
+        &[
+            "types",
+            "-t",
+            bad.to_str().unwrap(),
+            "validate",
+            "--collisions",
+        ],
+        &[],
+    );
+
+    assert_eq!(found.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(found.stdout).unwrap(),
+        "class Holder: properties m_first, m_second share the hash 0x00000007\n"
+    );
+}
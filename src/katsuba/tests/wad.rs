@@ -0,0 +1,391 @@
+mod common;
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use katsuba_wad::Archive;
+
+use common::scratch_dir;
+
+fn test_wad() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../katsuba-wad/tests/data/Test.wad")
+}
+
+#[test]
+fn unpack_with_patch_source() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    // An archive with zeroed out data for one of its files.
+    let mut raw = fs::read(test_wad()).unwrap();
+    let archive = Archive::from_vec(raw.clone()).unwrap();
+    let span = archive.entry_span(archive.file_raw("uncompressed.mp3").unwrap());
+    raw[span.start as usize..span.end as usize].fill(0);
+
+    let input = dir.join("Unpatched.wad");
+    fs::write(&input, raw).unwrap();
+
+    let unpack = |extra: &[&Path], out: &str| {
+        let status = common::command()
+            .args(["wad", "unpack"])
+            .arg(&input)
+            .args(extra)
+            .arg("-o")
+            .arg(dir.join(out))
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        dir.join(out).join("Unpatched/uncompressed.mp3")
+    };
+
+    let without = unpack(&[], "without");
+    assert!(!without.exists());
+
+    let with = unpack(&[Path::new("--patch-source"), &test_wad()], "with");
+    assert_eq!(fs::read(with).unwrap(), b"uncompressed data\n");
+}
+
+#[test]
+fn unpack_to_stdout_tar() {
+    let output = common::command()
+        .args(["wad", "unpack", "--stdout-tar"])
+        .arg(test_wad())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let archive = Archive::open_heap(test_wad()).unwrap();
+    let mut tar = tar::Archive::new(output.stdout.as_slice());
+    let mut count = 0;
+    for entry in tar.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let path = entry.path().unwrap().into_owned();
+        let name = path.strip_prefix("Test").unwrap().to_str().unwrap();
+
+        let file = archive.file_raw(name).unwrap();
+        assert_eq!(
+            entry.header().size().unwrap(),
+            file.uncompressed_size as u64
+        );
+
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).unwrap();
+        if name == "uncompressed.mp3" {
+            assert_eq!(data, b"uncompressed data\n");
+        }
+        count += 1;
+    }
+    assert_eq!(count, archive.len());
+}
+
+#[test]
+fn unpack_with_mode() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    let unpack = |extra: &[&str], out: &str| {
+        let status = common::command()
+            .args(["wad", "unpack"])
+            .arg(test_wad())
+            .args(extra)
+            .arg("-o")
+            .arg(dir.join(out))
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        fs::metadata(dir.join(out).join("Test/uncompressed.mp3"))
+            .unwrap()
+            .permissions()
+    };
+
+    let exec = unpack(&["--chmod", "755"], "exec");
+    let readonly = unpack(&["--chmod", "0o444"], "readonly");
+    let default = unpack(&["--no-preserve-mode"], "default");
+
+    assert!(readonly.readonly());
+    assert!(!default.readonly());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(exec.mode() & 0o111, 0o111);
+        assert_eq!(default.mode() & 0o111, 0);
+    }
+    #[cfg(not(unix))]
+    assert!(!exec.readonly());
+
+    let bad = common::command()
+        .args(["wad", "unpack", "--chmod", "9"])
+        .arg(test_wad())
+        .output()
+        .unwrap();
+    assert!(!bad.status.success());
+}
+
+#[test]
+fn unpack_resume() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+    let archive = Archive::open_heap(test_wad()).unwrap();
+    let crc = archive.file_raw("uncompressed.mp3").unwrap().crc;
+
+    // A journal from an interrupted run, with a torn last record.
+    let out = dir.join("Test");
+    fs::create_dir_all(&out).unwrap();
+    fs::write(
+        out.join(".katsuba-resume.jsonl"),
+        format!("{{\"path\":\"uncompressed.mp3\",\"crc\":{crc}}}\n{{\"path\":\"comp"),
+    )
+    .unwrap();
+
+    let status = common::command()
+        .args(["wad", "unpack", "--resume"])
+        .arg(test_wad())
+        .arg("-o")
+        .arg(dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // The journaled file is trusted and not written again.
+    assert!(!out.join("uncompressed.mp3").exists());
+    for name in archive.files().keys() {
+        if name != "uncompressed.mp3" {
+            assert!(out.join(name).exists(), "missing {name}");
+        }
+    }
+    assert!(!out.join(".katsuba-resume.jsonl").exists());
+}
+
+#[test]
+fn unpack_partial() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    // An archive which is missing the data of its last file.
+    let mut raw = fs::read(test_wad()).unwrap();
+    let archive = Archive::from_vec(raw.clone()).unwrap();
+    let (last, file) = archive
+        .files()
+        .iter()
+        .max_by_key(|(_, f)| f.span().end)
+        .unwrap();
+    raw.truncate(file.span().start as usize);
+
+    let input = dir.join("Partial.wad");
+    fs::write(&input, raw).unwrap();
+
+    let unpack = |extra: &[&str]| {
+        common::command()
+            .args(["wad", "unpack"])
+            .args(extra)
+            .arg(&input)
+            .arg("-o")
+            .arg(dir)
+            .status()
+            .unwrap()
+    };
+
+    assert_eq!(unpack(&[]).code(), Some(1));
+    assert_eq!(unpack(&["--partial"]).code(), Some(3));
+
+    let out = dir.join("Partial");
+    assert!(!out.join(last).exists());
+    for name in archive.files().keys().filter(|n| *n != last) {
+        assert!(out.join(name).exists(), "missing {name}");
+    }
+
+    let ls = common::command()
+        .args(["wad", "ls", "--partial"])
+        .arg(&input)
+        .output()
+        .unwrap();
+    assert!(ls.status.success());
+    let listing = String::from_utf8(ls.stdout).unwrap();
+    assert!(listing.contains(&format!(
+        "{last} ({} bytes) (unavailable)",
+        file.uncompressed_size
+    )));
+}
+
+#[test]
+fn unpack_reports_failed_writes() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    // Directories in place of files make writing them fail.
+    let out = dir.join("Test");
+    fs::create_dir_all(out.join("uncompressed.mp3")).unwrap();
+
+    let output = common::command()
+        .args(["wad", "unpack"])
+        .arg(test_wad())
+        .arg("-o")
+        .arg(dir)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let archive = Archive::open_heap(test_wad()).unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("failed to write 1 of {} files", archive.len())));
+    assert!(stderr.contains(&*out.join("uncompressed.mp3").to_string_lossy()));
+}
+
+#[test]
+fn unpack_strip_prefix() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+
+    let unpack = |extra: &[&str]| {
+        common::command()
+            .args(["wad", "unpack", "--strip-prefix", "subdir"])
+            .args(extra)
+            .arg(test_wad())
+            .arg("-o")
+            .arg(dir)
+            .output()
+            .unwrap()
+    };
+
+    // Most files of the archive are not in the directory.
+    let output = unpack(&[]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("'text1.txt' does not start with any of the prefixes to strip"));
+
+    let output = unpack(&["--strip-prefix-lenient"]);
+    assert!(output.status.success());
+    let out = dir.join("Test");
+    assert!(out.join("subdir_text1.txt").is_file());
+    assert!(out.join("text1.txt").is_file());
+    assert!(!out.join("subdir").exists());
+}
+
+#[test]
+fn unpack_dry_run() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+    let archive = Archive::from_vec(fs::read(test_wad()).unwrap()).unwrap();
+
+    let output = common::command()
+        .args(["wad", "unpack", "--dry-run"])
+        .arg(test_wad())
+        .arg("-o")
+        .arg(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let total = archive.total_uncompressed(|_, _| true);
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains(&format!("{} files, {total} bytes", archive.files().len())));
+    assert!(!dir.join("Test").exists());
+}
+
+#[test]
+fn unpack_with_metrics() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+    let archive = Archive::from_vec(fs::read(test_wad()).unwrap()).unwrap();
+    let metrics = dir.join("metrics.json");
+
+    let output = common::command()
+        .args(["wad", "unpack", "--metrics"])
+        .arg(&metrics)
+        .arg(test_wad())
+        .arg("-o")
+        .arg(dir.join("out"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // Requesting metrics also prints the summary line.
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Processed "), "{stderr}");
+
+    let report: serde_json::Value = serde_json::from_slice(&fs::read(metrics).unwrap()).unwrap();
+    let files = archive.files().len() as u64;
+    assert_eq!(report["entries"], files);
+    assert_eq!(report["bytes"], archive.total_uncompressed(|_, _| true));
+    assert_eq!(report["read"]["count"], files);
+    for key in ["write", "create_dir", "queue_wait"] {
+        assert!(report[key]["count"].as_u64().unwrap() > 0, "{key}");
+        assert!(report[key]["p50_us"].as_u64().unwrap() <= report[key]["max_us"].as_u64().unwrap());
+    }
+}
+
+#[test]
+fn ls_into_closed_pipe() {
+    for args in [&["wad", "ls"][..], &["wad", "ls", "--json"]] {
+        let mut child = common::command()
+            .args(args)
+            .arg(test_wad())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        // Close the reading end before anything could be written.
+        drop(child.stdout.take());
+
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{args:?} failed");
+        assert!(
+            output.stderr.is_empty(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[test]
+fn pack_with_baseline() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+    let baseline = Archive::open_heap(test_wad()).unwrap();
+
+    let status = common::command()
+        .args(["wad", "unpack"])
+        .arg(test_wad())
+        .arg("-o")
+        .arg(dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // Modify one of the files, all others can be reused.
+    let tree = dir.join("Test");
+    let (changed, _) = baseline.files().iter().next().unwrap();
+    fs::write(tree.join(changed), b"modified").unwrap();
+
+    let output = dir.join("Packed.wad");
+    let status = common::command()
+        .args(["wad", "pack"])
+        .arg(&tree)
+        .arg("-o")
+        .arg(&output)
+        .arg("--baseline")
+        .arg(test_wad())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let packed = Archive::open_heap(&output).unwrap();
+    assert_eq!(packed.len(), baseline.len());
+    for (name, file) in baseline.files() {
+        let new = packed.file_raw(name).unwrap();
+        if name == changed {
+            assert_eq!(new.uncompressed_size, 8);
+        } else {
+            assert_eq!(new.crc, file.crc, "{name} was recompressed");
+            assert_eq!(packed.file_contents(new), baseline.file_contents(file));
+        }
+    }
+}
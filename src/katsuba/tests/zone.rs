@@ -0,0 +1,93 @@
+mod common;
+
+use std::{fs, io::Cursor};
+
+use katsuba_bcd::*;
+
+use common::{run, scratch_dir};
+
+fn collision() -> Vec<u8> {
+    let bcd = Bcd {
+        collisions: vec![Collision {
+            category_flags: CollisionFlags::WALKABLE,
+            collision_flags: CollisionFlags::OBJECT,
+            mesh: None,
+            geometry: ProxyGeometry {
+                name: "floor".into(),
+                rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+                location: [0.0; 3],
+                scale: 1.0,
+                material: "stone".into(),
+                params: GeomParams::Sphere { radius: 2.0 },
+            },
+        }],
+        unknown_trailing: Vec::new(),
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    bcd.write(&mut out).unwrap();
+    out.into_inner()
+}
+
+// A NAV graph with two linked nodes.
+fn nav_graph() -> Vec<u8> {
+    let mut data = vec![1, 0];
+    data.extend(2u32.to_le_bytes());
+    for (location, id) in [([-1.0f32, 0.0, 4.0], 0u16), ([3.0, 2.0, -4.0], 1)] {
+        location.iter().for_each(|c| data.extend(c.to_le_bytes()));
+        data.extend(id.to_le_bytes());
+    }
+    data.extend(1u32.to_le_bytes());
+    data.extend([0, 0, 1, 0]);
+    data
+}
+
+#[test]
+fn info_summarizes_members() {
+    let scratch = scratch_dir();
+    let dir = scratch.path();
+    let zone = dir.join("Zone");
+    fs::create_dir_all(zone.join("Data")).unwrap();
+    fs::write(zone.join("collision.bcd"), collision()).unwrap();
+    fs::write(zone.join("Data/pathNodes.nav"), nav_graph()).unwrap();
+    fs::write(zone.join("broken.bcd"), 1u32.to_le_bytes()).unwrap();
+    fs::write(zone.join("readme.txt"), b"hi").unwrap();
+
+    let wad = dir.join("Zone.wad");
+    run(
+        &[
+            "wad",
+            "pack",
+            zone.to_str().unwrap(),
+            "-o",
+            wad.to_str().unwrap(),
+        ],
+        &[],
+    );
+
+    let info: serde_json::Value =
+        serde_json::from_slice(&run(&["zone", "info", wad.to_str().unwrap()], &[])).unwrap();
+
+    assert_eq!(info["files"]["count"], 4);
+    assert_eq!(info["files"]["extensions"]["bcd"], 2);
+    assert_eq!(info["missing"], serde_json::json!(["poi"]));
+
+    assert_eq!(info["errors"].as_array().unwrap().len(), 1);
+    assert_eq!(info["errors"][0]["name"], "broken.bcd");
+
+    let collision = &info["collision"][0];
+    assert_eq!(collision["name"], "collision.bcd");
+    assert_eq!(collision["collisions"], 1);
+    assert_eq!(collision["shapes"]["sphere"], 1);
+
+    let nav = &info["navigation"][0];
+    assert_eq!(nav["name"], "Data/pathNodes.nav");
+    assert_eq!(nav["nodes"], 2);
+    assert_eq!(nav["links"], 1);
+    assert_eq!(
+        nav["bounds"],
+        serde_json::json!({ "min": [-1.0, 0.0, -4.0], "max": [3.0, 2.0, 4.0] })
+    );
+
+    assert_eq!(info["points_of_interest"], serde_json::json!([]));
+}